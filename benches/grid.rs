@@ -0,0 +1,60 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use wave_function_collapse::wave_function::{
+    collapsable_wave_function::{
+        collapsable_wave_function::CollapsableWaveFunction,
+        entropic_collapsable_wave_function::EntropicCollapsableWaveFunction,
+        sequential_collapsable_wave_function::SequentialCollapsableWaveFunction,
+    },
+    grid::{self, AdjacencyKind},
+    WaveFunction,
+};
+
+/// This function builds a 25x25x25 grid where each node must be in a different color than each of its 6 neighbors.
+fn get_wave_function() -> WaveFunction<String> {
+    let node_state_ids: Vec<String> = (0..5).map(|index| format!("color_{index}")).collect();
+    let permitted_node_state_ids = node_state_ids.clone();
+    grid::build_3d_grid(
+        25,
+        25,
+        25,
+        node_state_ids,
+        AdjacencyKind::Six,
+        move |node_state_id, _| {
+            permitted_node_state_ids
+                .iter()
+                .filter(|other_node_state_id| *other_node_state_id != node_state_id)
+                .cloned()
+                .collect()
+        },
+    )
+    .unwrap()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let wave_function = get_wave_function();
+    wave_function.validate().unwrap();
+
+    let mut group = c.benchmark_group("grid_25x25x25");
+    group.sample_size(10);
+    // using a fixed seed for randomness so that every sample performs the same collapse
+    group.bench_function("sequential", |b| {
+        b.iter(|| {
+            wave_function
+                .get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0))
+                .collapse()
+                .unwrap()
+        })
+    });
+    group.bench_function("entropic", |b| {
+        b.iter(|| {
+            wave_function
+                .get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(0))
+                .collapse()
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -0,0 +1,125 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use wave_function_collapse::wave_function::{
+    collapsable_wave_function::{
+        accommodating_collapsable_wave_function::AccommodatingCollapsableWaveFunction,
+        accommodating_sequential_collapsable_wave_function::AccommodatingSequentialCollapsableWaveFunction,
+        collapsable_wave_function::{CollapsableWaveFunction, CollapseOptions},
+        entropic_collapsable_wave_function::EntropicCollapsableWaveFunction,
+        sequential_collapsable_wave_function::SequentialCollapsableWaveFunction,
+    },
+    testing, WaveFunction,
+};
+
+// every sample collapses once per random seed so that lucky and unlucky seeds are weighed alike
+const RANDOM_SEEDS_TOTAL: u64 = 8;
+// collapses that exceed this many iterations are counted as failures instead of stalling the benchmark
+const MAX_ITERATIONS: u64 = 200_000;
+
+const IMPLEMENTATIONS: [&str; 4] = [
+    "sequential",
+    "accommodating",
+    "accommodating_sequential",
+    "entropic",
+];
+
+/// This function collapses the wave function once per random seed, returning how many of the collapses failed.
+fn get_failures_total<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, String>>(
+    wave_function: &'a WaveFunction<String>,
+) -> u64 {
+    let mut failures_total: u64 = 0;
+    for random_seed in 0..RANDOM_SEEDS_TOTAL {
+        let mut collapsable_wave_function = wave_function
+            .get_collapsable_wave_function::<TCollapsableWaveFunction>(Some(random_seed));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: Some(MAX_ITERATIONS),
+            ..CollapseOptions::default()
+        });
+        if collapsable_wave_function.collapse().is_err() {
+            failures_total += 1;
+        }
+    }
+    failures_total
+}
+
+fn collapse_with_implementation(wave_function: &WaveFunction<String>, implementation: &str) -> u64 {
+    match implementation {
+        "sequential" => {
+            get_failures_total::<SequentialCollapsableWaveFunction<String>>(wave_function)
+        }
+        "accommodating" => {
+            get_failures_total::<AccommodatingCollapsableWaveFunction<String>>(wave_function)
+        }
+        "accommodating_sequential" => get_failures_total::<
+            AccommodatingSequentialCollapsableWaveFunction<String>,
+        >(wave_function),
+        "entropic" => get_failures_total::<EntropicCollapsableWaveFunction<String>>(wave_function),
+        _ => unreachable!("Unknown implementation {implementation}."),
+    }
+}
+
+/// This function benchmarks every implementation against each fixture of the family, printing the failure rate of each before its timings.
+fn bench_fixture_family(
+    c: &mut Criterion,
+    family_name: &str,
+    fixtures: Vec<(String, WaveFunction<String>)>,
+) {
+    let mut group = c.benchmark_group(family_name);
+    group.sample_size(10);
+    for (fixture_name, wave_function) in fixtures.iter() {
+        wave_function.validate().unwrap();
+        for implementation in IMPLEMENTATIONS {
+            let failures_total = collapse_with_implementation(wave_function, implementation);
+            println!(
+                "{family_name}/{implementation}/{fixture_name}: {failures_total} of {RANDOM_SEEDS_TOTAL} seeds failed to collapse"
+            );
+            group.bench_with_input(
+                BenchmarkId::new(implementation, fixture_name),
+                wave_function,
+                |b, wave_function| {
+                    b.iter(|| collapse_with_implementation(wave_function, implementation))
+                },
+            );
+        }
+    }
+    group.finish();
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    bench_fixture_family(
+        c,
+        "all_different_clique",
+        [4, 6, 8]
+            .into_iter()
+            .map(|nodes_total| {
+                (
+                    format!("{nodes_total}_nodes"),
+                    testing::generate_all_different_clique(nodes_total, nodes_total).unwrap(),
+                )
+            })
+            .collect(),
+    );
+    bench_fixture_family(
+        c,
+        "checkerboard",
+        [8, 12, 16]
+            .into_iter()
+            .map(|size| {
+                (
+                    format!("{size}x{size}"),
+                    testing::generate_checkerboard(size, size).unwrap(),
+                )
+            })
+            .collect(),
+    );
+    bench_fixture_family(
+        c,
+        "all_different_3d_grid",
+        vec![(
+            String::from("4x4x4"),
+            testing::generate_all_different_3d_grid(4, 8).unwrap(),
+        )],
+    );
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
@@ -77,66 +77,67 @@ impl Landscape {
         Landscape { width, height }
     }
     fn get_wave_function(&self) -> WaveFunction<LandscapeElement> {
-        let mut node_state_collections: Vec<NodeStateCollection<LandscapeElement>> = Vec::new();
-        // water
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("water"),
-            LandscapeElement::Water,
-            vec![LandscapeElement::Water, LandscapeElement::Sand],
-        ));
-        // sand
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("sand"),
-            LandscapeElement::Sand,
-            vec![
+        let node_state_collections: Vec<NodeStateCollection<LandscapeElement>> = vec![
+            // water
+            NodeStateCollection::new(
+                String::from("water"),
                 LandscapeElement::Water,
+                vec![LandscapeElement::Water, LandscapeElement::Sand],
+            ),
+            // sand
+            NodeStateCollection::new(
+                String::from("sand"),
                 LandscapeElement::Sand,
+                vec![
+                    LandscapeElement::Water,
+                    LandscapeElement::Sand,
+                    LandscapeElement::Grass,
+                ],
+            ),
+            // grass
+            NodeStateCollection::new(
+                String::from("grass"),
                 LandscapeElement::Grass,
-            ],
-        ));
-        // grass
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("grass"),
-            LandscapeElement::Grass,
-            vec![
-                LandscapeElement::Sand,
-                LandscapeElement::Grass,
-                LandscapeElement::Tree,
-                LandscapeElement::Hill,
-            ],
-        ));
-        // tree
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("tree"),
-            LandscapeElement::Tree,
-            vec![
-                LandscapeElement::Grass,
+                vec![
+                    LandscapeElement::Sand,
+                    LandscapeElement::Grass,
+                    LandscapeElement::Tree,
+                    LandscapeElement::Hill,
+                ],
+            ),
+            // tree
+            NodeStateCollection::new(
+                String::from("tree"),
                 LandscapeElement::Tree,
+                vec![
+                    LandscapeElement::Grass,
+                    LandscapeElement::Tree,
+                    LandscapeElement::Forest,
+                ],
+            ),
+            // forest
+            NodeStateCollection::new(
+                String::from("forest"),
                 LandscapeElement::Forest,
-            ],
-        ));
-        // forest
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("forest"),
-            LandscapeElement::Forest,
-            vec![LandscapeElement::Tree, LandscapeElement::Forest],
-        ));
-        // hill
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("hill"),
-            LandscapeElement::Hill,
-            vec![
-                LandscapeElement::Grass,
+                vec![LandscapeElement::Tree, LandscapeElement::Forest],
+            ),
+            // hill
+            NodeStateCollection::new(
+                String::from("hill"),
                 LandscapeElement::Hill,
+                vec![
+                    LandscapeElement::Grass,
+                    LandscapeElement::Hill,
+                    LandscapeElement::Mountain,
+                ],
+            ),
+            // mountain
+            NodeStateCollection::new(
+                String::from("mountain"),
                 LandscapeElement::Mountain,
-            ],
-        ));
-        // mountain
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("mountain"),
-            LandscapeElement::Mountain,
-            vec![LandscapeElement::Hill, LandscapeElement::Mountain],
-        ));
+                vec![LandscapeElement::Hill, LandscapeElement::Mountain],
+            )
+        ];
 
         let mut node_state_collection_ids: Vec<String> = Vec::new();
         for node_state_collection in node_state_collections.iter() {
@@ -165,30 +166,26 @@ impl Landscape {
                     .get(&from_width_index)
                     .unwrap()
                     .clone();
-                let min_to_height_index: u32;
-                if from_height_index == 0 {
-                    min_to_height_index = 0;
+                let min_to_height_index: u32 = if from_height_index == 0 {
+                    0
                 } else {
-                    min_to_height_index = from_height_index - 1;
-                }
-                let max_to_height_index: u32;
-                if from_height_index == self.height - 1 {
-                    max_to_height_index = self.height - 1;
+                    from_height_index - 1
+                };
+                let max_to_height_index: u32 = if from_height_index == self.height - 1 {
+                    self.height - 1
                 } else {
-                    max_to_height_index = from_height_index + 1;
-                }
-                let min_to_width_index: u32;
-                if from_width_index == 0 {
-                    min_to_width_index = 0;
+                    from_height_index + 1
+                };
+                let min_to_width_index: u32 = if from_width_index == 0 {
+                    0
                 } else {
-                    min_to_width_index = from_width_index - 1;
-                }
-                let max_to_width_index: u32;
-                if from_width_index == self.width - 1 {
-                    max_to_width_index = self.width - 1;
+                    from_width_index - 1
+                };
+                let max_to_width_index: u32 = if from_width_index == self.width - 1 {
+                    self.width - 1
                 } else {
-                    max_to_width_index = from_width_index + 1;
-                }
+                    from_width_index + 1
+                };
                 let mut node_state_collection_ids_per_neighbor_node_id: HashMap<
                     String,
                     Vec<String>,
@@ -216,7 +213,7 @@ impl Landscape {
                 } else {
                     for to_height_index in min_to_height_index..=max_to_height_index {
                         let to_width_index = from_width_index;
-                        if !(from_height_index == to_height_index) {
+                        if from_height_index != to_height_index {
                             debug!("connecting ({from_width_index}, {from_height_index}) to ({to_width_index}, {to_height_index})");
                             let to_node_id: String = node_id_per_x_per_y
                                 .get(&to_height_index)
@@ -230,7 +227,7 @@ impl Landscape {
                     }
                     for to_width_index in min_to_width_index..=max_to_width_index {
                         let to_height_index = from_height_index;
-                        if !(from_width_index == to_width_index) {
+                        if from_width_index != to_width_index {
                             debug!("connecting ({from_width_index}, {from_height_index}) to ({to_width_index}, {to_height_index})");
                             let to_node_id: String = node_id_per_x_per_y
                                 .get(&to_height_index)
@@ -244,7 +241,7 @@ impl Landscape {
                     }
                 }
 
-                let mut node_state_probability_per_node_state_id: HashMap<LandscapeElement, f32> =
+                let mut node_state_probability_per_node_state_id: HashMap<LandscapeElement, f64> =
                     HashMap::new();
                 node_state_probability_per_node_state_id.insert(LandscapeElement::Water, 1.0);
                 node_state_probability_per_node_state_id.insert(LandscapeElement::Sand, 0.1);
@@ -308,7 +305,7 @@ fn run() {
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
-    c.bench_function("landscape", |b| b.iter(|| run()));
+    c.bench_function("landscape", |b| b.iter(run));
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,56 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use wave_function_collapse::wave_function::{
+    collapsable_wave_function::{
+        collapsable_wave_function::CollapsableWaveFunction,
+        entropic_collapsable_wave_function::EntropicCollapsableWaveFunction,
+    },
+    wave_function_builder::WaveFunctionBuilder,
+    WaveFunction,
+};
+
+/// This function builds a path of nodes that can each be in one of 1000 node states, where each neighbor must be within 3 node states of it.
+fn get_wave_function() -> WaveFunction<u32> {
+    let node_states_total: u32 = 1000;
+    let band: u32 = 3;
+    let mut wave_function_builder: WaveFunctionBuilder<u32> = WaveFunctionBuilder::new();
+    for node_state_id in 0..node_states_total {
+        wave_function_builder.permit_only(
+            node_state_id,
+            (node_state_id.saturating_sub(band)..(node_state_id + band + 1).min(node_states_total))
+                .collect(),
+        );
+    }
+    for node_index in 0..50 {
+        wave_function_builder.add_node(
+            &format!("node_{node_index}"),
+            (0..node_states_total).collect(),
+        );
+        if node_index != 0 {
+            wave_function_builder.add_neighbors(
+                &format!("node_{}", node_index - 1),
+                &format!("node_{node_index}"),
+            );
+        }
+    }
+    wave_function_builder.build().unwrap()
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    let wave_function = get_wave_function();
+
+    let mut group = c.benchmark_group("one_thousand_node_states");
+    group.sample_size(10);
+    // using a fixed seed for randomness so that every sample performs the same collapse
+    group.bench_function("entropic", |b| {
+        b.iter(|| {
+            wave_function
+                .get_collapsable_wave_function::<EntropicCollapsableWaveFunction<u32>>(Some(0))
+                .collapse()
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
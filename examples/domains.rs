@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use colored::{ColoredString, Colorize};
+use serde::{Deserialize, Serialize};
+use wave_function_collapse::wave_function::{WaveFunction, NodeStateCollection, Node, NodeStateProbability, collapsable_wave_function::{sequential_collapsable_wave_function::SequentialCollapsableWaveFunction, collapsable_wave_function::CollapsableWaveFunction}};
+extern crate pretty_env_logger;
+
+// a room where the border is made of wall tiles and the inside is made of floor tiles
+const WIDTH: usize = 16;
+const HEIGHT: usize = 8;
+
+#[derive(Debug, Eq, Hash, PartialEq, Clone, PartialOrd, Ord, Serialize, Deserialize)]
+enum Tile {
+    Stone,
+    Brick,
+    Grass,
+    Dirt,
+    Water
+}
+
+impl Tile {
+    fn all() -> Vec<Tile> {
+        vec![Tile::Stone, Tile::Brick, Tile::Grass, Tile::Dirt, Tile::Water]
+    }
+    fn get_domain(&self) -> String {
+        match self {
+            Tile::Stone | Tile::Brick => String::from("wall"),
+            Tile::Grass | Tile::Dirt | Tile::Water => String::from("floor")
+        }
+    }
+    // bricks are never next to bricks and water never touches dirt
+    fn is_permitted_next_to(&self, other: &Tile) -> bool {
+        !matches!((self, other), (Tile::Brick, Tile::Brick) | (Tile::Dirt, Tile::Water) | (Tile::Water, Tile::Dirt))
+    }
+    fn get_colored_text(&self) -> ColoredString {
+        let character = "\u{2588}";
+        match self {
+            Tile::Stone => character.white(),
+            Tile::Brick => character.red(),
+            Tile::Grass => character.green(),
+            Tile::Dirt => character.yellow(),
+            Tile::Water => character.blue()
+        }
+    }
+}
+
+fn get_node_id(x: usize, y: usize) -> String {
+    format!("{x}_{y}")
+}
+
+fn get_domain(x: usize, y: usize) -> String {
+    if x == 0 || y == 0 || x == WIDTH - 1 || y == HEIGHT - 1 {
+        String::from("wall")
+    }
+    else {
+        String::from("floor")
+    }
+}
+
+fn get_neighbor_coordinates(x: usize, y: usize) -> Vec<(usize, usize)> {
+    let mut neighbor_coordinates: Vec<(usize, usize)> = Vec::new();
+    if x != 0 {
+        neighbor_coordinates.push((x - 1, y));
+    }
+    if y != 0 {
+        neighbor_coordinates.push((x, y - 1));
+    }
+    if x != WIDTH - 1 {
+        neighbor_coordinates.push((x + 1, y));
+    }
+    if y != HEIGHT - 1 {
+        neighbor_coordinates.push((x, y + 1));
+    }
+    neighbor_coordinates
+}
+
+// one node state collection per tile permitting every neighbor tile that it may be next to, limited to the neighbor tiles provided
+fn get_node_state_collections(id_prefix: &str, neighbor_tiles: &[Tile]) -> Vec<NodeStateCollection<Tile>> {
+    Tile::all()
+        .into_iter()
+        .map(|tile| {
+            let permitted_tiles: Vec<Tile> = neighbor_tiles.iter().filter(|neighbor_tile| tile.is_permitted_next_to(neighbor_tile)).cloned().collect();
+            NodeStateCollection::new(format!("{id_prefix}_{tile:?}"), tile, permitted_tiles)
+        })
+        .collect()
+}
+
+fn get_permitted_pairs_total(node_state_collections: &[NodeStateCollection<Tile>]) -> usize {
+    node_state_collections.iter().map(|node_state_collection| node_state_collection.node_state_ids.len()).sum()
+}
+
+// every node may be any tile, so the node state collections must differ per pair of domains to keep wall tiles off the floor and floor tiles out of the walls
+fn get_wave_function_without_domains() -> WaveFunction<Tile> {
+    let mut node_state_collections: Vec<NodeStateCollection<Tile>> = Vec::new();
+    for domain in ["wall", "floor"] {
+        for neighbor_domain in ["wall", "floor"] {
+            let neighbor_tiles: Vec<Tile> = Tile::all().into_iter().filter(|tile| tile.get_domain() == neighbor_domain).collect();
+            node_state_collections.extend(get_node_state_collections(&format!("{domain}_to_{neighbor_domain}"), &neighbor_tiles));
+        }
+    }
+
+    let mut nodes: Vec<Node<Tile>> = Vec::new();
+    for x in 0..WIDTH {
+        for y in 0..HEIGHT {
+            let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+            for (neighbor_x, neighbor_y) in get_neighbor_coordinates(x, y) {
+                let id_prefix = format!("{}_to_{}", get_domain(x, y), get_domain(neighbor_x, neighbor_y));
+                let node_state_collection_ids: Vec<String> = Tile::all().iter().map(|tile| format!("{id_prefix}_{tile:?}")).collect();
+                node_state_collection_ids_per_neighbor_node_id.insert(get_node_id(neighbor_x, neighbor_y), node_state_collection_ids);
+            }
+            nodes.push(Node::new(get_node_id(x, y), NodeStateProbability::get_equal_probability(&Tile::all()), node_state_collection_ids_per_neighbor_node_id));
+        }
+    }
+    WaveFunction::new(nodes, node_state_collections)
+}
+
+// every node shares the same node state collections since the domain of each node removes the tiles that it could never be
+fn get_wave_function_with_domains() -> WaveFunction<Tile> {
+    let node_state_collections = get_node_state_collections("tile", &Tile::all());
+    let node_state_collection_ids: Vec<String> = node_state_collections.iter().map(|node_state_collection| node_state_collection.id.clone()).collect();
+
+    let mut nodes: Vec<Node<Tile>> = Vec::new();
+    for x in 0..WIDTH {
+        for y in 0..HEIGHT {
+            let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+            for (neighbor_x, neighbor_y) in get_neighbor_coordinates(x, y) {
+                node_state_collection_ids_per_neighbor_node_id.insert(get_node_id(neighbor_x, neighbor_y), node_state_collection_ids.clone());
+            }
+            nodes.push(Node::new_with_domain(get_node_id(x, y), NodeStateProbability::get_equal_probability(&Tile::all()), node_state_collection_ids_per_neighbor_node_id, Some(get_domain(x, y))));
+        }
+    }
+    let state_domains: HashMap<Tile, String> = Tile::all().into_iter().map(|tile| {
+        let domain = tile.get_domain();
+        (tile, domain)
+    }).collect();
+    WaveFunction::new_with_domains(nodes, node_state_collections, state_domains).unwrap()
+}
+
+fn print_room(wave_function: &WaveFunction<Tile>) {
+    let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<Tile>>(None).collapse().unwrap();
+    for y in 0..HEIGHT {
+        for x in 0..WIDTH {
+            let tile = collapsed_wave_function.node_state_per_node_id.get(&get_node_id(x, y)).unwrap();
+            assert_eq!(get_domain(x, y), tile.get_domain());
+            print!("{}", tile.get_colored_text());
+        }
+        println!();
+    }
+}
+
+fn main() {
+    std::env::set_var("RUST_LOG", "trace");
+    //pretty_env_logger::init();
+
+    let wave_function_without_domains = get_wave_function_without_domains();
+    wave_function_without_domains.validate().unwrap();
+    let wave_function_with_domains = get_wave_function_with_domains();
+    wave_function_with_domains.validate().unwrap();
+
+    println!("Without domains: {} node state collections permitting {} neighbor tiles.", wave_function_without_domains.get_node_state_collections().len(), get_permitted_pairs_total(&wave_function_without_domains.get_node_state_collections()));
+    print_room(&wave_function_without_domains);
+    println!("With domains: {} node state collections permitting {} neighbor tiles.", wave_function_with_domains.get_node_state_collections().len(), get_permitted_pairs_total(&wave_function_with_domains.get_node_state_collections()));
+    print_room(&wave_function_with_domains);
+}
@@ -85,7 +85,7 @@ impl ImageFragment {
             }
         }
         ImageFragment {
-            pixels: pixels,
+            pixels,
             width: self.height,
             height: self.width
         }
@@ -107,7 +107,7 @@ impl ImageFragment {
             }
         }
         ImageFragment {
-            pixels: pixels,
+            pixels,
             width: self.width,
             height: self.height
         }
@@ -122,10 +122,11 @@ struct Canvas {
 impl Canvas {
     fn new(width: u32, height: u32) -> Self {
         Canvas {
-            width: width,
-            height: height
+            width,
+            height
         }
     }
+    #[allow(clippy::too_many_arguments)]
     fn get_wave_function(&self, source_image_file_path: &str, fragment_width: u32, fragment_height: u32, is_reflection_permitted: bool, is_rotation_permitted: bool, is_periodic: bool, contains_ground: bool) -> WaveFunction<ImageFragment> {
 
         // get all of the possible image fragments from the original image
@@ -136,7 +137,7 @@ impl Canvas {
         let image_height = image.height();
 
         let mut image_fragments: HashSet<ImageFragment> = HashSet::new();
-        let mut image_fragment_duplicates_total_per_image_fragment: HashMap<ImageFragment, f32> = HashMap::new();
+        let mut image_fragment_duplicates_total_per_image_fragment: HashMap<ImageFragment, f64> = HashMap::new();
         let mut ground_image_fragments: HashSet<ImageFragment> = HashSet::new();
 
         for image_height_index in 0..(image_height - (fragment_height - 1)) {
@@ -208,9 +209,9 @@ impl Canvas {
             //println!("Root:");
             //root_image_fragment.print();
             let mut permitted_node_states_per_height_offset_per_width_offset: HashMap<i8, HashMap<i8, Vec<ImageFragment>>> = HashMap::new();
-            for width_offset in -1..=1 as i8 {
+            for width_offset in -1..=1_i8 {
                 let mut permitted_node_states_per_height_offset: HashMap<i8, Vec<ImageFragment>> = HashMap::new();
-                for height_offset in -1..=1 as i8 {
+                for height_offset in -1..=1_i8 {
                     // do not setup node state collection for root overlapping root
                     if !(height_offset == 0 && width_offset == 0 ||
                         height_offset.abs() == 1 && width_offset.abs() == 1) {
@@ -234,9 +235,9 @@ impl Canvas {
         let mut node_state_collection_ids_per_height_offset_per_width_offset: HashMap<i8, HashMap<i8, Vec<String>>> = HashMap::new();
         for (from_node_state, permitted_node_states_per_height_offset_per_width_offset) in permitted_node_states_per_height_offset_per_width_offset_per_node_state.into_iter() {
             for (width_offset, permitted_node_states_per_height_offset) in permitted_node_states_per_height_offset_per_width_offset.into_iter() {
-                node_state_collection_ids_per_height_offset_per_width_offset.entry(width_offset).or_insert(HashMap::new());
+                node_state_collection_ids_per_height_offset_per_width_offset.entry(width_offset).or_default();
                 for (height_offset, permitted_node_states) in permitted_node_states_per_height_offset.into_iter() {
-                    node_state_collection_ids_per_height_offset_per_width_offset.get_mut(&width_offset).unwrap().entry(height_offset).or_insert(Vec::new());
+                    node_state_collection_ids_per_height_offset_per_width_offset.get_mut(&width_offset).unwrap().entry(height_offset).or_default();
 
                     let node_state_collection_id = Uuid::new_v4().to_string();
                     let node_state_collection: NodeStateCollection<ImageFragment> = NodeStateCollection::new(node_state_collection_id.clone(), from_node_state.clone(), permitted_node_states);
@@ -265,8 +266,8 @@ impl Canvas {
             for node_height_index in 0..(self.height - (fragment_height - 1)) as i8 {
                 let node_id: &String = node_id_per_height_index_per_width_index.get(&(node_width_index as usize)).unwrap().get(&(node_height_index as usize)).unwrap();
                 let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
-                for neighbor_width_offset in -1..=1 as i8 {
-                    for neighbor_height_offset in -1..=1 as i8 {
+                for neighbor_width_offset in -1..=1_i8 {
+                    for neighbor_height_offset in -1..=1_i8 {
                         if !(neighbor_width_offset == 0 && neighbor_height_offset == 0 ||
                             neighbor_width_offset.abs() == 1 && neighbor_height_offset.abs() == 1) {
                             let mut neighbor_width_index = node_width_index + neighbor_width_offset;
@@ -298,7 +299,7 @@ impl Canvas {
                     }
                 }
 
-                let mut node_state_ratio_per_node_state_id: HashMap<ImageFragment, f32> = HashMap::new();
+                let mut node_state_ratio_per_node_state_id: HashMap<ImageFragment, f64> = HashMap::new();
                 if contains_ground {
                     if node_height_index + 1 == (self.height - (fragment_height - 1)) as i8 {
                         for (image_fragment, ratio) in image_fragment_duplicates_total_per_image_fragment.iter() {
@@ -347,7 +348,7 @@ impl Canvas {
         for _ in 0..self.width {
             let mut vec = Vec::new();
             for _ in 0..self.height {
-                vec.push([0 as u8, 0, 128, 0]);
+                vec.push([0_u8, 0, 128, 0]);
             }
             pixels.push(vec);
         }
@@ -370,19 +371,19 @@ impl Canvas {
         }
 
         for height_index in 0..self.height as usize {
-            for width_index in 0..self.width as usize {
-                let color = pixels[width_index][height_index];
+            for pixels_per_height in pixels.iter() {
+                let color = pixels_per_height[height_index];
                 print_pixel(&color);
             }
-            println!("");
+            println!();
         }
     }
-    fn print_step(&self, collapsed_node_states: &Vec<CollapsedNodeState<ImageFragment>>, step_index: usize) {
+    fn print_step(&self, collapsed_node_states: &[CollapsedNodeState<ImageFragment>], step_index: usize) {
         let mut pixels: Vec<Vec<[u8; 4]>> = Vec::new();
         for _ in 0..self.width {
             let mut vec = Vec::new();
             for _ in 0..self.height {
-                vec.push([0 as u8, 0, 128, 0]);
+                vec.push([0_u8, 0, 128, 0]);
             }
             pixels.push(vec);
         }
@@ -411,10 +412,10 @@ impl Canvas {
 
         println!("Step {step_index} ======================================");
         for height_index in 0..self.height as usize {
-            for width_index in 0..self.width as usize {
-                print_pixel(&pixels[width_index][height_index]);
+            for pixels_per_height in pixels.iter() {
+                print_pixel(&pixels_per_height[height_index]);
             }
-            println!("");
+            println!();
         }
     }
 }
@@ -472,7 +473,7 @@ fn main() {
 
     let mut file = tempfile::NamedTempFile::new().unwrap();
     let bytes = base64::decode(image_base64).unwrap();
-    file.write(bytes.as_slice()).unwrap();
+    file.write_all(bytes.as_slice()).unwrap();
     let file_path: &str = file.path().to_str().unwrap();
 
     let canvas = Canvas::new(40, 40);
@@ -66,6 +66,19 @@ impl LandscapeElement {
             panic!("Unexpected node state: {node_state_id}.");
         }
     }
+    /// This function returns the RGBA color of each node state when saving the landscape as an image, approximating the terminal colors.
+    #[allow(dead_code)]
+    fn get_color(node_state_id: &LandscapeElement) -> [u8; 4] {
+        match node_state_id {
+            LandscapeElement::Water => [0, 0, 205, 255],
+            LandscapeElement::Sand => [205, 205, 0, 255],
+            LandscapeElement::Grass => [0, 255, 0, 255],
+            LandscapeElement::Tree => [0, 205, 0, 255],
+            LandscapeElement::Forest => [255, 0, 255, 255],
+            LandscapeElement::Hill => [127, 127, 127, 255],
+            LandscapeElement::Mountain => [229, 229, 229, 255]
+        }
+    }
 }
 
 impl std::fmt::Display for LandscapeElement {
@@ -83,55 +96,56 @@ struct Landscape {
 impl Landscape {
     fn new(width: u32, height: u32) -> Self {
         Landscape {
-            width: width,
-            height: height
+            width,
+            height
         }
     }
     fn get_wave_function(&self) -> WaveFunction<LandscapeElement> {
 
-        let mut node_state_collections: Vec<NodeStateCollection<LandscapeElement>> = Vec::new();
-        // water
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("water"),
-            LandscapeElement::Water,
-            vec![LandscapeElement::Water, LandscapeElement::Sand]
-        ));
-        // sand
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("sand"),
-            LandscapeElement::Sand,
-            vec![LandscapeElement::Water, LandscapeElement::Sand, LandscapeElement::Grass]
-        ));
-        // grass
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("grass"),
-            LandscapeElement::Grass,
-            vec![LandscapeElement::Sand, LandscapeElement::Grass, LandscapeElement::Tree, LandscapeElement::Hill]
-        ));
-        // tree
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("tree"),
-            LandscapeElement::Tree,
-            vec![LandscapeElement::Grass, LandscapeElement::Tree, LandscapeElement::Forest]
-        ));
-        // forest
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("forest"),
-            LandscapeElement::Forest,
-            vec![LandscapeElement::Tree, LandscapeElement::Forest]
-        ));
-        // hill
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("hill"),
-            LandscapeElement::Hill,
-            vec![LandscapeElement::Grass, LandscapeElement::Hill, LandscapeElement::Mountain]
-        ));
-        // mountain
-        node_state_collections.push(NodeStateCollection::new(
-            String::from("mountain"),
-            LandscapeElement::Mountain,
-            vec![LandscapeElement::Hill, LandscapeElement::Mountain]
-        ));
+        let node_state_collections: Vec<NodeStateCollection<LandscapeElement>> = vec![
+            // water
+            NodeStateCollection::new(
+                String::from("water"),
+                LandscapeElement::Water,
+                vec![LandscapeElement::Water, LandscapeElement::Sand]
+            ),
+            // sand
+            NodeStateCollection::new(
+                String::from("sand"),
+                LandscapeElement::Sand,
+                vec![LandscapeElement::Water, LandscapeElement::Sand, LandscapeElement::Grass]
+            ),
+            // grass
+            NodeStateCollection::new(
+                String::from("grass"),
+                LandscapeElement::Grass,
+                vec![LandscapeElement::Sand, LandscapeElement::Grass, LandscapeElement::Tree, LandscapeElement::Hill]
+            ),
+            // tree
+            NodeStateCollection::new(
+                String::from("tree"),
+                LandscapeElement::Tree,
+                vec![LandscapeElement::Grass, LandscapeElement::Tree, LandscapeElement::Forest]
+            ),
+            // forest
+            NodeStateCollection::new(
+                String::from("forest"),
+                LandscapeElement::Forest,
+                vec![LandscapeElement::Tree, LandscapeElement::Forest]
+            ),
+            // hill
+            NodeStateCollection::new(
+                String::from("hill"),
+                LandscapeElement::Hill,
+                vec![LandscapeElement::Grass, LandscapeElement::Hill, LandscapeElement::Mountain]
+            ),
+            // mountain
+            NodeStateCollection::new(
+                String::from("mountain"),
+                LandscapeElement::Mountain,
+                vec![LandscapeElement::Hill, LandscapeElement::Mountain]
+            )
+        ];
 
         let mut node_state_collection_ids: Vec<String> = Vec::new();
         for node_state_collection in node_state_collections.iter() {
@@ -155,34 +169,30 @@ impl Landscape {
             for from_width_index in 0..self.width {
                 debug!("setup ({from_width_index}, {from_height_index})");
                 let from_node_id: String = node_id_per_x_per_y.get(&from_height_index).unwrap().get(&from_width_index).unwrap().clone();
-                let min_to_height_index: u32;
-                if from_height_index == 0 {
-                    min_to_height_index = 0;
+                let min_to_height_index: u32 = if from_height_index == 0 {
+                    0
                 }
                 else {
-                    min_to_height_index = from_height_index - 1;
-                }
-                let max_to_height_index: u32;
-                if from_height_index == self.height - 1 {
-                    max_to_height_index = self.height - 1;
+                    from_height_index - 1
+                };
+                let max_to_height_index: u32 = if from_height_index == self.height - 1 {
+                    self.height - 1
                 }
                 else {
-                    max_to_height_index = from_height_index + 1;
-                }
-                let min_to_width_index: u32;
-                if from_width_index == 0 {
-                    min_to_width_index = 0;
+                    from_height_index + 1
+                };
+                let min_to_width_index: u32 = if from_width_index == 0 {
+                    0
                 }
                 else {
-                    min_to_width_index = from_width_index - 1;
-                }
-                let max_to_width_index: u32;
-                if from_width_index == self.width - 1 {
-                    max_to_width_index = self.width - 1;
+                    from_width_index - 1
+                };
+                let max_to_width_index: u32 = if from_width_index == self.width - 1 {
+                    self.width - 1
                 }
                 else {
-                    max_to_width_index = from_width_index + 1;
-                }
+                    from_width_index + 1
+                };
                 let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
 
                 if true {
@@ -200,7 +210,7 @@ impl Landscape {
                 else {
                     for to_height_index in min_to_height_index..=max_to_height_index {
                         let to_width_index = from_width_index;
-                        if !(from_height_index == to_height_index) {
+                        if from_height_index != to_height_index {
                             debug!("connecting ({from_width_index}, {from_height_index}) to ({to_width_index}, {to_height_index})");
                             let to_node_id: String = node_id_per_x_per_y.get(&to_height_index).unwrap().get(&to_width_index).unwrap().clone();
                             node_state_collection_ids_per_neighbor_node_id.insert(to_node_id, node_state_collection_ids.clone());
@@ -208,7 +218,7 @@ impl Landscape {
                     }
                     for to_width_index in min_to_width_index..=max_to_width_index {
                         let to_height_index = from_height_index;
-                        if !(from_width_index == to_width_index) {
+                        if from_width_index != to_width_index {
                             debug!("connecting ({from_width_index}, {from_height_index}) to ({to_width_index}, {to_height_index})");
                             let to_node_id: String = node_id_per_x_per_y.get(&to_height_index).unwrap().get(&to_width_index).unwrap().clone();
                             node_state_collection_ids_per_neighbor_node_id.insert(to_node_id, node_state_collection_ids.clone());
@@ -216,7 +226,7 @@ impl Landscape {
                     }
                 }
 
-                let mut node_state_probability_per_node_state_id: HashMap<LandscapeElement, f32> = HashMap::new();
+                let mut node_state_probability_per_node_state_id: HashMap<LandscapeElement, f64> = HashMap::new();
                 node_state_probability_per_node_state_id.insert(LandscapeElement::Water, 1.0);
                 node_state_probability_per_node_state_id.insert(LandscapeElement::Sand, 0.1);
                 node_state_probability_per_node_state_id.insert(LandscapeElement::Grass, 1.0);
@@ -238,10 +248,26 @@ impl Landscape {
     }
 }
 
+/// This function returns the value following the flag in the arguments, if the flag is present.
+fn get_argument_value<'a>(arguments: &'a [String], flag: &str) -> Option<&'a str> {
+    arguments
+        .iter()
+        .position(|argument| argument == flag)
+        .and_then(|flag_index| arguments.get(flag_index + 1))
+        .map(|value| value.as_str())
+}
+
 fn main() {
     std::env::set_var("RUST_LOG", "trace");
     //pretty_env_logger::init();
 
+    // the landscape is also saved as a PNG image when a file path is provided via --png, drawing each node as a square of --pixel-scale pixels
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    let png_file_path: Option<&str> = get_argument_value(&arguments, "--png");
+    let pixel_scale: u32 = get_argument_value(&arguments, "--pixel-scale")
+        .map(|pixel_scale| pixel_scale.parse::<u32>().expect("The pixel scale must be a positive integer."))
+        .unwrap_or(8);
+
     let start = Instant::now();
 
     let width: u32 = 50;
@@ -259,6 +285,22 @@ fn main() {
 
     let collapsed_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<LandscapeElement>>(random_seed).collapse().unwrap();
 
+    if let Some(png_file_path) = png_file_path {
+        #[cfg(feature = "image")]
+        {
+            let get_coordinate = |node_id: &str| -> Option<(u32, u32)> {
+                let (x, y) = node_id.split_once('_')?;
+                Some((x.parse::<u32>().ok()?, y.parse::<u32>().ok()?))
+            };
+            wave_function_collapse::wave_function::visualize::save_png(&collapsed_wave_function, png_file_path, width, height, Some(pixel_scale), get_coordinate, LandscapeElement::get_color).unwrap();
+            println!("Saved landscape to {png_file_path}");
+        }
+        #[cfg(not(feature = "image"))]
+        {
+            println!("Cannot save landscape to {png_file_path} with pixel scale {pixel_scale} without the image feature, such as via --features image.");
+        }
+    }
+
     let mut node_state_per_y_per_x: Vec<Vec<Option<LandscapeElement>>> = Vec::new();
     for _ in 0..width {
         let mut node_state_per_y: Vec<Option<LandscapeElement>> = Vec::new();
@@ -282,8 +324,8 @@ fn main() {
     println!("-");
     for y in 0..height as usize {
         print!("|");
-        for x in 0..width as usize {
-            let node_state_id = node_state_per_y_per_x[x][y].as_ref().unwrap();
+        for node_state_per_y in node_state_per_y_per_x.iter() {
+            let node_state_id = node_state_per_y[y].as_ref().unwrap();
             let colored_text = LandscapeElement::get_colored_text_by_node_state_id(node_state_id);
             print!("{}{}", colored_text, colored_text);
         }
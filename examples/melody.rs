@@ -0,0 +1,95 @@
+use std::{collections::HashMap, time::Instant};
+use serde::{Deserialize, Serialize};
+use wave_function_collapse::wave_function::{WaveFunction, NodeStateCollection, collapsable_wave_function::{entropic_collapsable_wave_function::EntropicCollapsableWaveFunction, collapsable_wave_function::CollapsableWaveFunction}};
+
+const NOTE_NAMES: [&str; 8] = ["C4", "D4", "E4", "F4", "G4", "A4", "B4", "C5"];
+
+/// This struct is a degree of the C major scale along with whether that degree was reached by a leap larger than a third, so that the rule for the following note only depends on this note.
+#[derive(Debug, Eq, Hash, PartialEq, Clone, Serialize, Deserialize)]
+struct Note {
+    degree: u8,
+    is_after_leap: bool
+}
+
+impl Note {
+    fn is_permitted_next(&self, next: &Note) -> bool {
+        let interval = self.degree.abs_diff(next.degree);
+        // no immediate repeats
+        interval != 0 &&
+            next.is_after_leap == (interval > 2) &&
+            // big leaps must be followed by steps
+            !(self.is_after_leap && interval > 1)
+    }
+}
+
+struct Melody {
+    length: usize
+}
+
+impl Melody {
+    fn new(length: usize) -> Self {
+        Melody {
+            length
+        }
+    }
+    fn get_wave_function(&self) -> WaveFunction<Note> {
+        let mut notes: Vec<Note> = Vec::new();
+        for degree in 0..NOTE_NAMES.len() as u8 {
+            notes.push(Note { degree, is_after_leap: false });
+            notes.push(Note { degree, is_after_leap: true });
+        }
+
+        let forward_node_state_collections: Vec<NodeStateCollection<Note>> = notes
+            .iter()
+            .map(|note| {
+                NodeStateCollection::new(
+                    format!("after_{}_{}", note.degree, note.is_after_leap),
+                    note.clone(),
+                    notes.iter().filter(|next_note| note.is_permitted_next(next_note)).cloned().collect()
+                )
+            })
+            .collect();
+
+        // the tonic and dominant are more likely so that the melody centers on them
+        let mut node_state_ratio_per_node_state_id: HashMap<Note, f64> = HashMap::new();
+        for note in notes.into_iter() {
+            let node_state_ratio = match note.degree {
+                0 | 4 | 7 => 3.0,
+                _ => 1.0
+            };
+            node_state_ratio_per_node_state_id.insert(note, node_state_ratio);
+        }
+
+        WaveFunction::new_chain(self.length, node_state_ratio_per_node_state_id, forward_node_state_collections, true).unwrap()
+    }
+}
+
+fn main() {
+    let length: usize = 32;
+    let melody = Melody::new(length);
+
+    let wave_function = melody.get_wave_function();
+
+    let mut random_instance = fastrand::Rng::new();
+    let random_seed = Some(random_instance.u64(..));
+
+    let start = Instant::now();
+
+    let collapsed_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<Note>>(random_seed).collapse().unwrap();
+
+    let mut previous_note: Option<&Note> = None;
+    for node_index in 0..length {
+        let note = collapsed_wave_function.node_state_per_node_id.get(&format!("chain_{node_index}")).unwrap();
+        if let Some(previous_note) = previous_note {
+            assert!(previous_note.is_permitted_next(note));
+        }
+        print!("{} ", NOTE_NAMES[note.degree as usize]);
+        if node_index % 8 == 7 {
+            println!("|");
+        }
+        previous_note = Some(note);
+    }
+
+    let duration = start.elapsed();
+    println!("Duration: {:?}", duration);
+}
@@ -20,7 +20,7 @@ enum Color {
     Red,
 }
 
-#[derive(Debug, Clone, Hash, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 struct QuestDestination {
     id: usize,
     name: String,
@@ -37,9 +37,15 @@ impl Eq for QuestDestination {
     
 }
 
+impl std::hash::Hash for QuestDestination {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
 impl PartialOrd for QuestDestination {
     fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-        self.id.partial_cmp(&other.id)
+        Some(self.cmp(other))
     }
 }
 
@@ -340,11 +346,10 @@ impl ToVecProximityGraphNode for Vec<Vec<bool>> {
                     let y = fastrand::usize(0..value.len());
                     let x = fastrand::usize(0..value[y].len());
                     let location = (x, y);
-                    if !excluded_locations.contains(&location) && !locations.contains(&location) {
-                        if value[y][x] {
+                    if !excluded_locations.contains(&location) && !locations.contains(&location)
+                        && value[y][x] {
                             locations.push(location);
                         }
-                    }
                 }
                 locations
             };
@@ -363,8 +368,8 @@ impl ToVecProximityGraphNode for Vec<Vec<bool>> {
                         }
                     }
                 }
-                if (failed_to_find_locations.len() as f32) > nodes_length as f32 * 0.5 {
-                    if !included_locations.contains(from_location) {
+                if (failed_to_find_locations.len() as f32) > nodes_length as f32 * 0.5
+                    && !included_locations.contains(from_location) {
                         //println!("excluding current location ({}, {})", from_location.0, from_location.1);
                         excluded_locations.insert(*from_location);
                         is_at_least_one_new_location_excluded = true;
@@ -372,7 +377,6 @@ impl ToVecProximityGraphNode for Vec<Vec<bool>> {
                     //println!("excluding {} locations", failed_to_find_locations.len());
                     //excluded_locations.extend(failed_to_find_locations.drain(..));
                     //is_at_least_one_new_location_excluded = true;
-                }
                 //else {
                 //    if !included_locations.contains(from_location) {
                 //        println!("excluding current location");
@@ -401,7 +405,7 @@ impl ToVecProximityGraphNode for Vec<Vec<bool>> {
 }
 
 fn find_distance(
-    grid: &Vec<Vec<bool>>,
+    grid: &[Vec<bool>],
     start: (usize, usize),
     destination: (usize, usize),
 ) -> Option<usize> {
@@ -516,13 +520,12 @@ fn main() {
             row.push(is_ground);
 
             //println!("({}, {}) = {}", x, y, noise);
-            let colored_character;
-            if noise < 0.0 {
-                colored_character = character.black();
+            let colored_character = if noise < 0.0 {
+                character.black()
             }
             else {
-                colored_character = character.white();
-            }
+                character.white()
+            };
             print!("{}{}", colored_character, colored_character);
         }
         grid.push(row);
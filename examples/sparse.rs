@@ -42,20 +42,20 @@ impl std::fmt::Display for SparseElement {
 impl Sparse {
     fn new(width: u32, height: u32, distance: u32) -> Self {
         Sparse {
-            width: width,
-            height: height,
-            distance: distance
+            width,
+            height,
+            distance
         }
     }
     fn get_wave_function(&self) -> WaveFunction<SparseElement> {
 
-        let mut node_state_collections: Vec<NodeStateCollection<SparseElement>> = Vec::new();
-
-        node_state_collections.push(NodeStateCollection::new(
-            Uuid::new_v4().to_string(),
-            SparseElement::Active,
-            vec![SparseElement::Empty]
-        ));
+        let node_state_collections: Vec<NodeStateCollection<SparseElement>> = vec![
+            NodeStateCollection::new(
+                Uuid::new_v4().to_string(),
+                SparseElement::Active,
+                vec![SparseElement::Empty]
+            )
+        ];
 
         let mut node_state_collection_ids: Vec<String> = Vec::new();
         for node_state_collection in node_state_collections.iter() {
@@ -95,7 +95,7 @@ impl Sparse {
                     }
                 }
 
-                let mut node_state_probability_per_node_state_id: HashMap<SparseElement, f32> = HashMap::new();
+                let mut node_state_probability_per_node_state_id: HashMap<SparseElement, f64> = HashMap::new();
                 node_state_probability_per_node_state_id.insert(SparseElement::Active, 1.0);
                 node_state_probability_per_node_state_id.insert(SparseElement::Empty, 100.0);
 
@@ -155,8 +155,8 @@ fn main() {
     println!("-");
     for y in 0..height as usize {
         print!("|");
-        for x in 0..width as usize {
-            let node_state_id = node_state_per_y_per_x[x][y].as_ref().unwrap();
+        for node_state_per_y in node_state_per_y_per_x.iter() {
+            let node_state_id = node_state_per_y[y].as_ref().unwrap();
             let colored_text = SparseElement::get_colored_text_by_node_state_id(node_state_id);
             print!("{}{}", colored_text, colored_text);
         }
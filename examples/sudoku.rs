@@ -1,7 +1,7 @@
-use std::{collections::HashMap, time::Instant};
+use std::{collections::HashMap, io::{IsTerminal, Read}, time::Instant};
 use wave_function_collapse::wave_function::{
     Node,
-    NodeStateCollection,
+    AllDifferentConstraint,
     WaveFunction, NodeStateProbability, collapsable_wave_function::{sequential_collapsable_wave_function::SequentialCollapsableWaveFunction, collapsable_wave_function::CollapsableWaveFunction}
 };
 
@@ -11,6 +11,24 @@ struct SudokuPuzzle {
 }
 
 impl SudokuPuzzle {
+    /// This function parses the 81 cells of the puzzle row by row, where each clue is a digit from 1 to 9 and each blank cell is either "." or "0". Whitespace is ignored so that the puzzle may span multiple lines.
+    fn parse(puzzle: &str) -> Result<SudokuPuzzle, String> {
+        let mut numbers: Vec<Option<u8>> = Vec::new();
+        for character in puzzle.chars().filter(|character| !character.is_whitespace()) {
+            let number = match character {
+                '.' | '0' => None,
+                '1'..='9' => Some(character as u8 - b'0'),
+                _ => return Err(format!("Cannot parse puzzle with unexpected character {character:?}."))
+            };
+            numbers.push(number);
+        }
+        if numbers.len() != 81 {
+            return Err(format!("Cannot parse puzzle with {} cells when expecting 81 cells.", numbers.len()));
+        }
+        Ok(SudokuPuzzle {
+            number_per_row_per_column: numbers.chunks(9).map(|number_per_column| number_per_column.to_vec()).collect()
+        })
+    }
     fn print(&self) {
         let mut number_per_column_per_row: Vec<Vec<Option<u8>>> = Vec::new();
 
@@ -19,14 +37,14 @@ impl SudokuPuzzle {
                 if y_index == 0 {
                     number_per_column_per_row.push(Vec::new());
                 }
-                number_per_column_per_row.get_mut(x_index).unwrap().push(number.clone());
+                number_per_column_per_row.get_mut(x_index).unwrap().push(*number);
             }
         }
 
-        for (_, number_per_column) in number_per_column_per_row.iter().enumerate() {
+        for number_per_column in number_per_column_per_row.iter() {
             println!("-------------------");
             print!("|");
-            for (_, number_option) in number_per_column.iter().enumerate() {
+            for number_option in number_per_column.iter() {
                 if let Some(number) = number_option {
                     print!("{number}");
                 }
@@ -35,246 +53,183 @@ impl SudokuPuzzle {
                 }
                 print!("|");
             }
-            println!("");
+            println!();
         }
         println!("-------------------");
     }
-    fn get_solution(&self) -> Result<SudokuPuzzle, String> {
-        // setup nodes and node state collections
-        let mut node_id_per_y_per_x: HashMap<usize, HashMap<usize, String>> = HashMap::new();
-        for (x_index, number_per_row) in self.number_per_row_per_column.iter().enumerate() {
-            let mut node_id_per_y: HashMap<usize, String> = HashMap::new();
-            for (y_index, _) in number_per_row.iter().enumerate() {
-                let node_id = format!("node_{}_{}", x_index, y_index);
-                node_id_per_y.insert(y_index, node_id);
-            }
-            node_id_per_y_per_x.insert(x_index, node_id_per_y);
-        }
+    /// This function returns None if the puzzle is unsolvable, such as when the clues contradict each other.
+    fn get_solution(&self) -> Result<Option<SudokuPuzzle>, String> {
+        let get_node_id = |row_index: usize, column_index: usize| -> String {
+            format!("node_{row_index}_{column_index}")
+        };
 
-        let mut exclusive_node_state_collections: Vec<NodeStateCollection<String>> = Vec::new();
-        for number in 1u8..10 {
-            let mut node_state_ids: Vec<String> = Vec::new();
-            for other_number in 1u8..10 {
-                if other_number != number {
-                    node_state_ids.push(format!("state_{other_number}"));
+        // the clues are nodes that can only be in their one node state
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        for (row_index, number_per_column) in self.number_per_row_per_column.iter().enumerate() {
+            for (column_index, number_option) in number_per_column.iter().enumerate() {
+                let node_state_ids: Vec<String> = if let Some(number) = number_option {
+                    vec![format!("state_{number}")]
                 }
+                else {
+                    (1u8..10).map(|number| format!("state_{number}")).collect()
+                };
+                nodes.push(Node::new(
+                    get_node_id(row_index, column_index),
+                    NodeStateProbability::get_equal_probability(&node_state_ids),
+                    HashMap::new()
+                ));
             }
-            let node_state_collection = NodeStateCollection::new(
-                format!("exclusive_{number}"),
-                format!("state_{number}"),
-                node_state_ids
-            );
-            exclusive_node_state_collections.push(node_state_collection);
         }
 
-        // all other node states are possible expect for "number"
-        let mut possible_node_state_collection_per_number: HashMap<u8, NodeStateCollection<String>> = HashMap::new();
-        for number in 1u8..10 {
-            let mut node_state_ids: Vec<String> = Vec::new();
-            for state in 1u8..10 {
-                if state != number {
-                    node_state_ids.push(format!("state_{state}"));
+        // each row, column, and box must contain different numbers
+        let mut all_different_constraints: Vec<AllDifferentConstraint> = Vec::new();
+        for index in 0..9 {
+            all_different_constraints.push(AllDifferentConstraint::new((0..9).map(|column_index| get_node_id(index, column_index)).collect()));
+            all_different_constraints.push(AllDifferentConstraint::new((0..9).map(|row_index| get_node_id(row_index, index)).collect()));
+            let box_row_index = (index / 3) * 3;
+            let box_column_index = (index % 3) * 3;
+            let mut box_node_ids: Vec<String> = Vec::new();
+            for row_index in box_row_index..(box_row_index + 3) {
+                for column_index in box_column_index..(box_column_index + 3) {
+                    box_node_ids.push(get_node_id(row_index, column_index));
                 }
             }
-            let node_state_collection = NodeStateCollection::new(
-                format!("specific_{number}_possible"),
-                format!("state_{number}"),
-                node_state_ids
-            );
-            possible_node_state_collection_per_number.insert(number, node_state_collection);
+            all_different_constraints.push(AllDifferentConstraint::new(box_node_ids));
         }
 
-        // no node states are possible if "number"
-        let mut impossible_node_state_collection_per_number: HashMap<u8, NodeStateCollection<String>> = HashMap::new();
-        for number in 1u8..10 {
-            let node_state_collection = NodeStateCollection::new(
-                format!("specific_{number}_impossible"),
-                format!("state_{number}"),
-                vec![]
-            );
-            impossible_node_state_collection_per_number.insert(number, node_state_collection);
+        let wave_function = WaveFunction::new_with_all_different_constraints(nodes, Vec::new(), all_different_constraints)?;
+        wave_function.validate()?;
+
+        // the sequential collapsable wave function tries every possibility before failing to collapse
+        let Ok(collapsed_wave_function) = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse() else {
+            return Ok(None);
+        };
+
+        let mut number_per_row_per_column: Vec<Vec<Option<u8>>> = vec![vec![None; 9]; 9];
+        for (node_id, node_state_id) in collapsed_wave_function.node_state_per_node_id.iter() {
+            let node_id_split = node_id.split('_').collect::<Vec<&str>>();
+            let row_index = node_id_split[1].parse::<usize>().unwrap();
+            let column_index = node_id_split[2].parse::<usize>().unwrap();
+            let number = node_state_id.split('_').collect::<Vec<&str>>()[1].parse::<u8>().unwrap();
+            number_per_row_per_column[row_index][column_index] = Some(number);
         }
 
-        // when from node is "from number" only "destination state" is possible for neighbor
-        let mut always_node_state_collection_per_to_number_per_from_number: HashMap<u8, HashMap<u8, NodeStateCollection<String>>> = HashMap::new();
-        for from_number in 1u8..10 {
-            let from_number_node_state_id = format!("state_{from_number}");
-            let mut always_node_state_collection_per_to_number: HashMap<u8, NodeStateCollection<String>> = HashMap::new();
-            for to_number in 1u8..10 {
-                let to_number_node_state_id = format!("state_{to_number}");
-
-                let node_state_collection = NodeStateCollection::new(
-                    format!("from_{from_number}_to_{to_number}"),
-                    from_number_node_state_id.clone(),
-                    vec![to_number_node_state_id]
-                );
-
-                always_node_state_collection_per_to_number.insert(to_number, node_state_collection);
-            }
-            always_node_state_collection_per_to_number_per_from_number.insert(from_number, always_node_state_collection_per_to_number);
-        }
+        Ok(Some(SudokuPuzzle {
+            number_per_row_per_column
+        }))
+    }
+}
 
-        let mut node_state_collection_per_id: HashMap<String, NodeStateCollection<String>> = HashMap::new();
-        let mut nodes: Vec<Node<String>> = Vec::new();
-        for (from_x_index, from_number_per_row) in self.number_per_row_per_column.iter().enumerate() {
-            for (from_y_index, from_number_option) in from_number_per_row.iter().enumerate() {
-                let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
-                for (to_x_index, to_number_per_row) in self.number_per_row_per_column.iter().enumerate() {
-                    for (to_y_index, to_number_option) in to_number_per_row.iter().enumerate() {
-                        if !(from_x_index == to_x_index && from_y_index == to_y_index) && 
-                            (
-                                from_x_index == to_x_index ||
-                                from_y_index == to_y_index ||
-                                ((from_x_index / 3) == (to_x_index / 3) && (from_y_index / 3) == (to_y_index / 3))
-                            ) {
-                            
-                            //println!("Neighbors: ({from_x_index}, {from_y_index}) and ({to_x_index}, {to_y_index})");
+// the puzzle that is solved when no puzzle is provided
+const DEFAULT_PUZZLE: &str = "
+    .732.4691
+    .28..6..7
+    ..61.7..8
+    .15763.24
+    6.....87.
+    7..9.....
+    3.16.....
+    28.5493..
+    .6.8.....
+";
+
+/// This function reads the puzzle from the first argument, or else from stdin, falling back to the default puzzle when stdin is a terminal.
+fn get_puzzle_text() -> Result<String, String> {
+    if let Some(puzzle_text) = std::env::args().nth(1) {
+        return Ok(puzzle_text);
+    }
+    let mut stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        return Ok(String::from(DEFAULT_PUZZLE));
+    }
+    let mut puzzle_text = String::new();
+    stdin.read_to_string(&mut puzzle_text).map_err(|error| format!("Cannot read puzzle from stdin: {error}"))?;
+    Ok(puzzle_text)
+}
 
-                            let mut node_state_collection_ids: Vec<String> = Vec::new();
-                            if let Some(from_number) = from_number_option {
-                                // if the from node will already have a value
-                                if let Some(to_number) = to_number_option {
-                                    // and the to node will already have a value
-                                    // then when "from" is in the required state, only permit "to"'s required state
-                                    // else when "from" is in any other state, permit nothing
-                                    
-                                    for possible_from_number in 1u8..10 {
-                                        let node_state_collection: &NodeStateCollection<String>;
-                                        if possible_from_number == *from_number {
-                                            node_state_collection = always_node_state_collection_per_to_number_per_from_number.get(&possible_from_number).unwrap().get(&to_number).unwrap();
-                                        }
-                                        else {
-                                            node_state_collection = impossible_node_state_collection_per_number.get(&possible_from_number).unwrap();
-                                        }
-                                        //println!("When from is {possible_from_number} and to is {to_number} then {:?}", node_state_collection);
-                                        node_state_collection_ids.push(node_state_collection.id.clone());
-                                        node_state_collection_per_id.insert(node_state_collection.id.clone(), node_state_collection.clone());
-                                    }
-                                }
-                                else {
-                                    // and the "to" node is unknown
-                                    // then when "from" is in the required state, permit all but that state
-                                    // else when "from" is in any other state, permit nothing
+fn main() {
 
-                                    for possible_from_number in 1u8..10 {
-                                        let node_state_collection: &NodeStateCollection<String>;
-                                        if possible_from_number == *from_number {
-                                            node_state_collection = possible_node_state_collection_per_number.get(&possible_from_number).unwrap();
-                                        }
-                                        else {
-                                            node_state_collection = impossible_node_state_collection_per_number.get(&possible_from_number).unwrap();
-                                        }
-                                        //println!("When from is {possible_from_number} and to is unknown then {:?}", node_state_collection);
-                                        node_state_collection_ids.push(node_state_collection.id.clone());
-                                        node_state_collection_per_id.insert(node_state_collection.id.clone(), node_state_collection.clone());
-                                    }
-                                }
-                            }
-                            else {
-                                // the "from" node is unknown
-                                if let Some(to_number) = to_number_option {
-                                    // and the "to" node is known
-                                    // then for any possible value of "from" always require the "to" value
-                                    for possible_from_number in 1u8..10 {
-                                        let node_state_collection = always_node_state_collection_per_to_number_per_from_number.get(&possible_from_number).unwrap().get(to_number).unwrap();
-                                        //println!("When from is {possible_from_number} and to is {to_number} then {:?}", node_state_collection);
-                                        node_state_collection_ids.push(node_state_collection.id.clone());
-                                        node_state_collection_per_id.insert(node_state_collection.id.clone(), node_state_collection.clone());
-                                    }
-                                }
-                                else {
-                                    // and the "to" node is always unknown
-                                    // then for any possible value of "from" permit anything but that value for "to"
-                                    for node_state_collection in exclusive_node_state_collections.iter() {
-                                        //println!("When from is unknown and to is unknown then {:?}", node_state_collection);
-                                        node_state_collection_ids.push(node_state_collection.id.clone());
-                                        node_state_collection_per_id.insert(node_state_collection.id.clone(), node_state_collection.clone());
-                                    }
-                                }
-                            }
+    let start = Instant::now();
 
-                            let to_node_id = format!("node_{}_{}", to_x_index, to_y_index);
-                            node_state_collection_ids_per_neighbor_node_id.insert(to_node_id, node_state_collection_ids);
-                        }
-                        else {
-                            //println!("Not neighbors: ({from_x_index}, {from_y_index}) and ({to_x_index}, {to_y_index})");
-                        }
-                    }
-                }
-                let mut node_state_ids: Vec<String> = Vec::new();
-                for number in 1u8..10 {
-                    node_state_ids.push(format!("state_{number}"));
-                }
-                let node = Node::new(
-                    node_id_per_y_per_x.get(&from_x_index).unwrap().get(&from_y_index).unwrap().clone(),
-                    NodeStateProbability::get_equal_probability(&node_state_ids),
-                    node_state_collection_ids_per_neighbor_node_id
-                );
-                nodes.push(node);
-            }
+    let puzzle = match get_puzzle_text().and_then(|puzzle_text| SudokuPuzzle::parse(&puzzle_text)) {
+        Ok(puzzle) => puzzle,
+        Err(error) => {
+            eprintln!("Error: {error}");
+            std::process::exit(2);
         }
+    };
+    puzzle.print();
 
-        let wave_function = WaveFunction::new(nodes, node_state_collection_per_id.values().cloned().collect());
-        wave_function.validate().unwrap();
-
-        let collapsed_wave_function_result = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse();
-
-        if let Ok(collapsed_wave_function) = collapsed_wave_function_result {
-            let mut state_per_row_per_column: Vec<Vec<Option<u8>>> = Vec::new();
-            for index in 1u8..10 {
-                state_per_row_per_column.push(Vec::new());
-                for _ in 1u8..10 {
-                    state_per_row_per_column[(index as usize) - 1].push(None);
-                }
-            }
-            for (node, node_state) in collapsed_wave_function.node_state_per_node_id.iter() {
-                let node_string_split = node.split("_").collect::<Vec<&str>>();
-                let x_index = node_string_split[2].parse::<u8>().unwrap();
-                let y_index = node_string_split[1].parse::<u8>().unwrap();
-                let node_state_string_split = node_state.split("_").collect::<Vec<&str>>();
-                let state = node_state_string_split[1].parse::<u8>().unwrap();
-                state_per_row_per_column[y_index as usize][x_index as usize] = Some(state);
-            }
-
-            let solved_puzzle = SudokuPuzzle {
-                number_per_row_per_column: state_per_row_per_column
-            };
-            Ok(solved_puzzle)
-        }
-        else {
-            Err(collapsed_wave_function_result.err().unwrap())
+    match puzzle.get_solution() {
+        Ok(Some(solution)) => solution.print(),
+        Ok(None) => {
+            println!("unsolvable");
+            std::process::exit(1);
+        },
+        Err(error) => {
+            eprintln!("Error: {error}");
+            std::process::exit(2);
         }
     }
-}
 
-fn main() {
+    let duration = start.elapsed();
+    println!("Duration: {:?}", duration);
+}
 
-    let start = Instant::now();
+#[cfg(test)]
+mod tests {
+    use super::SudokuPuzzle;
 
-    let mut number_per_row_per_column: Vec<Vec<Option<u8>>> = Vec::new();
-    number_per_row_per_column.push(vec![None,    Some(7), Some(3), Some(2), None,    Some(4), Some(6), Some(9), Some(1)]);
-    number_per_row_per_column.push(vec![None,    Some(2), Some(8), None,    None,    Some(6), None,    None,    Some(7)]);
-    number_per_row_per_column.push(vec![None,    None,    Some(6), Some(1), None,    Some(7), None,    None,    Some(8)]);
-    number_per_row_per_column.push(vec![None,    Some(1), Some(5), Some(7), Some(6), Some(3), None,    Some(2), Some(4)]);
-    number_per_row_per_column.push(vec![Some(6), None,    None,    None,    None,    None,    Some(8), Some(7), None]);
-    number_per_row_per_column.push(vec![Some(7), None,    None,    Some(9), None,    None,    None,    None,    None]);
-    number_per_row_per_column.push(vec![Some(3), None,    Some(1), Some(6), None,    None,    None,    None,    None]);
-    number_per_row_per_column.push(vec![Some(2), Some(8), None,    Some(5), Some(4), Some(9), Some(3), None,    None]);
-    number_per_row_per_column.push(vec![None,    Some(6), None,    Some(8), None,    None,    None,    None,    None]);
-    let puzzle = SudokuPuzzle {
-        number_per_row_per_column: number_per_row_per_column
-    };
-    puzzle.print();
+    fn get_numbers(puzzle: &SudokuPuzzle) -> Vec<Vec<u8>> {
+        puzzle.number_per_row_per_column
+            .iter()
+            .map(|number_per_column| number_per_column.iter().map(|number| number.unwrap()).collect())
+            .collect()
+    }
 
-    let solution_result = puzzle.get_solution();
+    #[test]
+    fn solve_known_puzzle() {
+        let puzzle = SudokuPuzzle::parse("53..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79").unwrap();
+        let solution = puzzle.get_solution().unwrap().unwrap();
+        let expected_solution = SudokuPuzzle::parse("
+            534678912
+            672195348
+            198342567
+            859761423
+            426853791
+            713924856
+            961537284
+            287419635
+            345286179
+        ").unwrap();
+        assert_eq!(get_numbers(&expected_solution), get_numbers(&solution));
+    }
 
-    if let Ok(solution) = solution_result {
-        solution.print();
+    #[test]
+    fn solve_default_puzzle() {
+        let solution = SudokuPuzzle::parse(super::DEFAULT_PUZZLE).unwrap().get_solution().unwrap().unwrap();
+        let numbers = get_numbers(&solution);
+        for index in 0..9 {
+            let mut row_numbers: Vec<u8> = numbers[index].clone();
+            let mut column_numbers: Vec<u8> = numbers.iter().map(|number_per_column| number_per_column[index]).collect();
+            let mut box_numbers: Vec<u8> = (0..9).map(|box_index| numbers[(index / 3) * 3 + box_index / 3][(index % 3) * 3 + box_index % 3]).collect();
+            for index_numbers in [&mut row_numbers, &mut column_numbers, &mut box_numbers] {
+                index_numbers.sort();
+                assert_eq!((1..10).collect::<Vec<u8>>(), *index_numbers);
+            }
+        }
     }
-    else {
-        println!("Error: {}", solution_result.err().unwrap());
+
+    #[test]
+    fn contradictory_puzzle_is_unsolvable() {
+        // the first row has two fives
+        let puzzle = SudokuPuzzle::parse("55..7....6..195....98....6.8...6...34..8.3..17...2...6.6....28....419..5....8..79").unwrap();
+        assert!(puzzle.get_solution().unwrap().is_none());
     }
 
-    let duration = start.elapsed();
-    println!("Duration: {:?}", duration);
-}
\ No newline at end of file
+    #[test]
+    fn malformed_puzzle() {
+        assert_eq!("Cannot parse puzzle with 80 cells when expecting 81 cells.", SudokuPuzzle::parse(&".".repeat(80)).err().unwrap());
+        assert_eq!("Cannot parse puzzle with unexpected character 'x'.", SudokuPuzzle::parse(&"x".repeat(81)).err().unwrap());
+    }
+}
@@ -234,6 +234,7 @@ enum Proximity {
 }
 
 /// This struct represents a flattened unit of information, defined by the type.
+#[derive(Default)]
 struct Information {
     national_origin: Option<NationalOrigin>,
     house_color: Option<HouseColor>,
@@ -243,18 +244,6 @@ struct Information {
     information_type: Option<InformationType>
 }
 
-impl Default for Information {
-    fn default() -> Self {
-        Information {
-            national_origin: None,
-            house_color: None,
-            cigarette_type: None,
-            pet: None,
-            drink: None,
-            information_type: None
-        }
-    }
-}
 
 impl Information {
     fn new_national_origin(national_origin: NationalOrigin) -> Self {
@@ -323,17 +312,17 @@ struct Dependency {
 impl Dependency {
     fn new(subject: Information, proximity: Proximity, target: Option<Information>) -> Self {
         Dependency {
-            subject: subject,
-            proximity: proximity,
-            target: target
+            subject,
+            proximity,
+            target
         }
     }
     fn is_static(&self) -> bool {
         self.target.is_none()
     }
     fn is_staticly_applicable(&self, from_house_index: usize, from_information_type: &InformationType) -> bool {
-        if self.subject.information_type.as_ref().unwrap() == from_information_type {
-            if self.target.is_none() {
+        if self.subject.information_type.as_ref().unwrap() == from_information_type
+            && self.target.is_none() {
                 match self.proximity {
                     Proximity::Index(index) => {
                         if from_house_index == index {
@@ -363,7 +352,6 @@ impl Dependency {
                     }
                 }
             }
-        }
         false
     }
     fn is_cross_domain(&self) -> bool {
@@ -429,25 +417,25 @@ impl Dependency {
                 panic!("Cannot use Index when a target is specified.");
             },
             Proximity::ImmediateLeft => {
-                return true;
+                true
             },
             Proximity::RelativeLeft => {
-                return false;
+                false
             },
             Proximity::Same => {
-                return true;
+                true
             },
             Proximity::RelativeRight => {
-                return false;
+                false
             },
             Proximity::ImmediateRight => {
-                return true;
+                true
             }
             Proximity::NotSame => {
-                return false;
+                false
             }
             Proximity::ImmediateAdjacent => {
-                return false;
+                false
             }
         }
     }
@@ -485,7 +473,7 @@ impl ZebraPuzzle {
             // create all node ids
             for house_index in 0..5 {
                 for information_type in InformationType::iter() {
-                    let node_id: String = format!("{}_{:?}_{}", house_index, information_type, Uuid::new_v4().to_string());
+                    let node_id: String = format!("{}_{:?}_{}", house_index, information_type, Uuid::new_v4());
                     all_node_ids.push(node_id);
                 }
             }
@@ -540,22 +528,21 @@ impl ZebraPuzzle {
                 for (from_information_type, from_node_id) in node_id_per_information_type_per_house_index[from_house_index].iter() {
                     let from_node_id: &str = from_node_id;
                     for dependency in self.dependencies.iter() {
-                        if dependency.is_static() {
-                            if dependency.is_staticly_applicable(from_house_index, from_information_type) {
+                        if dependency.is_static()
+                            && dependency.is_staticly_applicable(from_house_index, from_information_type) {
                                 // the nth house for this specific information type is this subject value
                                 let node_state_id: String = dependency.subject.get_node_state_id();
 
-                                if !existing_node_state_id_per_information_type_per_house_index.contains_key(&from_house_index) {
+                                existing_node_state_id_per_information_type_per_house_index.entry(from_house_index).or_insert_with(|| {
                                     let node_state_id_per_information_type: HashMap<&InformationType, String> = HashMap::new();
-                                    existing_node_state_id_per_information_type_per_house_index.insert(from_house_index, node_state_id_per_information_type);
-                                }
+                                    node_state_id_per_information_type
+                                });
                                 existing_node_state_id_per_information_type_per_house_index.get_mut(&from_house_index).unwrap().insert(from_information_type, node_state_id);
                             }
-                        }
                     }
 
-                    for to_house_index in 0..node_id_per_information_type_per_house_index.len() {
-                        for (to_information_type, to_node_id) in node_id_per_information_type_per_house_index[to_house_index].iter() {
+                    for (to_house_index, node_id_per_information_type) in node_id_per_information_type_per_house_index.iter().enumerate() {
+                        for (to_information_type, to_node_id) in node_id_per_information_type.iter() {
                             let to_node_id: &str = to_node_id;
                             if from_node_id != to_node_id {
                                 for dependency in self.dependencies.iter() {
@@ -704,7 +691,7 @@ impl ZebraPuzzle {
         // set the possible node states per node given its information type
         let mut node_state_collection_id_per_node_state_collection_key: HashMap<NodeStateCollectionKey, String> = HashMap::new();
         let mut node_index: usize = 0;
-        for house_index in 0..5 as usize {
+        for house_index in 0..5_usize {
             for information_type in InformationType::iter() {
                 let node_id: &str = all_node_ids.get(node_index).unwrap();
                 let mut node_state_ids: Vec<String> = Vec::new();
@@ -719,7 +706,7 @@ impl ZebraPuzzle {
                 // tie this node to all other neighbors of the same information type
                 let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
                 let mut neighbor_node_index: usize = 0;
-                for neighbor_house_index in 0..5 as usize {
+                for neighbor_house_index in 0..5_usize {
                     for neighbor_information_type in InformationType::iter() {
                         let neighbor_node_id: &str = all_node_ids.get(neighbor_node_index).unwrap();
                         if node_index != neighbor_node_index {
@@ -764,7 +751,7 @@ impl ZebraPuzzle {
                                         }
                                         permitted_node_state_ids.retain(|node_state_id| node_state_id != &from_node_state_id);
                                         let node_state_collection_key = NodeStateCollectionKey {
-                                            from_node_state_id: from_node_state_id,
+                                            from_node_state_id,
                                             to_node_state_ids: permitted_node_state_ids
                                         };
                                         debug!("allowing {house_index} {:?} to {neighbor_house_index} {:?} via {:?}.", information_type, neighbor_information_type, node_state_collection_key);
@@ -828,7 +815,7 @@ impl ZebraPuzzle {
                                         }
                                         permitted_node_state_ids.retain(|node_state_id| node_state_id != &from_node_state_id);
                                         let node_state_collection_key = NodeStateCollectionKey {
-                                            from_node_state_id: from_node_state_id,
+                                            from_node_state_id,
                                             to_node_state_ids: permitted_node_state_ids
                                         };
                                         debug!("allowing {house_index} {:?} to {neighbor_house_index} {:?} via {:?}.", information_type, neighbor_information_type, node_state_collection_key);
@@ -904,7 +891,7 @@ impl ZebraSolution {
             for column in row.into_iter() {
                 print!("{:<13}|", column.unwrap());
             }
-            println!("");
+            println!();
         }
     }
 }
@@ -6,7 +6,7 @@ use std::hash::Hash;
 use serde::{Deserialize, Serialize};
 use crate::wave_function::collapsable_wave_function::collapsable_wave_function::CollapsableWaveFunction;
 use crate::wave_function::collapsable_wave_function::sequential_collapsable_wave_function::SequentialCollapsableWaveFunction;
-use crate::wave_function::{Node, NodeStateCollection, NodeStateProbability, WaveFunction};
+use crate::wave_function::{Node, NodeStateCollection, WaveFunction};
 
 pub struct Distance {
     // the center of the point that the values are quantifiable
@@ -120,7 +120,7 @@ where
     }
 }
 
-pub trait HasProximity: Eq + Hash + Clone + std::fmt::Debug + Ord + Serialize + for<'de> Deserialize<'de> {
+pub trait HasProximity: Eq + Hash + Clone + std::fmt::Debug + Serialize + for<'de> Deserialize<'de> {
     fn get_proximity(&self, other: &Self) -> Proximity where Self: Sized;
 }
 
@@ -186,15 +186,18 @@ impl<T: Clone> ProximityGraph<T> {
             //    };
             //    println!("best is {} from {} to {} while at {}", best_is_what, distance_variance_factor_minimum, distance_variance_factor_maximum, distance_variance_factor);
             //}
-            let primary_node_state_ratio_per_node_state_id = {
-                let node_state_ids = values.iter()
-                    .map(|value| {
-                        NodeState::Primary {
-                            state: value.clone(),
-                        }
-                    })
-                    .collect::<Vec<NodeState<TValue>>>();
-                NodeStateProbability::get_equal_probability(&node_state_ids)
+            let primary_node_state_ratios = {
+                // values may repeat, but each is only a single node state
+                let mut node_state_ratios: Vec<(NodeState<TValue>, f64)> = Vec::new();
+                for value in values.iter() {
+                    let node_state = NodeState::Primary {
+                        state: value.clone(),
+                    };
+                    if !node_state_ratios.iter().any(|(other_node_state, _)| *other_node_state == node_state) {
+                        node_state_ratios.push((node_state, 1.0));
+                    }
+                }
+                node_state_ratios
             };
 
             let (nodes, node_state_collections) = {
@@ -263,9 +266,9 @@ impl<T: Clone> ProximityGraph<T> {
                         node_state_collection_ids_per_neighbor_node_id.insert(neighbor_node_id, node_state_collection_ids);
                     }
 
-                    let node = Node::new(
+                    let node = Node::new_with_ordered_node_states(
                         format!("primary_{}", proximity_graph_node.proximity_graph_node_id),
-                        primary_node_state_ratio_per_node_state_id.clone(),
+                        primary_node_state_ratios.clone(),
                         node_state_collection_ids_per_neighbor_node_id,
                     );
                     nodes.push(node);
@@ -273,19 +276,20 @@ impl<T: Clone> ProximityGraph<T> {
 
                 // create secondary nodes
                 for (value_index, value) in values.iter().enumerate() {
-                    if let Proximity::ExclusiveExistence = value.get_proximity(&value) {
+                    if let Proximity::ExclusiveExistence = value.get_proximity(value) {
                         // this value needs to only exist exactly once
-                        let secondary_node_state_ratio_per_node_state_id = {
-                            let mut node_states = Vec::new();
+                        let secondary_node_state_ratios = {
+                            let mut node_state_ratios = Vec::new();
                             for (node_index, _) in self.nodes.iter().enumerate() {
-                                node_states.push(
+                                node_state_ratios.push((
                                     NodeState::Secondary {
                                         node_index,
                                         state: value.clone(),
-                                    }
-                                );
+                                    },
+                                    1.0,
+                                ));
                             };
-                            NodeStateProbability::get_equal_probability(&node_states)
+                            node_state_ratios
                         };
                         let node_state_collection_ids_per_neighbor_node_id = {
                             let mut node_state_collection_ids_per_neighbor_node_id = HashMap::new();
@@ -313,9 +317,9 @@ impl<T: Clone> ProximityGraph<T> {
 
                             // TODO consider migrating all state logic from primary and secondary layers into secondary layer only
                         };
-                        let node = Node::new(
+                        let node = Node::new_with_ordered_node_states(
                             format!("secondary_{}", value_index),
-                            secondary_node_state_ratio_per_node_state_id,
+                            secondary_node_state_ratios,
                             node_state_collection_ids_per_neighbor_node_id,
                         );
                         nodes.push(node);
@@ -396,7 +400,7 @@ impl<T: Clone> ProximityGraph<T> {
                     }
                 },
                 NodeState::Secondary { state: _, node_index: _ } => {
-                    if let Some(_) = node_id.strip_prefix("primary_") {
+                    if node_id.strip_prefix("primary_").is_some() {
                         panic!("Unexpected secondary node state tied to a primary node.");
                     }
                 },
@@ -426,17 +430,7 @@ mod proximity_graph_tests {
                         if i != i_other || j != j_other {
                             let other_proximity_graph_node_id = format!("node_{}_{}", i_other, j_other);
                             let distance = (
-                                if i < i_other {
-                                    i_other - i
-                                }
-                                else {
-                                    i - i_other
-                                } + if j < j_other {
-                                    j_other - j
-                                }
-                                else {
-                                    j - j_other
-                                }
+                                i_other.abs_diff(i) + j_other.abs_diff(j)
                             ) as f32;
                             distance_per_proximity_graph_node_id.insert(other_proximity_graph_node_id, distance);
                         }
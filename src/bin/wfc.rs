@@ -0,0 +1,134 @@
+use std::process::ExitCode;
+use wave_function_collapse::wave_function::{
+    WaveFunction,
+    collapsable_wave_function::{
+        collapsable_wave_function::{CollapsableWaveFunction, CollapsedWaveFunction},
+        sequential_collapsable_wave_function::SequentialCollapsableWaveFunction,
+        accommodating_collapsable_wave_function::AccommodatingCollapsableWaveFunction,
+        accommodating_sequential_collapsable_wave_function::AccommodatingSequentialCollapsableWaveFunction,
+        entropic_collapsable_wave_function::EntropicCollapsableWaveFunction
+    }
+};
+
+const USAGE: &str = "Usage:
+    wfc validate <input.json>
+    wfc collapse <input.json> [--algorithm sequential|accommodating|accommodating_sequential|entropic] [--seed <u64>] [--output <output.json>]
+    wfc stats <input.json>
+Inputs ending in .ndjson or .jsonl are read as newline-delimited JSON one line at a time.";
+
+/// This enum represents each way that the command can fail, each with its own exit code.
+enum CommandError {
+    Usage(String),
+    Io(String),
+    Invalid(String),
+    Unsatisfiable(String)
+}
+
+impl CommandError {
+    fn get_exit_code(&self) -> u8 {
+        match self {
+            CommandError::Usage(_) => 1,
+            CommandError::Io(_) => 2,
+            CommandError::Invalid(_) => 3,
+            CommandError::Unsatisfiable(_) => 4
+        }
+    }
+    fn get_message(&self) -> &str {
+        match self {
+            CommandError::Usage(message) |
+            CommandError::Io(message) |
+            CommandError::Invalid(message) |
+            CommandError::Unsatisfiable(message) => message
+        }
+    }
+}
+
+fn load_wave_function(input_file_path: &str) -> Result<WaveFunction<String>, CommandError> {
+    if input_file_path.ends_with(".ndjson") || input_file_path.ends_with(".jsonl") {
+        let file = std::fs::File::open(input_file_path)
+            .map_err(|error| CommandError::Io(format!("Cannot read wave function from {input_file_path}: {error}")))?;
+        return WaveFunction::from_reader(std::io::BufReader::new(file))
+            .map_err(|error| CommandError::Invalid(format!("Cannot parse wave function from {input_file_path}: {error}")));
+    }
+    let file = std::fs::File::open(input_file_path)
+        .map_err(|error| CommandError::Io(format!("Cannot read wave function from {input_file_path}: {error}")))?;
+    WaveFunction::load_from_reader(std::io::BufReader::new(file))
+        .map_err(|error| CommandError::Invalid(format!("Cannot parse wave function from {input_file_path}: {error}")))
+}
+
+fn load_valid_wave_function(input_file_path: &str) -> Result<WaveFunction<String>, CommandError> {
+    let wave_function = load_wave_function(input_file_path)?;
+    wave_function.validate().map_err(CommandError::Invalid)?;
+    Ok(wave_function)
+}
+
+fn collapse(wave_function: &WaveFunction<String>, algorithm: &str, random_seed: Option<u64>) -> Result<CollapsedWaveFunction<String>, CommandError> {
+    let collapsed_wave_function_result = match algorithm {
+        "sequential" => wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(random_seed).collapse(),
+        "accommodating" => wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(random_seed).collapse(),
+        "accommodating_sequential" => wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(random_seed).collapse(),
+        "entropic" => wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(random_seed).collapse(),
+        _ => return Err(CommandError::Usage(format!("Unknown algorithm {algorithm}.\n{USAGE}")))
+    };
+    collapsed_wave_function_result.map_err(CommandError::Unsatisfiable)
+}
+
+fn run(arguments: &[String]) -> Result<(), CommandError> {
+    let (Some(subcommand), Some(input_file_path)) = (arguments.first(), arguments.get(1)) else {
+        return Err(CommandError::Usage(String::from(USAGE)));
+    };
+
+    match subcommand.as_str() {
+        "validate" => {
+            load_valid_wave_function(input_file_path)?;
+            println!("Wave function is valid.");
+        },
+        "stats" => {
+            let wave_function = load_wave_function(input_file_path)?;
+            let serialized_statistics = serde_json::to_string_pretty(&wave_function.get_statistics()).unwrap();
+            println!("{serialized_statistics}");
+        },
+        "collapse" => {
+            let mut algorithm: &str = "sequential";
+            let mut random_seed: Option<u64> = None;
+            let mut output_file_path: Option<&str> = None;
+            let mut option_index = 2;
+            while option_index < arguments.len() {
+                let Some(value) = arguments.get(option_index + 1) else {
+                    return Err(CommandError::Usage(format!("Missing value for {}.\n{USAGE}", arguments[option_index])));
+                };
+                match arguments[option_index].as_str() {
+                    "--algorithm" => algorithm = value,
+                    "--seed" => random_seed = Some(value.parse().map_err(|_| CommandError::Usage(format!("Seed {value} is not a valid u64.\n{USAGE}")))?),
+                    "--output" => output_file_path = Some(value),
+                    option => return Err(CommandError::Usage(format!("Unknown option {option}.\n{USAGE}")))
+                }
+                option_index += 2;
+            }
+
+            let wave_function = load_valid_wave_function(input_file_path)?;
+            let collapsed_wave_function = collapse(&wave_function, algorithm, random_seed)?;
+            let serialized_collapsed_wave_function = serde_json::to_string(&collapsed_wave_function).unwrap();
+            if let Some(output_file_path) = output_file_path {
+                std::fs::write(output_file_path, serialized_collapsed_wave_function)
+                    .map_err(|error| CommandError::Io(format!("Cannot write collapsed wave function to {output_file_path}: {error}")))?;
+            }
+            else {
+                println!("{serialized_collapsed_wave_function}");
+            }
+        },
+        _ => return Err(CommandError::Usage(format!("Unknown subcommand {subcommand}.\n{USAGE}")))
+    }
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    match run(&arguments) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(command_error) => {
+            eprintln!("{}", command_error.get_message());
+            ExitCode::from(command_error.get_exit_code())
+        }
+    }
+}
@@ -1,4 +1,15 @@
+#[cfg(feature = "logging")]
+#[macro_use] extern crate log;
+
+// without the logging feature the debug logging is compiled out entirely while its arguments are still type checked
+#[cfg(not(feature = "logging"))]
+macro_rules! debug {
+    ($($argument:tt)+) => {
+        if false {
+            let _ = format_args!($($argument)+);
+        }
+    };
+}
+
 pub mod wave_function;
 pub mod abstractions;
-extern crate pretty_env_logger;
-#[macro_use] extern crate log;
\ No newline at end of file
@@ -1,53 +1,193 @@
-use std::{collections::{HashMap, HashSet}, rc::Rc, hash::Hash, fs::File, io::BufReader, cell::RefCell};
+use std::{collections::{HashMap, HashSet, VecDeque}, rc::Rc, hash::Hash, io::{BufRead, Read, Write}, cell::RefCell, sync::{Arc, Mutex, atomic::{AtomicBool, AtomicUsize, Ordering}}};
 use serde::{Serialize, Deserialize, de::DeserializeOwned};
 use bitvec::prelude::*;
-use log::debug;
-extern crate pretty_env_logger;
 mod indexed_view;
-use crate::wave_function::collapsable_wave_function::collapsable_wave_function::CollapsableNode;
+use crate::wave_function::collapsable_wave_function::collapsable_wave_function::{CollapsableGlobalConstraint, CollapsableNode, SharedMaskPerState, CollapseSnapshot, serialize_sorted_by_node_id};
 
-use self::{collapsable_wave_function::collapsable_wave_function::CollapsableWaveFunction, indexed_view::IndexedView};
+use self::{collapsable_wave_function::{collapsable_wave_function::{CollapsableWaveFunction, CollapsedWaveFunction, CollapseOptions}, sequential_collapsable_wave_function::SequentialCollapsableWaveFunction}, indexed_view::IndexedView, visualize::DotOptions};
 mod probability_collection;
 mod probability_tree;
 mod probability_container;
 pub mod collapsable_wave_function;
+pub mod wave_function_builder;
+pub mod grid;
+pub mod adjacency_learning;
+pub mod visualize;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
 mod tests;
 
-/// This struct makes for housing convenient utility functions.
+// mixes the attempt index into the random seed (using the SplitMix64 finalizer) so that each restart is uncorrelated with the last
+fn get_restart_random_seed(random_seed: u64, attempt_index: u64) -> u64 {
+    if attempt_index == 0 {
+        return random_seed;
+    }
+    let mut mixed_random_seed = random_seed.wrapping_add(attempt_index.wrapping_mul(0x9E3779B97F4A7C15));
+    mixed_random_seed = (mixed_random_seed ^ (mixed_random_seed >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    mixed_random_seed = (mixed_random_seed ^ (mixed_random_seed >> 27)).wrapping_mul(0x94D049BB133111EB);
+    mixed_random_seed ^ (mixed_random_seed >> 31)
+}
+
+// the 128-bit FNV-1a hash, written here instead of using the standard library hasher since the algorithm of the latter is unspecified and may change between Rust releases
+struct NodeStateOrderHasher {
+    hash: u128
+}
+
+impl NodeStateOrderHasher {
+    fn new() -> Self {
+        NodeStateOrderHasher {
+            hash: 0x6c62272e07bb014262b821756295c58d
+        }
+    }
+}
+
+impl std::hash::Hasher for NodeStateOrderHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        for byte in bytes.iter() {
+            self.hash ^= *byte as u128;
+            self.hash = self.hash.wrapping_mul(0x0000000001000000000000000000013B);
+        }
+    }
+    // integers are always hashed as little-endian bytes, and usize as 64 bits, so that the hash is the same on every platform
+    fn write_u16(&mut self, value: u16) {
+        self.write(&value.to_le_bytes());
+    }
+    fn write_u32(&mut self, value: u32) {
+        self.write(&value.to_le_bytes());
+    }
+    fn write_u64(&mut self, value: u64) {
+        self.write(&value.to_le_bytes());
+    }
+    fn write_u128(&mut self, value: u128) {
+        self.write(&value.to_le_bytes());
+    }
+    fn write_usize(&mut self, value: usize) {
+        self.write_u64(value as u64);
+    }
+    fn write_i16(&mut self, value: i16) {
+        self.write_u16(value as u16);
+    }
+    fn write_i32(&mut self, value: i32) {
+        self.write_u32(value as u32);
+    }
+    fn write_i64(&mut self, value: i64) {
+        self.write_u64(value as u64);
+    }
+    fn write_i128(&mut self, value: i128) {
+        self.write_u128(value as u128);
+    }
+    fn write_isize(&mut self, value: isize) {
+        self.write_u64(value as u64);
+    }
+    fn finish(&self) -> u64 {
+        self.hash as u64
+    }
+}
+
+// orders node states by a fixed hash so that node states have the same order regardless of the order of the hashmap that they came from, on any platform and Rust release, without requiring that node states be ordered themselves
+// the hash is 128 bits wide so that two different node states practically never share an order key
+pub(crate) fn get_node_state_order_key<TNodeState: Hash>(node_state: &TNodeState) -> u128 {
+    let mut hasher = NodeStateOrderHasher::new();
+    node_state.hash(&mut hasher);
+    hasher.hash
+}
+
+// the connectivity error describes at most this many groups of nodes, each by at most this many node ids
+const MAXIMUM_DESCRIBED_GROUPS_TOTAL: usize = 10;
+const REPRESENTATIVE_NODE_IDS_PER_GROUP_TOTAL: usize = 3;
+
+// traverses every node reachable from the start node that has not already been traversed, returning the newly traversed node indexes
+fn traverse_node_indexes(neighbor_node_indexes_per_node_index: &[Vec<usize>], start_node_index: usize, is_traversed_per_node_index: &mut [bool]) -> Vec<usize> {
+    let mut traversed_node_indexes: Vec<usize> = Vec::new();
+    let mut potential_node_indexes: Vec<usize> = vec![start_node_index];
+    is_traversed_per_node_index[start_node_index] = true;
+    while let Some(node_index) = potential_node_indexes.pop() {
+        traversed_node_indexes.push(node_index);
+        for neighbor_node_index in neighbor_node_indexes_per_node_index[node_index].iter() {
+            if !is_traversed_per_node_index[*neighbor_node_index] {
+                is_traversed_per_node_index[*neighbor_node_index] = true;
+                potential_node_indexes.push(*neighbor_node_index);
+            }
+        }
+    }
+    traversed_node_indexes
+}
+
+/// This struct makes for housing convenient utility functions. The probabilities of a node are relative to each other and are never required to sum to one, since each node state is chosen in proportion to its share of the total, so normalizing them only makes them easier to read.
 pub struct NodeStateProbability;
 
 impl NodeStateProbability {
-    pub fn get_equal_probability<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord>(node_states: &Vec<TNodeState>) -> HashMap<TNodeState, f32> {
-        let mut node_state_probability_per_node_state: HashMap<TNodeState, f32> = HashMap::new();
+    #[allow(clippy::ptr_arg)]
+    pub fn get_equal_probability<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(node_states: &Vec<TNodeState>) -> HashMap<TNodeState, f64> {
+        let mut node_state_probability_per_node_state: HashMap<TNodeState, f64> = HashMap::new();
 
-        for node_state in node_states.into_iter() {
+        for node_state in node_states.iter() {
             node_state_probability_per_node_state.insert(node_state.clone(), 1.0);
         }
 
         node_state_probability_per_node_state
     }
+    /// This function pairs each node state with its weight, returning an error if any weight is not finite and positive or if a node state is weighted more than once.
+    pub fn get_weighted_probability<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(node_state_weights: Vec<(TNodeState, f64)>) -> Result<HashMap<TNodeState, f64>, String> {
+        let mut node_state_probability_per_node_state: HashMap<TNodeState, f64> = HashMap::new();
+
+        for (node_state, weight) in node_state_weights.into_iter() {
+            if !weight.is_finite() || weight <= 0.0 {
+                return Err(format!("Node state {node_state:?} has weight {weight} but weights must be finite and positive."));
+            }
+            if node_state_probability_per_node_state.contains_key(&node_state) {
+                return Err(format!("Node state {node_state:?} is weighted more than once."));
+            }
+            node_state_probability_per_node_state.insert(node_state, weight);
+        }
+
+        Ok(node_state_probability_per_node_state)
+    }
+    /// This function scales the probabilities so that they sum to one. Probabilities that sum to zero are left unchanged.
+    pub fn normalize<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(node_state_probability_per_node_state: &mut HashMap<TNodeState, f64>) {
+        let probability_total: f64 = node_state_probability_per_node_state.values().sum();
+        if probability_total != 0.0 {
+            for probability in node_state_probability_per_node_state.values_mut() {
+                *probability /= probability_total;
+            }
+        }
+    }
 }
 
 /// This is a node in the graph of the wave function. It can be in any of the provided node states, trying to achieve the cooresponding probability, connected to other nodes as described by the node state collections.
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct Node<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> {
+pub struct Node<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
     pub id: String,
+    // a node may be its own neighbor, restricting it to the node states that its node state collections for itself permit
+    #[serde(serialize_with = "serialize_sorted_by_node_id")]
     pub node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>>,
     pub node_state_ids: Vec<TNodeState>,
-    pub node_state_ratios: Vec<f32>
+    pub node_state_ratios: Vec<f64>,
+    // carried untouched into the collapsed wave function so that the meaning of the node is available alongside its node state
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+    // only node states of this domain, or without any domain, are kept by WaveFunction::new_with_domains
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub domain: Option<String>,
+    // added by add_constraint_to_neighbor along with their ids and moved into the node state collections of the wave function when it is constructed
+    #[serde(skip, default = "Vec::new")]
+    pub anonymous_node_state_collections: Vec<(String, AnonymousNodeStateCollection<TNodeState>)>,
+    // restrictions on how many of the neighbors of this node may be in certain node states, added by add_aggregate_constraint
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    pub aggregate_constraints: Vec<AggregateConstraint<TNodeState>>
 }
 
-impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> Node<TNodeState> {
-    pub fn new(id: String, node_state_ratio_per_node_state_id: HashMap<TNodeState, f32>, node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>>) -> Self {
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> Node<TNodeState> {
+    pub fn new(id: String, node_state_ratio_per_node_state_id: HashMap<TNodeState, f64>, node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>>) -> Self {
         let mut node_state_ids: Vec<TNodeState> = Vec::new();
-        let mut node_state_ratios: Vec<f32> = Vec::new();
+        let mut node_state_ratios: Vec<f64> = Vec::new();
         for (node_state_id, node_state_ratio) in node_state_ratio_per_node_state_id.iter() {
             node_state_ids.push(node_state_id.clone());
             node_state_ratios.push(*node_state_ratio);
         }
         
         // sort the node_state_ids and node_state_probabilities
-        let mut sort_permutation = permutation::sort(&node_state_ids);
+        let mut sort_permutation = permutation::sort_by_key(&node_state_ids, get_node_state_order_key);
         sort_permutation.apply_slice_in_place(&mut node_state_ids);
         sort_permutation.apply_slice_in_place(&mut node_state_ratios);
 
@@ -55,44 +195,915 @@ impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> Node<TNodeState> {
             id,
             node_state_collection_ids_per_neighbor_node_id,
             node_state_ids,
-            node_state_ratios
+            node_state_ratios,
+            metadata: None,
+            domain: None,
+            anonymous_node_state_collections: Vec::new(),
+            aggregate_constraints: Vec::new()
+        }
+    }
+    /// This function creates the node just like new while tagging it with the domain of node states that WaveFunction::new_with_domains keeps for it.
+    pub fn new_with_domain(id: String, node_state_ratio_per_node_state_id: HashMap<TNodeState, f64>, node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>>, domain: Option<String>) -> Self {
+        let mut node = Self::new(id, node_state_ratio_per_node_state_id, node_state_collection_ids_per_neighbor_node_id);
+        node.domain = domain;
+        node
+    }
+    /// This function creates the node just like new, returning an error if the node state ratios are invalid as described by validate_node_state_ratios.
+    pub fn try_new(id: String, node_state_ratio_per_node_state_id: HashMap<TNodeState, f64>, node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>>) -> Result<Self, String> {
+        let node = Self::new(id, node_state_ratio_per_node_state_id, node_state_collection_ids_per_neighbor_node_id);
+        node.validate_node_state_ratios()?;
+        Ok(node)
+    }
+    /// This function keeps the node states in the order provided, which is the order that they are tried in when collapsing without a random seed.
+    pub fn new_with_ordered_node_states(id: String, node_state_ratios: Vec<(TNodeState, f64)>, node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>>) -> Self {
+        let (node_state_ids, node_state_ratios): (Vec<TNodeState>, Vec<f64>) = node_state_ratios.into_iter().unzip();
+        Node {
+            id,
+            node_state_collection_ids_per_neighbor_node_id,
+            node_state_ids,
+            node_state_ratios,
+            metadata: None,
+            domain: None,
+            anonymous_node_state_collections: Vec::new(),
+            aggregate_constraints: Vec::new()
         }
     }
+    /// This function restricts how many of the neighbors of this node may be in the node states of the aggregate constraint, regardless of which node state this node is in.
+    pub fn add_aggregate_constraint(&mut self, aggregate_constraint: AggregateConstraint<TNodeState>) {
+        self.aggregate_constraints.push(aggregate_constraint);
+    }
     pub fn get_id(&self) -> String {
         self.id.clone()
     }
+    pub fn set_metadata(&mut self, metadata: serde_json::Value) {
+        self.metadata = Some(metadata);
+    }
+    /// This function returns an error listing every node state whose ratio is not finite and non-negative, or an error if every node state has a ratio of zero since none of them could be chosen. A node without any node states is permitted since it is simply uncollapsable.
+    pub fn validate_node_state_ratios(&self) -> Result<(), String> {
+        if self.node_state_ids.len() != self.node_state_ratios.len() {
+            return Err(format!("Node {} has {} node states but {} node state ratios.", self.id, self.node_state_ids.len(), self.node_state_ratios.len()));
+        }
+        let invalid_node_state_ratios: Vec<String> = self.node_state_ids.iter()
+            .zip(self.node_state_ratios.iter())
+            .filter(|(_, node_state_ratio)| !node_state_ratio.is_finite() || **node_state_ratio < 0.0)
+            .map(|(node_state_id, node_state_ratio)| format!("{node_state_id:?}: {node_state_ratio}"))
+            .collect();
+        if !invalid_node_state_ratios.is_empty() {
+            return Err(format!("Node {} has node state ratios {} but node state ratios must be finite and non-negative.", self.id, invalid_node_state_ratios.join(", ")));
+        }
+        if !self.node_state_ratios.is_empty() && self.node_state_ratios.iter().all(|node_state_ratio| *node_state_ratio == 0.0) {
+            return Err(format!("Node {} has node state ratios of only zero but at least one node state ratio must be positive.", self.id));
+        }
+        Ok(())
+    }
+    /// This function applies the node state collections from this node to the other node and their reversal from the other node back to this node, returning the reversed node state collections so that they can be provided to the wave function along with the original node state collections.
+    pub fn add_symmetric_neighbor(&mut self, other: &mut Node<TNodeState>, node_state_collections: &[NodeStateCollection<TNodeState>]) -> Vec<NodeStateCollection<TNodeState>> {
+        let reversed_node_state_collections = NodeStateCollection::reversed_from(node_state_collections);
+        self.add_neighbor_node_state_collections(&other.id, node_state_collections);
+        other.add_neighbor_node_state_collections(&self.id, &reversed_node_state_collections);
+        reversed_node_state_collections
+    }
+    /// This function restricts the neighbor node to the permitted node states when this node is in the node state, returning the id of the anonymous node state collection that is registered with the wave function when it is constructed. Identical constraints, from this node or any other, share the same id and therefore the same node state collection. If the id happens to collide with that of a node state collection with different contents, the wave function registers this one under the next free id and updates this node to match.
+    pub fn add_constraint_to_neighbor(&mut self, neighbor_node_id: &str, node_state_id: TNodeState, permitted_node_state_ids: Vec<TNodeState>) -> String {
+        let anonymous_node_state_collection = AnonymousNodeStateCollection::new(node_state_id, permitted_node_state_ids);
+        let (node_state_collection_id, is_added) = anonymous_node_state_collection.get_unique_id(|node_state_collection_id| {
+            self.anonymous_node_state_collections
+                .iter()
+                .find(|(other_node_state_collection_id, _)| other_node_state_collection_id == node_state_collection_id)
+                .map(|(_, other_anonymous_node_state_collection)| *other_anonymous_node_state_collection == anonymous_node_state_collection)
+        });
+        if !is_added {
+            self.anonymous_node_state_collections.push((node_state_collection_id.clone(), anonymous_node_state_collection));
+        }
+        let node_state_collection_ids = self.node_state_collection_ids_per_neighbor_node_id
+            .entry(neighbor_node_id.to_string())
+            .or_default();
+        if !node_state_collection_ids.contains(&node_state_collection_id) {
+            node_state_collection_ids.push(node_state_collection_id.clone());
+        }
+        node_state_collection_id
+    }
+    /// This function returns false if the node is its own neighbor and any of its node state collections for itself does not permit the node state while the node is in that same node state.
+    fn is_self_permitted(&self, node_state_id: &TNodeState, node_state_collection_per_id: &HashMap<&str, &NodeStateCollection<TNodeState>>) -> bool {
+        let Some(node_state_collection_ids) = self.node_state_collection_ids_per_neighbor_node_id.get(&self.id) else {
+            return true;
+        };
+        node_state_collection_ids
+            .iter()
+            .filter_map(|node_state_collection_id| node_state_collection_per_id.get(node_state_collection_id.as_str()))
+            .filter(|node_state_collection| &node_state_collection.node_state_id == node_state_id)
+            .all(|node_state_collection| node_state_collection.node_state_ids.contains(node_state_id))
+    }
+    fn add_neighbor_node_state_collections(&mut self, neighbor_node_id: &str, node_state_collections: &[NodeStateCollection<TNodeState>]) {
+        let node_state_collection_ids = self.node_state_collection_ids_per_neighbor_node_id
+            .entry(neighbor_node_id.to_string())
+            .or_default();
+        for node_state_collection in node_state_collections.iter() {
+            if !node_state_collection_ids.contains(&node_state_collection.id) {
+                node_state_collection_ids.push(node_state_collection.id.clone());
+            }
+        }
+    }
 }
 
 /// This struct represents a relationship between the state of one "original" node to another "neighbor" node, permitting only those node states for the connected neighbor if the original node is in the specific state. This defines the constraints between nodes.
 #[derive(Debug, Serialize, Deserialize, Clone)]
-pub struct NodeStateCollection<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> {
+pub struct NodeStateCollection<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
     pub id: String,
     pub node_state_id: TNodeState,
-    pub node_state_ids: Vec<TNodeState>
+    pub node_state_ids: Vec<TNodeState>,
+    // the node state ids are forbidden instead of permitted until the wave function resolves them into the permitted node state ids
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub is_forbidden: bool
+}
+
+fn is_false(value: &bool) -> bool {
+    !*value
 }
 
-impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> NodeStateCollection<TNodeState> {
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> NodeStateCollection<TNodeState> {
     pub fn new(id: String, node_state_id: TNodeState, node_state_ids: Vec<TNodeState>) -> Self {
         NodeStateCollection {
             id,
+            node_state_id,
+            node_state_ids,
+            is_forbidden: false
+        }
+    }
+    /// This function forbids the neighbor from being in any of the forbidden node states, permitting every other node state of any node in the wave function. The permitted node states are resolved when the wave function is created, so node states added later are permitted without changing this node state collection.
+    pub fn new_forbidden(id: String, node_state_id: TNodeState, forbidden_node_state_ids: Vec<TNodeState>) -> Self {
+        NodeStateCollection {
+            id,
+            node_state_id,
+            node_state_ids: forbidden_node_state_ids,
+            is_forbidden: true
+        }
+    }
+    /// This function moves the anonymous node state collections added to the nodes by add_constraint_to_neighbor into the node state collections, keeping only one node state collection per id. An anonymous node state collection whose id is already taken by a node state collection with different contents is registered under the next unique id instead, which the node is updated to refer to.
+    fn register_anonymous(node_state_collections: &mut Vec<NodeStateCollection<TNodeState>>, nodes: &mut [Node<TNodeState>]) {
+        if nodes.iter().all(|node| node.anonymous_node_state_collections.is_empty()) {
+            return;
+        }
+        let mut node_state_collection_index_per_id: HashMap<String, usize> = node_state_collections
+            .iter()
+            .enumerate()
+            .map(|(node_state_collection_index, node_state_collection)| (node_state_collection.id.clone(), node_state_collection_index))
+            .collect();
+        for node in nodes.iter_mut() {
+            let mut registered_node_state_collection_id_per_id: HashMap<String, String> = HashMap::new();
+            for (node_state_collection_id, anonymous_node_state_collection) in std::mem::take(&mut node.anonymous_node_state_collections) {
+                let registered_node_state_collection_id = anonymous_node_state_collection.register(&node_state_collection_id, node_state_collections, &mut node_state_collection_index_per_id);
+                if registered_node_state_collection_id != node_state_collection_id {
+                    registered_node_state_collection_id_per_id.insert(node_state_collection_id, registered_node_state_collection_id);
+                }
+            }
+            // every id is replaced at once since one anonymous node state collection may be registered under the id that another of this node was known by
+            for node_state_collection_ids in node.node_state_collection_ids_per_neighbor_node_id.values_mut() {
+                for node_state_collection_id in node_state_collection_ids.iter_mut() {
+                    if let Some(registered_node_state_collection_id) = registered_node_state_collection_id_per_id.get(node_state_collection_id) {
+                        node_state_collection_id.clone_from(registered_node_state_collection_id);
+                    }
+                }
+            }
+        }
+    }
+    /// This function replaces the forbidden node states of each forbidden node state collection with every other node state of the nodes, in the order that the node states are first found.
+    fn resolve_forbidden(node_state_collections: &mut [NodeStateCollection<TNodeState>], nodes: &[Node<TNodeState>]) {
+        if !node_state_collections.iter().any(|node_state_collection| node_state_collection.is_forbidden) {
+            return;
+        }
+        let mut all_node_state_ids: Vec<&TNodeState> = Vec::new();
+        let mut distinct_node_state_ids: HashSet<&TNodeState> = HashSet::new();
+        for node in nodes.iter() {
+            for node_state_id in node.node_state_ids.iter() {
+                if distinct_node_state_ids.insert(node_state_id) {
+                    all_node_state_ids.push(node_state_id);
+                }
+            }
+        }
+        for node_state_collection in node_state_collections.iter_mut() {
+            if node_state_collection.is_forbidden {
+                node_state_collection.node_state_ids = all_node_state_ids
+                    .iter()
+                    .filter(|node_state_id| !node_state_collection.node_state_ids.contains(node_state_id))
+                    .map(|node_state_id| (*node_state_id).clone())
+                    .collect();
+                node_state_collection.is_forbidden = false;
+            }
+        }
+    }
+    /// This function inverts node state collections applied from an original node to a neighbor node into the node state collections to apply from the neighbor node back to the original node, permitting each original node state that permits the neighbor node state. The id of each reversed node state collection is derived from the ids of the node state collections that permit its node state.
+    pub fn reversed_from(node_state_collections: &[NodeStateCollection<TNodeState>]) -> Vec<NodeStateCollection<TNodeState>> {
+        // the permitted node states in the order that they are first found
+        let mut reversed_node_state_ids: Vec<&TNodeState> = Vec::new();
+        let mut reversed_index_per_node_state_id: HashMap<&TNodeState, usize> = HashMap::new();
+        let mut permitting_node_state_collection_ids_per_reversed_index: Vec<Vec<&str>> = Vec::new();
+        let mut permitting_node_state_ids_per_reversed_index: Vec<Vec<TNodeState>> = Vec::new();
+        for node_state_collection in node_state_collections.iter() {
+            for node_state_id in node_state_collection.node_state_ids.iter() {
+                let reversed_index = *reversed_index_per_node_state_id
+                    .entry(node_state_id)
+                    .or_insert_with(|| {
+                        reversed_node_state_ids.push(node_state_id);
+                        permitting_node_state_collection_ids_per_reversed_index.push(Vec::new());
+                        permitting_node_state_ids_per_reversed_index.push(Vec::new());
+                        reversed_node_state_ids.len() - 1
+                    });
+                if !permitting_node_state_collection_ids_per_reversed_index[reversed_index].contains(&node_state_collection.id.as_str()) {
+                    permitting_node_state_collection_ids_per_reversed_index[reversed_index].push(&node_state_collection.id);
+                }
+                if !permitting_node_state_ids_per_reversed_index[reversed_index].contains(&node_state_collection.node_state_id) {
+                    permitting_node_state_ids_per_reversed_index[reversed_index].push(node_state_collection.node_state_id.clone());
+                }
+            }
+        }
+
+        reversed_node_state_ids
+            .into_iter()
+            .zip(permitting_node_state_collection_ids_per_reversed_index)
+            .zip(permitting_node_state_ids_per_reversed_index)
+            .enumerate()
+            .map(|(reversed_index, ((node_state_id, permitting_node_state_collection_ids), permitting_node_state_ids))| {
+                NodeStateCollection::new(format!("{}_reversed_{reversed_index}", permitting_node_state_collection_ids.join("_")), node_state_id.clone(), permitting_node_state_ids)
+            })
+            .collect()
+    }
+}
+
+/// This struct is an alternative to a NodeStateCollection for every node state, permitting each neighbor node state for which the predicate returns true given the original node state. Nodes refer to it by id just like a NodeStateCollection and it is expanded into NodeStateCollections by WaveFunction::new_with_functional_node_state_collections, so it is never serialized itself.
+pub struct FunctionalNodeStateCollection<TNodeState> {
+    pub id: String,
+    // called with the original node state and then the neighbor node state
+    #[allow(clippy::type_complexity)]
+    predicate: Box<dyn Fn(&TNodeState, &TNodeState) -> bool>
+}
+
+impl<TNodeState> FunctionalNodeStateCollection<TNodeState> {
+    pub fn new(id: String, predicate: impl Fn(&TNodeState, &TNodeState) -> bool + 'static) -> Self {
+        FunctionalNodeStateCollection {
+            id,
+            predicate: Box::new(predicate)
+        }
+    }
+    pub fn is_permitted(&self, node_state_id: &TNodeState, neighbor_node_state_id: &TNodeState) -> bool {
+        (self.predicate)(node_state_id, neighbor_node_state_id)
+    }
+}
+
+/// This struct is a node that refers to its neighbor nodes and node state collections by their index in the vectors provided to WaveFunction::new_anonymous instead of by their id.
+#[derive(Debug, Clone)]
+pub struct AnonymousNode<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    pub node_state_ratio_per_node_state_id: HashMap<TNodeState, f64>,
+    pub node_state_collection_indexes_per_neighbor_node_index: HashMap<usize, Vec<usize>>
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> AnonymousNode<TNodeState> {
+    pub fn new(node_state_ratio_per_node_state_id: HashMap<TNodeState, f64>, node_state_collection_indexes_per_neighbor_node_index: HashMap<usize, Vec<usize>>) -> Self {
+        AnonymousNode {
+            node_state_ratio_per_node_state_id,
+            node_state_collection_indexes_per_neighbor_node_index
+        }
+    }
+}
+
+/// This struct is a NodeStateCollection without an id, referred to by its index in the vector provided to WaveFunction::new_anonymous or identified by a digest of its contents when added by Node::add_constraint_to_neighbor.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AnonymousNodeStateCollection<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    pub node_state_id: TNodeState,
+    pub node_state_ids: Vec<TNodeState>
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> AnonymousNodeStateCollection<TNodeState> {
+    pub fn new(node_state_id: TNodeState, node_state_ids: Vec<TNodeState>) -> Self {
+        AnonymousNodeStateCollection {
             node_state_id,
             node_state_ids
         }
     }
+    /// This function derives the id from the node state and the permitted node states, in their order, so that identical anonymous node state collections always share the same id. The digest is the same fixed hash that orders node states, so the id does not change between versions or platforms.
+    pub fn get_id(&self) -> String {
+        format!("anonymous_{:032x}", get_node_state_order_key(&(&self.node_state_id, &self.node_state_ids)))
+    }
+    /// This function returns the id derived from the contents, or that id with the lowest numbered suffix if the derived id is already taken by different contents, along with whether the id is already taken by the same contents. The closure returns None for an unused id and otherwise whether the node state collection with that id has the same contents.
+    fn get_unique_id(&self, is_same_contents_per_id: impl Fn(&str) -> Option<bool>) -> (String, bool) {
+        let derived_node_state_collection_id = self.get_id();
+        let mut node_state_collection_id = derived_node_state_collection_id.clone();
+        let mut suffix: usize = 1;
+        loop {
+            match is_same_contents_per_id(&node_state_collection_id) {
+                None => return (node_state_collection_id, false),
+                Some(true) => return (node_state_collection_id, true),
+                Some(false) => {
+                    node_state_collection_id = format!("{derived_node_state_collection_id}_{suffix}");
+                    suffix += 1;
+                }
+            }
+        }
+    }
+    fn is_same_contents(&self, node_state_collection: &NodeStateCollection<TNodeState>) -> bool {
+        !node_state_collection.is_forbidden && node_state_collection.node_state_id == self.node_state_id && node_state_collection.node_state_ids == self.node_state_ids
+    }
+    /// This function registers the anonymous node state collection under the id that it is known by, or under the next unique id if that id is taken by different contents, returning the id that it is registered under.
+    fn register(self, node_state_collection_id: &str, node_state_collections: &mut Vec<NodeStateCollection<TNodeState>>, node_state_collection_index_per_id: &mut HashMap<String, usize>) -> String {
+        let is_same_contents_per_id = |node_state_collection_id: &str| {
+            node_state_collection_index_per_id.get(node_state_collection_id).map(|node_state_collection_index| self.is_same_contents(&node_state_collections[*node_state_collection_index]))
+        };
+        let (node_state_collection_id, is_registered) = match is_same_contents_per_id(node_state_collection_id) {
+            None => (String::from(node_state_collection_id), false),
+            Some(true) => (String::from(node_state_collection_id), true),
+            Some(false) => self.get_unique_id(is_same_contents_per_id)
+        };
+        if !is_registered {
+            node_state_collection_index_per_id.insert(node_state_collection_id.clone(), node_state_collections.len());
+            node_state_collections.push(NodeStateCollection::new(node_state_collection_id.clone(), self.node_state_id, self.node_state_ids));
+        }
+        node_state_collection_id
+    }
+}
+
+/// This struct summarizes the shape of a wave function so that it can be inspected prior to collapsing it.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WaveFunctionStatistics {
+    pub nodes_total: usize,
+    pub distinct_node_states_total: usize,
+    pub minimum_node_states_per_node: usize,
+    pub maximum_node_states_per_node: usize,
+    pub mean_node_states_per_node: f64,
+    // each node to neighbor node relationship is counted once per direction
+    pub neighbor_edges_total: usize,
+    pub mean_node_state_collections_per_neighbor_edge: f64,
+    // a node without any node states can never be collapsed
+    pub is_any_node_without_node_states: bool
+}
+
+/// This struct is the result of recollapsing the region around changed nodes, containing only the nodes whose node state differs from the previous collapsed wave function.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecollapsedRegion<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    pub node_state_per_node_id: HashMap<String, TNodeState>,
+    // the number of steps from the changed nodes that were recollapsed, which is larger than requested if the region had to grow
+    pub radius: usize,
+    pub is_region_grown: bool
+}
+
+/// This struct is a node state collection applied from a node to its neighbor that takes part in a conflict, along with the node states of the neighbor that it eliminates whenever the node is in the node state of the node state collection.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConflictingConstraint<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    pub node_id: String,
+    pub neighbor_node_id: String,
+    pub node_state_collection_id: String,
+    pub node_state_id: TNodeState,
+    pub eliminated_node_state_ids: Vec<TNodeState>
+}
+
+/// This struct explains why a wave function cannot be collapsed with constraints that, once all removed, leave the wave function collapsable.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConflictReport<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    // the nodes that the conflicting constraints are applied from or to, in node id order
+    pub node_ids: Vec<String>,
+    // the distinct node state collections of the conflicting constraints, in the order that they are first found
+    pub node_state_collection_ids: Vec<String>,
+    // in the order of the nodes, then their neighbor node ids, and then the node state collection ids listed for each neighbor
+    pub conflicting_constraints: Vec<ConflictingConstraint<TNodeState>>
+}
+
+/// This struct applies the same node state collections from the first node to the second node and from the second node to the first node, sparing the need to list them under both nodes.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct SymmetricConstraint {
+    pub first_node_id: String,
+    pub second_node_id: String,
+    pub node_state_collection_ids: Vec<String>
+}
+
+impl SymmetricConstraint {
+    pub fn new(first_node_id: String, second_node_id: String, node_state_collection_ids: Vec<String>) -> Self {
+        SymmetricConstraint {
+            first_node_id,
+            second_node_id,
+            node_state_collection_ids
+        }
+    }
+}
+
+/// This struct requires that every node in the group is in a different node state than every other node in the group, such as the cells of a row in a sudoku puzzle.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AllDifferentConstraint {
+    pub node_ids: Vec<String>
+}
+
+impl AllDifferentConstraint {
+    pub fn new(node_ids: Vec<String>) -> Self {
+        AllDifferentConstraint {
+            node_ids
+        }
+    }
+}
+
+/// This enum restricts how many nodes of a group are in a node state, regardless of whether the nodes are neighbors of each other.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum GlobalConstraint<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    AtLeastOne { node_ids: Vec<String>, state: TNodeState },
+    AtMostN { node_ids: Vec<String>, state: TNodeState, n: usize },
+    ExactlyN { node_ids: Vec<String>, state: TNodeState, n: usize }
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> GlobalConstraint<TNodeState> {
+    pub fn get_node_ids(&self) -> &Vec<String> {
+        match self {
+            GlobalConstraint::AtLeastOne { node_ids, .. } | GlobalConstraint::AtMostN { node_ids, .. } | GlobalConstraint::ExactlyN { node_ids, .. } => node_ids
+        }
+    }
+    pub fn get_state(&self) -> &TNodeState {
+        match self {
+            GlobalConstraint::AtLeastOne { state, .. } | GlobalConstraint::AtMostN { state, .. } | GlobalConstraint::ExactlyN { state, .. } => state
+        }
+    }
+    /// This function returns the inclusive range of how many of the nodes may be in the node state.
+    pub fn get_minimum_and_maximum(&self) -> (usize, usize) {
+        match self {
+            GlobalConstraint::AtLeastOne { node_ids, .. } => (1, node_ids.len()),
+            GlobalConstraint::AtMostN { n, .. } => (0, *n),
+            GlobalConstraint::ExactlyN { n, .. } => (*n, *n)
+        }
+    }
+}
+
+/// This enum restricts how many of the neighbors of a node are in any of the node states, such as requiring at least one neighbor to be solid or permitting no more than two neighbors to be bombs. The sequential and entropic collapsable wave functions enforce it while collapsing, the accommodating collapsable wave functions only verify it once they are done, and the parallel collapsable wave function enforces it within each partition since a node is always in the same partition as its neighbors.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub enum AggregateConstraint<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    AtLeast(usize, Vec<TNodeState>),
+    AtMost(usize, Vec<TNodeState>),
+    Exactly(usize, Vec<TNodeState>)
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> AggregateConstraint<TNodeState> {
+    pub fn get_states(&self) -> &Vec<TNodeState> {
+        match self {
+            AggregateConstraint::AtLeast(_, states) | AggregateConstraint::AtMost(_, states) | AggregateConstraint::Exactly(_, states) => states
+        }
+    }
+    /// This function returns the inclusive range of how many of the neighbors may be in any of the node states.
+    pub fn get_minimum_and_maximum(&self, neighbors_total: usize) -> (usize, usize) {
+        match self {
+            AggregateConstraint::AtLeast(count, _) => (*count, neighbors_total),
+            AggregateConstraint::AtMost(count, _) => (0, *count),
+            AggregateConstraint::Exactly(count, _) => (*count, *count)
+        }
+    }
+}
+
+// the serialized form of a wave function, which may also contain symmetric and all different constraints that are expanded when deserialized
+#[derive(Deserialize)]
+struct WaveFunctionDefinition<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    nodes: Vec<Node<TNodeState>>,
+    node_state_collections: Vec<NodeStateCollection<TNodeState>>,
+    #[serde(default)]
+    symmetric_constraints: Vec<SymmetricConstraint>,
+    #[serde(default)]
+    all_different_constraints: Vec<AllDifferentConstraint>,
+    #[serde(default = "Vec::new")]
+    global_constraints: Vec<GlobalConstraint<TNodeState>>
+}
+
+// the first line of the newline-delimited form of a wave function, announcing how many node lines and then node state collection lines follow it
+#[derive(Serialize, Deserialize)]
+struct WaveFunctionStreamHeader<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    nodes_total: usize,
+    node_state_collections_total: usize,
+    #[serde(default = "Vec::new", skip_serializing_if = "Vec::is_empty")]
+    global_constraints: Vec<GlobalConstraint<TNodeState>>
+}
+
+// the version of the envelope written by save_to_writer, so that files of any other version are rejected instead of misparsed
+const WAVE_FUNCTION_FORMAT_VERSION: u64 = 1;
+
+#[derive(Serialize)]
+struct WaveFunctionEnvelope<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    format_version: u64,
+    wave_function: &'a WaveFunction<TNodeState>
 }
 
 /// This struct represents the uncollapsed definition of nodes and their relationships to other nodes.
 #[derive(Serialize, Clone, Deserialize)]
-pub struct WaveFunction<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> {
+#[serde(try_from = "WaveFunctionDefinition<TNodeState>")]
+pub struct WaveFunction<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
     nodes: Vec<Node<TNodeState>>,
-    node_state_collections: Vec<NodeStateCollection<TNodeState>>
+    node_state_collections: Vec<NodeStateCollection<TNodeState>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    global_constraints: Vec<GlobalConstraint<TNodeState>>
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> TryFrom<WaveFunctionDefinition<TNodeState>> for WaveFunction<TNodeState> {
+    type Error = String;
+
+    fn try_from(wave_function_definition: WaveFunctionDefinition<TNodeState>) -> Result<Self, Self::Error> {
+        let mut nodes = wave_function_definition.nodes;
+        let mut node_state_collections = wave_function_definition.node_state_collections;
+
+        for node in nodes.iter() {
+            node.validate_node_state_ratios()?;
+        }
+        NodeStateCollection::register_anonymous(&mut node_state_collections, &mut nodes);
+        NodeStateCollection::resolve_forbidden(&mut node_state_collections, &nodes);
+
+        let mut node_index_per_id: HashMap<String, usize> = HashMap::new();
+        for (node_index, node) in nodes.iter().enumerate() {
+            node_index_per_id.insert(node.id.clone(), node_index);
+        }
+
+        // all different constraints over the same node states share one node state collection per node state instead of each pair of nodes receiving their own
+        let mut node_state_collection_ids: HashSet<String> = node_state_collections.iter().map(|node_state_collection| node_state_collection.id.clone()).collect();
+        let mut all_different_node_state_collection_ids_per_node_state_ids: HashMap<Vec<TNodeState>, Vec<String>> = HashMap::new();
+        for all_different_constraint in wave_function_definition.all_different_constraints.iter() {
+            let mut node_indexes: Vec<usize> = Vec::new();
+            let mut node_state_ids: Vec<TNodeState> = Vec::new();
+            for node_id in all_different_constraint.node_ids.iter() {
+                let Some(node_index) = node_index_per_id.get(node_id).cloned() else {
+                    return Err(format!("All different constraint references node {node_id} that does not exist."));
+                };
+                node_indexes.push(node_index);
+                for node_state_id in nodes[node_index].node_state_ids.iter() {
+                    if !node_state_ids.contains(node_state_id) {
+                        node_state_ids.push(node_state_id.clone());
+                    }
+                }
+            }
+            node_state_ids.sort_by_key(get_node_state_order_key);
+
+            if !all_different_node_state_collection_ids_per_node_state_ids.contains_key(&node_state_ids) {
+                let node_state_ids_index = all_different_node_state_collection_ids_per_node_state_ids.len();
+                let mut all_different_node_state_collection_ids: Vec<String> = Vec::new();
+                for (node_state_index, node_state_id) in node_state_ids.iter().enumerate() {
+                    let node_state_collection_id = format!("all_different_{node_state_ids_index}_{node_state_index}");
+                    if !node_state_collection_ids.insert(node_state_collection_id.clone()) {
+                        return Err(format!("Node state collection {node_state_collection_id} is reserved for all different constraints."));
+                    }
+                    let other_node_state_ids: Vec<TNodeState> = node_state_ids.iter().filter(|other_node_state_id| *other_node_state_id != node_state_id).cloned().collect();
+                    node_state_collections.push(NodeStateCollection::new(node_state_collection_id.clone(), node_state_id.clone(), other_node_state_ids));
+                    all_different_node_state_collection_ids.push(node_state_collection_id);
+                }
+                all_different_node_state_collection_ids_per_node_state_ids.insert(node_state_ids.clone(), all_different_node_state_collection_ids);
+            }
+            let all_different_node_state_collection_ids = all_different_node_state_collection_ids_per_node_state_ids.get(&node_state_ids).unwrap();
+
+            for node_index in node_indexes.iter() {
+                for neighbor_node_index in node_indexes.iter() {
+                    if node_index == neighbor_node_index {
+                        continue;
+                    }
+                    let neighbor_node_id = nodes[*neighbor_node_index].id.clone();
+                    let node_state_collection_ids = nodes[*node_index].node_state_collection_ids_per_neighbor_node_id
+                        .entry(neighbor_node_id)
+                        .or_default();
+                    if node_state_collection_ids.is_empty() {
+                        // there are no duplicates to search for when the neighbor is only related by this constraint
+                        node_state_collection_ids.extend(all_different_node_state_collection_ids.iter().cloned());
+                    }
+                    else {
+                        for node_state_collection_id in all_different_node_state_collection_ids.iter() {
+                            if !node_state_collection_ids.contains(node_state_collection_id) {
+                                node_state_collection_ids.push(node_state_collection_id.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        for symmetric_constraint in wave_function_definition.symmetric_constraints.iter() {
+            let Some(first_node_index) = node_index_per_id.get(&symmetric_constraint.first_node_id).cloned() else {
+                return Err(format!("Symmetric constraint references node {} that does not exist.", symmetric_constraint.first_node_id));
+            };
+            let Some(second_node_index) = node_index_per_id.get(&symmetric_constraint.second_node_id).cloned() else {
+                return Err(format!("Symmetric constraint references node {} that does not exist.", symmetric_constraint.second_node_id));
+            };
+            for (from_node_index, to_node_id) in [(first_node_index, &symmetric_constraint.second_node_id), (second_node_index, &symmetric_constraint.first_node_id)] {
+                let node_state_collection_ids = nodes[from_node_index].node_state_collection_ids_per_neighbor_node_id
+                    .entry(to_node_id.clone())
+                    .or_default();
+                for node_state_collection_id in symmetric_constraint.node_state_collection_ids.iter() {
+                    if !node_state_collection_ids.contains(node_state_collection_id) {
+                        node_state_collection_ids.push(node_state_collection_id.clone());
+                    }
+                }
+            }
+        }
+
+        for global_constraint in wave_function_definition.global_constraints.iter() {
+            for node_id in global_constraint.get_node_ids().iter() {
+                if !node_index_per_id.contains_key(node_id) {
+                    return Err(format!("Global constraint references node {node_id} that does not exist."));
+                }
+            }
+        }
+
+        Ok(WaveFunction {
+            nodes,
+            node_state_collections,
+            global_constraints: wave_function_definition.global_constraints
+        })
+    }
 }
 
-impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord + Serialize + DeserializeOwned> WaveFunction<TNodeState> {
-    pub fn new(nodes: Vec<Node<TNodeState>>, node_state_collections: Vec<NodeStateCollection<TNodeState>>) -> Self {
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Serialize + DeserializeOwned> WaveFunction<TNodeState> {
+    pub fn new(mut nodes: Vec<Node<TNodeState>>, mut node_state_collections: Vec<NodeStateCollection<TNodeState>>) -> Self {
+        NodeStateCollection::register_anonymous(&mut node_state_collections, &mut nodes);
+        NodeStateCollection::resolve_forbidden(&mut node_state_collections, &nodes);
         WaveFunction {
             nodes,
-            node_state_collections
+            node_state_collections,
+            global_constraints: Vec::new()
+        }
+    }
+
+    /// This function expands each symmetric constraint into the node state collection ids of both of its nodes, keeping any directional node state collection ids already present on the nodes.
+    pub fn new_with_symmetric_constraints(nodes: Vec<Node<TNodeState>>, node_state_collections: Vec<NodeStateCollection<TNodeState>>, symmetric_constraints: Vec<SymmetricConstraint>) -> Result<Self, String> {
+        WaveFunction::try_from(WaveFunctionDefinition {
+            nodes,
+            node_state_collections,
+            symmetric_constraints,
+            all_different_constraints: Vec::new(),
+            global_constraints: Vec::new()
+        })
+    }
+
+    /// This function expands each all different constraint into node state collections shared by every pair of nodes within the constraint, keeping any node state collection ids already present on the nodes. The generated node state collection ids begin with "all_different_".
+    pub fn new_with_all_different_constraints(nodes: Vec<Node<TNodeState>>, node_state_collections: Vec<NodeStateCollection<TNodeState>>, all_different_constraints: Vec<AllDifferentConstraint>) -> Result<Self, String> {
+        WaveFunction::try_from(WaveFunctionDefinition {
+            nodes,
+            node_state_collections,
+            symmetric_constraints: Vec::new(),
+            all_different_constraints,
+            global_constraints: Vec::new()
+        })
+    }
+
+    /// This function keeps each global constraint alongside the nodes so that every collapsable wave function respects them. The sequential collapsable wave function backtracks and the entropic collapsable wave function narrows the node states of the remaining nodes whenever a global constraint is threatened, while the accommodating collapsable wave functions only fail once they find that a global constraint is violated.
+    pub fn new_with_global_constraints(nodes: Vec<Node<TNodeState>>, node_state_collections: Vec<NodeStateCollection<TNodeState>>, global_constraints: Vec<GlobalConstraint<TNodeState>>) -> Result<Self, String> {
+        WaveFunction::try_from(WaveFunctionDefinition {
+            nodes,
+            node_state_collections,
+            symmetric_constraints: Vec::new(),
+            all_different_constraints: Vec::new(),
+            global_constraints
+        })
+    }
+
+    /// This function removes from each node with a domain the node states of any other domain, along with the node state collection ids for those node states, so that constraints are not needed to keep nodes out of node states that they could never be in. Node states without a domain remain available to every node and nodes without a domain keep all of their node states.
+    pub fn new_with_domains(mut nodes: Vec<Node<TNodeState>>, node_state_collections: Vec<NodeStateCollection<TNodeState>>, state_domains: HashMap<TNodeState, String>) -> Result<Self, String> {
+        let node_state_collection_per_id: HashMap<&str, &NodeStateCollection<TNodeState>> = node_state_collections.iter()
+            .map(|node_state_collection| (node_state_collection.id.as_str(), node_state_collection))
+            .collect();
+        for node in nodes.iter_mut() {
+            let Some(domain) = node.domain.clone() else {
+                continue;
+            };
+            let is_in_domain = |node_state_id: &TNodeState| -> bool {
+                match state_domains.get(node_state_id) {
+                    Some(state_domain) => *state_domain == domain,
+                    None => true
+                }
+            };
+
+            let (node_state_ids, node_state_ratios): (Vec<TNodeState>, Vec<f64>) = node.node_state_ids.iter()
+                .zip(node.node_state_ratios.iter())
+                .filter(|(node_state_id, _)| is_in_domain(node_state_id))
+                .map(|(node_state_id, node_state_ratio)| (node_state_id.clone(), *node_state_ratio))
+                .unzip();
+            if node_state_ids.is_empty() && !node.node_state_ids.is_empty() {
+                return Err(format!("Node {} is in domain {domain} but none of its node states are in that domain.", node.id));
+            }
+            node.node_state_ids = node_state_ids;
+            node.node_state_ratios = node_state_ratios;
+
+            // unknown node state collections are left for validate to report
+            for node_state_collection_ids in node.node_state_collection_ids_per_neighbor_node_id.values_mut() {
+                node_state_collection_ids.retain(|node_state_collection_id| {
+                    match node_state_collection_per_id.get(node_state_collection_id.as_str()) {
+                        Some(node_state_collection) => is_in_domain(&node_state_collection.node_state_id),
+                        None => true
+                    }
+                });
+            }
+        }
+        Ok(WaveFunction::new(nodes, node_state_collections))
+    }
+
+    /// This function expands each reference to a functional node state collection into one NodeStateCollection per node state of the original node, permitting the neighbor node states that satisfy every functional node state collection and every explicit NodeStateCollection of that node state listed for the same neighbor. Identical expansions are shared between nodes. The generated node state collection ids begin with "functional_".
+    pub fn new_with_functional_node_state_collections(mut nodes: Vec<Node<TNodeState>>, mut node_state_collections: Vec<NodeStateCollection<TNodeState>>, functional_node_state_collections: Vec<FunctionalNodeStateCollection<TNodeState>>) -> Result<Self, String> {
+        let mut node_state_collection_index_per_id: HashMap<String, usize> = HashMap::new();
+        for (node_state_collection_index, node_state_collection) in node_state_collections.iter().enumerate() {
+            node_state_collection_index_per_id.insert(node_state_collection.id.clone(), node_state_collection_index);
+        }
+        let mut functional_node_state_collection_per_id: HashMap<&str, &FunctionalNodeStateCollection<TNodeState>> = HashMap::new();
+        for functional_node_state_collection in functional_node_state_collections.iter() {
+            if node_state_collection_index_per_id.contains_key(&functional_node_state_collection.id) || functional_node_state_collection_per_id.insert(&functional_node_state_collection.id, functional_node_state_collection).is_some() {
+                return Err(format!("Node state collection {} is defined more than once.", functional_node_state_collection.id));
+            }
+        }
+
+        let mut node_state_ids_per_node_id: HashMap<String, Vec<TNodeState>> = HashMap::new();
+        for node in nodes.iter() {
+            node_state_ids_per_node_id.insert(node.id.clone(), node.node_state_ids.clone());
+        }
+
+        // each expansion is memoized by the node state collection ids that it combines, the original node state, and the index of the distinct neighbor node states
+        let mut generated_node_state_collection_id_per_expansion: HashMap<(Vec<String>, TNodeState, usize), String> = HashMap::new();
+        let mut neighbor_node_states_index_per_node_state_ids: HashMap<Vec<TNodeState>, usize> = HashMap::new();
+        for node in nodes.iter_mut() {
+            let mut neighbor_node_ids: Vec<String> = node.node_state_collection_ids_per_neighbor_node_id.keys().cloned().collect();
+            neighbor_node_ids.sort();
+            for neighbor_node_id in neighbor_node_ids {
+                let node_state_collection_ids = node.node_state_collection_ids_per_neighbor_node_id.get(&neighbor_node_id).unwrap();
+                let mut functional_node_state_collection_ids: Vec<&String> = node_state_collection_ids.iter().filter(|node_state_collection_id| functional_node_state_collection_per_id.contains_key(node_state_collection_id.as_str())).collect();
+                if functional_node_state_collection_ids.is_empty() {
+                    continue;
+                }
+                functional_node_state_collection_ids.sort();
+                functional_node_state_collection_ids.dedup();
+                let Some(neighbor_node_state_ids) = node_state_ids_per_node_id.get(&neighbor_node_id) else {
+                    return Err(format!("Neighbor node {neighbor_node_id} does not exist in main list of nodes."));
+                };
+                let neighbor_node_states_indexes_length = neighbor_node_states_index_per_node_state_ids.len();
+                let neighbor_node_states_index = *neighbor_node_states_index_per_node_state_ids.entry(neighbor_node_state_ids.clone()).or_insert(neighbor_node_states_indexes_length);
+
+                // explicit node state collections for the node states of this node are folded into the expansion while any others are kept as they are
+                let mut expanded_node_state_collection_ids: Vec<String> = Vec::new();
+                let mut explicit_node_state_collection_indexes: Vec<usize> = Vec::new();
+                for node_state_collection_id in node_state_collection_ids.iter() {
+                    if functional_node_state_collection_per_id.contains_key(node_state_collection_id.as_str()) {
+                        continue;
+                    }
+                    match node_state_collection_index_per_id.get(node_state_collection_id) {
+                        Some(node_state_collection_index) if node.node_state_ids.contains(&node_state_collections[*node_state_collection_index].node_state_id) => explicit_node_state_collection_indexes.push(*node_state_collection_index),
+                        _ => expanded_node_state_collection_ids.push(node_state_collection_id.clone())
+                    }
+                }
+
+                for node_state_id in node.node_state_ids.iter() {
+                    let mut combined_node_state_collection_ids: Vec<String> = functional_node_state_collection_ids.iter().map(|functional_node_state_collection_id| (*functional_node_state_collection_id).clone()).collect();
+                    let mut node_state_explicit_node_state_collection_indexes: Vec<usize> = Vec::new();
+                    for node_state_collection_index in explicit_node_state_collection_indexes.iter() {
+                        if &node_state_collections[*node_state_collection_index].node_state_id == node_state_id {
+                            node_state_explicit_node_state_collection_indexes.push(*node_state_collection_index);
+                            combined_node_state_collection_ids.push(node_state_collections[*node_state_collection_index].id.clone());
+                        }
+                    }
+
+                    let expansion = (combined_node_state_collection_ids, node_state_id.clone(), neighbor_node_states_index);
+                    if let Some(generated_node_state_collection_id) = generated_node_state_collection_id_per_expansion.get(&expansion) {
+                        expanded_node_state_collection_ids.push(generated_node_state_collection_id.clone());
+                        continue;
+                    }
+
+                    let permitted_node_state_ids: Vec<TNodeState> = neighbor_node_state_ids
+                        .iter()
+                        .filter(|neighbor_node_state_id| {
+                            functional_node_state_collection_ids.iter().all(|functional_node_state_collection_id| functional_node_state_collection_per_id.get(functional_node_state_collection_id.as_str()).unwrap().is_permitted(node_state_id, neighbor_node_state_id)) &&
+                                node_state_explicit_node_state_collection_indexes.iter().all(|node_state_collection_index| node_state_collections[*node_state_collection_index].node_state_ids.contains(neighbor_node_state_id))
+                        })
+                        .cloned()
+                        .collect();
+                    let generated_node_state_collection_id = format!("functional_{}", generated_node_state_collection_id_per_expansion.len());
+                    if node_state_collection_index_per_id.contains_key(&generated_node_state_collection_id) {
+                        return Err(format!("Node state collection {generated_node_state_collection_id} is reserved for functional node state collections."));
+                    }
+                    node_state_collection_index_per_id.insert(generated_node_state_collection_id.clone(), node_state_collections.len());
+                    node_state_collections.push(NodeStateCollection::new(generated_node_state_collection_id.clone(), node_state_id.clone(), permitted_node_state_ids));
+                    generated_node_state_collection_id_per_expansion.insert(expansion, generated_node_state_collection_id.clone());
+                    expanded_node_state_collection_ids.push(generated_node_state_collection_id);
+                }
+
+                node.node_state_collection_ids_per_neighbor_node_id.insert(neighbor_node_id, expanded_node_state_collection_ids);
+            }
+        }
+
+        Ok(WaveFunction::new(nodes, node_state_collections))
+    }
+
+    /// This function generates the id of each node and node state collection from its index, returning the wave function along with the generated node id per node index so that collapsed node states can be correlated back to the anonymous nodes.
+    pub fn new_anonymous(anonymous_nodes: Vec<AnonymousNode<TNodeState>>, anonymous_node_state_collections: Vec<AnonymousNodeStateCollection<TNodeState>>) -> Result<(Self, Vec<String>), String> {
+        let anonymous_nodes_length = anonymous_nodes.len();
+        let anonymous_node_state_collections_length = anonymous_node_state_collections.len();
+        let node_ids: Vec<String> = (0..anonymous_nodes_length)
+            .map(|node_index| format!("node_{node_index}"))
+            .collect();
+        let node_state_collection_ids: Vec<String> = (0..anonymous_node_state_collections_length)
+            .map(|node_state_collection_index| format!("node_state_collection_{node_state_collection_index}"))
+            .collect();
+
+        let mut nodes: Vec<Node<TNodeState>> = Vec::new();
+        for (node_index, anonymous_node) in anonymous_nodes.into_iter().enumerate() {
+            let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+            for (neighbor_node_index, node_state_collection_indexes) in anonymous_node.node_state_collection_indexes_per_neighbor_node_index.iter() {
+                let Some(neighbor_node_id) = node_ids.get(*neighbor_node_index) else {
+                    return Err(format!("Anonymous node {node_index} references neighbor node index {neighbor_node_index} but only {anonymous_nodes_length} nodes exist."));
+                };
+                let mut neighbor_node_state_collection_ids: Vec<String> = Vec::new();
+                for node_state_collection_index in node_state_collection_indexes.iter() {
+                    let Some(node_state_collection_id) = node_state_collection_ids.get(*node_state_collection_index) else {
+                        return Err(format!("Anonymous node {node_index} references node state collection index {node_state_collection_index} but only {anonymous_node_state_collections_length} node state collections exist."));
+                    };
+                    neighbor_node_state_collection_ids.push(node_state_collection_id.clone());
+                }
+                node_state_collection_ids_per_neighbor_node_id.insert(neighbor_node_id.clone(), neighbor_node_state_collection_ids);
+            }
+            nodes.push(Node::new(node_ids[node_index].clone(), anonymous_node.node_state_ratio_per_node_state_id, node_state_collection_ids_per_neighbor_node_id));
+        }
+
+        let node_state_collections: Vec<NodeStateCollection<TNodeState>> = anonymous_node_state_collections
+            .into_iter()
+            .zip(node_state_collection_ids)
+            .map(|(anonymous_node_state_collection, node_state_collection_id)| NodeStateCollection::new(node_state_collection_id, anonymous_node_state_collection.node_state_id, anonymous_node_state_collection.node_state_ids))
+            .collect();
+
+        Ok((WaveFunction::new(nodes, node_state_collections), node_ids))
+    }
+
+    /// This function constructs a chain of nodes with the ids "chain_0" through "chain_{length - 1}", where every node shares the same node state ratios and each node applies the forward node state collections to the next node in the chain. When bidirectional, the reversal of the forward node state collections is also applied from each node back to the previous node and provided to the wave function along with the forward node state collections. The constructed wave function is validated before being returned.
+    pub fn new_chain(length: usize, node_state_ratio_per_node_state_id: HashMap<TNodeState, f64>, mut forward_node_state_collections: Vec<NodeStateCollection<TNodeState>>, is_bidirectional: bool) -> Result<Self, String> {
+        if length == 0 {
+            return Err(String::from("A chain must have at least one node."));
+        }
+
+        let mut nodes: Vec<Node<TNodeState>> = (0..length)
+            .map(|node_index| Node::new(format!("chain_{node_index}"), node_state_ratio_per_node_state_id.clone(), HashMap::new()))
+            .collect();
+
+        // forbidden node state collections must permit their node states before they can be reversed
+        NodeStateCollection::resolve_forbidden(&mut forward_node_state_collections, &nodes);
+        let reversed_node_state_collections = if is_bidirectional {
+            NodeStateCollection::reversed_from(&forward_node_state_collections)
+        }
+        else {
+            Vec::new()
+        };
+        for node_index in 1..length {
+            let (previous_nodes, next_nodes) = nodes.split_at_mut(node_index);
+            let previous_node = &mut previous_nodes[node_index - 1];
+            let next_node = &mut next_nodes[0];
+            previous_node.add_neighbor_node_state_collections(&next_node.id, &forward_node_state_collections);
+            if is_bidirectional {
+                next_node.add_neighbor_node_state_collections(&previous_node.id, &reversed_node_state_collections);
+            }
+        }
+
+        let mut node_state_collections = forward_node_state_collections;
+        node_state_collections.extend(reversed_node_state_collections);
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate()?;
+        Ok(wave_function)
+    }
+
+    /// This function converts every node state of the wave function, such as replacing heavyweight node state structs with a compact enum before collapsing. Distinct node states should be converted into distinct node states. The node states of each node keep their order, so collapsing the converted wave function with the same random seed results in the converted collapsed wave function when every node state is converted into a distinct node state.
+    pub fn map_states<TMappedNodeState: Eq + Hash + Clone + std::fmt::Debug>(&self, map_node_state: impl Fn(&TNodeState) -> TMappedNodeState) -> WaveFunction<TMappedNodeState> {
+        let map_node_states = |node_state_ids: &Vec<TNodeState>| -> Vec<TMappedNodeState> {
+            node_state_ids.iter().map(&map_node_state).collect()
+        };
+        let nodes = self.nodes
+            .iter()
+            .map(|node| Node {
+                id: node.id.clone(),
+                node_state_collection_ids_per_neighbor_node_id: node.node_state_collection_ids_per_neighbor_node_id.clone(),
+                node_state_ids: map_node_states(&node.node_state_ids),
+                node_state_ratios: node.node_state_ratios.clone(),
+                metadata: node.metadata.clone(),
+                domain: node.domain.clone(),
+                // the ids are kept since the nodes already refer to them
+                anonymous_node_state_collections: node.anonymous_node_state_collections
+                    .iter()
+                    .map(|(node_state_collection_id, anonymous_node_state_collection)| (node_state_collection_id.clone(), AnonymousNodeStateCollection::new(map_node_state(&anonymous_node_state_collection.node_state_id), map_node_states(&anonymous_node_state_collection.node_state_ids))))
+                    .collect(),
+                aggregate_constraints: node.aggregate_constraints
+                    .iter()
+                    .map(|aggregate_constraint| match aggregate_constraint {
+                        AggregateConstraint::AtLeast(count, states) => AggregateConstraint::AtLeast(*count, map_node_states(states)),
+                        AggregateConstraint::AtMost(count, states) => AggregateConstraint::AtMost(*count, map_node_states(states)),
+                        AggregateConstraint::Exactly(count, states) => AggregateConstraint::Exactly(*count, map_node_states(states))
+                    })
+                    .collect()
+            })
+            .collect();
+        let node_state_collections = self.node_state_collections
+            .iter()
+            .map(|node_state_collection| NodeStateCollection {
+                id: node_state_collection.id.clone(),
+                node_state_id: map_node_state(&node_state_collection.node_state_id),
+                node_state_ids: map_node_states(&node_state_collection.node_state_ids),
+                is_forbidden: node_state_collection.is_forbidden
+            })
+            .collect();
+        let global_constraints = self.global_constraints
+            .iter()
+            .map(|global_constraint| match global_constraint {
+                GlobalConstraint::AtLeastOne { node_ids, state } => GlobalConstraint::AtLeastOne { node_ids: node_ids.clone(), state: map_node_state(state) },
+                GlobalConstraint::AtMostN { node_ids, state, n } => GlobalConstraint::AtMostN { node_ids: node_ids.clone(), state: map_node_state(state), n: *n },
+                GlobalConstraint::ExactlyN { node_ids, state, n } => GlobalConstraint::ExactlyN { node_ids: node_ids.clone(), state: map_node_state(state), n: *n }
+            })
+            .collect();
+        WaveFunction {
+            nodes,
+            node_state_collections,
+            global_constraints
         }
     }
 
@@ -100,20 +1111,310 @@ impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord + Serialize + Deseria
         self.nodes.clone()
     }
 
-    pub fn get_node_state_collections(&self) -> Vec<NodeStateCollection<TNodeState>> {
-        self.node_state_collections.clone()
+    pub fn get_node_state_collections(&self) -> Vec<NodeStateCollection<TNodeState>> {
+        self.node_state_collections.clone()
+    }
+
+    pub fn get_global_constraints(&self) -> Vec<GlobalConstraint<TNodeState>> {
+        self.global_constraints.clone()
+    }
+
+    pub fn get_statistics(&self) -> WaveFunctionStatistics {
+        let mut distinct_node_state_ids: HashSet<&TNodeState> = HashSet::new();
+        let mut node_states_total: usize = 0;
+        let mut neighbor_edges_total: usize = 0;
+        let mut node_state_collections_total: usize = 0;
+        for node in self.nodes.iter() {
+            distinct_node_state_ids.extend(node.node_state_ids.iter());
+            node_states_total += node.node_state_ids.len();
+            neighbor_edges_total += node.node_state_collection_ids_per_neighbor_node_id.len();
+            node_state_collections_total += node.node_state_collection_ids_per_neighbor_node_id
+                .values()
+                .map(|node_state_collection_ids| node_state_collection_ids.len())
+                .sum::<usize>();
+        }
+
+        let nodes_total: usize = self.nodes.len();
+        WaveFunctionStatistics {
+            nodes_total,
+            distinct_node_states_total: distinct_node_state_ids.len(),
+            minimum_node_states_per_node: self.nodes.iter().map(|node| node.node_state_ids.len()).min().unwrap_or(0),
+            maximum_node_states_per_node: self.nodes.iter().map(|node| node.node_state_ids.len()).max().unwrap_or(0),
+            mean_node_states_per_node: if nodes_total == 0 { 0.0 } else { node_states_total as f64 / nodes_total as f64 },
+            neighbor_edges_total,
+            mean_node_state_collections_per_neighbor_edge: if neighbor_edges_total == 0 { 0.0 } else { node_state_collections_total as f64 / neighbor_edges_total as f64 },
+            is_any_node_without_node_states: self.nodes.iter().any(|node| node.node_state_ids.is_empty())
+        }
+    }
+
+    /// This function combines two independently constructed wave functions, connecting them with bridges of (node id, neighbor node id, node state collection) that are applied from the node to the neighbor node and from the neighbor node back to the node.
+    pub fn merge(self, other: WaveFunction<TNodeState>, bridges: Vec<(String, String, NodeStateCollection<TNodeState>)>) -> Result<WaveFunction<TNodeState>, String> {
+        let mut nodes = self.nodes;
+        let mut node_state_collections = self.node_state_collections;
+        let mut global_constraints = self.global_constraints;
+        global_constraints.extend(other.global_constraints);
+
+        let mut node_index_per_id: HashMap<String, usize> = HashMap::new();
+        for (node_index, node) in nodes.iter().enumerate() {
+            node_index_per_id.insert(node.id.clone(), node_index);
+        }
+        for node in other.nodes.into_iter() {
+            if node_index_per_id.contains_key(&node.id) {
+                return Err(format!("Node {} exists in both wave functions.", node.id));
+            }
+            node_index_per_id.insert(node.id.clone(), nodes.len());
+            nodes.push(node);
+        }
+
+        let mut node_state_collection_index_per_id: HashMap<String, usize> = HashMap::new();
+        for (node_state_collection_index, node_state_collection) in node_state_collections.iter().enumerate() {
+            node_state_collection_index_per_id.insert(node_state_collection.id.clone(), node_state_collection_index);
+        }
+        let mut bridge_node_state_collections: Vec<NodeStateCollection<TNodeState>> = Vec::new();
+        for (_, _, node_state_collection) in bridges.iter() {
+            bridge_node_state_collections.push(node_state_collection.clone());
+        }
+        NodeStateCollection::resolve_forbidden(&mut bridge_node_state_collections, &nodes);
+
+        // node state collections that share an id are only kept once and must therefore be identical
+        for node_state_collection in other.node_state_collections.into_iter().chain(bridge_node_state_collections) {
+            if let Some(existing_node_state_collection_index) = node_state_collection_index_per_id.get(&node_state_collection.id) {
+                let existing_node_state_collection = &node_state_collections[*existing_node_state_collection_index];
+                if existing_node_state_collection.node_state_id != node_state_collection.node_state_id || existing_node_state_collection.node_state_ids != node_state_collection.node_state_ids {
+                    return Err(format!("Node state collection {} is defined differently in more than one place.", node_state_collection.id));
+                }
+            }
+            else {
+                node_state_collection_index_per_id.insert(node_state_collection.id.clone(), node_state_collections.len());
+                node_state_collections.push(node_state_collection);
+            }
+        }
+
+        for (node_id, neighbor_node_id, node_state_collection) in bridges.iter() {
+            let Some(node_index) = node_index_per_id.get(node_id).cloned() else {
+                return Err(format!("Bridge node {node_id} does not exist in either wave function."));
+            };
+            let Some(neighbor_node_index) = node_index_per_id.get(neighbor_node_id).cloned() else {
+                return Err(format!("Bridge neighbor node {neighbor_node_id} does not exist in either wave function."));
+            };
+            for (from_node_index, to_node_id) in [(node_index, neighbor_node_id), (neighbor_node_index, node_id)] {
+                let node_state_collection_ids = nodes[from_node_index].node_state_collection_ids_per_neighbor_node_id
+                    .entry(to_node_id.clone())
+                    .or_default();
+                if !node_state_collection_ids.contains(&node_state_collection.id) {
+                    node_state_collection_ids.push(node_state_collection.id.clone());
+                }
+            }
+        }
+
+        Ok(WaveFunction {
+            nodes,
+            node_state_collections,
+            global_constraints
+        })
+    }
+
+    /// This function mirrors each constraint declared only from a node to its neighbor by applying the inverse constraint from the neighbor back to the node, permitting for each neighbor node state only the node states of the node that permit it. Neighbors that already declare node state collections back to the node are left unchanged. The inverse node state collections are identified by their contents, so identical inverses share one node state collection and mirroring again adds nothing.
+    pub fn make_symmetric(&mut self) -> Result<(), String> {
+        let mut mirrored_node_state_collections: Vec<(usize, String, Vec<AnonymousNodeStateCollection<TNodeState>>)> = Vec::new();
+        {
+            let node_index_per_id: HashMap<&str, usize> = self.nodes.iter()
+                .enumerate()
+                .map(|(node_index, node)| (node.id.as_str(), node_index))
+                .collect();
+            let node_state_collection_per_id: HashMap<&str, &NodeStateCollection<TNodeState>> = self.node_state_collections.iter()
+                .map(|node_state_collection| (node_state_collection.id.as_str(), node_state_collection))
+                .collect();
+            for node in self.nodes.iter() {
+                // follow the order of the neighbor node ids so that the node state collections are added in the same order every time
+                let mut neighbor_node_ids: Vec<&String> = node.node_state_collection_ids_per_neighbor_node_id.keys().collect();
+                neighbor_node_ids.sort();
+                for neighbor_node_id in neighbor_node_ids {
+                    let Some(neighbor_node_index) = node_index_per_id.get(neighbor_node_id.as_str()).cloned() else {
+                        return Err(format!("Neighbor node {neighbor_node_id} does not exist in main list of nodes."));
+                    };
+                    let neighbor_node = &self.nodes[neighbor_node_index];
+                    if neighbor_node.node_state_collection_ids_per_neighbor_node_id.get(&node.id).is_some_and(|node_state_collection_ids| !node_state_collection_ids.is_empty()) {
+                        continue;
+                    }
+
+                    // a neighbor node state must be permitted by every node state collection for the same node state, just as when collapsing
+                    let mut permitted_node_state_ids_per_node_state_id: HashMap<&TNodeState, Vec<&Vec<TNodeState>>> = HashMap::new();
+                    for node_state_collection_id in node.node_state_collection_ids_per_neighbor_node_id[neighbor_node_id].iter() {
+                        let Some(node_state_collection) = node_state_collection_per_id.get(node_state_collection_id.as_str()) else {
+                            return Err(format!("Node state collection {node_state_collection_id} does not exist in main list of node state collections."));
+                        };
+                        permitted_node_state_ids_per_node_state_id.entry(&node_state_collection.node_state_id).or_default().push(&node_state_collection.node_state_ids);
+                    }
+
+                    // node states of the node without a node state collection permit every neighbor node state
+                    let mut anonymous_node_state_collections: Vec<AnonymousNodeStateCollection<TNodeState>> = Vec::new();
+                    for neighbor_node_state_id in neighbor_node.node_state_ids.iter() {
+                        let permitting_node_state_ids: Vec<TNodeState> = node.node_state_ids.iter()
+                            .filter(|node_state_id| permitted_node_state_ids_per_node_state_id.get(node_state_id).is_none_or(|permitted_node_state_ids_list| permitted_node_state_ids_list.iter().all(|permitted_node_state_ids| permitted_node_state_ids.contains(neighbor_node_state_id))))
+                            .cloned()
+                            .collect();
+                        if permitting_node_state_ids.len() != node.node_state_ids.len() {
+                            anonymous_node_state_collections.push(AnonymousNodeStateCollection::new(neighbor_node_state_id.clone(), permitting_node_state_ids));
+                        }
+                    }
+                    if !anonymous_node_state_collections.is_empty() {
+                        mirrored_node_state_collections.push((neighbor_node_index, node.id.clone(), anonymous_node_state_collections));
+                    }
+                }
+            }
+        }
+
+        let mut node_state_collection_index_per_id: HashMap<String, usize> = self.node_state_collections
+            .iter()
+            .enumerate()
+            .map(|(node_state_collection_index, node_state_collection)| (node_state_collection.id.clone(), node_state_collection_index))
+            .collect();
+        for (neighbor_node_index, node_id, anonymous_node_state_collections) in mirrored_node_state_collections.into_iter() {
+            let neighbor_node_state_collection_ids = self.nodes[neighbor_node_index].node_state_collection_ids_per_neighbor_node_id
+                .entry(node_id)
+                .or_default();
+            for anonymous_node_state_collection in anonymous_node_state_collections.into_iter() {
+                let node_state_collection_id = anonymous_node_state_collection.get_id();
+                let node_state_collection_id = anonymous_node_state_collection.register(&node_state_collection_id, &mut self.node_state_collections, &mut node_state_collection_index_per_id);
+                if !neighbor_node_state_collection_ids.contains(&node_state_collection_id) {
+                    neighbor_node_state_collection_ids.push(node_state_collection_id);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        let nodes_length: usize = self.nodes.len();
+
+        for node in self.nodes.iter() {
+            node.validate_node_state_ratios()?;
+        }
+
+        let node_ids: HashSet<&str> = self.nodes
+            .iter()
+            .map(|node| node.id.as_str())
+            .collect();
+
+        let mut node_state_collection_per_id: HashMap<&str, &NodeStateCollection<TNodeState>> = HashMap::new();
+        self.node_state_collections
+            .iter()
+            .for_each(|node_state_collection| {
+                node_state_collection_per_id.insert(&node_state_collection.id, node_state_collection);
+            });
+
+        // ensure that referenced neighbors are actually nodes and that referenced node state collections actually exist, describing every missing reference at once
+        // lists identical to the previously checked list are skipped once found to be complete since dense neighbors often share the same node state collections
+        let mut missing_reference_descriptions: Vec<String> = Vec::new();
+        let mut previous_node_state_collection_ids: Option<&Vec<String>> = None;
+        for node in self.nodes.iter() {
+            let mut neighbor_node_ids: Vec<&String> = node.node_state_collection_ids_per_neighbor_node_id.keys().collect();
+            neighbor_node_ids.sort();
+            for neighbor_node_id in neighbor_node_ids {
+                if !node_ids.contains(neighbor_node_id.as_str()) {
+                    missing_reference_descriptions.push(format!("Node {} references neighbor node {neighbor_node_id} but it does not exist in main list of nodes.", node.id));
+                }
+                let node_state_collection_ids = node.node_state_collection_ids_per_neighbor_node_id.get(neighbor_node_id).unwrap();
+                if previous_node_state_collection_ids == Some(node_state_collection_ids) {
+                    continue;
+                }
+                let mut is_complete = true;
+                for node_state_collection_id in node_state_collection_ids.iter() {
+                    if !node_state_collection_per_id.contains_key(node_state_collection_id.as_str()) {
+                        missing_reference_descriptions.push(format!("Node {} references node state collection {node_state_collection_id} for neighbor node {neighbor_node_id} but it does not exist in main list of node state collections.", node.id));
+                        is_complete = false;
+                    }
+                }
+                if is_complete {
+                    previous_node_state_collection_ids = Some(node_state_collection_ids);
+                }
+            }
+        }
+        if !missing_reference_descriptions.is_empty() {
+            return Err(missing_reference_descriptions.join(" "));
+        }
+
+        let not_connected_error_message = "Not all nodes connect together. At least one node must be able to traverse to all other nodes.";
+        if nodes_length == 0 {
+            return Err(String::from(not_connected_error_message));
+        }
+
+        // collect the neighbors of each node by index so that each traversal only visits each node and neighbor once
+        let mut node_index_per_id: HashMap<&str, usize> = HashMap::new();
+        for (node_index, node) in self.nodes.iter().enumerate() {
+            node_index_per_id.insert(&node.id, node_index);
+        }
+        let mut neighbor_node_indexes_per_node_index: Vec<Vec<usize>> = vec![Vec::new(); nodes_length];
+        let mut undirected_neighbor_node_indexes_per_node_index: Vec<Vec<usize>> = vec![Vec::new(); nodes_length];
+        for (node_index, node) in self.nodes.iter().enumerate() {
+            for neighbor_node_id in node.node_state_collection_ids_per_neighbor_node_id.keys() {
+                let neighbor_node_index: usize = *node_index_per_id.get(neighbor_node_id.as_str()).unwrap();
+                neighbor_node_indexes_per_node_index[node_index].push(neighbor_node_index);
+                undirected_neighbor_node_indexes_per_node_index[node_index].push(neighbor_node_index);
+                undirected_neighbor_node_indexes_per_node_index[neighbor_node_index].push(node_index);
+            }
+        }
+
+        // ignoring the direction of each neighbor, nodes that cannot reach each other are in separate groups
+        let mut is_traversed_per_node_index: Vec<bool> = vec![false; nodes_length];
+        let mut node_indexes_per_group: Vec<Vec<usize>> = Vec::new();
+        for node_index in 0..nodes_length {
+            if !is_traversed_per_node_index[node_index] {
+                node_indexes_per_group.push(traverse_node_indexes(&undirected_neighbor_node_indexes_per_node_index, node_index, &mut is_traversed_per_node_index));
+            }
+        }
+        if node_indexes_per_group.len() > 1 {
+            // describe the largest groups first, ordered by node id so that the description does not depend on the order of the nodes
+            let mut node_ids_per_group: Vec<Vec<&str>> = node_indexes_per_group
+                .iter()
+                .map(|node_indexes| {
+                    let mut node_ids: Vec<&str> = node_indexes.iter().map(|node_index| self.nodes[*node_index].id.as_str()).collect();
+                    node_ids.sort();
+                    node_ids
+                })
+                .collect();
+            node_ids_per_group.sort_by(|first_node_ids, second_node_ids| second_node_ids.len().cmp(&first_node_ids.len()).then_with(|| first_node_ids[0].cmp(second_node_ids[0])));
+            let mut group_descriptions: Vec<String> = Vec::new();
+            for node_ids in node_ids_per_group.iter() {
+                debug!("found group of {} nodes starting with node {}.", node_ids.len(), node_ids[0]);
+                if group_descriptions.len() < MAXIMUM_DESCRIBED_GROUPS_TOTAL {
+                    group_descriptions.push(format!("{} {} including {}", node_ids.len(), if node_ids.len() == 1 { "node" } else { "nodes" }, node_ids[..node_ids.len().min(REPRESENTATIVE_NODE_IDS_PER_GROUP_TOTAL)].join(", ")));
+                }
+            }
+            if node_ids_per_group.len() > MAXIMUM_DESCRIBED_GROUPS_TOTAL {
+                group_descriptions.push(format!("{} more groups", node_ids_per_group.len() - MAXIMUM_DESCRIBED_GROUPS_TOTAL));
+            }
+            return Err(format!("{not_connected_error_message} Found {} separate groups of nodes: {}.", node_ids_per_group.len(), group_descriptions.join("; ")));
+        }
+
+        // if any node can traverse to all other nodes then the node that starts the last traversal can as well, since every earlier traversal failed to reach it
+        let mut is_traversed_per_node_index: Vec<bool> = vec![false; nodes_length];
+        let mut last_start_node_index: usize = 0;
+        for node_index in 0..nodes_length {
+            if !is_traversed_per_node_index[node_index] {
+                last_start_node_index = node_index;
+                traverse_node_indexes(&neighbor_node_indexes_per_node_index, node_index, &mut is_traversed_per_node_index);
+            }
+        }
+        let mut is_traversed_per_node_index: Vec<bool> = vec![false; nodes_length];
+        let traversed_nodes_total: usize = traverse_node_indexes(&neighbor_node_indexes_per_node_index, last_start_node_index, &mut is_traversed_per_node_index).len();
+        if traversed_nodes_total != nodes_length {
+            return Err(format!("{not_connected_error_message} Node {} can only traverse to {traversed_nodes_total} of {nodes_length} nodes.", self.nodes[last_start_node_index].id));
+        }
+
+        Ok(())
     }
 
-    pub fn validate(&self) -> Result<(), String> {
-        let nodes_length: usize = self.nodes.len();
+    /// This function performs the same checks as validate while also ensuring that each node state collection only refers to node states that the nodes using it can be in.
+    pub fn validate_strict(&self) -> Result<(), String> {
+        self.validate()?;
 
         let mut node_per_id: HashMap<&str, &Node<TNodeState>> = HashMap::new();
-        let mut node_ids: HashSet<&str> = HashSet::new();
         self.nodes
             .iter()
             .for_each(|node: &Node<TNodeState>| {
                 node_per_id.insert(&node.id, node);
-                node_ids.insert(&node.id);
             });
 
         let mut node_state_collection_per_id: HashMap<&str, &NodeStateCollection<TNodeState>> = HashMap::new();
@@ -123,50 +1424,396 @@ impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord + Serialize + Deseria
                 node_state_collection_per_id.insert(&node_state_collection.id, node_state_collection);
             });
 
-        // ensure that references neighbors are actually nodes
-        for (_, node) in node_per_id.iter() {
-            for (neighbor_node_id_string, _) in node.node_state_collection_ids_per_neighbor_node_id.iter() {
-                let neighbor_node_id: &str = neighbor_node_id_string;
-                if !node_ids.contains(neighbor_node_id) {
+        for node in self.nodes.iter() {
+            let mut neighbor_node_ids: Vec<&String> = node.node_state_collection_ids_per_neighbor_node_id.keys().collect();
+            neighbor_node_ids.sort();
+            for neighbor_node_id in neighbor_node_ids {
+                let neighbor_node = node_per_id.get(neighbor_node_id.as_str()).unwrap();
+                for node_state_collection_id in node.node_state_collection_ids_per_neighbor_node_id.get(neighbor_node_id).unwrap().iter() {
+                    let node_state_collection = node_state_collection_per_id.get(node_state_collection_id.as_str()).unwrap();
+                    if !node.node_state_ids.contains(&node_state_collection.node_state_id) {
+                        return Err(format!("Node state collection {node_state_collection_id} is for node state {:?} that node {} can never be in.", node_state_collection.node_state_id, node.id));
+                    }
+                    for node_state_id in node_state_collection.node_state_ids.iter() {
+                        if !neighbor_node.node_state_ids.contains(node_state_id) {
+                            return Err(format!("Node state collection {node_state_collection_id} permits node state {node_state_id:?} that neighbor node {neighbor_node_id} of node {} can never be in.", node.id));
+                        }
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// This function performs the same checks as validate before propagating the constraints between every pair of neighboring nodes, reporting any node left without a possible node state along with the chain of nodes that restricted it. The wave function itself is not altered.
+    pub fn validate_with_arc_consistency(&self) -> Result<(), String> {
+        self.validate()?;
+        self.validate_satisfiability()
+    }
+
+    /// This function performs arc consistency over every neighbor constraint, returning the first node that is left without any possible node states along with the chain of nodes that restricted it. This is more expensive than validate and passing it does not guarantee that the wave function can be collapsed.
+    pub fn validate_satisfiability(&self) -> Result<(), String> {
+        let mut node_state_collection_per_id: HashMap<&str, &NodeStateCollection<TNodeState>> = HashMap::new();
+        self.node_state_collections
+            .iter()
+            .for_each(|node_state_collection| {
+                node_state_collection_per_id.insert(&node_state_collection.id, node_state_collection);
+            });
+
+        // a node that is its own neighbor starts with only the node states that it permits for itself, just as when collapsing
+        let mut possible_node_states_per_node_id: HashMap<&str, Vec<&TNodeState>> = HashMap::new();
+        for node in self.nodes.iter() {
+            possible_node_states_per_node_id.insert(&node.id, node.node_state_ids.iter().filter(|node_state_id| node.is_self_permitted(node_state_id, &node_state_collection_per_id)).collect());
+        }
+
+        // each constraint is the parent node, the child node, and the permitted child node states per parent node state
+        // a parent node state without a node state collection does not restrict the child node
+        // constraints between the same two nodes are grouped so that a pair of node states must satisfy every constraint in either direction
+        let mut constraints_per_node_id_pair = Vec::new();
+        let mut node_id_pair_index_per_node_id_pair: HashMap<(&str, &str), usize> = HashMap::new();
+        for node in self.nodes.iter() {
+            let mut neighbor_node_ids: Vec<&String> = node.node_state_collection_ids_per_neighbor_node_id.keys().collect();
+            neighbor_node_ids.sort();
+            for neighbor_node_id in neighbor_node_ids {
+                if !possible_node_states_per_node_id.contains_key(neighbor_node_id.as_str()) {
                     return Err(format!("Neighbor node {neighbor_node_id} does not exist in main list of nodes."));
                 }
+                let mut permitted_node_states_per_node_state: HashMap<&TNodeState, HashSet<&TNodeState>> = HashMap::new();
+                for node_state_collection_id in node.node_state_collection_ids_per_neighbor_node_id.get(neighbor_node_id).unwrap().iter() {
+                    let Some(node_state_collection) = node_state_collection_per_id.get(node_state_collection_id.as_str()) else {
+                        return Err(format!("Node state collection {node_state_collection_id} does not exist in main list of node state collections."));
+                    };
+                    // several node state collections for the same node state only permit the node states that they all permit
+                    permitted_node_states_per_node_state
+                        .entry(&node_state_collection.node_state_id)
+                        .and_modify(|permitted_node_states| permitted_node_states.retain(|node_state| node_state_collection.node_state_ids.contains(node_state)))
+                        .or_insert_with(|| node_state_collection.node_state_ids.iter().collect());
+                }
+                // the node states of a node that is its own neighbor were already restricted above
+                if neighbor_node_id == &node.id {
+                    continue;
+                }
+                let node_id_pair: (&str, &str) = if node.id.as_str() < neighbor_node_id.as_str() {
+                    (node.id.as_str(), neighbor_node_id.as_str())
+                }
+                else {
+                    (neighbor_node_id.as_str(), node.id.as_str())
+                };
+                let node_id_pair_index = *node_id_pair_index_per_node_id_pair.entry(node_id_pair).or_insert_with(|| {
+                    constraints_per_node_id_pair.push((node_id_pair, Vec::new()));
+                    constraints_per_node_id_pair.len() - 1
+                });
+                constraints_per_node_id_pair[node_id_pair_index].1.push((node.id.as_str(), permitted_node_states_per_node_state));
+            }
+        }
+
+        let mut node_id_pair_indexes_per_node_id: HashMap<&str, Vec<usize>> = HashMap::new();
+        for (node_id_pair_index, ((first_node_id, second_node_id), _)) in constraints_per_node_id_pair.iter().enumerate() {
+            node_id_pair_indexes_per_node_id.entry(first_node_id).or_default().push(node_id_pair_index);
+            if first_node_id != second_node_id {
+                node_id_pair_indexes_per_node_id.entry(second_node_id).or_default().push(node_id_pair_index);
+            }
+        }
+
+        // each arc is the node id pair index and if the first node (instead of the second node) is the node being revised
+        let mut arcs: VecDeque<(usize, bool)> = VecDeque::new();
+        let mut queued_arcs: HashSet<(usize, bool)> = HashSet::new();
+        for node_id_pair_index in 0..constraints_per_node_id_pair.len() {
+            for is_first_revised in [false, true] {
+                arcs.push_back((node_id_pair_index, is_first_revised));
+                queued_arcs.insert((node_id_pair_index, is_first_revised));
             }
         }
 
-        let mut at_least_one_node_connects_to_all_other_nodes: bool = false;
+        // the node whose possible node states most recently caused each node to be restricted
+        let mut restricting_node_id_per_node_id: HashMap<&str, &str> = HashMap::new();
+
         for node in self.nodes.iter() {
-            // ensure that all nodes connect to all other nodes
-            let mut all_traversed_node_ids: HashSet<&str> = HashSet::new();
-            let mut potential_node_ids: Vec<&str> = Vec::new();
+            if node.node_state_ids.is_empty() {
+                return Err(format!("Node {} has no possible node states.", node.id));
+            }
+            if possible_node_states_per_node_id[node.id.as_str()].is_empty() {
+                return Err(format!("Node {} has no possible node states that its node state collections for itself permit.", node.id));
+            }
+        }
+
+        while let Some(arc) = arcs.pop_front() {
+            queued_arcs.remove(&arc);
+            let (node_id_pair_index, is_first_revised) = arc;
+            let ((first_node_id, second_node_id), constraints) = &constraints_per_node_id_pair[node_id_pair_index];
+            let (revised_node_id, other_node_id): (&str, &str) = if is_first_revised {
+                (first_node_id, second_node_id)
+            }
+            else {
+                (second_node_id, first_node_id)
+            };
+
+            // the node states are permitted together if every constraint between the two nodes permits them
+            let is_permitted = |revised_node_state: &TNodeState, other_node_state: &TNodeState| -> bool {
+                constraints.iter().all(|(parent_node_id, permitted_node_states_per_node_state)| {
+                    let (parent_node_state, child_node_state) = if *parent_node_id == revised_node_id {
+                        (revised_node_state, other_node_state)
+                    }
+                    else {
+                        (other_node_state, revised_node_state)
+                    };
+                    if let Some(permitted_node_states) = permitted_node_states_per_node_state.get(parent_node_state) {
+                        permitted_node_states.contains(child_node_state)
+                    }
+                    else {
+                        true
+                    }
+                })
+            };
+
+            let other_possible_node_states: Vec<&TNodeState> = possible_node_states_per_node_id.get(other_node_id).unwrap().clone();
+            let revised_possible_node_states = possible_node_states_per_node_id.get_mut(revised_node_id).unwrap();
+            let original_possible_node_states_length = revised_possible_node_states.len();
+            revised_possible_node_states.retain(|revised_node_state| {
+                other_possible_node_states.iter().any(|other_node_state| is_permitted(revised_node_state, other_node_state))
+            });
+
+            if revised_possible_node_states.len() != original_possible_node_states_length {
+                debug!("node {revised_node_id} restricted by node {other_node_id} to {:?}", revised_possible_node_states);
+                restricting_node_id_per_node_id.insert(revised_node_id, other_node_id);
+
+                if revised_possible_node_states.is_empty() {
+                    let mut chain_node_ids: Vec<&str> = vec![revised_node_id];
+                    while let Some(restricting_node_id) = restricting_node_id_per_node_id.get(chain_node_ids.last().unwrap()) {
+                        if chain_node_ids.contains(restricting_node_id) {
+                            break;
+                        }
+                        chain_node_ids.push(restricting_node_id);
+                    }
+                    chain_node_ids.reverse();
+                    return Err(format!("Node {revised_node_id} has no possible node states after applying the constraints along {}.", chain_node_ids.join(" -> ")));
+                }
+
+                for other_node_id_pair_index in node_id_pair_indexes_per_node_id.get(revised_node_id).unwrap().iter() {
+                    let ((other_first_node_id, _), _) = &constraints_per_node_id_pair[*other_node_id_pair_index];
+                    let other_arc = (*other_node_id_pair_index, *other_first_node_id != revised_node_id);
+                    if other_arc != arc && !queued_arcs.contains(&other_arc) {
+                        arcs.push_back(other_arc);
+                        queued_arcs.insert(other_arc);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
 
-            potential_node_ids.push(&node.id);
+    /// This function explains why the wave function cannot be collapsed, returning None if it can be. Constraints are dropped greedily, those eliminating the most neighbor node states first, until the sequential collapsable wave function succeeds, after which each dropped constraint is restored if the wave function remains collapsable without it. The conflicting constraints are not guaranteed to be the smallest such set and every check is a full sequential search, so this is only suited to small and medium wave functions. The report has no conflicting constraints if the wave function cannot be collapsed even without its node state collections, such as when a global constraint cannot be satisfied.
+    pub fn explain_uncollapsable(&self, random_seed: Option<u64>) -> Option<ConflictReport<TNodeState>> {
+        let node_state_collection_per_id: HashMap<&str, &NodeStateCollection<TNodeState>> = self.node_state_collections.iter()
+            .map(|node_state_collection| (node_state_collection.id.as_str(), node_state_collection))
+            .collect();
+        let node_per_id: HashMap<&str, &Node<TNodeState>> = self.nodes.iter()
+            .map(|node| (node.id.as_str(), node))
+            .collect();
 
-            while let Some(node_id) = potential_node_ids.pop() {
-                let node = node_per_id.get(node_id).unwrap();
-                for neighbor_node_id_string in node.node_state_collection_ids_per_neighbor_node_id.keys() {
-                    let neighbor_node_id: &str = neighbor_node_id_string;
-                    if !all_traversed_node_ids.contains(neighbor_node_id) && !potential_node_ids.contains(&neighbor_node_id) {
-                        potential_node_ids.push(neighbor_node_id);
+        // each constraint is the index of the node that it is applied from, ignoring any that never eliminate a node state
+        let mut constraints: Vec<(usize, ConflictingConstraint<TNodeState>)> = Vec::new();
+        for (node_index, node) in self.nodes.iter().enumerate() {
+            let mut neighbor_node_ids: Vec<&String> = node.node_state_collection_ids_per_neighbor_node_id.keys().collect();
+            neighbor_node_ids.sort();
+            for neighbor_node_id in neighbor_node_ids {
+                let Some(neighbor_node) = node_per_id.get(neighbor_node_id.as_str()) else {
+                    continue;
+                };
+                for node_state_collection_id in node.node_state_collection_ids_per_neighbor_node_id[neighbor_node_id].iter() {
+                    let Some(node_state_collection) = node_state_collection_per_id.get(node_state_collection_id.as_str()) else {
+                        continue;
+                    };
+                    if !node.node_state_ids.contains(&node_state_collection.node_state_id) {
+                        continue;
+                    }
+                    let eliminated_node_state_ids: Vec<TNodeState> = neighbor_node.node_state_ids.iter()
+                        .filter(|neighbor_node_state_id| !node_state_collection.node_state_ids.contains(neighbor_node_state_id))
+                        .cloned()
+                        .collect();
+                    if !eliminated_node_state_ids.is_empty() {
+                        constraints.push((node_index, ConflictingConstraint {
+                            node_id: node.id.clone(),
+                            neighbor_node_id: neighbor_node_id.clone(),
+                            node_state_collection_id: node_state_collection_id.clone(),
+                            node_state_id: node_state_collection.node_state_id.clone(),
+                            eliminated_node_state_ids
+                        }));
                     }
                 }
-                all_traversed_node_ids.insert(node_id);
             }
+        }
+
+        let is_collapsable_without = |dropped_constraint_indexes: &[usize]| -> bool {
+            let mut wave_function = self.clone();
+            for constraint_index in dropped_constraint_indexes.iter() {
+                let (node_index, constraint) = &constraints[*constraint_index];
+                if let Some(node_state_collection_ids) = wave_function.nodes[*node_index].node_state_collection_ids_per_neighbor_node_id.get_mut(&constraint.neighbor_node_id) {
+                    node_state_collection_ids.retain(|node_state_collection_id| *node_state_collection_id != constraint.node_state_collection_id);
+                }
+            }
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<TNodeState>>(random_seed);
+            collapsable_wave_function.collapse().is_ok()
+        };
+
+        if is_collapsable_without(&[]) {
+            return None;
+        }
 
-            let all_traversed_node_ids_length = all_traversed_node_ids.len();
-            if all_traversed_node_ids_length == nodes_length {
-                at_least_one_node_connects_to_all_other_nodes = true;
+        // constraints that eliminate as many node states as each other are dropped together
+        let mut ordered_constraint_indexes: Vec<usize> = (0..constraints.len()).collect();
+        ordered_constraint_indexes.sort_by_key(|constraint_index| std::cmp::Reverse(constraints[*constraint_index].1.eliminated_node_state_ids.len()));
+        let mut dropped_constraint_indexes: Vec<usize> = Vec::new();
+        let mut is_collapsable: bool = false;
+        for ordered_constraint_indexes_chunk in ordered_constraint_indexes.chunk_by(|first_constraint_index, second_constraint_index| constraints[*first_constraint_index].1.eliminated_node_state_ids.len() == constraints[*second_constraint_index].1.eliminated_node_state_ids.len()) {
+            dropped_constraint_indexes.extend(ordered_constraint_indexes_chunk);
+            if is_collapsable_without(&dropped_constraint_indexes) {
+                is_collapsable = true;
                 break;
             }
         }
+        if !is_collapsable {
+            return Some(ConflictReport {
+                node_ids: Vec::new(),
+                node_state_collection_ids: Vec::new(),
+                conflicting_constraints: Vec::new()
+            });
+        }
+
+        let mut conflicting_constraint_indexes: Vec<usize> = dropped_constraint_indexes.clone();
+        for dropped_constraint_index in dropped_constraint_indexes.into_iter() {
+            let remaining_constraint_indexes: Vec<usize> = conflicting_constraint_indexes.iter()
+                .filter(|constraint_index| **constraint_index != dropped_constraint_index)
+                .cloned()
+                .collect();
+            if is_collapsable_without(&remaining_constraint_indexes) {
+                conflicting_constraint_indexes = remaining_constraint_indexes;
+            }
+        }
+        conflicting_constraint_indexes.sort();
+
+        let conflicting_constraints: Vec<ConflictingConstraint<TNodeState>> = conflicting_constraint_indexes.into_iter()
+            .map(|constraint_index| constraints[constraint_index].1.clone())
+            .collect();
+        let mut node_ids: Vec<String> = conflicting_constraints.iter()
+            .flat_map(|conflicting_constraint| [conflicting_constraint.node_id.clone(), conflicting_constraint.neighbor_node_id.clone()])
+            .collect();
+        node_ids.sort();
+        node_ids.dedup();
+        let mut node_state_collection_ids: Vec<String> = Vec::new();
+        for conflicting_constraint in conflicting_constraints.iter() {
+            if !node_state_collection_ids.contains(&conflicting_constraint.node_state_collection_id) {
+                node_state_collection_ids.push(conflicting_constraint.node_state_collection_id.clone());
+            }
+        }
+        Some(ConflictReport {
+            node_ids,
+            node_state_collection_ids,
+            conflicting_constraints
+        })
+    }
+
+    /// This function checks that the collapsed wave function is a solution of this wave function, returning an error for the first node, in node order, that is missing or in a node state that it cannot be in, and then for the first node in a node state that does not permit the node state of one of its neighbors. Global constraints are checked last.
+    pub fn validate_collapsed(&self, collapsed_wave_function: &CollapsedWaveFunction<TNodeState>) -> Result<(), String> {
+        let node_state_per_node_id = &collapsed_wave_function.node_state_per_node_id;
+
+        let node_ids: HashSet<&str> = self.nodes.iter().map(|node| node.id.as_str()).collect();
+        let mut collapsed_node_ids: Vec<&String> = node_state_per_node_id.keys().collect();
+        collapsed_node_ids.sort();
+        for collapsed_node_id in collapsed_node_ids {
+            if !node_ids.contains(collapsed_node_id.as_str()) {
+                return Err(format!("Collapsed node {collapsed_node_id} does not exist in main list of nodes."));
+            }
+        }
+
+        let node_state_collection_per_id: HashMap<&str, &NodeStateCollection<TNodeState>> = self.node_state_collections
+            .iter()
+            .map(|node_state_collection| (node_state_collection.id.as_str(), node_state_collection))
+            .collect();
+
+        for node in self.nodes.iter() {
+            let Some(node_state_id) = node_state_per_node_id.get(&node.id) else {
+                return Err(format!("Node {} is not collapsed.", node.id));
+            };
+            if !node.node_state_ids.contains(node_state_id) {
+                return Err(format!("Node {} cannot be in node state {:?}.", node.id, node_state_id));
+            }
+        }
+
+        for node in self.nodes.iter() {
+            let node_state_id = node_state_per_node_id.get(&node.id).unwrap();
+            let mut neighbor_node_ids: Vec<&String> = node.node_state_collection_ids_per_neighbor_node_id.keys().collect();
+            neighbor_node_ids.sort();
+            for neighbor_node_id in neighbor_node_ids {
+                let Some(neighbor_node_state_id) = node_state_per_node_id.get(neighbor_node_id) else {
+                    return Err(format!("Neighbor node {neighbor_node_id} does not exist in main list of nodes."));
+                };
+                for node_state_collection_id in node.node_state_collection_ids_per_neighbor_node_id.get(neighbor_node_id).unwrap().iter() {
+                    let Some(node_state_collection) = node_state_collection_per_id.get(node_state_collection_id.as_str()) else {
+                        return Err(format!("Node state collection {node_state_collection_id} does not exist in main list of node state collections."));
+                    };
+                    if &node_state_collection.node_state_id == node_state_id && !node_state_collection.node_state_ids.contains(neighbor_node_state_id) {
+                        return Err(format!("Node {} in node state {:?} does not permit neighbor node {} to be in node state {:?}.", node.id, node_state_id, neighbor_node_id, neighbor_node_state_id));
+                    }
+                }
+            }
+        }
+
+        for global_constraint in self.global_constraints.iter() {
+            let node_state_id = global_constraint.get_state();
+            let (minimum, maximum) = global_constraint.get_minimum_and_maximum();
+            let nodes_in_node_state_total = global_constraint.get_node_ids()
+                .iter()
+                .filter(|node_id| node_state_per_node_id.get(*node_id) == Some(node_state_id))
+                .count();
+            if nodes_in_node_state_total < minimum || nodes_in_node_state_total > maximum {
+                return Err(format!("Global constraint requires between {minimum} and {maximum} nodes to be in node state {node_state_id:?} but {nodes_in_node_state_total} are."));
+            }
+        }
 
-        if !at_least_one_node_connects_to_all_other_nodes {
-            return Err(String::from("Not all nodes connect together. At least one node must be able to traverse to all other nodes."));
+        for node in self.nodes.iter() {
+            // a node that is its own neighbor is not counted among its neighbors
+            let neighbor_node_ids: Vec<&String> = node.node_state_collection_ids_per_neighbor_node_id
+                .keys()
+                .filter(|neighbor_node_id| **neighbor_node_id != node.id)
+                .collect();
+            for aggregate_constraint in node.aggregate_constraints.iter() {
+                let node_state_ids = aggregate_constraint.get_states();
+                let (minimum, maximum) = aggregate_constraint.get_minimum_and_maximum(neighbor_node_ids.len());
+                let neighbors_in_node_states_total = neighbor_node_ids
+                    .iter()
+                    .filter(|neighbor_node_id| node_state_per_node_id.get(**neighbor_node_id).is_some_and(|node_state_id| node_state_ids.contains(node_state_id)))
+                    .count();
+                if neighbors_in_node_states_total < minimum || neighbors_in_node_states_total > maximum {
+                    return Err(format!("Aggregate constraint of node {} requires between {minimum} and {maximum} neighbors to be in node states {node_state_ids:?} but {neighbors_in_node_states_total} are.", node.id));
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// Providing a random seed shuffles the node states and neighbors of each node. Without one, they are kept in the order that they were provided and a random seed is chosen for the remaining random decisions, which is recorded in the collapsed wave function so that the collapse can be reproduced by get_unshuffled_collapsable_wave_function.
     pub fn get_collapsable_wave_function<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, TNodeState>>(&'a self, random_seed: Option<u64>) -> TCollapsableWaveFunction {
+        self.get_collapsable_wave_function_with_possible_node_states(random_seed, &HashMap::new())
+    }
+
+    /// This function continues a collapse from a snapshot of a collapsable wave function that was created from this wave function by get_collapsable_wave_function with a random seed. Collapsing the returned collapsable wave function produces the same collapsed wave function as the original collapse would have.
+    pub fn resume_collapsable_wave_function<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, TNodeState>>(&'a self, collapse_snapshot: &CollapseSnapshot<TNodeState>) -> Result<TCollapsableWaveFunction, String> {
+        let mut collapsable_wave_function = self.get_collapsable_wave_function::<TCollapsableWaveFunction>(Some(collapse_snapshot.random_seed));
+        collapsable_wave_function.restore(collapse_snapshot)?;
+        Ok(collapsable_wave_function)
+    }
+
+    /// This function is the same as get_collapsable_wave_function without a random seed except that the random seed is known, reproducing a collapse from the random seed recorded in its collapsed wave function.
+    pub fn get_unshuffled_collapsable_wave_function<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, TNodeState>>(&'a self, random_seed: u64) -> TCollapsableWaveFunction {
+        self.get_collapsable_wave_function_with_possible_node_states_and_random_seed(random_seed, false, &HashMap::new())
+    }
+
+    /// This function pins each provided node to its fixed node state so that collapsing only determines the remaining nodes. An error naming the offending node and node state is returned if a fixed node state does not exist or if it would not permit a neighbor node to be in any of its possible node states.
+    pub fn get_collapsable_wave_function_with_fixed_states<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, TNodeState>>(&'a self, random_seed: Option<u64>, fixed_node_state_per_node_id: &HashMap<String, TNodeState>) -> Result<TCollapsableWaveFunction, String> {
         let mut node_per_id: HashMap<&str, &Node<TNodeState>> = HashMap::new();
         self.nodes
             .iter()
@@ -181,83 +1828,262 @@ impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord + Serialize + Deseria
                 node_state_collection_per_id.insert(&node_state_collection.id, node_state_collection);
             });
 
-        // for each neighbor node
-        //      for each possible state for this node
-        //          create a mutable bit vector
-        //          for each possible node state for the neighbor node
-        //              get if the neighbor node state is permitted by this node's possible node state
-        //              push the boolean into bit vector
-        //          push bit vector into hashmap of mask per node state per neighbor node
+        let mut fixed_node_ids: Vec<&String> = fixed_node_state_per_node_id.keys().collect();
+        fixed_node_ids.sort();
 
-        // neighbor_mask_mapped_view_per_node_id is equivalent to mask_per_child_neighbor_per_state_per_node
-        let mut neighbor_mask_mapped_view_per_node_id: HashMap<&str, HashMap<&TNodeState, HashMap<&str, BitVec>>> = HashMap::new();
+        let mut possible_node_state_ids_per_node_id: HashMap<&str, Vec<&TNodeState>> = HashMap::new();
+        for fixed_node_id in fixed_node_ids.iter() {
+            let fixed_node_state_id = fixed_node_state_per_node_id.get(*fixed_node_id).unwrap();
+            let Some(fixed_node) = node_per_id.get(fixed_node_id.as_str()) else {
+                return Err(format!("Fixed node {fixed_node_id} does not exist in main list of nodes."));
+            };
+            let Some(node_state_id) = fixed_node.node_state_ids.iter().find(|node_state_id| *node_state_id == fixed_node_state_id) else {
+                return Err(format!("Fixed node {fixed_node_id} does not have node state {fixed_node_state_id:?}."));
+            };
+            possible_node_state_ids_per_node_id.insert(&fixed_node.id, vec![node_state_id]);
+        }
 
-        // create, per parent neighbor, a mask for each node (as child of parent neighbor)
-        let mut mask_per_parent_state_per_parent_neighbor_per_node: HashMap<&str, HashMap<&str, HashMap<&TNodeState, BitVec>>> = HashMap::new();
+        // ensure that each fixed node permits at least one possible node state for each of its neighbors
+        for fixed_node_id in fixed_node_ids.iter() {
+            let fixed_node = node_per_id.get(fixed_node_id.as_str()).unwrap();
+            let fixed_node_state_id = fixed_node_state_per_node_id.get(*fixed_node_id).unwrap();
+            let mut neighbor_node_ids: Vec<&String> = fixed_node.node_state_collection_ids_per_neighbor_node_id.keys().collect();
+            neighbor_node_ids.sort();
+            for neighbor_node_id in neighbor_node_ids {
+                let Some(neighbor_node) = node_per_id.get(neighbor_node_id.as_str()) else {
+                    return Err(format!("Neighbor node {neighbor_node_id} does not exist in main list of nodes."));
+                };
+                let neighbor_possible_node_state_ids: Vec<&TNodeState> = if let Some(possible_node_state_ids) = possible_node_state_ids_per_node_id.get(neighbor_node_id.as_str()) {
+                    possible_node_state_ids.clone()
+                }
+                else {
+                    neighbor_node.node_state_ids.iter().collect()
+                };
+                // the neighbor must be able to be in a node state that every node state collection for the fixed node state permits
+                let mut fixed_node_state_collections: Vec<&NodeStateCollection<TNodeState>> = Vec::new();
+                for node_state_collection_id in fixed_node.node_state_collection_ids_per_neighbor_node_id.get(neighbor_node_id).unwrap().iter() {
+                    let Some(node_state_collection) = node_state_collection_per_id.get(node_state_collection_id.as_str()) else {
+                        return Err(format!("Node state collection {node_state_collection_id} does not exist in main list of node state collections."));
+                    };
+                    if &node_state_collection.node_state_id == fixed_node_state_id {
+                        fixed_node_state_collections.push(node_state_collection);
+                    }
+                }
+                if !fixed_node_state_collections.is_empty() && !neighbor_possible_node_state_ids.iter().any(|node_state_id| fixed_node_state_collections.iter().all(|node_state_collection| node_state_collection.node_state_ids.contains(node_state_id))) {
+                    return Err(format!("Fixed node {fixed_node_id} in node state {fixed_node_state_id:?} does not permit any possible node state of neighbor node {neighbor_node_id}."));
+                }
+            }
+        }
 
-        // for each node
-        for child_node in self.nodes.iter() {
+        Ok(self.get_collapsable_wave_function_with_possible_node_states(random_seed, &possible_node_state_ids_per_node_id))
+    }
 
-            let mut mask_per_parent_state_per_parent_neighbor: HashMap<&str, HashMap<&TNodeState, BitVec>> = HashMap::new();
+    /// Nodes missing from possible_node_state_ids_per_node_id can be in any of their node states.
+    fn get_collapsable_wave_function_with_possible_node_states<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, TNodeState>>(&'a self, random_seed: Option<u64>, possible_node_state_ids_per_node_id: &HashMap<&str, Vec<&'a TNodeState>>) -> TCollapsableWaveFunction {
+        // a random seed is chosen when one is not provided so that the collapse can always be reproduced from the random seed recorded in the collapsed wave function
+        let is_shuffled = random_seed.is_some();
+        let random_seed = random_seed.unwrap_or_else(|| fastrand::u64(..));
+        self.get_collapsable_wave_function_with_possible_node_states_and_random_seed(random_seed, is_shuffled, possible_node_state_ids_per_node_id)
+    }
 
-            // look for each parent neighbor node
-            for parent_neighbor_node in self.nodes.iter() {
-                // if you find that this is a parent neighbor node
-                if parent_neighbor_node.node_state_collection_ids_per_neighbor_node_id.contains_key(&child_node.id) {
+    fn get_collapsable_wave_function_with_possible_node_states_and_random_seed<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, TNodeState>>(&'a self, random_seed: u64, is_shuffled: bool, possible_node_state_ids_per_node_id: &HashMap<&str, Vec<&'a TNodeState>>) -> TCollapsableWaveFunction {
+        let random_instance = Rc::new(RefCell::new(fastrand::Rng::with_seed(random_seed)));
+        let collapsable_nodes = if is_shuffled {
+            self.get_collapsable_nodes(Some(&mut random_instance.borrow_mut()), possible_node_state_ids_per_node_id)
+        }
+        else {
+            self.get_collapsable_nodes(None, possible_node_state_ids_per_node_id)
+        };
 
-                    debug!("constructing mask for {:?}'s child node {:?}.", parent_neighbor_node.id, child_node.id);
+        let mut collapsable_node_per_id: HashMap<&str, Rc<RefCell<CollapsableNode<TNodeState>>>> = HashMap::new();
+        for wrapped_collapsable_node in collapsable_nodes.iter() {
+            let collapsable_node = wrapped_collapsable_node.borrow();
+            collapsable_node_per_id.insert(collapsable_node.id, wrapped_collapsable_node.clone());
+        }
 
-                    let mut mask_per_parent_state: HashMap<&TNodeState, BitVec> = HashMap::new();
+        let mut collapsable_global_constraints: Vec<CollapsableGlobalConstraint<TNodeState>> = Vec::new();
+        for global_constraint in self.global_constraints.iter() {
+            let (minimum, maximum) = global_constraint.get_minimum_and_maximum();
+            collapsable_global_constraints.push(CollapsableGlobalConstraint {
+                node_ids: global_constraint.get_node_ids().iter().map(String::as_str).collect(),
+                node_states: vec![global_constraint.get_state()],
+                minimum,
+                maximum
+            });
+        }
+        // each aggregate constraint is enforced just like a global constraint over the neighbors of its node
+        for node in self.nodes.iter() {
+            if node.aggregate_constraints.is_empty() {
+                continue;
+            }
+            // a node that is its own neighbor is not counted among its neighbors
+            let mut neighbor_node_ids: Vec<&str> = node.node_state_collection_ids_per_neighbor_node_id
+                .keys()
+                .map(String::as_str)
+                .filter(|neighbor_node_id| *neighbor_node_id != node.id)
+                .collect();
+            neighbor_node_ids.sort_unstable();
+            for aggregate_constraint in node.aggregate_constraints.iter() {
+                let (minimum, maximum) = aggregate_constraint.get_minimum_and_maximum(neighbor_node_ids.len());
+                collapsable_global_constraints.push(CollapsableGlobalConstraint {
+                    node_ids: neighbor_node_ids.clone(),
+                    node_states: aggregate_constraint.get_states().iter().collect(),
+                    minimum,
+                    maximum
+                });
+            }
+        }
 
-                    // get the node state collections that this parent neighbor node forces upon this node
-                    let node_state_collection_ids: &Vec<String> = parent_neighbor_node.node_state_collection_ids_per_neighbor_node_id.get(&child_node.id).unwrap();
-                    for node_state_collection_id in node_state_collection_ids.iter() {
-                        let node_state_collection = node_state_collection_per_id.get(node_state_collection_id.as_str()).unwrap();
-                        // construct a mask for this parent neighbor's node state collection and node state for this child node
-                        let mut mask: BitVec = BitVec::new();
-                        for node_state_id in child_node.node_state_ids.iter() {
-                            // if the node state for the child is permitted by the parent neighbor node state collection
-                            mask.push(node_state_collection.node_state_ids.contains(node_state_id));
-                        }
-                        // store the mask for this child node
-                        mask_per_parent_state.insert(&node_state_collection.node_state_id, mask);
-                    }
+        let mut collapsable_wave_function = TCollapsableWaveFunction::new(collapsable_nodes, collapsable_node_per_id, random_seed, random_instance);
+        collapsable_wave_function.set_global_constraints(collapsable_global_constraints);
+        collapsable_wave_function
+    }
 
-                    mask_per_parent_state_per_parent_neighbor.insert(&parent_neighbor_node.id, mask_per_parent_state);
+    // the node states and parent neighbors of each collapsable node are shuffled when a random instance is provided
+    fn get_collapsable_nodes<'a>(&'a self, mut random_instance: Option<&mut fastrand::Rng>, possible_node_state_ids_per_node_id: &HashMap<&str, Vec<&'a TNodeState>>) -> Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>> {
+        // a node that is its own neighbor can only be in the node states that its node state collections for itself permit, so the rest are removed up front instead of masking the node against itself
+        let mut possible_node_state_ids_per_node_id = possible_node_state_ids_per_node_id.clone();
+        if self.nodes.iter().any(|node| node.node_state_collection_ids_per_neighbor_node_id.contains_key(&node.id)) {
+            let node_state_collection_per_id: HashMap<&str, &NodeStateCollection<TNodeState>> = self.node_state_collections
+                .iter()
+                .map(|node_state_collection| (node_state_collection.id.as_str(), node_state_collection))
+                .collect();
+            for node in self.nodes.iter() {
+                if node.node_state_collection_ids_per_neighbor_node_id.contains_key(&node.id) {
+                    let possible_node_state_ids: Vec<&'a TNodeState> = possible_node_state_ids_per_node_id
+                        .get(node.id.as_str())
+                        .cloned()
+                        .unwrap_or_else(|| node.node_state_ids.iter().collect())
+                        .into_iter()
+                        .filter(|node_state_id| node.is_self_permitted(node_state_id, &node_state_collection_per_id))
+                        .collect();
+                    possible_node_state_ids_per_node_id.insert(&node.id, possible_node_state_ids);
                 }
             }
+        }
+        let get_possible_node_state_ids = |node: &'a Node<TNodeState>| -> Vec<&'a TNodeState> {
+            if let Some(possible_node_state_ids) = possible_node_state_ids_per_node_id.get(node.id.as_str()) {
+                possible_node_state_ids.clone()
+            }
+            else {
+                node.node_state_ids.iter().collect()
+            }
+        };
 
-            mask_per_parent_state_per_parent_neighbor_per_node.insert(&child_node.id, mask_per_parent_state_per_parent_neighbor);
+        let mut node_index_per_id: HashMap<&str, usize> = HashMap::new();
+        for (node_index, node) in self.nodes.iter().enumerate() {
+            node_index_per_id.insert(&node.id, node_index);
         }
 
-        // fill the neighbor_mask_mapped_view_per_node_id now that all masks have been constructed
-        // neighbor_mask_mapped_view_per_node_id is equivalent to mask_per_child_neighbor_per_state_per_node
+        // intern each node state as a key so that the masks are constructed by indexing instead of comparing node states
+        let mut node_state_key_per_node_state_id: HashMap<&TNodeState, usize> = HashMap::new();
+        let mut possible_node_state_keys_per_node_index: Vec<Vec<usize>> = Vec::new();
         for node in self.nodes.iter() {
+            let mut possible_node_state_keys: Vec<usize> = Vec::new();
+            for node_state_id in get_possible_node_state_ids(node).into_iter() {
+                let node_state_keys_length = node_state_key_per_node_state_id.len();
+                let node_state_key = *node_state_key_per_node_state_id.entry(node_state_id).or_insert(node_state_keys_length);
+                possible_node_state_keys.push(node_state_key);
+            }
+            possible_node_state_keys_per_node_index.push(possible_node_state_keys);
+        }
+        let node_state_keys_length = node_state_key_per_node_state_id.len();
+
+        // the permitted node state keys of every node state collection are kept in one bit vector, where node states that no node can be in are never permitted
+        let mut node_state_collection_index_per_id: HashMap<&str, usize> = HashMap::new();
+        let mut permitted_node_state_keys: BitVec = bitvec![0; self.node_state_collections.len() * node_state_keys_length];
+        for (node_state_collection_index, node_state_collection) in self.node_state_collections.iter().enumerate() {
+            node_state_collection_index_per_id.insert(&node_state_collection.id, node_state_collection_index);
+            for node_state_id in node_state_collection.node_state_ids.iter() {
+                if let Some(node_state_key) = node_state_key_per_node_state_id.get(node_state_id) {
+                    permitted_node_state_keys.set(node_state_collection_index * node_state_keys_length + node_state_key, true);
+                }
+            }
+        }
 
-            // for this node, find all child neighbors
-            let node_id: &str = node.id.as_str();
+        // child nodes with the same possible node states are given the same masks for the same node state collections
+        let mut possible_node_state_keys_group_index_per_possible_node_state_keys: HashMap<&Vec<usize>, usize> = HashMap::new();
+        let possible_node_state_keys_group_index_per_node_index: Vec<usize> = possible_node_state_keys_per_node_index
+            .iter()
+            .map(|possible_node_state_keys| {
+                let possible_node_state_keys_groups_length = possible_node_state_keys_group_index_per_possible_node_state_keys.len();
+                *possible_node_state_keys_group_index_per_possible_node_state_keys
+                    .entry(possible_node_state_keys)
+                    .or_insert(possible_node_state_keys_groups_length)
+            })
+            .collect();
 
-            let mut mask_per_neighbor_per_state: HashMap<&TNodeState, HashMap<&str, BitVec>> = HashMap::new();
+        // for each neighbor node
+        //      for each node state collection that this node applies to the neighbor node
+        //          create a mutable bit vector
+        //          for each possible node state for the neighbor node
+        //              get if the neighbor node state is permitted by the node state collection
+        //              push the boolean into bit vector
+        //          store the bit vector as the mask per node state, shared by every neighbor node given the same node state collections with the same possible node states
 
-            for (neighbor_node_id, _) in node.node_state_collection_ids_per_neighbor_node_id.iter() {
-                let neighbor_node_id: &str = neighbor_node_id;
+        // mask_per_state_per_neighbor_per_node_index is equivalent to mask_per_child_neighbor_per_state_per_node
+        let mut mask_per_state_per_neighbor_per_node_index: Vec<HashMap<&str, SharedMaskPerState>> = (0..self.nodes.len()).map(|_| HashMap::new()).collect();
+        // the masks are keyed by the node state collection ids and the possible node state keys group of the child node
+        let mut mask_per_state_per_key: HashMap<(&[String], usize), SharedMaskPerState> = HashMap::new();
+        // dense neighbors tend to repeat the previous key, which is cheaper to compare than to hash
+        let mut previous_key_and_mask_per_state: Option<(_, SharedMaskPerState)> = None;
 
-                // get the inverse hashmap of this node to its child neighbor
-                let mask_per_parent_state_per_parent_neighbor = mask_per_parent_state_per_parent_neighbor_per_node.get(neighbor_node_id).unwrap();
-                let mask_per_parent_state = mask_per_parent_state_per_parent_neighbor.get(node_id).unwrap();
+        // the parent neighbors of each node are the nodes that have it as a neighbor
+        let mut parent_neighbor_node_ids_per_node_index: Vec<Vec<&str>> = vec![Vec::new(); self.nodes.len()];
 
-                for (node_state_id, mask) in mask_per_parent_state.iter() {
-                    mask_per_neighbor_per_state
-                        .entry(node_state_id)
-                        .or_insert(HashMap::new())
-                        .insert(neighbor_node_id, mask.clone());
+        for (parent_neighbor_node_index, parent_neighbor_node) in self.nodes.iter().enumerate() {
+            for (child_node_id, node_state_collection_ids) in parent_neighbor_node.node_state_collection_ids_per_neighbor_node_id.iter() {
+                // the node states of a node that is its own neighbor were already restricted above
+                if child_node_id == &parent_neighbor_node.id {
+                    continue;
                 }
-            }
+                let child_node_index = *node_index_per_id.get(child_node_id.as_str()).unwrap();
+                let child_node = &self.nodes[child_node_index];
+
+                debug!("constructing mask for {:?}'s child node {:?}.", parent_neighbor_node.id, child_node.id);
 
-            neighbor_mask_mapped_view_per_node_id.insert(node_id, mask_per_neighbor_per_state);
+                let key = (node_state_collection_ids.as_slice(), possible_node_state_keys_group_index_per_node_index[child_node_index]);
+                let mask_per_state = match &previous_key_and_mask_per_state {
+                    Some((previous_key, previous_mask_per_state)) if *previous_key == key => previous_mask_per_state.clone(),
+                    _ => {
+                        mask_per_state_per_key
+                            .entry(key)
+                            .or_insert_with(|| {
+                                let mut mask_per_state: Vec<Option<BitVec>> = vec![None; node_state_keys_length];
+                                // get the node state collections that this parent neighbor node forces upon this node
+                                for node_state_collection_id in node_state_collection_ids.iter() {
+                                    let node_state_collection_index = *node_state_collection_index_per_id.get(node_state_collection_id.as_str()).unwrap();
+                                    let node_state_collection = &self.node_state_collections[node_state_collection_index];
+                                    // a node state that no node can be in never needs a mask
+                                    let Some(node_state_key) = node_state_key_per_node_state_id.get(&node_state_collection.node_state_id) else {
+                                        continue;
+                                    };
+                                    // construct a mask for this parent neighbor's node state collection and node state for this child node
+                                    let mask: BitVec = possible_node_state_keys_per_node_index[child_node_index]
+                                        .iter()
+                                        .map(|node_state_key| permitted_node_state_keys[node_state_collection_index * node_state_keys_length + node_state_key])
+                                        .collect();
+                                    // several node state collections for the same node state only permit the node states that they all permit
+                                    match &mut mask_per_state[*node_state_key] {
+                                        Some(existing_mask) => *existing_mask &= mask,
+                                        None => mask_per_state[*node_state_key] = Some(mask)
+                                    }
+                                }
+                                Rc::new(mask_per_state)
+                            })
+                            .clone()
+                    }
+                };
+                previous_key_and_mask_per_state = Some((key, mask_per_state.clone()));
+
+                // the parent neighbor pulls this child node's masks from its own mapped view
+                mask_per_state_per_neighbor_per_node_index[parent_neighbor_node_index].insert(&child_node.id, mask_per_state);
+
+                parent_neighbor_node_ids_per_node_index[child_node_index].push(&parent_neighbor_node.id);
+            }
         }
 
-        let mut node_state_indexed_view_per_node_id: HashMap<&str, IndexedView<&TNodeState>> = HashMap::new();
+        let mut node_state_indexed_views: Vec<IndexedView<&TNodeState>> = Vec::new();
 
         // store all of the masks that my neighbors will be orienting so that this node can check for restrictions
         for node in self.nodes.iter() {
@@ -265,73 +2091,389 @@ impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord + Serialize + Deseria
 
             //debug!("storing for node {node_id} restrictive masks into node state indexed view.");
 
-            let referenced_node_state_ids: Vec<&TNodeState> = node.node_state_ids.iter().collect();
-            let cloned_node_state_ratios: Vec<f32> = node.node_state_ratios.clone();
+            let (referenced_node_state_ids, cloned_node_state_ratios): (Vec<&TNodeState>, Vec<f64>) = if let Some(possible_node_state_ids) = possible_node_state_ids_per_node_id.get(node_id) {
+                let node_state_ratios: Vec<f64> = possible_node_state_ids
+                    .iter()
+                    .map(|node_state_id| node.node_state_ratios[node.node_state_ids.iter().position(|other_node_state_id| other_node_state_id == *node_state_id).unwrap()])
+                    .collect();
+                (possible_node_state_ids.clone(), node_state_ratios)
+            }
+            else {
+                (node.node_state_ids.iter().collect(), node.node_state_ratios.clone())
+            };
 
             let node_state_indexed_view = IndexedView::new(referenced_node_state_ids, cloned_node_state_ratios);
             //debug!("stored for node {node_id} node state indexed view {:?}", node_state_indexed_view);
-            node_state_indexed_view_per_node_id.insert(node_id, node_state_indexed_view);
+            node_state_indexed_views.push(node_state_indexed_view);
         }
 
         let mut collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<TNodeState>>>> = Vec::new();
-        let mut collapsable_node_per_id: HashMap<&str, Rc<RefCell<CollapsableNode<TNodeState>>>> = HashMap::new();
         // contains the mask to apply to the neighbor when this node is in a specific state
-        let random_instance = if let Some(seed) = random_seed {
-            Rc::new(RefCell::new(fastrand::Rng::with_seed(seed)))
+        for (((node, node_state_indexed_view), mask_per_state_per_neighbor), node_state_keys) in self.nodes.iter().zip(node_state_indexed_views).zip(mask_per_state_per_neighbor_per_node_index).zip(possible_node_state_keys_per_node_index) {
+            let mut collapsable_node = CollapsableNode::new(&node.id, &node.node_state_collection_ids_per_neighbor_node_id, mask_per_state_per_neighbor, node_state_indexed_view, node_state_keys);
+            collapsable_node.metadata = node.metadata.as_ref();
+
+            if let Some(random_instance) = random_instance.as_deref_mut() {
+                collapsable_node.randomize(random_instance);
+            }
+
+            collapsable_nodes.push(Rc::new(RefCell::new(collapsable_node)));
         }
-        else {
-            Rc::new(RefCell::new(fastrand::Rng::new()))
-        };
-        for node in self.nodes.iter() {
-            let node_id: &str = node.id.as_str();
 
-            let node_state_indexed_view: IndexedView<&TNodeState> = node_state_indexed_view_per_node_id.remove(node_id).unwrap();
-            let mask_per_neighbor_per_state = neighbor_mask_mapped_view_per_node_id.remove(node_id).unwrap();
+        for (wrapped_collapsable_node, parent_neighbor_node_ids) in collapsable_nodes.iter().zip(parent_neighbor_node_ids_per_node_index) {
+            let mut collapsable_node = wrapped_collapsable_node.borrow_mut();
+            collapsable_node.parent_neighbor_node_ids = parent_neighbor_node_ids;
+            // sort prior to shuffling so that the same random seed always produces the same order regardless of hashmap order
+            collapsable_node.parent_neighbor_node_ids.sort();
+            if let Some(random_instance) = random_instance.as_deref_mut() {
+                random_instance.shuffle(collapsable_node.parent_neighbor_node_ids.as_mut_slice());
+            }
+        }
+
+        collapsable_nodes
+    }
+
+    /// This function propagates the restrictions of each node that can only be in one node state to its neighbors, continuing from any neighbor that is left with only one possible node state, without making any random decisions. The remaining possible node states of each node are returned in the order of its node states, where an empty list means that the wave function cannot be collapsed. The wave function itself is not altered.
+    pub fn get_initial_domains(&self) -> HashMap<String, Vec<TNodeState>> {
+        let collapsable_nodes = self.get_collapsable_nodes(None, &HashMap::new());
+
+        let mut collapsable_node_index_per_id: HashMap<&str, usize> = HashMap::new();
+        for (collapsable_node_index, wrapped_collapsable_node) in collapsable_nodes.iter().enumerate() {
+            collapsable_node_index_per_id.insert(wrapped_collapsable_node.borrow().id, collapsable_node_index);
+        }
 
-            let mut collapsable_node = CollapsableNode::new(&node.id, &node.node_state_collection_ids_per_neighbor_node_id, mask_per_neighbor_per_state, node_state_indexed_view);
+        // each forced node is only propagated once since its masks remain applied to its neighbors
+        let mut is_forced_per_collapsable_node_index: Vec<bool> = collapsable_nodes
+            .iter()
+            .map(|wrapped_collapsable_node| wrapped_collapsable_node.borrow().node_state_indexed_view.get_unrestricted_node_states_total() == 1)
+            .collect();
+        let mut forced_collapsable_node_indexes: VecDeque<usize> = (0..collapsable_nodes.len())
+            .filter(|collapsable_node_index| is_forced_per_collapsable_node_index[*collapsable_node_index])
+            .collect();
 
-            if random_seed.is_some() {
-                collapsable_node.randomize(&mut random_instance.borrow_mut());
+        while let Some(collapsable_node_index) = forced_collapsable_node_indexes.pop_front() {
+            // collect the masks first in case the node is its own neighbor
+            let neighbor_collapsable_node_index_and_mask_pairs: Vec<(usize, BitVec)> = {
+                let collapsable_node = collapsable_nodes[collapsable_node_index].borrow();
+                let Some(node_state_index) = collapsable_node.node_state_indexed_view.get_possible_node_state_indexes().first().copied() else {
+                    // a later neighbor restricted the forced node state
+                    continue;
+                };
+                collapsable_node.neighbor_node_ids
+                    .iter()
+                    .filter_map(|neighbor_node_id| {
+                        collapsable_node.get_mask_at_index(node_state_index, neighbor_node_id).map(|mask| (*collapsable_node_index_per_id.get(neighbor_node_id).unwrap(), mask.clone()))
+                    })
+                    .collect()
+            };
+            for (neighbor_collapsable_node_index, mask) in neighbor_collapsable_node_index_and_mask_pairs.iter() {
+                let mut neighbor_collapsable_node = collapsable_nodes[*neighbor_collapsable_node_index].borrow_mut();
+                neighbor_collapsable_node.add_mask(mask);
+                if !is_forced_per_collapsable_node_index[*neighbor_collapsable_node_index] && neighbor_collapsable_node.node_state_indexed_view.get_unrestricted_node_states_total() == 1 {
+                    debug!("node {} was forced into one node state.", neighbor_collapsable_node.id);
+                    is_forced_per_collapsable_node_index[*neighbor_collapsable_node_index] = true;
+                    forced_collapsable_node_indexes.push_back(*neighbor_collapsable_node_index);
+                }
             }
+        }
 
-            collapsable_nodes.push(Rc::new(RefCell::new(collapsable_node)));
+        collapsable_nodes
+            .iter()
+            .map(|wrapped_collapsable_node| {
+                let collapsable_node = wrapped_collapsable_node.borrow();
+                (String::from(collapsable_node.id), collapsable_node.node_state_indexed_view.get_possible_states().into_iter().cloned().collect())
+            })
+            .collect()
+    }
+
+    /// This function lazily enumerates every distinct collapsed wave function by exhaustively backtracking, stopping after the limit if provided. This is only practical for small wave functions.
+    pub fn get_all_collapsed_wave_functions(&self, limit: Option<usize>) -> impl Iterator<Item = Result<CollapsedWaveFunction<TNodeState>, String>> + '_ {
+        // sequential collapsing makes no random decisions, so every enumeration shares the same random seed
+        self.get_unshuffled_collapsable_wave_function::<SequentialCollapsableWaveFunction<TNodeState>>(0)
+            .collapse_into_all()
+            .take(limit.unwrap_or(usize::MAX))
+    }
+
+    /// This function collapses the wave function using consecutive random seeds starting at start_seed, returning the first seed (and its collapsed wave function) that satisfies the predicate. Attempts that fail to collapse are skipped. None is returned if no seed is found within max_attempts.
+    pub fn search_seed<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, TNodeState>, TPredicate: Fn(&CollapsedWaveFunction<TNodeState>) -> bool>(&'a self, start_seed: u64, max_attempts: u64, predicate: TPredicate) -> Option<(u64, CollapsedWaveFunction<TNodeState>)> {
+        for attempt_index in 0..max_attempts {
+            let random_seed = start_seed.wrapping_add(attempt_index);
+            let mut collapsable_wave_function = self.get_collapsable_wave_function::<TCollapsableWaveFunction>(Some(random_seed));
+            match collapsable_wave_function.collapse() {
+                Ok(collapsed_wave_function) => {
+                    if predicate(&collapsed_wave_function) {
+                        return Some((random_seed, collapsed_wave_function));
+                    }
+                },
+                Err(error_message) => {
+                    debug!("failed to collapse with random seed {}: {}", random_seed, error_message);
+                }
+            }
         }
+        None
+    }
 
-        for wrapped_collapsable_node in collapsable_nodes.iter() {
-            let collapsable_node = wrapped_collapsable_node.borrow();
-            collapsable_node_per_id.insert(collapsable_node.id, wrapped_collapsable_node.clone());
+    /// This function restarts the collapse from scratch with a newly derived random seed whenever an attempt fails, such as by exceeding the provided collapse options, returning the number of attempts made along with the collapsed wave function. The first attempt uses the provided random seed.
+    pub fn collapse_with_restarts<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, TNodeState>>(&'a self, random_seed: u64, collapse_options: CollapseOptions, max_attempts: u64) -> Result<(u64, CollapsedWaveFunction<TNodeState>), String> {
+        for attempt_index in 0..max_attempts {
+            let attempt_random_seed = get_restart_random_seed(random_seed, attempt_index);
+            let mut collapsable_wave_function = self.get_collapsable_wave_function::<TCollapsableWaveFunction>(Some(attempt_random_seed));
+            collapsable_wave_function.set_collapse_options(collapse_options.clone());
+            match collapsable_wave_function.collapse() {
+                Ok(collapsed_wave_function) => {
+                    return Ok((attempt_index + 1, collapsed_wave_function));
+                },
+                Err(error_message) => {
+                    debug!("failed to collapse on attempt {} with random seed {}: {}", attempt_index, attempt_random_seed, error_message);
+                }
+            }
         }
+        Err(format!("Cannot collapse wave function after {max_attempts} attempts."))
+    }
 
-        for wrapped_collapsable_node in collapsable_nodes.iter() {
-            let mut collapsable_node = wrapped_collapsable_node.borrow_mut();
-            let collapsable_node_id: &str = collapsable_node.id;
+    /// This function renders the wave function as a GraphViz DOT graph for debugging, as described by visualize::get_dot.
+    pub fn to_dot(&self, dot_options: DotOptions) -> String {
+        visualize::get_dot(self, None, &dot_options)
+    }
+
+    /// This function renders the wave function as a GraphViz DOT graph where each node is labelled and colored by its node state in the collapsed wave function.
+    pub fn to_dot_with_collapsed(&self, collapsed_wave_function: &CollapsedWaveFunction<TNodeState>, dot_options: DotOptions) -> String {
+        visualize::get_dot(self, Some(collapsed_wave_function), &dot_options)
+    }
+
+    /// This function collapses again only the nodes within the radius of the changed nodes, ignoring the direction of each neighbor, while every other node and each changed node is pinned to its node state in the previous collapsed wave function, such as after editing a single tile of a collapsed level. If the region cannot be collapsed, the radius grows one step at a time until it can be or until the region stops growing.
+    pub fn recollapse_region<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, TNodeState>>(&'a self, previous_collapsed_wave_function: &CollapsedWaveFunction<TNodeState>, changed_node_ids: &[String], radius: usize, random_seed: Option<u64>) -> Result<RecollapsedRegion<TNodeState>, String> {
+        let mut node_index_per_id: HashMap<&str, usize> = HashMap::new();
+        for (node_index, node) in self.nodes.iter().enumerate() {
+            node_index_per_id.insert(&node.id, node_index);
+        }
+        let mut undirected_neighbor_node_indexes_per_node_index: Vec<Vec<usize>> = vec![Vec::new(); self.nodes.len()];
+        for (node_index, node) in self.nodes.iter().enumerate() {
+            for neighbor_node_id in node.node_state_collection_ids_per_neighbor_node_id.keys() {
+                let Some(neighbor_node_index) = node_index_per_id.get(neighbor_node_id.as_str()).cloned() else {
+                    return Err(format!("Neighbor node {neighbor_node_id} does not exist in main list of nodes."));
+                };
+                undirected_neighbor_node_indexes_per_node_index[node_index].push(neighbor_node_index);
+                undirected_neighbor_node_indexes_per_node_index[neighbor_node_index].push(node_index);
+            }
+        }
+
+        // the distance of each node from the nearest changed node
+        let mut distance_per_node_index: Vec<Option<usize>> = vec![None; self.nodes.len()];
+        let mut frontier_node_indexes: VecDeque<usize> = VecDeque::new();
+        for changed_node_id in changed_node_ids.iter() {
+            let Some(changed_node_index) = node_index_per_id.get(changed_node_id.as_str()).cloned() else {
+                return Err(format!("Changed node {changed_node_id} does not exist in main list of nodes."));
+            };
+            if distance_per_node_index[changed_node_index].is_none() {
+                distance_per_node_index[changed_node_index] = Some(0);
+                frontier_node_indexes.push_back(changed_node_index);
+            }
+        }
+        while let Some(node_index) = frontier_node_indexes.pop_front() {
+            let neighbor_distance = distance_per_node_index[node_index].unwrap() + 1;
+            for neighbor_node_index in undirected_neighbor_node_indexes_per_node_index[node_index].iter() {
+                if distance_per_node_index[*neighbor_node_index].is_none() {
+                    distance_per_node_index[*neighbor_node_index] = Some(neighbor_distance);
+                    frontier_node_indexes.push_back(*neighbor_node_index);
+                }
+            }
+        }
+        let maximum_distance: usize = distance_per_node_index.iter().flatten().cloned().max().unwrap_or(0);
+
+        let mut current_radius = radius;
+        loop {
+            let mut fixed_node_state_per_node_id: HashMap<String, TNodeState> = HashMap::new();
+            for (node_index, node) in self.nodes.iter().enumerate() {
+                let is_recollapsed = distance_per_node_index[node_index].is_some_and(|distance| distance != 0 && distance <= current_radius);
+                if !is_recollapsed {
+                    let Some(node_state) = previous_collapsed_wave_function.node_state_per_node_id.get(&node.id) else {
+                        return Err(format!("Previous collapsed wave function does not contain node {}.", node.id));
+                    };
+                    fixed_node_state_per_node_id.insert(node.id.clone(), node_state.clone());
+                }
+            }
+
+            let collapsed_wave_function_result = self.get_collapsable_wave_function_with_fixed_states::<TCollapsableWaveFunction>(random_seed, &fixed_node_state_per_node_id)
+                .and_then(|mut collapsable_wave_function| collapsable_wave_function.collapse());
+            match collapsed_wave_function_result {
+                Ok(collapsed_wave_function) => {
+                    let node_state_per_node_id: HashMap<String, TNodeState> = collapsed_wave_function.node_state_per_node_id
+                        .into_iter()
+                        .filter(|(node_id, node_state)| previous_collapsed_wave_function.node_state_per_node_id.get(node_id) != Some(node_state))
+                        .collect();
+                    return Ok(RecollapsedRegion {
+                        node_state_per_node_id,
+                        radius: current_radius,
+                        is_region_grown: current_radius != radius
+                    });
+                },
+                Err(error_message) => {
+                    debug!("failed to recollapse with radius {}: {}", current_radius, error_message);
+                    if current_radius >= maximum_distance {
+                        return Err(format!("Cannot recollapse the nodes around the changed nodes at any radius: {error_message}"));
+                    }
+                }
+            }
+            current_radius += 1;
+        }
+    }
+
+    /// This function collapses the wave function on multiple threads, each trying the next unattempted random seed, returning the first seed that collapses along with its collapsed wave function. Once a seed succeeds no further seeds are attempted and the collapses still in progress on other threads are cancelled.
+    pub fn collapse_with_parallel_seeds<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, TNodeState>>(&'a self, random_seeds: Vec<u64>, threads_total: usize) -> Result<(u64, CollapsedWaveFunction<TNodeState>), String>
+    where
+        TNodeState: Send + Sync
+    {
+        let random_seeds_length: usize = random_seeds.len();
+        let next_random_seed_index: AtomicUsize = AtomicUsize::new(0);
+        let attempts_total: AtomicUsize = AtomicUsize::new(0);
+        // shared as the cancel token of every collapse so that the other threads stop as soon as one seed succeeds
+        let is_collapsed: Arc<AtomicBool> = Arc::new(AtomicBool::new(false));
+        let collapsed_result: Mutex<Option<(u64, CollapsedWaveFunction<TNodeState>)>> = Mutex::new(None);
+
+        std::thread::scope(|scope| {
+            for _ in 0..threads_total.clamp(1, random_seeds_length.max(1)) {
+                scope.spawn(|| {
+                    while !is_collapsed.load(Ordering::Acquire) {
+                        let random_seed_index = next_random_seed_index.fetch_add(1, Ordering::AcqRel);
+                        if random_seed_index >= random_seeds_length {
+                            break;
+                        }
+                        let random_seed = random_seeds[random_seed_index];
+                        attempts_total.fetch_add(1, Ordering::AcqRel);
+                        let mut collapsable_wave_function = self.get_collapsable_wave_function::<TCollapsableWaveFunction>(Some(random_seed));
+                        collapsable_wave_function.set_collapse_options(CollapseOptions {
+                            cancel_token: Some(is_collapsed.clone()),
+                            ..CollapseOptions::default()
+                        });
+                        match collapsable_wave_function.collapse() {
+                            Ok(collapsed_wave_function) => {
+                                let mut collapsed_result = collapsed_result.lock().unwrap();
+                                if collapsed_result.is_none() {
+                                    *collapsed_result = Some((random_seed, collapsed_wave_function));
+                                }
+                                is_collapsed.store(true, Ordering::Release);
+                            },
+                            Err(error_message) => {
+                                debug!("failed to collapse with random seed {}: {}", random_seed, error_message);
+                            }
+                        }
+                    }
+                });
+            }
+        });
+
+        if let Some(collapsed_result) = collapsed_result.into_inner().unwrap() {
+            Ok(collapsed_result)
+        }
+        else {
+            Err(format!("Cannot collapse wave function with any of the {} attempted random seeds.", attempts_total.load(Ordering::Acquire)))
+        }
+    }
+
+    /// This function reads the newline-delimited form of a wave function, where the first line is a header containing nodes_total and node_state_collections_total, followed by one node per line and then one node state collection per line. Each line is parsed as it is read so that very large wave functions are never buffered in full, and the first line that cannot be parsed is reported by its line number. Blank lines are ignored.
+    pub fn from_reader(mut reader: impl BufRead) -> Result<Self, String> {
+        let mut header: Option<WaveFunctionStreamHeader<TNodeState>> = None;
+        let mut nodes: Vec<Node<TNodeState>> = Vec::new();
+        let mut node_state_collections: Vec<NodeStateCollection<TNodeState>> = Vec::new();
+        let mut line = String::new();
+        let mut line_number: usize = 0;
+        loop {
+            line.clear();
+            let bytes_total = reader.read_line(&mut line).map_err(|error| format!("Cannot read line {} of wave function: {error}", line_number + 1))?;
+            if bytes_total == 0 {
+                break;
+            }
+            line_number += 1;
+            if line.trim().is_empty() {
+                continue;
+            }
 
-            if mask_per_parent_state_per_parent_neighbor_per_node.contains_key(collapsable_node_id) {
-                let mask_per_parent_state_per_parent_neighbor = mask_per_parent_state_per_parent_neighbor_per_node.get(collapsable_node_id).unwrap();
-                for parent_neighbor_node_id in mask_per_parent_state_per_parent_neighbor.keys() {
-                    collapsable_node.parent_neighbor_node_ids.push(parent_neighbor_node_id);
+            let parse_error = |error: serde_json::Error| format!("Cannot parse line {line_number} of wave function: {error}");
+            if let Some(header) = header.as_ref() {
+                if nodes.len() < header.nodes_total {
+                    nodes.push(serde_json::from_str(&line).map_err(parse_error)?);
                 }
-                if random_seed.is_some() {
-                    random_instance.borrow_mut().shuffle(collapsable_node.parent_neighbor_node_ids.as_mut_slice());
+                else if node_state_collections.len() < header.node_state_collections_total {
+                    node_state_collections.push(serde_json::from_str(&line).map_err(parse_error)?);
                 }
                 else {
-                    collapsable_node.parent_neighbor_node_ids.sort();
+                    return Err(format!("Cannot parse line {line_number} of wave function: expected only {} nodes and {} node state collections.", header.nodes_total, header.node_state_collections_total));
                 }
             }
+            else {
+                let parsed_header: WaveFunctionStreamHeader<TNodeState> = serde_json::from_str(&line).map_err(parse_error)?;
+                nodes.reserve(parsed_header.nodes_total);
+                node_state_collections.reserve(parsed_header.node_state_collections_total);
+                header = Some(parsed_header);
+            }
+        }
+
+        let Some(header) = header else {
+            return Err(String::from("Cannot parse wave function without a header line."));
+        };
+        if nodes.len() != header.nodes_total || node_state_collections.len() != header.node_state_collections_total {
+            return Err(format!("Cannot parse wave function with {} nodes and {} node state collections when the header expects {} nodes and {} node state collections.", nodes.len(), node_state_collections.len(), header.nodes_total, header.node_state_collections_total));
+        }
+        WaveFunction::new_with_global_constraints(nodes, node_state_collections, header.global_constraints)
+    }
+
+    /// This function writes the newline-delimited form of the wave function that is read by from_reader.
+    pub fn to_writer(&self, mut writer: impl Write) -> Result<(), String> {
+        let header = WaveFunctionStreamHeader {
+            nodes_total: self.nodes.len(),
+            node_state_collections_total: self.node_state_collections.len(),
+            global_constraints: self.global_constraints.clone()
+        };
+        let write_error = |error: std::io::Error| format!("Cannot write wave function: {error}");
+        writeln!(writer, "{}", serde_json::to_string(&header).unwrap()).map_err(write_error)?;
+        for node in self.nodes.iter() {
+            writeln!(writer, "{}", serde_json::to_string(node).unwrap()).map_err(write_error)?;
+        }
+        for node_state_collection in self.node_state_collections.iter() {
+            writeln!(writer, "{}", serde_json::to_string(node_state_collection).unwrap()).map_err(write_error)?;
         }
+        writer.flush().map_err(write_error)
+    }
+
+    /// This function writes the wave function as JSON within an envelope that records its format version, as opposed to the newline-delimited form written by to_writer.
+    pub fn save_to_writer(&self, mut writer: impl Write) -> Result<(), String> {
+        let wave_function_envelope = WaveFunctionEnvelope {
+            format_version: WAVE_FUNCTION_FORMAT_VERSION,
+            wave_function: self
+        };
+        serde_json::to_writer(&mut writer, &wave_function_envelope).map_err(|error| format!("Cannot write wave function: {error}"))?;
+        writer.flush().map_err(|error| format!("Cannot write wave function: {error}"))
+    }
 
-        TCollapsableWaveFunction::new(collapsable_nodes, collapsable_node_per_id, random_instance)
+    /// This function reads the wave function written by save_to_writer, returning an error if its format version is unknown or it cannot be parsed. A wave function saved as bare JSON, before the format was versioned, is read as well.
+    pub fn load_from_reader(reader: impl Read) -> Result<Self, String> {
+        let parse_error = |error: serde_json::Error| format!("Cannot parse wave function: {error}");
+        let serialized_wave_function = match serde_json::from_reader(reader).map_err(parse_error)? {
+            serde_json::Value::Object(mut serialized_wave_function_envelope) if serialized_wave_function_envelope.contains_key("format_version") => {
+                let format_version = serialized_wave_function_envelope.remove("format_version").unwrap();
+                if format_version.as_u64() != Some(WAVE_FUNCTION_FORMAT_VERSION) {
+                    return Err(format!("Cannot load wave function of format version {format_version} when expecting format version {WAVE_FUNCTION_FORMAT_VERSION}."));
+                }
+                let Some(serialized_wave_function) = serialized_wave_function_envelope.remove("wave_function") else {
+                    return Err(format!("Cannot load wave function of format version {format_version} without a wave_function."));
+                };
+                serialized_wave_function
+            },
+            serialized_wave_function => serialized_wave_function
+        };
+        serde_json::from_value(serialized_wave_function).map_err(parse_error)
     }
 
-    pub fn save_to_file(&self, file_path: &str) {
-        let serialized_self = serde_json::to_string(self).unwrap();
-        std::fs::write(file_path, serialized_self).unwrap();
+    #[cfg(feature = "file-io")]
+    pub fn save_to_file(&self, file_path: &str) -> Result<(), String> {
+        let file = std::fs::File::create(file_path).map_err(|error| format!("Cannot save wave function to {file_path}: {error}"))?;
+        self.save_to_writer(std::io::BufWriter::new(file))
     }
 
-    pub fn load_from_file(file_path: &str) -> Self {
-        let file = File::open(file_path).unwrap();
-        let reader = BufReader::new(file);
-        let deserialized_self: WaveFunction<TNodeState> = serde_json::from_reader(reader).unwrap();
-        deserialized_self
+    #[cfg(feature = "file-io")]
+    pub fn load_from_file(file_path: &str) -> Result<Self, String> {
+        let file = std::fs::File::open(file_path).map_err(|error| format!("Cannot load wave function from {file_path}: {error}"))?;
+        Self::load_from_reader(std::io::BufReader::new(file))
     }
 }
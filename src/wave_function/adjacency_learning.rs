@@ -0,0 +1,186 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use serde::{Serialize, de::DeserializeOwned};
+use super::{WaveFunction, collapsable_wave_function::collapsable_wave_function::CollapsedWaveFunction, grid::{self, AdjacencyKind, Direction, GridBuilder}};
+
+/// This function learns which node states are next to each other in the sample, indexed by x and then y just like GridBuilder::get_grid, as the node state, the direction, and the neighbor node state in that direction. Only the provided directions are learned and each rule is returned once in the order that it is first found.
+pub fn learn_adjacency_rules<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(sample: &[Vec<TNodeState>], directions: &[Direction]) -> Vec<(TNodeState, Direction, TNodeState)> {
+    let mut adjacency_rules: Vec<(TNodeState, Direction, TNodeState)> = Vec::new();
+    for (x, node_state_per_y) in sample.iter().enumerate() {
+        for (y, node_state_id) in node_state_per_y.iter().enumerate() {
+            for direction in directions.iter() {
+                let (x_offset, y_offset) = direction.get_offset();
+                let (Some(neighbor_x), Some(neighbor_y)) = (x.checked_add_signed(x_offset as isize), y.checked_add_signed(y_offset as isize)) else {
+                    continue;
+                };
+                let Some(neighbor_node_state_id) = sample.get(neighbor_x).and_then(|node_state_per_y| node_state_per_y.get(neighbor_y)) else {
+                    continue;
+                };
+                let adjacency_rule = (node_state_id.clone(), *direction, neighbor_node_state_id.clone());
+                if !adjacency_rules.contains(&adjacency_rule) {
+                    adjacency_rules.push(adjacency_rule);
+                }
+            }
+        }
+    }
+    adjacency_rules
+}
+
+/// This function adds every rotation and reflection of the adjacency rules. Since node states are not themselves oriented, rotating or reflecting a pair of node states only changes the direction between them, so each pair becomes permitted in every direction.
+pub fn get_rotated_and_reflected_adjacency_rules<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(adjacency_rules: &[(TNodeState, Direction, TNodeState)]) -> Vec<(TNodeState, Direction, TNodeState)> {
+    let mut rotated_and_reflected_adjacency_rules: Vec<(TNodeState, Direction, TNodeState)> = Vec::new();
+    for (node_state_id, _, neighbor_node_state_id) in adjacency_rules.iter() {
+        for direction in Direction::all() {
+            let adjacency_rule = (node_state_id.clone(), direction, neighbor_node_state_id.clone());
+            if !rotated_and_reflected_adjacency_rules.contains(&adjacency_rule) {
+                rotated_and_reflected_adjacency_rules.push(adjacency_rule);
+            }
+        }
+    }
+    rotated_and_reflected_adjacency_rules
+}
+
+/// This function creates a GridBuilder of the provided size that permits only the node states and adjacencies found in the sample, optionally including every rotation and reflection of the learned adjacencies.
+pub fn get_grid_builder<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Serialize + DeserializeOwned>(sample: &[Vec<TNodeState>], width: usize, height: usize, is_including_rotations_and_reflections: bool) -> Result<GridBuilder<TNodeState>, String> {
+    let mut node_state_ids: Vec<TNodeState> = Vec::new();
+    for node_state_id in sample.iter().flatten() {
+        if !node_state_ids.contains(node_state_id) {
+            node_state_ids.push(node_state_id.clone());
+        }
+    }
+    if node_state_ids.is_empty() {
+        return Err(String::from("Cannot learn adjacency rules from an empty sample."));
+    }
+
+    let mut adjacency_rules = learn_adjacency_rules(sample, &Direction::all());
+    if is_including_rotations_and_reflections {
+        adjacency_rules = get_rotated_and_reflected_adjacency_rules(&adjacency_rules);
+    }
+
+    let mut grid_builder = GridBuilder::new(width, height, node_state_ids);
+    for (node_state_id, direction, neighbor_node_state_id) in adjacency_rules.into_iter() {
+        grid_builder.allow(node_state_id, direction, neighbor_node_state_id);
+    }
+    Ok(grid_builder)
+}
+
+/// This struct is the overlapping model learned from a sample by learn_from_grid. Each node of its wave function is the position of a pattern in the output, where neighboring patterns must agree wherever they overlap, so that every pattern size by pattern size area of the output is a pattern found in the sample.
+#[derive(Debug, Clone)]
+pub struct LearnedModel<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    pattern_size: usize,
+    // each distinct pattern in the order that it is first found, indexed by x and then y
+    patterns: Vec<Vec<Vec<TNodeState>>>,
+    // how many times each pattern was found in the sample
+    pattern_counts: Vec<u32>
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> LearnedModel<TNodeState> {
+    pub fn get_pattern_size(&self) -> usize {
+        self.pattern_size
+    }
+    pub fn get_patterns(&self) -> &[Vec<Vec<TNodeState>>] {
+        &self.patterns
+    }
+    pub fn get_pattern_counts(&self) -> &[u32] {
+        &self.pattern_counts
+    }
+    // the other pattern is placed at the offset from the pattern
+    fn is_overlap_agreeing(&self, pattern_index: usize, other_pattern_index: usize, neighbor_offset: &[i64]) -> bool {
+        let pattern = &self.patterns[pattern_index];
+        let other_pattern = &self.patterns[other_pattern_index];
+        let pattern_size = self.pattern_size as i64;
+        for x in 0..pattern_size {
+            for y in 0..pattern_size {
+                let (other_x, other_y) = (x - neighbor_offset[0], y - neighbor_offset[1]);
+                if (0..pattern_size).contains(&other_x) && (0..pattern_size).contains(&other_y) && pattern[x as usize][y as usize] != other_pattern[other_x as usize][other_y as usize] {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+    /// This function creates the wave function of an output of the provided size, where each node state is the index of a pattern that is chosen in proportion to how often it was found in the sample.
+    pub fn get_wave_function(&self, width: usize, height: usize) -> Result<WaveFunction<usize>, String> {
+        if width < self.pattern_size || height < self.pattern_size {
+            return Err(format!("Cannot create an output of {width} by {height} from patterns of size {}.", self.pattern_size));
+        }
+        let node_state_ratio_per_node_state_id: HashMap<usize, f64> = self.pattern_counts
+            .iter()
+            .enumerate()
+            .map(|(pattern_index, pattern_count)| (pattern_index, *pattern_count as f64))
+            .collect();
+        grid::build_2d_grid_with_probabilities(width - self.pattern_size + 1, height - self.pattern_size + 1, node_state_ratio_per_node_state_id, AdjacencyKind::Four, |pattern_index, neighbor_offset| {
+            (0..self.patterns.len())
+                .filter(|other_pattern_index| self.is_overlap_agreeing(*pattern_index, *other_pattern_index, neighbor_offset))
+                .collect()
+        })
+    }
+    /// This function maps the collapsed wave function from get_wave_function back onto the output, indexed by x and then y, taking each node state from the pattern that covers it with the greatest x and y.
+    pub fn get_grid(&self, collapsed_wave_function: &CollapsedWaveFunction<usize>, width: usize, height: usize) -> Result<Vec<Vec<TNodeState>>, String> {
+        if width < self.pattern_size || height < self.pattern_size {
+            return Err(format!("Cannot create an output of {width} by {height} from patterns of size {}.", self.pattern_size));
+        }
+        let pattern_index_per_y_per_x = grid::get_2d_grid(collapsed_wave_function, width - self.pattern_size + 1, height - self.pattern_size + 1)?;
+        let mut node_state_per_y_per_x: Vec<Vec<TNodeState>> = Vec::with_capacity(width);
+        for x in 0..width {
+            let pattern_x = x.min(width - self.pattern_size);
+            let mut node_state_per_y: Vec<TNodeState> = Vec::with_capacity(height);
+            for y in 0..height {
+                let pattern_y = y.min(height - self.pattern_size);
+                let pattern = &self.patterns[pattern_index_per_y_per_x[pattern_x][pattern_y]];
+                node_state_per_y.push(pattern[x - pattern_x][y - pattern_y].clone());
+            }
+            node_state_per_y_per_x.push(node_state_per_y);
+        }
+        Ok(node_state_per_y_per_x)
+    }
+}
+
+/// This function learns every pattern size by pattern size area of the sample, indexed by x and then y just like GridBuilder::get_grid, along with how often each pattern is found. A wrapping sample also learns the areas that continue past each edge onto the opposite edge.
+pub fn learn_from_grid<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(sample: &[Vec<TNodeState>], pattern_size: usize, is_wrapping: bool) -> Result<LearnedModel<TNodeState>, String> {
+    if pattern_size == 0 {
+        return Err(String::from("Cannot learn patterns of size 0."));
+    }
+    let width = sample.len();
+    let height = sample.first().map_or(0, |node_state_per_y| node_state_per_y.len());
+    if width == 0 || height == 0 {
+        return Err(String::from("Cannot learn patterns from an empty sample."));
+    }
+    if sample.iter().any(|node_state_per_y| node_state_per_y.len() != height) {
+        return Err(String::from("Cannot learn patterns from a sample with columns of different lengths."));
+    }
+    if width < pattern_size || height < pattern_size {
+        return Err(format!("Cannot learn patterns of size {pattern_size} from a sample of {width} by {height}."));
+    }
+
+    let (positions_width, positions_height) = if is_wrapping {
+        (width, height)
+    }
+    else {
+        (width - pattern_size + 1, height - pattern_size + 1)
+    };
+    let mut patterns: Vec<Vec<Vec<TNodeState>>> = Vec::new();
+    let mut pattern_counts: Vec<u32> = Vec::new();
+    let mut pattern_index_per_pattern: HashMap<Vec<Vec<TNodeState>>, usize> = HashMap::new();
+    for position_y in 0..positions_height {
+        for position_x in 0..positions_width {
+            let pattern: Vec<Vec<TNodeState>> = (0..pattern_size)
+                .map(|x| (0..pattern_size).map(|y| sample[(position_x + x) % width][(position_y + y) % height].clone()).collect())
+                .collect();
+            if let Some(pattern_index) = pattern_index_per_pattern.get(&pattern) {
+                pattern_counts[*pattern_index] += 1;
+            }
+            else {
+                pattern_index_per_pattern.insert(pattern.clone(), patterns.len());
+                patterns.push(pattern);
+                pattern_counts.push(1);
+            }
+        }
+    }
+
+    Ok(LearnedModel {
+        pattern_size,
+        patterns,
+        pattern_counts
+    })
+}
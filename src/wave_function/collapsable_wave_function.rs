@@ -1,5 +1,7 @@
+#[allow(clippy::module_inception)]
 pub mod collapsable_wave_function;
 pub mod sequential_collapsable_wave_function;
 pub mod accommodating_collapsable_wave_function;
 pub mod accommodating_sequential_collapsable_wave_function;
-pub mod entropic_collapsable_wave_function;
\ No newline at end of file
+pub mod entropic_collapsable_wave_function;
+pub mod parallel_collapsable_wave_function;
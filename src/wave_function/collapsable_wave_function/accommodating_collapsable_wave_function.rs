@@ -1,12 +1,14 @@
 use std::collections::HashSet;
+use std::collections::hash_map::DefaultHasher;
 use std::marker::PhantomData;
 use std::{cell::RefCell, rc::Rc, collections::HashMap};
-use std::hash::Hash;
-use bitvec::vec::BitVec;
-use super::collapsable_wave_function::{CollapsableWaveFunction, CollapsableNode, CollapsedNodeState, CollapsedWaveFunction};
+use std::hash::{Hash, Hasher};
+use super::collapsable_wave_function::{CollapsableWaveFunction, CollapsableGlobalConstraint, CollapsableNode, CollapsedNodeState, CollapsedWaveFunction, CollapseLimiter, CollapseOptions, CollapseProgressCallback, CollapseProgressReporter, CollapseReport, CollapseSnapshot};
+use super::sequential_collapsable_wave_function::SequentialCollapsableWaveFunction;
 
-/// This struct represents a CollapsableWaveFunction that picks a random node, tries to get each parent to accommodate to the current state of the random node, repeating until all nodes are unrestricted. This is best for finding solutions when the condition problem has many possible solutions and you want a more random solution. If there are very few solutions, the wave function is uncollapsable by design, or there are certain types of cycles in the graph, this algorithm with perform poorly or never complete.
-pub struct AccommodatingCollapsableWaveFunction<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> {
+/// This struct represents a CollapsableWaveFunction that picks a random node, tries to get each parent to accommodate to the current state of the random node, repeating until all nodes are unrestricted. This is best for finding solutions when the condition problem has many possible solutions and you want a more random solution. If there are very few solutions or the wave function is uncollapsable by design, this algorithm will perform poorly.
+/// Certain cycles in the graph can cause the accommodating to move conflicts around the cycle forever, so if every node returns to a node state assignment that a previous pass already started from, the collapse falls back to a sequential search that continues counting against the same collapse options and reports to the same progress callback.
+pub struct AccommodatingCollapsableWaveFunction<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
     collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>>,
     collapsable_node_per_id: HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>,
     accommodate_node_ids: Vec<&'a str>,
@@ -14,11 +16,19 @@ pub struct AccommodatingCollapsableWaveFunction<'a, TNodeState: Eq + Hash + Clon
     accommodate_node_ids_index: usize,
     accommodated_total: usize,
     impacted_node_ids: HashSet<&'a str>,
+    // the hash of the node state of every node at the start of each pass
+    visited_assignment_hashes: HashSet<u64>,
+    random_seed: u64,
     random_instance: Rc<RefCell<fastrand::Rng>>,
+    collapse_limiter: CollapseLimiter,
+    collapse_progress_reporter: CollapseProgressReporter<'a>,
+    global_constraints: Vec<CollapsableGlobalConstraint<'a, TNodeState>>,
+    // the sequential search that a previous or the current collapse fell back to
+    sequential_collapsable_wave_function: Option<SequentialCollapsableWaveFunction<'a, TNodeState>>,
     node_state_type: PhantomData<TNodeState>
 }
 
-impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingCollapsableWaveFunction<'a, TNodeState> {
+impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> AccommodatingCollapsableWaveFunction<'a, TNodeState> {
     fn initialize_nodes(&mut self) -> Result<Vec<CollapsedNodeState<TNodeState>>, String> {
 
         // initialize each collapsable node to its first (random) state, storing them for the return
@@ -33,11 +43,7 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingCol
             }
             
             self.accommodate_node_ids.push(collapsable_node.id);
-            let node_state = collapsable_node.node_state_indexed_view.get().unwrap();
-            let collapsed_node_state: CollapsedNodeState<TNodeState> = CollapsedNodeState {
-                node_id: String::from(collapsable_node.id),
-                node_state_id: Some((*node_state).clone())
-            };
+            let collapsed_node_state: CollapsedNodeState<TNodeState> = collapsable_node.get_collapsed_node_state();
             initial_node_states.push(collapsed_node_state);
         }
         self.accommodate_node_ids_length = self.accommodate_node_ids.len();
@@ -45,20 +51,14 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingCol
 
         for wrapped_collapsable_node in self.collapsable_nodes.iter() {
             let collapsable_node = wrapped_collapsable_node.borrow();
-            let node_state = collapsable_node.node_state_indexed_view.get().unwrap();
             let neighbor_node_ids: &Vec<&str> = &collapsable_node.neighbor_node_ids;
-            let mask_per_neighbor_per_state: &HashMap<&TNodeState, HashMap<&str, BitVec>> = &collapsable_node.mask_per_neighbor_per_state;
-            if let Some(mask_per_neighbor) = mask_per_neighbor_per_state.get(node_state) {
-                for neighbor_node_id in neighbor_node_ids.iter() {
-                    if mask_per_neighbor.contains_key(neighbor_node_id) {
-                        let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
-                        let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
-                        //debug!("looking for mask from parent {:?} to child {:?}.", collapsable_node.id, neighbor_node_id);
-                        //debug!("mask_per_neighbor: {:?}", mask_per_neighbor);
-                        let mask = mask_per_neighbor.get(neighbor_node_id).unwrap();
-                        neighbor_collapsable_node.add_mask(mask);
-                        debug!("adding mask to {:?} when in initialize_nodes", neighbor_node_id);
-                    }
+            for neighbor_node_id in neighbor_node_ids.iter() {
+                if let Some(mask) = collapsable_node.get_current_mask(neighbor_node_id) {
+                    let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
+                    let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
+                    //debug!("looking for mask from parent {:?} to child {:?}.", collapsable_node.id, neighbor_node_id);
+                    neighbor_collapsable_node.add_mask(mask);
+                    debug!("adding mask to {:?} when in initialize_nodes", neighbor_node_id);
                 }
             }
         }
@@ -85,6 +85,49 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingCol
      
         debug!("after being prepared: {:?}", self.accommodate_node_ids);
     }
+    fn is_assignment_repeated(&mut self) -> bool {
+
+        // returns if every node is in the same node state as at the start of a previous pass
+
+        let mut hasher = DefaultHasher::new();
+        for wrapped_collapsable_node in self.collapsable_nodes.iter() {
+            wrapped_collapsable_node.borrow().node_state_indexed_view.get_node_state_index().unwrap().hash(&mut hasher);
+        }
+        !self.visited_assignment_hashes.insert(hasher.finish())
+    }
+    fn fall_back_to_sequential_collapsable_wave_function(&mut self) {
+
+        // remove the masks that each node applied to its neighbors, returning each node to its unstarted state
+        // store the sequential search so that every later collapse continues it
+
+        for wrapped_collapsable_node in self.collapsable_nodes.iter() {
+            let collapsable_node = wrapped_collapsable_node.borrow();
+            for neighbor_node_id in collapsable_node.neighbor_node_ids.iter() {
+                if let Some(mask) = collapsable_node.get_current_mask(neighbor_node_id) {
+                    let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
+                    wrapped_neighbor_collapsable_node.borrow_mut().subtract_mask(mask);
+                }
+            }
+        }
+        for wrapped_collapsable_node in self.collapsable_nodes.iter() {
+            wrapped_collapsable_node.borrow_mut().node_state_indexed_view.reset();
+        }
+
+        self.sequential_collapsable_wave_function = Some(SequentialCollapsableWaveFunction::new(self.collapsable_nodes.clone(), self.collapsable_node_per_id.clone(), self.random_seed, self.random_instance.clone()));
+    }
+    fn collapse_with_sequential_collapsable_wave_function<TCollapseResult>(&mut self, collapse: impl FnOnce(&mut SequentialCollapsableWaveFunction<'a, TNodeState>) -> TCollapseResult) -> TCollapseResult {
+
+        // pass the collapse limiter, progress reporter, and global constraints along to the sequential search
+        // take them back once it is done so that they still apply to the next collapse and to any options or callback set in between
+
+        let sequential_collapsable_wave_function = self.sequential_collapsable_wave_function.as_mut().unwrap();
+        let collapse_options = self.collapse_limiter.get_collapse_options().clone();
+        sequential_collapsable_wave_function.continue_collapse(std::mem::replace(&mut self.collapse_limiter, CollapseLimiter::new(collapse_options)), std::mem::take(&mut self.collapse_progress_reporter));
+        sequential_collapsable_wave_function.set_global_constraints(std::mem::take(&mut self.global_constraints));
+        let collapse_result = collapse(sequential_collapsable_wave_function);
+        (self.collapse_limiter, self.collapse_progress_reporter, self.global_constraints) = sequential_collapsable_wave_function.end_continued_collapse();
+        collapse_result
+    }
     fn is_done_accommodating_nodes(&self) -> bool {
 
         // returns if pointer is outside the bounds of the collapsable_nodes
@@ -132,7 +175,8 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingCol
         // NOTE: resetting the indexed_view for each accommodating parent significantly reduces the performance of this algorithm
 
         let mut changed_parent_node_states: Vec<CollapsedNodeState<TNodeState>> = Vec::new();
-        let mut to_node_state_and_from_node_state_tuple_per_parent_node_id: HashMap<&str, (&TNodeState, &TNodeState)> = HashMap::new();
+        // the node states are compared by their index within the node state indexed view of the parent neighbor node
+        let mut to_node_state_and_from_node_state_tuple_per_parent_node_id: HashMap<&str, (usize, usize)> = HashMap::new();
 
         // try to get each parent neighbor node to accommodate the current node
         {
@@ -148,18 +192,12 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingCol
 
                 let wrapped_parent_neighbor_node = self.collapsable_node_per_id.get(parent_neighbor_node_id).unwrap();
                 let mut parent_neighbor_node = wrapped_parent_neighbor_node.borrow_mut();
-                let original_node_state = *parent_neighbor_node.node_state_indexed_view.get().unwrap();
+                let original_node_state = parent_neighbor_node.node_state_indexed_view.get_node_state_index().unwrap();
                 let mut current_node_state = original_node_state;
                 let mut is_current_node_state_restrictive = true;
                 while is_current_node_state_restrictive {
-                    let is_current_mask_from_parent_restrictive: bool = if parent_neighbor_node.mask_per_neighbor_per_state.contains_key(&current_node_state) {
-                        let mask_per_neighbor = parent_neighbor_node.mask_per_neighbor_per_state.get(&current_node_state).unwrap();
-                        if let Some(mask) = mask_per_neighbor.get(current_collapsable_node_id) {
-                            current_collapsable_node.is_mask_restrictive_to_current_state(mask)
-                        }
-                        else {
-                            false
-                        }
+                    let is_current_mask_from_parent_restrictive: bool = if let Some(mask) = parent_neighbor_node.get_mask_at_index(current_node_state, current_collapsable_node_id) {
+                        current_collapsable_node.is_mask_restrictive_to_current_state(mask)
                     }
                     else {
                         false
@@ -169,23 +207,20 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingCol
                         is_current_node_state_restrictive = false;  // leave the while loop for this parent neighbor node
 
                         if current_node_state != original_node_state {
-                            debug!("the node state had to change to {:?}", current_node_state);
+                            debug!("the node state had to change to {:?}", parent_neighbor_node.node_state_indexed_view.get_node_state_at_index(current_node_state));
 
                             // store the changed node state
-                            changed_parent_node_states.push(CollapsedNodeState {
-                                node_id: String::from(*parent_neighbor_node_id),
-                                node_state_id: Some(current_node_state.clone())
-                            });
+                            changed_parent_node_states.push(parent_neighbor_node.get_collapsed_node_state());
                             
                             to_node_state_and_from_node_state_tuple_per_parent_node_id.insert(parent_neighbor_node_id, (original_node_state, current_node_state));
                         }
                         else {
-                            debug!("the node state was already good at {:?}", current_node_state);
+                            debug!("the node state was already good at {:?}", parent_neighbor_node.node_state_indexed_view.get_node_state_at_index(current_node_state));
                         }
                     }
                     else {
                         parent_neighbor_node.node_state_indexed_view.move_next();
-                        let next_node_state = *parent_neighbor_node.node_state_indexed_view.get().unwrap();
+                        let next_node_state = parent_neighbor_node.node_state_indexed_view.get_node_state_index().unwrap();
                         if next_node_state == original_node_state {
                             // unable to accommodate the current collapsable node
                             debug!("Unable to accommodate the current collapsable node {:?} at state {:?}", current_collapsable_node_id, current_collapsable_node.node_state_indexed_view.get().unwrap());
@@ -205,31 +240,22 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingCol
                 
                 // inform the impacted neighbors
                 let neighbor_node_ids: &Vec<&str> = &parent_neighbor_node.neighbor_node_ids;
-                let mask_per_neighbor_per_state: &HashMap<&TNodeState, HashMap<&str, BitVec>> = &parent_neighbor_node.mask_per_neighbor_per_state;
-                if let Some(mask_per_neighbor) = mask_per_neighbor_per_state.get(original_node_state) {
-                    for neighbor_node_id in neighbor_node_ids.iter() {
-                        if mask_per_neighbor.contains_key(neighbor_node_id) {
-                            let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
-                            let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
-                            //debug!("looking for mask from parent {:?} to child {:?}.", collapsable_node.id, neighbor_node_id);
-                            //debug!("mask_per_neighbor: {:?}", mask_per_neighbor);
-                            let mask = mask_per_neighbor.get(neighbor_node_id).unwrap();
-                            neighbor_collapsable_node.subtract_mask(mask);
-                            debug!("subtracting mask to {:?} when in accommodate_current_node", neighbor_node_id);
-                        }
+                for neighbor_node_id in neighbor_node_ids.iter() {
+                    if let Some(mask) = parent_neighbor_node.get_mask_at_index(*original_node_state, neighbor_node_id) {
+                        let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
+                        let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
+                        //debug!("looking for mask from parent {:?} to child {:?}.", collapsable_node.id, neighbor_node_id);
+                        neighbor_collapsable_node.subtract_mask(mask);
+                        debug!("subtracting mask to {:?} when in accommodate_current_node", neighbor_node_id);
                     }
                 }
-                if let Some(mask_per_neighbor) = mask_per_neighbor_per_state.get(current_node_state) {
-                    for neighbor_node_id in neighbor_node_ids.iter() {
-                        if mask_per_neighbor.contains_key(neighbor_node_id) {
-                            let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
-                            let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
-                            //debug!("looking for mask from parent {:?} to child {:?}.", collapsable_node.id, neighbor_node_id);
-                            //debug!("mask_per_neighbor: {:?}", mask_per_neighbor);
-                            let mask = mask_per_neighbor.get(neighbor_node_id).unwrap();
-                            neighbor_collapsable_node.add_mask(mask);
-                            debug!("adding mask to {:?} when in accommodate_current_node", neighbor_node_id);
-                        }
+                for neighbor_node_id in neighbor_node_ids.iter() {
+                    if let Some(mask) = parent_neighbor_node.get_mask_at_index(*current_node_state, neighbor_node_id) {
+                        let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
+                        let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
+                        //debug!("looking for mask from parent {:?} to child {:?}.", collapsable_node.id, neighbor_node_id);
+                        neighbor_collapsable_node.add_mask(mask);
+                        debug!("adding mask to {:?} when in accommodate_current_node", neighbor_node_id);
                     }
                 }
             }
@@ -239,25 +265,56 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingCol
 
         changed_parent_node_states
     }
+    fn get_collapsed_nodes_total(&self) -> usize {
+        self.collapsable_nodes
+            .iter()
+            .filter(|wrapped_collapsable_node| !wrapped_collapsable_node.borrow().node_state_indexed_view.is_current_state_restricted())
+            .count()
+    }
+    fn try_report_node_assignments(&mut self, collapsed_node_states: &[CollapsedNodeState<TNodeState>]) -> Result<(), String> {
+        for collapsed_node_state in collapsed_node_states.iter() {
+            if self.collapse_progress_reporter.assign_node() {
+                let collapsed_nodes_total = self.get_collapsed_nodes_total();
+                self.collapse_progress_reporter.try_report(&collapsed_node_state.node_id, collapsed_nodes_total, self.collapsable_nodes.len())?;
+            }
+        }
+        Ok(())
+    }
+    // accommodating does not consider the global constraints, so they are only verified once every node is unrestricted
+    fn try_verify_global_constraints(&self) -> Result<(), String> {
+        if self.global_constraints.iter().all(|global_constraint| global_constraint.is_satisfiable(&self.collapsable_node_per_id)) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Cannot collapse wave function without violating a global constraint."))
+        }
+    }
     fn get_collapsed_wave_function(&self) -> CollapsedWaveFunction<TNodeState> {
         let mut node_state_per_node_id: HashMap<String, TNodeState> = HashMap::new();
+        let mut node_metadata_per_node: HashMap<String, serde_json::Value> = HashMap::new();
         for wrapped_collapsable_node in self.collapsable_nodes.iter() {
             let collapsable_node = wrapped_collapsable_node.borrow();
             let node_state: TNodeState = (*collapsable_node.node_state_indexed_view.get().unwrap()).clone();
             let node_id: String = String::from(collapsable_node.id);
             debug!("established node {node_id} in state {:?}.", node_state);
+            if let Some(metadata) = collapsable_node.metadata {
+                node_metadata_per_node.insert(node_id.clone(), metadata.clone());
+            }
             node_state_per_node_id.insert(node_id, node_state);
         }
         CollapsedWaveFunction {
-            node_state_per_node_id
+            node_state_per_node_id,
+            random_seed: self.random_seed,
+            node_metadata_per_node
         }
     }
 }
 
-impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveFunction<'a, TNodeState> for AccommodatingCollapsableWaveFunction<'a, TNodeState> {
+impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> CollapsableWaveFunction<'a, TNodeState> for AccommodatingCollapsableWaveFunction<'a, TNodeState> {
     fn new(
         collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>>,
         collapsable_node_per_id: HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>,
+        random_seed: u64,
         random_instance: Rc<RefCell<fastrand::Rng>>
     ) -> Self {
         AccommodatingCollapsableWaveFunction {
@@ -268,37 +325,64 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
             accommodate_node_ids_index: 0,
             accommodated_total: 0,
             impacted_node_ids: HashSet::new(),
+            visited_assignment_hashes: HashSet::new(),
+            random_seed,
             random_instance,
+            collapse_limiter: CollapseLimiter::new(CollapseOptions::default()),
+            collapse_progress_reporter: CollapseProgressReporter::new(),
+            global_constraints: Vec::new(),
+            sequential_collapsable_wave_function: None,
             node_state_type: PhantomData
         }
     }
-    fn collapse(&'a mut self) -> Result<CollapsedWaveFunction<TNodeState>, String> {
+    fn collapse(&mut self) -> Result<CollapsedWaveFunction<TNodeState>, String> {
+        self.collapse_limiter.start();
+        self.collapse_progress_reporter.start();
+        if self.sequential_collapsable_wave_function.is_some() {
+            // a collapse that already fell back continues the sequential search, just as a sequential collapse continues where it stopped
+            return self.collapse_with_sequential_collapsable_wave_function(|sequential_collapsable_wave_function| sequential_collapsable_wave_function.collapse());
+        }
         let initialize_result = self.initialize_nodes();
         if initialize_result.is_err() {
             return Err(initialize_result.err().unwrap());
         }
+        self.try_report_node_assignments(&initialize_result.unwrap())?;
 
         let mut iterations_total: u32 = 0;
 
         debug!("about to enter while loop");
         while !self.is_fully_collapsed() {
+            if self.is_assignment_repeated() {
+                debug!("falling back to a sequential search after {:?} iterations", iterations_total);
+                self.fall_back_to_sequential_collapsable_wave_function();
+                return self.collapse_with_sequential_collapsable_wave_function(|sequential_collapsable_wave_function| sequential_collapsable_wave_function.collapse());
+            }
             debug!("preparing nodes for iteration");
             self.prepare_nodes_for_iteration();
+            if self.collapse_progress_reporter.begin_pass() {
+                self.collapse_limiter.restart();
+            }
             debug!("checking if done accommodating nodes");
             while !self.is_done_accommodating_nodes() {
+                if self.collapse_limiter.try_iterate() {
+                    return Err(self.collapse_limiter.get_error_message(self.get_collapsed_nodes_total(), self.collapsable_nodes.len()));
+                }
                 debug!("checking if current node is in conflict");
                 if self.is_current_node_in_conflict() {
                     debug!("accommodating current node");
-                    self.accommodate_current_node();
+                    let accommodated_neighbor_node_states = self.accommodate_current_node();
+                    self.collapse_progress_reporter.recollapse_nodes(accommodated_neighbor_node_states.len());
+                    self.try_report_node_assignments(&accommodated_neighbor_node_states)?;
                 }
                 iterations_total += 1;
             }
         }
         debug!("fully collapsed after {:?} iterations", iterations_total);
 
+        self.try_verify_global_constraints()?;
         Ok(self.get_collapsed_wave_function())
     }
-    fn collapse_into_steps(&'a mut self) -> Result<Vec<CollapsedNodeState<TNodeState>>, String> {
+    fn collapse_into_steps(&mut self) -> Result<Vec<CollapsedNodeState<TNodeState>>, String> {
 
         // initialize each collapsable node to its first (random) state
         // alter masks for every collapsable node to its neighbors
@@ -306,6 +390,8 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
         // while not yet fully collapsed
         //      shuffle collapsable nodes
         //      initialize pointer to first element of collapsable_nodes
+        //      if the node states are the same as at the start of a previous pass
+        //          fall back to a sequential search
         //      while pointer is inside the bounds
         //          if current collapsable node is in conflict and not already impacted
         //              accommodate this collapsable node
@@ -313,27 +399,84 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
         //              cache impacted nodes
         //          increment pointer
         //
-        // NOTE: this could cause an infinite loop for the AB<-->CD unit test
 
         let mut collapsed_node_states: Vec<CollapsedNodeState<TNodeState>> = Vec::new();
 
+        self.collapse_limiter.start();
+        self.collapse_progress_reporter.start();
+        if self.sequential_collapsable_wave_function.is_some() {
+            return self.collapse_with_sequential_collapsable_wave_function(|sequential_collapsable_wave_function| sequential_collapsable_wave_function.collapse_into_steps());
+        }
         let initialized_node_states_result = self.initialize_nodes();
         if initialized_node_states_result.is_err() {
             return Err(initialized_node_states_result.err().unwrap());
         }
         let initialized_node_states = initialized_node_states_result.unwrap();
+        self.try_report_node_assignments(&initialized_node_states)?;
         collapsed_node_states.extend(initialized_node_states);
 
         while !self.is_fully_collapsed() {
+            if self.is_assignment_repeated() {
+                // replaying the sequential steps after the accommodating steps still reproduces the collapsed wave function
+                self.fall_back_to_sequential_collapsable_wave_function();
+                collapsed_node_states.extend(self.collapse_with_sequential_collapsable_wave_function(|sequential_collapsable_wave_function| sequential_collapsable_wave_function.collapse_into_steps())?);
+                return Ok(collapsed_node_states);
+            }
             self.prepare_nodes_for_iteration();
+            if self.collapse_progress_reporter.begin_pass() {
+                self.collapse_limiter.restart();
+            }
             while !self.is_done_accommodating_nodes() {
+                if self.collapse_limiter.try_iterate() {
+                    return Err(self.collapse_limiter.get_error_message(self.get_collapsed_nodes_total(), self.collapsable_nodes.len()));
+                }
                 if self.is_current_node_in_conflict() {
                     let accommodated_neighbor_node_states = self.accommodate_current_node();
+                    self.collapse_progress_reporter.recollapse_nodes(accommodated_neighbor_node_states.len());
+                    self.try_report_node_assignments(&accommodated_neighbor_node_states)?;
                     collapsed_node_states.extend(accommodated_neighbor_node_states);
                 }
             }
         }
 
+        self.try_verify_global_constraints()?;
         Ok(collapsed_node_states)
     }
-}
\ No newline at end of file
+    fn get_random_seed(&self) -> u64 {
+        self.random_seed
+    }
+    fn collapse_with_report(&mut self) -> (Result<CollapsedWaveFunction<TNodeState>, String>, CollapseReport) {
+        let collapsed_wave_function_result = self.collapse();
+        let collapse_report = self.collapse_progress_reporter.get_collapse_report(&self.collapse_limiter);
+        (collapsed_wave_function_result, collapse_report)
+    }
+    fn set_collapse_options(&mut self, collapse_options: CollapseOptions) {
+        self.collapse_limiter = CollapseLimiter::new(collapse_options);
+    }
+    fn set_progress_callback(&mut self, node_assignments_per_report: u64, progress_callback: CollapseProgressCallback<'a>) {
+        self.collapse_progress_reporter.set_progress_callback(node_assignments_per_report, progress_callback);
+    }
+    fn set_global_constraints(&mut self, global_constraints: Vec<CollapsableGlobalConstraint<'a, TNodeState>>) {
+        self.global_constraints = global_constraints;
+    }
+    fn snapshot(&self) -> Result<CollapseSnapshot<TNodeState>, String> {
+        Err(String::from("The accommodating collapsable wave function does not support snapshots."))
+    }
+    fn restore(&mut self, _collapse_snapshot: &CollapseSnapshot<TNodeState>) -> Result<(), String> {
+        Err(String::from("The accommodating collapsable wave function does not support snapshots."))
+    }
+    fn get_collapsed_node_states(&self) -> Vec<CollapsedNodeState<TNodeState>> {
+        if let Some(sequential_collapsable_wave_function) = self.sequential_collapsable_wave_function.as_ref() {
+            return sequential_collapsable_wave_function.get_collapsed_node_states();
+        }
+        // every node is in a node state while accommodating, including the nodes still in conflict
+        self.collapsable_nodes
+            .iter()
+            .map(|wrapped_collapsable_node| wrapped_collapsable_node.borrow().get_collapsed_node_state())
+            .filter(|collapsed_node_state| collapsed_node_state.node_state_id.is_some())
+            .collect()
+    }
+    fn is_aborted(&self) -> bool {
+        self.collapse_limiter.is_exceeded() || self.collapse_progress_reporter.is_aborted()
+    }
+}
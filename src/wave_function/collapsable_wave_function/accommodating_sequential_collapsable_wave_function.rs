@@ -1,10 +1,9 @@
 use std::{rc::Rc, cell::RefCell, collections::{HashMap, HashSet}, marker::PhantomData};
 use std::hash::Hash;
-use bitvec::vec::BitVec;
 use crate::wave_function::indexed_view::IndexedViewMaskState;
-use super::collapsable_wave_function::{CollapsableNode, CollapsedNodeState, CollapsedWaveFunction, CollapsableWaveFunction};
+use super::collapsable_wave_function::{CollapsableGlobalConstraint, CollapsableNode, CollapsedNodeState, CollapsedWaveFunction, CollapsableWaveFunction, CollapseLimiter, CollapseOptions, CollapseProgressCallback, CollapseProgressReporter, CollapseReport, CollapseSnapshot};
 
-pub struct AccommodatingSequentialCollapsableWaveFunction<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> {
+pub struct AccommodatingSequentialCollapsableWaveFunction<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
     collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>>,
     collapsable_node_per_id: HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>,
     spread_node_ids: Vec<&'a str>,
@@ -20,11 +19,15 @@ pub struct AccommodatingSequentialCollapsableWaveFunction<'a, TNodeState: Eq + H
     current_neighbor_node_ids_length: usize,
     is_current_neighbor_node_cycle_required: bool,
     is_current_node_neighbors_collapse_possible: bool,
+    random_seed: u64,
     random_instance: Rc<RefCell<fastrand::Rng>>,
+    collapse_limiter: CollapseLimiter,
+    collapse_progress_reporter: CollapseProgressReporter<'a>,
+    global_constraints: Vec<CollapsableGlobalConstraint<'a, TNodeState>>,
     node_state_type: PhantomData<TNodeState>
 }
 
-impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingSequentialCollapsableWaveFunction<'a, TNodeState> {
+impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> AccommodatingSequentialCollapsableWaveFunction<'a, TNodeState> {
     fn initialize_nodes(&mut self) -> Result<Vec<CollapsedNodeState<TNodeState>>, String> {
 
         // initialize each collapsable node to its first (random) state, storing them for the return
@@ -40,10 +43,7 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingSeq
             
             self.spread_node_ids.push(collapsable_node.id);
             let node_state = collapsable_node.node_state_indexed_view.get().unwrap();
-            let collapsed_node_state: CollapsedNodeState<TNodeState> = CollapsedNodeState {
-                node_id: String::from(collapsable_node.id),
-                node_state_id: Some((*node_state).clone())
-            };
+            let collapsed_node_state: CollapsedNodeState<TNodeState> = collapsable_node.get_collapsed_node_state();
             debug!("node {:?} is currently in state {:?}", collapsable_node.id, node_state);
             initial_node_states.push(collapsed_node_state);
         }
@@ -52,20 +52,14 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingSeq
 
         for wrapped_collapsable_node in self.collapsable_nodes.iter() {
             let collapsable_node = wrapped_collapsable_node.borrow();
-            let node_state = collapsable_node.node_state_indexed_view.get().unwrap();
             let neighbor_node_ids: &Vec<&str> = &collapsable_node.neighbor_node_ids;
-            let mask_per_neighbor_per_state: &HashMap<&TNodeState, HashMap<&str, BitVec>> = &collapsable_node.mask_per_neighbor_per_state;
-            if let Some(mask_per_neighbor) = mask_per_neighbor_per_state.get(node_state) {
-                for neighbor_node_id in neighbor_node_ids.iter() {
-                    if mask_per_neighbor.contains_key(neighbor_node_id) {
-                        let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
-                        let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
-                        //debug!("looking for mask from parent {:?} to child {:?}.", collapsable_node.id, neighbor_node_id);
-                        //debug!("mask_per_neighbor: {:?}", mask_per_neighbor);
-                        let mask = mask_per_neighbor.get(neighbor_node_id).unwrap();
-                        neighbor_collapsable_node.add_mask(mask);
-                        debug!("adding mask to {:?} from {:?} when in initialize_nodes", neighbor_node_id, collapsable_node.id);
-                    }
+            for neighbor_node_id in neighbor_node_ids.iter() {
+                if let Some(mask) = collapsable_node.get_current_mask(neighbor_node_id) {
+                    let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
+                    let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
+                    //debug!("looking for mask from parent {:?} to child {:?}.", collapsable_node.id, neighbor_node_id);
+                    neighbor_collapsable_node.add_mask(mask);
+                    debug!("adding mask to {:?} from {:?} when in initialize_nodes", neighbor_node_id, collapsable_node.id);
                 }
             }
         }
@@ -206,16 +200,12 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingSeq
             self.current_neighbor_node_ids.dedup();
             debug!("caching current neighbor nodes: {:?}", self.current_neighbor_node_ids);
 
-            let current_collapsable_node_state = current_collapsable_node.node_state_indexed_view.get().unwrap();
-            if current_collapsable_node.mask_per_neighbor_per_state.contains_key(current_collapsable_node_state) {
-                let mask_per_neighbor = current_collapsable_node.mask_per_neighbor_per_state.get(current_collapsable_node_state).unwrap();
-                for neighbor_node_id in current_collapsable_node.neighbor_node_ids.iter() {
-                    if mask_per_neighbor.contains_key(neighbor_node_id) {
-                        let mask = mask_per_neighbor.get(neighbor_node_id).unwrap();
-                        let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
-                        let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
-                        neighbor_collapsable_node.subtract_mask(mask);
-                    }
+            let current_collapsable_node_state_index = current_collapsable_node.node_state_indexed_view.get_node_state_index().unwrap();
+            for neighbor_node_id in current_collapsable_node.neighbor_node_ids.iter() {
+                if let Some(mask) = current_collapsable_node.get_mask_at_index(current_collapsable_node_state_index, neighbor_node_id) {
+                    let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
+                    let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
+                    neighbor_collapsable_node.subtract_mask(mask);
                 }
             }
 
@@ -232,15 +222,11 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingSeq
                 
                 self.original_node_state_per_node_id.insert(neighbor_node_id, neighbor_collapsable_node_state);
 
-                if neighbor_collapsable_node.mask_per_neighbor_per_state.contains_key(neighbor_collapsable_node_state) {
-                    let mask_per_neighbor = neighbor_collapsable_node.mask_per_neighbor_per_state.get(neighbor_collapsable_node_state).unwrap();
-                    for great_neighbor_node_id in neighbor_collapsable_node.neighbor_node_ids.iter() {
-                        if mask_per_neighbor.contains_key(great_neighbor_node_id) {
-                            let mask = mask_per_neighbor.get(great_neighbor_node_id).unwrap();
-                            let wrapped_great_neighbor_collapsable_node = self.collapsable_node_per_id.get(great_neighbor_node_id).unwrap();
-                            let mut great_neighbor_collapsable_node = wrapped_great_neighbor_collapsable_node.borrow_mut();
-                            great_neighbor_collapsable_node.subtract_mask(mask);
-                        }
+                for great_neighbor_node_id in neighbor_collapsable_node.neighbor_node_ids.iter() {
+                    if let Some(mask) = neighbor_collapsable_node.get_current_mask(great_neighbor_node_id) {
+                        let wrapped_great_neighbor_collapsable_node = self.collapsable_node_per_id.get(great_neighbor_node_id).unwrap();
+                        let mut great_neighbor_collapsable_node = wrapped_great_neighbor_collapsable_node.borrow_mut();
+                        great_neighbor_collapsable_node.subtract_mask(mask);
                     }
                 }
             }
@@ -265,16 +251,12 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingSeq
         {
             let wrapped_current_collapsable_node = self.collapsable_node_per_id.get(current_collapsable_node_id).unwrap();
             let current_collapsable_node = wrapped_current_collapsable_node.borrow();
-            let current_collapsable_node_state = current_collapsable_node.node_state_indexed_view.get().unwrap();
-            if current_collapsable_node.mask_per_neighbor_per_state.contains_key(current_collapsable_node_state) {
-                let mask_per_neighbor = current_collapsable_node.mask_per_neighbor_per_state.get(current_collapsable_node_state).unwrap();
-                for neighbor_node_id in current_collapsable_node.neighbor_node_ids.iter() {
-                    if mask_per_neighbor.contains_key(neighbor_node_id) {
-                        let mask = mask_per_neighbor.get(neighbor_node_id).unwrap();
-                        let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
-                        let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
-                        neighbor_collapsable_node.add_mask(mask);
-                    }
+            let current_collapsable_node_state_index = current_collapsable_node.node_state_indexed_view.get_node_state_index().unwrap();
+            for neighbor_node_id in current_collapsable_node.neighbor_node_ids.iter() {
+                if let Some(mask) = current_collapsable_node.get_mask_at_index(current_collapsable_node_state_index, neighbor_node_id) {
+                    let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
+                    let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
+                    neighbor_collapsable_node.add_mask(mask);
                 }
             }
 
@@ -375,57 +357,45 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingSeq
 
         debug!("neighbor node trying to cycle: {:?}", neighbor_collapsable_node.id);
         let is_successful_neighbor_nove_next_cycle = neighbor_collapsable_node.node_state_indexed_view.try_move_next_cycle(original_neighbor_node_state);
-        let neighbor_collapsable_node_state = neighbor_collapsable_node.node_state_indexed_view.get().unwrap();
 
-        changed_neighbor_node_states.push(CollapsedNodeState {
-            node_id: String::from(neighbor_node_id),
-            node_state_id: Some((*neighbor_collapsable_node_state).clone())
-        });
+        changed_neighbor_node_states.push(neighbor_collapsable_node.get_collapsed_node_state());
         
         if is_successful_neighbor_nove_next_cycle {
             debug!("successfully move next cycled");
-            let neighbor_node_state = neighbor_collapsable_node.node_state_indexed_view.get().unwrap();
-            if neighbor_collapsable_node.mask_per_neighbor_per_state.contains_key(neighbor_collapsable_node_state) {
-                let mask_per_neighbor = neighbor_collapsable_node.mask_per_neighbor_per_state.get(neighbor_node_state).unwrap();
-                let great_neighbor_node_ids = self.great_neighbor_node_ids_per_neighbor_node_id.get(neighbor_node_id).unwrap();
-                let mut masked_great_neighbor_node_ids: Vec<&str> = Vec::new();
-                let mut is_rollback_required: bool = false;
+            let neighbor_node_state_index = neighbor_collapsable_node.node_state_indexed_view.get_node_state_index().unwrap();
+            let great_neighbor_node_ids = self.great_neighbor_node_ids_per_neighbor_node_id.get(neighbor_node_id).unwrap();
+            let mut masked_great_neighbor_node_ids: Vec<&str> = Vec::new();
+            let mut is_rollback_required: bool = false;
 
-                for great_neighbor_node_id in great_neighbor_node_ids.iter() {
-                    if mask_per_neighbor.contains_key(great_neighbor_node_id) {
-                        let mask = mask_per_neighbor.get(great_neighbor_node_id).unwrap();
-                        let wrapped_great_neighbor_collapsable_node = self.collapsable_node_per_id.get(great_neighbor_node_id).unwrap();
-                        let mut great_neighbor_collapsable_node = wrapped_great_neighbor_collapsable_node.borrow_mut();
+            for great_neighbor_node_id in great_neighbor_node_ids.iter() {
+                if let Some(mask) = neighbor_collapsable_node.get_mask_at_index(neighbor_node_state_index, great_neighbor_node_id) {
+                    let wrapped_great_neighbor_collapsable_node = self.collapsable_node_per_id.get(great_neighbor_node_id).unwrap();
+                    let mut great_neighbor_collapsable_node = wrapped_great_neighbor_collapsable_node.borrow_mut();
 
-                        if !great_neighbor_collapsable_node.node_state_indexed_view.is_mask_restrictive_to_current_state(mask) {
+                    if !great_neighbor_collapsable_node.node_state_indexed_view.is_mask_restrictive_to_current_state(mask) {
 
-                            great_neighbor_collapsable_node.add_mask(mask);
-                            masked_great_neighbor_node_ids.push(great_neighbor_node_id);
-                        }
-                        else {
-                            is_rollback_required = true;
-                            break;
-                        }
+                        great_neighbor_collapsable_node.add_mask(mask);
+                        masked_great_neighbor_node_ids.push(great_neighbor_node_id);
                     }
-                }
-
-                if is_rollback_required {
-                    debug!("rollback required after over-restricting neighbors");
-                    for great_neighbor_node_id in masked_great_neighbor_node_ids.iter() {
-                        let mask = mask_per_neighbor.get(great_neighbor_node_id).unwrap();
-                        let wrapped_great_neighbor_collapsable_node = self.collapsable_node_per_id.get(great_neighbor_node_id).unwrap();
-                        let mut great_neighbor_collapsable_node = wrapped_great_neighbor_collapsable_node.borrow_mut();
-                        great_neighbor_collapsable_node.subtract_mask(mask);
+                    else {
+                        is_rollback_required = true;
+                        break;
                     }
-                    self.is_current_neighbor_node_cycle_required = true;
                 }
-                else {
-                    debug!("maintaining state was successful, move to next neighbor (1)");
-                    self.current_neighbor_node_ids_index += 1;
+            }
+
+            if is_rollback_required {
+                debug!("rollback required after over-restricting neighbors");
+                for great_neighbor_node_id in masked_great_neighbor_node_ids.iter() {
+                    let mask = neighbor_collapsable_node.get_mask_at_index(neighbor_node_state_index, great_neighbor_node_id).unwrap();
+                    let wrapped_great_neighbor_collapsable_node = self.collapsable_node_per_id.get(great_neighbor_node_id).unwrap();
+                    let mut great_neighbor_collapsable_node = wrapped_great_neighbor_collapsable_node.borrow_mut();
+                    great_neighbor_collapsable_node.subtract_mask(mask);
                 }
+                self.is_current_neighbor_node_cycle_required = true;
             }
             else {
-                debug!("maintaining state was successful, move to next neighbor (2)");
+                debug!("maintaining state was successful, move to next neighbor");
                 self.current_neighbor_node_ids_index += 1;
             }
         }
@@ -436,6 +406,8 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingSeq
             }
             else {
                 debug!("move back a neighbor");
+                self.collapse_limiter.backtrack();
+                self.collapse_progress_reporter.backtrack();
                 self.current_neighbor_node_ids_index -= 1;
                 self.is_current_neighbor_node_cycle_required = true;
 
@@ -443,21 +415,17 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingSeq
                 let previous_neighbor_node_id = self.current_neighbor_node_ids[self.current_neighbor_node_ids_index];
                 let wrapped_previous_neighbor_collapsable_node = self.collapsable_node_per_id.get(previous_neighbor_node_id).unwrap();
                 let previous_neighbor_collapsable_node = wrapped_previous_neighbor_collapsable_node.borrow();
-                let previous_neighbor_node_state = previous_neighbor_collapsable_node.node_state_indexed_view.get().unwrap();
-                if previous_neighbor_collapsable_node.mask_per_neighbor_per_state.contains_key(previous_neighbor_node_state) {
-                    let mask_per_neighbor = previous_neighbor_collapsable_node.mask_per_neighbor_per_state.get(previous_neighbor_node_state).unwrap();
-                    let great_neighbor_node_ids = self.great_neighbor_node_ids_per_neighbor_node_id.get(previous_neighbor_node_id).unwrap();
-                    for great_neighbor_node_id in great_neighbor_node_ids.iter() {
-                        if mask_per_neighbor.contains_key(great_neighbor_node_id) {
-                            let mask = mask_per_neighbor.get(great_neighbor_node_id).unwrap();
-                            if *great_neighbor_node_id == neighbor_node_id {
-                                neighbor_collapsable_node.subtract_mask(mask);
-                            }
-                            else {
-                                let wrapped_great_neighbor_collapsable_node = self.collapsable_node_per_id.get(great_neighbor_node_id).unwrap();
-                                let mut great_neighbor_collapsable_node = wrapped_great_neighbor_collapsable_node.borrow_mut();
-                                great_neighbor_collapsable_node.subtract_mask(mask);
-                            }
+                let previous_neighbor_node_state_index = previous_neighbor_collapsable_node.node_state_indexed_view.get_node_state_index().unwrap();
+                let great_neighbor_node_ids = self.great_neighbor_node_ids_per_neighbor_node_id.get(previous_neighbor_node_id).unwrap();
+                for great_neighbor_node_id in great_neighbor_node_ids.iter() {
+                    if let Some(mask) = previous_neighbor_collapsable_node.get_mask_at_index(previous_neighbor_node_state_index, great_neighbor_node_id) {
+                        if *great_neighbor_node_id == neighbor_node_id {
+                            neighbor_collapsable_node.subtract_mask(mask);
+                        }
+                        else {
+                            let wrapped_great_neighbor_collapsable_node = self.collapsable_node_per_id.get(great_neighbor_node_id).unwrap();
+                            let mut great_neighbor_collapsable_node = wrapped_great_neighbor_collapsable_node.borrow_mut();
+                            great_neighbor_collapsable_node.subtract_mask(mask);
                         }
                     }
                 }
@@ -477,48 +445,40 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingSeq
         let neighbor_node_id = self.current_neighbor_node_ids[self.current_neighbor_node_ids_index];
         let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
         let neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow();
-        let neighbor_node_state = neighbor_collapsable_node.node_state_indexed_view.get().unwrap();
-
-        if neighbor_collapsable_node.mask_per_neighbor_per_state.contains_key(neighbor_node_state) {
-            let mask_per_neighbor = neighbor_collapsable_node.mask_per_neighbor_per_state.get(neighbor_node_state).unwrap();
-            let great_neighbor_node_ids = self.great_neighbor_node_ids_per_neighbor_node_id.get(neighbor_node_id).unwrap();
-            let mut masked_great_neighbor_node_ids: Vec<&str> = Vec::new();
-            let mut is_rollback_required: bool = false;
-            
-            for great_neighbor_node_id in great_neighbor_node_ids.iter() {
-                if mask_per_neighbor.contains_key(great_neighbor_node_id) {
-                    let mask = mask_per_neighbor.get(great_neighbor_node_id).unwrap();
-                    let wrapped_great_neighbor_collapsable_node = self.collapsable_node_per_id.get(great_neighbor_node_id).unwrap();
-                    let mut great_neighbor_collapsable_node = wrapped_great_neighbor_collapsable_node.borrow_mut();
-                    if !great_neighbor_collapsable_node.node_state_indexed_view.is_mask_restrictive_to_current_state(mask) {
+        let neighbor_node_state_index = neighbor_collapsable_node.node_state_indexed_view.get_node_state_index().unwrap();
 
-                        great_neighbor_collapsable_node.add_mask(mask);
-                        masked_great_neighbor_node_ids.push(great_neighbor_node_id);
-                    }
-                    else {
-                        is_rollback_required = true;
-                        break;
-                    }
+        let great_neighbor_node_ids = self.great_neighbor_node_ids_per_neighbor_node_id.get(neighbor_node_id).unwrap();
+        let mut masked_great_neighbor_node_ids: Vec<&str> = Vec::new();
+        let mut is_rollback_required: bool = false;
+        
+        for great_neighbor_node_id in great_neighbor_node_ids.iter() {
+            if let Some(mask) = neighbor_collapsable_node.get_mask_at_index(neighbor_node_state_index, great_neighbor_node_id) {
+                let wrapped_great_neighbor_collapsable_node = self.collapsable_node_per_id.get(great_neighbor_node_id).unwrap();
+                let mut great_neighbor_collapsable_node = wrapped_great_neighbor_collapsable_node.borrow_mut();
+                if !great_neighbor_collapsable_node.node_state_indexed_view.is_mask_restrictive_to_current_state(mask) {
+
+                    great_neighbor_collapsable_node.add_mask(mask);
+                    masked_great_neighbor_node_ids.push(great_neighbor_node_id);
                 }
-            }
-
-            if is_rollback_required {
-                debug!("rollback required after over-restricting neighbors");
-                for great_neighbor_node_id in masked_great_neighbor_node_ids.iter() {
-                    let mask = mask_per_neighbor.get(great_neighbor_node_id).unwrap();
-                    let wrapped_great_neighbor_collapsable_node = self.collapsable_node_per_id.get(great_neighbor_node_id).unwrap();
-                    let mut great_neighbor_collapsable_node = wrapped_great_neighbor_collapsable_node.borrow_mut();
-                    great_neighbor_collapsable_node.subtract_mask(mask);
+                else {
+                    is_rollback_required = true;
+                    break;
                 }
-                self.is_current_neighbor_node_cycle_required = true;
             }
-            else {
-                debug!("maintaining state was successful, move to next neighbor (1)");
-                self.current_neighbor_node_ids_index += 1;
+        }
+
+        if is_rollback_required {
+            debug!("rollback required after over-restricting neighbors");
+            for great_neighbor_node_id in masked_great_neighbor_node_ids.iter() {
+                let mask = neighbor_collapsable_node.get_mask_at_index(neighbor_node_state_index, great_neighbor_node_id).unwrap();
+                let wrapped_great_neighbor_collapsable_node = self.collapsable_node_per_id.get(great_neighbor_node_id).unwrap();
+                let mut great_neighbor_collapsable_node = wrapped_great_neighbor_collapsable_node.borrow_mut();
+                great_neighbor_collapsable_node.subtract_mask(mask);
             }
+            self.is_current_neighbor_node_cycle_required = true;
         }
         else {
-            debug!("maintaining state was successful, move to next neighbor (2)");
+            debug!("maintaining state was successful, move to next neighbor");
             self.current_neighbor_node_ids_index += 1;
         }
     }
@@ -543,18 +503,13 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingSeq
             for neighbor_node_id in self.current_neighbor_node_ids.iter() {
                 let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
                 let neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow();
-                let neighbor_node_state = neighbor_collapsable_node.node_state_indexed_view.get().unwrap();
-                if neighbor_collapsable_node.mask_per_neighbor_per_state.contains_key(neighbor_node_state) {
-                    let mask_per_neighbor = neighbor_collapsable_node.mask_per_neighbor_per_state.get(neighbor_node_state).unwrap();
-
-                    let nongreat_neighbor_node_ids = self.nongreat_neighbor_node_ids_per_neighbor_node_id.get(neighbor_node_id).unwrap();
-                    for nongreat_neighbor_node_id in nongreat_neighbor_node_ids.iter() {
-                        if mask_per_neighbor.contains_key(nongreat_neighbor_node_id) {
-                            let mask = mask_per_neighbor.get(nongreat_neighbor_node_id).unwrap();
-                            let wrapped_nongreat_neighbor_collapsable_node = self.collapsable_node_per_id.get(nongreat_neighbor_node_id).unwrap();
-                            let mut nongreat_collapsable_node = wrapped_nongreat_neighbor_collapsable_node.borrow_mut();
-                            nongreat_collapsable_node.add_mask(mask);
-                        }
+                let neighbor_node_state_index = neighbor_collapsable_node.node_state_indexed_view.get_node_state_index().unwrap();
+                let nongreat_neighbor_node_ids = self.nongreat_neighbor_node_ids_per_neighbor_node_id.get(neighbor_node_id).unwrap();
+                for nongreat_neighbor_node_id in nongreat_neighbor_node_ids.iter() {
+                    if let Some(mask) = neighbor_collapsable_node.get_mask_at_index(neighbor_node_state_index, nongreat_neighbor_node_id) {
+                        let wrapped_nongreat_neighbor_collapsable_node = self.collapsable_node_per_id.get(nongreat_neighbor_node_id).unwrap();
+                        let mut nongreat_collapsable_node = wrapped_nongreat_neighbor_collapsable_node.borrow_mut();
+                        nongreat_collapsable_node.add_mask(mask);
                     }
                 }
             }
@@ -563,17 +518,12 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingSeq
             for neighbor_node_id in self.current_neighbor_node_ids.iter() {
                 let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
                 let neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow();
-                let neighbor_node_state = neighbor_collapsable_node.node_state_indexed_view.get().unwrap();
-                if neighbor_collapsable_node.mask_per_neighbor_per_state.contains_key(neighbor_node_state) {
-                    let mask_per_neighbor = neighbor_collapsable_node.mask_per_neighbor_per_state.get(neighbor_node_state).unwrap();
-
-                    for all_great_neighbor_node_id in neighbor_collapsable_node.neighbor_node_ids.iter() {
-                        if mask_per_neighbor.contains_key(all_great_neighbor_node_id) {
-                            let mask = mask_per_neighbor.get(all_great_neighbor_node_id).unwrap();
-                            let wrapped_nongreat_neighbor_collapsable_node = self.collapsable_node_per_id.get(all_great_neighbor_node_id).unwrap();
-                            let mut nongreat_collapsable_node = wrapped_nongreat_neighbor_collapsable_node.borrow_mut();
-                            nongreat_collapsable_node.add_mask(mask);
-                        }
+                let neighbor_node_state_index = neighbor_collapsable_node.node_state_indexed_view.get_node_state_index().unwrap();
+                for all_great_neighbor_node_id in neighbor_collapsable_node.neighbor_node_ids.iter() {
+                    if let Some(mask) = neighbor_collapsable_node.get_mask_at_index(neighbor_node_state_index, all_great_neighbor_node_id) {
+                        let wrapped_nongreat_neighbor_collapsable_node = self.collapsable_node_per_id.get(all_great_neighbor_node_id).unwrap();
+                        let mut nongreat_collapsable_node = wrapped_nongreat_neighbor_collapsable_node.borrow_mut();
+                        nongreat_collapsable_node.add_mask(mask);
                     }
                 }
             }
@@ -597,23 +547,53 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> AccommodatingSeq
 
         self.spread_node_ids_index += 1;
     }
+    fn get_collapsed_nodes_total(&self) -> usize {
+        self.collapsable_nodes
+            .iter()
+            .filter(|wrapped_collapsable_node| !wrapped_collapsable_node.borrow().node_state_indexed_view.is_current_state_restricted())
+            .count()
+    }
+    fn try_report_node_assignments(&mut self, collapsed_node_states: &[CollapsedNodeState<TNodeState>]) -> Result<(), String> {
+        for collapsed_node_state in collapsed_node_states.iter() {
+            if self.collapse_progress_reporter.assign_node() {
+                let collapsed_nodes_total = self.get_collapsed_nodes_total();
+                self.collapse_progress_reporter.try_report(&collapsed_node_state.node_id, collapsed_nodes_total, self.collapsable_nodes.len())?;
+            }
+        }
+        Ok(())
+    }
+    // accommodating does not consider the global constraints, so they are only verified once every node is unrestricted
+    fn try_verify_global_constraints(&self) -> Result<(), String> {
+        if self.global_constraints.iter().all(|global_constraint| global_constraint.is_satisfiable(&self.collapsable_node_per_id)) {
+            Ok(())
+        }
+        else {
+            Err(String::from("Cannot collapse wave function without violating a global constraint."))
+        }
+    }
     fn get_collapsed_wave_function(&self) -> CollapsedWaveFunction<TNodeState> {
         let mut node_state_per_node_id: HashMap<String, TNodeState> = HashMap::new();
+        let mut node_metadata_per_node: HashMap<String, serde_json::Value> = HashMap::new();
         for wrapped_collapsable_node in self.collapsable_nodes.iter() {
             let collapsable_node = wrapped_collapsable_node.borrow();
             let node_state: TNodeState = (*collapsable_node.node_state_indexed_view.get().unwrap()).clone();
             let node_id: String = String::from(collapsable_node.id);
             debug!("established node {node_id} in state {:?}.", node_state);
+            if let Some(metadata) = collapsable_node.metadata {
+                node_metadata_per_node.insert(node_id.clone(), metadata.clone());
+            }
             node_state_per_node_id.insert(node_id, node_state);
         }
         CollapsedWaveFunction {
-            node_state_per_node_id
+            node_state_per_node_id,
+            random_seed: self.random_seed,
+            node_metadata_per_node
         }
     }
 }
 
-impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveFunction<'a, TNodeState> for AccommodatingSequentialCollapsableWaveFunction<'a, TNodeState> {
-    fn new(collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, collapsable_node_per_id: HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, random_instance: Rc<RefCell<fastrand::Rng>>) -> Self {
+impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> CollapsableWaveFunction<'a, TNodeState> for AccommodatingSequentialCollapsableWaveFunction<'a, TNodeState> {
+    fn new(collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, collapsable_node_per_id: HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, random_seed: u64, random_instance: Rc<RefCell<fastrand::Rng>>) -> Self {
         AccommodatingSequentialCollapsableWaveFunction {
             collapsable_nodes,
             collapsable_node_per_id,
@@ -630,36 +610,54 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
             current_neighbor_node_ids_length: 0,
             is_current_neighbor_node_cycle_required: false,
             is_current_node_neighbors_collapse_possible: true,
+            random_seed,
             random_instance,
+            collapse_limiter: CollapseLimiter::new(CollapseOptions::default()),
+            collapse_progress_reporter: CollapseProgressReporter::new(),
+            global_constraints: Vec::new(),
             node_state_type: PhantomData
         }
     }
-    fn collapse(&'a mut self) -> Result<CollapsedWaveFunction<TNodeState>, String> {
+    fn collapse(&mut self) -> Result<CollapsedWaveFunction<TNodeState>, String> {
       
         let mut iterations_total: u32 = 0;
 
         debug!("initializing node states");
+        self.collapse_limiter.start();
+        self.collapse_progress_reporter.start();
         let initialized_node_states_result = self.initialize_nodes();
         if initialized_node_states_result.is_err() {
             return Err(initialized_node_states_result.err().unwrap());
         }
+        self.try_report_node_assignments(&initialized_node_states_result.unwrap())?;
 
         debug!("beginning main while loop");
         while !self.is_fully_collapsed() {
             debug!("preparing nodes for iteration");
             self.prepare_nodes_for_iteration();
+            if self.collapse_progress_reporter.begin_pass() {
+                self.collapse_limiter.restart();
+            }
             debug!("while not done spreading nodes");
             while !self.is_done_spreading_nodes() {
+                if self.collapse_limiter.try_iterate() {
+                    return Err(self.collapse_limiter.get_error_message(self.get_collapsed_nodes_total(), self.collapsable_nodes.len()));
+                }
                 debug!("checking if current node is in conflict");
                 if self.is_current_node_in_conflict() {
                     debug!("preparing current node neighbors");
                     self.prepare_current_node_neighbors();
                     debug!("while current node neighbors not yet collapsed");
                     while !self.is_current_node_neighbors_collapsed() {
+                        if self.collapse_limiter.try_iterate() {
+                            return Err(self.collapse_limiter.get_error_message(self.get_collapsed_nodes_total(), self.collapsable_nodes.len()));
+                        }
                         debug!("checking if current node neighbor state change is required");
                         if self.is_current_node_neighbor_state_change_required() {
                             debug!("changing state of current node neighbor");
-                            self.change_state_of_current_node_neighbor();
+                            let spreading_neighbor_node_states = self.change_state_of_current_node_neighbor();
+                            self.collapse_progress_reporter.recollapse_nodes(spreading_neighbor_node_states.len());
+                            self.try_report_node_assignments(&spreading_neighbor_node_states)?;
                         }
                         else {
                             debug!("allowing current node neighbor to maintain its state");
@@ -677,9 +675,10 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
 
         debug!("fully collapsed after {:?} iterations", iterations_total);
 
+        self.try_verify_global_constraints()?;
         Ok(self.get_collapsed_wave_function())
     }
-    fn collapse_into_steps(&'a mut self) -> Result<Vec<CollapsedNodeState<TNodeState>>, String> {
+    fn collapse_into_steps(&mut self) -> Result<Vec<CollapsedNodeState<TNodeState>>, String> {
 
         // initialize each collapsable node to its first (random) state
         // alter masks for every collapsable node to its neighbors
@@ -740,21 +739,35 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
 
         let mut collapsed_node_states: Vec<CollapsedNodeState<TNodeState>> = Vec::new();
 
+        self.collapse_limiter.start();
+        self.collapse_progress_reporter.start();
         let initialized_node_states_result = self.initialize_nodes();
         if initialized_node_states_result.is_err() {
             return Err(initialized_node_states_result.err().unwrap());
         }
         let initialized_node_states = initialized_node_states_result.unwrap();
+        self.try_report_node_assignments(&initialized_node_states)?;
         collapsed_node_states.extend(initialized_node_states);
 
         while !self.is_fully_collapsed() {
             self.prepare_nodes_for_iteration();
+            if self.collapse_progress_reporter.begin_pass() {
+                self.collapse_limiter.restart();
+            }
             while !self.is_done_spreading_nodes() {
+                if self.collapse_limiter.try_iterate() {
+                    return Err(self.collapse_limiter.get_error_message(self.get_collapsed_nodes_total(), self.collapsable_nodes.len()));
+                }
                 if self.is_current_node_in_conflict() {
                     self.prepare_current_node_neighbors();
                     while !self.is_current_node_neighbors_collapsed() {
+                        if self.collapse_limiter.try_iterate() {
+                            return Err(self.collapse_limiter.get_error_message(self.get_collapsed_nodes_total(), self.collapsable_nodes.len()));
+                        }
                         if self.is_current_node_neighbor_state_change_required() {
                             let spreading_neighbor_node_state = self.change_state_of_current_node_neighbor();
+                            self.collapse_progress_reporter.recollapse_nodes(spreading_neighbor_node_state.len());
+                            self.try_report_node_assignments(&spreading_neighbor_node_state)?;
                             collapsed_node_states.extend(spreading_neighbor_node_state);
                         }
                         else {
@@ -767,6 +780,40 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
             }
         }
 
+        self.try_verify_global_constraints()?;
         Ok(collapsed_node_states)
     }
-}
\ No newline at end of file
+    fn get_random_seed(&self) -> u64 {
+        self.random_seed
+    }
+    fn collapse_with_report(&mut self) -> (Result<CollapsedWaveFunction<TNodeState>, String>, CollapseReport) {
+        let collapsed_wave_function_result = self.collapse();
+        (collapsed_wave_function_result, self.collapse_progress_reporter.get_collapse_report(&self.collapse_limiter))
+    }
+    fn set_collapse_options(&mut self, collapse_options: CollapseOptions) {
+        self.collapse_limiter = CollapseLimiter::new(collapse_options);
+    }
+    fn set_progress_callback(&mut self, node_assignments_per_report: u64, progress_callback: CollapseProgressCallback<'a>) {
+        self.collapse_progress_reporter.set_progress_callback(node_assignments_per_report, progress_callback);
+    }
+    fn set_global_constraints(&mut self, global_constraints: Vec<CollapsableGlobalConstraint<'a, TNodeState>>) {
+        self.global_constraints = global_constraints;
+    }
+    fn snapshot(&self) -> Result<CollapseSnapshot<TNodeState>, String> {
+        Err(String::from("The accommodating sequential collapsable wave function does not support snapshots."))
+    }
+    fn restore(&mut self, _collapse_snapshot: &CollapseSnapshot<TNodeState>) -> Result<(), String> {
+        Err(String::from("The accommodating sequential collapsable wave function does not support snapshots."))
+    }
+    fn get_collapsed_node_states(&self) -> Vec<CollapsedNodeState<TNodeState>> {
+        // every node is in a node state while accommodating, including the nodes still in conflict
+        self.collapsable_nodes
+            .iter()
+            .map(|wrapped_collapsable_node| wrapped_collapsable_node.borrow().get_collapsed_node_state())
+            .filter(|collapsed_node_state| collapsed_node_state.node_state_id.is_some())
+            .collect()
+    }
+    fn is_aborted(&self) -> bool {
+        self.collapse_limiter.is_exceeded() || self.collapse_progress_reporter.is_aborted()
+    }
+}
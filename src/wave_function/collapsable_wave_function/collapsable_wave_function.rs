@@ -1,37 +1,642 @@
 use std::fmt::Display;
-use std::{collections::HashMap, marker::PhantomData};
+use std::ops::ControlFlow;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use std::{collections::{BTreeMap, HashMap}, marker::PhantomData};
 use std::rc::Rc;
 use std::cell::RefCell;
+use bincode::Options;
 use bitvec::vec::BitVec;
 use fastrand::Rng;
-use serde::{Serialize, Deserialize};
+use serde::{Serialize, Serializer, Deserialize, Deserializer, de::DeserializeOwned};
 use std::hash::Hash;
 use crate::wave_function::indexed_view::IndexedView;
 
 /// This trait defines the relationship between collapsable nodes and a collapsed state.
-pub trait CollapsableWaveFunction<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> {
-    fn new(collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, collapsable_node_per_id: HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, random_instance: Rc<RefCell<fastrand::Rng>>) -> Self where Self: Sized;
-    fn collapse_into_steps(&'a mut self) -> Result<Vec<CollapsedNodeState<TNodeState>>, String>;
-    fn collapse(&'a mut self) -> Result<CollapsedWaveFunction<TNodeState>, String>;
+pub trait CollapsableWaveFunction<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    fn new(collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, collapsable_node_per_id: HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, random_seed: u64, random_instance: Rc<RefCell<fastrand::Rng>>) -> Self where Self: Sized;
+    /// Each step is a node being set to a node state, or reset to None, such that replaying the steps in order reproduces the collapsed wave function.
+    fn collapse_into_steps(&mut self) -> Result<Vec<CollapsedNodeState<TNodeState>>, String>;
+    /// This is the random seed that the random instance was created from, even if it was chosen because no random seed was provided, so that the collapse can be reproduced.
+    fn get_random_seed(&self) -> u64;
+    fn collapse(&mut self) -> Result<CollapsedWaveFunction<TNodeState>, String>;
+    /// This function collapses just like collapse while also reporting how much work the collapse performed, even if the collapse failed.
+    fn collapse_with_report(&mut self) -> (Result<CollapsedWaveFunction<TNodeState>, String>, CollapseReport);
+    /// This function collapses via collapse_into_steps while describing how each node came to be in its node state, which costs more than collapse since every step is kept.
+    fn collapse_with_metadata(&mut self) -> Result<CollapsedWaveFunctionWithMetadata<TNodeState>, String> {
+        let collapsed_node_states = self.collapse_into_steps()?;
+        CollapsedWaveFunctionWithMetadata::from_steps(&collapsed_node_states, self.get_random_seed())
+    }
+    fn set_collapse_options(&mut self, collapse_options: CollapseOptions);
+    /// The progress callback is called after every node_assignments_per_report node assignments, aborting the collapse if it returns ControlFlow::Break.
+    fn set_progress_callback(&mut self, node_assignments_per_report: u64, progress_callback: CollapseProgressCallback<'a>);
+    fn set_global_constraints(&mut self, global_constraints: Vec<CollapsableGlobalConstraint<'a, TNodeState>>);
+    /// This function captures the progress of a collapse that stopped after exceeding its CollapseOptions so that it can be continued by WaveFunction::resume_collapsable_wave_function.
+    fn snapshot(&self) -> Result<CollapseSnapshot<TNodeState>, String>;
+    /// This function restores the progress captured by snapshot into a collapsable wave function created from the same wave function and random seed.
+    fn restore(&mut self, collapse_snapshot: &CollapseSnapshot<TNodeState>) -> Result<(), String>;
+    /// This function describes the node state of each node that is collapsed so far, which is the partial collapse once a collapse stops early.
+    fn get_collapsed_node_states(&self) -> Vec<CollapsedNodeState<TNodeState>>;
+    /// This function returns true if the last collapse stopped early because it exceeded its CollapseOptions, was cancelled, or was aborted by its progress callback.
+    fn is_aborted(&self) -> bool;
+    /// This function collapses just like collapse while distinguishing a collapse that stopped early from a wave function that cannot be collapsed, where a collapse that stopped early carries the partial collapse.
+    fn collapse_or_abort(&mut self) -> Result<CollapsedWaveFunction<TNodeState>, CollapseError<TNodeState>> {
+        let (collapsed_wave_function_result, collapse_report) = self.collapse_with_report();
+        collapsed_wave_function_result.map_err(|message| {
+            if self.is_aborted() {
+                CollapseError::Aborted {
+                    message,
+                    iterations_completed: collapse_report.iterations_total,
+                    partial: self.get_collapsed_node_states()
+                }
+            }
+            else {
+                CollapseError::Uncollapsable(message)
+            }
+        })
+    }
+}
+
+/// This enum is the reason that collapse_or_abort did not return a collapsed wave function.
+#[derive(Debug, Clone, PartialEq)]
+pub enum CollapseError<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    // the collapse exceeded its CollapseOptions, was cancelled, or was aborted by its progress callback
+    Aborted {
+        message: String,
+        iterations_completed: u64,
+        // the node state of each node that was collapsed when the collapse stopped
+        partial: Vec<CollapsedNodeState<TNodeState>>
+    },
+    // the wave function has no collapsed state that satisfies every constraint
+    Uncollapsable(String)
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> Display for CollapseError<TNodeState> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CollapseError::Aborted { message, .. } => write!(f, "{message}"),
+            CollapseError::Uncollapsable(message) => write!(f, "{message}")
+        }
+    }
+}
+
+/// This struct is the serializable progress of a partially collapsed wave function. Continuing from it produces the same collapsed wave function as collapsing directly from the same random seed.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(bound(serialize = "TNodeState: Serialize", deserialize = "TNodeState: DeserializeOwned"))]
+pub struct CollapseSnapshot<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    pub random_seed: u64,
+    // the state of the random instance if the collapsable wave function draws from it while collapsing
+    pub random_state: Option<u64>,
+    // the node state of each collapsed node in the order that the nodes are collapsed
+    pub collapsed_node_states: Vec<(String, TNodeState)>,
+    // the node state that the current node last tried without success, so that the next node state is tried after it
+    pub current_node_state: Option<(String, TNodeState)>,
+    // the node states that each node can no longer be in, for each node that has any
+    pub excluded_node_states_per_node_id: BTreeMap<String, Vec<TNodeState>>
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> CollapseSnapshot<TNodeState> {
+    /// This function collects the excluded node states of each collapsable node.
+    pub fn get_excluded_node_states_per_node_id(collapsable_nodes: &[Rc<RefCell<CollapsableNode<'_, TNodeState>>>]) -> BTreeMap<String, Vec<TNodeState>> {
+        let mut excluded_node_states_per_node_id: BTreeMap<String, Vec<TNodeState>> = BTreeMap::new();
+        for wrapped_collapsable_node in collapsable_nodes.iter() {
+            let collapsable_node = wrapped_collapsable_node.borrow();
+            let excluded_node_states: Vec<TNodeState> = collapsable_node.node_state_indexed_view.get_restricted_node_states().into_iter().cloned().collect();
+            if !excluded_node_states.is_empty() {
+                excluded_node_states_per_node_id.insert(String::from(collapsable_node.id), excluded_node_states);
+            }
+        }
+        excluded_node_states_per_node_id
+    }
+    /// This function ensures that every node state excluded in this snapshot is also excluded by the restored collapsable nodes, since otherwise the snapshot was not taken from the same wave function.
+    pub fn validate_excluded_node_states(&self, collapsable_nodes: &[Rc<RefCell<CollapsableNode<'_, TNodeState>>>]) -> Result<(), String> {
+        let excluded_node_states_per_node_id = Self::get_excluded_node_states_per_node_id(collapsable_nodes);
+        for (node_id, excluded_node_states) in self.excluded_node_states_per_node_id.iter() {
+            for excluded_node_state in excluded_node_states.iter() {
+                if !excluded_node_states_per_node_id.get(node_id).is_some_and(|restored_excluded_node_states| restored_excluded_node_states.contains(excluded_node_state)) {
+                    return Err(format!("Node {node_id} has node state {excluded_node_state:?} excluded in the snapshot but not after resuming."));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// This struct limits how much work collapsing may perform before giving up. Each iteration is a single step of the collapsing algorithm, including each step of propagating restrictions to neighbors. Each backtrack is a return to a previously collapsed node after reaching a contradiction. Each restart is another pass over every node by an accommodating collapsable wave function after the previous pass left conflicts behind. Setting the cancel token to true from another thread stops the collapse at its next iteration. The lookahead is only honored by the sequential collapsable wave function, including when an accommodating collapsable wave function falls back to it.
+#[derive(Debug, Clone, Default)]
+pub struct CollapseOptions {
+    pub max_iterations: Option<u64>,
+    pub max_duration: Option<Duration>,
+    pub max_backtracks: Option<u64>,
+    pub max_restarts: Option<u64>,
+    pub cancel_token: Option<Arc<AtomicBool>>,
+    pub lookahead: PropagationDepth
+}
+
+/// This enum describes how far the restrictions of each node state that is tried are propagated before moving on to the next node. Propagating further finds contradictions before they require backtracking, at the cost of more work for every node state tried even when the wave function is easy to collapse.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PropagationDepth {
+    // only the neighbors of the node are restricted
+    #[default]
+    None,
+    // the neighbors of each restricted neighbor are also restricted to what its remaining node states permit
+    Neighbors,
+    // every restricted node restricts its own neighbors in turn until no more nodes are restricted
+    Full
+}
+
+// the duration is only checked periodically to keep each iteration cheap
+const ITERATIONS_PER_DURATION_CHECK: u64 = 1024;
+
+/// This struct tracks the iterations of a collapse against its CollapseOptions.
+pub struct CollapseLimiter {
+    collapse_options: CollapseOptions,
+    iterations_total: u64,
+    backtracks_total: u64,
+    restarts_total: u64,
+    started_at: Instant,
+    is_exceeded: bool,
+    is_duration_exceeded: bool,
+    is_cancelled: bool
+}
+
+impl CollapseLimiter {
+    pub fn new(collapse_options: CollapseOptions) -> Self {
+        CollapseLimiter {
+            collapse_options,
+            iterations_total: 0,
+            backtracks_total: 0,
+            restarts_total: 0,
+            started_at: Instant::now(),
+            is_exceeded: false,
+            is_duration_exceeded: false,
+            is_cancelled: false
+        }
+    }
+    pub fn start(&mut self) {
+        self.iterations_total = 0;
+        self.backtracks_total = 0;
+        self.restarts_total = 0;
+        self.started_at = Instant::now();
+        self.is_exceeded = false;
+        self.is_duration_exceeded = false;
+        self.is_cancelled = false;
+    }
+    /// This function counts a backtrack, which is only checked against its limit at the next iteration.
+    pub fn backtrack(&mut self) {
+        self.backtracks_total += 1;
+    }
+    /// This function counts a restart, which is only checked against its limit at the next iteration.
+    pub fn restart(&mut self) {
+        self.restarts_total += 1;
+    }
+    /// This function counts an iteration, returning true if a limit has been exceeded.
+    pub fn try_iterate(&mut self) -> bool {
+        self.iterations_total += 1;
+        self.is_exceeded = self.is_limit_exceeded();
+        self.is_exceeded
+    }
+    fn is_limit_exceeded(&mut self) -> bool {
+        if let Some(cancel_token) = self.collapse_options.cancel_token.as_ref() {
+            if cancel_token.load(Ordering::Relaxed) {
+                self.is_cancelled = true;
+                return true;
+            }
+        }
+        if let Some(max_iterations) = self.collapse_options.max_iterations {
+            if self.iterations_total > max_iterations {
+                return true;
+            }
+        }
+        if let Some(max_backtracks) = self.collapse_options.max_backtracks {
+            if self.backtracks_total > max_backtracks {
+                return true;
+            }
+        }
+        if let Some(max_restarts) = self.collapse_options.max_restarts {
+            if self.restarts_total > max_restarts {
+                return true;
+            }
+        }
+        if let Some(max_duration) = self.collapse_options.max_duration {
+            if self.iterations_total.is_multiple_of(ITERATIONS_PER_DURATION_CHECK) && self.started_at.elapsed() > max_duration {
+                self.is_duration_exceeded = true;
+                return true;
+            }
+        }
+        false
+    }
+    pub fn get_iterations_total(&self) -> u64 {
+        self.iterations_total
+    }
+    /// This function returns true if the collapse was stopped because a limit was exceeded since the collapse started.
+    pub fn is_exceeded(&self) -> bool {
+        self.is_exceeded
+    }
+    pub fn get_collapse_options(&self) -> &CollapseOptions {
+        &self.collapse_options
+    }
+    pub fn get_elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+    pub fn get_error_message(&self, collapsed_nodes_total: usize, nodes_total: usize) -> String {
+        if self.is_cancelled {
+            format!("Collapse cancelled after {} iterations with {collapsed_nodes_total} of {nodes_total} nodes collapsed.", self.iterations_total)
+        }
+        else if self.is_duration_exceeded {
+            format!("Collapse exceeded the maximum duration of {:?} after {} iterations with {collapsed_nodes_total} of {nodes_total} nodes collapsed.", self.collapse_options.max_duration.unwrap(), self.iterations_total)
+        }
+        else if self.collapse_options.max_backtracks.is_some_and(|max_backtracks| self.backtracks_total > max_backtracks) {
+            format!("Collapse exceeded the maximum of {} backtracks with {collapsed_nodes_total} of {nodes_total} nodes collapsed.", self.collapse_options.max_backtracks.unwrap())
+        }
+        else if self.collapse_options.max_restarts.is_some_and(|max_restarts| self.restarts_total > max_restarts) {
+            format!("Collapse exceeded the maximum of {} restarts with {collapsed_nodes_total} of {nodes_total} nodes collapsed.", self.collapse_options.max_restarts.unwrap())
+        }
+        else {
+            format!("Collapse exceeded the maximum of {} iterations with {collapsed_nodes_total} of {nodes_total} nodes collapsed.", self.collapse_options.max_iterations.unwrap())
+        }
+    }
+}
+
+/// This struct describes how far along a collapse is each time that it is reported to the progress callback.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct CollapseProgress {
+    pub collapsed_nodes_total: usize,
+    pub nodes_total: usize,
+    pub current_node_id: String,
+    pub node_assignments_total: u64,
+    pub backtracks_total: u64,
+    pub restarts_total: u64
 }
 
-#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq, Hash)]
-pub struct CollapsedNodeState<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> {
+/// This struct describes how much work a collapse performed. Each backtrack is a return to a previously collapsed node and the deepest backtrack is the most nodes returned to in a row before a node state could be chosen again. Each recollapse is a node assignment made while accommodating a conflict, changing a node that was already in a node state. Each iteration is a single step of the collapsing algorithm, including each step of propagating restrictions to neighbors.
+#[derive(Debug, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub struct CollapseReport {
+    pub node_assignments_total: u64,
+    pub backtracks_total: u64,
+    pub deepest_backtrack: u64,
+    pub restarts_total: u64,
+    #[serde(default)]
+    pub recollapses_total: u64,
+    pub iterations_total: u64,
+    pub duration: Duration
+}
+
+pub type CollapseProgressCallback<'a> = Box<dyn FnMut(CollapseProgress) -> ControlFlow<()> + 'a>;
+
+/// This struct counts the node assignments, backtracks, and restarts of a collapse, passing them to the progress callback periodically.
+pub struct CollapseProgressReporter<'a> {
+    progress_callback: Option<CollapseProgressCallback<'a>>,
+    node_assignments_per_report: u64,
+    node_assignments_total: u64,
+    backtracks_total: u64,
+    // the backtracks since the last node assignment
+    current_backtrack_depth: u64,
+    deepest_backtrack: u64,
+    restarts_total: u64,
+    recollapses_total: u64,
+    is_pass_begun: bool,
+    is_aborted: bool
+}
+
+impl<'a> Default for CollapseProgressReporter<'a> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<'a> CollapseProgressReporter<'a> {
+    pub fn new() -> Self {
+        CollapseProgressReporter {
+            progress_callback: None,
+            node_assignments_per_report: 1,
+            node_assignments_total: 0,
+            backtracks_total: 0,
+            current_backtrack_depth: 0,
+            deepest_backtrack: 0,
+            restarts_total: 0,
+            recollapses_total: 0,
+            is_pass_begun: false,
+            is_aborted: false
+        }
+    }
+    pub fn set_progress_callback(&mut self, node_assignments_per_report: u64, progress_callback: CollapseProgressCallback<'a>) {
+        self.node_assignments_per_report = node_assignments_per_report.max(1);
+        self.progress_callback = Some(progress_callback);
+    }
+    pub fn start(&mut self) {
+        self.node_assignments_total = 0;
+        self.backtracks_total = 0;
+        self.current_backtrack_depth = 0;
+        self.deepest_backtrack = 0;
+        self.restarts_total = 0;
+        self.recollapses_total = 0;
+        self.is_pass_begun = false;
+        self.is_aborted = false;
+    }
+    pub fn backtrack(&mut self) {
+        self.backtracks_total += 1;
+        self.current_backtrack_depth += 1;
+        self.deepest_backtrack = self.deepest_backtrack.max(self.current_backtrack_depth);
+    }
+    /// This function marks the start of a pass over the nodes, where every pass after the first is counted as a restart, returning true if this pass is a restart.
+    pub fn begin_pass(&mut self) -> bool {
+        let is_restart = self.is_pass_begun;
+        if is_restart {
+            self.restarts_total += 1;
+        }
+        self.is_pass_begun = true;
+        is_restart
+    }
+    /// This function counts nodes that were changed while already in a node state.
+    pub fn recollapse_nodes(&mut self, recollapsed_nodes_total: usize) {
+        self.recollapses_total += recollapsed_nodes_total as u64;
+    }
+    /// This function counts a node being set to a node state, returning true if the progress callback is due to be called.
+    pub fn assign_node(&mut self) -> bool {
+        self.node_assignments_total += 1;
+        self.current_backtrack_depth = 0;
+        self.progress_callback.is_some() && self.node_assignments_total.is_multiple_of(self.node_assignments_per_report)
+    }
+    /// This function describes the work of the collapse started along with the collapse limiter.
+    pub fn get_collapse_report(&self, collapse_limiter: &CollapseLimiter) -> CollapseReport {
+        CollapseReport {
+            node_assignments_total: self.node_assignments_total,
+            backtracks_total: self.backtracks_total,
+            deepest_backtrack: self.deepest_backtrack,
+            restarts_total: self.restarts_total,
+            recollapses_total: self.recollapses_total,
+            iterations_total: collapse_limiter.get_iterations_total(),
+            duration: collapse_limiter.get_elapsed()
+        }
+    }
+    pub fn try_report(&mut self, current_node_id: &str, collapsed_nodes_total: usize, nodes_total: usize) -> Result<(), String> {
+        if let Some(progress_callback) = self.progress_callback.as_mut() {
+            let collapse_progress = CollapseProgress {
+                collapsed_nodes_total,
+                nodes_total,
+                current_node_id: String::from(current_node_id),
+                node_assignments_total: self.node_assignments_total,
+                backtracks_total: self.backtracks_total,
+                restarts_total: self.restarts_total
+            };
+            if progress_callback(collapse_progress).is_break() {
+                self.is_aborted = true;
+                return Err(format!("Collapse aborted by caller after {} node assignments with {collapsed_nodes_total} of {nodes_total} nodes collapsed.", self.node_assignments_total));
+            }
+        }
+        Ok(())
+    }
+    /// This function returns true if the progress callback aborted the collapse since the collapse started.
+    pub fn is_aborted(&self) -> bool {
+        self.is_aborted
+    }
+}
+
+/// This struct describes how likely a node state was at the moment that it was chosen for a node.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CollapsedNodeStateDiagnostics {
+    // the ratio of the chosen node state relative to all of the node states of the node
+    pub probability: f64,
+    // the number of node states, including the chosen node state, that were not restricted by neighbors
+    pub remaining_node_states_total: usize
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CollapsedNodeState<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
     pub node_id: String,
-    pub node_state_id: Option<TNodeState>
+    pub node_state_id: Option<TNodeState>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub diagnostics: Option<CollapsedNodeStateDiagnostics>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>
+}
+
+/// This struct is the node state of every node once collapsed. Each map keyed by node id is serialized in node id order, so the same collapsed wave function is always serialized identically.
+#[derive(Debug, Serialize, Deserialize, Clone, Eq, PartialEq)]
+#[serde(bound(serialize = "TNodeState: Serialize", deserialize = "TNodeState: DeserializeOwned"))]
+pub struct CollapsedWaveFunction<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    #[serde(serialize_with = "serialize_sorted_by_node_id")]
+    pub node_state_per_node_id: HashMap<String, TNodeState>,
+    // the random seed that reproduces this collapsed wave function when provided to get_collapsable_wave_function
+    #[serde(default)]
+    pub random_seed: u64,
+    // the metadata of each node that has metadata
+    #[serde(default, serialize_with = "serialize_node_metadata_sorted_by_node_id", deserialize_with = "deserialize_node_metadata")]
+    pub node_metadata_per_node: HashMap<String, serde_json::Value>
+}
+
+// serialize in node id order so that equal values are serialized identically regardless of the order that the hashmap iterates in
+pub(crate) fn serialize_sorted_by_node_id<TValue: Serialize, TSerializer: Serializer>(value_per_node_id: &HashMap<String, TValue>, serializer: TSerializer) -> Result<TSerializer::Ok, TSerializer::Error> {
+    let sorted_value_per_node_id: BTreeMap<&String, &TValue> = value_per_node_id.iter().collect();
+    sorted_value_per_node_id.serialize(serializer)
+}
+
+// binary formats cannot deserialize arbitrary json values, so the metadata is stored as json text when not human-readable
+fn serialize_node_metadata_sorted_by_node_id<TSerializer: Serializer>(node_metadata_per_node: &HashMap<String, serde_json::Value>, serializer: TSerializer) -> Result<TSerializer::Ok, TSerializer::Error> {
+    if serializer.is_human_readable() {
+        serialize_sorted_by_node_id(node_metadata_per_node, serializer)
+    }
+    else {
+        let sorted_node_metadata_text_per_node: BTreeMap<&String, String> = node_metadata_per_node.iter()
+            .map(|(node_id, metadata)| (node_id, metadata.to_string()))
+            .collect();
+        sorted_node_metadata_text_per_node.serialize(serializer)
+    }
 }
 
-#[derive(Serialize)]
-pub struct CollapsedWaveFunction<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> {
-    pub node_state_per_node_id: HashMap<String, TNodeState>
+fn deserialize_node_metadata<'de, TDeserializer: Deserializer<'de>>(deserializer: TDeserializer) -> Result<HashMap<String, serde_json::Value>, TDeserializer::Error> {
+    if deserializer.is_human_readable() {
+        HashMap::<String, serde_json::Value>::deserialize(deserializer)
+    }
+    else {
+        HashMap::<String, String>::deserialize(deserializer)?
+            .into_iter()
+            .map(|(node_id, metadata_text)| {
+                serde_json::from_str(&metadata_text)
+                    .map(|metadata| (node_id, metadata))
+                    .map_err(serde::de::Error::custom)
+            })
+            .collect()
+    }
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> CollapsedWaveFunction<TNodeState> {
+    /// This function replays the steps from collapse_into_steps, returning an error if any node is not in a node state after the last step. The random seed is expected to come from get_random_seed of the same collapsable wave function.
+    pub fn from_steps(collapsed_node_states: &[CollapsedNodeState<TNodeState>], random_seed: u64) -> Result<Self, String> {
+        let mut node_state_per_node_id: HashMap<String, Option<TNodeState>> = HashMap::new();
+        let mut node_metadata_per_node: HashMap<String, serde_json::Value> = HashMap::new();
+        for collapsed_node_state in collapsed_node_states.iter() {
+            node_state_per_node_id.insert(collapsed_node_state.node_id.clone(), collapsed_node_state.node_state_id.clone());
+            if let Some(metadata) = &collapsed_node_state.metadata {
+                node_metadata_per_node.insert(collapsed_node_state.node_id.clone(), metadata.clone());
+            }
+        }
+        let mut collapsed_node_state_per_node_id: HashMap<String, TNodeState> = HashMap::new();
+        for (node_id, node_state_id) in node_state_per_node_id.into_iter() {
+            let Some(node_state_id) = node_state_id else {
+                return Err(format!("Node {node_id} is not in a node state after replaying the steps."));
+            };
+            collapsed_node_state_per_node_id.insert(node_id, node_state_id);
+        }
+        Ok(CollapsedWaveFunction {
+            node_state_per_node_id: collapsed_node_state_per_node_id,
+            random_seed,
+            node_metadata_per_node
+        })
+    }
+    /// This function converts the node state of every node, such as from the String node states of a loaded wave function into an enum, keeping the random seed and metadata so that the converted collapsed wave function is serialized in the same order.
+    pub fn map_states<TMappedNodeState: Eq + Hash + Clone + std::fmt::Debug>(&self, map_node_state: impl Fn(&TNodeState) -> TMappedNodeState) -> CollapsedWaveFunction<TMappedNodeState> {
+        CollapsedWaveFunction {
+            node_state_per_node_id: self.node_state_per_node_id
+                .iter()
+                .map(|(node_id, node_state_id)| (node_id.clone(), map_node_state(node_state_id)))
+                .collect(),
+            random_seed: self.random_seed,
+            node_metadata_per_node: self.node_metadata_per_node.clone()
+        }
+    }
+    /// This function keys the node state of every node by its converted node id, such as the coordinates parsed from the node id. Node ids converted into the same key keep only one of their node states.
+    pub fn map_nodes<TKey: Eq + Hash>(&self, map_node_id: impl Fn(&str) -> TKey) -> HashMap<TKey, TNodeState> {
+        self.node_state_per_node_id
+            .iter()
+            .map(|(node_id, node_state_id)| (map_node_id(node_id), node_state_id.clone()))
+            .collect()
+    }
+    /// This function converts both the node id and the node state of every node, combining map_nodes and map_states without cloning the node states.
+    pub fn into_typed<TKey: Eq + Hash, TMappedNodeState>(self, map_node_id: impl Fn(&str) -> TKey, map_node_state: impl Fn(TNodeState) -> TMappedNodeState) -> HashMap<TKey, TMappedNodeState> {
+        self.node_state_per_node_id
+            .into_iter()
+            .map(|(node_id, node_state_id)| (map_node_id(&node_id), map_node_state(node_state_id)))
+            .collect()
+    }
+}
+
+/// This struct pairs a collapsed wave function with the diagnostics of the node state chosen last for each node.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(bound(serialize = "TNodeState: Serialize", deserialize = "TNodeState: DeserializeOwned"))]
+pub struct CollapsedWaveFunctionWithDiagnostics<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    pub collapsed_wave_function: CollapsedWaveFunction<TNodeState>,
+    #[serde(serialize_with = "serialize_sorted_by_node_id")]
+    pub diagnostics_per_node_id: HashMap<String, CollapsedNodeStateDiagnostics>
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> CollapsedWaveFunctionWithDiagnostics<TNodeState> {
+    /// This function replays the steps from collapse_into_steps just like CollapsedWaveFunction::from_steps while keeping the diagnostics of the last step for each node.
+    pub fn from_steps(collapsed_node_states: &[CollapsedNodeState<TNodeState>], random_seed: u64) -> Result<Self, String> {
+        let collapsed_wave_function = CollapsedWaveFunction::from_steps(collapsed_node_states, random_seed)?;
+        let mut diagnostics_per_node_id: HashMap<String, CollapsedNodeStateDiagnostics> = HashMap::new();
+        for collapsed_node_state in collapsed_node_states.iter() {
+            if let Some(diagnostics) = &collapsed_node_state.diagnostics {
+                diagnostics_per_node_id.insert(collapsed_node_state.node_id.clone(), diagnostics.clone());
+            }
+        }
+        Ok(CollapsedWaveFunctionWithDiagnostics {
+            collapsed_wave_function,
+            diagnostics_per_node_id
+        })
+    }
+}
+
+/// This struct describes how a node came to be in its node state, as opposed to the metadata provided for the node itself.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CollapsedNodeStateMetadata {
+    // the number of node states that were not restricted by neighbors when the node state was chosen last
+    pub remaining_node_states_total: usize,
+    // the node state was the only one remaining instead of being chosen from several
+    pub is_forced: bool,
+    // the position of the node among every node ordered by when each was last set to its node state
+    pub collapse_order_index: usize,
+    // the number of times that the node was set to a node state after the first time, such as when backtracking or accommodating a neighbor
+    pub revisits_total: usize
+}
+
+/// This struct pairs a collapsed wave function with how each node came to be in its node state.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(bound(serialize = "TNodeState: Serialize", deserialize = "TNodeState: DeserializeOwned"))]
+pub struct CollapsedWaveFunctionWithMetadata<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    pub collapsed_wave_function: CollapsedWaveFunction<TNodeState>,
+    #[serde(serialize_with = "serialize_sorted_by_node_id")]
+    pub metadata_per_node_id: HashMap<String, CollapsedNodeStateMetadata>
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> CollapsedWaveFunctionWithMetadata<TNodeState> {
+    /// This function replays the steps from collapse_into_steps just like CollapsedWaveFunction::from_steps while describing the last step of each node and how many steps came before it.
+    pub fn from_steps(collapsed_node_states: &[CollapsedNodeState<TNodeState>], random_seed: u64) -> Result<Self, String> {
+        let collapsed_wave_function = CollapsedWaveFunction::from_steps(collapsed_node_states, random_seed)?;
+        let mut last_step_index_and_assignments_total_per_node_id: HashMap<&str, (usize, usize)> = HashMap::new();
+        for (step_index, collapsed_node_state) in collapsed_node_states.iter().enumerate() {
+            let (last_step_index, assignments_total) = last_step_index_and_assignments_total_per_node_id
+                .entry(collapsed_node_state.node_id.as_str())
+                .or_insert((step_index, 0));
+            *last_step_index = step_index;
+            if collapsed_node_state.node_state_id.is_some() {
+                *assignments_total += 1;
+            }
+        }
+
+        let mut last_step_index_and_node_ids: Vec<(usize, &str)> = last_step_index_and_assignments_total_per_node_id
+            .iter()
+            .map(|(node_id, (last_step_index, _))| (*last_step_index, *node_id))
+            .collect();
+        last_step_index_and_node_ids.sort_unstable();
+
+        let mut metadata_per_node_id: HashMap<String, CollapsedNodeStateMetadata> = HashMap::new();
+        for (collapse_order_index, (last_step_index, node_id)) in last_step_index_and_node_ids.into_iter().enumerate() {
+            let Some(diagnostics) = &collapsed_node_states[last_step_index].diagnostics else {
+                return Err(format!("Node {node_id} has no diagnostics for the node state that it was set to last."));
+            };
+            metadata_per_node_id.insert(String::from(node_id), CollapsedNodeStateMetadata {
+                remaining_node_states_total: diagnostics.remaining_node_states_total,
+                is_forced: diagnostics.remaining_node_states_total == 1,
+                collapse_order_index,
+                revisits_total: last_step_index_and_assignments_total_per_node_id[node_id].1 - 1
+            });
+        }
+        Ok(CollapsedWaveFunctionWithMetadata {
+            collapsed_wave_function,
+            metadata_per_node_id
+        })
+    }
+}
+
+// the version of the binary format, stored as the first byte so that older bytes can be detected
+const COLLAPSED_WAVE_FUNCTION_BYTES_VERSION: u8 = 3;
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Serialize + DeserializeOwned> CollapsedWaveFunction<TNodeState> {
+    /// This function encodes the collapsed wave function as a version byte followed by its compact binary encoding, where lengths are variable-length integers.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = vec![COLLAPSED_WAVE_FUNCTION_BYTES_VERSION];
+        bytes.extend(bincode::DefaultOptions::new().serialize(self).unwrap());
+        bytes
+    }
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let Some((version, encoded_bytes)) = bytes.split_first() else {
+            return Err(String::from("Cannot decode collapsed wave function from empty bytes."));
+        };
+        if *version != COLLAPSED_WAVE_FUNCTION_BYTES_VERSION {
+            return Err(format!("Cannot decode collapsed wave function from bytes of version {version} when expecting version {COLLAPSED_WAVE_FUNCTION_BYTES_VERSION}."));
+        }
+        bincode::DefaultOptions::new().deserialize(encoded_bytes).map_err(|error| format!("Cannot decode collapsed wave function: {error}"))
+    }
+    #[cfg(feature = "file-io")]
+    pub fn save_to_file(&self, file_path: &str) -> Result<(), String> {
+        std::fs::write(file_path, self.to_bytes()).map_err(|error| format!("Cannot save collapsed wave function to {file_path}: {error}"))
+    }
+    #[cfg(feature = "file-io")]
+    pub fn load_from_file(file_path: &str) -> Result<Self, String> {
+        let bytes = std::fs::read(file_path).map_err(|error| format!("Cannot load collapsed wave function from {file_path}: {error}"))?;
+        Self::from_bytes(&bytes)
+    }
 }
 
 #[derive(Clone, Eq, PartialEq, Debug)]
-pub struct UncollapsedWaveFunction<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> {
+pub struct UncollapsedWaveFunction<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
     pub node_state_per_node: HashMap<String, Option<TNodeState>>
 }
 
-impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> Hash for UncollapsedWaveFunction<TNodeState> {
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug> Hash for UncollapsedWaveFunction<TNodeState> {
     fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
         for property in self.node_state_per_node.iter() {
             property.hash(state);
@@ -39,33 +644,77 @@ impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> Hash for Uncollapsed
     }
 }
 
+/// This struct represents a global or aggregate constraint of the wave function, requiring that the number of its nodes in any of the node states is between the minimum and maximum, inclusive.
+#[derive(Debug, Clone)]
+pub struct CollapsableGlobalConstraint<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    pub node_ids: Vec<&'a str>,
+    pub node_states: Vec<&'a TNodeState>,
+    pub minimum: usize,
+    pub maximum: usize
+}
+
+impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> CollapsableGlobalConstraint<'a, TNodeState> {
+    /// This function returns how many of the nodes are currently in any of the node states along with the nodes that are not yet in any node state but are not restricted from all of the node states.
+    pub fn get_decided_total_and_undecided_node_ids(&self, collapsable_node_per_id: &HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>) -> (usize, Vec<&'a str>) {
+        let mut decided_total: usize = 0;
+        let mut undecided_node_ids: Vec<&'a str> = Vec::new();
+        for node_id in self.node_ids.iter() {
+            let collapsable_node = collapsable_node_per_id.get(node_id).unwrap().borrow();
+            if let Some(node_state) = collapsable_node.node_state_indexed_view.get() {
+                if self.node_states.contains(node_state) {
+                    decided_total += 1;
+                }
+            }
+            else if self.node_states.iter().any(|node_state| collapsable_node.node_state_indexed_view.is_node_state_unrestricted(node_state)) {
+                undecided_node_ids.push(node_id);
+            }
+        }
+        (decided_total, undecided_node_ids)
+    }
+    /// This function returns false if the nodes can no longer satisfy this global constraint no matter which node states the undecided nodes end up in.
+    pub fn is_satisfiable(&self, collapsable_node_per_id: &HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>) -> bool {
+        let (decided_total, undecided_node_ids) = self.get_decided_total_and_undecided_node_ids(collapsable_node_per_id);
+        decided_total <= self.maximum && decided_total + undecided_node_ids.len() >= self.minimum
+    }
+}
+
+/// The masks that a node applies to a neighbor per node state key of the node, shared between every neighbor given the same node state collections with the same possible node states. Each node state key is the position of the node state among every node state interned by the wave function.
+pub type SharedMaskPerState = Rc<Vec<Option<BitVec>>>;
+
 /// This struct represents a stateful node in a collapsable wave function which references a base node from the wave function.
 #[derive(Debug)]
-pub struct CollapsableNode<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> {
+pub struct CollapsableNode<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
     // the node id that this collapsable node refers to
     pub id: &'a str,
     // this nodes list of neighbor node ids
     pub neighbor_node_ids: Vec<&'a str>,
     // the full list of possible node states, masked by internal references to neighbor masks
     pub node_state_indexed_view: IndexedView<&'a TNodeState>,
-    // the mapped view that this node's neighbors will have a reference to and pull their masks from
-    pub mask_per_neighbor_per_state: HashMap<&'a TNodeState, HashMap<&'a str, BitVec>>,
+    // the node state key of each node state of the node state indexed view, in the order that the view was constructed with
+    pub node_state_keys: Vec<usize>,
+    // the masks that this node applies to each neighbor per node state key
+    pub mask_per_state_per_neighbor: HashMap<&'a str, SharedMaskPerState>,
     // the index of traversed nodes based on the sorted vector of nodes as they are chosen for state determination
     pub current_chosen_from_sort_index: Option<usize>,
     // the neighbors that are pointing to this collapsable node
     pub parent_neighbor_node_ids: Vec<&'a str>,
+    // the metadata of the node, copied into each collapsed node state and the collapsed wave function
+    pub metadata: Option<&'a serde_json::Value>,
     // allowing for Node<TNodeState> to be an argument of CollapsableNode functions
     node_state_type: PhantomData<TNodeState>
 }
 
-impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableNode<'a, TNodeState> {
-    pub fn new(id: &'a str, node_state_collection_ids_per_neighbor_node_id: &'a HashMap<String, Vec<String>>, mask_per_neighbor_per_state: HashMap<&'a TNodeState, HashMap<&'a str, BitVec>>, node_state_indexed_view: IndexedView<&'a TNodeState>) -> Self {
+impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> CollapsableNode<'a, TNodeState> {
+    pub fn new(id: &'a str, node_state_collection_ids_per_neighbor_node_id: &'a HashMap<String, Vec<String>>, mask_per_state_per_neighbor: HashMap<&'a str, SharedMaskPerState>, node_state_indexed_view: IndexedView<&'a TNodeState>, node_state_keys: Vec<usize>) -> Self {
         // get the neighbors for this node
         let mut neighbor_node_ids: Vec<&str> = Vec::new();
 
         for neighbor_node_id_string in node_state_collection_ids_per_neighbor_node_id.keys() {
             let neighbor_node_id: &str = neighbor_node_id_string;
-            neighbor_node_ids.push(neighbor_node_id);
+            // a node that is its own neighbor has already had its node states restricted by itself
+            if neighbor_node_id != id {
+                neighbor_node_ids.push(neighbor_node_id);
+            }
         }
         neighbor_node_ids.sort();
 
@@ -73,15 +722,35 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableNode<
             id,
             neighbor_node_ids,
             node_state_indexed_view,
-            mask_per_neighbor_per_state,
+            node_state_keys,
+            mask_per_state_per_neighbor,
             current_chosen_from_sort_index: None,
             parent_neighbor_node_ids: Vec::new(),
+            metadata: None,
             node_state_type: PhantomData
         }
     }
     pub fn randomize(&mut self, random_instance: &mut Rng) {
         self.node_state_indexed_view.shuffle(random_instance);
     }
+    /// This function returns the mask that this node applies to the neighbor while in the node state, if any node state collection of that node state is applied to the neighbor.
+    pub fn get_mask(&self, node_state: &TNodeState, neighbor_node_id: &str) -> Option<&BitVec> {
+        self.get_mask_at_index(self.node_state_indexed_view.get_node_state_index_of(&node_state)?, neighbor_node_id)
+    }
+    /// This function returns the mask that this node applies to the neighbor while in the node state at the position within its node state indexed view, avoiding hashing the node state.
+    pub fn get_mask_at_index(&self, node_state_index: usize, neighbor_node_id: &str) -> Option<&BitVec> {
+        self.mask_per_state_per_neighbor
+            .get(neighbor_node_id)
+            .and_then(|mask_per_state| mask_per_state[self.node_state_keys[node_state_index]].as_ref())
+    }
+    /// This function returns the mask that this node applies to the neighbor while in its current node state.
+    pub fn get_current_mask(&self, neighbor_node_id: &str) -> Option<&BitVec> {
+        self.get_mask_at_index(self.node_state_indexed_view.get_node_state_index()?, neighbor_node_id)
+    }
+    /// This function returns how many node states this node could still be in given the masks applied to it, regardless of its current node state.
+    pub fn domain_size(&self) -> usize {
+        self.node_state_indexed_view.get_unrestricted_node_states_total()
+    }
     pub fn is_fully_restricted(&mut self) -> bool {
         self.node_state_indexed_view.is_fully_restricted() || self.node_state_indexed_view.is_current_state_restricted()
     }
@@ -97,6 +766,20 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableNode<
     pub fn reverse_mask(&mut self) {
         self.node_state_indexed_view.reverse_mask();
     }
+    /// This function describes the current node state of this node along with the diagnostics of it being chosen.
+    pub fn get_collapsed_node_state(&self) -> CollapsedNodeState<TNodeState> {
+        let node_state_id: Option<TNodeState> = self.node_state_indexed_view.get().map(|node_state_id| (*node_state_id).clone());
+        let diagnostics = self.node_state_indexed_view.get_probability().map(|probability| CollapsedNodeStateDiagnostics {
+            probability,
+            remaining_node_states_total: self.node_state_indexed_view.get_unrestricted_node_states_total()
+        });
+        CollapsedNodeState {
+            node_id: String::from(self.id),
+            node_state_id,
+            diagnostics,
+            metadata: self.metadata.cloned()
+        }
+    }
     pub fn is_mask_restrictive_to_current_state(&self, mask: &BitVec) -> bool {
         let is_restrictive = self.node_state_indexed_view.is_mask_restrictive_to_current_state(mask);
         if is_restrictive {
@@ -109,7 +792,7 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableNode<
     }
 }
 
-impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> Display for CollapsableNode<'a, TNodeState> {
+impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> Display for CollapsableNode<'a, TNodeState> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "{}", self.id)
     }
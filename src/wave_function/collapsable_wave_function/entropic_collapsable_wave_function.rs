@@ -1,4 +1,4 @@
-use std::ops::{BitOr, BitOrAssign};
+use std::ops::{BitAndAssign, BitOrAssign};
 use std::cell::RefCell;
 use std::collections::HashMap;
 use std::hash::Hash;
@@ -7,90 +7,131 @@ use std::rc::Rc;
 use bitvec::vec::BitVec;
 use indexmap::IndexMap;
 
-use super::collapsable_wave_function::{CollapsableNode, CollapsableWaveFunction, CollapsedNodeState, CollapsedWaveFunction};
+use super::collapsable_wave_function::{CollapsableGlobalConstraint, CollapsableNode, CollapsableWaveFunction, CollapsedNodeState, CollapsedWaveFunction, CollapseLimiter, CollapseOptions, CollapseProgressCallback, CollapseProgressReporter, CollapseReport, CollapseSnapshot};
 
-pub struct EntropicCollapsableWaveFunction<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> {
+/// This enum determines how the next node to collapse is chosen, always choosing the uncollapsed node with the lowest value and breaking ties with the seeded random instance so that the collapse can be reproduced.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NodeSelectionHeuristic {
+    /// The number of node states that the node could still be in.
+    MinimumRemainingStates,
+    /// The Shannon entropy of the probabilities of the node states that the node could still be in, so that a node that is very likely to be in one node state is chosen before a node that is equally likely to be in fewer node states.
+    #[default]
+    ShannonEntropy,
+    /// The negated number of collapsed neighbors that restrict the node, so that the node most restricted by its collapsed neighbors is chosen first.
+    MostConstrainedNeighbors,
+    /// Every uncollapsed node has the same value, so that the next node is chosen at random.
+    Random
+}
+
+pub struct EntropicCollapsableWaveFunction<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
     collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>>,
     collapsable_node_per_id: HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>,
+    collapsable_node_index_per_id: HashMap<&'a str, usize>,
     collapsable_nodes_length: usize,
     current_collapsable_node_index: usize,
     collapsed_nodes_total: usize,
     is_node_collapsed: BitVec,
+    // the index of each collapsed node in the order that the nodes were collapsed
+    collapsed_node_indexes: Vec<usize>,
     cached_mask_per_neighbor_node_id: IndexMap<String, BitVec>,
     popped_neighbor_node_id: Option<String>,
     popped_mask: Option<BitVec>,
-    possible_states_from_popped_neighbor: Vec<&'a TNodeState>,
+    possible_node_state_indexes_from_popped_neighbor: Vec<usize>,
     great_neighbors_from_popped_neighbor: Vec<&'a str>,
     great_neighbors_from_popped_neighbor_length: usize,
     explored_great_neighbor_node_index: Option<usize>,
-    collected_masks_for_each_possible_state_for_currently_explored_neighbor: Vec<BitVec>,
     calculated_flattened_mask: Option<BitVec>,
+    collapse_limiter: CollapseLimiter,
+    collapse_progress_reporter: CollapseProgressReporter<'a>,
+    node_selection_heuristic: NodeSelectionHeuristic,
+    global_constraints: Vec<CollapsableGlobalConstraint<'a, TNodeState>>,
+    random_seed: u64,
+    random_instance: Rc<RefCell<fastrand::Rng>>,
     node_state_type: PhantomData<TNodeState>
 }
 
-impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> EntropicCollapsableWaveFunction<'a, TNodeState> {
+impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> EntropicCollapsableWaveFunction<'a, TNodeState> {
     fn is_fully_collapsed(&self) -> bool {
         self.collapsable_nodes_length == self.collapsed_nodes_total
     }
+    /// The node selection heuristic may be changed before collapsing, defaulting to ShannonEntropy.
+    pub fn set_node_selection_heuristic(&mut self, node_selection_heuristic: NodeSelectionHeuristic) {
+        self.node_selection_heuristic = node_selection_heuristic;
+    }
     fn set_current_collapsable_node_to_least_entropic_collapsable_node(&mut self) {
-        let mut lowest_entropy: Option<f32> = None;
+        let mut lowest_entropy: Option<f64> = None;
         let mut lowest_entropy_index: Option<usize> = None;
+        let mut lowest_entropy_indexes_total: usize = 0;
         for index in 0..self.collapsable_nodes_length {
             if !self.is_node_collapsed[index] {
                 let wrapped_collapsable_node = self.collapsable_nodes.get(index).unwrap();
                 let mut collapsable_node = wrapped_collapsable_node.borrow_mut();
+                let current_entropy_value = match self.node_selection_heuristic {
+                    NodeSelectionHeuristic::MinimumRemainingStates => collapsable_node.domain_size() as f64,
+                    NodeSelectionHeuristic::ShannonEntropy => collapsable_node.node_state_indexed_view.entropy(),
+                    NodeSelectionHeuristic::MostConstrainedNeighbors => {
+                        // the parent neighbors are the nodes that mask this node when they are collapsed
+                        let collapsed_parent_neighbors_total = collapsable_node.parent_neighbor_node_ids
+                            .iter()
+                            .filter(|parent_neighbor_node_id| self.is_node_collapsed[*self.collapsable_node_index_per_id.get(*parent_neighbor_node_id).unwrap()])
+                            .count();
+                        -(collapsed_parent_neighbors_total as f64)
+                    },
+                    NodeSelectionHeuristic::Random => 0.0
+                };
                 if let Some(lowest_entropy_value) = lowest_entropy {
-                    let current_entropy_value = collapsable_node.node_state_indexed_view.entropy();
                     if current_entropy_value < lowest_entropy_value {
                         lowest_entropy = Some(current_entropy_value);
                         lowest_entropy_index = Some(index);
+                        lowest_entropy_indexes_total = 1;
+                    }
+                    else if current_entropy_value == lowest_entropy_value {
+                        // each tied node is equally likely to be chosen
+                        lowest_entropy_indexes_total += 1;
+                        if self.random_instance.borrow_mut().usize(..lowest_entropy_indexes_total) == 0 {
+                            lowest_entropy_index = Some(index);
+                        }
                     }
                 }
                 else {
-                    lowest_entropy = Some(collapsable_node.node_state_indexed_view.entropy());
+                    lowest_entropy = Some(current_entropy_value);
                     lowest_entropy_index = Some(index);
+                    lowest_entropy_indexes_total = 1;
                 }
             }
         }
         self.current_collapsable_node_index = lowest_entropy_index.unwrap();
     }
-    fn try_increment_current_collapsable_node_state(&mut self) -> CollapsedNodeState<TNodeState> {
+    /// This function returns if the current node was incremented to its next node state, where its node state is None otherwise.
+    fn try_increment_current_collapsable_node_state(&mut self) -> bool {
 
         let wrapped_current_collapsable_node = self.collapsable_nodes.get(self.current_collapsable_node_index).unwrap();
         let mut current_collapsable_node = wrapped_current_collapsable_node.borrow_mut();
 
         let is_successful = current_collapsable_node.node_state_indexed_view.try_move_next();
-        let collapsed_node_state: CollapsedNodeState<TNodeState>;
         if is_successful {
             current_collapsable_node.current_chosen_from_sort_index = Some(self.current_collapsable_node_index);
-            collapsed_node_state = CollapsedNodeState {
-                node_id: String::from(current_collapsable_node.id),
-                node_state_id: Some((*current_collapsable_node.node_state_indexed_view.get().unwrap()).clone())
-            };
         }
         else {
             current_collapsable_node.current_chosen_from_sort_index = None;
-            collapsed_node_state = CollapsedNodeState {
-                node_id: String::from(current_collapsable_node.id),
-                node_state_id: None
-            };
         }
         self.is_node_collapsed.set(self.current_collapsable_node_index, true);
         self.collapsed_nodes_total += 1;
+        self.collapsed_node_indexes.push(self.current_collapsable_node_index);
 
-        collapsed_node_state
+        is_successful
+    }
+    fn get_current_collapsable_node_id(&self) -> &'a str {
+        self.collapsable_nodes[self.current_collapsable_node_index].borrow().id
     }
     fn cache_neighbor_node_and_mask_pairs(&mut self) {
         let wrapped_current_collapsable_node = self.collapsable_nodes.get_mut(self.current_collapsable_node_index).expect("The collapsable node should exist at this index.");
         let current_collapsable_node = wrapped_current_collapsable_node.borrow();
-        let current_possible_state = current_collapsable_node.node_state_indexed_view.get().unwrap();
+        let current_node_state_index = current_collapsable_node.node_state_indexed_view.get_node_state_index().unwrap();
         let neighbor_node_ids: &Vec<&str> = &current_collapsable_node.neighbor_node_ids;
-        let mask_per_neighbor_per_state: &HashMap<&TNodeState, HashMap<&str, BitVec>> = &current_collapsable_node.mask_per_neighbor_per_state;
-        if let Some(mask_per_neighbor) = mask_per_neighbor_per_state.get(current_possible_state) {
-            for neighbor_node_id in neighbor_node_ids.iter() {
-                if let Some(mask) = mask_per_neighbor.get(neighbor_node_id) {
-                    self.cached_mask_per_neighbor_node_id.insert(String::from(*neighbor_node_id), mask.clone());
-                }
+        for neighbor_node_id in neighbor_node_ids.iter() {
+            if let Some(mask) = current_collapsable_node.get_mask_at_index(current_node_state_index, neighbor_node_id) {
+                self.cached_mask_per_neighbor_node_id.insert(String::from(*neighbor_node_id), mask.clone());
             }
         }
     }
@@ -114,7 +155,7 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> EntropicCollapsa
             false
         }
         else {
-            self.possible_states_from_popped_neighbor = neighbor_collapsable_node.node_state_indexed_view.get_possible_states();
+            self.possible_node_state_indexes_from_popped_neighbor = neighbor_collapsable_node.node_state_indexed_view.get_possible_node_state_indexes();
             self.great_neighbors_from_popped_neighbor = neighbor_collapsable_node.neighbor_node_ids.clone();
             self.great_neighbors_from_popped_neighbor_length = self.great_neighbors_from_popped_neighbor.len();
             debug!("is not fully restricted after applying mask");
@@ -143,36 +184,25 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> EntropicCollapsa
             self.explored_great_neighbor_node_index = Some(0);
         }
     }
-    fn collect_masks_for_each_possible_state_of_popped_neighbor_for_currently_explored_great_neighbor(&mut self) {
-        self.collected_masks_for_each_possible_state_for_currently_explored_neighbor.clear();
+    /// This function combines the masks of each possible node state of the popped neighbor for the currently explored great neighbor into the one mask of every node state that the great neighbor could still be in, combining them in place so that nodes with many possible node states do not allocate a mask per node state.
+    fn calculate_flattened_mask_for_currently_explored_great_neighbor(&mut self) {
+        // TODO compress "calculate_flattened_mask_for_currently_explored_great_neighbor" and "is_flattened_mask_restrictive_to_explored_neighbor" into one function
         let popped_neighbor_node_id: &str = self.popped_neighbor_node_id.as_ref().unwrap();
         let wrapped_popped_neighbor_collapsable_node = self.collapsable_node_per_id.get(popped_neighbor_node_id).unwrap();
         let popped_neighbor_collapsable_node = wrapped_popped_neighbor_collapsable_node.borrow();
         let explored_great_neighbor_node_id = self.great_neighbors_from_popped_neighbor[self.explored_great_neighbor_node_index.unwrap()];
-        for possible_state in self.possible_states_from_popped_neighbor.iter() {
-            if popped_neighbor_collapsable_node.mask_per_neighbor_per_state.contains_key(possible_state) {
-                let mask_per_neighbor = popped_neighbor_collapsable_node.mask_per_neighbor_per_state.get(possible_state).unwrap();
-                if let Some(mask) = mask_per_neighbor.get(explored_great_neighbor_node_id) {
-                    self.collected_masks_for_each_possible_state_for_currently_explored_neighbor.push(mask.clone());
-                }
-            }
-        }
-    }
-    fn calculate_flattened_mask(&mut self) {
-        // TODO compress "collect_masks_for_each_possible_state_of_popped_neighbor_for_currently_explored_great_neighbor", "calculate_flattened_mask", and "is_flattened_mask_restrictive_to_explored_neighbor" into one function
-        if !self.collected_masks_for_each_possible_state_for_currently_explored_neighbor.is_empty() {
-            let mut flattened_mask: Option<BitVec> = None;
-            for mask in self.collected_masks_for_each_possible_state_for_currently_explored_neighbor.iter() {
-                if let Some(flattened_mask_value) = flattened_mask {
-                    let bitwise_or_mask = flattened_mask_value.bitor(mask);
-                    flattened_mask = Some(bitwise_or_mask);
+        let mut flattened_mask: Option<BitVec> = None;
+        for possible_node_state_index in self.possible_node_state_indexes_from_popped_neighbor.iter() {
+            if let Some(mask) = popped_neighbor_collapsable_node.get_mask_at_index(*possible_node_state_index, explored_great_neighbor_node_id) {
+                if let Some(flattened_mask_value) = flattened_mask.as_mut() {
+                    flattened_mask_value.bitor_assign(mask);
                 }
                 else {
                     flattened_mask = Some(mask.clone());
                 }
             }
-            self.calculated_flattened_mask = flattened_mask;
         }
+        self.calculated_flattened_mask = flattened_mask;
     }
     fn is_flattened_mask_restrictive_to_explored_neighbor(&self) -> bool {
         if let Some(flattened_mask_value) = self.calculated_flattened_mask.as_ref() {
@@ -201,48 +231,142 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> EntropicCollapsa
         self.calculated_flattened_mask = None;
         debug!("pushed to back with length {:?}", self.cached_mask_per_neighbor_node_id.keys().len());
     }
+    /// This function applies each cached mask to its neighbor, caching the masks that then follow for the great neighbors, until none remain. Whenever none remain, the masks necessary to keep each global constraint satisfiable are cached. Returns false if any node became fully restricted or any global constraint can no longer be satisfied.
+    fn try_propagate_cached_neighbor_node_and_mask_pairs(&mut self) -> Result<bool, String> {
+        loop {
+            if self.is_cached_neighbor_node_and_mask_pairs_empty() {
+                if !self.try_cache_global_constraint_neighbor_node_and_mask_pairs() {
+                    debug!("at least one global constraint can no longer be satisfied");
+                    return Ok(false);
+                }
+                if self.is_cached_neighbor_node_and_mask_pairs_empty() {
+                    return Ok(true);
+                }
+            }
+            if self.collapse_limiter.try_iterate() {
+                return Err(self.collapse_limiter.get_error_message(self.collapsed_nodes_total, self.collapsable_nodes_length));
+            }
+            debug!("popping first neighbor node and mask");
+            self.pop_first_neighbor_node_and_mask();
+            debug!("trying to apply popped mask to neighbor node (etc.)");
+            let is_successful = self.try_apply_popped_mask_to_neighbor_node_and_collect_possible_states_and_great_neighbors();
+            if !is_successful {
+                debug!("failed to apply popped mask");
+                return Ok(false);
+            }
+            debug!("succeeded to apply popped mask and preparing to explore great neighbors");
+            self.prepare_to_explore_each_great_neighbor_of_popped_neighbor();
+            debug!("while not every great neighbor has been explored");
+            while !self.is_every_great_neighbor_explored() {
+                debug!("incrementing to next great neighbor node");
+                self.explore_next_great_neighbor_node();
+                debug!("calculate flattened mask");
+                self.calculate_flattened_mask_for_currently_explored_great_neighbor();
+                let is_restrictive = self.is_flattened_mask_restrictive_to_explored_neighbor();
+                if is_restrictive {
+                    debug!("is restrictive");
+                    self.append_explored_neighbor_and_flattened_mask_to_cache_of_neighbor_node_and_mask_pairs();
+                }
+                else {
+                    debug!("is not restrictive");
+                }
+            }
+        }
+    }
+    /// This function caches a mask for each undecided node of a global constraint that must either be in or must not be in the node states of the global constraint, returning false if any global constraint can no longer be satisfied.
+    fn try_cache_global_constraint_neighbor_node_and_mask_pairs(&mut self) -> bool {
+        for global_constraint in self.global_constraints.iter() {
+            let (decided_total, undecided_node_ids) = global_constraint.get_decided_total_and_undecided_node_ids(&self.collapsable_node_per_id);
+            if decided_total > global_constraint.maximum || decided_total + undecided_node_ids.len() < global_constraint.minimum {
+                return false;
+            }
+            let is_only_permitted: bool;
+            if decided_total == global_constraint.maximum {
+                // every other node must not be in any of the node states
+                is_only_permitted = false;
+            }
+            else if decided_total + undecided_node_ids.len() == global_constraint.minimum {
+                // every other node that could be in any of the node states must be in one of them
+                is_only_permitted = true;
+            }
+            else {
+                continue;
+            }
+            for node_id in undecided_node_ids.into_iter() {
+                let collapsable_node = self.collapsable_node_per_id.get(node_id).unwrap().borrow();
+                let mask = collapsable_node.node_state_indexed_view.get_node_states_mask(&global_constraint.node_states, is_only_permitted);
+                if collapsable_node.node_state_indexed_view.is_mask_restrictive(&mask) {
+                    if let Some(existing_mask) = self.cached_mask_per_neighbor_node_id.get_mut(node_id) {
+                        existing_mask.bitand_assign(mask);
+                    }
+                    else {
+                        self.cached_mask_per_neighbor_node_id.insert(String::from(node_id), mask);
+                    }
+                }
+            }
+        }
+        true
+    }
     fn get_collapsed_wave_function(&self) -> CollapsedWaveFunction<TNodeState> {
         let mut node_state_per_node_id: HashMap<String, TNodeState> = HashMap::new();
+        let mut node_metadata_per_node: HashMap<String, serde_json::Value> = HashMap::new();
         for wrapped_collapsable_node in self.collapsable_nodes.iter() {
             let collapsable_node = wrapped_collapsable_node.borrow();
             let node_state: TNodeState = (*collapsable_node.node_state_indexed_view.get().unwrap()).clone();
             let node_id: String = String::from(collapsable_node.id);
             debug!("established node {node_id} in state {:?}.", node_state);
+            if let Some(metadata) = collapsable_node.metadata {
+                node_metadata_per_node.insert(node_id.clone(), metadata.clone());
+            }
             node_state_per_node_id.insert(node_id, node_state);
         }
         CollapsedWaveFunction {
-            node_state_per_node_id
+            node_state_per_node_id,
+            random_seed: self.random_seed,
+            node_metadata_per_node
         }
     }
 }
 
-impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveFunction<'a, TNodeState> for EntropicCollapsableWaveFunction<'a, TNodeState> {
-    fn new(collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, collapsable_node_per_id: HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, _random_instance: Rc<RefCell<fastrand::Rng>>) -> Self {
+impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> CollapsableWaveFunction<'a, TNodeState> for EntropicCollapsableWaveFunction<'a, TNodeState> {
+    fn new(collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, collapsable_node_per_id: HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, random_seed: u64, random_instance: Rc<RefCell<fastrand::Rng>>) -> Self {
         let collapsable_nodes_length: usize = collapsable_nodes.len();
         let mut is_node_collapsed: BitVec = BitVec::new();
         for _ in 0..collapsable_nodes_length {
             is_node_collapsed.push(false);
         }
+        let collapsable_node_index_per_id: HashMap<&'a str, usize> = collapsable_nodes
+            .iter()
+            .enumerate()
+            .map(|(index, wrapped_collapsable_node)| (wrapped_collapsable_node.borrow().id, index))
+            .collect();
         EntropicCollapsableWaveFunction {
             collapsable_nodes,
             collapsable_node_per_id,
+            collapsable_node_index_per_id,
             collapsable_nodes_length,
             current_collapsable_node_index: 0,
             collapsed_nodes_total: 0,
             is_node_collapsed,
+            collapsed_node_indexes: Vec::new(),
             cached_mask_per_neighbor_node_id: IndexMap::new(),
             popped_neighbor_node_id: None,
             popped_mask: None,
-            possible_states_from_popped_neighbor: Vec::new(),
+            possible_node_state_indexes_from_popped_neighbor: Vec::new(),
             great_neighbors_from_popped_neighbor: Vec::new(),
             great_neighbors_from_popped_neighbor_length: 0,
             explored_great_neighbor_node_index: None,
-            collected_masks_for_each_possible_state_for_currently_explored_neighbor: Vec::new(),
             calculated_flattened_mask: None,
+            collapse_limiter: CollapseLimiter::new(CollapseOptions::default()),
+            collapse_progress_reporter: CollapseProgressReporter::new(),
+            node_selection_heuristic: NodeSelectionHeuristic::default(),
+            global_constraints: Vec::new(),
+            random_seed,
+            random_instance,
             node_state_type: PhantomData
         }
     }
-    fn collapse_into_steps(&'a mut self) -> Result<Vec<CollapsedNodeState<TNodeState>>, String> {
+    fn collapse_into_steps(&mut self) -> Result<Vec<CollapsedNodeState<TNodeState>>, String> {
 
         // while not yet fully collapsed and is still able to collapse
         //      find least entropic node not yet collapsed
@@ -263,17 +387,26 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
         //                      perform a bitwise OR over all of the masks
         //                      if the bitwised mask would be newly restrictive to this neighbor
         //                          append this neighbor node id and bitwise mask respectively to the pair cache
+        //          once the pair cache is empty, append the masks that keep each global constraint satisfiable to the pair cache
 
         let mut collapsed_node_states: Vec<CollapsedNodeState<TNodeState>> = Vec::new();
-        let mut is_unable_to_collapse = false;
+        self.collapse_limiter.start();
+        self.collapse_progress_reporter.start();
+        // the global constraints may restrict node states before any node is collapsed
+        let mut is_unable_to_collapse = !self.try_propagate_cached_neighbor_node_and_mask_pairs()?;
         debug!("starting main while loop");
         while !self.is_fully_collapsed() && !is_unable_to_collapse {
+            if self.collapse_limiter.try_iterate() {
+                return Err(self.collapse_limiter.get_error_message(self.collapsed_nodes_total, self.collapsable_nodes_length));
+            }
             debug!("finding least entropic collapsable node");
             self.set_current_collapsable_node_to_least_entropic_collapsable_node();
             debug!("try incrementing current collapsable node state");
-            let collapsed_node_state = self.try_increment_current_collapsable_node_state();
-            let is_successful: bool = collapsed_node_state.node_state_id.is_some();
-            collapsed_node_states.push(collapsed_node_state);
+            let is_successful: bool = self.try_increment_current_collapsable_node_state();
+            if is_successful && self.collapse_progress_reporter.assign_node() {
+                self.collapse_progress_reporter.try_report(self.get_current_collapsable_node_id(), self.collapsed_nodes_total, self.collapsable_nodes_length)?;
+            }
+            collapsed_node_states.push(self.collapsable_nodes[self.current_collapsable_node_index].borrow().get_collapsed_node_state());
             if !is_successful {
                 debug!("failed to increment node");
                 is_unable_to_collapse = true;
@@ -281,53 +414,33 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
             else {
                 debug!("succeeded to increment node and caching pairs");
                 self.cache_neighbor_node_and_mask_pairs();
-                debug!("starting neighbor node and mask pairs while loop");
-                while !self.is_cached_neighbor_node_and_mask_pairs_empty() {
-                    debug!("popping first neighbor node and mask");
-                    self.pop_first_neighbor_node_and_mask();
-                    debug!("trying to apply popped mask to neighbor node (etc.)");
-                    let is_successful = self.try_apply_popped_mask_to_neighbor_node_and_collect_possible_states_and_great_neighbors();
-                    if !is_successful {
-                        debug!("failed to apply popped mask");
-                        is_unable_to_collapse = true;
-                    }
-                    else {
-                        debug!("succeeded to apply popped mask and preparing to explore great neighbors");
-                        self.prepare_to_explore_each_great_neighbor_of_popped_neighbor();
-                        debug!("while not every great neighbor has been explored");
-                        while !self.is_every_great_neighbor_explored() {
-                            debug!("incrementing to next great neighbor node");
-                            self.explore_next_great_neighbor_node();
-                            debug!("collecting masks");
-                            self.collect_masks_for_each_possible_state_of_popped_neighbor_for_currently_explored_great_neighbor();
-                            debug!("calculate flattened mask");
-                            self.calculate_flattened_mask();
-                            let is_restrictive = self.is_flattened_mask_restrictive_to_explored_neighbor();
-                            if is_restrictive {
-                                debug!("is restrictive");
-                                self.append_explored_neighbor_and_flattened_mask_to_cache_of_neighbor_node_and_mask_pairs();
-                            }
-                            else {
-                                debug!("is not restrictive");
-                            }
-                        }
-                    }
+                debug!("propagating cached neighbor node and mask pairs");
+                if !self.try_propagate_cached_neighbor_node_and_mask_pairs()? {
+                    is_unable_to_collapse = true;
                 }
             }
         }
 
         Ok(collapsed_node_states)
     }
-    fn collapse(&'a mut self) -> Result<CollapsedWaveFunction<TNodeState>, String> {
+    fn collapse(&mut self) -> Result<CollapsedWaveFunction<TNodeState>, String> {
 
-        let mut is_unable_to_collapse = false;
+        self.collapse_limiter.start();
+        self.collapse_progress_reporter.start();
+        // the global constraints may restrict node states before any node is collapsed
+        let mut is_unable_to_collapse = !self.try_propagate_cached_neighbor_node_and_mask_pairs()?;
         debug!("starting main while loop");
         while !self.is_fully_collapsed() && !is_unable_to_collapse {
+            if self.collapse_limiter.try_iterate() {
+                return Err(self.collapse_limiter.get_error_message(self.collapsed_nodes_total, self.collapsable_nodes_length));
+            }
             debug!("finding least entropic collapsable node");
             self.set_current_collapsable_node_to_least_entropic_collapsable_node();
             debug!("try incrementing current collapsable node state");
-            let collapsed_node_state = self.try_increment_current_collapsable_node_state();
-            let is_successful: bool = collapsed_node_state.node_state_id.is_some();
+            let is_successful: bool = self.try_increment_current_collapsable_node_state();
+            if is_successful && self.collapse_progress_reporter.assign_node() {
+                self.collapse_progress_reporter.try_report(self.get_current_collapsable_node_id(), self.collapsed_nodes_total, self.collapsable_nodes_length)?;
+            }
             if !is_successful {
                 debug!("failed to increment node");
                 is_unable_to_collapse = true;
@@ -335,37 +448,9 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
             else {
                 debug!("succeeded to increment node and caching pairs");
                 self.cache_neighbor_node_and_mask_pairs();
-                debug!("starting neighbor node and mask pairs while loop");
-                while !self.is_cached_neighbor_node_and_mask_pairs_empty() {
-                    debug!("popping first neighbor node and mask");
-                    self.pop_first_neighbor_node_and_mask();
-                    debug!("trying to apply popped mask to neighbor node (etc.)");
-                    let is_successful = self.try_apply_popped_mask_to_neighbor_node_and_collect_possible_states_and_great_neighbors();
-                    if !is_successful {
-                        debug!("failed to apply popped mask");
-                        is_unable_to_collapse = true;
-                    }
-                    else {
-                        debug!("succeeded to apply popped mask and preparing to explore great neighbors");
-                        self.prepare_to_explore_each_great_neighbor_of_popped_neighbor();
-                        debug!("while not every great neighbor has been explored");
-                        while !self.is_every_great_neighbor_explored() {
-                            debug!("incrementing to next great neighbor node");
-                            self.explore_next_great_neighbor_node();
-                            debug!("collecting masks");
-                            self.collect_masks_for_each_possible_state_of_popped_neighbor_for_currently_explored_great_neighbor();
-                            debug!("calculate flattened mask");
-                            self.calculate_flattened_mask();
-                            let is_restrictive = self.is_flattened_mask_restrictive_to_explored_neighbor();
-                            if is_restrictive {
-                                debug!("is restrictive");
-                                self.append_explored_neighbor_and_flattened_mask_to_cache_of_neighbor_node_and_mask_pairs();
-                            }
-                            else {
-                                debug!("is not restrictive");
-                            }
-                        }
-                    }
+                debug!("propagating cached neighbor node and mask pairs");
+                if !self.try_propagate_cached_neighbor_node_and_mask_pairs()? {
+                    is_unable_to_collapse = true;
                 }
             }
         }
@@ -378,4 +463,78 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
             Ok(collapsed_wave_function)
         }
     }
-}
\ No newline at end of file
+    fn get_random_seed(&self) -> u64 {
+        self.random_seed
+    }
+    fn collapse_with_report(&mut self) -> (Result<CollapsedWaveFunction<TNodeState>, String>, CollapseReport) {
+        let collapsed_wave_function_result = self.collapse();
+        (collapsed_wave_function_result, self.collapse_progress_reporter.get_collapse_report(&self.collapse_limiter))
+    }
+    fn set_collapse_options(&mut self, collapse_options: CollapseOptions) {
+        self.collapse_limiter = CollapseLimiter::new(collapse_options);
+    }
+    fn set_progress_callback(&mut self, node_assignments_per_report: u64, progress_callback: CollapseProgressCallback<'a>) {
+        self.collapse_progress_reporter.set_progress_callback(node_assignments_per_report, progress_callback);
+    }
+    fn set_global_constraints(&mut self, global_constraints: Vec<CollapsableGlobalConstraint<'a, TNodeState>>) {
+        self.global_constraints = global_constraints;
+    }
+    fn snapshot(&self) -> Result<CollapseSnapshot<TNodeState>, String> {
+        let mut collapsed_node_states: Vec<(String, TNodeState)> = Vec::new();
+        for collapsed_node_index in self.collapsed_node_indexes.iter() {
+            let collapsable_node = self.collapsable_nodes[*collapsed_node_index].borrow();
+            if let Some(node_state) = collapsable_node.node_state_indexed_view.get() {
+                collapsed_node_states.push((String::from(collapsable_node.id), (*node_state).clone()));
+            }
+            else {
+                return Err(format!("Node {} could not be collapsed, so the collapse cannot be resumed.", collapsable_node.id));
+            }
+        }
+        Ok(CollapseSnapshot {
+            random_seed: self.random_seed,
+            random_state: Some(self.random_instance.borrow().get_seed()),
+            collapsed_node_states,
+            current_node_state: None,
+            excluded_node_states_per_node_id: CollapseSnapshot::get_excluded_node_states_per_node_id(&self.collapsable_nodes)
+        })
+    }
+    fn restore(&mut self, collapse_snapshot: &CollapseSnapshot<TNodeState>) -> Result<(), String> {
+        // the global constraints may restrict node states before any node is collapsed
+        if !self.try_propagate_cached_neighbor_node_and_mask_pairs()? {
+            return Err(String::from("Cannot collapse wave function."));
+        }
+        // collapse each node in the same order, which chooses the same node state given the same restrictions
+        for (node_id, node_state) in collapse_snapshot.collapsed_node_states.iter() {
+            let collapsable_node_index = self.collapsable_nodes
+                .iter()
+                .position(|wrapped_collapsable_node| wrapped_collapsable_node.borrow().id == node_id)
+                .ok_or_else(|| format!("Node {node_id} does not exist in the wave function being resumed."))?;
+            if self.is_node_collapsed[collapsable_node_index] {
+                return Err(format!("Node {node_id} is collapsed more than once in the snapshot."));
+            }
+            self.current_collapsable_node_index = collapsable_node_index;
+            self.try_increment_current_collapsable_node_state();
+            if !self.collapsable_nodes[collapsable_node_index].borrow().node_state_indexed_view.get().is_some_and(|current_node_state| *current_node_state == node_state) {
+                return Err(format!("Node {node_id} cannot be in node state {node_state:?} when resuming."));
+            }
+            self.cache_neighbor_node_and_mask_pairs();
+            if !self.try_propagate_cached_neighbor_node_and_mask_pairs()? {
+                return Err(format!("Node {node_id} cannot be in node state {node_state:?} when resuming."));
+            }
+        }
+        if let Some(random_state) = collapse_snapshot.random_state {
+            self.random_instance.borrow_mut().seed(random_state);
+        }
+        collapse_snapshot.validate_excluded_node_states(&self.collapsable_nodes)
+    }
+    fn get_collapsed_node_states(&self) -> Vec<CollapsedNodeState<TNodeState>> {
+        self.collapsed_node_indexes
+            .iter()
+            .map(|collapsed_node_index| self.collapsable_nodes[*collapsed_node_index].borrow().get_collapsed_node_state())
+            .filter(|collapsed_node_state| collapsed_node_state.node_state_id.is_some())
+            .collect()
+    }
+    fn is_aborted(&self) -> bool {
+        self.collapse_limiter.is_exceeded() || self.collapse_progress_reporter.is_aborted()
+    }
+}
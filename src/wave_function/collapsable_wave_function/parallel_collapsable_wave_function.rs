@@ -0,0 +1,215 @@
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::sync::{Mutex, atomic::{AtomicUsize, Ordering}};
+use serde::{Serialize, de::DeserializeOwned};
+use crate::wave_function::WaveFunction;
+use super::collapsable_wave_function::{CollapsableWaveFunction, CollapsedWaveFunction, CollapseOptions};
+use super::sequential_collapsable_wave_function::SequentialCollapsableWaveFunction;
+
+type CollapsedWaveFunctionResult<TNodeState> = Result<CollapsedWaveFunction<TNodeState>, String>;
+
+/// This struct collapses each group of nodes that share no constraints with any other group on its own thread, merging the collapsed node states of every group into one collapsed wave function. Each group is collapsed by a sequential collapsable wave function with a random seed derived from the random seed of this collapsable wave function, so the same random seed always produces the same collapsed wave function regardless of how many threads are used.
+pub struct ParallelCollapsableWaveFunction<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    // each partition as its own wave function, in the order that the first node of each partition appears in the original wave function
+    partition_wave_functions: Vec<WaveFunction<TNodeState>>,
+    random_seed: u64,
+    threads_total: usize,
+    collapse_options: CollapseOptions
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Serialize + DeserializeOwned + Send + Sync> ParallelCollapsableWaveFunction<TNodeState> {
+    /// This function partitions the wave function into its connected components, treating every neighbor relationship as connecting both nodes.
+    pub fn new(wave_function: &WaveFunction<TNodeState>, random_seed: Option<u64>, threads_total: usize) -> Self {
+        let partition_index_per_node_index: Vec<usize> = (0..wave_function.nodes.len()).collect();
+        Self::new_with_partition_indexes(wave_function, partition_index_per_node_index, random_seed, threads_total)
+    }
+    /// This function partitions the wave function into the provided partitions of node ids, each of which must contain every node exactly once. Partitions that are connected by a neighbor relationship or share a global constraint are collapsed together so that no constraint is split across threads.
+    pub fn new_with_partitions(wave_function: &WaveFunction<TNodeState>, partitions: Vec<Vec<String>>, random_seed: Option<u64>, threads_total: usize) -> Result<Self, String> {
+        let node_index_per_node_id: HashMap<&str, usize> = wave_function.nodes
+            .iter()
+            .enumerate()
+            .map(|(node_index, node)| (node.id.as_str(), node_index))
+            .collect();
+        let mut partition_index_per_node_index: Vec<Option<usize>> = vec![None; wave_function.nodes.len()];
+        for (partition_index, partition) in partitions.iter().enumerate() {
+            for node_id in partition.iter() {
+                let Some(node_index) = node_index_per_node_id.get(node_id.as_str()) else {
+                    return Err(format!("Partition references node {node_id} that does not exist."));
+                };
+                if partition_index_per_node_index[*node_index].replace(partition_index).is_some() {
+                    return Err(format!("Node {node_id} is in more than one partition."));
+                }
+            }
+        }
+        let mut partition_index_per_node_index_without_gaps: Vec<usize> = Vec::with_capacity(partition_index_per_node_index.len());
+        for (node, partition_index) in wave_function.nodes.iter().zip(partition_index_per_node_index) {
+            let Some(partition_index) = partition_index else {
+                return Err(format!("Node {} is not in any partition.", node.id));
+            };
+            partition_index_per_node_index_without_gaps.push(partition_index);
+        }
+        Ok(Self::new_with_partition_indexes(wave_function, partition_index_per_node_index_without_gaps, random_seed, threads_total))
+    }
+    fn new_with_partition_indexes(wave_function: &WaveFunction<TNodeState>, partition_index_per_node_index: Vec<usize>, random_seed: Option<u64>, threads_total: usize) -> Self {
+        let random_seed = random_seed.unwrap_or_else(|| fastrand::u64(..));
+        let nodes_length: usize = wave_function.nodes.len();
+        let node_index_per_node_id: HashMap<&str, usize> = wave_function.nodes
+            .iter()
+            .enumerate()
+            .map(|(node_index, node)| (node.id.as_str(), node_index))
+            .collect();
+
+        // merge every partition with the partitions of its neighbors and of the nodes that share its global constraints
+        let partitions_length: usize = partition_index_per_node_index.iter().max().map_or(0, |partition_index| partition_index + 1);
+        let mut parent_per_index: Vec<usize> = (0..partitions_length).collect();
+        let mut join = |first_node_index: usize, second_node_index: usize| {
+            let first_root = get_root(&mut parent_per_index, partition_index_per_node_index[first_node_index]);
+            let second_root = get_root(&mut parent_per_index, partition_index_per_node_index[second_node_index]);
+            if first_root != second_root {
+                parent_per_index[first_root.max(second_root)] = first_root.min(second_root);
+            }
+        };
+        for (node_index, node) in wave_function.nodes.iter().enumerate() {
+            for neighbor_node_id in node.node_state_collection_ids_per_neighbor_node_id.keys() {
+                // unknown neighbors are left for validate to report
+                if let Some(neighbor_node_index) = node_index_per_node_id.get(neighbor_node_id.as_str()) {
+                    join(node_index, *neighbor_node_index);
+                }
+            }
+        }
+        for global_constraint in wave_function.global_constraints.iter() {
+            let node_indexes: Vec<usize> = global_constraint.get_node_ids()
+                .iter()
+                .filter_map(|node_id| node_index_per_node_id.get(node_id.as_str()).cloned())
+                .collect();
+            for node_index_pair in node_indexes.windows(2) {
+                join(node_index_pair[0], node_index_pair[1]);
+            }
+        }
+
+        let mut partition_wave_function_index_per_root: HashMap<usize, usize> = HashMap::new();
+        let mut node_indexes_per_partition_wave_function_index: Vec<Vec<usize>> = Vec::new();
+        for (node_index, partition_index) in partition_index_per_node_index.iter().enumerate() {
+            let root = get_root(&mut parent_per_index, *partition_index);
+            let partition_wave_function_index = *partition_wave_function_index_per_root
+                .entry(root)
+                .or_insert_with(|| {
+                    node_indexes_per_partition_wave_function_index.push(Vec::new());
+                    node_indexes_per_partition_wave_function_index.len() - 1
+                });
+            node_indexes_per_partition_wave_function_index[partition_wave_function_index].push(node_index);
+        }
+
+        let mut partition_wave_function_index_per_node_id: HashMap<&str, usize> = HashMap::new();
+        for (partition_wave_function_index, node_indexes) in node_indexes_per_partition_wave_function_index.iter().enumerate() {
+            for node_index in node_indexes.iter() {
+                partition_wave_function_index_per_node_id.insert(&wave_function.nodes[*node_index].id, partition_wave_function_index);
+            }
+        }
+        let partition_wave_functions: Vec<WaveFunction<TNodeState>> = node_indexes_per_partition_wave_function_index
+            .iter()
+            .enumerate()
+            .map(|(partition_wave_function_index, node_indexes)| {
+                let nodes: Vec<_> = node_indexes
+                    .iter()
+                    .map(|node_index| wave_function.nodes[*node_index].clone())
+                    .collect();
+                let node_state_collection_ids: HashSet<&String> = nodes
+                    .iter()
+                    .flat_map(|node| node.node_state_collection_ids_per_neighbor_node_id.values().flatten())
+                    .collect();
+                let node_state_collections = wave_function.node_state_collections
+                    .iter()
+                    .filter(|node_state_collection| node_state_collection_ids.contains(&node_state_collection.id))
+                    .cloned()
+                    .collect();
+                let global_constraints = wave_function.global_constraints
+                    .iter()
+                    .filter(|global_constraint| {
+                        global_constraint.get_node_ids().iter().any(|node_id| partition_wave_function_index_per_node_id.get(node_id.as_str()) == Some(&partition_wave_function_index))
+                    })
+                    .cloned()
+                    .collect();
+                WaveFunction {
+                    nodes,
+                    node_state_collections,
+                    global_constraints
+                }
+            })
+            .collect();
+        debug!("partitioned {} nodes into {} partitions.", nodes_length, partition_wave_functions.len());
+
+        ParallelCollapsableWaveFunction {
+            partition_wave_functions,
+            random_seed,
+            threads_total,
+            collapse_options: CollapseOptions::default()
+        }
+    }
+    /// This is the random seed that the random seed of each partition is derived from, even if it was chosen because no random seed was provided, so that the collapse can be reproduced.
+    pub fn get_random_seed(&self) -> u64 {
+        self.random_seed
+    }
+    pub fn get_partitions_total(&self) -> usize {
+        self.partition_wave_functions.len()
+    }
+    /// These options apply to the collapse of each partition separately.
+    pub fn set_collapse_options(&mut self, collapse_options: CollapseOptions) {
+        self.collapse_options = collapse_options;
+    }
+    pub fn collapse(&self) -> Result<CollapsedWaveFunction<TNodeState>, String> {
+        let partitions_length: usize = self.partition_wave_functions.len();
+        let mut random_instance = fastrand::Rng::with_seed(self.random_seed);
+        let random_seeds: Vec<u64> = (0..partitions_length).map(|_| random_instance.u64(..)).collect();
+        let next_partition_index: AtomicUsize = AtomicUsize::new(0);
+        let collapsed_wave_function_result_per_partition_index: Mutex<Vec<Option<CollapsedWaveFunctionResult<TNodeState>>>> = Mutex::new(vec![None; partitions_length]);
+
+        std::thread::scope(|scope| {
+            for _ in 0..self.threads_total.clamp(1, partitions_length.max(1)) {
+                scope.spawn(|| {
+                    loop {
+                        let partition_index = next_partition_index.fetch_add(1, Ordering::AcqRel);
+                        if partition_index >= partitions_length {
+                            break;
+                        }
+                        let mut collapsable_wave_function = self.partition_wave_functions[partition_index].get_collapsable_wave_function::<SequentialCollapsableWaveFunction<TNodeState>>(Some(random_seeds[partition_index]));
+                        collapsable_wave_function.set_collapse_options(self.collapse_options.clone());
+                        let collapsed_wave_function_result = collapsable_wave_function.collapse();
+                        collapsed_wave_function_result_per_partition_index.lock().unwrap()[partition_index] = Some(collapsed_wave_function_result);
+                    }
+                });
+            }
+        });
+
+        let mut node_state_per_node_id: HashMap<String, TNodeState> = HashMap::new();
+        let mut node_metadata_per_node: HashMap<String, serde_json::Value> = HashMap::new();
+        for (partition_index, collapsed_wave_function_result) in collapsed_wave_function_result_per_partition_index.into_inner().unwrap().into_iter().enumerate() {
+            // report the failure of the first partition so that the error does not depend on which thread finished first
+            let collapsed_wave_function = collapsed_wave_function_result
+                .unwrap()
+                .map_err(|error_message| format!("Cannot collapse partition {partition_index} of {partitions_length}: {error_message}"))?;
+            node_state_per_node_id.extend(collapsed_wave_function.node_state_per_node_id);
+            node_metadata_per_node.extend(collapsed_wave_function.node_metadata_per_node);
+        }
+        Ok(CollapsedWaveFunction {
+            node_state_per_node_id,
+            random_seed: self.random_seed,
+            node_metadata_per_node
+        })
+    }
+}
+
+// each partition index points toward the root partition index that it was merged into
+fn get_root(parent_per_index: &mut [usize], index: usize) -> usize {
+    let mut root = index;
+    while parent_per_index[root] != root {
+        root = parent_per_index[root];
+    }
+    let mut index = index;
+    while parent_per_index[index] != root {
+        let parent = parent_per_index[index];
+        parent_per_index[index] = root;
+        index = parent;
+    }
+    root
+}
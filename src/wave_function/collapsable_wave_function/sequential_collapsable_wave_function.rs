@@ -1,77 +1,187 @@
 use std::marker::PhantomData;
-use std::{cell::RefCell, rc::Rc, collections::HashMap};
+use std::ops::BitOrAssign;
+use std::{cell::RefCell, rc::Rc, collections::{HashMap, VecDeque}};
 use std::hash::Hash;
 use bitvec::vec::BitVec;
-use super::collapsable_wave_function::{CollapsableWaveFunction, CollapsableNode, CollapsedNodeState, CollapsedWaveFunction};
+use super::collapsable_wave_function::{CollapsableWaveFunction, CollapsableGlobalConstraint, CollapsableNode, CollapsedNodeState, CollapsedWaveFunction, CollapseLimiter, CollapseOptions, CollapseProgressCallback, CollapseProgressReporter, CollapseReport, CollapseSnapshot, PropagationDepth};
 
 /// This struct represents a CollapsableWaveFunction that sequentially searches every possible state systematically. This is best for finding solutions when the condition problem has very few, one, or no solutions.
-pub struct SequentialCollapsableWaveFunction<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> {
+pub struct SequentialCollapsableWaveFunction<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
     // represents a wave function with all of the necessary steps to collapse
     collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>>,
     collapsable_node_per_id: HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>,
     collapsable_nodes_length: usize,
     current_collapsable_node_index: usize,
+    // set once collapse_next has explored every possible combination of node states
+    is_exhausted: bool,
+    collapse_limiter: CollapseLimiter,
+    collapse_progress_reporter: CollapseProgressReporter<'a>,
+    // set while the collapse limiter and progress reporter belong to a collapse that another collapsable wave function already started
+    is_collapse_started: bool,
+    global_constraints: Vec<CollapsableGlobalConstraint<'a, TNodeState>>,
+    // the nodes restricted by the lookahead while each node is in its current node state, in the order that their masks were forwarded
+    propagated_node_ids_per_collapsable_node_index: Vec<Vec<&'a str>>,
+    random_seed: u64,
     node_state_type: PhantomData<TNodeState>
 }
 
-impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> SequentialCollapsableWaveFunction<'a, TNodeState> {
-    fn try_increment_current_collapsable_node_state(&mut self) -> CollapsedNodeState<TNodeState> {
+impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> SequentialCollapsableWaveFunction<'a, TNodeState> {
+    /// This function returns if the current node was incremented to its next node state, where its node state is None otherwise.
+    fn try_increment_current_collapsable_node_state(&mut self) -> bool {
         let wrapped_current_collapsable_node = self.collapsable_nodes.get(self.current_collapsable_node_index).unwrap();
         let mut current_collapsable_node = wrapped_current_collapsable_node.borrow_mut();
 
         let is_successful = current_collapsable_node.node_state_indexed_view.try_move_next();
         if is_successful {
             current_collapsable_node.current_chosen_from_sort_index = Some(self.current_collapsable_node_index);
-            CollapsedNodeState {
-                node_id: String::from(current_collapsable_node.id),
-                node_state_id: Some((*current_collapsable_node.node_state_indexed_view.get().unwrap()).clone())
-            }
         }
         else {
             current_collapsable_node.current_chosen_from_sort_index = None;
-            CollapsedNodeState {
-                node_id: String::from(current_collapsable_node.id),
-                node_state_id: None
-            }
         }
+        is_successful
+    }
+    fn start_collapse(&mut self) {
+        if !std::mem::take(&mut self.is_collapse_started) {
+            self.collapse_limiter.start();
+            self.collapse_progress_reporter.start();
+        }
+    }
+    /// This function hands over the collapse limiter and progress reporter of a collapse that is already underway, so that the next collapse continues counting against the same limits and is described by the same report.
+    pub(crate) fn continue_collapse(&mut self, collapse_limiter: CollapseLimiter, collapse_progress_reporter: CollapseProgressReporter<'a>) {
+        self.collapse_limiter = collapse_limiter;
+        self.collapse_progress_reporter = collapse_progress_reporter;
+        self.is_collapse_started = true;
+    }
+    fn get_collapse_report(&self) -> CollapseReport {
+        self.collapse_progress_reporter.get_collapse_report(&self.collapse_limiter)
     }
-    fn try_alter_reference_to_current_collapsable_node_mask(&mut self) -> bool {
+    /// This function hands back the collapse limiter, progress reporter, and global constraints once a collapse continued by continue_collapse is done.
+    pub(crate) fn end_continued_collapse(&mut self) -> (CollapseLimiter, CollapseProgressReporter<'a>, Vec<CollapsableGlobalConstraint<'a, TNodeState>>) {
+        (
+            std::mem::replace(&mut self.collapse_limiter, CollapseLimiter::new(CollapseOptions::default())),
+            std::mem::take(&mut self.collapse_progress_reporter),
+            std::mem::take(&mut self.global_constraints)
+        )
+    }
+    fn get_current_collapsable_node_id(&self) -> &'a str {
+        self.collapsable_nodes[self.current_collapsable_node_index].borrow().id
+    }
+    /// This function returns an error if the collapse limiter is exceeded while propagating restrictions, in which case the restrictions are reverted just as if the neighbors were fully restricted.
+    fn try_alter_reference_to_current_collapsable_node_mask(&mut self) -> Result<bool, String> {
         let mut is_successful: bool = true;
-        let wrapped_current_collapsable_node = self.collapsable_nodes.get_mut(self.current_collapsable_node_index).expect("The collapsable node should exist at this index.");
+        let mut limit_error_message: Option<String> = None;
+        let mut propagated_node_ids: Vec<&'a str> = Vec::new();
+        let wrapped_current_collapsable_node = self.collapsable_nodes.get(self.current_collapsable_node_index).expect("The collapsable node should exist at this index.").clone();
         let current_collapsable_node = wrapped_current_collapsable_node.borrow();
-        if let Some(current_possible_state) = current_collapsable_node.node_state_indexed_view.get() {
+        if let Some(current_node_state_index) = current_collapsable_node.node_state_indexed_view.get_node_state_index() {
             let neighbor_node_ids: &Vec<&str> = &current_collapsable_node.neighbor_node_ids;
-            let mask_per_neighbor_per_state: &HashMap<&TNodeState, HashMap<&str, BitVec>> = &current_collapsable_node.mask_per_neighbor_per_state;
-            if let Some(mask_per_neighbor) = mask_per_neighbor_per_state.get(current_possible_state) {
-                let mut traversed_neighbor_node_ids: Vec<&str> = Vec::new();
-                for neighbor_node_id in neighbor_node_ids.iter() {
-                    if mask_per_neighbor.contains_key(neighbor_node_id) {
-                        let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
-                        let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
-                        //debug!("looking for mask from parent {:?} to child {:?}.", current_collapsable_node.id, neighbor_node_id);
-                        //debug!("mask_per_neighbor: {:?}", mask_per_neighbor);
-                        let mask = mask_per_neighbor.get(neighbor_node_id).unwrap();
-                        neighbor_collapsable_node.forward_mask(mask);
-                        debug!("adding mask to {:?} when in try_alter_reference_to_current_collapsable_node_mask", neighbor_node_id);
-                        traversed_neighbor_node_ids.push(neighbor_node_id);
-                        if neighbor_collapsable_node.is_fully_restricted() {
-                            is_successful = false;
-                            break;
-                        }
+            let mut traversed_neighbor_node_ids: Vec<&str> = Vec::new();
+            for neighbor_node_id in neighbor_node_ids.iter() {
+                if let Some(mask) = current_collapsable_node.get_mask_at_index(current_node_state_index, neighbor_node_id) {
+                    let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
+                    let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
+                    //debug!("looking for mask from parent {:?} to child {:?}.", current_collapsable_node.id, neighbor_node_id);
+                    neighbor_collapsable_node.forward_mask(mask);
+                    debug!("adding mask to {:?} when in try_alter_reference_to_current_collapsable_node_mask", neighbor_node_id);
+                    traversed_neighbor_node_ids.push(neighbor_node_id);
+                    if neighbor_collapsable_node.is_fully_restricted() {
+                        is_successful = false;
+                        break;
                     }
                 }
-                if !is_successful {
-                    // revert all of the traversed neighbors
-                    for neighbor_node_id in traversed_neighbor_node_ids.iter() {
-                        let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
-                        let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
-                        debug!("reversing mask for {:?} when in try_alter_reference_to_current_collapsable_node_mask", neighbor_node_id);
-                        neighbor_collapsable_node.reverse_mask();
+            }
+            if is_successful {
+                match self.try_propagate_restrictions(&traversed_neighbor_node_ids, &mut propagated_node_ids) {
+                    Ok(true) => {},
+                    Ok(false) => {
+                        debug!("at least one node is fully restricted by the lookahead");
+                        is_successful = false;
+                    },
+                    Err(error_message) => {
+                        limit_error_message = Some(error_message);
+                        is_successful = false;
                     }
                 }
             }
+            if is_successful && !self.global_constraints.iter().all(|global_constraint| global_constraint.is_satisfiable(&self.collapsable_node_per_id)) {
+                debug!("at least one global constraint can no longer be satisfied");
+                is_successful = false;
+            }
+            if !is_successful {
+                // revert the nodes restricted by the lookahead before the traversed neighbors since their masks were forwarded last
+                for propagated_node_id in propagated_node_ids.drain(..).rev() {
+                    self.collapsable_node_per_id.get(propagated_node_id).unwrap().borrow_mut().reverse_mask();
+                }
+                // revert all of the traversed neighbors
+                for neighbor_node_id in traversed_neighbor_node_ids.iter() {
+                    let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
+                    let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
+                    debug!("reversing mask for {:?} when in try_alter_reference_to_current_collapsable_node_mask", neighbor_node_id);
+                    neighbor_collapsable_node.reverse_mask();
+                }
+            }
+        }
+        drop(current_collapsable_node);
+        self.propagated_node_ids_per_collapsable_node_index[self.current_collapsable_node_index] = propagated_node_ids;
+        match limit_error_message {
+            Some(error_message) => Err(error_message),
+            None => Ok(is_successful)
         }
-        is_successful
+    }
+    /// This function restricts the uncollapsed neighbors of each restricted node to the node states permitted by the node states that remain possible for it, continuing from every node restricted along the way until the depth of the lookahead is reached. Each restricted node is recorded in the order that its mask was forwarded so that the masks can be reversed. Returns false once any node is fully restricted. Each restricted node taken from the queue counts as an iteration, returning an error once the collapse limiter is exceeded.
+    fn try_propagate_restrictions(&mut self, restricted_node_ids: &[&'a str], propagated_node_ids: &mut Vec<&'a str>) -> Result<bool, String> {
+        let maximum_depth: usize = match self.collapse_limiter.get_collapse_options().lookahead {
+            PropagationDepth::None => return Ok(true),
+            PropagationDepth::Neighbors => 1,
+            PropagationDepth::Full => usize::MAX
+        };
+        let mut restricted_node_ids_and_depths: VecDeque<(&'a str, usize)> = restricted_node_ids.iter().map(|restricted_node_id| (*restricted_node_id, 1)).collect();
+        while let Some((restricted_node_id, depth)) = restricted_node_ids_and_depths.pop_front() {
+            if self.collapse_limiter.try_iterate() {
+                return Err(self.collapse_limiter.get_error_message(self.current_collapsable_node_index, self.collapsable_nodes_length));
+            }
+            let restricted_collapsable_node = self.collapsable_node_per_id.get(restricted_node_id).unwrap().borrow();
+            if depth > maximum_depth || restricted_collapsable_node.node_state_indexed_view.get().is_some() {
+                continue;
+            }
+            let possible_node_state_indexes = restricted_collapsable_node.node_state_indexed_view.get_possible_node_state_indexes();
+            for neighbor_node_id in restricted_collapsable_node.neighbor_node_ids.iter() {
+                let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
+                if wrapped_neighbor_collapsable_node.borrow().node_state_indexed_view.get().is_some() {
+                    // collapsed nodes, including the current node, were already checked against the masks of their neighbors when they were collapsed
+                    continue;
+                }
+                let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
+                // a possible node state without a mask permits every node state of the neighbor
+                let mut flattened_mask: Option<BitVec> = None;
+                for possible_node_state_index in possible_node_state_indexes.iter() {
+                    let Some(mask) = restricted_collapsable_node.get_mask_at_index(*possible_node_state_index, neighbor_node_id) else {
+                        flattened_mask = None;
+                        break;
+                    };
+                    if let Some(flattened_mask_value) = flattened_mask.as_mut() {
+                        flattened_mask_value.bitor_assign(mask);
+                    }
+                    else {
+                        flattened_mask = Some(mask.clone());
+                    }
+                }
+                let Some(flattened_mask) = flattened_mask else {
+                    continue;
+                };
+                if !neighbor_collapsable_node.node_state_indexed_view.is_mask_restrictive(&flattened_mask) {
+                    continue;
+                }
+                debug!("propagating mask to {:?} when in try_propagate_restrictions", neighbor_node_id);
+                neighbor_collapsable_node.forward_mask(&flattened_mask);
+                propagated_node_ids.push(neighbor_node_id);
+                if neighbor_collapsable_node.is_fully_restricted() {
+                    return Ok(false);
+                }
+                restricted_node_ids_and_depths.push_back((neighbor_node_id, depth + 1));
+            }
+        }
+        Ok(true)
     }
     fn move_to_next_collapsable_node(&mut self) {
         let wrapped_current_collapsable_node = self.collapsable_nodes.get(self.current_collapsable_node_index).unwrap();
@@ -96,6 +206,20 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> SequentialCollap
     fn is_fully_collapsed(&self) -> bool {
         self.current_collapsable_node_index == self.collapsable_nodes_length
     }
+    fn try_move_current_collapsable_node_to_node_state(&mut self, node_id: &str, node_state: &TNodeState) -> Result<(), String> {
+        let wrapped_current_collapsable_node = self.collapsable_nodes.get(self.current_collapsable_node_index).ok_or_else(|| format!("Node {node_id} does not exist in the wave function being resumed."))?;
+        let mut current_collapsable_node = wrapped_current_collapsable_node.borrow_mut();
+        if current_collapsable_node.id != node_id {
+            return Err(format!("Node {node_id} was collapsed in the snapshot but node {} is collapsed next when resuming.", current_collapsable_node.id));
+        }
+        while !current_collapsable_node.node_state_indexed_view.get().is_some_and(|current_node_state| *current_node_state == node_state) {
+            if !current_collapsable_node.node_state_indexed_view.try_move_next() {
+                return Err(format!("Node {node_id} cannot be in node state {node_state:?} when resuming."));
+            }
+        }
+        current_collapsable_node.current_chosen_from_sort_index = Some(self.current_collapsable_node_index);
+        Ok(())
+    }
     fn try_move_to_previous_collapsable_node_neighbor(&mut self) {
 
         {
@@ -113,27 +237,31 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> SequentialCollap
             self.current_collapsable_node_index -= 1;
 
             // revert the masks of the new current collapsable node prior to the next state change/increment
-            {
-                let wrapped_current_collapsable_node = self.collapsable_nodes.get_mut(self.current_collapsable_node_index).expect("The collapsable node should exist at this index.");
-                let current_collapsable_node = wrapped_current_collapsable_node.borrow_mut();
+            self.reverse_current_collapsable_node_masks();
+        }
+            
+    }
+    fn reverse_current_collapsable_node_masks(&mut self) {
+        // the nodes restricted by the lookahead are reverted first since their masks were forwarded last
+        for propagated_node_id in std::mem::take(&mut self.propagated_node_ids_per_collapsable_node_index[self.current_collapsable_node_index]).into_iter().rev() {
+            self.collapsable_node_per_id.get(propagated_node_id).unwrap().borrow_mut().reverse_mask();
+        }
 
-                let neighbor_node_ids: &Vec<&str>;
-                if let Some(current_collapsable_node_state) = current_collapsable_node.node_state_indexed_view.get() {
-                    neighbor_node_ids = &current_collapsable_node.neighbor_node_ids;
-                    if let Some(mask_per_neighbor) = current_collapsable_node.mask_per_neighbor_per_state.get(current_collapsable_node_state) {
-                        for neighbor_node_id in neighbor_node_ids.iter() {
-                            if mask_per_neighbor.contains_key(neighbor_node_id) {
-                                let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
-                                let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
-                                debug!("reversing mask for {:?} when in try_move_to_previous_collapsable_node_neighbor", neighbor_node_id);
-                                neighbor_collapsable_node.reverse_mask();
-                            }
-                        }
-                    }
+        let wrapped_current_collapsable_node = self.collapsable_nodes.get_mut(self.current_collapsable_node_index).expect("The collapsable node should exist at this index.");
+        let current_collapsable_node = wrapped_current_collapsable_node.borrow_mut();
+
+        let neighbor_node_ids: &Vec<&str>;
+        if let Some(current_node_state_index) = current_collapsable_node.node_state_indexed_view.get_node_state_index() {
+            neighbor_node_ids = &current_collapsable_node.neighbor_node_ids;
+            for neighbor_node_id in neighbor_node_ids.iter() {
+                if current_collapsable_node.get_mask_at_index(current_node_state_index, neighbor_node_id).is_some() {
+                    let wrapped_neighbor_collapsable_node = self.collapsable_node_per_id.get(neighbor_node_id).unwrap();
+                    let mut neighbor_collapsable_node = wrapped_neighbor_collapsable_node.borrow_mut();
+                    debug!("reversing mask for {:?} when in reverse_current_collapsable_node_masks", neighbor_node_id);
+                    neighbor_collapsable_node.reverse_mask();
                 }
             }
         }
-            
     }
     fn is_fully_reset(&self) -> bool {
         if self.current_collapsable_node_index != 0 {
@@ -141,87 +269,10 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> SequentialCollap
         }
         let wrapped_current_collapsable_node = self.collapsable_nodes.get(self.current_collapsable_node_index).unwrap();
         let current_collapsable_node = wrapped_current_collapsable_node.borrow();
-        return current_collapsable_node.current_chosen_from_sort_index.is_none();
-    }
-    fn get_collapsed_wave_function(&self) -> CollapsedWaveFunction<TNodeState> {
-        let mut node_state_per_node_id: HashMap<String, TNodeState> = HashMap::new();
-        for wrapped_collapsable_node in self.collapsable_nodes.iter() {
-            let collapsable_node = wrapped_collapsable_node.borrow();
-            let node_state: TNodeState = (*collapsable_node.node_state_indexed_view.get().unwrap()).clone();
-            let node_id: String = String::from(collapsable_node.id);
-            debug!("established node {node_id} in state {:?}.", node_state);
-            node_state_per_node_id.insert(node_id, node_state);
-        }
-        CollapsedWaveFunction {
-            node_state_per_node_id
-        }
-    }
-}
-
-impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveFunction<'a, TNodeState> for SequentialCollapsableWaveFunction<'a, TNodeState> {
-    fn new(collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, collapsable_node_per_id: HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, _random_instance: Rc<RefCell<fastrand::Rng>>) -> Self {
-        let collapsable_nodes_length: usize = collapsable_nodes.len();
-
-        SequentialCollapsableWaveFunction {
-            collapsable_nodes,
-            collapsable_node_per_id,
-            collapsable_nodes_length,
-            current_collapsable_node_index: 0,
-            node_state_type: PhantomData
-        }
-    }
-    fn collapse_into_steps(&'a mut self) -> Result<Vec<CollapsedNodeState<TNodeState>>, String> {
-
-        let mut collapsed_node_states: Vec<CollapsedNodeState<TNodeState>> = Vec::new();
-
-        let mut is_unable_to_collapse = false;
-        debug!("starting while loop");
-        while !is_unable_to_collapse && !self.is_fully_collapsed() {
-            debug!("incrementing node state");
-            // the current collapsable node is either in a None state or is in a successful Some state but my neighbors are not aware
-            let collapsed_node_state = self.try_increment_current_collapsable_node_state();
-            // this will be None if the current collapsable node did not have another unmasked state that it could increment to
-            let is_successful: bool = collapsed_node_state.node_state_id.is_some();
-            collapsed_node_states.push(collapsed_node_state);
-
-            debug!("stored node state");
-            if is_successful {
-                debug!("incremented node state: {:?}", collapsed_node_states.last());
-                if self.try_alter_reference_to_current_collapsable_node_mask() {
-                    debug!("altered reference and all neighbors have at least one valid state");
-                    self.move_to_next_collapsable_node(); // this has the potential to move outside of the bounds and put the collapsable wave function in a state of being fully collapsed
-                    debug!("moved to next collapsable node");
-                    if !self.is_fully_collapsed() {
-                        debug!("not yet fully collapsed");
-                        //collapsable_wave_function.sort_collapsable_nodes();
-                        //debug!("sorted nodes");
-                    }
-                }
-                else {
-                    debug!("at least one neighbor is fully restricted");
-                }
-            }
-            else {
-                debug!("failed to incremented node");
-                self.try_move_to_previous_collapsable_node_neighbor();
-
-                if self.is_fully_reset() {
-                    debug!("moved back to first node and reset it");
-                    is_unable_to_collapse = true;
-                }
-                else {
-                    debug!("moved back to previous neighbor");
-                    //collapsable_wave_function.alter_reference_to_current_collapsable_node_mask();
-                    //debug!("stored uncollapsed_wave_function state");
-                }
-            }
-        }
-        debug!("finished while loop");
-
-        Ok(collapsed_node_states)
+        current_collapsable_node.current_chosen_from_sort_index.is_none()
     }
-
-    fn collapse(&'a mut self) -> Result<CollapsedWaveFunction<TNodeState>, String> {
+    // returns false if every possible combination of node states has been explored without fully collapsing
+    fn try_collapse(&mut self) -> Result<bool, String> {
 
         // while not yet discovered that the wave function is uncollapsable and not yet fully collapsed
         //      try to increment the state of the current node forward
@@ -234,14 +285,21 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
         //          if we ended up back at the root node and it has also been fully reset
         //              this fully explored wave function is discovered to be uncollapsable
 
+        self.start_collapse();
         let mut is_unable_to_collapse = false;
         debug!("starting while loop");
         while !is_unable_to_collapse && !self.is_fully_collapsed() {
+            if self.collapse_limiter.try_iterate() {
+                return Err(self.collapse_limiter.get_error_message(self.current_collapsable_node_index, self.collapsable_nodes_length));
+            }
             debug!("incrementing node state");
-            let is_increment_successful = self.try_increment_current_collapsable_node_state().node_state_id.is_some();
+            let is_increment_successful = self.try_increment_current_collapsable_node_state();
+            if is_increment_successful && self.collapse_progress_reporter.assign_node() {
+                self.collapse_progress_reporter.try_report(self.get_current_collapsable_node_id(), self.current_collapsable_node_index + 1, self.collapsable_nodes_length)?;
+            }
             if is_increment_successful {
                 debug!("incremented node state");
-                if self.try_alter_reference_to_current_collapsable_node_mask() {
+                if self.try_alter_reference_to_current_collapsable_node_mask()? {
                     debug!("altered reference and all neighbors have at least one valid state");
                     self.move_to_next_collapsable_node();
                     debug!("moved to next collapsable node");
@@ -252,6 +310,8 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
             }
             else {
                 debug!("failed to incremented node");
+                self.collapse_limiter.backtrack();
+                self.collapse_progress_reporter.backtrack();
                 self.try_move_to_previous_collapsable_node_neighbor();
                 if self.is_fully_reset() {
                     debug!("moved back to first node");
@@ -264,12 +324,237 @@ impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug + Ord> CollapsableWaveF
         }
         debug!("finished while loop");
 
-        if is_unable_to_collapse {
-            Err(String::from("Cannot collapse wave function."))
+        Ok(!is_unable_to_collapse)
+    }
+    /// This function performs one iteration of the collapse, returning the node state that the current node was incremented to, which is None if the current node had to be reset, along with whether the wave function is now known to be uncollapsable.
+    fn try_collapse_step(&mut self) -> Result<(CollapsedNodeState<TNodeState>, bool), String> {
+        let mut is_unable_to_collapse = false;
+        if self.collapse_limiter.try_iterate() {
+            return Err(self.collapse_limiter.get_error_message(self.current_collapsable_node_index, self.collapsable_nodes_length));
+        }
+        debug!("incrementing node state");
+        // the current collapsable node is either in a None state or is in a successful Some state but my neighbors are not aware
+        // this will be false if the current collapsable node did not have another unmasked state that it could increment to
+        let is_successful: bool = self.try_increment_current_collapsable_node_state();
+        let collapsed_node_state = self.collapsable_nodes[self.current_collapsable_node_index].borrow().get_collapsed_node_state();
+        if is_successful && self.collapse_progress_reporter.assign_node() {
+            self.collapse_progress_reporter.try_report(&collapsed_node_state.node_id, self.current_collapsable_node_index + 1, self.collapsable_nodes_length)?;
+        }
+
+        if is_successful {
+            debug!("incremented node state: {:?}", collapsed_node_state);
+            if self.try_alter_reference_to_current_collapsable_node_mask()? {
+                debug!("altered reference and all neighbors have at least one valid state");
+                self.move_to_next_collapsable_node(); // this has the potential to move outside of the bounds and put the collapsable wave function in a state of being fully collapsed
+                debug!("moved to next collapsable node");
+                if !self.is_fully_collapsed() {
+                    debug!("not yet fully collapsed");
+                    //collapsable_wave_function.sort_collapsable_nodes();
+                    //debug!("sorted nodes");
+                }
+            }
+            else {
+                debug!("at least one neighbor is fully restricted");
+            }
         }
         else {
+            debug!("failed to incremented node");
+            self.collapse_limiter.backtrack();
+            self.collapse_progress_reporter.backtrack();
+            self.try_move_to_previous_collapsable_node_neighbor();
+
+            if self.is_fully_reset() {
+                debug!("moved back to first node and reset it");
+                is_unable_to_collapse = true;
+            }
+            else {
+                debug!("moved back to previous neighbor");
+                //collapsable_wave_function.alter_reference_to_current_collapsable_node_mask();
+                //debug!("stored uncollapsed_wave_function state");
+            }
+        }
+
+        Ok((collapsed_node_state, is_unable_to_collapse))
+    }
+    /// This function returns an iterator that performs one step of the collapse per call to next, yielding the same node states as collapse_into_steps without storing them. The iterator ends once the wave function is fully collapsed or cannot be collapsed, and it can be dropped at any point to stop collapsing.
+    pub fn collapse_step_iter(&mut self) -> impl Iterator<Item = Result<CollapsedNodeState<TNodeState>, String>> + use<'_, 'a, TNodeState> {
+        self.start_collapse();
+        let mut is_unable_to_collapse = false;
+        std::iter::from_fn(move || {
+            if is_unable_to_collapse || self.is_fully_collapsed() {
+                return None;
+            }
+            match self.try_collapse_step() {
+                Ok((collapsed_node_state, is_unable_to_collapse_after_step)) => {
+                    is_unable_to_collapse = is_unable_to_collapse_after_step;
+                    Some(Ok(collapsed_node_state))
+                },
+                Err(error_message) => {
+                    // the collapse limiter stays exceeded, so stop rather than report the same error again
+                    is_unable_to_collapse = true;
+                    Some(Err(error_message))
+                }
+            }
+        })
+    }
+    /// This function continues searching from the previously found collapsed wave function, returning the next distinct collapsed wave function or None once every possible combination of node states has been explored.
+    pub fn collapse_next(&mut self) -> Result<Option<CollapsedWaveFunction<TNodeState>>, String> {
+        if self.is_exhausted {
+            return Ok(None);
+        }
+        if self.collapsable_nodes_length == 0 {
+            // the only collapsed wave function of no nodes is the empty one
+            self.is_exhausted = true;
+            return Ok(Some(self.get_collapsed_wave_function()));
+        }
+        if self.is_fully_collapsed() {
+            // move back onto the last node so that it increments past the previously found node state
+            self.current_collapsable_node_index -= 1;
+            self.reverse_current_collapsable_node_masks();
+        }
+        if self.try_collapse()? {
+            Ok(Some(self.get_collapsed_wave_function()))
+        }
+        else {
+            self.is_exhausted = true;
+            Ok(None)
+        }
+    }
+    /// This function consumes the collapsable wave function, yielding each distinct collapsed wave function exactly once. The iterator is empty if the wave function cannot be collapsed and stops after the first error.
+    pub fn collapse_into_all(mut self) -> impl Iterator<Item = Result<CollapsedWaveFunction<TNodeState>, String>> + 'a {
+        let mut is_failed = false;
+        std::iter::from_fn(move || {
+            if is_failed {
+                return None;
+            }
+            let collapse_next_result = self.collapse_next();
+            is_failed = collapse_next_result.is_err();
+            collapse_next_result.transpose()
+        })
+    }
+    fn get_collapsed_wave_function(&self) -> CollapsedWaveFunction<TNodeState> {
+        let mut node_state_per_node_id: HashMap<String, TNodeState> = HashMap::new();
+        let mut node_metadata_per_node: HashMap<String, serde_json::Value> = HashMap::new();
+        for wrapped_collapsable_node in self.collapsable_nodes.iter() {
+            let collapsable_node = wrapped_collapsable_node.borrow();
+            let node_state: TNodeState = (*collapsable_node.node_state_indexed_view.get().unwrap()).clone();
+            let node_id: String = String::from(collapsable_node.id);
+            debug!("established node {node_id} in state {:?}.", node_state);
+            if let Some(metadata) = collapsable_node.metadata {
+                node_metadata_per_node.insert(node_id.clone(), metadata.clone());
+            }
+            node_state_per_node_id.insert(node_id, node_state);
+        }
+        CollapsedWaveFunction {
+            node_state_per_node_id,
+            random_seed: self.random_seed,
+            node_metadata_per_node
+        }
+    }
+}
+
+impl<'a, TNodeState: Eq + Hash + Clone + std::fmt::Debug> CollapsableWaveFunction<'a, TNodeState> for SequentialCollapsableWaveFunction<'a, TNodeState> {
+    fn new(collapsable_nodes: Vec<Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, collapsable_node_per_id: HashMap<&'a str, Rc<RefCell<CollapsableNode<'a, TNodeState>>>>, random_seed: u64, _random_instance: Rc<RefCell<fastrand::Rng>>) -> Self {
+        let collapsable_nodes_length: usize = collapsable_nodes.len();
+
+        SequentialCollapsableWaveFunction {
+            collapsable_nodes,
+            collapsable_node_per_id,
+            collapsable_nodes_length,
+            current_collapsable_node_index: 0,
+            is_exhausted: false,
+            collapse_limiter: CollapseLimiter::new(CollapseOptions::default()),
+            collapse_progress_reporter: CollapseProgressReporter::new(),
+            is_collapse_started: false,
+            global_constraints: Vec::new(),
+            propagated_node_ids_per_collapsable_node_index: vec![Vec::new(); collapsable_nodes_length],
+            random_seed,
+            node_state_type: PhantomData
+        }
+    }
+    fn collapse_into_steps(&mut self) -> Result<Vec<CollapsedNodeState<TNodeState>>, String> {
+
+        let mut collapsed_node_states: Vec<CollapsedNodeState<TNodeState>> = Vec::new();
+
+        self.start_collapse();
+        let mut is_unable_to_collapse = false;
+        debug!("starting while loop");
+        while !is_unable_to_collapse && !self.is_fully_collapsed() {
+            let collapsed_node_state;
+            (collapsed_node_state, is_unable_to_collapse) = self.try_collapse_step()?;
+            collapsed_node_states.push(collapsed_node_state);
+        }
+        debug!("finished while loop");
+
+        Ok(collapsed_node_states)
+    }
+
+    fn collapse(&mut self) -> Result<CollapsedWaveFunction<TNodeState>, String> {
+        if self.try_collapse()? {
             let collapsed_wave_function = self.get_collapsed_wave_function();
             Ok(collapsed_wave_function)
         }
+        else {
+            Err(String::from("Cannot collapse wave function."))
+        }
+    }
+    fn get_random_seed(&self) -> u64 {
+        self.random_seed
+    }
+    fn collapse_with_report(&mut self) -> (Result<CollapsedWaveFunction<TNodeState>, String>, CollapseReport) {
+        let collapsed_wave_function_result = self.collapse();
+        (collapsed_wave_function_result, self.get_collapse_report())
+    }
+    fn set_collapse_options(&mut self, collapse_options: CollapseOptions) {
+        self.collapse_limiter = CollapseLimiter::new(collapse_options);
+    }
+    fn set_progress_callback(&mut self, node_assignments_per_report: u64, progress_callback: CollapseProgressCallback<'a>) {
+        self.collapse_progress_reporter.set_progress_callback(node_assignments_per_report, progress_callback);
+    }
+    fn set_global_constraints(&mut self, global_constraints: Vec<CollapsableGlobalConstraint<'a, TNodeState>>) {
+        self.global_constraints = global_constraints;
+    }
+    fn snapshot(&self) -> Result<CollapseSnapshot<TNodeState>, String> {
+        let mut collapsed_node_states: Vec<(String, TNodeState)> = Vec::new();
+        for wrapped_collapsable_node in self.collapsable_nodes.iter().take(self.current_collapsable_node_index) {
+            let collapsable_node = wrapped_collapsable_node.borrow();
+            let node_state: TNodeState = (*collapsable_node.node_state_indexed_view.get().unwrap()).clone();
+            collapsed_node_states.push((String::from(collapsable_node.id), node_state));
+        }
+        let current_node_state: Option<(String, TNodeState)> = self.collapsable_nodes.get(self.current_collapsable_node_index).and_then(|wrapped_current_collapsable_node| {
+            let current_collapsable_node = wrapped_current_collapsable_node.borrow();
+            current_collapsable_node.node_state_indexed_view.get().map(|node_state| (String::from(current_collapsable_node.id), (*node_state).clone()))
+        });
+        Ok(CollapseSnapshot {
+            random_seed: self.random_seed,
+            random_state: None,
+            collapsed_node_states,
+            current_node_state,
+            excluded_node_states_per_node_id: CollapseSnapshot::get_excluded_node_states_per_node_id(&self.collapsable_nodes)
+        })
+    }
+    fn restore(&mut self, collapse_snapshot: &CollapseSnapshot<TNodeState>) -> Result<(), String> {
+        // reapply each collapsed node state in order, leaving the current node in the node state that it last tried
+        for (node_id, node_state) in collapse_snapshot.collapsed_node_states.iter() {
+            self.try_move_current_collapsable_node_to_node_state(node_id, node_state)?;
+            if !self.try_alter_reference_to_current_collapsable_node_mask()? {
+                return Err(format!("Node {node_id} cannot be in node state {node_state:?} when resuming."));
+            }
+            self.move_to_next_collapsable_node();
+        }
+        if let Some((node_id, node_state)) = collapse_snapshot.current_node_state.as_ref() {
+            self.try_move_current_collapsable_node_to_node_state(node_id, node_state)?;
+        }
+        collapse_snapshot.validate_excluded_node_states(&self.collapsable_nodes)
     }
-}
\ No newline at end of file
+    fn get_collapsed_node_states(&self) -> Vec<CollapsedNodeState<TNodeState>> {
+        self.collapsable_nodes
+            .iter()
+            .take(self.current_collapsable_node_index)
+            .map(|wrapped_collapsable_node| wrapped_collapsable_node.borrow().get_collapsed_node_state())
+            .collect()
+    }
+    fn is_aborted(&self) -> bool {
+        self.collapse_limiter.is_exceeded() || self.collapse_progress_reporter.is_aborted()
+    }
+}
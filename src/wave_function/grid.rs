@@ -0,0 +1,367 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use serde::{Serialize, de::DeserializeOwned};
+use super::{NodeStateProbability, WaveFunction, collapsable_wave_function::collapsable_wave_function::CollapsedWaveFunction, wave_function_builder::WaveFunctionBuilder};
+
+/// This enum represents the direction from one grid node to its neighbor. Up decreases y and Left decreases x.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Direction {
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+impl Direction {
+    pub fn all() -> [Direction; 4] {
+        [Direction::Up, Direction::Down, Direction::Left, Direction::Right]
+    }
+    pub fn opposite(&self) -> Direction {
+        match self {
+            Direction::Up => Direction::Down,
+            Direction::Down => Direction::Up,
+            Direction::Left => Direction::Right,
+            Direction::Right => Direction::Left
+        }
+    }
+    pub(crate) fn get_offset(&self) -> (i64, i64) {
+        match self {
+            Direction::Up => (0, -1),
+            Direction::Down => (0, 1),
+            Direction::Left => (-1, 0),
+            Direction::Right => (1, 0)
+        }
+    }
+}
+
+/// This struct determines the neighbors of each node in a grid of any number of dimensions directly from the coordinate of the node, taking O(n * k) for n nodes and k neighbor offsets rather than comparing every pair of nodes. Each node is indexed with the first dimension changing fastest.
+#[derive(Debug, Clone)]
+pub struct GridTopology {
+    sizes: Vec<usize>,
+    neighbor_offsets: Vec<Vec<i64>>,
+    is_wrapping: bool
+}
+
+impl GridTopology {
+    pub fn new(sizes: Vec<usize>, neighbor_offsets: Vec<Vec<i64>>) -> Self {
+        GridTopology {
+            sizes,
+            neighbor_offsets,
+            is_wrapping: false
+        }
+    }
+
+    /// This function creates a grid where every node is a neighbor of each node that it shares an edge or face with, such as the 6 nodes surrounding a node in a three-dimensional grid.
+    pub fn new_with_orthogonal_neighbors(sizes: Vec<usize>) -> Self {
+        let mut neighbor_offsets: Vec<Vec<i64>> = Vec::new();
+        for dimension_index in 0..sizes.len() {
+            for offset in [-1, 1] {
+                let mut neighbor_offset: Vec<i64> = vec![0; sizes.len()];
+                neighbor_offset[dimension_index] = offset;
+                neighbor_offsets.push(neighbor_offset);
+            }
+        }
+        Self::new(sizes, neighbor_offsets)
+    }
+
+    /// This function creates a grid where every node is a neighbor of each node that it touches, including diagonally, such as the 26 nodes surrounding a node in a three-dimensional grid.
+    pub fn new_with_surrounding_neighbors(sizes: Vec<usize>) -> Self {
+        let mut neighbor_offsets: Vec<Vec<i64>> = vec![Vec::new()];
+        for _ in 0..sizes.len() {
+            neighbor_offsets = neighbor_offsets
+                .into_iter()
+                .flat_map(|neighbor_offset| (-1..=1).map(move |offset| {
+                    let mut neighbor_offset = neighbor_offset.clone();
+                    neighbor_offset.push(offset);
+                    neighbor_offset
+                }))
+                .collect();
+        }
+        neighbor_offsets.retain(|neighbor_offset| neighbor_offset.iter().any(|offset| *offset != 0));
+        Self::new(sizes, neighbor_offsets)
+    }
+
+    /// This function sets if the nodes on each edge of the grid are neighbors of the nodes on the opposite edge.
+    pub fn set_is_wrapping(&mut self, is_wrapping: bool) -> &mut Self {
+        self.is_wrapping = is_wrapping;
+        self
+    }
+
+    pub fn is_wrapping(&self) -> bool {
+        self.is_wrapping
+    }
+
+    pub fn get_nodes_total(&self) -> usize {
+        self.sizes.iter().product()
+    }
+
+    pub fn get_coordinate(&self, index: usize) -> Vec<usize> {
+        let mut remaining_index = index;
+        let mut coordinate: Vec<usize> = Vec::with_capacity(self.sizes.len());
+        for size in self.sizes.iter() {
+            coordinate.push(remaining_index % size);
+            remaining_index /= size;
+        }
+        coordinate
+    }
+
+    pub fn get_index(&self, coordinate: &[usize]) -> usize {
+        let mut index: usize = 0;
+        for (value, size) in coordinate.iter().zip(self.sizes.iter()).rev() {
+            index = index * size + value;
+        }
+        index
+    }
+
+    /// This function returns the coordinate at the offset from the coordinate, or None if it is outside of the grid.
+    pub fn get_neighbor_coordinate(&self, coordinate: &[usize], neighbor_offset: &[i64]) -> Option<Vec<usize>> {
+        let mut neighbor_coordinate: Vec<usize> = Vec::with_capacity(self.sizes.len());
+        for ((value, offset), size) in coordinate.iter().zip(neighbor_offset.iter()).zip(self.sizes.iter()) {
+            let mut neighbor_value = *value as i64 + offset;
+            if self.is_wrapping {
+                neighbor_value = neighbor_value.rem_euclid(*size as i64);
+            }
+            if neighbor_value < 0 || neighbor_value >= *size as i64 {
+                return None;
+            }
+            neighbor_coordinate.push(neighbor_value as usize);
+        }
+        Some(neighbor_coordinate)
+    }
+
+    /// This function returns the index of each distinct neighbor of the node at the index, in the order of the neighbor offsets.
+    pub fn get_neighbor_indexes(&self, index: usize) -> Vec<usize> {
+        let coordinate = self.get_coordinate(index);
+        let mut neighbor_indexes: Vec<usize> = Vec::with_capacity(self.neighbor_offsets.len());
+        for neighbor_offset in self.neighbor_offsets.iter() {
+            if let Some(neighbor_coordinate) = self.get_neighbor_coordinate(&coordinate, neighbor_offset) {
+                let neighbor_index = self.get_index(&neighbor_coordinate);
+                // wrapping a small grid may reach the node itself or the same neighbor from more than one offset
+                if neighbor_index != index && !neighbor_indexes.contains(&neighbor_index) {
+                    neighbor_indexes.push(neighbor_index);
+                }
+            }
+        }
+        neighbor_indexes
+    }
+}
+
+/// This struct builds a WaveFunction for a two-dimensional grid of nodes from the node states that are permitted to be next to each other in each direction. Any pairing that is not allowed is forbidden.
+pub struct GridBuilder<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    width: usize,
+    height: usize,
+    node_state_ids: Vec<TNodeState>,
+    permitted_node_state_ids_per_direction_per_node_state_id: HashMap<TNodeState, HashMap<Direction, Vec<TNodeState>>>,
+    grid_topology: GridTopology
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Serialize + DeserializeOwned> GridBuilder<TNodeState> {
+    pub fn new(width: usize, height: usize, node_state_ids: Vec<TNodeState>) -> Self {
+        GridBuilder {
+            width,
+            height,
+            node_state_ids,
+            permitted_node_state_ids_per_direction_per_node_state_id: HashMap::new(),
+            grid_topology: GridTopology::new(vec![width, height], Direction::all().iter().map(|direction| {
+                let (x_offset, y_offset) = direction.get_offset();
+                vec![x_offset, y_offset]
+            }).collect())
+        }
+    }
+
+    /// This function permits the neighbor node state to be in the direction of the node state, also permitting the node state to be in the opposite direction of the neighbor node state.
+    pub fn allow(&mut self, node_state_id: TNodeState, direction: Direction, neighbor_node_state_id: TNodeState) -> &mut Self {
+        self.permit(node_state_id.clone(), direction, neighbor_node_state_id.clone());
+        self.permit(neighbor_node_state_id, direction.opposite(), node_state_id);
+        self
+    }
+
+    fn permit(&mut self, node_state_id: TNodeState, direction: Direction, neighbor_node_state_id: TNodeState) {
+        let permitted_node_state_ids = self.permitted_node_state_ids_per_direction_per_node_state_id
+            .entry(node_state_id)
+            .or_default()
+            .entry(direction)
+            .or_default();
+        if !permitted_node_state_ids.contains(&neighbor_node_state_id) {
+            permitted_node_state_ids.push(neighbor_node_state_id);
+        }
+    }
+
+    /// This function sets if the nodes on each edge of the grid are neighbors of the nodes on the opposite edge.
+    pub fn set_is_wrapping(&mut self, is_wrapping: bool) -> &mut Self {
+        self.grid_topology.set_is_wrapping(is_wrapping);
+        self
+    }
+
+    pub fn get_node_id(x: usize, y: usize) -> String {
+        format!("{x}_{y}")
+    }
+
+    fn get_neighbor_coordinate(&self, x: usize, y: usize, direction: Direction) -> Option<(usize, usize)> {
+        let (x_offset, y_offset) = direction.get_offset();
+        self.grid_topology
+            .get_neighbor_coordinate(&[x, y], &[x_offset, y_offset])
+            .map(|neighbor_coordinate| (neighbor_coordinate[0], neighbor_coordinate[1]))
+    }
+
+    pub fn build(&self) -> Result<WaveFunction<TNodeState>, String> {
+        if self.grid_topology.is_wrapping() && (self.width < 3 || self.height < 3) {
+            return Err(format!("A wrapping grid must be at least 3 by 3 but was {} by {}.", self.width, self.height));
+        }
+
+        let mut permitted_node_state_ids_per_node_state_id_per_direction: HashMap<Direction, HashMap<TNodeState, Vec<TNodeState>>> = HashMap::new();
+        for direction in Direction::all() {
+            let mut permitted_node_state_ids_per_node_state_id: HashMap<TNodeState, Vec<TNodeState>> = HashMap::new();
+            for node_state_id in self.node_state_ids.iter() {
+                let permitted_node_state_ids: Vec<TNodeState> = self.permitted_node_state_ids_per_direction_per_node_state_id
+                    .get(node_state_id)
+                    .and_then(|permitted_node_state_ids_per_direction| permitted_node_state_ids_per_direction.get(&direction))
+                    .cloned()
+                    .unwrap_or_default();
+                permitted_node_state_ids_per_node_state_id.insert(node_state_id.clone(), permitted_node_state_ids);
+            }
+            permitted_node_state_ids_per_node_state_id_per_direction.insert(direction, permitted_node_state_ids_per_node_state_id);
+        }
+
+        let mut wave_function_builder: WaveFunctionBuilder<TNodeState> = WaveFunctionBuilder::new();
+        for x in 0..self.width {
+            for y in 0..self.height {
+                wave_function_builder.add_node(&Self::get_node_id(x, y), self.node_state_ids.clone());
+            }
+        }
+        for x in 0..self.width {
+            for y in 0..self.height {
+                let node_id = Self::get_node_id(x, y);
+                for direction in Direction::all() {
+                    if let Some((neighbor_x, neighbor_y)) = self.get_neighbor_coordinate(x, y, direction) {
+                        let permitted_node_state_ids_per_node_state_id = permitted_node_state_ids_per_node_state_id_per_direction.get(&direction).unwrap();
+                        wave_function_builder.add_constraint(&node_id, &Self::get_node_id(neighbor_x, neighbor_y), permitted_node_state_ids_per_node_state_id.clone());
+                    }
+                }
+            }
+        }
+
+        wave_function_builder.build()
+    }
+
+    /// This function maps the collapsed wave function back onto the grid, indexed by x and then y.
+    pub fn get_grid(&self, collapsed_wave_function: &CollapsedWaveFunction<TNodeState>) -> Result<Vec<Vec<TNodeState>>, String> {
+        let mut node_state_per_y_per_x: Vec<Vec<TNodeState>> = Vec::new();
+        for x in 0..self.width {
+            let mut node_state_per_y: Vec<TNodeState> = Vec::new();
+            for y in 0..self.height {
+                let node_id = Self::get_node_id(x, y);
+                let Some(node_state_id) = collapsed_wave_function.node_state_per_node_id.get(&node_id) else {
+                    return Err(format!("Collapsed wave function does not contain node {node_id}."));
+                };
+                node_state_per_y.push(node_state_id.clone());
+            }
+            node_state_per_y_per_x.push(node_state_per_y);
+        }
+        Ok(node_state_per_y_per_x)
+    }
+}
+
+/// This enum determines which nodes of a grid built by build_2d_grid or build_3d_grid are neighbors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdjacencyKind {
+    /// The 4 nodes sharing an edge with each node of a two-dimensional grid.
+    Four,
+    /// The 8 nodes touching each node of a two-dimensional grid, including diagonally.
+    Eight,
+    /// The 6 nodes sharing a face with each node of a three-dimensional grid.
+    Six,
+    /// The 26 nodes touching each node of a three-dimensional grid, including diagonally.
+    TwentySix
+}
+
+impl AdjacencyKind {
+    fn get_dimensions_total(&self) -> usize {
+        match self {
+            AdjacencyKind::Four | AdjacencyKind::Eight => 2,
+            AdjacencyKind::Six | AdjacencyKind::TwentySix => 3
+        }
+    }
+    fn get_grid_topology(&self, sizes: Vec<usize>) -> GridTopology {
+        match self {
+            AdjacencyKind::Four | AdjacencyKind::Six => GridTopology::new_with_orthogonal_neighbors(sizes),
+            AdjacencyKind::Eight | AdjacencyKind::TwentySix => GridTopology::new_with_surrounding_neighbors(sizes)
+        }
+    }
+}
+
+/// This function returns the id of the node at the coordinate of a grid built by build_2d_grid or build_3d_grid, such as "2_0_5" for x of 2, y of 0, and z of 5.
+pub fn get_coordinate_node_id(coordinate: &[usize]) -> String {
+    coordinate.iter().map(|value| value.to_string()).collect::<Vec<String>>().join("_")
+}
+
+/// This function builds a WaveFunction for a two-dimensional grid of nodes, each of which may be in any of the node states, where the node with id "x_y" is the neighbor of each node adjacent to it. The neighbor in the direction of each offset, such as [1, 0] for the neighbor with a greater x or [-1, -1] for the neighbor with a lesser x and y, is permitted to be in the node states returned by get_permitted_node_state_ids for each node state and offset. Identical node state collections are shared by every node.
+pub fn build_2d_grid<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Serialize + DeserializeOwned>(width: usize, height: usize, node_state_ids: Vec<TNodeState>, adjacency_kind: AdjacencyKind, get_permitted_node_state_ids: impl Fn(&TNodeState, &[i64]) -> Vec<TNodeState>) -> Result<WaveFunction<TNodeState>, String> {
+    build_grid(vec![width, height], NodeStateProbability::get_equal_probability(&node_state_ids), adjacency_kind, get_permitted_node_state_ids)
+}
+
+/// This function builds a WaveFunction for a two-dimensional grid of nodes just like build_2d_grid, where each node is in each node state in proportion to its ratio.
+pub fn build_2d_grid_with_probabilities<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Serialize + DeserializeOwned>(width: usize, height: usize, node_state_ratio_per_node_state_id: HashMap<TNodeState, f64>, adjacency_kind: AdjacencyKind, get_permitted_node_state_ids: impl Fn(&TNodeState, &[i64]) -> Vec<TNodeState>) -> Result<WaveFunction<TNodeState>, String> {
+    build_grid(vec![width, height], node_state_ratio_per_node_state_id, adjacency_kind, get_permitted_node_state_ids)
+}
+
+/// This function builds a WaveFunction for a three-dimensional grid of nodes just like build_2d_grid, where the node with id "x_y_z" is the neighbor of each node adjacent to it and each offset has three values.
+pub fn build_3d_grid<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Serialize + DeserializeOwned>(width: usize, height: usize, depth: usize, node_state_ids: Vec<TNodeState>, adjacency_kind: AdjacencyKind, get_permitted_node_state_ids: impl Fn(&TNodeState, &[i64]) -> Vec<TNodeState>) -> Result<WaveFunction<TNodeState>, String> {
+    build_grid(vec![width, height, depth], NodeStateProbability::get_equal_probability(&node_state_ids), adjacency_kind, get_permitted_node_state_ids)
+}
+
+fn build_grid<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Serialize + DeserializeOwned>(sizes: Vec<usize>, node_state_ratio_per_node_state_id: HashMap<TNodeState, f64>, adjacency_kind: AdjacencyKind, get_permitted_node_state_ids: impl Fn(&TNodeState, &[i64]) -> Vec<TNodeState>) -> Result<WaveFunction<TNodeState>, String> {
+    if adjacency_kind.get_dimensions_total() != sizes.len() {
+        return Err(format!("Adjacency {adjacency_kind:?} cannot be used for a grid of {} dimensions.", sizes.len()));
+    }
+    let grid_topology = adjacency_kind.get_grid_topology(sizes);
+
+    // the permitted node states only depend on the offset, so they are determined once per offset
+    let permitted_node_state_ids_per_node_state_id_per_neighbor_offset_index: Vec<HashMap<TNodeState, Vec<TNodeState>>> = grid_topology.neighbor_offsets
+        .iter()
+        .map(|neighbor_offset| {
+            node_state_ratio_per_node_state_id
+                .keys()
+                .map(|node_state_id| (node_state_id.clone(), get_permitted_node_state_ids(node_state_id, neighbor_offset)))
+                .collect()
+        })
+        .collect();
+
+    let mut wave_function_builder: WaveFunctionBuilder<TNodeState> = WaveFunctionBuilder::new();
+    for index in 0..grid_topology.get_nodes_total() {
+        wave_function_builder.add_node_with_probabilities(&get_coordinate_node_id(&grid_topology.get_coordinate(index)), node_state_ratio_per_node_state_id.clone());
+    }
+    for index in 0..grid_topology.get_nodes_total() {
+        let coordinate = grid_topology.get_coordinate(index);
+        let node_id = get_coordinate_node_id(&coordinate);
+        for (neighbor_offset, permitted_node_state_ids_per_node_state_id) in grid_topology.neighbor_offsets.iter().zip(permitted_node_state_ids_per_node_state_id_per_neighbor_offset_index.iter()) {
+            if let Some(neighbor_coordinate) = grid_topology.get_neighbor_coordinate(&coordinate, neighbor_offset) {
+                wave_function_builder.add_constraint(&node_id, &get_coordinate_node_id(&neighbor_coordinate), permitted_node_state_ids_per_node_state_id.clone());
+            }
+        }
+    }
+
+    wave_function_builder.build()
+}
+
+fn get_collapsed_node_state<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(collapsed_wave_function: &CollapsedWaveFunction<TNodeState>, coordinate: &[usize]) -> Result<TNodeState, String> {
+    let node_id = get_coordinate_node_id(coordinate);
+    collapsed_wave_function.node_state_per_node_id
+        .get(&node_id)
+        .cloned()
+        .ok_or_else(|| format!("Collapsed wave function does not contain node {node_id}."))
+}
+
+/// This function maps the collapsed wave function of a grid built by build_2d_grid back onto the grid, indexed by x and then y.
+pub fn get_2d_grid<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(collapsed_wave_function: &CollapsedWaveFunction<TNodeState>, width: usize, height: usize) -> Result<Vec<Vec<TNodeState>>, String> {
+    (0..width)
+        .map(|x| (0..height).map(|y| get_collapsed_node_state(collapsed_wave_function, &[x, y])).collect())
+        .collect()
+}
+
+/// This function maps the collapsed wave function of a grid built by build_3d_grid back onto the grid, indexed by x, then y, and then z.
+pub fn get_3d_grid<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(collapsed_wave_function: &CollapsedWaveFunction<TNodeState>, width: usize, height: usize, depth: usize) -> Result<Vec<Vec<Vec<TNodeState>>>, String> {
+    (0..width)
+        .map(|x| (0..height).map(|y| (0..depth).map(|z| get_collapsed_node_state(collapsed_wave_function, &[x, y, z])).collect()).collect())
+        .collect()
+}
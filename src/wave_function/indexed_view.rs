@@ -2,21 +2,22 @@ use std::collections::VecDeque;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::collections::HashMap;
+use std::collections::hash_map::Entry;
 use bitvec::prelude::*;
 use crate::wave_function::probability_container::ProbabilityContainer;
 
 /// This struct represents a stashed state of the IndexedView.
 pub struct IndexedViewMaskState {
     mask_counter: Vec<u32>,
-    is_restricted_at_index: BitVec
+    is_restricted_at_index: BitVec,
+    mask_per_source_id: HashMap<String, BitVec>
 }
 
 /// This struct represents a collection that can be incremented from an unstarted state to each sequential state provided. As masks are provided that either restrict or permit certain states, they will be skipped when performing try_move_next.
 pub struct IndexedView<TNodeState: Clone + Eq + Hash + Debug> {
     // items are states of the node
     node_state_ids: Vec<TNodeState>,
-    node_state_ratios: Vec<f32>,
-    index_per_node_state_id: HashMap<TNodeState, usize>,
+    node_state_ratios: Vec<f64>,
     node_state_ids_length: usize,
     index: Option<usize>,
     index_mapping: Vec<usize>,
@@ -26,18 +27,19 @@ pub struct IndexedView<TNodeState: Clone + Eq + Hash + Debug> {
     is_fully_restricted: bool,
     previous_mask_counters: VecDeque<Vec<u32>>,
     previous_is_restricted_at_index: VecDeque<BitVec>,
-    entropy: Option<f32>
+    // the mask currently applied by each source so that it can be removed without affecting the masks of other sources
+    mask_per_source_id: HashMap<String, BitVec>,
+    previous_mask_per_source_ids: VecDeque<HashMap<String, BitVec>>,
+    entropy: Option<f64>
 }
 
-impl<TNodeState: Clone + Ord + Eq + Hash + Debug> IndexedView<TNodeState> {
-    pub fn new(node_state_ids: Vec<TNodeState>, node_state_ratios: Vec<f32>) -> Self {
+impl<TNodeState: Clone + Eq + Hash + Debug> IndexedView<TNodeState> {
+    pub fn new(node_state_ids: Vec<TNodeState>, node_state_ratios: Vec<f64>) -> Self {
         let node_state_ids_length: usize = node_state_ids.len();
-        let mut index_per_node_state_id: HashMap<TNodeState, usize> = HashMap::new();
         let mut index_mapping = Vec::new();
         let mut mask_counter: Vec<u32> = Vec::new();
         let mut is_restricted_at_index: BitVec = BitVec::new();
-        for (index, node_state_id) in node_state_ids.iter().enumerate() {
-            index_per_node_state_id.insert(node_state_id.clone(), index);
+        for index in 0..node_state_ids_length {
             index_mapping.push(index);
             mask_counter.push(0);
             is_restricted_at_index.push(false);
@@ -45,7 +47,6 @@ impl<TNodeState: Clone + Ord + Eq + Hash + Debug> IndexedView<TNodeState> {
         IndexedView {
             node_state_ids,
             node_state_ratios,
-            index_per_node_state_id,
             node_state_ids_length,
             index: Option::None,
             index_mapping,
@@ -55,9 +56,12 @@ impl<TNodeState: Clone + Ord + Eq + Hash + Debug> IndexedView<TNodeState> {
             is_fully_restricted: false,
             previous_mask_counters: VecDeque::new(),
             previous_is_restricted_at_index: VecDeque::new(),
+            mask_per_source_id: HashMap::new(),
+            previous_mask_per_source_ids: VecDeque::new(),
             entropy: None
         }
     }
+    /// This function orders the node states by repeatedly drawing from the remaining node states in proportion to their ratios. Within such an order, the first node state of any subset is itself drawn in proportion to the ratios of that subset, so the node states later restricted by masks do not bias which of the unrestricted node states try_move_next reaches first.
     pub fn shuffle(&mut self, random_instance: &mut fastrand::Rng) {
         if self.index.is_some() {
             panic!("Can only be shuffled prior to use.");
@@ -65,17 +69,22 @@ impl<TNodeState: Clone + Ord + Eq + Hash + Debug> IndexedView<TNodeState> {
 
         self.index_mapping.clear();
         let mut probability_container = ProbabilityContainer::default();
-        for (node_state_id, ratio) in std::iter::zip(self.node_state_ids.iter(), self.node_state_ratios.iter()) {
-            probability_container.push(node_state_id, *ratio);
+        for (index, ratio) in self.node_state_ratios.iter().enumerate() {
+            probability_container.push(index, *ratio);
         }
 
         for _ in 0..self.node_state_ids_length {
-            let node_state_id = probability_container.pop_random(random_instance).unwrap();
-            self.index_mapping.push(*self.index_per_node_state_id.get(node_state_id).unwrap());
+            let index = probability_container.pop_random(random_instance).unwrap();
+            self.index_mapping.push(index);
         }
 
         debug!("randomized index mapping to {:?}.", self.index_mapping);
     }
+    /// This function resets the view and then shuffles it into a new order, keeping every mask.
+    pub fn reshuffle(&mut self, random_instance: &mut fastrand::Rng) {
+        self.reset();
+        self.shuffle(random_instance);
+    }
     pub fn try_move_next(&mut self) -> bool {
         let mut is_unmasked = false;
         let mut next_index: usize;
@@ -129,7 +138,7 @@ impl<TNodeState: Clone + Ord + Eq + Hash + Debug> IndexedView<TNodeState> {
             debug!("trying to get next state while cycling starting with None and cycling at {}.", self.node_state_ids_length);
         }
 
-        let terminal_node_state_index: usize = *self.index_per_node_state_id.get(terminal_node_state).unwrap();
+        let terminal_node_state_index: usize = self.get_node_state_index_of(terminal_node_state).unwrap();
 
         let mut is_incremented_at_least_once: bool = false;
         let mut is_current_state_terminal_node_state: bool = false;
@@ -215,6 +224,22 @@ impl<TNodeState: Clone + Ord + Eq + Hash + Debug> IndexedView<TNodeState> {
         }
         value
     }
+    /// This function returns the position of the current node state within the node states that this view was constructed with, if in a node state.
+    pub fn get_node_state_index(&self) -> Option<usize> {
+        match self.index {
+            Some(index) if index != self.node_state_ids_length => Some(self.index_mapping[index]),
+            _ => None
+        }
+    }
+    /// This function returns the position of the node state within the node states that this view was constructed with, if it is one of them. The node states are searched rather than hashed since the hot paths only refer to node states by their position.
+    pub fn get_node_state_index_of(&self, node_state: &TNodeState) -> Option<usize> {
+        self.node_state_ids.iter().position(|node_state_id| node_state_id == node_state)
+    }
+    /// This function returns the node state at the position within the node states that this view was constructed with.
+    pub fn get_node_state_at_index(&self, node_state_index: usize) -> &TNodeState {
+        &self.node_state_ids[node_state_index]
+    }
+    /// This function returns the view to its unstarted state so that try_move_next visits the node states again in the same order.
     pub fn reset(&mut self) {
         self.index = Option::None;
         // NOTE: the mask_counter should not be fully reverted to ensure that the neighbor restrictions are still being considered
@@ -236,92 +261,155 @@ impl<TNodeState: Clone + Ord + Eq + Hash + Debug> IndexedView<TNodeState> {
     }
     pub fn add_mask(&mut self, mask: &BitVec) {
         //debug!("adding mask {:?} at current state {:?}.", mask, self.mask_counter);
-        for index in 0..self.node_state_ids_length {
-            if !mask[index] {
-                //debug!("adding mask at {index}");
-                let next_mask_counter = self.mask_counter[index] + 1;
-                self.mask_counter[index] = next_mask_counter;
-                if next_mask_counter == 1 {
-                    self.is_restricted_at_index.set(index, true);
-                    self.is_mask_dirty = true;
-                    self.entropy = None;
-                }
+        // only the restricted node states of the mask need to be visited
+        for index in mask[..self.node_state_ids_length].iter_zeros() {
+            //debug!("adding mask at {index}");
+            let next_mask_counter = self.mask_counter[index] + 1;
+            self.mask_counter[index] = next_mask_counter;
+            if next_mask_counter == 1 {
+                self.is_restricted_at_index.set(index, true);
+                self.is_mask_dirty = true;
+                self.entropy = None;
             }
         }
         //debug!("added mask {:?} at current state {:?}.", mask, self.mask_counter);
     }
     pub fn subtract_mask(&mut self, mask: &BitVec) {
         //debug!("removing mask {:?} at current state {:?}.", mask, self.mask_counter);
-        for index in 0..self.node_state_ids_length {
-            if !mask[index] {
-                //debug!("removing mask at {index}");
-                let next_mask_counter = self.mask_counter[index] - 1;
-                self.mask_counter[index] = next_mask_counter;
-                if next_mask_counter == 0 {
-                    self.is_restricted_at_index.set(index, false);
-                    self.is_mask_dirty = true;
-                    self.entropy = None;
-                }
+        for index in mask[..self.node_state_ids_length].iter_zeros() {
+            //debug!("removing mask at {index}");
+            let next_mask_counter = self.mask_counter[index] - 1;
+            self.mask_counter[index] = next_mask_counter;
+            if next_mask_counter == 0 {
+                self.is_restricted_at_index.set(index, false);
+                self.is_mask_dirty = true;
+                self.entropy = None;
             }
         }
         //debug!("removed mask {:?} at current state {:?}.", mask, self.mask_counter);
     }
+    /// This function applies the mask on behalf of the source, replacing any mask that the source previously applied. Node states restricted by other sources remain restricted.
+    #[allow(dead_code)]
+    pub fn add_mask_from_source(&mut self, source_id: &str, mask: &BitVec) {
+        self.remove_mask_from_source(source_id);
+        self.add_mask(mask);
+        self.mask_per_source_id.insert(String::from(source_id), mask.clone());
+    }
+    /// This function removes the mask previously applied on behalf of the source, returning false if the source has not applied a mask.
+    #[allow(dead_code)]
+    pub fn remove_mask_from_source(&mut self, source_id: &str) -> bool {
+        if let Some(mask) = self.mask_per_source_id.remove(source_id) {
+            self.subtract_mask(&mask);
+            true
+        }
+        else {
+            false
+        }
+    }
     pub fn forward_mask(&mut self, mask: &BitVec) {
         self.previous_mask_counters.push_back(self.mask_counter.clone());
         self.previous_is_restricted_at_index.push_back(self.is_restricted_at_index.clone());
+        self.previous_mask_per_source_ids.push_back(self.mask_per_source_id.clone());
         self.add_mask(mask);
     }
     pub fn reverse_mask(&mut self) {
         //debug!("removing mask {:?} at current state {:?}.", mask, self.mask_counter);
         self.mask_counter = self.previous_mask_counters.pop_back().unwrap();
         self.is_restricted_at_index = self.previous_is_restricted_at_index.pop_back().unwrap();
+        self.mask_per_source_id = self.previous_mask_per_source_ids.pop_back().unwrap();
         self.is_fully_restricted = false;  // any movement backwards is to a non-restricted state
         self.entropy = None;
         //debug!("removed mask {:?} at current state {:?}.", mask, self.mask_counter);
     }
     /// This function will return if the provided mask would change the restrictions of this indexed view
     pub fn is_mask_restrictive(&self, mask: &BitVec) -> bool {
-        let mut is_at_least_one_bit_updated = false;
-        for index in 0..self.node_state_ids_length {
-            if !mask[index] && !self.is_restricted_at_index[index] {
-                is_at_least_one_bit_updated = true;
-                break;
-            }
-        }
-        is_at_least_one_bit_updated
+        // the mask is restrictive if it does not permit every node state that is not yet restricted
+        let mut is_permitted_or_restricted_at_index = mask[..self.node_state_ids_length].to_bitvec();
+        is_permitted_or_restricted_at_index |= &self.is_restricted_at_index;
+        !is_permitted_or_restricted_at_index.all()
     }
     pub fn stash_mask_state(&mut self) -> IndexedViewMaskState {
         let indexed_view_mask_state = IndexedViewMaskState {
             mask_counter: self.mask_counter.clone(),
-            is_restricted_at_index: self.is_restricted_at_index.clone()
+            is_restricted_at_index: self.is_restricted_at_index.clone(),
+            mask_per_source_id: std::mem::take(&mut self.mask_per_source_id)
         };
-        for index in 0..self.node_state_ids_length {
-            self.mask_counter[index] = 0;
-            self.is_restricted_at_index.set(index, false);
-        }
+        self.mask_counter.fill(0);
+        self.is_restricted_at_index.fill(false);
         self.is_mask_dirty = true;
         indexed_view_mask_state
     }
     pub fn unstash_mask_state(&mut self, mask_state: &mut IndexedViewMaskState) {
-        for index in 0..self.node_state_ids_length {
-            self.mask_counter[index] += mask_state.mask_counter[index];
-            let is_restricted_at_index: bool = self.is_restricted_at_index[index];
-            self.is_restricted_at_index.set(index, is_restricted_at_index || mask_state.is_restricted_at_index[index]);
-
-            mask_state.mask_counter[index] = 0;
-            mask_state.is_restricted_at_index.set(index, false);
+        for (mask_counter, stashed_mask_counter) in self.mask_counter.iter_mut().zip(mask_state.mask_counter.iter_mut()) {
+            *mask_counter += *stashed_mask_counter;
+            *stashed_mask_counter = 0;
         }
+        self.is_restricted_at_index |= &mask_state.is_restricted_at_index;
+        mask_state.is_restricted_at_index.fill(false);
         self.is_mask_dirty = true;
+        // a source that applied a mask since the stash keeps its newer mask, just as if it had been applied with add_mask_from_source
+        for (source_id, mask) in mask_state.mask_per_source_id.drain() {
+            let superseded_mask = match self.mask_per_source_id.entry(source_id) {
+                Entry::Occupied(_) => Some(mask),
+                Entry::Vacant(entry) => {
+                    entry.insert(mask);
+                    None
+                }
+            };
+            if let Some(superseded_mask) = superseded_mask {
+                self.subtract_mask(&superseded_mask);
+            }
+        }
     }
     pub fn is_fully_unmasked(&self) -> bool {
-        let mut is_masked = false;
-        for index in 0..self.node_state_ids_length {
-            if self.mask_counter[index] != 0 {
-                is_masked = true;
-                break;
+        // a node state is restricted exactly when at least one mask is counted against it
+        self.is_restricted_at_index.not_any()
+    }
+    pub fn get_unrestricted_node_states_total(&self) -> usize {
+        self.node_state_ids_length - self.is_restricted_at_index.count_ones()
+    }
+    /// This function returns how many more times try_move_next would succeed if the masks do not change.
+    pub fn get_remaining_node_states_total(&self) -> usize {
+        let next_index = match self.index {
+            Some(index) => (index + 1).min(self.node_state_ids_length),
+            None => 0
+        };
+        (next_index..self.node_state_ids_length)
+            .filter(|index| self.is_unmasked_at_index(*index))
+            .count()
+    }
+    /// This function returns if the node state is one of the node states of this view and is not currently restricted by any mask.
+    pub fn is_node_state_unrestricted(&self, node_state: &TNodeState) -> bool {
+        if let Some(index) = self.get_node_state_index_of(node_state) {
+            !self.is_restricted_at_index[index]
+        }
+        else {
+            false
+        }
+    }
+    /// This function returns a mask that either permits only the provided node states or restricts only the provided node states.
+    pub fn get_node_states_mask(&self, node_states: &[TNodeState], is_only_permitted: bool) -> BitVec {
+        let mut mask: BitVec = BitVec::repeat(!is_only_permitted, self.node_state_ids_length);
+        for node_state in node_states.iter() {
+            if let Some(index) = self.get_node_state_index_of(node_state) {
+                mask.set(index, is_only_permitted);
             }
         }
-        !is_masked
+        mask
+    }
+    /// This function returns the ratio of the current node state relative to the ratios of every node state, regardless of any restrictions.
+    pub fn get_probability(&self) -> Option<f64> {
+        let index = self.index?;
+        if index == self.node_state_ids_length {
+            return None;
+        }
+        let ratios_total: f64 = self.node_state_ratios.iter().sum();
+        if ratios_total == 0.0 {
+            Some(0.0)
+        }
+        else {
+            Some(self.node_state_ratios[self.index_mapping[index]] / ratios_total)
+        }
     }
     pub fn get_mask_density(&self) -> u32 {
         let mut mask_density = 0;
@@ -330,38 +418,38 @@ impl<TNodeState: Clone + Ord + Eq + Hash + Debug> IndexedView<TNodeState> {
         }
         mask_density
     }
-    pub fn entropy(&mut self) -> f32 {
+    pub fn entropy(&mut self) -> f64 {
         if self.entropy.is_none() {
-            let mut weights_total: f32 = 0.0;
-            let mut weights_times_log_weights_total: f32 = 0.0;
-            for index in 0..self.node_state_ids_length {
-                if !self.is_restricted_at_index[index] {
-                    let weight = self.node_state_ratios[index];
-                    let log_weight = weight.ln();
-                    weights_total += weight;
-                    weights_times_log_weights_total += weight * log_weight;
-                }
+            let mut weights_total: f64 = 0.0;
+            let mut weights_times_log_weights_total: f64 = 0.0;
+            for index in self.is_restricted_at_index.iter_zeros() {
+                let weight = self.node_state_ratios[index];
+                let log_weight = weight.ln();
+                weights_total += weight;
+                weights_times_log_weights_total += weight * log_weight;
             }
             self.entropy = Some(weights_total.ln() - weights_times_log_weights_total / weights_total);
         }
         self.entropy.unwrap()
     }
-    pub fn get_possible_states(&self) -> Vec<TNodeState> {
-        let mut possible_states: Vec<TNodeState> = Vec::new();
+    /// This function returns every node state restricted by a mask, regardless of the current node state.
+    pub fn get_restricted_node_states(&self) -> Vec<TNodeState> {
+        self.is_restricted_at_index.iter_ones().map(|index| self.node_state_ids[index].clone()).collect()
+    }
+    /// This function returns the positions of the node states that get_possible_states would return within the node states that this view was constructed with.
+    pub fn get_possible_node_state_indexes(&self) -> Vec<usize> {
         if let Some(index) = self.index {
-            let mapped_index = self.index_mapping[index];
-            let node_state = self.node_state_ids.get(mapped_index).unwrap();
-            possible_states.push(node_state.clone());
+            vec![self.index_mapping[index]]
         }
         else {
-            for index in 0..self.node_state_ids_length {
-                if !self.is_restricted_at_index[index] {
-                    let node_state = self.node_state_ids.get(index).unwrap();
-                    possible_states.push(node_state.clone());
-                }
-            }
+            self.is_restricted_at_index.iter_zeros().collect()
         }
-        possible_states
+    }
+    pub fn get_possible_states(&self) -> Vec<TNodeState> {
+        self.get_possible_node_state_indexes()
+            .into_iter()
+            .map(|index| self.node_state_ids[index].clone())
+            .collect()
     }
 }
 
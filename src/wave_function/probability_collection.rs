@@ -1,29 +1,31 @@
 use std::{fmt::Debug, collections::HashMap};
 use std::hash::Hash;
+use crate::wave_function::get_node_state_order_key;
 
 /// This struct is optimized better than ProbabilityContainer to remove a random item but does not permit searching for a random item.
 #[allow(dead_code)]
 pub struct ProbabilityCollection<T> {
-    probability_total: f32,
+    probability_total: f64,
     items_total: u32,
-    probability_per_item: HashMap<T, f32>,
+    probability_per_item: HashMap<T, f64>,
     items: Vec<T>
 }
 
 #[allow(dead_code)]
-impl<T: Ord + Eq + Hash + Clone + Debug> ProbabilityCollection<T> {
-    pub fn new(probability_per_item: HashMap<T, f32>) -> Self {
+impl<T: Eq + Hash + Clone + Debug> ProbabilityCollection<T> {
+    pub fn new(probability_per_item: HashMap<T, f64>) -> Self {
         let mut probability_total = 0.0;
-        let mut items_total: u32 = 0;
-        let mut items: Vec<T> = probability_per_item.keys().cloned().collect::<Vec<T>>();
-        items.sort();
+        // items without any probability are never chosen
+        let mut items: Vec<T> = probability_per_item
+            .iter()
+            .filter(|(_, probability)| **probability != 0.0)
+            .map(|(item, _)| item.clone())
+            .collect::<Vec<T>>();
+        items.sort_by_key(get_node_state_order_key);
         for item in items.iter() {
-            let probability = &probability_per_item[item];
-            if probability != &0.0 {
-                probability_total += probability;
-                items_total += 1;
-            }
+            probability_total += probability_per_item[item];
         }
+        let items_total: u32 = items.len() as u32;
         ProbabilityCollection {
             probability_total,
             items_total,
@@ -47,7 +49,12 @@ impl<T: Ord + Eq + Hash + Clone + Debug> ProbabilityCollection<T> {
             item_option
         }
         else {
-            let random_value = random_instance.f32() * self.probability_total;
+            // summing the remaining probabilities in the same order as they are searched ensures that the random value is always reached, no matter how small the remaining probabilities are compared to those already removed
+            self.probability_total = self.items
+                .iter()
+                .map(|item| self.probability_per_item.get(item).unwrap())
+                .sum();
+            let random_value = random_instance.f64() * self.probability_total;
             debug!("random_value: {:?}", random_value);
             let mut current_probability = 0.0;
             let mut found_item_index: Option<usize> = None;
@@ -1,220 +1,206 @@
-use std::{collections::{BTreeMap, HashMap}, fmt::Debug};
-use ordered_float::OrderedFloat;
+use std::{collections::HashMap, fmt::Debug};
 use std::hash::Hash;
+use crate::wave_function::get_node_state_order_key;
 
+// the tree is only rebuilt once there are more removed items than remaining items, and at least this many removed items
+const REMOVED_ITEMS_TOTAL_BEFORE_REBUILD: usize = 64;
+// the tree is also rebuilt once the remaining probability is this small relative to the largest probability total since it was built, since the floating point error of each cumulative probability is relative to that largest total
+const REMAINING_PROBABILITY_RATIO_BEFORE_REBUILD: f64 = 1e-9;
+
+/// This struct permits randomly choosing an item based on its probability, keeping the cumulative probabilities in a Fenwick tree so that peeking, popping, and altering items are O(log n).
 pub struct ProbabilityContainer<T> {
-    probability_total: f32,
-    items_total: u32,
-    probability_per_item: HashMap<T, f32>,
-    items: Vec<T>,
-    item_index_per_cumulative_probability: BTreeMap<OrderedFloat<f32>, usize>,
-    last_item_index_to_apply_to_item_index_per_cumulative_probability: usize,
-    last_cumulative_probability: f32
+    probability_total: f64,
+    largest_probability_total: f64,
+    items_total: usize,
+    // removed items remain as None until the tree is rebuilt so that the index of each item is stable
+    items: Vec<Option<T>>,
+    probabilities: Vec<f64>,
+    item_index_per_item: HashMap<T, usize>,
+    // each element holds the sum of the probabilities for the range of items that it is responsible for
+    cumulative_probability_tree: Vec<f64>
 }
 
-impl<T: Ord + Eq + Hash + Clone + Debug> ProbabilityContainer<T> {
+impl<T: Eq + Hash + Clone + Debug> ProbabilityContainer<T> {
     pub fn default() -> Self {
-        let probability_per_item: HashMap<T, f32> = HashMap::new();
         ProbabilityContainer {
             probability_total: 0.0,
+            largest_probability_total: 0.0,
             items_total: 0,
-            probability_per_item,
             items: Vec::new(),
-            item_index_per_cumulative_probability: BTreeMap::new(),
-            last_item_index_to_apply_to_item_index_per_cumulative_probability: 0,
-            last_cumulative_probability: 0.0
+            probabilities: Vec::new(),
+            item_index_per_item: HashMap::new(),
+            cumulative_probability_tree: Vec::new()
         }
     }
     #[allow(dead_code)]
-    pub fn new(probability_per_item: HashMap<T, f32>) -> Self {
-        let mut probability_total = 0.0;
-        let mut items_total: u32 = 0;
+    pub fn new(probability_per_item: HashMap<T, f64>) -> Self {
         let mut items: Vec<T> = probability_per_item.keys().cloned().collect::<Vec<T>>();
-        items.sort();
-        for item in items.iter() {
-            let probability = &probability_per_item[item];
-            if probability != &0.0 {
-                probability_total += probability;
-                items_total += 1;
-            }
+        items.sort_by_key(get_node_state_order_key);
+        let mut probability_container = ProbabilityContainer::default();
+        for item in items.into_iter() {
+            let probability = probability_per_item[&item];
+            probability_container.push(item, probability);
         }
-        ProbabilityContainer {
-            probability_total,
-            items_total,
-            probability_per_item,
-            items,
-            item_index_per_cumulative_probability: BTreeMap::new(),
-            last_item_index_to_apply_to_item_index_per_cumulative_probability: 0,
-            last_cumulative_probability: 0.0
+        probability_container
+    }
+    fn get_least_significant_bit(value: usize) -> usize {
+        value & value.wrapping_neg()
+    }
+    // the sum of the probabilities of the first items_length items
+    fn get_cumulative_probability(&self, items_length: usize) -> f64 {
+        let mut cumulative_probability: f64 = 0.0;
+        let mut tree_position = items_length;
+        while tree_position > 0 {
+            cumulative_probability += self.cumulative_probability_tree[tree_position - 1];
+            tree_position -= Self::get_least_significant_bit(tree_position);
         }
+        cumulative_probability
     }
-    pub fn push(&mut self, item: T, probability: f32) {
-        self.probability_total += probability;
+    fn add_to_cumulative_probabilities(&mut self, item_index: usize, probability_difference: f64) {
+        let mut tree_position = item_index + 1;
+        while tree_position <= self.cumulative_probability_tree.len() {
+            self.cumulative_probability_tree[tree_position - 1] += probability_difference;
+            tree_position += Self::get_least_significant_bit(tree_position);
+        }
+    }
+    pub fn push(&mut self, item: T, probability: f64) {
+        if let Some(existing_item_index) = self.item_index_per_item.get(&item).cloned() {
+            self.remove_at_item_index(existing_item_index);
+        }
+        let item_index = self.items.len();
+        let tree_position = item_index + 1;
+        let cumulative_probability = probability + self.get_cumulative_probability(item_index) - self.get_cumulative_probability(tree_position - Self::get_least_significant_bit(tree_position));
+        self.cumulative_probability_tree.push(cumulative_probability);
+        self.item_index_per_item.insert(item.clone(), item_index);
+        self.items.push(Some(item));
+        self.probabilities.push(probability);
         self.items_total += 1;
-        self.probability_per_item.insert(item.clone(), probability);
-        self.items.push(item);
+        self.probability_total += probability;
+        self.largest_probability_total = self.largest_probability_total.max(self.probability_total);
     }
+    /// This function removes the item, returning its probability if it was present.
     #[allow(dead_code)]
-    pub fn peek_random(&mut self, random_instance: &mut fastrand::Rng) -> Option<T> {
-        let item_option: Option<T>;
-        if self.items_total == 0 {
-            //debug!("no items");
-            item_option = None;
+    pub fn remove(&mut self, item: &T) -> Option<f64> {
+        let item_index = self.item_index_per_item.get(item).cloned()?;
+        let (_, probability) = self.remove_at_item_index(item_index);
+        Some(probability)
+    }
+    /// This function changes the probability of the item, returning false if the item is not present.
+    #[allow(dead_code)]
+    pub fn update_probability(&mut self, item: &T, probability: f64) -> bool {
+        let Some(item_index) = self.item_index_per_item.get(item).cloned() else {
+            return false;
+        };
+        let probability_difference = probability - self.probabilities[item_index];
+        self.probabilities[item_index] = probability;
+        self.add_to_cumulative_probabilities(item_index, probability_difference);
+        self.probability_total += probability_difference;
+        self.largest_probability_total = self.largest_probability_total.max(self.probability_total);
+        if self.is_precision_lost() {
+            self.rebuild();
         }
-        else if self.items_total == 1 {
-            item_option = Some(self.items.first().unwrap().clone());
-            //debug!("one item: {:?}", item_option);
+        true
+    }
+    fn remove_at_item_index(&mut self, item_index: usize) -> (T, f64) {
+        let item = self.items[item_index].take().unwrap();
+        let probability = self.probabilities[item_index];
+        self.probabilities[item_index] = 0.0;
+        self.add_to_cumulative_probabilities(item_index, -probability);
+        self.item_index_per_item.remove(&item);
+        self.items_total -= 1;
+        self.probability_total -= probability;
+
+        if self.items_total == 0 {
+            self.clear();
         }
         else {
-            let random_value = random_instance.f32() * self.probability_total;
-            if random_value > self.last_cumulative_probability {
-                let mut current_item: Option<&T> = None;
-                while random_value > self.last_cumulative_probability {
-                    current_item = Some(self.items.get(self.last_item_index_to_apply_to_item_index_per_cumulative_probability).unwrap());
-                    let item_probability = self.probability_per_item.get(current_item.unwrap()).unwrap();
-                    if item_probability != &0.0 {
-                        self.last_cumulative_probability += item_probability;
-                        //debug!("inserting {:?} with cumulative probability {:?}", self.last_item_index_to_apply_to_item_index_per_cumulative_probability, self.last_cumulative_probability);
-                        self.item_index_per_cumulative_probability.insert(OrderedFloat(self.last_cumulative_probability), self.last_item_index_to_apply_to_item_index_per_cumulative_probability);
-                    }
-                    self.last_item_index_to_apply_to_item_index_per_cumulative_probability += 1;
-                }
-                let current_item = current_item.unwrap().clone();
-                //debug!("found item {:?}", current_item);
-                item_option = Some(current_item.clone());
+            let removed_items_total = self.items.len() - self.items_total;
+            let is_many_items_removed = removed_items_total > self.items_total && removed_items_total >= REMOVED_ITEMS_TOTAL_BEFORE_REBUILD;
+            if is_many_items_removed || self.is_precision_lost() {
+                self.rebuild();
             }
-            else {
-                //debug!("random_value: {:?}", random_value);
-                let (_temp_key, temp_value) = self.item_index_per_cumulative_probability.range(OrderedFloat(random_value)..).next().unwrap();
-                //debug!("found item {:?} with probability {:?}", temp_value, temp_key);
-                item_option = Some(self.items.get(*temp_value).unwrap().clone());
+        }
+        (item, probability)
+    }
+    // removing a likely item, or making it unlikely, leaves the remaining unlikely items with cumulative probabilities that are mostly floating point error
+    fn is_precision_lost(&self) -> bool {
+        self.probability_total < self.largest_probability_total * REMAINING_PROBABILITY_RATIO_BEFORE_REBUILD
+    }
+    fn clear(&mut self) {
+        self.probability_total = 0.0;
+        self.largest_probability_total = 0.0;
+        self.items_total = 0;
+        self.items.clear();
+        self.probabilities.clear();
+        self.item_index_per_item.clear();
+        self.cumulative_probability_tree.clear();
+    }
+    // discards removed items and any accumulated floating point error
+    fn rebuild(&mut self) {
+        let items = std::mem::take(&mut self.items);
+        let probabilities = std::mem::take(&mut self.probabilities);
+        self.clear();
+        for (item, probability) in items.into_iter().zip(probabilities) {
+            if let Some(item) = item {
+                self.push(item, probability);
+            }
+        }
+    }
+    // finds the first item whose cumulative probability exceeds the random value
+    fn get_random_item_index(&self, random_instance: &mut fastrand::Rng) -> usize {
+        let items_length = self.items.len();
+        if self.items_total == 1 || self.probability_total <= 0.0 {
+            return self.get_nearest_item_index(0, false);
+        }
+
+        let mut remaining_probability = random_instance.f64() * self.probability_total;
+        let mut tree_position: usize = 0;
+        let mut step: usize = if items_length == 0 { 0 } else { 1 << items_length.ilog2() };
+        while step > 0 {
+            let next_tree_position = tree_position + step;
+            if next_tree_position <= items_length && self.cumulative_probability_tree[next_tree_position - 1] <= remaining_probability {
+                tree_position = next_tree_position;
+                remaining_probability -= self.cumulative_probability_tree[next_tree_position - 1];
             }
+            step >>= 1;
+        }
+
+        // floating point error may land on a removed item or past the last item
+        self.get_nearest_item_index(tree_position.min(items_length - 1), true)
+    }
+    fn get_nearest_item_index(&self, item_index: usize, is_probability_required: bool) -> usize {
+        let is_candidate = |other_item_index: usize| -> bool {
+            self.items[other_item_index].is_some() && (!is_probability_required || self.probabilities[other_item_index] > 0.0)
+        };
+        if let Some(candidate_item_index) = (item_index..self.items.len()).find(|other_item_index| is_candidate(*other_item_index)) {
+            return candidate_item_index;
+        }
+        if let Some(candidate_item_index) = (0..item_index).rev().find(|other_item_index| is_candidate(*other_item_index)) {
+            return candidate_item_index;
+        }
+        if is_probability_required {
+            return self.get_nearest_item_index(0, false);
+        }
+        panic!("Failed to find any remaining item in the probability container.");
+    }
+    #[allow(dead_code)]
+    pub fn peek_random(&mut self, random_instance: &mut fastrand::Rng) -> Option<T> {
+        if self.items_total == 0 {
+            None
+        }
+        else {
+            let item_index = self.get_random_item_index(random_instance);
+            self.items[item_index].clone()
         }
-        item_option
     }
     pub fn pop_random(&mut self, random_instance: &mut fastrand::Rng) -> Option<T> {
-        //debug!("current state: {:?}", self.probability_per_item);
         if self.items_total == 0 {
-            //debug!("no items");
             None
         }
         else {
-            let item_option: Option<T>;
-            if self.items_total == 1 {
-                //self.item_per_cumulative_probability.remove(&OrderedFloat(self.probability_total))
-                item_option = self.items.first().cloned();
-                //debug!("one item: {:?}", item_option);
-                self.items.clear();
-                self.items_total = 0;
-                self.probability_total = 0.0;
-                self.item_index_per_cumulative_probability.clear();
-                self.last_item_index_to_apply_to_item_index_per_cumulative_probability = 0;
-                self.last_cumulative_probability = 0.0;
-                self.probability_per_item.clear();
-            }
-            else {
-                //let random_value = random_instance.gen::<f32>() * self.probability_total;
-                let random_value = random_instance.f32() * self.probability_total;
-                //debug!("random_value: {:?}", random_value);
-                //debug!("self.probability_total: {:?}", self.probability_total);
-                //debug!("self.last_cumulative_probability: {:?}", self.last_cumulative_probability);
-                //debug!("self.last_item_index_to_apply_to_item_index_per_cumulative_probability: {:?}", self.last_item_index_to_apply_to_item_index_per_cumulative_probability);
-                
-                let mut is_item_outside_random_value: bool = if self.last_item_index_to_apply_to_item_index_per_cumulative_probability as u32 == self.items_total {
-                    false
-                }
-                else if random_value == 0.0 && self.last_item_index_to_apply_to_item_index_per_cumulative_probability == 0 {
-                    true
-                }
-                else {
-                    random_value > self.last_cumulative_probability
-                };
-
-                if is_item_outside_random_value {
-                    let mut current_item: &T;
-                    // if the random value is out of range of the known probabilities
-                    while is_item_outside_random_value {
-                        current_item = self.items.get(self.last_item_index_to_apply_to_item_index_per_cumulative_probability).unwrap();
-                        let item_probability = self.probability_per_item.get(current_item).unwrap();
-                        if item_probability != &0.0 {
-                            if self.last_cumulative_probability + item_probability >= random_value {
-                                //debug!("found next item with probability {:?}", item_probability);
-
-                                // that there hasn't been floating point errors leading to missing the last item
-                                if (self.last_item_index_to_apply_to_item_index_per_cumulative_probability as u32) + 1 == self.items_total {
-                                    self.probability_total = self.last_cumulative_probability + item_probability;
-                                    //debug!("fixed probability total after incrementing to item");
-                                }
-                                
-                                break;
-                            }
-                            else {
-                                self.last_cumulative_probability += item_probability;
-                                //debug!("inserting {:?} with cumulative probability {:?} into index {:?}", current_item, self.last_cumulative_probability, self.last_item_index_to_apply_to_item_index_per_cumulative_probability);
-                                self.item_index_per_cumulative_probability.insert(OrderedFloat(self.last_cumulative_probability), self.last_item_index_to_apply_to_item_index_per_cumulative_probability);
-                            }
-                        }
-                        self.last_item_index_to_apply_to_item_index_per_cumulative_probability += 1;
-                        //debug!("self.last_item_index_to_apply_to_item_index_per_cumulative_probability: {:?}", self.last_item_index_to_apply_to_item_index_per_cumulative_probability);
-
-                        // that there hasn't been floating point errors leading to missing the last item
-                        if (self.last_item_index_to_apply_to_item_index_per_cumulative_probability as u32) == self.items_total {
-                            self.probability_total = self.last_cumulative_probability;
-                            //debug!("fixed probability total after missing item");
-
-                            // move back one item so that the process ends up grabbing the last item
-                            self.last_item_index_to_apply_to_item_index_per_cumulative_probability -= 1;
-                            break;
-                        }
-
-                        is_item_outside_random_value = random_value > self.last_cumulative_probability;
-                    }
-
-                    let item = self.items.remove(self.last_item_index_to_apply_to_item_index_per_cumulative_probability);
-                    self.probability_total -= self.probability_per_item.remove(&item).unwrap();
-                    item_option = Some(item);
-                    self.items_total -= 1;
-
-                    //debug!("found item {:?}", item_option);
-                }
-                else {
-                    let found_key: f32;
-                    let found_index: usize;
-                    let found_item: T;
-                    {
-                        let (temp_key, temp_value) = self.item_index_per_cumulative_probability.range(OrderedFloat(random_value)..).next().unwrap();
-                        //debug!("found item {:?} with probability {:?}", temp_value, temp_key);
-                        found_item = self.items.remove(*temp_value);
-                        self.items_total -= 1;
-                        item_option = Some(found_item.clone());
-                        
-                        found_key = temp_key.0;
-                        found_index = *temp_value;
-                    }
-
-                    let found_key_ordered_float = &OrderedFloat(found_key);
-                    self.item_index_per_cumulative_probability.retain(|probability, _| probability < found_key_ordered_float);
-                    self.last_item_index_to_apply_to_item_index_per_cumulative_probability = found_index;
-                    //debug!("self.last_item_index_to_apply_to_item_index_per_cumulative_probability: {:?}", self.last_item_index_to_apply_to_item_index_per_cumulative_probability);
-                    let found_item_probability = self.probability_per_item.remove(&found_item).unwrap();
-                    self.last_cumulative_probability = found_key - found_item_probability;
-
-                    // that there hasn't been floating point errors leading to missing the last item
-                    if (self.last_item_index_to_apply_to_item_index_per_cumulative_probability as u32) == self.items_total {
-                        self.probability_total = self.last_cumulative_probability;
-                        //debug!("fixed probability total after finding item");
-                    }
-                    else {
-                        self.probability_total -= found_item_probability;
-                    }
-                }
-
-                if item_option.is_none() {
-                    panic!("Failed to find item even though some exists.");
-                }
-                //debug!("more than one item: {:?}", item_option);
-            }
-            item_option
+            let item_index = self.get_random_item_index(random_instance);
+            let (item, _) = self.remove_at_item_index(item_index);
+            Some(item)
         }
     }
-}
\ No newline at end of file
+}
@@ -5,17 +5,17 @@ use std::hash::Hash;
 /// This struct is optimized better than ProbabilityContainer to search for a random item but does not permit removing a random item.
 #[allow(dead_code)]
 pub struct ProbabilityTree<T> {
-    probability_total: f32,
-    item_per_cumulative_probability: BTreeMap<OrderedFloat<f32>, T>,
+    probability_total: f64,
+    item_per_cumulative_probability: BTreeMap<OrderedFloat<f64>, T>,
     items_total: u32,
-    probability_per_item: HashMap<T, f32>
+    probability_per_item: HashMap<T, f64>
 }
 
 #[allow(dead_code)]
-impl<T: Ord + Eq + Hash + Clone + Debug> ProbabilityTree<T> {
-    pub fn new(probability_per_item: HashMap<T, f32>) -> Self {
+impl<T: Eq + Hash + Clone + Debug> ProbabilityTree<T> {
+    pub fn new(probability_per_item: HashMap<T, f64>) -> Self {
         let mut probability_total = 0.0;
-        let mut item_per_cumulative_probability: BTreeMap<OrderedFloat<f32>, T> = BTreeMap::new();
+        let mut item_per_cumulative_probability: BTreeMap<OrderedFloat<f64>, T> = BTreeMap::new();
         let mut items_total: u32 = 0;
         // TODO fix to not use random order from HashMap.iter()
         for (item, probability) in probability_per_item.iter() {
@@ -44,7 +44,7 @@ impl<T: Ord + Eq + Hash + Clone + Debug> ProbabilityTree<T> {
             item_option = Some(self.item_per_cumulative_probability.get(&key).unwrap().clone());
         }
         else {
-            let random_value = OrderedFloat(random_instance.f32() * self.probability_total);
+            let random_value = OrderedFloat(random_instance.f64() * self.probability_total);
             debug!("random_value: {:?}", random_value);
             let (temp_key, temp_value) = self.item_per_cumulative_probability.range(random_value..).next().unwrap();
             debug!("found item {:?} with probability {:?}", temp_value, temp_key);
@@ -0,0 +1,177 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use super::{WaveFunction, NodeStateCollection, wave_function_builder::WaveFunctionBuilder, grid::{self, AdjacencyKind, GridBuilder}};
+
+/// This function generates a grid of nodes, each in one of the node states from 0 to states, where every neighbor constraint is random except that it always permits the returned node states. The same seed always generates the same wave function. Node ids match those of GridBuilder.
+pub fn generate_grid(width: usize, height: usize, states: u32, seed: u64) -> Result<(WaveFunction<u32>, HashMap<String, u32>), String> {
+    let mut node_ids: Vec<String> = Vec::new();
+    let mut neighbor_node_index_pairs: Vec<(usize, usize)> = Vec::new();
+    for x in 0..width {
+        for y in 0..height {
+            let node_index = node_ids.len();
+            node_ids.push(GridBuilder::<u32>::get_node_id(x, y));
+            // the node to the left and the node above were added before this node
+            if x != 0 {
+                neighbor_node_index_pairs.push((node_index - height, node_index));
+            }
+            if y != 0 {
+                neighbor_node_index_pairs.push((node_index - 1, node_index));
+            }
+        }
+    }
+    generate(node_ids, neighbor_node_index_pairs, states, seed)
+}
+
+/// This function generates a tree of nodes where each node below the root has a single parent and each node above the deepest level has branching children, constrained just like generate_grid. A depth of zero is only the root.
+pub fn generate_tree(depth: usize, branching: usize, states: u32, seed: u64) -> Result<(WaveFunction<u32>, HashMap<String, u32>), String> {
+    let mut node_ids: Vec<String> = vec![String::from("node_0")];
+    let mut neighbor_node_index_pairs: Vec<(usize, usize)> = Vec::new();
+    let mut parent_node_indexes: Vec<usize> = vec![0];
+    for _ in 0..depth {
+        let mut child_node_indexes: Vec<usize> = Vec::new();
+        for parent_node_index in parent_node_indexes.iter() {
+            for _ in 0..branching {
+                let child_node_index = node_ids.len();
+                node_ids.push(format!("node_{child_node_index}"));
+                neighbor_node_index_pairs.push((*parent_node_index, child_node_index));
+                child_node_indexes.push(child_node_index);
+            }
+        }
+        parent_node_indexes = child_node_indexes;
+    }
+    generate(node_ids, neighbor_node_index_pairs, states, seed)
+}
+
+fn generate(node_ids: Vec<String>, neighbor_node_index_pairs: Vec<(usize, usize)>, states: u32, seed: u64) -> Result<(WaveFunction<u32>, HashMap<String, u32>), String> {
+    if states == 0 {
+        return Err(String::from("Cannot generate a wave function without any node states."));
+    }
+    let mut random_instance = fastrand::Rng::with_seed(seed);
+    let node_state_ids: Vec<u32> = (0..states).collect();
+
+    let mut wave_function_builder: WaveFunctionBuilder<u32> = WaveFunctionBuilder::new();
+    let mut node_states: Vec<u32> = Vec::new();
+    for node_id in node_ids.iter() {
+        wave_function_builder.add_node(node_id, node_state_ids.clone());
+        node_states.push(random_instance.u32(0..states));
+    }
+
+    for (first_node_index, second_node_index) in neighbor_node_index_pairs.into_iter() {
+        for (node_index, neighbor_node_index) in [(first_node_index, second_node_index), (second_node_index, first_node_index)] {
+            let mut permitted_node_state_ids_per_node_state_id: HashMap<u32, Vec<u32>> = HashMap::new();
+            for node_state_id in node_state_ids.iter() {
+                let mut permitted_node_state_ids: Vec<u32> = node_state_ids.iter()
+                    .filter(|_| random_instance.bool())
+                    .cloned()
+                    .collect();
+                // the generated node states must remain a valid assignment
+                if *node_state_id == node_states[node_index] && !permitted_node_state_ids.contains(&node_states[neighbor_node_index]) {
+                    permitted_node_state_ids.push(node_states[neighbor_node_index]);
+                }
+                permitted_node_state_ids_per_node_state_id.insert(*node_state_id, permitted_node_state_ids);
+            }
+            wave_function_builder.add_constraint(&node_ids[node_index], &node_ids[neighbor_node_index], permitted_node_state_ids_per_node_state_id);
+        }
+    }
+
+    let wave_function = wave_function_builder.build()?;
+    let node_state_per_node_id: HashMap<String, u32> = node_ids.into_iter().zip(node_states).collect();
+    Ok((wave_function, node_state_per_node_id))
+}
+
+// every node state permits each other node state but not itself
+fn get_all_different_permitted_node_state_ids_per_node_state_id(node_state_ids: &[String]) -> HashMap<String, Vec<String>> {
+    let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+    for node_state_id in node_state_ids.iter() {
+        permitted_node_state_ids_per_node_state_id.insert(node_state_id.clone(), node_state_ids.iter().filter(|other_node_state_id| *other_node_state_id != node_state_id).cloned().collect());
+    }
+    permitted_node_state_ids_per_node_state_id
+}
+
+/// This function generates nodes "node_0" through "node_{nodes_total - 1}" in node states "state_0" through "state_{node_states_total - 1}", where every node must differ from every other node. The wave function is only collapsable if there are at least as many node states as there are nodes.
+pub fn generate_all_different_clique(nodes_total: usize, node_states_total: usize) -> Result<WaveFunction<String>, String> {
+    let node_state_ids: Vec<String> = (0..node_states_total).map(|index| format!("state_{index}")).collect();
+    let permitted_node_state_ids_per_node_state_id = get_all_different_permitted_node_state_ids_per_node_state_id(&node_state_ids);
+    let mut wave_function_builder = WaveFunctionBuilder::new();
+    for index in 0..nodes_total {
+        wave_function_builder.add_node(&format!("node_{index}"), node_state_ids.clone());
+    }
+    for index in 0..nodes_total {
+        for other_index in (index + 1)..nodes_total {
+            wave_function_builder.add_bidirectional_constraint(&format!("node_{index}"), &format!("node_{other_index}"), permitted_node_state_ids_per_node_state_id.clone());
+        }
+    }
+    wave_function_builder.build()
+}
+
+/// This function generates a cube of nodes "node_{x}_{y}_{z}" in node states "state_0" through "state_{node_states_total - 1}", where every node must differ from each node that it touches, including diagonally.
+pub fn generate_all_different_3d_grid(size: usize, node_states_total: usize) -> Result<WaveFunction<String>, String> {
+    let node_state_ids: Vec<String> = (0..node_states_total).map(|index| format!("state_{index}")).collect();
+    let permitted_node_state_ids_per_node_state_id = get_all_different_permitted_node_state_ids_per_node_state_id(&node_state_ids);
+
+    let get_node_id = |x: usize, y: usize, z: usize| -> String {
+        format!("node_{x}_{y}_{z}")
+    };
+
+    let mut wave_function_builder = WaveFunctionBuilder::new();
+    for x in 0..size {
+        for y in 0..size {
+            for z in 0..size {
+                wave_function_builder.add_node(&get_node_id(x, y, z), node_state_ids.clone());
+            }
+        }
+    }
+    for x in 0..size {
+        for y in 0..size {
+            for z in 0..size {
+                for other_x in x.saturating_sub(1)..(x + 2).min(size) {
+                    for other_y in y.saturating_sub(1)..(y + 2).min(size) {
+                        for other_z in z.saturating_sub(1)..(z + 2).min(size) {
+                            if (x, y, z) != (other_x, other_y, other_z) {
+                                wave_function_builder.add_constraint(&get_node_id(x, y, z), &get_node_id(other_x, other_y, other_z), permitted_node_state_ids_per_node_state_id.clone());
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+    wave_function_builder.build()
+}
+
+/// This function generates a two-dimensional grid, with node ids matching those of grid::build_2d_grid, where each node must be in the other of the node states "black" and "white" than each node sharing an edge with it. Only the two checkerboards satisfy it.
+pub fn generate_checkerboard(width: usize, height: usize) -> Result<WaveFunction<String>, String> {
+    let node_state_ids: Vec<String> = vec![String::from("black"), String::from("white")];
+    let permitted_node_state_ids_per_node_state_id = get_all_different_permitted_node_state_ids_per_node_state_id(&node_state_ids);
+    grid::build_2d_grid(width, height, node_state_ids, AdjacencyKind::Four, |node_state_id, _| permitted_node_state_ids_per_node_state_id[node_state_id].clone())
+}
+
+/// This function describes the first node that is missing a node state, is in a node state that it can never be in, or is in a node state that a neighbor does not permit, returning None if the node states satisfy every constraint of the wave function.
+pub fn find_violation<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(wave_function: &WaveFunction<TNodeState>, node_state_per_node_id: &HashMap<String, TNodeState>) -> Option<String> {
+    let node_state_collection_per_id: HashMap<&str, &NodeStateCollection<TNodeState>> = wave_function.node_state_collections
+        .iter()
+        .map(|node_state_collection| (node_state_collection.id.as_str(), node_state_collection))
+        .collect();
+    for node in wave_function.nodes.iter() {
+        let Some(node_state_id) = node_state_per_node_id.get(&node.id) else {
+            return Some(format!("Node {} is not in a node state.", node.id));
+        };
+        if !node.node_state_ids.contains(node_state_id) {
+            return Some(format!("Node {} is in node state {node_state_id:?} that it can never be in.", node.id));
+        }
+        for (neighbor_node_id, node_state_collection_ids) in node.node_state_collection_ids_per_neighbor_node_id.iter() {
+            let Some(neighbor_node_state_id) = node_state_per_node_id.get(neighbor_node_id) else {
+                return Some(format!("Node {neighbor_node_id} is not in a node state."));
+            };
+            for node_state_collection_id in node_state_collection_ids.iter() {
+                let Some(node_state_collection) = node_state_collection_per_id.get(node_state_collection_id.as_str()) else {
+                    return Some(format!("Node state collection {node_state_collection_id} does not exist."));
+                };
+                if node_state_collection.node_state_id == *node_state_id && !node_state_collection.node_state_ids.contains(neighbor_node_state_id) {
+                    return Some(format!("Node {} in node state {node_state_id:?} does not permit neighbor node {neighbor_node_id} to be in node state {neighbor_node_state_id:?}.", node.id));
+                }
+            }
+        }
+    }
+    None
+}
@@ -1,7 +1,7 @@
 mod model {
     use uuid::Uuid;
 
-    #[derive(PartialOrd, Ord, Eq, PartialEq, Hash, Clone, Debug)]
+    #[derive(Eq, PartialEq, Hash, Clone, Debug)]
     pub struct TestStruct {
         pub id: String
     }
@@ -21,6 +21,43 @@ mod model {
     }
 }
 
+#[cfg(test)]
+mod allocation {
+    use std::{alloc::{GlobalAlloc, Layout, System}, cell::Cell};
+
+    // counts the allocations of each thread so that tests running concurrently do not affect each other
+    pub struct CountingAllocator;
+
+    thread_local! {
+        static ALLOCATIONS_TOTAL: Cell<usize> = const { Cell::new(0) };
+        static ALLOCATED_BYTES_TOTAL: Cell<usize> = const { Cell::new(0) };
+    }
+
+    unsafe impl GlobalAlloc for CountingAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            let _ = ALLOCATIONS_TOTAL.try_with(|allocations_total| allocations_total.set(allocations_total.get() + 1));
+            let _ = ALLOCATED_BYTES_TOTAL.try_with(|allocated_bytes_total| allocated_bytes_total.set(allocated_bytes_total.get() + layout.size()));
+            System.alloc(layout)
+        }
+        unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+            System.dealloc(ptr, layout)
+        }
+    }
+
+    #[global_allocator]
+    static COUNTING_ALLOCATOR: CountingAllocator = CountingAllocator;
+
+    /// This function returns the number of allocations and the number of bytes allocated by the current thread while performing the action.
+    pub fn count_allocations<T>(action: impl FnOnce() -> T) -> (T, usize, usize) {
+        let starting_allocations_total = ALLOCATIONS_TOTAL.with(|allocations_total| allocations_total.get());
+        let starting_allocated_bytes_total = ALLOCATED_BYTES_TOTAL.with(|allocated_bytes_total| allocated_bytes_total.get());
+        let result = action();
+        let allocations_total = ALLOCATIONS_TOTAL.with(|allocations_total| allocations_total.get()) - starting_allocations_total;
+        let allocated_bytes_total = ALLOCATED_BYTES_TOTAL.with(|allocated_bytes_total| allocated_bytes_total.get()) - starting_allocated_bytes_total;
+        (result, allocations_total, allocated_bytes_total)
+    }
+}
+
 #[cfg(test)]
 mod probability_collection_unit_tests {
 
@@ -45,7 +82,7 @@ mod probability_collection_unit_tests {
         let mut random_instance = fastrand::Rng::new();
 
         for _ in 0..100 {
-            let probability_per_item: HashMap<TestStruct, f32> = HashMap::new();
+            let probability_per_item: HashMap<TestStruct, f64> = HashMap::new();
             let mut probability_collection: ProbabilityCollection<TestStruct> = ProbabilityCollection::new(probability_per_item);
         
             for _ in 0..100 {
@@ -62,7 +99,7 @@ mod probability_collection_unit_tests {
         let mut random_instance = fastrand::Rng::new();
         
         for _ in 0..100 {
-            let mut probability_per_item: HashMap<TestStruct, f32> = HashMap::new();
+            let mut probability_per_item: HashMap<TestStruct, f64> = HashMap::new();
             probability_per_item.insert(TestStruct::new_random(), 1.0);
             let mut probability_collection: ProbabilityCollection<TestStruct> = ProbabilityCollection::new(probability_per_item);
         
@@ -82,7 +119,7 @@ mod probability_collection_unit_tests {
         let mut random_instance = fastrand::Rng::new();
         
         for _ in 0..100 {
-            let mut probability_per_item: HashMap<TestStruct, f32> = HashMap::new();
+            let mut probability_per_item: HashMap<TestStruct, f64> = HashMap::new();
 
             //let number_of_items = rng.gen::<u8>(); // TODO uncomment
             let number_of_items = 13;
@@ -111,7 +148,7 @@ mod probability_collection_unit_tests {
         let mut random_instance = fastrand::Rng::new();
         
         for _ in 0..100 {
-            let mut probability_per_item: HashMap<TestStruct, f32> = HashMap::new();
+            let mut probability_per_item: HashMap<TestStruct, f64> = HashMap::new();
 
             //let number_of_items = rng.gen::<u8>(); // TODO uncomment
             let mut number_of_items = 13;
@@ -140,12 +177,44 @@ mod probability_collection_unit_tests {
             }
         }
     }
+
+    #[test]
+    fn pop_random_all_with_extreme_probabilities() {
+        init();
+
+        let mut random_instance = fastrand::Rng::with_seed(0);
+
+        let number_of_instances = 10000;
+        // each item is 100000 times more likely than the previous item, from 1e-35 to 1e+5
+        let number_of_items: i32 = 9;
+
+        let mut descending_pops_total = 0;
+        for _ in 0..number_of_instances {
+            let mut probability_per_item: HashMap<TestStruct, f64> = HashMap::new();
+            for index in 0..number_of_items {
+                probability_per_item.insert(TestStruct { id: index.to_string() }, 10.0_f64.powi(index * 5 - 35));
+            }
+            let mut probability_collection: ProbabilityCollection<TestStruct> = ProbabilityCollection::new(probability_per_item);
+
+            let mut popped_ids: Vec<String> = Vec::new();
+            while let Some(popped_item) = probability_collection.pop_random(&mut random_instance) {
+                popped_ids.push(popped_item.id);
+            }
+            assert_eq!(number_of_items as usize, popped_ids.len());
+            if popped_ids.iter().rev().enumerate().all(|(index, id)| *id == index.to_string()) {
+                descending_pops_total += 1;
+            }
+        }
+
+        assert!(descending_pops_total > number_of_instances - 20, "Items were only popped in order of probability {descending_pops_total} times.");
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_range_loop, clippy::same_item_push, clippy::excessive_precision, clippy::redundant_field_names)]
 mod probability_container_unit_tests {
 
-    use std::collections::HashMap;
+    use std::collections::{HashMap, HashSet};
     use uuid::Uuid;
     use crate::wave_function::probability_container::ProbabilityContainer;
     use super::model::TestStruct;
@@ -167,7 +236,7 @@ mod probability_container_unit_tests {
         let mut random_instance = fastrand::Rng::new();
         
         for _ in 0..100 {
-            let probability_per_item: HashMap<TestStruct, f32> = HashMap::new();
+            let probability_per_item: HashMap<TestStruct, f64> = HashMap::new();
             let mut probability_container: ProbabilityContainer<TestStruct> = ProbabilityContainer::new(probability_per_item);
         
             for _ in 0..100 {
@@ -184,7 +253,7 @@ mod probability_container_unit_tests {
         let mut random_instance = fastrand::Rng::new();
         
         for _ in 0..100 {
-            let mut probability_per_item: HashMap<TestStruct, f32> = HashMap::new();
+            let mut probability_per_item: HashMap<TestStruct, f64> = HashMap::new();
             probability_per_item.insert(TestStruct::new_random(), 1.0);
             let mut probability_container: ProbabilityContainer<TestStruct> = ProbabilityContainer::new(probability_per_item);
         
@@ -204,7 +273,7 @@ mod probability_container_unit_tests {
         let mut random_instance = fastrand::Rng::new();
         
         for _ in 0..100 {
-            let mut probability_per_item: HashMap<TestStruct, f32> = HashMap::new();
+            let mut probability_per_item: HashMap<TestStruct, f64> = HashMap::new();
 
             //let number_of_items = rng.gen::<u8>(); // TODO uncomment
             let number_of_items = 13;
@@ -233,7 +302,7 @@ mod probability_container_unit_tests {
         let mut random_instance = fastrand::Rng::new();
         
         for _ in 0..100 {
-            let mut probability_per_item: HashMap<TestStruct, f32> = HashMap::new();
+            let mut probability_per_item: HashMap<TestStruct, f64> = HashMap::new();
 
             //let number_of_items = rng.gen::<u8>(); // TODO uncomment
             let mut number_of_items = 13;
@@ -269,7 +338,7 @@ mod probability_container_unit_tests {
 
         let mut random_instance = fastrand::Rng::new();
 
-        let mut probability_per_item: HashMap<TestStruct, f32> = HashMap::new();
+        let mut probability_per_item: HashMap<TestStruct, f64> = HashMap::new();
         let number_of_items = 100;
         let mut instances_per_index: Vec<u32> = Vec::new();
         debug!("inserting {number_of_items} items");
@@ -391,7 +460,7 @@ mod probability_container_unit_tests {
 
         let number_of_items = 20;
         
-        let mut probability_per_item: HashMap<TestStruct, f32> = HashMap::new();
+        let mut probability_per_item: HashMap<TestStruct, f64> = HashMap::new();
         //debug!("inserting {number_of_items} items");
         for index in 0..number_of_items {
             probability_per_item.insert(TestStruct::new(index.to_string()), 1.0);
@@ -455,7 +524,7 @@ mod probability_container_unit_tests {
         let mut nodes: Vec<TestNode> = Vec::new();
         for _ in 0..number_of_nodes {
             let mut probability_container: ProbabilityContainer<TestStruct> = ProbabilityContainer::default();
-            let mut previous_probability: f32 = 1.0;
+            let mut previous_probability: f64 = 1.0;
             for index in 0..number_of_items {
                 let id = index.to_string();
                 count_per_id.insert(id.clone(), 0);
@@ -484,7 +553,7 @@ mod probability_container_unit_tests {
             println!("item count: {:?}", count_per_id.get(&item_index.to_string()).unwrap());
             let current_count = count_per_id.get(&item_index.to_string()).unwrap();
             let next_count = count_per_id.get(&(item_index + 1).to_string()).unwrap();
-            let scale = (*next_count as f32) / (*current_count as f32);
+            let scale = (*next_count as f64) / (*current_count as f64);
             println!("scale: {scale}");
         }
         println!("item count: {:?}", count_per_id.get(&((number_of_items - 1) as usize).to_string()).unwrap());
@@ -509,7 +578,7 @@ mod probability_container_unit_tests {
         let mut nodes: Vec<TestNode> = Vec::new();
         for _ in 0..number_of_nodes {
             let mut probability_container: ProbabilityContainer<TestStruct> = ProbabilityContainer::default();
-            let mut previous_probability: f32 = (2.0_f32).powf(0.0);
+            let mut previous_probability: f64 = (2.0_f64).powf(0.0);
             for index in 0..number_of_items {
                 let id = index.to_string();
                 count_per_id.insert(id.clone(), 0);
@@ -543,7 +612,7 @@ mod probability_container_unit_tests {
             println!("item count: {:?}", count_per_id.get(&item_index.to_string()).unwrap());
             let current_count = count_per_id.get(&item_index.to_string()).unwrap();
             let next_count = count_per_id.get(&(item_index + 1).to_string()).unwrap();
-            let scale = (*next_count as f32) / (*current_count as f32);
+            let scale = (*next_count as f64) / (*current_count as f64);
             println!("scale: {scale}");
         }
         println!("item count: {:?}", count_per_id.get(&((number_of_items - 1) as usize).to_string()).unwrap());
@@ -554,14 +623,256 @@ mod probability_container_unit_tests {
     
         // TODO calculate standard deviation and compare each value
     }
+
+    fn get_peeked_count_per_id(probability_container: &mut ProbabilityContainer<TestStruct>, random_instance: &mut fastrand::Rng, trials: u32) -> HashMap<String, u32> {
+        let mut count_per_id: HashMap<String, u32> = HashMap::new();
+        for _ in 0..trials {
+            let item = probability_container.peek_random(random_instance).unwrap();
+            *count_per_id.entry(item.id).or_insert(0) += 1;
+        }
+        count_per_id
+    }
+
+    #[test]
+    fn probability_container_remove_mid_stream() {
+        init();
+
+        let mut random_instance = fastrand::Rng::new();
+
+        let mut probability_container: ProbabilityContainer<TestStruct> = ProbabilityContainer::default();
+        for index in 0..4 {
+            probability_container.push(TestStruct::new(index.to_string()), 1.0);
+        }
+
+        // peek first so that removing happens after sampling has already begun
+        let count_per_id = get_peeked_count_per_id(&mut probability_container, &mut random_instance, 100000);
+        assert_eq!(4, count_per_id.len());
+
+        assert_eq!(Some(1.0), probability_container.remove(&TestStruct::new(String::from("1"))));
+        assert_eq!(None, probability_container.remove(&TestStruct::new(String::from("1"))));
+
+        let trials = 1000000;
+        let count_per_id = get_peeked_count_per_id(&mut probability_container, &mut random_instance, trials);
+        assert!(!count_per_id.contains_key("1"));
+        for id in ["0", "2", "3"] {
+            let difference = count_per_id.get(id).unwrap().abs_diff(trials / 3);
+            assert!(difference < 5000, "Item {id} was peeked {:?} times.", count_per_id.get(id));
+        }
+
+        let mut popped_ids: Vec<String> = Vec::new();
+        while let Some(item) = probability_container.pop_random(&mut random_instance) {
+            popped_ids.push(item.id);
+        }
+        popped_ids.sort();
+        assert_eq!(vec![String::from("0"), String::from("2"), String::from("3")], popped_ids);
+    }
+
+    #[test]
+    fn probability_container_update_probability_mid_stream() {
+        init();
+
+        let mut random_instance = fastrand::Rng::new();
+
+        let mut probability_container: ProbabilityContainer<TestStruct> = ProbabilityContainer::default();
+        for index in 0..4 {
+            probability_container.push(TestStruct::new(index.to_string()), 1.0);
+        }
+
+        let count_per_id = get_peeked_count_per_id(&mut probability_container, &mut random_instance, 100000);
+        assert_eq!(4, count_per_id.len());
+
+        assert!(probability_container.update_probability(&TestStruct::new(String::from("0")), 3.0));
+        assert!(probability_container.update_probability(&TestStruct::new(String::from("3")), 0.0));
+        assert!(!probability_container.update_probability(&TestStruct::new(String::from("4")), 1.0));
+
+        // the probabilities are now 3 to 1 to 1 to 0
+        let trials = 1000000;
+        let count_per_id = get_peeked_count_per_id(&mut probability_container, &mut random_instance, trials);
+        assert!(!count_per_id.contains_key("3"));
+        assert!(count_per_id.get("0").unwrap().abs_diff(trials * 3 / 5) < 5000, "Item 0 was peeked {:?} times.", count_per_id.get("0"));
+        for id in ["1", "2"] {
+            let difference = count_per_id.get(id).unwrap().abs_diff(trials / 5);
+            assert!(difference < 5000, "Item {id} was peeked {:?} times.", count_per_id.get(id));
+        }
+    }
+
+    fn assert_peeked_count_per_id_tracks_probabilities(probability_container: &mut ProbabilityContainer<TestStruct>, random_instance: &mut fastrand::Rng, probability_per_id: &[(&str, f64)]) {
+        let trials: u32 = 1000000;
+        let count_per_id = get_peeked_count_per_id(probability_container, random_instance, trials);
+        let probability_total: f64 = probability_per_id.iter().map(|(_, probability)| probability).sum();
+        assert_eq!(probability_per_id.iter().filter(|(_, probability)| *probability > 0.0).count(), count_per_id.len(), "Peeked {count_per_id:?}.");
+        for (id, probability) in probability_per_id.iter() {
+            let expected_count = trials as f64 * probability / probability_total;
+            let count = *count_per_id.get(*id).unwrap_or(&0) as f64;
+            // within five standard deviations of the binomial distribution
+            let tolerance = 5.0 * expected_count.sqrt() + 1.0;
+            assert!((count - expected_count).abs() < tolerance, "Item {id} was peeked {count} times when expecting {expected_count}.");
+        }
+    }
+
+    #[test]
+    fn probability_container_interleaved_push_remove_update_probability() {
+        init();
+
+        let mut random_instance = fastrand::Rng::with_seed(0);
+
+        let mut probability_container: ProbabilityContainer<TestStruct> = ProbabilityContainer::default();
+        for (id, probability) in [("0", 1.0), ("1", 2.0), ("2", 3.0)] {
+            probability_container.push(TestStruct::new(String::from(id)), probability);
+        }
+        assert_peeked_count_per_id_tracks_probabilities(&mut probability_container, &mut random_instance, &[("0", 1.0), ("1", 2.0), ("2", 3.0)]);
+
+        assert!(probability_container.update_probability(&TestStruct::new(String::from("0")), 4.0));
+        probability_container.push(TestStruct::new(String::from("3")), 2.0);
+        assert_peeked_count_per_id_tracks_probabilities(&mut probability_container, &mut random_instance, &[("0", 4.0), ("1", 2.0), ("2", 3.0), ("3", 2.0)]);
+
+        assert_eq!(Some(3.0), probability_container.remove(&TestStruct::new(String::from("2"))));
+        assert_eq!(None, probability_container.remove(&TestStruct::new(String::from("2"))));
+        assert!(!probability_container.update_probability(&TestStruct::new(String::from("2")), 1.0));
+        assert!(probability_container.update_probability(&TestStruct::new(String::from("1")), 0.5));
+        assert_peeked_count_per_id_tracks_probabilities(&mut probability_container, &mut random_instance, &[("0", 4.0), ("1", 0.5), ("3", 2.0)]);
+
+        // pushing an existing item replaces its probability
+        probability_container.push(TestStruct::new(String::from("3")), 0.5);
+        probability_container.push(TestStruct::new(String::from("2")), 1.0);
+        assert!(probability_container.update_probability(&TestStruct::new(String::from("0")), 0.0));
+        assert_peeked_count_per_id_tracks_probabilities(&mut probability_container, &mut random_instance, &[("0", 0.0), ("1", 0.5), ("2", 1.0), ("3", 0.5)]);
+
+        // the remaining items are still chosen by their probabilities after the only likely item becomes unlikely
+        assert!(probability_container.update_probability(&TestStruct::new(String::from("0")), 100000.0));
+        for (id, probability) in [("1", 1e-20), ("2", 3e-20), ("3", 2e-20)] {
+            assert!(probability_container.update_probability(&TestStruct::new(String::from(id)), probability));
+        }
+        assert!(probability_container.update_probability(&TestStruct::new(String::from("0")), 1e-20));
+        assert_peeked_count_per_id_tracks_probabilities(&mut probability_container, &mut random_instance, &[("0", 1e-20), ("1", 1e-20), ("2", 3e-20), ("3", 2e-20)]);
+
+        let mut popped_ids: Vec<String> = Vec::new();
+        while let Some(item) = probability_container.pop_random(&mut random_instance) {
+            popped_ids.push(item.id);
+        }
+        popped_ids.sort();
+        assert_eq!(vec![String::from("0"), String::from("1"), String::from("2"), String::from("3")], popped_ids);
+    }
+
+    #[test]
+    fn probability_container_many_peeks_over_many_items_quickly() {
+        init();
+
+        let mut random_instance = fastrand::Rng::with_seed(0);
+
+        let items_total: u32 = 10000;
+        let mut probability_container: ProbabilityContainer<u32> = ProbabilityContainer::default();
+        for item in 0..items_total {
+            probability_container.push(item, (item % 7 + 1) as f64);
+        }
+
+        // a linear scan would need on the order of 10^11 comparisons
+        let start = std::time::Instant::now();
+        let mut peeked_items_total: u64 = 0;
+        for _ in 0..10000000 {
+            peeked_items_total += probability_container.peek_random(&mut random_instance).unwrap() as u64;
+        }
+        let elapsed = start.elapsed();
+
+        assert!(peeked_items_total > 0);
+        assert!(elapsed < std::time::Duration::from_secs(60), "Peeking took {:?}.", elapsed);
+    }
+
+    #[test]
+    fn probability_container_many_tiny_probabilities_remain_reachable() {
+        init();
+
+        let mut random_instance = fastrand::Rng::with_seed(0);
+
+        // the heavy item is sorted first so that the tiny probabilities accumulate on top of it
+        let mut probability_per_item: HashMap<u32, f64> = HashMap::new();
+        probability_per_item.insert(0, 1.0);
+        for item in 1..=10000 {
+            probability_per_item.insert(item, 0.0000001);
+        }
+        let mut probability_container: ProbabilityContainer<u32> = ProbabilityContainer::new(probability_per_item);
+
+        // the tiny probabilities total 0.001 and so should be chosen about 1000 times
+        let trials = 1000000;
+        let mut tiny_probability_items_total = 0;
+        for _ in 0..trials {
+            if probability_container.peek_random(&mut random_instance).unwrap() != 0 {
+                tiny_probability_items_total += 1;
+            }
+        }
+
+        assert!(tiny_probability_items_total > 500, "Tiny probability items were only chosen {tiny_probability_items_total} times.");
+        assert!(tiny_probability_items_total < 1500, "Tiny probability items were chosen {tiny_probability_items_total} times.");
+    }
+
+    #[test]
+    fn probability_container_create_many_instances_and_pop_random_all_with_extreme_probabilities() {
+        init();
+
+        let mut random_instance = fastrand::Rng::with_seed(0);
+
+        let number_of_instances = 10000;
+        // each item is 100000 times more likely than the previous item, from 1e-35 to 1e+5
+        let number_of_items: i32 = 9;
+
+        let mut descending_pops_total = 0;
+        for instance_index in 0..number_of_instances {
+            let mut probability_container: ProbabilityContainer<TestStruct> = ProbabilityContainer::default();
+            // pushing the most likely item first adds the tiny probabilities on top of it within the cumulative probabilities
+            for index_offset in 0..number_of_items {
+                let index = if instance_index % 2 == 0 { index_offset } else { (index_offset + number_of_items - 1) % number_of_items };
+                probability_container.push(TestStruct { id: index.to_string() }, 10.0_f64.powi(index * 5 - 35));
+            }
+
+            let mut popped_ids: Vec<String> = Vec::new();
+            while let Some(popped_item) = probability_container.pop_random(&mut random_instance) {
+                popped_ids.push(popped_item.id);
+            }
+            assert_eq!(number_of_items as usize, popped_ids.len());
+            if popped_ids.iter().rev().enumerate().all(|(index, id)| *id == index.to_string()) {
+                descending_pops_total += 1;
+            }
+        }
+
+        // any pair of items is popped out of order about once per 100000 pops
+        assert!(descending_pops_total > number_of_instances - 20, "Items were only popped in order of probability {descending_pops_total} times.");
+    }
+
+    #[test]
+    fn probability_container_ten_thousand_items_of_tiny_equal_probability() {
+        init();
+
+        let number_of_items: usize = 10000;
+        let get_popped_ids = || -> Vec<String> {
+            // each hashmap iterates in its own order, so only the order key of each item decides its position
+            let mut probability_per_item: HashMap<TestStruct, f64> = HashMap::new();
+            for index in 0..number_of_items {
+                probability_per_item.insert(TestStruct::new(index.to_string()), 1e-5);
+            }
+            let mut probability_container: ProbabilityContainer<TestStruct> = ProbabilityContainer::new(probability_per_item);
+            let mut random_instance = fastrand::Rng::with_seed(0);
+            let mut popped_ids: Vec<String> = Vec::new();
+            while let Some(popped_item) = probability_container.pop_random(&mut random_instance) {
+                popped_ids.push(popped_item.id);
+            }
+            popped_ids
+        };
+
+        let popped_ids = get_popped_ids();
+        assert_eq!(number_of_items, popped_ids.len());
+        assert_eq!(number_of_items, popped_ids.iter().collect::<HashSet<&String>>().len());
+        assert_eq!(popped_ids, get_popped_ids());
+    }
 }
 
 #[cfg(test)]
+#[allow(clippy::needless_late_init, clippy::needless_borrow, clippy::to_string_in_format_args)]
 mod wave_function_unit_tests {
 
-    use std::collections::HashMap;
+    use std::{cell::RefCell, collections::{HashMap, HashSet}, ops::ControlFlow, rc::Rc, sync::{Arc, atomic::{AtomicBool, Ordering}}, time::Duration};
     use uuid::Uuid;
-    use crate::wave_function::{Node, WaveFunction, NodeStateCollection, NodeStateProbability, collapsable_wave_function::{sequential_collapsable_wave_function::SequentialCollapsableWaveFunction, collapsable_wave_function::{CollapsedWaveFunction, CollapsedNodeState, CollapsableWaveFunction}, accommodating_collapsable_wave_function::AccommodatingCollapsableWaveFunction, accommodating_sequential_collapsable_wave_function::AccommodatingSequentialCollapsableWaveFunction}};
+    use super::allocation;
+    use crate::wave_function::{Node, WaveFunction, NodeStateCollection, SymmetricConstraint, AllDifferentConstraint, GlobalConstraint, AggregateConstraint, FunctionalNodeStateCollection, NodeStateProbability, AnonymousNode, AnonymousNodeStateCollection, wave_function_builder::WaveFunctionBuilder, grid::{self, AdjacencyKind, Direction, GridBuilder, GridTopology}, adjacency_learning, testing, visualize::{self, DotOptions}, collapsable_wave_function::{sequential_collapsable_wave_function::SequentialCollapsableWaveFunction, collapsable_wave_function::{CollapsedWaveFunction, CollapsedNodeState, CollapsableWaveFunction, CollapseOptions, PropagationDepth, CollapseProgress, CollapsedWaveFunctionWithDiagnostics, CollapsedNodeStateDiagnostics, CollapsedWaveFunctionWithMetadata, CollapsedNodeStateMetadata, CollapseSnapshot, CollapseError}, accommodating_collapsable_wave_function::AccommodatingCollapsableWaveFunction, accommodating_sequential_collapsable_wave_function::AccommodatingSequentialCollapsableWaveFunction, entropic_collapsable_wave_function::{EntropicCollapsableWaveFunction, NodeSelectionHeuristic}, parallel_collapsable_wave_function::ParallelCollapsableWaveFunction}};
 
     fn init() {
         std::env::set_var("RUST_LOG", "trace");
@@ -859,7 +1170,47 @@ mod wave_function_unit_tests {
 
         let wave_function = WaveFunction::new(nodes, node_state_collections);
         let validation_result = wave_function.validate();
-        assert_eq!("Not all nodes connect together. At least one node must be able to traverse to all other nodes.", validation_result.err().unwrap());
+        let error_message = validation_result.err().unwrap();
+        assert!(error_message.starts_with("Not all nodes connect together. At least one node must be able to traverse to all other nodes. Found 2 separate groups of nodes: 1 node including "), "Unexpected error message: {error_message}");
+    }
+
+    #[test]
+    fn two_islands_of_three_nodes() {
+        init();
+
+        let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_A"), vec![String::from("state_A")]);
+        let mut wave_function_builder: WaveFunctionBuilder<String> = WaveFunctionBuilder::new();
+        for island_name in ["first", "second"] {
+            for node_index in 0..3 {
+                wave_function_builder.add_node(&format!("{island_name}_{node_index}"), vec![String::from("state_A")]);
+            }
+            for node_index in 0..2 {
+                wave_function_builder.add_bidirectional_constraint(&format!("{island_name}_{node_index}"), &format!("{island_name}_{}", node_index + 1), permitted_node_state_ids_per_node_state_id.clone());
+            }
+        }
+        let error_message = wave_function_builder.build().err().unwrap();
+        assert_eq!("Not all nodes connect together. At least one node must be able to traverse to all other nodes. Found 2 separate groups of nodes: 3 nodes including first_0, first_1, first_2; 3 nodes including second_0, second_1, second_2.", error_message);
+    }
+
+    #[test]
+    fn one_directional_chain_without_start() {
+        init();
+
+        // each node can only traverse toward the middle node, so no node can traverse to both ends
+        let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_A"), vec![String::from("state_A")]);
+        let error_message = WaveFunctionBuilder::<String>::new()
+            .add_node("node_1", vec![String::from("state_A")])
+            .add_node("node_2", vec![String::from("state_A")])
+            .add_node("node_3", vec![String::from("state_A")])
+            .add_constraint("node_1", "node_2", permitted_node_state_ids_per_node_state_id.clone())
+            .add_constraint("node_3", "node_2", permitted_node_state_ids_per_node_state_id)
+            .build()
+            .err()
+            .unwrap();
+        assert!(error_message.starts_with("Not all nodes connect together. At least one node must be able to traverse to all other nodes. Node "), "Unexpected error message: {error_message}");
+        assert!(error_message.ends_with(" can only traverse to 2 of 3 nodes."), "Unexpected error message: {error_message}");
     }
 
     #[test]
@@ -1115,6 +1466,64 @@ mod wave_function_unit_tests {
         assert_eq!(&restricted_node_state_id, collapsed_wave_function.node_state_per_node_id.get(&second_node_id).unwrap());
     }
 
+    #[test]
+    fn two_nodes_with_parent_unrestricted_and_child_only_one_state_restricted_with_metadata() {
+        init();
+
+        let restricting_node_state_id: String = String::from("restricting");
+        let restricted_node_state_id: String = String::from("restricted");
+        let permitting_node_state_id: String = String::from("z_permitting");
+
+        let mut nodes: Vec<Node<String>> = vec![
+            // the permitting node state is tried first so that the parent is never revisited
+            Node::new_with_ordered_node_states(String::from("node_1"), vec![(permitting_node_state_id.clone(), 1.0), (restricting_node_state_id.clone(), 1.0)], HashMap::new()),
+            Node::new(String::from("node_2"), NodeStateProbability::get_equal_probability(&vec![restricted_node_state_id.clone()]), HashMap::new())
+        ];
+        let node_state_collections: Vec<NodeStateCollection<String>> = vec![
+            NodeStateCollection::new(String::from("restrictive"), restricting_node_state_id.clone(), vec![]),
+            NodeStateCollection::new(String::from("permitted"), permitting_node_state_id.clone(), vec![restricted_node_state_id.clone()])
+        ];
+        nodes[0].node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_2"), vec![String::from("restrictive"), String::from("permitted")]);
+
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate().unwrap();
+
+        let collapsed_wave_functions_with_metadata: Vec<CollapsedWaveFunctionWithMetadata<String>> = vec![
+            wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse_with_metadata().unwrap(),
+            wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(None).collapse_with_metadata().unwrap(),
+            wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(None).collapse_with_metadata().unwrap(),
+            wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(None).collapse_with_metadata().unwrap()
+        ];
+        // the entropic collapsable wave function collapses the child first since it has the least entropy
+        for (collapsed_wave_function_with_metadata, expected_collapse_order_indexes) in collapsed_wave_functions_with_metadata.iter().zip([(0, 1), (1, 0), (0, 1), (0, 1)]) {
+            let collapsed_wave_function = &collapsed_wave_function_with_metadata.collapsed_wave_function;
+            assert_eq!(&permitting_node_state_id, collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap());
+            assert_eq!(&restricted_node_state_id, collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap());
+            assert_eq!(&CollapsedNodeStateMetadata {
+                remaining_node_states_total: 2,
+                is_forced: false,
+                collapse_order_index: expected_collapse_order_indexes.0,
+                revisits_total: 0
+            }, collapsed_wave_function_with_metadata.metadata_per_node_id.get("node_1").unwrap());
+            assert_eq!(&CollapsedNodeStateMetadata {
+                remaining_node_states_total: 1,
+                is_forced: true,
+                collapse_order_index: expected_collapse_order_indexes.1,
+                revisits_total: 0
+            }, collapsed_wave_function_with_metadata.metadata_per_node_id.get("node_2").unwrap());
+        }
+
+        // trying the restricting node state first requires returning to the parent once the child cannot be collapsed
+        let mut nodes = wave_function.get_nodes();
+        nodes[0] = Node::new_with_ordered_node_states(String::from("node_1"), vec![(restricting_node_state_id.clone(), 1.0), (permitting_node_state_id.clone(), 1.0)], nodes[0].node_state_collection_ids_per_neighbor_node_id.clone());
+        let wave_function = WaveFunction::new(nodes, wave_function.get_node_state_collections());
+        let collapsed_wave_function_with_metadata = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse_with_metadata().unwrap();
+        assert_eq!(&permitting_node_state_id, collapsed_wave_function_with_metadata.collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap());
+        assert_eq!(1, collapsed_wave_function_with_metadata.metadata_per_node_id.get("node_1").unwrap().revisits_total);
+        assert_eq!(0, collapsed_wave_function_with_metadata.metadata_per_node_id.get("node_2").unwrap().revisits_total);
+        assert_eq!(0, collapsed_wave_function_with_metadata.metadata_per_node_id.get("node_1").unwrap().collapse_order_index);
+    }
+
     #[test]
     fn two_nodes_with_child_two_states_restricted_and_parent_one_state_unrestricted_sequential() {
         init();
@@ -1496,6 +1905,141 @@ mod wave_function_unit_tests {
         assert_ne!(collapsed_wave_function.node_state_per_node_id.get(&second_node_id).unwrap(), collapsed_wave_function.node_state_per_node_id.get(&first_node_id).unwrap());
     }
 
+    #[test]
+    fn two_nodes_both_as_neighbors_and_different_states_with_symmetric_neighbor() {
+        init();
+
+        let one_node_state_id: String = String::from("one");
+        let two_node_state_id: String = String::from("two");
+
+        // only the rules from the first node to the second node are provided
+        let node_state_collections: Vec<NodeStateCollection<String>> = vec![
+            NodeStateCollection::new(String::from("if_one_not_two"), one_node_state_id.clone(), vec![two_node_state_id.clone()]),
+            NodeStateCollection::new(String::from("if_two_not_one"), two_node_state_id.clone(), vec![one_node_state_id.clone()])
+        ];
+
+        for random_seed in 0..10 {
+            let mut first_node = Node::new(
+                String::from("first"),
+                NodeStateProbability::get_equal_probability(&vec![one_node_state_id.clone(), two_node_state_id.clone()]),
+                HashMap::new()
+            );
+            let mut second_node = Node::new(
+                String::from("second"),
+                NodeStateProbability::get_equal_probability(&vec![one_node_state_id.clone(), two_node_state_id.clone()]),
+                HashMap::new()
+            );
+
+            let reversed_node_state_collections = first_node.add_symmetric_neighbor(&mut second_node, &node_state_collections);
+            assert_eq!(vec![String::from("if_one_not_two"), String::from("if_two_not_one")], first_node.node_state_collection_ids_per_neighbor_node_id["second"]);
+            assert_eq!(vec![String::from("if_one_not_two_reversed_0"), String::from("if_two_not_one_reversed_1")], second_node.node_state_collection_ids_per_neighbor_node_id["first"]);
+
+            let mut all_node_state_collections = node_state_collections.clone();
+            all_node_state_collections.extend(reversed_node_state_collections);
+
+            let wave_function = WaveFunction::new(vec![first_node, second_node], all_node_state_collections);
+            wave_function.validate().unwrap();
+
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+
+            assert_ne!(collapsed_wave_function.node_state_per_node_id["first"], collapsed_wave_function.node_state_per_node_id["second"]);
+        }
+    }
+
+    #[test]
+    fn reversed_node_state_collections_permit_each_original_node_state() {
+        init();
+
+        let node_state_collections: Vec<NodeStateCollection<char>> = vec![
+            NodeStateCollection::new(String::from("a_permits"), 'a', vec!['b', 'c']),
+            NodeStateCollection::new(String::from("b_permits"), 'b', vec!['c', 'c'])
+        ];
+
+        let reversed_node_state_collections = NodeStateCollection::reversed_from(&node_state_collections);
+
+        assert_eq!(2, reversed_node_state_collections.len());
+        assert_eq!("a_permits_reversed_0", reversed_node_state_collections[0].id);
+        assert_eq!('b', reversed_node_state_collections[0].node_state_id);
+        assert_eq!(vec!['a'], reversed_node_state_collections[0].node_state_ids);
+        assert_eq!("a_permits_b_permits_reversed_1", reversed_node_state_collections[1].id);
+        assert_eq!('c', reversed_node_state_collections[1].node_state_id);
+        assert_eq!(vec!['a', 'b'], reversed_node_state_collections[1].node_state_ids);
+    }
+
+    fn get_two_nodes_with_forbidden_node_state_collections(node_state_ids: &[char]) -> (Vec<Node<char>>, Vec<NodeStateCollection<char>>) {
+        // a is never next to b, while any node state without a forbidden node state collection is permitted next to anything
+        let mut node_state_collections: Vec<NodeStateCollection<char>> = vec![
+            NodeStateCollection::new_forbidden(String::from("a_forbids"), 'a', vec!['b']),
+            NodeStateCollection::new_forbidden(String::from("b_forbids"), 'b', vec!['a'])
+        ];
+        for node_state_id in node_state_ids.iter().filter(|node_state_id| !['a', 'b'].contains(node_state_id)) {
+            node_state_collections.push(NodeStateCollection::new_forbidden(format!("{node_state_id}_forbids"), *node_state_id, Vec::new()));
+        }
+        let node_state_collection_ids: Vec<String> = node_state_collections.iter().map(|node_state_collection| node_state_collection.id.clone()).collect();
+        let nodes: Vec<Node<char>> = vec![
+            Node::new(String::from("node_1"), NodeStateProbability::get_equal_probability(&node_state_ids.to_vec()), HashMap::from([(String::from("node_2"), node_state_collection_ids.clone())])),
+            Node::new(String::from("node_2"), NodeStateProbability::get_equal_probability(&node_state_ids.to_vec()), HashMap::from([(String::from("node_1"), node_state_collection_ids)]))
+        ];
+        (nodes, node_state_collections)
+    }
+
+    #[test]
+    fn forbidden_node_state_collections_permit_new_node_states() {
+        init();
+
+        let (nodes, node_state_collections) = get_two_nodes_with_forbidden_node_state_collections(&['a', 'b']);
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate().unwrap();
+        assert_eq!(2, wave_function.get_all_collapsed_wave_functions(None).count());
+
+        // c is added without changing the forbidden node state collections of a and b
+        let (nodes, node_state_collections) = get_two_nodes_with_forbidden_node_state_collections(&['a', 'b', 'c']);
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate().unwrap();
+        for node_state_collection in wave_function.get_node_state_collections().iter() {
+            assert!(!node_state_collection.is_forbidden);
+            if node_state_collection.node_state_id == 'a' {
+                let mut node_state_ids = node_state_collection.node_state_ids.clone();
+                node_state_ids.sort();
+                assert_eq!(vec!['a', 'c'], node_state_ids);
+            }
+        }
+        let mut node_state_pairs: Vec<(char, char)> = wave_function
+            .get_all_collapsed_wave_functions(None)
+            .map(|collapsed_wave_function| {
+                let collapsed_wave_function = collapsed_wave_function.unwrap();
+                (*collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap(), *collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap())
+            })
+            .collect();
+        node_state_pairs.sort();
+        assert_eq!(vec![('a', 'a'), ('a', 'c'), ('b', 'b'), ('b', 'c'), ('c', 'a'), ('c', 'b'), ('c', 'c')], node_state_pairs);
+    }
+
+    #[test]
+    fn forbidden_node_state_collections_round_trip_through_json() {
+        init();
+
+        let node_state_collection = NodeStateCollection::new_forbidden(String::from("a_forbids"), 'a', vec!['b']);
+        let serialized_node_state_collection = serde_json::to_string(&node_state_collection).unwrap();
+        assert_eq!("{\"id\":\"a_forbids\",\"node_state_id\":\"a\",\"node_state_ids\":[\"b\"],\"is_forbidden\":true}", serialized_node_state_collection);
+        let deserialized_node_state_collection: NodeStateCollection<char> = serde_json::from_str(&serialized_node_state_collection).unwrap();
+        assert!(deserialized_node_state_collection.is_forbidden);
+        assert_eq!(vec!['b'], deserialized_node_state_collection.node_state_ids);
+
+        // permitted node state collections are serialized as they were before forbidden node state collections existed
+        let node_state_collection = NodeStateCollection::new(String::from("a_permits"), 'a', vec!['c']);
+        assert_eq!("{\"id\":\"a_permits\",\"node_state_id\":\"a\",\"node_state_ids\":[\"c\"]}", serde_json::to_string(&node_state_collection).unwrap());
+
+        // loading a wave function resolves its forbidden node state collections
+        let (nodes, node_state_collections) = get_two_nodes_with_forbidden_node_state_collections(&['a', 'b', 'c']);
+        let serialized_wave_function = serde_json::json!({
+            "nodes": nodes,
+            "node_state_collections": node_state_collections
+        }).to_string();
+        let wave_function: WaveFunction<char> = serde_json::from_str(&serialized_wave_function).unwrap();
+        assert_eq!(7, wave_function.get_all_collapsed_wave_functions(None).count());
+    }
+
     #[test]
     fn two_nodes_both_as_neighbors_and_different_states_with_random_runs() {
         init();
@@ -1570,112 +2114,66 @@ mod wave_function_unit_tests {
         let mut random_instance = fastrand::Rng::new();
 
         for _ in 0..10 {
-            let mut nodes: Vec<Node<String>> = Vec::new();
-            let mut node_state_collections: Vec<NodeStateCollection<String>> = Vec::new();
-
-            let one_node_state_id: String = String::from("state_A");
-            let two_node_state_id: String = String::from("state_B");
-            let three_node_state_id: String = String::from("state_C");
-            let four_node_state_id: String = String::from("state_D");
-
-            nodes.push(Node::new(
-                String::from("node_1"),
-                NodeStateProbability::get_equal_probability(&vec![one_node_state_id.clone(), two_node_state_id.clone(), three_node_state_id.clone(), four_node_state_id.clone()]),
-                HashMap::new()
-            ));
-            nodes.push(Node::new(
-                String::from("node_2"),
-                NodeStateProbability::get_equal_probability(&vec![one_node_state_id.clone(), two_node_state_id.clone(), three_node_state_id.clone(), four_node_state_id.clone()]),
-                HashMap::new()
-            ));
-
-            let first_node_id: String = nodes[0].id.clone();
-            let second_node_id: String = nodes[1].id.clone();
-
-            let if_one_then_three_node_state_collection_id: String = Uuid::new_v4().to_string();
-            let if_one_then_three_node_state_collection = NodeStateCollection::new(
-                if_one_then_three_node_state_collection_id.clone(),
-                one_node_state_id.clone(),
-                vec![three_node_state_id.clone()]
-            );
-            node_state_collections.push(if_one_then_three_node_state_collection);
-
-            let if_two_then_four_node_state_collection_id: String = Uuid::new_v4().to_string();
-            let if_two_then_four_node_state_collection = NodeStateCollection::new(
-                if_two_then_four_node_state_collection_id.clone(),
-                two_node_state_id.clone(),
-                vec![four_node_state_id.clone()]
-            );
-            node_state_collections.push(if_two_then_four_node_state_collection);
-
-            let if_three_then_no_node_state_collection_id: String = Uuid::new_v4().to_string();
-            let if_three_then_no_node_state_collection = NodeStateCollection::new(
-                if_three_then_no_node_state_collection_id.clone(),
-                three_node_state_id.clone(),
-                Vec::new()
-            );
-            node_state_collections.push(if_three_then_no_node_state_collection);
-
-            let if_four_then_no_node_state_collection_id: String = Uuid::new_v4().to_string();
-            let if_four_then_no_node_state_collection = NodeStateCollection::new(
-                if_four_then_no_node_state_collection_id.clone(),
-                four_node_state_id.clone(),
-                Vec::new()
-            );
-            node_state_collections.push(if_four_then_no_node_state_collection);
-
-            let if_three_then_two_node_state_collection_id: String = Uuid::new_v4().to_string();
-            let if_three_then_two_node_state_collection = NodeStateCollection::new(
-                if_three_then_two_node_state_collection_id.clone(),
-                three_node_state_id.clone(),
-                vec![two_node_state_id.clone()]
-            );
-            node_state_collections.push(if_three_then_two_node_state_collection);
+            let wave_function = get_two_nodes_with_conflicting_state_requirements_wave_function();
+            let random_seed = Some(random_instance.u64(..));
 
-            let if_four_then_one_node_state_collection_id: String = Uuid::new_v4().to_string();
-            let if_four_then_one_node_state_collection = NodeStateCollection::new(
-                if_four_then_one_node_state_collection_id.clone(),
-                four_node_state_id.clone(),
-                vec![one_node_state_id.clone()]
-            );
-            node_state_collections.push(if_four_then_one_node_state_collection);
+            let collapsed_wave_function_result = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(random_seed).collapse();
 
-            let if_one_then_no_node_state_collection_id: String = Uuid::new_v4().to_string();
-            let if_one_then_no_node_state_collection = NodeStateCollection::new(
-                if_one_then_no_node_state_collection_id.clone(),
-                one_node_state_id.clone(),
-                Vec::new()
-            );
-            node_state_collections.push(if_one_then_no_node_state_collection);
+            assert_eq!("Cannot collapse wave function.", collapsed_wave_function_result.err().unwrap());
+        }
+    }
 
-            let if_two_then_no_node_state_collection_id: String = Uuid::new_v4().to_string();
-            let if_two_then_no_node_state_collection = NodeStateCollection::new(
-                if_two_then_no_node_state_collection_id.clone(),
-                two_node_state_id.clone(),
-                Vec::new()
-            );
-            node_state_collections.push(if_two_then_no_node_state_collection);
+    #[test]
+    fn explain_uncollapsable_two_nodes_both_as_neighbors_with_conflicting_state_requirements() {
+        init();
 
-            nodes[0].node_state_collection_ids_per_neighbor_node_id.insert(second_node_id.clone(), Vec::new());
-            nodes[0].node_state_collection_ids_per_neighbor_node_id.get_mut(&second_node_id).unwrap().push(if_one_then_three_node_state_collection_id.clone());
-            nodes[0].node_state_collection_ids_per_neighbor_node_id.get_mut(&second_node_id).unwrap().push(if_two_then_four_node_state_collection_id.clone());
-            nodes[0].node_state_collection_ids_per_neighbor_node_id.get_mut(&second_node_id).unwrap().push(if_three_then_no_node_state_collection_id.clone());
-            nodes[0].node_state_collection_ids_per_neighbor_node_id.get_mut(&second_node_id).unwrap().push(if_four_then_no_node_state_collection_id.clone());
+        let wave_function = get_two_nodes_with_conflicting_state_requirements_wave_function();
+        let then_no_node_state_collection_ids: Vec<String> = wave_function
+            .get_node_state_collections()
+            .into_iter()
+            .filter(|node_state_collection| node_state_collection.node_state_ids.is_empty())
+            .map(|node_state_collection| node_state_collection.id)
+            .collect();
+        assert_eq!(4, then_no_node_state_collection_ids.len());
+
+        let conflict_report = wave_function.explain_uncollapsable(Some(0)).unwrap();
+
+        assert_eq!(vec![String::from("node_1"), String::from("node_2")], conflict_report.node_ids);
+        assert!(!conflict_report.conflicting_constraints.is_empty());
+        for conflicting_constraint in conflict_report.conflicting_constraints.iter() {
+            assert!(then_no_node_state_collection_ids.contains(&conflicting_constraint.node_state_collection_id), "Unexpected conflicting constraint: {conflicting_constraint:?}");
+            assert_eq!(4, conflicting_constraint.eliminated_node_state_ids.len());
+        }
+        assert_eq!(conflict_report.conflicting_constraints.len(), conflict_report.node_state_collection_ids.len());
 
-            nodes[1].node_state_collection_ids_per_neighbor_node_id.insert(first_node_id.clone(), Vec::new());
-            nodes[1].node_state_collection_ids_per_neighbor_node_id.get_mut(&first_node_id).unwrap().push(if_three_then_two_node_state_collection_id.clone());
-            nodes[1].node_state_collection_ids_per_neighbor_node_id.get_mut(&first_node_id).unwrap().push(if_four_then_one_node_state_collection_id.clone());
-            nodes[1].node_state_collection_ids_per_neighbor_node_id.get_mut(&first_node_id).unwrap().push(if_one_then_no_node_state_collection_id.clone());
-            nodes[1].node_state_collection_ids_per_neighbor_node_id.get_mut(&first_node_id).unwrap().push(if_two_then_no_node_state_collection_id.clone());
+        // removing the conflicting constraints leaves the wave function collapsable
+        let mut nodes = wave_function.get_nodes();
+        for conflicting_constraint in conflict_report.conflicting_constraints.iter() {
+            let node = nodes.iter_mut().find(|node| node.id == conflicting_constraint.node_id).unwrap();
+            node.node_state_collection_ids_per_neighbor_node_id.get_mut(&conflicting_constraint.neighbor_node_id).unwrap().retain(|node_state_collection_id| *node_state_collection_id != conflicting_constraint.node_state_collection_id);
+        }
+        let relaxed_wave_function = WaveFunction::new(nodes, wave_function.get_node_state_collections());
+        let collapsed_wave_function = relaxed_wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap();
+        relaxed_wave_function.validate_collapsed(&collapsed_wave_function).unwrap();
 
-            let wave_function = WaveFunction::new(nodes, node_state_collections);
-            wave_function.validate().unwrap();
-            let random_seed = Some(random_instance.u64(..));
+        // the same report is found regardless of the random seed
+        assert_eq!(conflict_report, wave_function.explain_uncollapsable(Some(1)).unwrap());
+    }
 
-            let collapsed_wave_function_result = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(random_seed).collapse();
+    #[test]
+    fn explain_uncollapsable_of_collapsable_wave_function() {
+        init();
 
-            assert_eq!("Cannot collapse wave function.", collapsed_wave_function_result.err().unwrap());
+        let wave_function = get_two_nodes_with_conflicting_state_requirements_wave_function();
+        let mut nodes = wave_function.get_nodes();
+        for node in nodes.iter_mut() {
+            for node_state_collection_ids in node.node_state_collection_ids_per_neighbor_node_id.values_mut() {
+                node_state_collection_ids.clear();
+            }
         }
+        let unrestricted_wave_function = WaveFunction::new(nodes, wave_function.get_node_state_collections());
+
+        assert_eq!(None, unrestricted_wave_function.explain_uncollapsable(Some(0)));
     }
 
     #[test]
@@ -1740,90 +2238,40 @@ mod wave_function_unit_tests {
         assert_eq!(&node_state_id, collapsed_wave_function.node_state_per_node_id.get(&third_node_id).unwrap());
     }
 
-    #[test]
-    fn three_nodes_as_dense_neighbors_all_different_states_sequential() {
-        init();
+    // every node state collection id is prefixed along with the node ids so that copies of this wave function can be merged together
+    fn get_three_nodes_as_dense_neighbors_all_different_states_wave_function(node_id_prefix: &str) -> WaveFunction<String> {
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B"), String::from("state_C")];
 
-        let mut nodes: Vec<Node<String>> = Vec::new();
         let mut node_state_collections: Vec<NodeStateCollection<String>> = Vec::new();
+        for node_state_id in node_state_ids.iter() {
+            let other_node_state_ids: Vec<String> = node_state_ids.iter().filter(|other_node_state_id| *other_node_state_id != node_state_id).cloned().collect();
+            node_state_collections.push(NodeStateCollection::new(format!("{node_id_prefix}_different_{node_state_id}"), node_state_id.clone(), other_node_state_ids));
+        }
+        let node_state_collection_ids: Vec<String> = node_state_collections.iter().map(|node_state_collection| node_state_collection.id.clone()).collect();
 
-        let first_node_state_id: String = String::from("state_A");
-        let second_node_state_id: String = String::from("state_B");
-        let third_node_state_id: String = String::from("state_C");
-
-        nodes.push(Node::new(
-            String::from("node_1"),
-            NodeStateProbability::get_equal_probability(&vec![first_node_state_id.clone(), second_node_state_id.clone(), third_node_state_id.clone()]),
-            HashMap::new()
-        ));
-        nodes.push(Node::new(
-            String::from("node_2"),
-            NodeStateProbability::get_equal_probability(&vec![first_node_state_id.clone(), second_node_state_id.clone(), third_node_state_id.clone()]),
-            HashMap::new()
-        ));
-        nodes.push(Node::new(
-            String::from("node_3"),
-            NodeStateProbability::get_equal_probability(&vec![first_node_state_id.clone(), second_node_state_id.clone(), third_node_state_id.clone()]),
-            HashMap::new()
-        ));
-
-        let first_node_id: String = nodes[0].id.clone();
-        let second_node_id: String = nodes[1].id.clone();
-        let third_node_id: String = nodes[2].id.clone();
-
-        let all_but_first_node_state_collection_id: String = String::from("nsc_1");
-        let all_but_first_node_state_collection = NodeStateCollection::new(
-            all_but_first_node_state_collection_id.clone(),
-            first_node_state_id.clone(),
-            vec![second_node_state_id.clone(), third_node_state_id.clone()]
-        );
-        node_state_collections.push(all_but_first_node_state_collection);
-
-        let all_but_second_node_state_collection_id: String = String::from("nsc_2");
-        let all_but_second_node_state_collection = NodeStateCollection::new(
-            all_but_second_node_state_collection_id.clone(),
-            second_node_state_id.clone(),
-            vec![first_node_state_id.clone(), third_node_state_id.clone()]
-        );
-        node_state_collections.push(all_but_second_node_state_collection);
-
-        let all_but_third_node_state_collection_id: String = String::from("nsc_3");
-        let all_but_third_node_state_collection = NodeStateCollection::new(
-            all_but_third_node_state_collection_id.clone(),
-            third_node_state_id.clone(),
-            vec![first_node_state_id.clone(), second_node_state_id.clone()]
-        );
-        node_state_collections.push(all_but_third_node_state_collection);
-
-        nodes[0].node_state_collection_ids_per_neighbor_node_id.insert(second_node_id.clone(), Vec::new());
-        nodes[0].node_state_collection_ids_per_neighbor_node_id.get_mut(&second_node_id).unwrap().push(all_but_first_node_state_collection_id.clone());
-        nodes[0].node_state_collection_ids_per_neighbor_node_id.get_mut(&second_node_id).unwrap().push(all_but_second_node_state_collection_id.clone());
-        nodes[0].node_state_collection_ids_per_neighbor_node_id.get_mut(&second_node_id).unwrap().push(all_but_third_node_state_collection_id.clone());
-        nodes[0].node_state_collection_ids_per_neighbor_node_id.insert(third_node_id.clone(), Vec::new());
-        nodes[0].node_state_collection_ids_per_neighbor_node_id.get_mut(&third_node_id).unwrap().push(all_but_first_node_state_collection_id.clone());
-        nodes[0].node_state_collection_ids_per_neighbor_node_id.get_mut(&third_node_id).unwrap().push(all_but_second_node_state_collection_id.clone());
-        nodes[0].node_state_collection_ids_per_neighbor_node_id.get_mut(&third_node_id).unwrap().push(all_but_third_node_state_collection_id.clone());
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        for node_index in 1..=3 {
+            let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+            for neighbor_node_index in 1..=3 {
+                if neighbor_node_index != node_index {
+                    node_state_collection_ids_per_neighbor_node_id.insert(format!("{node_id_prefix}_{neighbor_node_index}"), node_state_collection_ids.clone());
+                }
+            }
+            nodes.push(Node::new(format!("{node_id_prefix}_{node_index}"), NodeStateProbability::get_equal_probability(&node_state_ids), node_state_collection_ids_per_neighbor_node_id));
+        }
 
-        nodes[1].node_state_collection_ids_per_neighbor_node_id.insert(first_node_id.clone(), Vec::new());
-        nodes[1].node_state_collection_ids_per_neighbor_node_id.get_mut(&first_node_id).unwrap().push(all_but_first_node_state_collection_id.clone());
-        nodes[1].node_state_collection_ids_per_neighbor_node_id.get_mut(&first_node_id).unwrap().push(all_but_second_node_state_collection_id.clone());
-        nodes[1].node_state_collection_ids_per_neighbor_node_id.get_mut(&first_node_id).unwrap().push(all_but_third_node_state_collection_id.clone());
-        nodes[1].node_state_collection_ids_per_neighbor_node_id.insert(third_node_id.clone(), Vec::new());
-        nodes[1].node_state_collection_ids_per_neighbor_node_id.get_mut(&third_node_id).unwrap().push(all_but_first_node_state_collection_id.clone());
-        nodes[1].node_state_collection_ids_per_neighbor_node_id.get_mut(&third_node_id).unwrap().push(all_but_second_node_state_collection_id.clone());
-        nodes[1].node_state_collection_ids_per_neighbor_node_id.get_mut(&third_node_id).unwrap().push(all_but_third_node_state_collection_id.clone());
+        WaveFunction::new(nodes, node_state_collections)
+    }
 
-        nodes[2].node_state_collection_ids_per_neighbor_node_id.insert(first_node_id.clone(), Vec::new());
-        nodes[2].node_state_collection_ids_per_neighbor_node_id.get_mut(&first_node_id).unwrap().push(all_but_first_node_state_collection_id.clone());
-        nodes[2].node_state_collection_ids_per_neighbor_node_id.get_mut(&first_node_id).unwrap().push(all_but_second_node_state_collection_id.clone());
-        nodes[2].node_state_collection_ids_per_neighbor_node_id.get_mut(&first_node_id).unwrap().push(all_but_third_node_state_collection_id.clone());
-        nodes[2].node_state_collection_ids_per_neighbor_node_id.insert(second_node_id.clone(), Vec::new());
-        nodes[2].node_state_collection_ids_per_neighbor_node_id.get_mut(&second_node_id).unwrap().push(all_but_first_node_state_collection_id.clone());
-        nodes[2].node_state_collection_ids_per_neighbor_node_id.get_mut(&second_node_id).unwrap().push(all_but_second_node_state_collection_id.clone());
-        nodes[2].node_state_collection_ids_per_neighbor_node_id.get_mut(&second_node_id).unwrap().push(all_but_third_node_state_collection_id.clone());
+    #[test]
+    fn three_nodes_as_dense_neighbors_all_different_states_sequential() {
+        init();
 
-        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        let wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
         wave_function.validate().unwrap();
+        let first_node_id: String = String::from("node_1");
+        let second_node_id: String = String::from("node_2");
+        let third_node_id: String = String::from("node_3");
 
         let collapsed_wave_function_result = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse();
 
@@ -2414,6 +2862,33 @@ mod wave_function_unit_tests {
 
     }
 
+    #[test]
+    fn many_nodes_as_dense_neighbors_all_different_states_from_constraint_sequential() {
+        init();
+
+        let nodes_total = 200;
+
+        let node_state_ids: Vec<u32> = (0..nodes_total).collect();
+        let node_ids: Vec<String> = (0..nodes_total).map(|index| format!("node_{index}")).collect();
+        let nodes: Vec<Node<u32>> = node_ids
+            .iter()
+            .map(|node_id| Node::new(
+                node_id.clone(),
+                NodeStateProbability::get_equal_probability(&node_state_ids),
+                HashMap::new()
+            ))
+            .collect();
+
+        // every pair of nodes shares the same node state collections, so the masks are only constructed once
+        let wave_function = WaveFunction::new_with_all_different_constraints(nodes, Vec::new(), vec![AllDifferentConstraint::new(node_ids.clone())]).unwrap();
+        wave_function.validate().unwrap();
+
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<u32>>(Some(0)).collapse().unwrap();
+
+        let node_states: std::collections::HashSet<u32> = collapsed_wave_function.node_state_per_node_id.values().cloned().collect();
+        assert_eq!(nodes_total as usize, node_states.len());
+    }
+
     #[test]
     fn many_nodes_as_dense_neighbors_randomly_all_different_states() {
         //init();
@@ -2499,6 +2974,61 @@ mod wave_function_unit_tests {
         }
     }
 
+    #[allow(non_snake_case)]
+    fn get_3D_grid_topology(nodes_width: i32, nodes_height: i32, nodes_depth: i32) -> GridTopology {
+        GridTopology::new_with_surrounding_neighbors(vec![nodes_width as usize, nodes_height as usize, nodes_depth as usize])
+    }
+
+    fn tie_nodes_to_grid_neighbors(nodes: &mut [Node<String>], node_ids: &[String], grid_topology: &GridTopology, node_state_collection_ids: &[String]) {
+        for (node_index, node) in nodes.iter_mut().enumerate() {
+            for neighbor_node_index in grid_topology.get_neighbor_indexes(node_index) {
+                node.node_state_collection_ids_per_neighbor_node_id.insert(node_ids[neighbor_node_index].clone(), node_state_collection_ids.to_vec());
+            }
+        }
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn grid_topology_3D_neighbors_same_as_comparing_every_pair() {
+        init();
+
+        let (nodes_width, nodes_height, nodes_depth): (i32, i32, i32) = (4, 3, 5);
+        let nodes_total = nodes_width * nodes_height * nodes_depth;
+        let grid_topology = get_3D_grid_topology(nodes_width, nodes_height, nodes_depth);
+        assert_eq!(nodes_total as usize, grid_topology.get_nodes_total());
+
+        for node_index in 0..nodes_total {
+            let node_x: i32 = node_index % nodes_width;
+            let node_y: i32 = (node_index / nodes_width) % nodes_height;
+            let node_z: i32 = (node_index / (nodes_width * nodes_height)) % nodes_depth;
+            assert_eq!(vec![node_x as usize, node_y as usize, node_z as usize], grid_topology.get_coordinate(node_index as usize));
+            assert_eq!(node_index as usize, grid_topology.get_index(&grid_topology.get_coordinate(node_index as usize)));
+
+            let mut expected_neighbor_node_indexes: Vec<usize> = Vec::new();
+            for other_node_index in 0..nodes_total {
+                let other_node_x: i32 = other_node_index % nodes_width;
+                let other_node_y: i32 = (other_node_index / nodes_width) % nodes_height;
+                let other_node_z: i32 = (other_node_index / (nodes_width * nodes_height)) % nodes_depth;
+                if node_index != other_node_index && (node_x - other_node_x).abs() <= 1 && (node_y - other_node_y).abs() <= 1 && (node_z - other_node_z).abs() <= 1 {
+                    expected_neighbor_node_indexes.push(other_node_index as usize);
+                }
+            }
+            let mut neighbor_node_indexes = grid_topology.get_neighbor_indexes(node_index as usize);
+            neighbor_node_indexes.sort();
+            assert_eq!(expected_neighbor_node_indexes, neighbor_node_indexes);
+        }
+
+        // wrapping a grid only three nodes wide reaches each neighbor from only one offset
+        let mut grid_topology = GridTopology::new_with_surrounding_neighbors(vec![3, 3, 3]);
+        grid_topology.set_is_wrapping(true);
+        for node_index in 0..27 {
+            assert_eq!(26, grid_topology.get_neighbor_indexes(node_index).len());
+        }
+        let mut grid_topology = GridTopology::new_with_surrounding_neighbors(vec![2, 3]);
+        grid_topology.set_is_wrapping(true);
+        assert_eq!(vec![5, 1, 3, 4, 2], grid_topology.get_neighbor_indexes(0));
+    }
+
     #[test]
     #[allow(non_snake_case)]
     fn many_nodes_as_3D_grid_all_different_states_sequential() {
@@ -2548,21 +3078,15 @@ mod wave_function_unit_tests {
         }
 
         // tie nodes to their neighbors
-        for (node_index, node) in std::iter::zip(0..nodes_total, nodes.iter_mut()) {
-            let node_x: i32 = node_index % nodes_width;
-            let node_y: i32 = (node_index / nodes_width) % nodes_height;
-            let node_z: i32 = (node_index / (nodes_width * nodes_height)) % nodes_depth;
-            //debug!("processing node {node_x}, {node_y}, {node_z}.");
-            for (other_node_index, other_node_id) in std::iter::zip(0..nodes_total, node_ids.iter()) {
-                let other_node_x: i32 = other_node_index % nodes_width;
-                let other_node_y: i32 = (other_node_index / nodes_width) % nodes_height;
-                let other_node_z: i32 = (other_node_index / (nodes_width * nodes_height)) % nodes_depth;
-                if node_index != other_node_index && (node_x - other_node_x).abs() <= 1 && (node_y - other_node_y).abs() <= 1 && (node_z - other_node_z).abs() <= 1 {
-                    //debug!("found neighbor at {other_node_x}, {other_node_y}, {other_node_z}.");
-                    node.node_state_collection_ids_per_neighbor_node_id.insert(other_node_id.clone(), node_state_collection_ids.clone());
-                }
-            }
-        }
+        let grid_topology = get_3D_grid_topology(nodes_width, nodes_height, nodes_depth);
+        tie_nodes_to_grid_neighbors(&mut nodes, &node_ids, &grid_topology, &node_state_collection_ids);
+
+        // finding the neighbors of every node took over half a second when every pair of nodes was compared
+        let neighbors_started_at = std::time::Instant::now();
+        let neighbors_total: usize = (0..nodes_total as usize).map(|node_index| grid_topology.get_neighbor_indexes(node_index).len()).sum();
+        let neighbors_elapsed = neighbors_started_at.elapsed();
+        assert_eq!(26 * 23 * 23 * 23 + 17 * 23 * 23 * 6 + 11 * 23 * 12 + 7 * 8, neighbors_total);
+        assert!(neighbors_elapsed < Duration::from_millis(500), "Finding neighbors took {neighbors_elapsed:?}.");
 
         let wave_function: WaveFunction<String>;
 
@@ -2581,17 +3105,9 @@ mod wave_function_unit_tests {
         let collapsed_wave_function = collapsed_wave_function_result.ok().unwrap();
 
         // check that none of the neighbors match the same state
-        for (node_index, node_id) in std::iter::zip(0..nodes_total, node_ids.iter()) {
-            let node_x: i32 = node_index % nodes_width;
-            let node_y: i32 = (node_index / nodes_width) % nodes_height;
-            let node_z: i32 = (node_index / (nodes_width * nodes_height)) % nodes_depth;
-            for (other_node_index, other_node_id) in std::iter::zip(0..nodes_total, node_ids.iter()) {
-                let other_node_x: i32 = other_node_index % nodes_width;
-                let other_node_y: i32 = (other_node_index / nodes_width) % nodes_height;
-                let other_node_z: i32 = (other_node_index / (nodes_width * nodes_height)) % nodes_depth;
-                if node_index != other_node_index && (node_x - other_node_x).abs() <= 1 && (node_y - other_node_y).abs() <= 1 && (node_z - other_node_z).abs() <= 1 {
-                    assert_ne!(collapsed_wave_function.node_state_per_node_id.get(node_id), collapsed_wave_function.node_state_per_node_id.get(other_node_id));
-                }
+        for (node_index, node_id) in node_ids.iter().enumerate() {
+            for neighbor_node_index in grid_topology.get_neighbor_indexes(node_index) {
+                assert_ne!(collapsed_wave_function.node_state_per_node_id.get(node_id), collapsed_wave_function.node_state_per_node_id.get(&node_ids[neighbor_node_index]));
             }
         }
     }
@@ -2645,21 +3161,8 @@ mod wave_function_unit_tests {
         }
 
         // tie nodes to their neighbors
-        for (node_index, node) in std::iter::zip(0..nodes_total, nodes.iter_mut()) {
-            let node_x: i32 = node_index % nodes_width;
-            let node_y: i32 = (node_index / nodes_width) % nodes_height;
-            let node_z: i32 = (node_index / (nodes_width * nodes_height)) % nodes_depth;
-            //debug!("processing node {node_x}, {node_y}, {node_z}.");
-            for (other_node_index, other_node_id) in std::iter::zip(0..nodes_total, node_ids.iter()) {
-                let other_node_x: i32 = other_node_index % nodes_width;
-                let other_node_y: i32 = (other_node_index / nodes_width) % nodes_height;
-                let other_node_z: i32 = (other_node_index / (nodes_width * nodes_height)) % nodes_depth;
-                if node_index != other_node_index && (node_x - other_node_x).abs() <= 1 && (node_y - other_node_y).abs() <= 1 && (node_z - other_node_z).abs() <= 1 {
-                    //debug!("found neighbor at {other_node_x}, {other_node_y}, {other_node_z}.");
-                    node.node_state_collection_ids_per_neighbor_node_id.insert(other_node_id.clone(), node_state_collection_ids.clone());
-                }
-            }
-        }
+        let grid_topology = get_3D_grid_topology(nodes_width, nodes_height, nodes_depth);
+        tie_nodes_to_grid_neighbors(&mut nodes, &node_ids, &grid_topology, &node_state_collection_ids);
 
         let wave_function: WaveFunction<String>;
 
@@ -2678,17 +3181,9 @@ mod wave_function_unit_tests {
         let collapsed_wave_function = collapsed_wave_function_result.ok().unwrap();
 
         // check that none of the neighbors match the same state
-        for (node_index, node_id) in std::iter::zip(0..nodes_total, node_ids.iter()) {
-            let node_x: i32 = node_index % nodes_width;
-            let node_y: i32 = (node_index / nodes_width) % nodes_height;
-            let node_z: i32 = (node_index / (nodes_width * nodes_height)) % nodes_depth;
-            for (other_node_index, other_node_id) in std::iter::zip(0..nodes_total, node_ids.iter()) {
-                let other_node_x: i32 = other_node_index % nodes_width;
-                let other_node_y: i32 = (other_node_index / nodes_width) % nodes_height;
-                let other_node_z: i32 = (other_node_index / (nodes_width * nodes_height)) % nodes_depth;
-                if node_index != other_node_index && (node_x - other_node_x).abs() <= 1 && (node_y - other_node_y).abs() <= 1 && (node_z - other_node_z).abs() <= 1 {
-                    assert_ne!(collapsed_wave_function.node_state_per_node_id.get(node_id), collapsed_wave_function.node_state_per_node_id.get(other_node_id));
-                }
+        for (node_index, node_id) in node_ids.iter().enumerate() {
+            for neighbor_node_index in grid_topology.get_neighbor_indexes(node_index) {
+                assert_ne!(collapsed_wave_function.node_state_per_node_id.get(node_id), collapsed_wave_function.node_state_per_node_id.get(&node_ids[neighbor_node_index]));
             }
         }
     }
@@ -2742,21 +3237,8 @@ mod wave_function_unit_tests {
         }
 
         // tie nodes to their neighbors
-        for (node_index, node) in std::iter::zip(0..nodes_total, nodes.iter_mut()) {
-            let node_x: i32 = node_index % nodes_width;
-            let node_y: i32 = (node_index / nodes_width) % nodes_height;
-            let node_z: i32 = (node_index / (nodes_width * nodes_height)) % nodes_depth;
-            //debug!("processing node {node_x}, {node_y}, {node_z}.");
-            for (other_node_index, other_node_id) in std::iter::zip(0..nodes_total, node_ids.iter()) {
-                let other_node_x: i32 = other_node_index % nodes_width;
-                let other_node_y: i32 = (other_node_index / nodes_width) % nodes_height;
-                let other_node_z: i32 = (other_node_index / (nodes_width * nodes_height)) % nodes_depth;
-                if node_index != other_node_index && (node_x - other_node_x).abs() <= 1 && (node_y - other_node_y).abs() <= 1 && (node_z - other_node_z).abs() <= 1 {
-                    //debug!("found neighbor at {other_node_x}, {other_node_y}, {other_node_z}.");
-                    node.node_state_collection_ids_per_neighbor_node_id.insert(other_node_id.clone(), node_state_collection_ids.clone());
-                }
-            }
-        }
+        let grid_topology = get_3D_grid_topology(nodes_width, nodes_height, nodes_depth);
+        tie_nodes_to_grid_neighbors(&mut nodes, &node_ids, &grid_topology, &node_state_collection_ids);
 
         let wave_function: WaveFunction<String>;
 
@@ -2775,17 +3257,9 @@ mod wave_function_unit_tests {
         let collapsed_wave_function = collapsed_wave_function_result.ok().unwrap();
 
         // check that none of the neighbors match the same state
-        for (node_index, node_id) in std::iter::zip(0..nodes_total, node_ids.iter()) {
-            let node_x: i32 = node_index % nodes_width;
-            let node_y: i32 = (node_index / nodes_width) % nodes_height;
-            let node_z: i32 = (node_index / (nodes_width * nodes_height)) % nodes_depth;
-            for (other_node_index, other_node_id) in std::iter::zip(0..nodes_total, node_ids.iter()) {
-                let other_node_x: i32 = other_node_index % nodes_width;
-                let other_node_y: i32 = (other_node_index / nodes_width) % nodes_height;
-                let other_node_z: i32 = (other_node_index / (nodes_width * nodes_height)) % nodes_depth;
-                if node_index != other_node_index && (node_x - other_node_x).abs() <= 1 && (node_y - other_node_y).abs() <= 1 && (node_z - other_node_z).abs() <= 1 {
-                    assert_ne!(collapsed_wave_function.node_state_per_node_id.get(node_id), collapsed_wave_function.node_state_per_node_id.get(other_node_id));
-                }
+        for (node_index, node_id) in node_ids.iter().enumerate() {
+            for neighbor_node_index in grid_topology.get_neighbor_indexes(node_index) {
+                assert_ne!(collapsed_wave_function.node_state_per_node_id.get(node_id), collapsed_wave_function.node_state_per_node_id.get(&node_ids[neighbor_node_index]));
             }
         }
     }
@@ -2849,21 +3323,8 @@ mod wave_function_unit_tests {
             }
 
             // tie nodes to their neighbors
-            for (node_index, node) in std::iter::zip(0..nodes_total, nodes.iter_mut()) {
-                let node_x: i32 = node_index % nodes_width;
-                let node_y: i32 = (node_index / nodes_width) % nodes_height;
-                let node_z: i32 = (node_index / (nodes_width * nodes_height)) % nodes_depth;
-                //debug!("processing node {node_x}, {node_y}, {node_z}.");
-                for (other_node_index, other_node_id) in std::iter::zip(0..nodes_total, node_ids.iter()) {
-                    let other_node_x: i32 = other_node_index % nodes_width;
-                    let other_node_y: i32 = (other_node_index / nodes_width) % nodes_height;
-                    let other_node_z: i32 = (other_node_index / (nodes_width * nodes_height)) % nodes_depth;
-                    if node_index != other_node_index && (node_x - other_node_x).abs() <= 1 && (node_y - other_node_y).abs() <= 1 && (node_z - other_node_z).abs() <= 1 {
-                        //debug!("found neighbor at {other_node_x}, {other_node_y}, {other_node_z}.");
-                        node.node_state_collection_ids_per_neighbor_node_id.insert(other_node_id.clone(), node_state_collection_ids.clone());
-                    }
-                }
-            }
+            let grid_topology = get_3D_grid_topology(nodes_width, nodes_height, nodes_depth);
+            tie_nodes_to_grid_neighbors(&mut nodes, &node_ids, &grid_topology, &node_state_collection_ids);
 
             let wave_function: WaveFunction<String>;
 
@@ -2883,17 +3344,9 @@ mod wave_function_unit_tests {
 
             let collapsed_wave_function = collapsed_wave_function_result.ok().unwrap();
 
-            for (node_index, node_id) in std::iter::zip(0..nodes_total, node_ids.iter()) {
-                let node_x: i32 = node_index % nodes_width;
-                let node_y: i32 = (node_index / nodes_width) % nodes_height;
-                let node_z: i32 = (node_index / (nodes_width * nodes_height)) % nodes_depth;
-                for (other_node_index, other_node_id) in std::iter::zip(0..nodes_total, node_ids.iter()) {
-                    let other_node_x: i32 = other_node_index % nodes_width;
-                    let other_node_y: i32 = (other_node_index / nodes_width) % nodes_height;
-                    let other_node_z: i32 = (other_node_index / (nodes_width * nodes_height)) % nodes_depth;
-                    if node_index != other_node_index && (node_x - other_node_x).abs() <= 1 && (node_y - other_node_y).abs() <= 1 && (node_z - other_node_z).abs() <= 1 {
-                        assert_ne!(collapsed_wave_function.node_state_per_node_id.get(node_id), collapsed_wave_function.node_state_per_node_id.get(other_node_id));
-                    }
+            for (node_index, node_id) in node_ids.iter().enumerate() {
+                for neighbor_node_index in grid_topology.get_neighbor_indexes(node_index) {
+                    assert_ne!(collapsed_wave_function.node_state_per_node_id.get(node_id), collapsed_wave_function.node_state_per_node_id.get(&node_ids[neighbor_node_index]));
                 }
             }
         }
@@ -2959,21 +3412,8 @@ mod wave_function_unit_tests {
             }
 
             // tie nodes to their neighbors
-            for (node_index, node) in std::iter::zip(0..nodes_total, nodes.iter_mut()) {
-                let node_x: i32 = node_index % nodes_width;
-                let node_y: i32 = (node_index / nodes_width) % nodes_height;
-                let node_z: i32 = (node_index / (nodes_width * nodes_height)) % nodes_depth;
-                //debug!("processing node {node_x}, {node_y}, {node_z}.");
-                for (other_node_index, other_node_id) in std::iter::zip(0..nodes_total, node_ids.iter()) {
-                    let other_node_x: i32 = other_node_index % nodes_width;
-                    let other_node_y: i32 = (other_node_index / nodes_width) % nodes_height;
-                    let other_node_z: i32 = (other_node_index / (nodes_width * nodes_height)) % nodes_depth;
-                    if node_index != other_node_index && (node_x - other_node_x).abs() <= 1 && (node_y - other_node_y).abs() <= 1 && (node_z - other_node_z).abs() <= 1 {
-                        //debug!("found neighbor at {other_node_x}, {other_node_y}, {other_node_z}.");
-                        node.node_state_collection_ids_per_neighbor_node_id.insert(other_node_id.clone(), node_state_collection_ids.clone());
-                    }
-                }
-            }
+            let grid_topology = get_3D_grid_topology(nodes_width, nodes_height, nodes_depth);
+            tie_nodes_to_grid_neighbors(&mut nodes, &node_ids, &grid_topology, &node_state_collection_ids);
 
             let wave_function: WaveFunction<String>;
 
@@ -3001,6 +3441,7 @@ mod wave_function_unit_tests {
     }
 
     #[test]
+    #[cfg(feature = "file-io")]
     fn write_and_read_wave_function_from_tempfile() {
         init();
 
@@ -3043,9 +3484,9 @@ mod wave_function_unit_tests {
         let file = tempfile::NamedTempFile::new().unwrap();
         let file_path: &str = file.path().to_str().unwrap();
         debug!("Saving wave function to {:?}", file_path);
-        wave_function.save_to_file(file_path);
+        wave_function.save_to_file(file_path).unwrap();
 
-        let loaded_wave_function: WaveFunction<String> = WaveFunction::load_from_file(file_path);
+        let loaded_wave_function: WaveFunction<String> = WaveFunction::load_from_file(file_path).unwrap();
         loaded_wave_function.validate().unwrap();
 
         file.close().unwrap();
@@ -3056,6 +3497,145 @@ mod wave_function_unit_tests {
         assert_eq!(collapsed_wave_function.node_state_per_node_id, loaded_collapsed_wave_function.node_state_per_node_id);
     }
 
+    #[test]
+    fn save_and_load_versioned_and_legacy_wave_function() {
+        init();
+
+        let wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        let expected_collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse().unwrap();
+
+        let mut bytes: Vec<u8> = Vec::new();
+        wave_function.save_to_writer(&mut bytes).unwrap();
+        let serialized_wave_function_envelope: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(Some(1), serialized_wave_function_envelope["format_version"].as_u64());
+        assert!(serialized_wave_function_envelope["wave_function"]["nodes"].is_array());
+
+        // wave functions saved as bare json before the format was versioned are still loaded
+        let legacy_bytes: Vec<u8> = serde_json::to_vec(&wave_function).unwrap();
+        for bytes in [bytes, legacy_bytes] {
+            let loaded_wave_function: WaveFunction<String> = WaveFunction::load_from_reader(bytes.as_slice()).unwrap();
+            loaded_wave_function.validate().unwrap();
+            let collapsed_wave_function = loaded_wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse().unwrap();
+            assert_eq!(expected_collapsed_wave_function.node_state_per_node_id, collapsed_wave_function.node_state_per_node_id);
+        }
+    }
+
+    #[test]
+    fn load_unknown_version_or_corrupted_wave_function() {
+        init();
+
+        let mut bytes: Vec<u8> = Vec::new();
+        get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node").save_to_writer(&mut bytes).unwrap();
+        let mut serialized_wave_function_envelope: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+
+        serialized_wave_function_envelope["format_version"] = serde_json::json!(2);
+        let error_message = WaveFunction::<String>::load_from_reader(serialized_wave_function_envelope.to_string().as_bytes()).err().unwrap();
+        assert_eq!("Cannot load wave function of format version 2 when expecting format version 1.", error_message);
+
+        serialized_wave_function_envelope["format_version"] = serde_json::json!("1");
+        let error_message = WaveFunction::<String>::load_from_reader(serialized_wave_function_envelope.to_string().as_bytes()).err().unwrap();
+        assert_eq!("Cannot load wave function of format version \"1\" when expecting format version 1.", error_message);
+
+        serialized_wave_function_envelope["format_version"] = serde_json::json!(1);
+        serialized_wave_function_envelope.as_object_mut().unwrap().remove("wave_function");
+        let error_message = WaveFunction::<String>::load_from_reader(serialized_wave_function_envelope.to_string().as_bytes()).err().unwrap();
+        assert_eq!("Cannot load wave function of format version 1 without a wave_function.", error_message);
+
+        // a wave function truncated partway through is malformed json
+        let error_message = WaveFunction::<String>::load_from_reader(&bytes[..bytes.len() / 2]).err().unwrap();
+        assert!(error_message.starts_with("Cannot parse wave function: EOF while parsing"), "Unexpected error: {error_message}");
+
+        #[cfg(feature = "file-io")]
+        {
+            // the file path outlives the file so that loading it can be attempted once it is deleted
+            let file = tempfile::NamedTempFile::new().unwrap();
+            let file_path: String = file.path().to_str().unwrap().to_string();
+            let file_path: &str = &file_path;
+            std::fs::write(file_path, &bytes[..bytes.len() / 2]).unwrap();
+            let error_message = WaveFunction::<String>::load_from_file(file_path).err().unwrap();
+            assert!(error_message.starts_with("Cannot parse wave function: EOF while parsing"), "Unexpected error: {error_message}");
+
+            // json that is not a wave function
+            std::fs::write(file_path, "{\"format_version\": 1, \"wave_function\": {\"nodes\": 3}}").unwrap();
+            let error_message = WaveFunction::<String>::load_from_file(file_path).err().unwrap();
+            assert!(error_message.starts_with("Cannot parse wave function: invalid type"), "Unexpected error: {error_message}");
+            file.close().unwrap();
+
+            let error_message = WaveFunction::<String>::load_from_file(file_path).err().unwrap();
+            assert!(error_message.starts_with(&format!("Cannot load wave function from {file_path}: ")), "Unexpected error: {error_message}");
+        }
+    }
+
+    #[test]
+    fn write_and_read_newline_delimited_wave_function_with_thousands_of_nodes() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        let node_state_collections: Vec<NodeStateCollection<String>> = vec![
+            NodeStateCollection::new(String::from("a_permits_b"), String::from("state_A"), vec![String::from("state_B")]),
+            NodeStateCollection::new(String::from("b_permits_a"), String::from("state_B"), vec![String::from("state_A")])
+        ];
+        let nodes_total: usize = 3000;
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        for index in 0..nodes_total {
+            let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+            for neighbor_index in [index.wrapping_sub(1), index + 1] {
+                if neighbor_index < nodes_total {
+                    node_state_collection_ids_per_neighbor_node_id.insert(format!("node_{neighbor_index}"), vec![String::from("a_permits_b"), String::from("b_permits_a")]);
+                }
+            }
+            nodes.push(Node::new(
+                format!("node_{index}"),
+                NodeStateProbability::get_equal_probability(&node_state_ids),
+                node_state_collection_ids_per_neighbor_node_id
+            ));
+        }
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate().unwrap();
+
+        let mut bytes: Vec<u8> = Vec::new();
+        wave_function.to_writer(&mut bytes).unwrap();
+        assert_eq!(1 + nodes_total + 2, bytes.iter().filter(|byte| **byte == b'\n').count());
+
+        let streamed_wave_function: WaveFunction<String> = WaveFunction::from_reader(bytes.as_slice()).unwrap();
+        streamed_wave_function.validate().unwrap();
+        assert_eq!(wave_function.get_statistics(), streamed_wave_function.get_statistics());
+
+        for random_seed in 0..3 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let streamed_collapsed_wave_function = streamed_wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            assert_eq!(collapsed_wave_function, streamed_collapsed_wave_function);
+        }
+    }
+
+    #[test]
+    fn read_newline_delimited_wave_function_with_invalid_lines() {
+        init();
+
+        let node = Node::new(
+            String::from("node_1"),
+            NodeStateProbability::get_equal_probability(&vec![String::from("state_A")]),
+            HashMap::new()
+        );
+        let serialized_node = serde_json::to_string(&node).unwrap();
+
+        // blank lines are skipped but still counted
+        let malformed_lines = format!("{{\"nodes_total\": 2, \"node_state_collections_total\": 0}}\n\n{serialized_node}\n{{\"id\": \n{serialized_node}\n");
+        let error_message = WaveFunction::<String>::from_reader(malformed_lines.as_bytes()).err().unwrap();
+        assert!(error_message.starts_with("Cannot parse line 4 of wave function:"), "{error_message}");
+
+        let missing_lines = format!("{{\"nodes_total\": 2, \"node_state_collections_total\": 0}}\n{serialized_node}\n");
+        let error_message = WaveFunction::<String>::from_reader(missing_lines.as_bytes()).err().unwrap();
+        assert_eq!("Cannot parse wave function with 1 nodes and 0 node state collections when the header expects 2 nodes and 0 node state collections.", error_message);
+
+        let extra_lines = format!("{{\"nodes_total\": 1, \"node_state_collections_total\": 0}}\n{serialized_node}\n{serialized_node}\n");
+        let error_message = WaveFunction::<String>::from_reader(extra_lines.as_bytes()).err().unwrap();
+        assert_eq!("Cannot parse line 3 of wave function: expected only 1 nodes and 0 node state collections.", error_message);
+
+        let error_message = WaveFunction::<String>::from_reader("\n".as_bytes()).err().unwrap();
+        assert_eq!("Cannot parse wave function without a header line.", error_message);
+    }
+
     #[test]
     fn four_nodes_as_square_neighbors_randomly() {
         init();
@@ -3232,6 +3812,110 @@ mod wave_function_unit_tests {
         }
     }
 
+    // each node forces the next node around the square into the other node state
+    fn get_four_nodes_as_square_neighbors_in_cycle_wave_function() -> WaveFunction<String> {
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_A"), vec![String::from("state_B")]);
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_B"), vec![String::from("state_A")]);
+        let mut wave_function_builder: WaveFunctionBuilder<String> = WaveFunctionBuilder::new();
+        for node_index in 1..=4 {
+            wave_function_builder.add_node(&format!("node_{node_index}"), node_state_ids.clone());
+        }
+        for node_index in 1..=4 {
+            wave_function_builder.add_constraint(&format!("node_{node_index}"), &format!("node_{}", node_index % 4 + 1), permitted_node_state_ids_per_node_state_id.clone());
+        }
+        wave_function_builder.build().unwrap()
+    }
+
+    #[test]
+    fn four_nodes_as_square_neighbors_in_cycle_alone_accommodating() {
+        init();
+
+        let wave_function = get_four_nodes_as_square_neighbors_in_cycle_wave_function();
+        let collapse_options = CollapseOptions {
+            max_iterations: Some(1000),
+            max_duration: None,
+            max_backtracks: None,
+            max_restarts: None,
+            cancel_token: None,
+            lookahead: PropagationDepth::None
+        };
+
+        // the random seed 3 used to keep moving the conflicts around the square forever
+        for random_seed in [3, 0, 1, 2, 4, 5, 6, 7, 8, 9] {
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(random_seed));
+            collapsable_wave_function.set_collapse_options(collapse_options.clone());
+            let collapsed_wave_function = collapsable_wave_function.collapse().unwrap();
+            wave_function.validate_collapsed(&collapsed_wave_function).unwrap();
+            assert_ne!(collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap(), collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap());
+            assert_eq!(collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap(), collapsed_wave_function.node_state_per_node_id.get("node_3").unwrap());
+            assert_eq!(collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap(), collapsed_wave_function.node_state_per_node_id.get("node_4").unwrap());
+
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(random_seed));
+            collapsable_wave_function.set_collapse_options(collapse_options.clone());
+            let collapsed_node_states = collapsable_wave_function.collapse_into_steps().unwrap();
+            assert_eq!(collapsed_wave_function, CollapsedWaveFunction::from_steps(&collapsed_node_states, random_seed).unwrap());
+        }
+    }
+
+    #[test]
+    fn four_nodes_as_square_neighbors_in_cycle_alone_accommodating_falls_back_within_collapse_options() {
+        init();
+
+        let wave_function = get_four_nodes_as_square_neighbors_in_cycle_wave_function();
+
+        // the random seed 3 falls back to the sequential search, whose work is reported along with the accommodating that came before it
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(3));
+        let (collapsed_wave_function_result, collapse_report) = collapsable_wave_function.collapse_with_report();
+        wave_function.validate_collapsed(&collapsed_wave_function_result.unwrap()).unwrap();
+        assert!(collapse_report.restarts_total > 0, "Unexpected report: {collapse_report:?}");
+        assert!(collapse_report.node_assignments_total > 4 + collapse_report.recollapses_total, "Unexpected report: {collapse_report:?}");
+
+        // the sequential search continues counting against the same maximum iterations instead of starting over
+        let max_iterations: u64 = collapse_report.iterations_total - 1;
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(3));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: Some(max_iterations),
+            ..CollapseOptions::default()
+        });
+        let (collapsed_wave_function_result, collapse_report) = collapsable_wave_function.collapse_with_report();
+        let error_message = collapsed_wave_function_result.err().unwrap();
+        assert!(error_message.starts_with(&format!("Collapse exceeded the maximum of {max_iterations} iterations with ")), "Unexpected error: {error_message}");
+        assert_eq!(max_iterations + 1, collapse_report.iterations_total);
+        assert!(collapsable_wave_function.is_aborted());
+
+        // collapsing again continues the sequential search, still reporting progress and staying within the collapse options
+        let cancel_token = Arc::new(AtomicBool::new(false));
+        let progress_reports_total: Rc<RefCell<u64>> = Rc::new(RefCell::new(0));
+        let callback_progress_reports_total = progress_reports_total.clone();
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(3));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: Some(max_iterations),
+            cancel_token: Some(cancel_token.clone()),
+            ..CollapseOptions::default()
+        });
+        collapsable_wave_function.set_progress_callback(1, Box::new(move |_| {
+            *callback_progress_reports_total.borrow_mut() += 1;
+            ControlFlow::Continue(())
+        }));
+        let (collapsed_wave_function_result, collapse_report) = collapsable_wave_function.collapse_with_report();
+        assert!(collapsed_wave_function_result.is_err());
+        assert_eq!(max_iterations + 1, collapse_report.iterations_total);
+        assert_eq!(collapse_report.node_assignments_total, *progress_reports_total.borrow());
+
+        cancel_token.store(true, Ordering::Relaxed);
+        let error_message = collapsable_wave_function.collapse().err().unwrap();
+        assert!(error_message.starts_with("Collapse cancelled after 1 iterations with "), "Unexpected error: {error_message}");
+
+        cancel_token.store(false, Ordering::Relaxed);
+        *progress_reports_total.borrow_mut() = 0;
+        let (collapsed_wave_function_result, collapse_report) = collapsable_wave_function.collapse_with_report();
+        wave_function.validate_collapsed(&collapsed_wave_function_result.unwrap()).unwrap();
+        assert!(collapse_report.node_assignments_total > 0, "Unexpected report: {collapse_report:?}");
+        assert_eq!(collapse_report.node_assignments_total, *progress_reports_total.borrow());
+    }
+
     #[test]
     fn four_nodes_as_square_neighbors_in_cycle_affects_another_square_sequential() {
         init();
@@ -3608,12 +4292,4128 @@ mod wave_function_unit_tests {
         assert_eq!(&two_node_state_id, collapsed_wave_function.node_state_per_node_id.get(&three_node_id).unwrap());
         assert_eq!(&one_node_state_id, collapsed_wave_function.node_state_per_node_id.get(&four_node_id).unwrap());
     }
-}
 
-#[cfg(test)]
-mod indexed_view_unit_tests {
+    #[test]
+    fn search_seed_always_true_returns_start_seed() {
+        init();
 
-    use uuid::Uuid;
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        let node_state_collections: Vec<NodeStateCollection<String>> = Vec::new();
+
+        let one_node_state_id: String = Uuid::new_v4().to_string();
+        let two_node_state_id: String = Uuid::new_v4().to_string();
+        let node_id: String = Uuid::new_v4().to_string();
+
+        nodes.push(Node::new(
+            node_id.clone(),
+            NodeStateProbability::get_equal_probability(&vec![one_node_state_id.clone(), two_node_state_id.clone()]),
+            HashMap::new()
+        ));
+
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate().unwrap();
+
+        let (random_seed, collapsed_wave_function) = wave_function.search_seed::<SequentialCollapsableWaveFunction<String>, _>(1234, 10, |_| true).unwrap();
+
+        assert_eq!(1234, random_seed);
+        assert!(collapsed_wave_function.node_state_per_node_id.contains_key(&node_id));
+    }
+
+    #[test]
+    fn search_seed_finds_rare_node_state() {
+        init();
+
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        let node_state_collections: Vec<NodeStateCollection<String>> = Vec::new();
+
+        let common_node_state_id: String = Uuid::new_v4().to_string();
+        let rare_node_state_id: String = Uuid::new_v4().to_string();
+        let node_id: String = Uuid::new_v4().to_string();
+
+        let mut node_state_ratio_per_node_state_id: HashMap<String, f64> = HashMap::new();
+        node_state_ratio_per_node_state_id.insert(common_node_state_id.clone(), 1.0);
+        node_state_ratio_per_node_state_id.insert(rare_node_state_id.clone(), 0.01);
+
+        nodes.push(Node::new(
+            node_id.clone(),
+            node_state_ratio_per_node_state_id,
+            HashMap::new()
+        ));
+
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate().unwrap();
+
+        let (random_seed, collapsed_wave_function) = wave_function.search_seed::<SequentialCollapsableWaveFunction<String>, _>(0, 10000, |collapsed_wave_function| {
+            collapsed_wave_function.node_state_per_node_id.get(&node_id).unwrap() == &rare_node_state_id
+        }).expect("The rare node state should be found within the attempts.");
+
+        assert_eq!(&rare_node_state_id, collapsed_wave_function.node_state_per_node_id.get(&node_id).unwrap());
+
+        // the found seed must reproduce the same collapsed wave function
+        let reproduced_collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+        assert_eq!(&rare_node_state_id, reproduced_collapsed_wave_function.node_state_per_node_id.get(&node_id).unwrap());
+    }
+
+    #[test]
+    fn search_seed_impossible_predicate_returns_none() {
+        init();
+
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        let node_state_collections: Vec<NodeStateCollection<String>> = Vec::new();
+
+        let one_node_state_id: String = Uuid::new_v4().to_string();
+        let two_node_state_id: String = Uuid::new_v4().to_string();
+        let node_id: String = Uuid::new_v4().to_string();
+
+        nodes.push(Node::new(
+            node_id,
+            NodeStateProbability::get_equal_probability(&vec![one_node_state_id, two_node_state_id]),
+            HashMap::new()
+        ));
+
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate().unwrap();
+
+        let predicate_calls_total = std::cell::Cell::new(0);
+        let search_result = wave_function.search_seed::<SequentialCollapsableWaveFunction<String>, _>(0, 25, |_| {
+            predicate_calls_total.set(predicate_calls_total.get() + 1);
+            false
+        });
+
+        assert!(search_result.is_none());
+        assert_eq!(25, predicate_calls_total.get());
+    }
+
+    #[test]
+    fn validate_satisfiability_two_nodes_parent_forbids_only_child_state() {
+        init();
+
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        let mut node_state_collections: Vec<NodeStateCollection<String>> = Vec::new();
+
+        let first_restricting_node_state_id: String = String::from("first_restricting");
+        let second_restricting_node_state_id: String = String::from("second_restricting");
+        let restricted_node_state_id: String = String::from("restricted");
+
+        nodes.push(Node::new(
+            String::from("node_1"),
+            NodeStateProbability::get_equal_probability(&vec![first_restricting_node_state_id.clone(), second_restricting_node_state_id.clone()]),
+            HashMap::new()
+        ));
+
+        nodes.push(Node::new(
+            String::from("node_2"),
+            NodeStateProbability::get_equal_probability(&vec![restricted_node_state_id.clone()]),
+            HashMap::new()
+        ));
+
+        let first_node_id: String = nodes[0].id.clone();
+        let second_node_id: String = nodes[1].id.clone();
+
+        let first_restrictive_node_state_collection_id: String = Uuid::new_v4().to_string();
+        node_state_collections.push(NodeStateCollection::new(
+            first_restrictive_node_state_collection_id.clone(),
+            first_restricting_node_state_id.clone(),
+            vec![]
+        ));
+
+        let second_restrictive_node_state_collection_id: String = Uuid::new_v4().to_string();
+        node_state_collections.push(NodeStateCollection::new(
+            second_restrictive_node_state_collection_id.clone(),
+            second_restricting_node_state_id.clone(),
+            vec![]
+        ));
+
+        nodes[0].node_state_collection_ids_per_neighbor_node_id.insert(second_node_id.clone(), vec![first_restrictive_node_state_collection_id, second_restrictive_node_state_collection_id]);
+
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate().unwrap();
+
+        let validation_result = wave_function.validate_satisfiability();
+
+        assert_eq!(format!("Node {second_node_id} has no possible node states after applying the constraints along {first_node_id} -> {second_node_id}."), validation_result.err().unwrap());
+    }
+
+    #[test]
+    fn validate_satisfiability_two_nodes_parent_permits_only_child_state_once() {
+        init();
+
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        let mut node_state_collections: Vec<NodeStateCollection<String>> = Vec::new();
+
+        let restricting_node_state_id: String = String::from("restricting");
+        let restricted_node_state_id: String = String::from("restricted");
+        let permitting_node_state_id: String = String::from("z_permitting");
+
+        nodes.push(Node::new(
+            String::from("node_1"),
+            NodeStateProbability::get_equal_probability(&vec![restricting_node_state_id.clone(), permitting_node_state_id.clone()]),
+            HashMap::new()
+        ));
+
+        nodes.push(Node::new(
+            String::from("node_2"),
+            NodeStateProbability::get_equal_probability(&vec![restricted_node_state_id.clone()]),
+            HashMap::new()
+        ));
+
+        let second_node_id: String = nodes[1].id.clone();
+
+        let restrictive_node_state_collection_id: String = Uuid::new_v4().to_string();
+        node_state_collections.push(NodeStateCollection::new(
+            restrictive_node_state_collection_id.clone(),
+            restricting_node_state_id.clone(),
+            vec![]
+        ));
+
+        let permitted_node_state_collection_id: String = Uuid::new_v4().to_string();
+        node_state_collections.push(NodeStateCollection::new(
+            permitted_node_state_collection_id.clone(),
+            permitting_node_state_id.clone(),
+            vec![restricted_node_state_id.clone()]
+        ));
+
+        nodes[0].node_state_collection_ids_per_neighbor_node_id.insert(second_node_id.clone(), vec![restrictive_node_state_collection_id, permitted_node_state_collection_id]);
+
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate().unwrap();
+        wave_function.validate_satisfiability().unwrap();
+    }
+
+    fn get_alternating_chain_wave_function(last_node_state_id: &str) -> WaveFunction<String> {
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        let mut node_state_collections: Vec<NodeStateCollection<String>> = Vec::new();
+
+        let one_node_state_id: String = String::from("one");
+        let two_node_state_id: String = String::from("two");
+
+        // the first node must be "one" and each neighbor must alternate, so the last node of the chain must be "two"
+        for index in 0..4 {
+            let node_state_ids: Vec<String> = if index == 0 {
+                vec![one_node_state_id.clone()]
+            }
+            else if index == 3 {
+                vec![String::from(last_node_state_id)]
+            }
+            else {
+                vec![one_node_state_id.clone(), two_node_state_id.clone()]
+            };
+            nodes.push(Node::new(
+                format!("node_{index}"),
+                NodeStateProbability::get_equal_probability(&node_state_ids),
+                HashMap::new()
+            ));
+        }
+
+        let one_permits_two_node_state_collection_id: String = Uuid::new_v4().to_string();
+        node_state_collections.push(NodeStateCollection::new(
+            one_permits_two_node_state_collection_id.clone(),
+            one_node_state_id.clone(),
+            vec![two_node_state_id.clone()]
+        ));
+
+        let two_permits_one_node_state_collection_id: String = Uuid::new_v4().to_string();
+        node_state_collections.push(NodeStateCollection::new(
+            two_permits_one_node_state_collection_id.clone(),
+            two_node_state_id.clone(),
+            vec![one_node_state_id.clone()]
+        ));
+
+        for index in 0..3 {
+            let neighbor_node_id: String = nodes[index + 1].id.clone();
+            nodes[index].node_state_collection_ids_per_neighbor_node_id.insert(neighbor_node_id, vec![one_permits_two_node_state_collection_id.clone(), two_permits_one_node_state_collection_id.clone()]);
+        }
+
+        WaveFunction::new(nodes, node_state_collections)
+    }
+
+    #[test]
+    fn validate_satisfiability_chain_satisfiable() {
+        init();
+
+        let wave_function = get_alternating_chain_wave_function("two");
+        wave_function.validate().unwrap();
+        wave_function.validate_satisfiability().unwrap();
+
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse().unwrap();
+        assert_eq!("two", collapsed_wave_function.node_state_per_node_id.get("node_3").unwrap());
+    }
+
+    #[test]
+    fn validate_satisfiability_chain_unsatisfiable_transitively() {
+        init();
+
+        let wave_function = get_alternating_chain_wave_function("one");
+        wave_function.validate().unwrap();
+
+        let error_message = wave_function.validate_satisfiability().err().unwrap();
+        assert!(error_message.contains(" -> node_1 -> ") || error_message.contains(" -> node_2 -> "), "The contradiction should be reported through the middle of the chain: {error_message}");
+
+        let collapsed_wave_function_result = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse();
+        assert_eq!("Cannot collapse wave function.", collapsed_wave_function_result.err().unwrap());
+    }
+
+    #[test]
+    fn get_initial_domains_propagates_only_forced_node_states() {
+        init();
+
+        // node_0 can only be 1, which forces node_1 to be 2, which forces node_2 to be 3, which does not restrict node_3
+        let node_state_collections: Vec<NodeStateCollection<u32>> = vec![
+            NodeStateCollection::new(String::from("one_forces_two"), 1, vec![2]),
+            NodeStateCollection::new(String::from("two_forces_three"), 2, vec![3]),
+            NodeStateCollection::new(String::from("one_forbids_one"), 1, vec![2, 3])
+        ];
+        let node_state_collection_ids_per_node_index: Vec<Vec<String>> = vec![
+            vec![String::from("one_forces_two")],
+            vec![String::from("two_forces_three")],
+            vec![String::from("one_forbids_one")]
+        ];
+
+        let mut nodes: Vec<Node<u32>> = Vec::new();
+        for index in 0..4 {
+            let node_state_ids: Vec<u32> = if index == 0 {
+                vec![1]
+            }
+            else {
+                vec![1, 2, 3]
+            };
+            let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+            if let Some(node_state_collection_ids) = node_state_collection_ids_per_node_index.get(index) {
+                node_state_collection_ids_per_neighbor_node_id.insert(format!("node_{}", index + 1), node_state_collection_ids.clone());
+            }
+            nodes.push(Node::new(
+                format!("node_{index}"),
+                NodeStateProbability::get_equal_probability(&node_state_ids),
+                node_state_collection_ids_per_neighbor_node_id
+            ));
+        }
+
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate().unwrap();
+
+        let mut initial_domains = wave_function.get_initial_domains();
+        for domain in initial_domains.values_mut() {
+            domain.sort();
+        }
+
+        assert_eq!(4, initial_domains.len());
+        assert_eq!(&vec![1], initial_domains.get("node_0").unwrap());
+        assert_eq!(&vec![2], initial_domains.get("node_1").unwrap());
+        assert_eq!(&vec![3], initial_domains.get("node_2").unwrap());
+        assert_eq!(&vec![1, 2, 3], initial_domains.get("node_3").unwrap());
+
+        // the wave function is unaltered and still collapses into the forced node states
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<u32>>(Some(0)).collapse().unwrap();
+        assert_eq!(&3, collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap());
+    }
+
+    fn get_all_different_node_state_ids_per_node_state_id() -> HashMap<String, Vec<String>> {
+        let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_A"), vec![String::from("state_B"), String::from("state_C")]);
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_B"), vec![String::from("state_A"), String::from("state_C")]);
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_C"), vec![String::from("state_A"), String::from("state_B")]);
+        permitted_node_state_ids_per_node_state_id
+    }
+
+    #[test]
+    fn three_nodes_as_dense_neighbors_all_different_states_builder() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B"), String::from("state_C")];
+
+        let wave_function = WaveFunctionBuilder::new()
+            .add_node("node_1", node_state_ids.clone())
+            .add_node("node_2", node_state_ids.clone())
+            .add_node("node_3", node_state_ids.clone())
+            .add_bidirectional_constraint("node_1", "node_2", get_all_different_node_state_ids_per_node_state_id())
+            .add_bidirectional_constraint("node_1", "node_3", get_all_different_node_state_ids_per_node_state_id())
+            .add_bidirectional_constraint("node_2", "node_3", get_all_different_node_state_ids_per_node_state_id())
+            .build()
+            .unwrap();
+        wave_function.validate().unwrap();
+
+        // the builder produces the same wave function as constructing the nodes and node state collections directly
+        let manual_wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        for random_seed in 0..20 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let manual_collapsed_wave_function = manual_wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            assert_eq!(manual_collapsed_wave_function, collapsed_wave_function);
+            assert_ne!(collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap(), collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap());
+            assert_ne!(collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap(), collapsed_wave_function.node_state_per_node_id.get("node_3").unwrap());
+            assert_ne!(collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap(), collapsed_wave_function.node_state_per_node_id.get("node_3").unwrap());
+        }
+    }
+
+    #[test]
+    fn three_nodes_as_dense_neighbors_all_different_states_builder_permit_only() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B"), String::from("state_C")];
+        let mut wave_function_builder: WaveFunctionBuilder<String> = WaveFunctionBuilder::new();
+        for node_state_id in node_state_ids.iter() {
+            wave_function_builder.permit_only(node_state_id.clone(), node_state_ids.iter().filter(|other_node_state_id| *other_node_state_id != node_state_id).cloned().collect());
+        }
+        for node_id in ["node_1", "node_2", "node_3"] {
+            wave_function_builder.add_node(node_id, node_state_ids.clone());
+        }
+        let wave_function = wave_function_builder.add_neighbors("node_1", "node_2").add_neighbors("node_1", "node_3").add_neighbors("node_2", "node_3").build().unwrap();
+
+        // the identical node state collections of every pair of neighbors are shared
+        assert_eq!(3, wave_function.get_node_state_collections().len());
+
+        let manual_wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        for random_seed in 0..20 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let manual_collapsed_wave_function = manual_wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            assert_eq!(manual_collapsed_wave_function, collapsed_wave_function);
+        }
+    }
+
+    #[test]
+    fn builder_neighbors_ignore_node_states_that_node_cannot_be_in() {
+        init();
+
+        let wave_function = WaveFunctionBuilder::new()
+            .permit_only(String::from("state_A"), vec![String::from("state_B")])
+            .permit_only(String::from("state_B"), vec![String::from("state_A"), String::from("state_C")])
+            .permit_only(String::from("state_C"), vec![String::from("state_B")])
+            .add_node("node_1", vec![String::from("state_A"), String::from("state_B")])
+            .add_node("node_2", vec![String::from("state_B"), String::from("state_C")])
+            .add_neighbors("node_1", "node_2")
+            .build()
+            .unwrap();
+
+        let mut node_state_collections: Vec<(String, Vec<String>)> = wave_function.get_node_state_collections()
+            .into_iter()
+            .map(|node_state_collection| (node_state_collection.node_state_id, node_state_collection.node_state_ids))
+            .collect();
+        node_state_collections.sort();
+        assert_eq!(vec![
+            (String::from("state_A"), vec![String::from("state_B")]),
+            (String::from("state_B"), vec![String::from("state_A")]),
+            (String::from("state_B"), vec![String::from("state_C")]),
+            (String::from("state_C"), vec![String::from("state_B")])
+        ], node_state_collections);
+
+        for random_seed in 0..10 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            assert_ne!(collapsed_wave_function.node_state_per_node_id.get("node_1"), collapsed_wave_function.node_state_per_node_id.get("node_2"));
+        }
+    }
+
+    #[test]
+    fn builder_neighbors_to_unknown_node() {
+        init();
+
+        let build_result = WaveFunctionBuilder::new()
+            .add_node("node_1", vec![String::from("state_A")])
+            .add_neighbors("node_1", "node_2")
+            .build();
+
+        assert_eq!("Neighbors reference node node_2 that does not exist.", build_result.err().unwrap());
+    }
+
+    #[test]
+    fn builder_duplicate_node_id() {
+        init();
+
+        let build_result = WaveFunctionBuilder::new()
+            .add_node("node_1", vec![String::from("state_A")])
+            .add_node("node_2", vec![String::from("state_A")])
+            .add_node("node_1", vec![String::from("state_B")])
+            .build();
+
+        assert_eq!("Node node_1 is added more than once.", build_result.err().unwrap());
+    }
+
+    #[test]
+    fn builder_constraint_to_unknown_node() {
+        init();
+
+        let build_result = WaveFunctionBuilder::new()
+            .add_node("node_1", vec![String::from("state_A"), String::from("state_B"), String::from("state_C")])
+            .add_bidirectional_constraint("node_1", "node_2", get_all_different_node_state_ids_per_node_state_id())
+            .build();
+
+        assert_eq!("Constraint references node node_2 that does not exist.", build_result.err().unwrap());
+    }
+
+    #[test]
+    fn builder_constraint_to_unknown_node_state() {
+        init();
+
+        let build_result = WaveFunctionBuilder::new()
+            .add_node("node_1", vec![String::from("state_A"), String::from("state_B"), String::from("state_C")])
+            .add_node("node_2", vec![String::from("state_A"), String::from("state_B")])
+            .add_constraint("node_1", "node_2", get_all_different_node_state_ids_per_node_state_id())
+            .build();
+
+        assert_eq!("Constraint references node state \"state_C\" that does not exist for node node_2.", build_result.err().unwrap());
+    }
+
+    #[test]
+    fn collapse_with_parallel_seeds_returns_reproducible_seed() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B"), String::from("state_C")];
+
+        let wave_function = WaveFunctionBuilder::new()
+            .add_node("node_1", node_state_ids.clone())
+            .add_node("node_2", node_state_ids.clone())
+            .add_node("node_3", node_state_ids.clone())
+            .add_bidirectional_constraint("node_1", "node_2", get_all_different_node_state_ids_per_node_state_id())
+            .add_bidirectional_constraint("node_1", "node_3", get_all_different_node_state_ids_per_node_state_id())
+            .add_bidirectional_constraint("node_2", "node_3", get_all_different_node_state_ids_per_node_state_id())
+            .build()
+            .unwrap();
+        wave_function.validate().unwrap();
+
+        let random_seeds: Vec<u64> = (100..108).collect();
+        let (random_seed, collapsed_wave_function) = wave_function.collapse_with_parallel_seeds::<SequentialCollapsableWaveFunction<String>>(random_seeds.clone(), 4).unwrap();
+
+        assert!(random_seeds.contains(&random_seed));
+
+        let reproduced_collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+        assert_eq!(collapsed_wave_function.node_state_per_node_id, reproduced_collapsed_wave_function.node_state_per_node_id);
+    }
+
+    #[test]
+    fn collapse_with_parallel_seeds_all_seeds_fail() {
+        init();
+
+        let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_A"), Vec::new());
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_B"), Vec::new());
+
+        let wave_function = WaveFunctionBuilder::new()
+            .add_node("node_1", vec![String::from("state_A"), String::from("state_B")])
+            .add_node("node_2", vec![String::from("state_C")])
+            .add_constraint("node_1", "node_2", permitted_node_state_ids_per_node_state_id)
+            .build()
+            .unwrap();
+        wave_function.validate().unwrap();
+
+        let collapsed_result = wave_function.collapse_with_parallel_seeds::<SequentialCollapsableWaveFunction<String>>(vec![1, 2, 3, 4, 5], 2);
+
+        assert_eq!("Cannot collapse wave function with any of the 5 attempted random seeds.", collapsed_result.err().unwrap());
+    }
+
+    #[test]
+    fn fixed_states_three_nodes_as_dense_neighbors() {
+        init();
+
+        let wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        wave_function.validate().unwrap();
+
+        let mut fixed_node_state_per_node_id: HashMap<String, String> = HashMap::new();
+        fixed_node_state_per_node_id.insert(String::from("node_1"), String::from("state_B"));
+        fixed_node_state_per_node_id.insert(String::from("node_2"), String::from("state_C"));
+
+        for random_seed in 0..20 {
+            let collapsed_wave_functions: Vec<CollapsedWaveFunction<String>> = vec![
+                wave_function.get_collapsable_wave_function_with_fixed_states::<SequentialCollapsableWaveFunction<String>>(Some(random_seed), &fixed_node_state_per_node_id).unwrap().collapse().unwrap(),
+                wave_function.get_collapsable_wave_function_with_fixed_states::<AccommodatingCollapsableWaveFunction<String>>(Some(random_seed), &fixed_node_state_per_node_id).unwrap().collapse().unwrap(),
+                wave_function.get_collapsable_wave_function_with_fixed_states::<AccommodatingSequentialCollapsableWaveFunction<String>>(Some(random_seed), &fixed_node_state_per_node_id).unwrap().collapse().unwrap(),
+                wave_function.get_collapsable_wave_function_with_fixed_states::<EntropicCollapsableWaveFunction<String>>(Some(random_seed), &fixed_node_state_per_node_id).unwrap().collapse().unwrap()
+            ];
+            for collapsed_wave_function in collapsed_wave_functions.iter() {
+                assert_eq!("state_B", collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap());
+                assert_eq!("state_C", collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap());
+                assert_eq!("state_A", collapsed_wave_function.node_state_per_node_id.get("node_3").unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_states_contradicting_neighbor() {
+        init();
+
+        let wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        wave_function.validate().unwrap();
+
+        let mut fixed_node_state_per_node_id: HashMap<String, String> = HashMap::new();
+        fixed_node_state_per_node_id.insert(String::from("node_1"), String::from("state_A"));
+        fixed_node_state_per_node_id.insert(String::from("node_2"), String::from("state_A"));
+
+        let collapsable_wave_function_result = wave_function.get_collapsable_wave_function_with_fixed_states::<SequentialCollapsableWaveFunction<String>>(None, &fixed_node_state_per_node_id);
+
+        assert_eq!("Fixed node node_1 in node state \"state_A\" does not permit any possible node state of neighbor node node_2.", collapsable_wave_function_result.err().unwrap());
+    }
+
+    #[test]
+    fn fixed_states_unknown_node_state() {
+        init();
+
+        let wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        wave_function.validate().unwrap();
+
+        let mut fixed_node_state_per_node_id: HashMap<String, String> = HashMap::new();
+        fixed_node_state_per_node_id.insert(String::from("node_3"), String::from("state_D"));
+
+        let collapsable_wave_function_result = wave_function.get_collapsable_wave_function_with_fixed_states::<SequentialCollapsableWaveFunction<String>>(None, &fixed_node_state_per_node_id);
+
+        assert_eq!("Fixed node node_3 does not have node state \"state_D\".", collapsable_wave_function_result.err().unwrap());
+    }
+
+    #[test]
+    fn fixed_states_unknown_node_state_collection() {
+        init();
+
+        let mut wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        let node = wave_function.nodes.iter_mut().find(|node| node.id == "node_1").unwrap();
+        node.node_state_collection_ids_per_neighbor_node_id.get_mut("node_2").unwrap().push(String::from("missing_node_state_collection"));
+
+        let mut fixed_node_state_per_node_id: HashMap<String, String> = HashMap::new();
+        fixed_node_state_per_node_id.insert(String::from("node_1"), String::from("state_A"));
+
+        let collapsable_wave_function_result = wave_function.get_collapsable_wave_function_with_fixed_states::<SequentialCollapsableWaveFunction<String>>(None, &fixed_node_state_per_node_id);
+
+        assert_eq!("Node state collection missing_node_state_collection does not exist in main list of node state collections.", collapsable_wave_function_result.err().unwrap());
+    }
+
+    #[test]
+    fn collapse_into_steps_replays_to_valid_collapse_accommodating() {
+        init();
+
+        let wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        wave_function.validate().unwrap();
+
+        for random_seed in 0..100 {
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(random_seed));
+            let collapsed_node_states = collapsable_wave_function.collapse_into_steps().unwrap();
+            let collapsed_wave_function = CollapsedWaveFunction::from_steps(&collapsed_node_states, collapsable_wave_function.get_random_seed()).unwrap();
+
+            assert_eq!(random_seed, collapsed_wave_function.random_seed);
+
+            assert_eq!(3, collapsed_wave_function.node_state_per_node_id.len());
+            assert_ne!(collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap(), collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap());
+            assert_ne!(collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap(), collapsed_wave_function.node_state_per_node_id.get("node_3").unwrap());
+            assert_ne!(collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap(), collapsed_wave_function.node_state_per_node_id.get("node_3").unwrap());
+        }
+    }
+
+    #[test]
+    fn collapse_into_steps_replays_to_valid_collapse_acc_seq() {
+        init();
+
+        let wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        wave_function.validate().unwrap();
+
+        for random_seed in 0..100 {
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(Some(random_seed));
+            let collapsed_node_states = collapsable_wave_function.collapse_into_steps().unwrap();
+            let collapsed_wave_function = CollapsedWaveFunction::from_steps(&collapsed_node_states, collapsable_wave_function.get_random_seed()).unwrap();
+
+            assert_eq!(random_seed, collapsed_wave_function.random_seed);
+
+            assert_eq!(3, collapsed_wave_function.node_state_per_node_id.len());
+            assert_ne!(collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap(), collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap());
+            assert_ne!(collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap(), collapsed_wave_function.node_state_per_node_id.get("node_3").unwrap());
+            assert_ne!(collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap(), collapsed_wave_function.node_state_per_node_id.get("node_3").unwrap());
+        }
+    }
+
+    #[test]
+    fn collapse_into_steps_replay_with_reset_node() {
+        init();
+
+        let collapsed_node_states: Vec<CollapsedNodeState<String>> = vec![
+            CollapsedNodeState { node_id: String::from("node_1"), node_state_id: Some(String::from("state_A")), diagnostics: None, metadata: None },
+            CollapsedNodeState { node_id: String::from("node_2"), node_state_id: Some(String::from("state_A")), diagnostics: None, metadata: None },
+            CollapsedNodeState { node_id: String::from("node_2"), node_state_id: None, diagnostics: None, metadata: None }
+        ];
+
+        assert_eq!("Node node_2 is not in a node state after replaying the steps.", CollapsedWaveFunction::from_steps(&collapsed_node_states, 0).err().unwrap());
+    }
+
+    fn get_two_nodes_with_conflicting_state_requirements_wave_function() -> WaveFunction<String> {
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B"), String::from("state_C"), String::from("state_D")];
+
+        let mut first_permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        first_permitted_node_state_ids_per_node_state_id.insert(String::from("state_A"), vec![String::from("state_C")]);
+        first_permitted_node_state_ids_per_node_state_id.insert(String::from("state_B"), vec![String::from("state_D")]);
+        first_permitted_node_state_ids_per_node_state_id.insert(String::from("state_C"), Vec::new());
+        first_permitted_node_state_ids_per_node_state_id.insert(String::from("state_D"), Vec::new());
+
+        let mut second_permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        second_permitted_node_state_ids_per_node_state_id.insert(String::from("state_A"), Vec::new());
+        second_permitted_node_state_ids_per_node_state_id.insert(String::from("state_B"), Vec::new());
+        second_permitted_node_state_ids_per_node_state_id.insert(String::from("state_C"), vec![String::from("state_B")]);
+        second_permitted_node_state_ids_per_node_state_id.insert(String::from("state_D"), vec![String::from("state_A")]);
+
+        WaveFunctionBuilder::new()
+            .add_node("node_1", node_state_ids.clone())
+            .add_node("node_2", node_state_ids)
+            .add_constraint("node_1", "node_2", first_permitted_node_state_ids_per_node_state_id)
+            .add_constraint("node_2", "node_1", second_permitted_node_state_ids_per_node_state_id)
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn collapse_with_report_of_conflicting_nodes() {
+        init();
+
+        let wave_function = get_two_nodes_with_conflicting_state_requirements_wave_function();
+        let (collapsed_wave_function_result, collapse_report) = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse_with_report();
+        assert_eq!("Cannot collapse wave function.", collapsed_wave_function_result.err().unwrap());
+        assert!(collapse_report.backtracks_total >= 1, "Unexpected report: {collapse_report:?}");
+        assert!(collapse_report.deepest_backtrack >= 1, "Unexpected report: {collapse_report:?}");
+        assert!(collapse_report.deepest_backtrack <= collapse_report.backtracks_total, "Unexpected report: {collapse_report:?}");
+        assert!(collapse_report.node_assignments_total >= 1, "Unexpected report: {collapse_report:?}");
+
+        // the report covers a collapse that gave up after exceeding its options
+        let wave_function = get_dense_neighbors_with_too_few_node_states_wave_function(12);
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(0));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: Some(100),
+            max_duration: None,
+            max_backtracks: None,
+            max_restarts: None,
+            cancel_token: None,
+            lookahead: PropagationDepth::None
+        });
+        let (collapsed_wave_function_result, collapse_report) = collapsable_wave_function.collapse_with_report();
+        assert!(collapsed_wave_function_result.is_err());
+        assert_eq!(101, collapse_report.iterations_total);
+    }
+
+    #[test]
+    fn collapse_with_report_of_trivially_solvable_nodes() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        for node_state_id in node_state_ids.iter() {
+            permitted_node_state_ids_per_node_state_id.insert(node_state_id.clone(), node_state_ids.clone());
+        }
+        let wave_function = WaveFunctionBuilder::new()
+            .add_node("node_1", node_state_ids.clone())
+            .add_node("node_2", node_state_ids)
+            .add_bidirectional_constraint("node_1", "node_2", permitted_node_state_ids_per_node_state_id)
+            .build()
+            .unwrap();
+
+        let collapse_reports = vec![
+            wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse_with_report(),
+            wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(0)).collapse_with_report(),
+            wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(Some(0)).collapse_with_report(),
+            wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(0)).collapse_with_report()
+        ];
+        for (collapsed_wave_function_result, collapse_report) in collapse_reports.into_iter() {
+            assert_eq!(2, collapsed_wave_function_result.unwrap().node_state_per_node_id.len());
+            assert_eq!(0, collapse_report.backtracks_total, "Unexpected report: {collapse_report:?}");
+            assert_eq!(0, collapse_report.deepest_backtrack, "Unexpected report: {collapse_report:?}");
+            assert_eq!(0, collapse_report.restarts_total, "Unexpected report: {collapse_report:?}");
+            assert!(collapse_report.node_assignments_total >= 2, "Unexpected report: {collapse_report:?}");
+
+            let serialized_collapse_report = serde_json::to_string(&collapse_report).unwrap();
+            assert!(serialized_collapse_report.contains("\"backtracks_total\":0"), "Unexpected serialization: {serialized_collapse_report}");
+        }
+    }
+
+    #[test]
+    fn collapse_options_max_iterations_accommodating() {
+        init();
+
+        let wave_function = get_dense_neighbors_with_too_few_node_states_wave_function(12);
+        wave_function.validate().unwrap();
+
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(0));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: Some(100),
+            max_duration: None,
+            max_backtracks: None,
+            max_restarts: None,
+            cancel_token: None,
+            lookahead: PropagationDepth::None
+        });
+        let error_message = collapsable_wave_function.collapse().err().unwrap();
+
+        assert!(error_message.starts_with("Collapse exceeded the maximum of 100 iterations with "), "Unexpected error: {error_message}");
+        assert!(error_message.ends_with(" of 12 nodes collapsed."), "Unexpected error: {error_message}");
+
+        // two nodes accommodating each other return to the same node states, so the sequential search finds them to be uncollapsable
+        let wave_function = get_two_nodes_with_conflicting_state_requirements_wave_function();
+        let collapsed_wave_function_result = wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(0)).collapse();
+        assert_eq!("Cannot collapse wave function.", collapsed_wave_function_result.err().unwrap());
+    }
+
+    #[test]
+    fn collapse_options_max_iterations_acc_seq() {
+        init();
+
+        let wave_function = get_two_nodes_with_conflicting_state_requirements_wave_function();
+        wave_function.validate().unwrap();
+
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(Some(0));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: Some(100),
+            max_duration: None,
+            max_backtracks: None,
+            max_restarts: None,
+            cancel_token: None,
+            lookahead: PropagationDepth::None
+        });
+        let error_message = collapsable_wave_function.collapse_into_steps().err().unwrap();
+
+        assert!(error_message.starts_with("Collapse exceeded the maximum of 100 iterations with "), "Unexpected error: {error_message}");
+    }
+
+    #[test]
+    fn collapse_options_max_iterations_sequential() {
+        init();
+
+        let wave_function = get_two_nodes_with_conflicting_state_requirements_wave_function();
+        wave_function.validate().unwrap();
+
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None);
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: Some(2),
+            max_duration: None,
+            max_backtracks: None,
+            max_restarts: None,
+            cancel_token: None,
+            lookahead: PropagationDepth::None
+        });
+        let error_message = collapsable_wave_function.collapse().err().unwrap();
+
+        assert!(error_message.starts_with("Collapse exceeded the maximum of 2 iterations with "), "Unexpected error: {error_message}");
+
+        // without options the same wave function is found to be uncollapsable
+        let collapsed_wave_function_result = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse();
+        assert_eq!("Cannot collapse wave function.", collapsed_wave_function_result.err().unwrap());
+    }
+
+    #[test]
+    fn collapse_options_max_iterations_entropic() {
+        init();
+
+        let wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        wave_function.validate().unwrap();
+
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(None);
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: Some(1),
+            max_duration: None,
+            max_backtracks: None,
+            max_restarts: None,
+            cancel_token: None,
+            lookahead: PropagationDepth::None
+        });
+        let error_message = collapsable_wave_function.collapse().err().unwrap();
+
+        assert_eq!("Collapse exceeded the maximum of 1 iterations with 1 of 3 nodes collapsed.", error_message);
+    }
+
+    #[test]
+    fn collapse_options_max_duration_accommodating() {
+        init();
+
+        let wave_function = get_dense_neighbors_with_too_few_node_states_wave_function(12);
+        wave_function.validate().unwrap();
+
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(0));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: None,
+            max_duration: Some(Duration::ZERO),
+            max_backtracks: None,
+            max_restarts: None,
+            cancel_token: None,
+            lookahead: PropagationDepth::None
+        });
+        let error_message = collapsable_wave_function.collapse().err().unwrap();
+
+        assert!(error_message.starts_with("Collapse exceeded the maximum duration of 0ns after 1024 iterations with "), "Unexpected error: {error_message}");
+    }
+
+    fn get_dense_neighbors_with_too_few_node_states_wave_function(nodes_total: usize) -> WaveFunction<String> {
+        // every node must differ from every other node while there is one less node state than there are nodes
+        testing::generate_all_different_clique(nodes_total, nodes_total - 1).unwrap()
+    }
+
+    #[test]
+    fn collapse_options_max_iterations_of_uncollapsable_dense_neighbors() {
+        init();
+
+        let wave_function = get_dense_neighbors_with_too_few_node_states_wave_function(12);
+        wave_function.validate().unwrap();
+
+        let collapse_options = CollapseOptions {
+            max_iterations: Some(1000),
+            max_duration: None,
+            max_backtracks: None,
+            max_restarts: None,
+            cancel_token: None,
+            lookahead: PropagationDepth::None
+        };
+        let mut collapsable_wave_functions: Vec<Box<dyn CollapsableWaveFunction<String>>> = vec![
+            Box::new(wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0))),
+            Box::new(wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(0))),
+            Box::new(wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(Some(0)))
+        ];
+        for collapsable_wave_function in collapsable_wave_functions.iter_mut() {
+            collapsable_wave_function.set_collapse_options(collapse_options.clone());
+            let CollapseError::Aborted { message, iterations_completed, partial } = collapsable_wave_function.collapse_or_abort().err().unwrap() else {
+                panic!("The collapse should have been aborted.");
+            };
+            assert!(message.starts_with("Collapse exceeded the maximum of 1000 iterations with "), "Unexpected error: {message}");
+            assert!(message.ends_with(" of 12 nodes collapsed."), "Unexpected error: {message}");
+            assert_eq!(1001, iterations_completed);
+            assert!(partial.len() <= 12, "Unexpected partial collapse: {partial:?}");
+            assert!(partial.iter().all(|collapsed_node_state| collapsed_node_state.node_state_id.is_some()), "Unexpected partial collapse: {partial:?}");
+        }
+
+        // propagating restrictions lets the entropic collapse find the contradiction within the same limit
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(0));
+        collapsable_wave_function.set_collapse_options(collapse_options.clone());
+        assert_eq!(CollapseError::Uncollapsable(String::from("Cannot collapse wave function.")), collapsable_wave_function.collapse_or_abort().err().unwrap());
+
+        // the partial collapse can be inspected after giving up, where the sequential collapse got far enough to be backtracking among the last nodes
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0));
+        collapsable_wave_function.set_collapse_options(collapse_options);
+        let CollapseError::Aborted { partial, .. } = collapsable_wave_function.collapse_or_abort().err().unwrap() else {
+            panic!("The collapse should have been aborted.");
+        };
+        let collapse_snapshot = collapsable_wave_function.snapshot().unwrap();
+        assert!(collapse_snapshot.collapsed_node_states.len() >= 9, "Unexpected snapshot: {collapse_snapshot:?}");
+        let partial_node_states: Vec<(String, String)> = partial.into_iter().map(|collapsed_node_state| (collapsed_node_state.node_id, collapsed_node_state.node_state_id.unwrap())).collect();
+        assert_eq!(collapse_snapshot.collapsed_node_states, partial_node_states);
+    }
+
+    #[test]
+    fn collapse_options_max_restarts_of_uncollapsable_dense_neighbors_accommodating_sequential() {
+        init();
+
+        let wave_function = get_dense_neighbors_with_too_few_node_states_wave_function(6);
+        wave_function.validate().unwrap();
+
+        for random_seed in 0..5 {
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(Some(random_seed));
+            collapsable_wave_function.set_collapse_options(CollapseOptions {
+                max_iterations: None,
+                max_duration: None,
+                max_backtracks: None,
+                max_restarts: Some(10),
+                cancel_token: None,
+                lookahead: PropagationDepth::None
+            });
+            let (collapsed_wave_function_result, collapse_report) = collapsable_wave_function.collapse_with_report();
+            let error_message = collapsed_wave_function_result.err().unwrap();
+            assert!(error_message.starts_with("Collapse exceeded the maximum of 10 restarts with "), "Unexpected error: {error_message}");
+            assert!(error_message.ends_with(" of 6 nodes collapsed."), "Unexpected error: {error_message}");
+            assert_eq!(11, collapse_report.restarts_total, "Unexpected report: {collapse_report:?}");
+            assert!(collapse_report.recollapses_total > 0, "Unexpected report: {collapse_report:?}");
+            assert_eq!(collapse_report.node_assignments_total, 6 + collapse_report.recollapses_total, "Unexpected report: {collapse_report:?}");
+        }
+    }
+
+    #[test]
+    fn collapse_options_lookahead_avoids_backtracking_along_chain() {
+        init();
+
+        // the last node of the chain cannot follow state_x, which only full propagation notices when the first node tries state_x
+        let node_state_ids: Vec<String> = vec![String::from("state_x"), String::from("state_y")];
+        let mut node_state_collections: Vec<NodeStateCollection<String>> = Vec::new();
+        for node_state_id in node_state_ids.iter() {
+            node_state_collections.push(NodeStateCollection::new(format!("same_as_{node_state_id}"), node_state_id.clone(), vec![node_state_id.clone()]));
+        }
+        node_state_collections.push(NodeStateCollection::new(String::from("nothing_after_state_x"), String::from("state_x"), Vec::new()));
+        let node_state_collection_ids_per_index: Vec<Vec<String>> = vec![
+            vec![String::from("same_as_state_x"), String::from("same_as_state_y")],
+            vec![String::from("same_as_state_x"), String::from("same_as_state_y")],
+            vec![String::from("nothing_after_state_x"), String::from("same_as_state_y")]
+        ];
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        for index in 0..4 {
+            let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+            if let Some(node_state_collection_ids) = node_state_collection_ids_per_index.get(index) {
+                node_state_collection_ids_per_neighbor_node_id.insert(format!("node_{}", index + 1), node_state_collection_ids.clone());
+            }
+            nodes.push(Node::new_with_ordered_node_states(format!("node_{index}"), node_state_ids.iter().map(|node_state_id| (node_state_id.clone(), 1.0)).collect(), node_state_collection_ids_per_neighbor_node_id));
+        }
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate().unwrap();
+
+        let mut backtracks_total_per_lookahead: Vec<u64> = Vec::new();
+        for lookahead in [PropagationDepth::None, PropagationDepth::Neighbors, PropagationDepth::Full] {
+            let mut collapsable_wave_function = wave_function.get_unshuffled_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(0);
+            collapsable_wave_function.set_collapse_options(CollapseOptions {
+                lookahead,
+                ..CollapseOptions::default()
+            });
+            let (collapsed_wave_function_result, collapse_report) = collapsable_wave_function.collapse_with_report();
+            let collapsed_wave_function = collapsed_wave_function_result.unwrap();
+            assert!(collapsed_wave_function.node_state_per_node_id.values().all(|node_state_id| node_state_id == "state_y"));
+            wave_function.validate_collapsed(&collapsed_wave_function).unwrap();
+            backtracks_total_per_lookahead.push(collapse_report.backtracks_total);
+        }
+        assert!(backtracks_total_per_lookahead[0] > backtracks_total_per_lookahead[1], "Unexpected backtracks: {backtracks_total_per_lookahead:?}");
+        assert!(backtracks_total_per_lookahead[1] > 0, "Unexpected backtracks: {backtracks_total_per_lookahead:?}");
+        assert_eq!(0, backtracks_total_per_lookahead[2]);
+
+        // propagating further never makes an uncollapsable wave function collapsable
+        let wave_function = get_dense_neighbors_with_too_few_node_states_wave_function(5);
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            lookahead: PropagationDepth::Full,
+            ..CollapseOptions::default()
+        });
+        assert_eq!("Cannot collapse wave function.", collapsable_wave_function.collapse().err().unwrap());
+    }
+
+    #[test]
+    fn collapse_options_lookahead_counts_iterations() {
+        init();
+
+        // the first node state chosen restricts every other node of the chain through the full lookahead
+        let node_state_ids: Vec<String> = vec![String::from("state_x"), String::from("state_y")];
+        let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        for node_state_id in node_state_ids.iter() {
+            permitted_node_state_ids_per_node_state_id.insert(node_state_id.clone(), vec![node_state_id.clone()]);
+        }
+        let mut wave_function_builder: WaveFunctionBuilder<String> = WaveFunctionBuilder::new();
+        for node_index in 0..200 {
+            wave_function_builder.add_node(&format!("node_{node_index}"), node_state_ids.clone());
+        }
+        for node_index in 0..199 {
+            wave_function_builder.add_constraint(&format!("node_{node_index}"), &format!("node_{}", node_index + 1), permitted_node_state_ids_per_node_state_id.clone());
+        }
+        let wave_function = wave_function_builder.build().unwrap();
+
+        let mut collapsable_wave_function = wave_function.get_unshuffled_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(0);
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: Some(50),
+            lookahead: PropagationDepth::Full,
+            ..CollapseOptions::default()
+        });
+        let (collapsed_wave_function_result, collapse_report) = collapsable_wave_function.collapse_with_report();
+        assert_eq!("Collapse exceeded the maximum of 50 iterations with 0 of 200 nodes collapsed.", collapsed_wave_function_result.err().unwrap());
+        assert_eq!(51, collapse_report.iterations_total);
+
+        // the same lookahead within the limit collapses every node in one pass
+        let mut collapsable_wave_function = wave_function.get_unshuffled_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(0);
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            lookahead: PropagationDepth::Full,
+            ..CollapseOptions::default()
+        });
+        let (collapsed_wave_function_result, collapse_report) = collapsable_wave_function.collapse_with_report();
+        wave_function.validate_collapsed(&collapsed_wave_function_result.unwrap()).unwrap();
+        assert_eq!(0, collapse_report.backtracks_total);
+        assert!(collapse_report.iterations_total > 200 + 199, "Unexpected report: {collapse_report:?}");
+    }
+
+    #[test]
+    fn collapse_options_cancel_token() {
+        init();
+
+        let wave_function = get_dense_neighbors_with_too_few_node_states_wave_function(12);
+        wave_function.validate().unwrap();
+
+        // a collapse that is cancelled before it starts stops at its first iteration
+        let cancel_token = Arc::new(AtomicBool::new(true));
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: None,
+            max_duration: None,
+            max_backtracks: None,
+            max_restarts: None,
+            cancel_token: Some(cancel_token),
+            lookahead: PropagationDepth::None
+        });
+        assert_eq!("Collapse cancelled after 1 iterations with 0 of 12 nodes collapsed.", collapsable_wave_function.collapse().err().unwrap());
+
+        // without any other limit the collapse would search every ordering of the node states
+        // the maximum duration only keeps the test from hanging if cancelling stops working
+        let cancel_token = Arc::new(AtomicBool::new(false));
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: None,
+            max_duration: Some(Duration::from_secs(60)),
+            max_backtracks: None,
+            max_restarts: None,
+            cancel_token: Some(cancel_token.clone()),
+            lookahead: PropagationDepth::None
+        });
+        let error_message = std::thread::scope(|scope| {
+            scope.spawn(|| {
+                std::thread::sleep(Duration::from_millis(50));
+                cancel_token.store(true, Ordering::Relaxed);
+            });
+            collapsable_wave_function.collapse().err().unwrap()
+        });
+        assert!(error_message.starts_with("Collapse cancelled after "), "Unexpected error: {error_message}");
+        assert!(error_message.ends_with(" of 12 nodes collapsed."), "Unexpected error: {error_message}");
+    }
+
+    fn get_checkerboard_sample() -> Vec<Vec<String>> {
+        (0..4).map(|x| (0..3).map(|y| String::from(if (x + y) % 2 == 0 { "black" } else { "white" })).collect()).collect()
+    }
+
+    #[test]
+    fn learn_adjacency_rules_from_checkerboard() {
+        init();
+
+        let sample = get_checkerboard_sample();
+        let adjacency_rules = adjacency_learning::learn_adjacency_rules(&sample, &Direction::all());
+        assert_eq!(8, adjacency_rules.len());
+        for direction in Direction::all() {
+            assert!(adjacency_rules.contains(&(String::from("black"), direction, String::from("white"))));
+            assert!(adjacency_rules.contains(&(String::from("white"), direction, String::from("black"))));
+        }
+
+        let adjacency_rules = adjacency_learning::learn_adjacency_rules(&sample, &[Direction::Right]);
+        assert_eq!(vec![(String::from("black"), Direction::Right, String::from("white")), (String::from("white"), Direction::Right, String::from("black"))], adjacency_rules);
+    }
+
+    #[test]
+    fn learned_checkerboard_collapses_into_checkerboard() {
+        init();
+
+        let grid_builder = adjacency_learning::get_grid_builder(&get_checkerboard_sample(), 7, 5, false).unwrap();
+        let wave_function = grid_builder.build().unwrap();
+        for random_seed in 0..5 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let grid = grid_builder.get_grid(&collapsed_wave_function).unwrap();
+            for x in 0..7 {
+                for y in 0..5 {
+                    // every node matches the parity of the node in the corner
+                    assert_eq!((x + y) % 2 == 0, grid[x][y] == grid[0][0]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn learned_stripes_with_rotations_and_reflections() {
+        init();
+
+        // horizontal stripes only ever permit the same node state to the left and right
+        let sample: Vec<Vec<String>> = (0..3).map(|_| (0..4).map(|y| String::from(if y % 2 == 0 { "red" } else { "blue" })).collect()).collect();
+        let adjacency_rules = adjacency_learning::learn_adjacency_rules(&sample, &Direction::all());
+        assert!(adjacency_rules.contains(&(String::from("red"), Direction::Right, String::from("red"))));
+        assert!(!adjacency_rules.contains(&(String::from("red"), Direction::Down, String::from("red"))));
+        assert!(!adjacency_rules.contains(&(String::from("red"), Direction::Right, String::from("blue"))));
+
+        let adjacency_rules = adjacency_learning::get_rotated_and_reflected_adjacency_rules(&adjacency_rules);
+        assert_eq!(16, adjacency_rules.len());
+        assert!(adjacency_rules.contains(&(String::from("red"), Direction::Down, String::from("red"))));
+        assert!(adjacency_rules.contains(&(String::from("red"), Direction::Right, String::from("blue"))));
+
+        // without rotations and reflections only horizontal stripes are possible
+        let grid_builder = adjacency_learning::get_grid_builder(&sample, 4, 4, false).unwrap();
+        let collapsed_wave_function = grid_builder.build().unwrap().get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap();
+        let grid = grid_builder.get_grid(&collapsed_wave_function).unwrap();
+        for x in 1..4 {
+            assert_eq!(grid[0], grid[x]);
+        }
+
+        assert_eq!("Cannot learn adjacency rules from an empty sample.", adjacency_learning::get_grid_builder::<String>(&[], 2, 2, true).err().unwrap());
+    }
+
+    #[test]
+    fn learn_from_grid_striped_sample_only_produces_seen_adjacencies() {
+        init();
+
+        // vertical stripes that are two red columns followed by one blue column
+        let sample: Vec<Vec<String>> = (0..6).map(|x| (0..4).map(|_| String::from(if x % 3 == 2 { "blue" } else { "red" })).collect()).collect();
+        let learned_model = adjacency_learning::learn_from_grid(&sample, 2, false).unwrap();
+        assert_eq!(2, learned_model.get_pattern_size());
+        // the columns of each pattern are red and red, red and blue, or blue and red
+        assert_eq!(3, learned_model.get_patterns().len());
+        assert_eq!(15, learned_model.get_pattern_counts().iter().sum::<u32>());
+        assert_eq!(vec![6, 6, 3], learned_model.get_pattern_counts());
+
+        let seen_adjacency_rules = adjacency_learning::learn_adjacency_rules(&sample, &Direction::all());
+        let wave_function = learned_model.get_wave_function(9, 7).unwrap();
+        for random_seed in 0..10 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<usize>>(Some(random_seed)).collapse().unwrap();
+            let grid = learned_model.get_grid(&collapsed_wave_function, 9, 7).unwrap();
+            assert_eq!(9, grid.len());
+            for x in 0..9usize {
+                assert_eq!(7, grid[x].len());
+                for y in 0..7usize {
+                    for direction in Direction::all() {
+                        let (x_offset, y_offset) = direction.get_offset();
+                        let (Some(neighbor_x), Some(neighbor_y)) = (x.checked_add_signed(x_offset as isize), y.checked_add_signed(y_offset as isize)) else {
+                            continue;
+                        };
+                        if neighbor_x < 9 && neighbor_y < 7 {
+                            let adjacency_rule = (grid[x][y].clone(), direction, grid[neighbor_x][neighbor_y].clone());
+                            assert!(seen_adjacency_rules.contains(&adjacency_rule), "Adjacency {adjacency_rule:?} was not seen in the sample.");
+                        }
+                    }
+                }
+                // every column is a stripe
+                assert!(grid[x].iter().all(|node_state_id| *node_state_id == grid[x][0]));
+            }
+        }
+    }
+
+    #[test]
+    fn learn_from_grid_wrapping_checkerboard() {
+        init();
+
+        let sample = get_checkerboard_sample();
+        // the odd height breaks the checkerboard where the sample wraps vertically
+        let learned_model = adjacency_learning::learn_from_grid(&sample, 2, true).unwrap();
+        assert_eq!(12, learned_model.get_pattern_counts().iter().sum::<u32>());
+        assert_eq!(4, learned_model.get_patterns().len());
+        let learned_model = adjacency_learning::learn_from_grid(&sample, 2, false).unwrap();
+        assert_eq!(6, learned_model.get_pattern_counts().iter().sum::<u32>());
+        assert_eq!(2, learned_model.get_patterns().len());
+
+        let wave_function = learned_model.get_wave_function(6, 6).unwrap();
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<usize>>(Some(0)).collapse().unwrap();
+        let grid = learned_model.get_grid(&collapsed_wave_function, 6, 6).unwrap();
+        for x in 0..6 {
+            for y in 0..6 {
+                assert_eq!((x + y) % 2 == 0, grid[x][y] == grid[0][0]);
+            }
+        }
+
+        assert_eq!("Cannot learn patterns of size 0.", adjacency_learning::learn_from_grid(&sample, 0, false).err().unwrap());
+        assert_eq!("Cannot learn patterns of size 4 from a sample of 4 by 3.", adjacency_learning::learn_from_grid(&sample, 4, false).err().unwrap());
+        assert_eq!("Cannot learn patterns from an empty sample.", adjacency_learning::learn_from_grid::<String>(&[], 2, false).err().unwrap());
+        assert_eq!("Cannot learn patterns from a sample with columns of different lengths.", adjacency_learning::learn_from_grid(&[vec![0, 1], vec![0]], 1, false).err().unwrap());
+        assert_eq!("Cannot create an output of 1 by 6 from patterns of size 2.", learned_model.get_wave_function(1, 6).err().unwrap());
+        assert_eq!("Cannot create an output of 6 by 1 from patterns of size 2.", learned_model.get_grid(&collapsed_wave_function, 6, 1).err().unwrap());
+    }
+
+    fn get_three_color_grid_wave_function() -> WaveFunction<String> {
+        get_three_color_grid_wave_function_with_size(6, 6)
+    }
+
+    fn get_three_color_grid_wave_function_with_size(width: usize, height: usize) -> WaveFunction<String> {
+        let node_state_ids: Vec<String> = vec![String::from("red"), String::from("green"), String::from("blue")];
+        let mut grid_builder = GridBuilder::new(width, height, node_state_ids.clone());
+        for node_state_id in node_state_ids.iter() {
+            for other_node_state_id in node_state_ids.iter() {
+                if node_state_id != other_node_state_id {
+                    grid_builder
+                        .allow(node_state_id.clone(), Direction::Right, other_node_state_id.clone())
+                        .allow(node_state_id.clone(), Direction::Down, other_node_state_id.clone());
+                }
+            }
+        }
+        grid_builder.build().unwrap()
+    }
+
+    #[test]
+    fn node_state_order_key_is_fixed() {
+        init();
+
+        // these are the 128-bit FNV-1a hashes of the bytes that each node state feeds the hasher, which must never change so that seeded collapses are reproducible everywhere
+        assert_eq!(0x6b8d6b2180659bafa30a34ffe26b462b, crate::wave_function::get_node_state_order_key(&String::from("state_A")));
+        assert_eq!(0x6696dd5cf2757277b806e8964df91bea, crate::wave_function::get_node_state_order_key(&7_u32));
+    }
+
+    #[test]
+    fn serialization_is_identical_regardless_of_insertion_order() {
+        init();
+
+        let node_ids: Vec<String> = (0..50).map(|index| format!("node_{index}")).collect();
+        let get_collapsed_wave_function_with_diagnostics = |node_ids: &mut dyn Iterator<Item = &String>| -> CollapsedWaveFunctionWithDiagnostics<String> {
+            let mut node_state_per_node_id: HashMap<String, String> = HashMap::new();
+            let mut node_metadata_per_node: HashMap<String, serde_json::Value> = HashMap::new();
+            let mut diagnostics_per_node_id: HashMap<String, CollapsedNodeStateDiagnostics> = HashMap::new();
+            for node_id in node_ids {
+                node_state_per_node_id.insert(node_id.clone(), format!("state_of_{node_id}"));
+                node_metadata_per_node.insert(node_id.clone(), serde_json::json!({ "node_id": node_id }));
+                diagnostics_per_node_id.insert(node_id.clone(), CollapsedNodeStateDiagnostics {
+                    probability: 0.5,
+                    remaining_node_states_total: node_id.len()
+                });
+            }
+            CollapsedWaveFunctionWithDiagnostics {
+                collapsed_wave_function: CollapsedWaveFunction {
+                    node_state_per_node_id,
+                    random_seed: 0,
+                    node_metadata_per_node
+                },
+                diagnostics_per_node_id
+            }
+        };
+        let first = get_collapsed_wave_function_with_diagnostics(&mut node_ids.iter());
+        let second = get_collapsed_wave_function_with_diagnostics(&mut node_ids.iter().rev());
+        assert_eq!(first, second);
+        let serialized_first = serde_json::to_string(&first).unwrap();
+        assert_eq!(serialized_first, serde_json::to_string(&second).unwrap());
+        assert_eq!(first, serde_json::from_str::<CollapsedWaveFunctionWithDiagnostics<String>>(&serialized_first).unwrap());
+
+        // the neighbors of each node are serialized in node id order when saving a wave function
+        let get_node = |node_ids: &mut dyn Iterator<Item = &String>| -> Node<String> {
+            let node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = node_ids.map(|node_id| (node_id.clone(), vec![format!("collection_for_{node_id}")])).collect();
+            Node::new(String::from("center"), NodeStateProbability::get_equal_probability(&vec![String::from("state")]), node_state_collection_ids_per_neighbor_node_id)
+        };
+        let serialized_node = serde_json::to_string(&get_node(&mut node_ids.iter())).unwrap();
+        assert_eq!(serialized_node, serde_json::to_string(&get_node(&mut node_ids.iter().rev())).unwrap());
+        assert!(serialized_node.starts_with("{\"id\":\"center\",\"node_state_collection_ids_per_neighbor_node_id\":{\"node_0\":[\"collection_for_node_0\"],\"node_1\":"), "Unexpected serialization: {serialized_node}");
+    }
+
+    fn get_disjoint_cliques_wave_function(cliques_total: usize, clique_nodes_total: usize) -> WaveFunction<String> {
+        let node_state_ids: Vec<String> = (0..clique_nodes_total).map(|index| format!("state_{index}")).collect();
+        let node_state_collections: Vec<NodeStateCollection<String>> = node_state_ids
+            .iter()
+            .map(|node_state_id| NodeStateCollection::new_forbidden(format!("not_{node_state_id}"), node_state_id.clone(), vec![node_state_id.clone()]))
+            .collect();
+        let node_state_collection_ids: Vec<String> = node_state_collections.iter().map(|node_state_collection| node_state_collection.id.clone()).collect();
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        for clique_index in 0..cliques_total {
+            for node_index in 0..clique_nodes_total {
+                let node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = (0..clique_nodes_total)
+                    .filter(|other_node_index| *other_node_index != node_index)
+                    .map(|other_node_index| (format!("clique_{clique_index}_node_{other_node_index}"), node_state_collection_ids.clone()))
+                    .collect();
+                nodes.push(Node::new(format!("clique_{clique_index}_node_{node_index}"), NodeStateProbability::get_equal_probability(&node_state_ids), node_state_collection_ids_per_neighbor_node_id));
+            }
+        }
+        WaveFunction::new(nodes, node_state_collections)
+    }
+
+    fn assert_disjoint_cliques_all_different(collapsed_wave_function: &CollapsedWaveFunction<String>, cliques_total: usize, clique_nodes_total: usize) {
+        assert_eq!(cliques_total * clique_nodes_total, collapsed_wave_function.node_state_per_node_id.len());
+        for clique_index in 0..cliques_total {
+            let mut node_state_ids: Vec<&String> = (0..clique_nodes_total)
+                .map(|node_index| collapsed_wave_function.node_state_per_node_id.get(&format!("clique_{clique_index}_node_{node_index}")).unwrap())
+                .collect();
+            node_state_ids.sort();
+            node_state_ids.dedup();
+            assert_eq!(clique_nodes_total, node_state_ids.len());
+        }
+    }
+
+    #[test]
+    fn parallel_collapse_of_disjoint_cliques() {
+        init();
+
+        let wave_function = get_disjoint_cliques_wave_function(8, 10);
+
+        for random_seed in 0..10 {
+            let sequential_start = std::time::Instant::now();
+            let sequential_collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let sequential_duration = sequential_start.elapsed();
+            assert_disjoint_cliques_all_different(&sequential_collapsed_wave_function, 8, 10);
+
+            let parallel_collapsable_wave_function = ParallelCollapsableWaveFunction::new(&wave_function, Some(random_seed), 4);
+            assert_eq!(8, parallel_collapsable_wave_function.get_partitions_total());
+            let parallel_start = std::time::Instant::now();
+            let parallel_collapsed_wave_function = parallel_collapsable_wave_function.collapse().unwrap();
+            let parallel_duration = parallel_start.elapsed();
+            debug!("sequential collapse took {:?} and parallel collapse took {:?}.", sequential_duration, parallel_duration);
+            assert_disjoint_cliques_all_different(&parallel_collapsed_wave_function, 8, 10);
+            assert_eq!(random_seed, parallel_collapsed_wave_function.random_seed);
+
+            // the collapsed wave function does not depend on how many threads collapsed the partitions
+            let single_thread_collapsed_wave_function = ParallelCollapsableWaveFunction::new(&wave_function, Some(random_seed), 1).collapse().unwrap();
+            assert_eq!(parallel_collapsed_wave_function, single_thread_collapsed_wave_function);
+        }
+    }
+
+    #[test]
+    fn parallel_collapse_with_partitions() {
+        init();
+
+        let wave_function = get_disjoint_cliques_wave_function(4, 6);
+
+        // splitting each clique in half still collapses each clique together since its halves are neighbors
+        let mut partitions: Vec<Vec<String>> = Vec::new();
+        for clique_index in 0..4 {
+            partitions.push((0..3).map(|node_index| format!("clique_{clique_index}_node_{node_index}")).collect());
+            partitions.push((3..6).map(|node_index| format!("clique_{clique_index}_node_{node_index}")).collect());
+        }
+        let parallel_collapsable_wave_function = ParallelCollapsableWaveFunction::new_with_partitions(&wave_function, partitions.clone(), Some(0), 2).unwrap();
+        assert_eq!(4, parallel_collapsable_wave_function.get_partitions_total());
+        assert_disjoint_cliques_all_different(&parallel_collapsable_wave_function.collapse().unwrap(), 4, 6);
+
+        // all of the cliques in one partition are collapsed together
+        let all_node_ids: Vec<String> = partitions.iter().flatten().cloned().collect();
+        let parallel_collapsable_wave_function = ParallelCollapsableWaveFunction::new_with_partitions(&wave_function, vec![all_node_ids], Some(0), 2).unwrap();
+        assert_eq!(1, parallel_collapsable_wave_function.get_partitions_total());
+        assert_disjoint_cliques_all_different(&parallel_collapsable_wave_function.collapse().unwrap(), 4, 6);
+
+        let mut missing_node_partitions = partitions.clone();
+        missing_node_partitions[7].pop();
+        assert_eq!("Node clique_3_node_5 is not in any partition.", ParallelCollapsableWaveFunction::new_with_partitions(&wave_function, missing_node_partitions, Some(0), 2).err().unwrap());
+
+        let mut repeated_node_partitions = partitions.clone();
+        repeated_node_partitions[1].push(String::from("clique_0_node_0"));
+        assert_eq!("Node clique_0_node_0 is in more than one partition.", ParallelCollapsableWaveFunction::new_with_partitions(&wave_function, repeated_node_partitions, Some(0), 2).err().unwrap());
+
+        let mut unknown_node_partitions = partitions;
+        unknown_node_partitions[0].push(String::from("missing"));
+        assert_eq!("Partition references node missing that does not exist.", ParallelCollapsableWaveFunction::new_with_partitions(&wave_function, unknown_node_partitions, Some(0), 2).err().unwrap());
+    }
+
+    #[test]
+    fn parallel_collapse_of_uncollapsable_partition() {
+        init();
+
+        let wave_function = get_two_nodes_with_conflicting_state_requirements_wave_function();
+        let error_message = ParallelCollapsableWaveFunction::new(&wave_function, Some(0), 2).collapse().err().unwrap();
+        assert_eq!("Cannot collapse partition 0 of 1: Cannot collapse wave function.", error_message);
+    }
+
+    #[test]
+    fn collapse_step_iter_first_steps_of_large_grid_are_consistent() {
+        init();
+
+        let wave_function = get_three_color_grid_wave_function_with_size(100, 100);
+        let nodes = wave_function.get_nodes();
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0));
+        let mut node_state_per_node_id: HashMap<String, String> = HashMap::new();
+        for collapsed_node_state in collapsable_wave_function.collapse_step_iter().take(10) {
+            let collapsed_node_state = collapsed_node_state.unwrap();
+            match collapsed_node_state.node_state_id {
+                Some(node_state_id) => node_state_per_node_id.insert(collapsed_node_state.node_id, node_state_id),
+                None => node_state_per_node_id.remove(&collapsed_node_state.node_id)
+            };
+        }
+        assert!(!node_state_per_node_id.is_empty());
+        for node in nodes.iter() {
+            let Some(node_state_id) = node_state_per_node_id.get(&node.id) else {
+                continue;
+            };
+            for neighbor_node_id in node.node_state_collection_ids_per_neighbor_node_id.keys() {
+                assert_ne!(Some(node_state_id), node_state_per_node_id.get(neighbor_node_id));
+            }
+        }
+
+        // the partially collapsed wave function can still be collapsed after the iterator is dropped
+        let collapsed_wave_function = collapsable_wave_function.collapse().unwrap();
+        for node in nodes.iter() {
+            for neighbor_node_id in node.node_state_collection_ids_per_neighbor_node_id.keys() {
+                assert_ne!(collapsed_wave_function.node_state_per_node_id.get(&node.id), collapsed_wave_function.node_state_per_node_id.get(neighbor_node_id));
+            }
+        }
+    }
+
+    #[test]
+    fn collapse_step_iter_same_as_collapse_into_steps() {
+        init();
+
+        let wave_function = get_three_color_grid_wave_function();
+        for random_seed in 0..10 {
+            let collapsed_node_states = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse_into_steps().unwrap();
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed));
+            let iterated_collapsed_node_states: Vec<CollapsedNodeState<String>> = collapsable_wave_function.collapse_step_iter().collect::<Result<Vec<_>, String>>().unwrap();
+            assert_eq!(collapsed_node_states, iterated_collapsed_node_states);
+        }
+
+        // the iterator ends once the wave function is known to be uncollapsable
+        let wave_function = get_two_nodes_with_conflicting_state_requirements_wave_function();
+        let collapsed_node_states = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse_into_steps().unwrap();
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0));
+        let iterated_collapsed_node_states: Vec<CollapsedNodeState<String>> = collapsable_wave_function.collapse_step_iter().collect::<Result<Vec<_>, String>>().unwrap();
+        assert_eq!(collapsed_node_states, iterated_collapsed_node_states);
+        assert_eq!(None, iterated_collapsed_node_states.last().unwrap().node_state_id);
+
+        // the iterator ends after reporting that the collapse limit was exceeded
+        let wave_function = get_three_color_grid_wave_function();
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: Some(5),
+            max_duration: None,
+            max_backtracks: None,
+            max_restarts: None,
+            cancel_token: None,
+            lookahead: PropagationDepth::None
+        });
+        let collapse_step_results: Vec<Result<CollapsedNodeState<String>, String>> = collapsable_wave_function.collapse_step_iter().collect();
+        assert_eq!(6, collapse_step_results.len());
+        assert!(collapse_step_results.last().unwrap().is_err());
+    }
+
+    fn assert_resumed_collapse_is_same_as_direct_collapse<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, String>>(wave_function: &'a WaveFunction<String>) {
+        let (collapsed_wave_function_result, collapse_report) = wave_function.get_collapsable_wave_function::<TCollapsableWaveFunction>(Some(12345)).collapse_with_report();
+        let expected_collapsed_wave_function = collapsed_wave_function_result.unwrap();
+
+        // stop the collapse halfway through
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<TCollapsableWaveFunction>(Some(12345));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: Some(collapse_report.iterations_total / 2),
+            max_duration: None,
+            max_backtracks: None,
+            max_restarts: None,
+            cancel_token: None,
+            lookahead: PropagationDepth::None
+        });
+        assert!(collapsable_wave_function.collapse().is_err());
+        let collapse_snapshot = collapsable_wave_function.snapshot().unwrap();
+        assert!(!collapse_snapshot.collapsed_node_states.is_empty());
+        assert!(collapse_snapshot.collapsed_node_states.len() < expected_collapsed_wave_function.node_state_per_node_id.len());
+
+        // the snapshot is saved and loaded, such as across a restart
+        let serialized_collapse_snapshot = serde_json::to_string(&collapse_snapshot).unwrap();
+        let collapse_snapshot: CollapseSnapshot<String> = serde_json::from_str(&serialized_collapse_snapshot).unwrap();
+
+        let mut resumed_collapsable_wave_function = wave_function.resume_collapsable_wave_function::<TCollapsableWaveFunction>(&collapse_snapshot).unwrap();
+        assert_eq!(expected_collapsed_wave_function, resumed_collapsable_wave_function.collapse().unwrap());
+    }
+
+    #[test]
+    fn resumed_collapse_is_same_as_direct_collapse_sequential() {
+        init();
+
+        let wave_function = get_three_color_grid_wave_function_with_size(10, 10);
+        assert_resumed_collapse_is_same_as_direct_collapse::<SequentialCollapsableWaveFunction<String>>(&wave_function);
+    }
+
+    #[test]
+    fn resumed_collapse_is_same_as_direct_collapse_entropic() {
+        init();
+
+        let wave_function = get_three_color_grid_wave_function_with_size(10, 10);
+        assert_resumed_collapse_is_same_as_direct_collapse::<EntropicCollapsableWaveFunction<String>>(&wave_function);
+    }
+
+    #[test]
+    fn resume_collapsable_wave_function_from_mismatched_snapshot() {
+        init();
+
+        let wave_function = get_three_color_grid_wave_function_with_size(10, 10);
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(12345));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: Some(20),
+            max_duration: None,
+            max_backtracks: None,
+            max_restarts: None,
+            cancel_token: None,
+            lookahead: PropagationDepth::None
+        });
+        assert!(collapsable_wave_function.collapse().is_err());
+        let mut collapse_snapshot = collapsable_wave_function.snapshot().unwrap();
+        collapse_snapshot.collapsed_node_states[0].0 = String::from("missing");
+        let error_message = wave_function.resume_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(&collapse_snapshot).err().unwrap();
+        assert!(error_message.starts_with("Node missing was collapsed in the snapshot but node "), "Unexpected error: {error_message}");
+
+        let collapsable_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(12345));
+        assert_eq!("The accommodating collapsable wave function does not support snapshots.", collapsable_wave_function.snapshot().err().unwrap());
+    }
+
+    fn assert_seeded_collapse_is_deterministic(get_wave_function: impl Fn() -> WaveFunction<String>, collapse: impl Fn(&WaveFunction<String>) -> CollapsedWaveFunction<String>) {
+        let mut expected_serialized_collapsed_wave_function: Option<(String, Vec<u8>)> = None;
+        for _ in 0..50 {
+            // rebuild the wave function each time so that every hashmap is constructed anew
+            let wave_function = get_wave_function();
+            let collapsed_wave_function = collapse(&wave_function);
+            // both the json and the bytes saved to file must be identical, not just equal once deserialized
+            let serialized_collapsed_wave_function = (serde_json::to_string(&collapsed_wave_function).unwrap(), collapsed_wave_function.to_bytes());
+            if let Some(expected_serialized_collapsed_wave_function) = &expected_serialized_collapsed_wave_function {
+                assert_eq!(expected_serialized_collapsed_wave_function, &serialized_collapsed_wave_function);
+            }
+            else {
+                expected_serialized_collapsed_wave_function = Some(serialized_collapsed_wave_function);
+            }
+        }
+    }
+
+    #[test]
+    fn seeded_collapse_is_deterministic_sequential() {
+        init();
+
+        assert_seeded_collapse_is_deterministic(get_three_color_grid_wave_function, |wave_function| wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(12345)).collapse().unwrap());
+    }
+
+    #[test]
+    fn seeded_collapse_is_deterministic_accommodating() {
+        init();
+
+        assert_seeded_collapse_is_deterministic(get_three_color_grid_wave_function, |wave_function| wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(12345)).collapse().unwrap());
+    }
+
+    #[test]
+    fn seeded_collapse_is_deterministic_acc_seq() {
+        init();
+
+        assert_seeded_collapse_is_deterministic(get_three_color_grid_wave_function, |wave_function| wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(Some(12345)).collapse().unwrap());
+    }
+
+    #[test]
+    fn seeded_collapse_is_deterministic_entropic() {
+        init();
+
+        assert_seeded_collapse_is_deterministic(get_three_color_grid_wave_function, |wave_function| wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(12345)).collapse().unwrap());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn seeded_collapse_is_deterministic_3D_grid_sequential() {
+        init();
+
+        // most random seeds backtrack for a long time on this grid, so this random seed is known to collapse quickly
+        assert_seeded_collapse_is_deterministic(|| get_3D_grid_all_different_states_wave_function(4, 8), |wave_function| wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(16)).collapse().unwrap());
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn seeded_collapse_is_deterministic_3D_grid_accommodating() {
+        init();
+
+        // the accommodating collapsable wave function needs more node states than the sequential collapsable wave function to collapse this grid, just as in many_nodes_as_3D_grid_all_different_states_accommodating
+        assert_seeded_collapse_is_deterministic(|| get_3D_grid_all_different_states_wave_function(4, 12), |wave_function| wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(16)).collapse().unwrap());
+    }
+
+    fn assert_unseeded_collapse_is_reproducible(collapse: impl Fn(&WaveFunction<String>, Option<u64>) -> CollapsedWaveFunction<String>, unshuffled_collapse: impl Fn(&WaveFunction<String>, u64) -> CollapsedWaveFunction<String>) {
+        let wave_function = get_three_color_grid_wave_function();
+        assert_eq!(12345, collapse(&wave_function, Some(12345)).random_seed);
+        for _ in 0..10 {
+            let collapsed_wave_function = collapse(&wave_function, None);
+            assert_eq!(collapsed_wave_function, unshuffled_collapse(&wave_function, collapsed_wave_function.random_seed));
+        }
+    }
+
+    #[test]
+    fn unseeded_collapse_is_reproducible_from_random_seed() {
+        init();
+
+        assert_unseeded_collapse_is_reproducible(
+            |wave_function, random_seed| wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(random_seed).collapse().unwrap(),
+            |wave_function, random_seed| wave_function.get_unshuffled_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(random_seed).collapse().unwrap()
+        );
+        assert_unseeded_collapse_is_reproducible(
+            |wave_function, random_seed| wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(random_seed).collapse().unwrap(),
+            |wave_function, random_seed| wave_function.get_unshuffled_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(random_seed).collapse().unwrap()
+        );
+        assert_unseeded_collapse_is_reproducible(
+            |wave_function, random_seed| wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(random_seed).collapse().unwrap(),
+            |wave_function, random_seed| wave_function.get_unshuffled_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(random_seed).collapse().unwrap()
+        );
+        assert_unseeded_collapse_is_reproducible(
+            |wave_function, random_seed| wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(random_seed).collapse().unwrap(),
+            |wave_function, random_seed| wave_function.get_unshuffled_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(random_seed).collapse().unwrap()
+        );
+    }
+
+    #[test]
+    fn weighted_probability_rejects_invalid_weights() {
+        init();
+
+        for weight in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            let error_message = NodeStateProbability::get_weighted_probability(vec![(String::from("state_A"), 1.0), (String::from("state_B"), weight)]).err().unwrap();
+            assert_eq!(format!("Node state \"state_B\" has weight {weight} but weights must be finite and positive."), error_message);
+        }
+
+        let error_message = NodeStateProbability::get_weighted_probability(vec![(String::from("state_A"), 1.0), (String::from("state_A"), 2.0)]).err().unwrap();
+        assert_eq!("Node state \"state_A\" is weighted more than once.", error_message);
+    }
+
+    #[test]
+    fn new_with_domains_removes_node_states_of_other_domains() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("wall_A"), String::from("wall_B"), String::from("floor_A"), String::from("floor_B"), String::from("empty")];
+        let mut state_domains: HashMap<String, String> = HashMap::new();
+        state_domains.insert(String::from("wall_A"), String::from("wall"));
+        state_domains.insert(String::from("wall_B"), String::from("wall"));
+        state_domains.insert(String::from("floor_A"), String::from("floor"));
+        state_domains.insert(String::from("floor_B"), String::from("floor"));
+
+        // each node state permits every node state except itself
+        let node_state_collections: Vec<NodeStateCollection<String>> = node_state_ids.iter()
+            .map(|node_state_id| NodeStateCollection::new(format!("not_{node_state_id}"), node_state_id.clone(), node_state_ids.iter().filter(|other_node_state_id| *other_node_state_id != node_state_id).cloned().collect()))
+            .collect();
+        let node_state_collection_ids: Vec<String> = node_state_collections.iter().map(|node_state_collection| node_state_collection.id.clone()).collect();
+
+        let domains = [Some(String::from("wall")), Some(String::from("floor")), None];
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        for (node_index, domain) in domains.iter().enumerate() {
+            let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+            for other_node_index in 0..domains.len() {
+                if node_index != other_node_index {
+                    node_state_collection_ids_per_neighbor_node_id.insert(format!("node_{other_node_index}"), node_state_collection_ids.clone());
+                }
+            }
+            nodes.push(Node::new_with_domain(format!("node_{node_index}"), NodeStateProbability::get_equal_probability(&node_state_ids), node_state_collection_ids_per_neighbor_node_id, domain.clone()));
+        }
+
+        let wave_function = WaveFunction::new_with_domains(nodes, node_state_collections, state_domains.clone()).unwrap();
+        wave_function.validate().unwrap();
+
+        let nodes = wave_function.get_nodes();
+        let mut wall_node_state_ids = nodes[0].node_state_ids.clone();
+        wall_node_state_ids.sort();
+        assert_eq!(vec![String::from("empty"), String::from("wall_A"), String::from("wall_B")], wall_node_state_ids);
+        let mut wall_node_state_collection_ids = nodes[0].node_state_collection_ids_per_neighbor_node_id.get("node_1").unwrap().clone();
+        wall_node_state_collection_ids.sort();
+        assert_eq!(vec![String::from("not_empty"), String::from("not_wall_A"), String::from("not_wall_B")], wall_node_state_collection_ids);
+        assert_eq!(3, nodes[1].node_state_ids.len());
+        assert_eq!(5, nodes[2].node_state_ids.len());
+        assert_eq!(Some(String::from("floor")), nodes[1].domain);
+
+        for random_seed in 0..10 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let wall_node_state_id = collapsed_wave_function.node_state_per_node_id.get("node_0").unwrap();
+            let floor_node_state_id = collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap();
+            assert!(wall_node_state_id.starts_with("wall_") || wall_node_state_id == "empty");
+            assert!(floor_node_state_id.starts_with("floor_") || floor_node_state_id == "empty");
+            assert_ne!(wall_node_state_id, floor_node_state_id);
+        }
+
+        let node = Node::new_with_domain(String::from("node_0"), NodeStateProbability::get_equal_probability(&vec![String::from("wall_A")]), HashMap::new(), Some(String::from("floor")));
+        assert_eq!("Node node_0 is in domain floor but none of its node states are in that domain.", WaveFunction::new_with_domains(vec![node], Vec::new(), state_domains).err().unwrap());
+    }
+
+    #[test]
+    fn node_try_new_rejects_invalid_node_state_ratios() {
+        init();
+
+        for node_state_ratio in [-1.0, f64::NAN, f64::INFINITY] {
+            let mut node_state_ratio_per_node_state_id: HashMap<String, f64> = HashMap::new();
+            node_state_ratio_per_node_state_id.insert(String::from("state_A"), 1.0);
+            node_state_ratio_per_node_state_id.insert(String::from("state_B"), node_state_ratio);
+            let error_message = Node::try_new(String::from("node_1"), node_state_ratio_per_node_state_id, HashMap::new()).err().unwrap();
+            assert_eq!(format!("Node node_1 has node state ratios \"state_B\": {node_state_ratio} but node state ratios must be finite and non-negative."), error_message);
+        }
+
+        // a node without any node states is simply uncollapsable
+        let node = Node::<String>::try_new(String::from("node_1"), HashMap::new(), HashMap::new()).unwrap();
+        assert_eq!("Cannot collapse wave function.", WaveFunction::new(vec![node], Vec::new()).get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse().err().unwrap());
+
+        let mut node_state_ratio_per_node_state_id: HashMap<String, f64> = HashMap::new();
+        node_state_ratio_per_node_state_id.insert(String::from("state_A"), 0.0);
+        node_state_ratio_per_node_state_id.insert(String::from("state_B"), 0.0);
+        let error_message = Node::try_new(String::from("node_1"), node_state_ratio_per_node_state_id.clone(), HashMap::new()).err().unwrap();
+        assert_eq!("Node node_1 has node state ratios of only zero but at least one node state ratio must be positive.", error_message);
+
+        // a single node state that can never be chosen is permitted as long as another node state can be
+        node_state_ratio_per_node_state_id.insert(String::from("state_B"), 2.0);
+        Node::try_new(String::from("node_1"), node_state_ratio_per_node_state_id, HashMap::new()).unwrap();
+    }
+
+    #[test]
+    fn wave_function_with_invalid_node_state_ratios() {
+        init();
+
+        let mut node_state_ratio_per_node_state_id: HashMap<String, f64> = HashMap::new();
+        node_state_ratio_per_node_state_id.insert(String::from("state_A"), -2.0);
+        let wave_function = WaveFunction::new(vec![Node::new(String::from("node_1"), node_state_ratio_per_node_state_id, HashMap::new())], Vec::new());
+        assert_eq!("Node node_1 has node state ratios \"state_A\": -2 but node state ratios must be finite and non-negative.", wave_function.validate().err().unwrap());
+
+        let error_message = serde_json::from_str::<WaveFunction<String>>(&serde_json::to_string(&wave_function).unwrap()).err().unwrap().to_string();
+        assert!(error_message.starts_with("Node node_1 has node state ratios \"state_A\": -2 but node state ratios must be finite and non-negative."), "Unexpected error: {error_message}");
+
+        let mut node_state_ratio_per_node_state_id: HashMap<String, f64> = HashMap::new();
+        node_state_ratio_per_node_state_id.insert(String::from("state_A"), 0.0);
+        let wave_function = WaveFunction::new(vec![Node::new(String::from("node_1"), node_state_ratio_per_node_state_id, HashMap::new())], Vec::new());
+        assert_eq!("Node node_1 has node state ratios of only zero but at least one node state ratio must be positive.", wave_function.validate().err().unwrap());
+
+        let error_message = serde_json::from_str::<WaveFunction<String>>(&serde_json::to_string(&wave_function).unwrap()).err().unwrap().to_string();
+        assert!(error_message.starts_with("Node node_1 has node state ratios of only zero but at least one node state ratio must be positive."), "Unexpected error: {error_message}");
+
+        // json cannot represent NaN, so it is serialized as null and fails to load
+        let mut node_state_ratio_per_node_state_id: HashMap<String, f64> = HashMap::new();
+        node_state_ratio_per_node_state_id.insert(String::from("state_A"), f64::NAN);
+        let wave_function = WaveFunction::new(vec![Node::new(String::from("node_1"), node_state_ratio_per_node_state_id, HashMap::new())], Vec::new());
+        assert_eq!("Node node_1 has node state ratios \"state_A\": NaN but node state ratios must be finite and non-negative.", wave_function.validate().err().unwrap());
+        assert!(serde_json::from_str::<WaveFunction<String>>(&serde_json::to_string(&wave_function).unwrap()).is_err());
+
+        let error_message = serde_json::from_str::<WaveFunction<String>>(r#"{"nodes":[{"id":"node_1","node_state_collection_ids_per_neighbor_node_id":{},"node_state_ids":["state_A","state_B"],"node_state_ratios":[1.0]}],"node_state_collections":[]}"#).err().unwrap().to_string();
+        assert!(error_message.starts_with("Node node_1 has 2 node states but 1 node state ratios."), "Unexpected error: {error_message}");
+    }
+
+    #[test]
+    fn weighted_probability_normalize() {
+        init();
+
+        let mut node_state_probability_per_node_state = NodeStateProbability::get_weighted_probability(vec![(String::from("state_A"), 3.0), (String::from("state_B"), 1.0)]).unwrap();
+        NodeStateProbability::normalize(&mut node_state_probability_per_node_state);
+
+        assert_eq!(&0.75, node_state_probability_per_node_state.get("state_A").unwrap());
+        assert_eq!(&0.25, node_state_probability_per_node_state.get("state_B").unwrap());
+
+        let mut empty_node_state_probability_per_node_state: HashMap<String, f64> = HashMap::new();
+        NodeStateProbability::normalize(&mut empty_node_state_probability_per_node_state);
+        assert!(empty_node_state_probability_per_node_state.is_empty());
+    }
+
+    #[test]
+    fn weighted_probability_does_not_need_to_be_normalized() {
+        init();
+
+        let get_wave_function = |is_normalized: bool| -> WaveFunction<String> {
+            let mut node_state_probability_per_node_state = NodeStateProbability::get_weighted_probability(vec![(String::from("state_A"), 9.0), (String::from("state_B"), 1.0)]).unwrap();
+            if is_normalized {
+                NodeStateProbability::normalize(&mut node_state_probability_per_node_state);
+            }
+            WaveFunction::new(vec![Node::new(String::from("node_1"), node_state_probability_per_node_state, HashMap::new())], Vec::new())
+        };
+        let weighted_wave_function = get_wave_function(false);
+        let normalized_wave_function = get_wave_function(true);
+        weighted_wave_function.validate().unwrap();
+        normalized_wave_function.validate().unwrap();
+
+        // the same random seed chooses the same node state whether or not the weights sum to one
+        let mut state_a_total: u32 = 0;
+        for random_seed in 0..10000 {
+            let weighted_collapsed_wave_function = weighted_wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let normalized_collapsed_wave_function = normalized_wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            assert_eq!(weighted_collapsed_wave_function.node_state_per_node_id, normalized_collapsed_wave_function.node_state_per_node_id);
+            if weighted_collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap() == "state_A" {
+                state_a_total += 1;
+            }
+        }
+        assert!((8800..=9200).contains(&state_a_total), "The first node state was chosen {state_a_total} times instead of about 9000.");
+    }
+
+    #[test]
+    fn one_node_randomly_two_weighted_states_sequential() {
+        init();
+
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        let node_state_collections: Vec<NodeStateCollection<String>> = Vec::new();
+
+        let one_node_state_id: String = Uuid::new_v4().to_string();
+        let two_node_state_id: String = Uuid::new_v4().to_string();
+        let mut count_per_node_state_id: HashMap<&str, u32> = HashMap::new();
+        count_per_node_state_id.insert(&one_node_state_id, 0);
+        count_per_node_state_id.insert(&two_node_state_id, 0);
+
+        let node_id: String = Uuid::new_v4().to_string();
+
+        let mut node_state_probability_per_node_state = NodeStateProbability::get_weighted_probability(vec![(one_node_state_id.clone(), 9.0), (two_node_state_id.clone(), 1.0)]).unwrap();
+        NodeStateProbability::normalize(&mut node_state_probability_per_node_state);
+
+        nodes.push(Node::new(
+            node_id.clone(),
+            node_state_probability_per_node_state,
+            HashMap::new()
+        ));
+
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate().unwrap();
+
+        let mut random_instance = fastrand::Rng::new();
+
+        for _ in 0..100000 {
+            let random_seed = Some(random_instance.u64(..));
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(random_seed).collapse().unwrap();
+
+            let node_state_id: &str = collapsed_wave_function.node_state_per_node_id.get(&node_id).unwrap();
+            *count_per_node_state_id.get_mut(node_state_id).unwrap() += 1;
+        }
+
+        let one_count = *count_per_node_state_id.get(one_node_state_id.as_str()).unwrap();
+        assert!((89000..=91000).contains(&one_count), "The first node state was chosen {one_count} times instead of about 90000.");
+        let two_count = *count_per_node_state_id.get(two_node_state_id.as_str()).unwrap();
+        assert!((9000..=11000).contains(&two_count), "The second node state was chosen {two_count} times instead of about 10000.");
+    }
+
+    #[test]
+    fn one_node_ten_thousand_states_of_tiny_equal_probability() {
+        init();
+
+        let node_states_total: u32 = 10000;
+        let get_wave_function = || -> WaveFunction<u32> {
+            // each hashmap iterates in its own order, so only the order key of each node state decides its position
+            let node_state_probability_per_node_state: HashMap<u32, f64> = (0..node_states_total).map(|node_state| (node_state, 1e-5)).collect();
+            let wave_function = WaveFunction::new(vec![Node::new(String::from("node_1"), node_state_probability_per_node_state, HashMap::new())], Vec::new());
+            wave_function.validate().unwrap();
+            wave_function
+        };
+        let first_wave_function = get_wave_function();
+        let second_wave_function = get_wave_function();
+
+        for random_seed in 0..3 {
+            let collapsed_node_states: Vec<u32> = first_wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<u32>>(Some(random_seed))
+                .collapse_into_all()
+                .map(|collapsed_wave_function| collapsed_wave_function.unwrap().node_state_per_node_id["node_1"])
+                .collect();
+
+            // every node state is chosen exactly once
+            assert_eq!(node_states_total as usize, collapsed_node_states.len());
+            assert_eq!(node_states_total as usize, collapsed_node_states.iter().collect::<HashSet<&u32>>().len());
+
+            let reproduced_collapsed_node_states: Vec<u32> = second_wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<u32>>(Some(random_seed))
+                .collapse_into_all()
+                .map(|collapsed_wave_function| collapsed_wave_function.unwrap().node_state_per_node_id["node_1"])
+                .collect();
+            assert_eq!(collapsed_node_states, reproduced_collapsed_node_states);
+        }
+    }
+
+    fn assert_progress_is_reported<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, String>>(mut collapsable_wave_function: TCollapsableWaveFunction) {
+        let collapse_progresses: Rc<RefCell<Vec<CollapseProgress>>> = Rc::new(RefCell::new(Vec::new()));
+        let callback_collapse_progresses = collapse_progresses.clone();
+        collapsable_wave_function.set_progress_callback(1, Box::new(move |collapse_progress| {
+            callback_collapse_progresses.borrow_mut().push(collapse_progress);
+            ControlFlow::Continue(())
+        }));
+        collapsable_wave_function.collapse().unwrap();
+
+        let collapse_progresses = collapse_progresses.borrow();
+        assert!(collapse_progresses.len() >= 36);
+        for (collapse_progress_index, collapse_progress) in collapse_progresses.iter().enumerate() {
+            assert_eq!(36, collapse_progress.nodes_total);
+            assert!(collapse_progress.collapsed_nodes_total <= collapse_progress.nodes_total);
+            assert_eq!(collapse_progress_index as u64 + 1, collapse_progress.node_assignments_total);
+        }
+    }
+
+    fn assert_progress_callback_aborts<'a, TCollapsableWaveFunction: CollapsableWaveFunction<'a, String>>(mut collapsable_wave_function: TCollapsableWaveFunction) {
+        let mut reports_total: u32 = 0;
+        collapsable_wave_function.set_progress_callback(2, Box::new(move |collapse_progress| {
+            reports_total += 1;
+            assert_eq!(reports_total as u64 * 2, collapse_progress.node_assignments_total);
+            if reports_total == 3 {
+                ControlFlow::Break(())
+            }
+            else {
+                ControlFlow::Continue(())
+            }
+        }));
+        let error_message = collapsable_wave_function.collapse().err().unwrap();
+
+        assert!(error_message.starts_with("Collapse aborted by caller after 6 node assignments with "), "Unexpected error: {error_message}");
+        assert!(collapsable_wave_function.is_aborted());
+    }
+
+    #[test]
+    fn progress_callback_sequential() {
+        init();
+
+        let wave_function = get_three_color_grid_wave_function();
+        assert_progress_is_reported(wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)));
+        assert_progress_callback_aborts(wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)));
+    }
+
+    #[test]
+    fn progress_callback_accommodating() {
+        init();
+
+        let wave_function = get_three_color_grid_wave_function();
+        assert_progress_is_reported(wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(0)));
+        assert_progress_callback_aborts(wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(0)));
+    }
+
+    #[test]
+    fn progress_callback_acc_seq() {
+        init();
+
+        let wave_function = get_three_color_grid_wave_function();
+        assert_progress_is_reported(wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(Some(0)));
+        assert_progress_callback_aborts(wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(Some(0)));
+    }
+
+    #[test]
+    fn progress_callback_entropic() {
+        init();
+
+        let wave_function = get_three_color_grid_wave_function();
+        assert_progress_is_reported(wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(0)));
+        assert_progress_callback_aborts(wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(0)));
+    }
+
+    #[test]
+    fn progress_callback_sequential_counts_backtracks() {
+        init();
+
+        let wave_function = get_two_nodes_with_conflicting_state_requirements_wave_function();
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None);
+        let backtracks_totals: Rc<RefCell<Vec<u64>>> = Rc::new(RefCell::new(Vec::new()));
+        let callback_backtracks_totals = backtracks_totals.clone();
+        collapsable_wave_function.set_progress_callback(1, Box::new(move |collapse_progress| {
+            callback_backtracks_totals.borrow_mut().push(collapse_progress.backtracks_total);
+            ControlFlow::Continue(())
+        }));
+
+        assert_eq!("Cannot collapse wave function.", collapsable_wave_function.collapse().err().unwrap());
+        assert!(backtracks_totals.borrow().last().unwrap() > &0);
+    }
+
+    #[test]
+    fn progress_callback_sequential_reports_once_per_node() {
+        init();
+
+        // a path of nodes that never restrict each other, so that each node is collapsed exactly once
+        let nodes_total: usize = 100;
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B"), String::from("state_C")];
+        let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        for node_state_id in node_state_ids.iter() {
+            permitted_node_state_ids_per_node_state_id.insert(node_state_id.clone(), node_state_ids.clone());
+        }
+        let mut wave_function_builder: WaveFunctionBuilder<String> = WaveFunctionBuilder::new();
+        for index in 0..nodes_total {
+            wave_function_builder.add_node(&format!("node_{index}"), node_state_ids.clone());
+            if index != 0 {
+                wave_function_builder.add_bidirectional_constraint(&format!("node_{}", index - 1), &format!("node_{index}"), permitted_node_state_ids_per_node_state_id.clone());
+            }
+        }
+        let wave_function = wave_function_builder.build().unwrap();
+
+        let collapse_progresses: Rc<RefCell<Vec<CollapseProgress>>> = Rc::new(RefCell::new(Vec::new()));
+        let callback_collapse_progresses = collapse_progresses.clone();
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0));
+        collapsable_wave_function.set_progress_callback(1, Box::new(move |collapse_progress| {
+            callback_collapse_progresses.borrow_mut().push(collapse_progress);
+            ControlFlow::Continue(())
+        }));
+        let collapsed_wave_function = collapsable_wave_function.collapse().unwrap();
+        assert_eq!(nodes_total, collapsed_wave_function.node_state_per_node_id.len());
+
+        let collapse_progresses = collapse_progresses.borrow();
+        assert_eq!(nodes_total, collapse_progresses.len());
+        let mut current_node_ids: Vec<&str> = collapse_progresses.iter().map(|collapse_progress| collapse_progress.current_node_id.as_str()).collect();
+        current_node_ids.sort();
+        current_node_ids.dedup();
+        assert_eq!(nodes_total, current_node_ids.len());
+        for collapse_progress in collapse_progresses.iter() {
+            assert_eq!(nodes_total, collapse_progress.nodes_total);
+            assert_eq!(0, collapse_progress.backtracks_total);
+            assert_eq!(0, collapse_progress.restarts_total);
+        }
+        assert_eq!(nodes_total, collapse_progresses.last().unwrap().collapsed_nodes_total);
+
+        // the same wave function collapses into the same node states without a progress callback
+        assert_eq!(collapsed_wave_function, wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap());
+    }
+
+    // a three by three grid where orthogonal neighbors are in different node states, where each node knows its coordinates
+    fn get_grid_wave_function_with_coordinate_metadata() -> WaveFunction<String> {
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B"), String::from("state_C")];
+        let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        for node_state_id in node_state_ids.iter() {
+            permitted_node_state_ids_per_node_state_id.insert(node_state_id.clone(), node_state_ids.iter().filter(|other_node_state_id| *other_node_state_id != node_state_id).cloned().collect());
+        }
+        let mut wave_function_builder: WaveFunctionBuilder<String> = WaveFunctionBuilder::new();
+        for x in 0..3 {
+            for y in 0..3 {
+                let node_id = format!("node_{x}_{y}");
+                wave_function_builder.add_node(&node_id, node_state_ids.clone());
+                wave_function_builder.set_node_metadata(&node_id, serde_json::json!({ "x": x, "y": y }));
+                if x != 0 {
+                    wave_function_builder.add_bidirectional_constraint(&format!("node_{}_{y}", x - 1), &node_id, permitted_node_state_ids_per_node_state_id.clone());
+                }
+                if y != 0 {
+                    wave_function_builder.add_bidirectional_constraint(&format!("node_{x}_{}", y - 1), &node_id, permitted_node_state_ids_per_node_state_id.clone());
+                }
+            }
+        }
+        wave_function_builder.build().unwrap()
+    }
+
+    fn assert_collapsed_grid_by_coordinate_metadata(collapsed_wave_function: &CollapsedWaveFunction<String>) {
+        assert_eq!(9, collapsed_wave_function.node_metadata_per_node.len());
+        let mut node_state_per_coordinate: HashMap<(u64, u64), &String> = HashMap::new();
+        for (node_id, node_state) in collapsed_wave_function.node_state_per_node_id.iter() {
+            let metadata = collapsed_wave_function.node_metadata_per_node.get(node_id).unwrap();
+            node_state_per_coordinate.insert((metadata["x"].as_u64().unwrap(), metadata["y"].as_u64().unwrap()), node_state);
+        }
+        for x in 0..3 {
+            for y in 0..3 {
+                let node_state = node_state_per_coordinate.get(&(x, y)).unwrap();
+                if x != 0 {
+                    assert_ne!(node_state, node_state_per_coordinate.get(&(x - 1, y)).unwrap());
+                }
+                if y != 0 {
+                    assert_ne!(node_state, node_state_per_coordinate.get(&(x, y - 1)).unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn node_metadata_carried_into_collapsed_wave_function() {
+        init();
+
+        let wave_function = get_grid_wave_function_with_coordinate_metadata();
+        wave_function.validate().unwrap();
+
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap();
+        assert_collapsed_grid_by_coordinate_metadata(&collapsed_wave_function);
+        assert_eq!(Some(&serde_json::json!({ "x": 2, "y": 1 })), collapsed_wave_function.node_metadata_per_node.get("node_2_1"));
+
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap();
+        assert_collapsed_grid_by_coordinate_metadata(&collapsed_wave_function);
+
+        // the metadata survives serialization of both the wave function and the collapsed wave function
+        let loaded_wave_function: WaveFunction<String> = serde_json::from_str(&serde_json::to_string(&wave_function).unwrap()).unwrap();
+        let loaded_nodes = loaded_wave_function.get_nodes();
+        let node = loaded_nodes.iter().find(|node| node.id == "node_1_2").unwrap();
+        assert_eq!(Some(serde_json::json!({ "x": 1, "y": 2 })), node.metadata);
+
+        let deserialized_collapsed_wave_function: CollapsedWaveFunction<String> = serde_json::from_str(&serde_json::to_string(&collapsed_wave_function).unwrap()).unwrap();
+        assert_eq!(collapsed_wave_function, deserialized_collapsed_wave_function);
+        assert_eq!(collapsed_wave_function, CollapsedWaveFunction::from_bytes(&collapsed_wave_function.to_bytes()).unwrap());
+    }
+
+    #[test]
+    fn node_metadata_included_in_collapsed_node_states() {
+        init();
+
+        let wave_function = get_grid_wave_function_with_coordinate_metadata();
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0));
+        let collapsed_node_states = collapsable_wave_function.collapse_into_steps().unwrap();
+        for collapsed_node_state in collapsed_node_states.iter() {
+            let metadata = collapsed_node_state.metadata.as_ref().unwrap();
+            assert_eq!(format!("node_{}_{}", metadata["x"], metadata["y"]), collapsed_node_state.node_id);
+        }
+
+        let collapsed_wave_function = CollapsedWaveFunction::from_steps(&collapsed_node_states, collapsable_wave_function.get_random_seed()).unwrap();
+        assert_collapsed_grid_by_coordinate_metadata(&collapsed_wave_function);
+        assert_eq!(wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap(), collapsed_wave_function);
+    }
+
+    #[test]
+    fn node_metadata_for_missing_node() {
+        init();
+
+        let error_message = WaveFunctionBuilder::<String>::new()
+            .add_node("node_1", vec![String::from("state_A")])
+            .set_node_metadata("node_2", serde_json::json!("unknown"))
+            .build()
+            .err()
+            .unwrap();
+        assert_eq!("Metadata references node node_2 that does not exist.", error_message);
+    }
+
+    #[test]
+    fn collapsed_wave_function_bytes_round_trip() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("red"), String::from("green"), String::from("blue")];
+        let mut grid_builder = GridBuilder::new(60, 50, node_state_ids.clone());
+        for node_state_id in node_state_ids.iter() {
+            for other_node_state_id in node_state_ids.iter() {
+                if node_state_id != other_node_state_id {
+                    grid_builder
+                        .allow(node_state_id.clone(), Direction::Right, other_node_state_id.clone())
+                        .allow(node_state_id.clone(), Direction::Down, other_node_state_id.clone());
+                }
+            }
+        }
+        let wave_function = grid_builder.build().unwrap();
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap();
+        assert_eq!(3000, collapsed_wave_function.node_state_per_node_id.len());
+
+        let bytes = collapsed_wave_function.to_bytes();
+        assert!(bytes.len() < serde_json::to_string(&collapsed_wave_function).unwrap().len());
+        let decoded_collapsed_wave_function: CollapsedWaveFunction<String> = CollapsedWaveFunction::from_bytes(&bytes).unwrap();
+        assert_eq!(collapsed_wave_function, decoded_collapsed_wave_function);
+
+        #[cfg(feature = "file-io")]
+        {
+            let file = tempfile::NamedTempFile::new().unwrap();
+            let file_path: &str = file.path().to_str().unwrap();
+            collapsed_wave_function.save_to_file(file_path).unwrap();
+            let loaded_collapsed_wave_function: CollapsedWaveFunction<String> = CollapsedWaveFunction::load_from_file(file_path).unwrap();
+            file.close().unwrap();
+
+            assert_eq!(collapsed_wave_function, loaded_collapsed_wave_function);
+        }
+    }
+
+    #[test]
+    fn collapsed_wave_function_bytes_with_unknown_version() {
+        init();
+
+        let mut node_state_per_node_id: HashMap<String, String> = HashMap::new();
+        node_state_per_node_id.insert(String::from("node_1"), String::from("state_A"));
+        let mut bytes = CollapsedWaveFunction { node_state_per_node_id, random_seed: 0, node_metadata_per_node: HashMap::new() }.to_bytes();
+        bytes[0] = 0;
+
+        assert_eq!("Cannot decode collapsed wave function from bytes of version 0 when expecting version 3.", CollapsedWaveFunction::<String>::from_bytes(&bytes).err().unwrap());
+        assert_eq!("Cannot decode collapsed wave function from empty bytes.", CollapsedWaveFunction::<String>::from_bytes(&[]).err().unwrap());
+    }
+
+    #[test]
+    fn collapsed_three_nodes_round_trip_and_validate() {
+        init();
+
+        let wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        for random_seed in 0..10 {
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed));
+            let collapsed_node_states = collapsable_wave_function.collapse_into_steps().unwrap();
+            let collapsed_wave_function = CollapsedWaveFunction::from_steps(&collapsed_node_states, collapsable_wave_function.get_random_seed()).unwrap();
+            wave_function.validate_collapsed(&collapsed_wave_function).unwrap();
+
+            let serialized_collapsed_node_states = serde_json::to_string(&collapsed_node_states).unwrap();
+            let deserialized_collapsed_node_states: Vec<CollapsedNodeState<String>> = serde_json::from_str(&serialized_collapsed_node_states).unwrap();
+            assert_eq!(collapsed_node_states, deserialized_collapsed_node_states);
+
+            let serialized_collapsed_wave_function = serde_json::to_string(&collapsed_wave_function).unwrap();
+            let deserialized_collapsed_wave_function: CollapsedWaveFunction<String> = serde_json::from_str(&serialized_collapsed_wave_function).unwrap();
+            assert_eq!(collapsed_wave_function, deserialized_collapsed_wave_function);
+            wave_function.validate_collapsed(&deserialized_collapsed_wave_function).unwrap();
+
+            #[cfg(feature = "file-io")]
+            {
+                let file = tempfile::NamedTempFile::new().unwrap();
+                let file_path: &str = file.path().to_str().unwrap();
+                collapsed_wave_function.save_to_file(file_path).unwrap();
+                let loaded_collapsed_wave_function: CollapsedWaveFunction<String> = CollapsedWaveFunction::load_from_file(file_path).unwrap();
+                file.close().unwrap();
+
+                assert_eq!(collapsed_wave_function, loaded_collapsed_wave_function);
+                wave_function.validate_collapsed(&loaded_collapsed_wave_function).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn validate_collapsed_reports_first_violation() {
+        init();
+
+        let wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        let get_collapsed_wave_function = |node_states: &[(&str, &str)]| -> CollapsedWaveFunction<String> {
+            CollapsedWaveFunction {
+                node_state_per_node_id: node_states.iter().map(|(node_id, node_state_id)| (String::from(*node_id), String::from(*node_state_id))).collect(),
+                random_seed: 0,
+                node_metadata_per_node: HashMap::new()
+            }
+        };
+
+        wave_function.validate_collapsed(&get_collapsed_wave_function(&[("node_1", "state_A"), ("node_2", "state_B"), ("node_3", "state_C")])).unwrap();
+        assert_eq!("Node node_1 in node state \"state_B\" does not permit neighbor node node_3 to be in node state \"state_B\".", wave_function.validate_collapsed(&get_collapsed_wave_function(&[("node_1", "state_B"), ("node_2", "state_A"), ("node_3", "state_B")])).err().unwrap());
+        assert_eq!("Node node_3 is not collapsed.", wave_function.validate_collapsed(&get_collapsed_wave_function(&[("node_1", "state_A"), ("node_2", "state_B")])).err().unwrap());
+        assert_eq!("Node node_2 cannot be in node state \"state_D\".", wave_function.validate_collapsed(&get_collapsed_wave_function(&[("node_1", "state_A"), ("node_2", "state_D"), ("node_3", "state_C")])).err().unwrap());
+        assert_eq!("Collapsed node node_4 does not exist in main list of nodes.", wave_function.validate_collapsed(&get_collapsed_wave_function(&[("node_1", "state_A"), ("node_2", "state_B"), ("node_3", "state_C"), ("node_4", "state_A")])).err().unwrap());
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        let nodes = get_unconnected_nodes(2, NodeStateProbability::get_equal_probability(&node_state_ids));
+        let node_ids: Vec<String> = nodes.iter().map(|node| node.get_id()).collect();
+        let wave_function = WaveFunction::new_with_global_constraints(nodes, Vec::new(), vec![
+            GlobalConstraint::AtMostN { node_ids, state: String::from("state_A"), n: 1 }
+        ]).unwrap();
+        wave_function.validate_collapsed(&get_collapsed_wave_function(&[("node_0", "state_A"), ("node_1", "state_B")])).unwrap();
+        assert_eq!("Global constraint requires between 0 and 1 nodes to be in node state \"state_A\" but 2 are.", wave_function.validate_collapsed(&get_collapsed_wave_function(&[("node_0", "state_A"), ("node_1", "state_A")])).err().unwrap());
+    }
+
+    #[test]
+    fn node_state_collections_for_the_same_node_state_must_all_be_satisfied() {
+        init();
+
+        // node_1 in state_A permits node_2 to be in state_A by one node state collection and in state_B by another, so together they permit nothing
+        let get_wave_function = |node_1_node_state_ids: Vec<String>| -> WaveFunction<String> {
+            let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+            let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+            node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_2"), vec![String::from("c1"), String::from("c2")]);
+            let nodes: Vec<Node<String>> = vec![
+                Node::new(String::from("node_1"), NodeStateProbability::get_equal_probability(&node_1_node_state_ids), node_state_collection_ids_per_neighbor_node_id),
+                Node::new(String::from("node_2"), NodeStateProbability::get_equal_probability(&node_state_ids), HashMap::new())
+            ];
+            let node_state_collections: Vec<NodeStateCollection<String>> = vec![
+                NodeStateCollection::new(String::from("c1"), String::from("state_A"), vec![String::from("state_A")]),
+                NodeStateCollection::new(String::from("c2"), String::from("state_A"), vec![String::from("state_B")])
+            ];
+            WaveFunction::new(nodes, node_state_collections)
+        };
+
+        let wave_function = get_wave_function(vec![String::from("state_A"), String::from("state_B")]);
+        wave_function.validate().unwrap();
+        wave_function.validate_satisfiability().unwrap();
+        for random_seed in 0..40 {
+            let collapsed_wave_functions: Vec<CollapsedWaveFunction<String>> = vec![
+                wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap(),
+                wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap(),
+                wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap()
+            ];
+            for collapsed_wave_function in collapsed_wave_functions.iter() {
+                assert_eq!("state_B", collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap());
+                wave_function.validate_collapsed(collapsed_wave_function).unwrap();
+            }
+        }
+
+        let mut fixed_node_state_per_node_id: HashMap<String, String> = HashMap::new();
+        fixed_node_state_per_node_id.insert(String::from("node_1"), String::from("state_A"));
+        assert_eq!("Fixed node node_1 in node state \"state_A\" does not permit any possible node state of neighbor node node_2.", wave_function.get_collapsable_wave_function_with_fixed_states::<SequentialCollapsableWaveFunction<String>>(None, &fixed_node_state_per_node_id).err().unwrap());
+
+        // without state_B the node cannot be in any node state
+        let wave_function = get_wave_function(vec![String::from("state_A")]);
+        wave_function.validate().unwrap();
+        assert!(wave_function.validate_satisfiability().is_err());
+        assert!(wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().is_err());
+    }
+
+    // a ten by ten grid where orthogonal neighbors are in different node states, with enough node states that any node can always differ from its neighbors
+    fn get_five_color_grid_wave_function() -> WaveFunction<String> {
+        let node_state_ids: Vec<String> = (0..5).map(|index| format!("state_{index}")).collect();
+        let permitted_node_state_ids = node_state_ids.clone();
+        grid::build_2d_grid(10, 10, node_state_ids, AdjacencyKind::Four, move |node_state_id, _| {
+            permitted_node_state_ids.iter().filter(|other_node_state_id| *other_node_state_id != node_state_id).cloned().collect()
+        }).unwrap()
+    }
+
+    fn get_five_color_grid_rows(collapsed_wave_function: &CollapsedWaveFunction<String>) -> Vec<String> {
+        let node_state_per_y_per_x = grid::get_2d_grid(collapsed_wave_function, 10, 10).unwrap();
+        (0..10)
+            .map(|y| (0..10).map(|x| node_state_per_y_per_x[x][y].trim_start_matches("state_")).collect())
+            .collect()
+    }
+
+    #[test]
+    fn five_color_grid_collapses_identically_for_fixed_random_seed() {
+        init();
+
+        let wave_function = get_five_color_grid_wave_function();
+        let collapsed_wave_functions = [
+            wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap(),
+            wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap(),
+            wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap(),
+            wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap()
+        ];
+        // these were collapsed prior to the node states being interned within the collapsable wave functions
+        let expected_rows_per_collapsable_wave_function = [
+            ["3140313123", "0431031314", "1342123132", "2410304020", "4024230434", "3402103020", "0241214241", "2102320103", "3013012420", "1324141032"],
+            ["3014313123", "0423131432", "1342042314", "2431303042", "4014020424", "1342102342", "3421214021", "2102320143", "3013012420", "1324141032"],
+            ["3104313423", "1431432132", "0342021314", "2431303042", "1014020403", "4302103024", "3041240212", "0212314141", "3043032423", "1324141032"],
+            ["3014313423", "0402132134", "1343021312", "2412103040", "1021230423", "4342404302", "3424142021", "0212314143", "3143102420", "1320434132"]
+        ];
+        for (collapsed_wave_function, expected_rows) in collapsed_wave_functions.iter().zip(expected_rows_per_collapsable_wave_function) {
+            wave_function.validate_collapsed(collapsed_wave_function).unwrap();
+            assert_eq!(expected_rows.to_vec(), get_five_color_grid_rows(collapsed_wave_function));
+        }
+    }
+
+    // each node of the path can be in one of the node states and each neighbor must be within the band of it
+    fn get_banded_path_wave_function(nodes_total: usize, node_states_total: u32, band: u32) -> WaveFunction<u32> {
+        let mut wave_function_builder: WaveFunctionBuilder<u32> = WaveFunctionBuilder::new();
+        for node_state_id in 0..node_states_total {
+            wave_function_builder.permit_only(node_state_id, (node_state_id.saturating_sub(band)..(node_state_id + band + 1).min(node_states_total)).collect());
+        }
+        for node_index in 0..nodes_total {
+            wave_function_builder.add_node(&format!("node_{node_index}"), (0..node_states_total).collect());
+            if node_index != 0 {
+                wave_function_builder.add_neighbors(&format!("node_{}", node_index - 1), &format!("node_{node_index}"));
+            }
+        }
+        wave_function_builder.build().unwrap()
+    }
+
+    #[test]
+    fn entropic_propagation_of_one_thousand_node_states_per_node() {
+        init();
+
+        let wave_function = get_banded_path_wave_function(20, 1000, 3);
+        for node_selection_heuristic in [NodeSelectionHeuristic::ShannonEntropy, NodeSelectionHeuristic::MinimumRemainingStates] {
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<u32>>(Some(0));
+            collapsable_wave_function.set_node_selection_heuristic(node_selection_heuristic);
+            let collapsed_node_states = collapsable_wave_function.collapse_into_steps().unwrap();
+            let collapsed_wave_function = CollapsedWaveFunction::from_steps(&collapsed_node_states, 0).unwrap();
+            wave_function.validate_collapsed(&collapsed_wave_function).unwrap();
+            // propagation must restrict each node to exactly the same node states regardless of how the masks are combined
+            let node_ids: Vec<String> = collapsed_node_states.iter().map(|collapsed_node_state| collapsed_node_state.node_id.clone()).collect();
+            assert_eq!((0..20).rev().map(|node_index| format!("node_{node_index}")).collect::<Vec<String>>(), node_ids);
+            let node_state_ids: Vec<u32> = (0..20).map(|node_index| collapsed_wave_function.node_state_per_node_id[&format!("node_{node_index}")]).collect();
+            assert_eq!(vec![578, 579, 576, 577, 578, 576, 575, 577, 574, 573, 570, 569, 568, 565, 566, 563, 561, 561, 559, 556], node_state_ids);
+        }
+    }
+
+    fn get_grid_distance(node_id: &str, other_node_id: &str) -> usize {
+        let coordinate: Vec<usize> = node_id.split('_').map(|value| value.parse().unwrap()).collect();
+        let other_coordinate: Vec<usize> = other_node_id.split('_').map(|value| value.parse().unwrap()).collect();
+        coordinate[0].abs_diff(other_coordinate[0]) + coordinate[1].abs_diff(other_coordinate[1])
+    }
+
+    #[test]
+    fn recollapse_region_after_editing_one_node() {
+        init();
+
+        let wave_function = get_five_color_grid_wave_function();
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap();
+        wave_function.validate_collapsed(&collapsed_wave_function).unwrap();
+
+        // edit an interior node into the node state of its right neighbor, which now conflicts with that neighbor
+        let changed_node_id = String::from("5_5");
+        let mut edited_collapsed_wave_function = collapsed_wave_function.clone();
+        let right_node_state_id = collapsed_wave_function.node_state_per_node_id.get("6_5").unwrap().clone();
+        edited_collapsed_wave_function.node_state_per_node_id.insert(changed_node_id.clone(), right_node_state_id);
+        assert!(wave_function.validate_collapsed(&edited_collapsed_wave_function).is_err());
+
+        for random_seed in 0..10 {
+            let recollapsed_region = wave_function.recollapse_region::<SequentialCollapsableWaveFunction<String>>(&edited_collapsed_wave_function, std::slice::from_ref(&changed_node_id), 1, Some(random_seed)).unwrap();
+            assert_eq!(1, recollapsed_region.radius);
+            assert!(!recollapsed_region.is_region_grown);
+            assert!(recollapsed_region.node_state_per_node_id.contains_key("6_5"));
+            for node_id in recollapsed_region.node_state_per_node_id.keys() {
+                assert_eq!(1, get_grid_distance(node_id, &changed_node_id), "Node {node_id} was recollapsed.");
+            }
+
+            let mut recollapsed_wave_function = edited_collapsed_wave_function.clone();
+            recollapsed_wave_function.node_state_per_node_id.extend(recollapsed_region.node_state_per_node_id);
+            wave_function.validate_collapsed(&recollapsed_wave_function).unwrap();
+            assert_eq!(edited_collapsed_wave_function.node_state_per_node_id.get(&changed_node_id), recollapsed_wave_function.node_state_per_node_id.get(&changed_node_id));
+        }
+
+        // only the changed node is in the region at radius zero, so the region has to grow to its neighbors
+        let recollapsed_region = wave_function.recollapse_region::<SequentialCollapsableWaveFunction<String>>(&edited_collapsed_wave_function, std::slice::from_ref(&changed_node_id), 0, Some(0)).unwrap();
+        assert_eq!(1, recollapsed_region.radius);
+        assert!(recollapsed_region.is_region_grown);
+
+        // an edit that does not conflict leaves every other node as it was
+        let recollapsed_region = wave_function.recollapse_region::<SequentialCollapsableWaveFunction<String>>(&collapsed_wave_function, std::slice::from_ref(&changed_node_id), 0, Some(0)).unwrap();
+        assert!(recollapsed_region.node_state_per_node_id.is_empty());
+        assert!(!recollapsed_region.is_region_grown);
+
+        assert_eq!("Changed node 10_10 does not exist in main list of nodes.", wave_function.recollapse_region::<SequentialCollapsableWaveFunction<String>>(&collapsed_wave_function, &[String::from("10_10")], 1, Some(0)).err().unwrap());
+        let mut incomplete_collapsed_wave_function = collapsed_wave_function.clone();
+        incomplete_collapsed_wave_function.node_state_per_node_id.remove("0_0");
+        assert_eq!("Previous collapsed wave function does not contain node 0_0.", wave_function.recollapse_region::<SequentialCollapsableWaveFunction<String>>(&incomplete_collapsed_wave_function, std::slice::from_ref(&changed_node_id), 1, Some(0)).err().unwrap());
+    }
+
+    #[test]
+    fn three_nodes_as_dense_neighbors_all_different_states_anonymous() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B"), String::from("state_C")];
+
+        // each node state collection restricts the neighbor to the other node states
+        let mut anonymous_node_state_collections: Vec<AnonymousNodeStateCollection<String>> = Vec::new();
+        for node_state_id in node_state_ids.iter() {
+            let other_node_state_ids: Vec<String> = node_state_ids.iter().filter(|other_node_state_id| *other_node_state_id != node_state_id).cloned().collect();
+            anonymous_node_state_collections.push(AnonymousNodeStateCollection::new(node_state_id.clone(), other_node_state_ids));
+        }
+
+        let mut anonymous_nodes: Vec<AnonymousNode<String>> = Vec::new();
+        for node_index in 0..3 {
+            let mut node_state_collection_indexes_per_neighbor_node_index: HashMap<usize, Vec<usize>> = HashMap::new();
+            for neighbor_node_index in 0..3 {
+                if neighbor_node_index != node_index {
+                    node_state_collection_indexes_per_neighbor_node_index.insert(neighbor_node_index, vec![0, 1, 2]);
+                }
+            }
+            anonymous_nodes.push(AnonymousNode::new(NodeStateProbability::get_equal_probability(&node_state_ids), node_state_collection_indexes_per_neighbor_node_index));
+        }
+
+        let (wave_function, node_ids) = WaveFunction::new_anonymous(anonymous_nodes, anonymous_node_state_collections).unwrap();
+        wave_function.validate().unwrap();
+        assert_eq!(3, node_ids.len());
+
+        for random_seed in 0..20 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let mut collapsed_node_state_ids: Vec<&String> = node_ids.iter().map(|node_id| collapsed_wave_function.node_state_per_node_id.get(node_id).unwrap()).collect();
+            collapsed_node_state_ids.sort();
+            collapsed_node_state_ids.dedup();
+            assert_eq!(3, collapsed_node_state_ids.len());
+        }
+    }
+
+    #[test]
+    fn anonymous_node_with_out_of_range_indexes() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A")];
+        let anonymous_node_state_collections: Vec<AnonymousNodeStateCollection<String>> = vec![AnonymousNodeStateCollection::new(String::from("state_A"), node_state_ids.clone())];
+
+        let mut node_state_collection_indexes_per_neighbor_node_index: HashMap<usize, Vec<usize>> = HashMap::new();
+        node_state_collection_indexes_per_neighbor_node_index.insert(1, vec![0]);
+        let anonymous_nodes: Vec<AnonymousNode<String>> = vec![AnonymousNode::new(NodeStateProbability::get_equal_probability(&node_state_ids), node_state_collection_indexes_per_neighbor_node_index)];
+        let error_message = WaveFunction::new_anonymous(anonymous_nodes, anonymous_node_state_collections.clone()).err().unwrap();
+        assert_eq!("Anonymous node 0 references neighbor node index 1 but only 1 nodes exist.", error_message);
+
+        let mut node_state_collection_indexes_per_neighbor_node_index: HashMap<usize, Vec<usize>> = HashMap::new();
+        node_state_collection_indexes_per_neighbor_node_index.insert(0, vec![1]);
+        let anonymous_nodes: Vec<AnonymousNode<String>> = vec![AnonymousNode::new(NodeStateProbability::get_equal_probability(&node_state_ids), node_state_collection_indexes_per_neighbor_node_index)];
+        let error_message = WaveFunction::new_anonymous(anonymous_nodes, anonymous_node_state_collections).err().unwrap();
+        assert_eq!("Anonymous node 0 references node state collection index 1 but only 1 node state collections exist.", error_message);
+    }
+
+    #[test]
+    fn identical_constraints_to_neighbors_share_one_node_state_collection() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        let mut nodes: Vec<Node<String>> = (0..3)
+            .map(|node_index| Node::new(format!("node_{node_index}"), NodeStateProbability::get_equal_probability(&node_state_ids), HashMap::new()))
+            .collect();
+
+        // the middle node must differ from the first and last node
+        let mut node_state_collection_ids: Vec<String> = Vec::new();
+        for (node_index, neighbor_node_id) in [(0, "node_1"), (2, "node_1"), (1, "node_0"), (1, "node_2")] {
+            for (node_state_id, other_node_state_id) in [("state_A", "state_B"), ("state_B", "state_A")] {
+                node_state_collection_ids.push(nodes[node_index].add_constraint_to_neighbor(neighbor_node_id, String::from(node_state_id), vec![String::from(other_node_state_id)]));
+            }
+        }
+        for node_state_collection_ids_chunk in node_state_collection_ids.chunks(2) {
+            assert_eq!(node_state_collection_ids[0..2], *node_state_collection_ids_chunk);
+        }
+        assert_ne!(node_state_collection_ids[0], node_state_collection_ids[1]);
+
+        // adding the same constraint again does not reference it twice
+        assert_eq!(node_state_collection_ids[0], nodes[0].add_constraint_to_neighbor("node_1", String::from("state_A"), vec![String::from("state_B")]));
+        assert_eq!(node_state_collection_ids[0..2].to_vec(), nodes[0].node_state_collection_ids_per_neighbor_node_id["node_1"]);
+
+        // the order of the permitted node states is part of the contents
+        let first_node_state_collection_id = AnonymousNodeStateCollection::new(String::from("state_A"), node_state_ids.clone()).get_id();
+        let second_node_state_collection_id = AnonymousNodeStateCollection::new(String::from("state_A"), node_state_ids.iter().rev().cloned().collect()).get_id();
+        assert_ne!(first_node_state_collection_id, second_node_state_collection_id);
+
+        let wave_function = WaveFunction::new(nodes, vec![NodeStateCollection::new(String::from("unused"), String::from("state_A"), node_state_ids.clone())]);
+        wave_function.validate().unwrap();
+        let node_state_collection_ids_per_id: Vec<String> = wave_function.get_node_state_collections().into_iter().map(|node_state_collection| node_state_collection.id).collect();
+        assert_eq!(vec![String::from("unused"), node_state_collection_ids[0].clone(), node_state_collection_ids[1].clone()], node_state_collection_ids_per_id);
+        assert!(wave_function.get_nodes().iter().all(|node| node.anonymous_node_state_collections.is_empty()));
+
+        for random_seed in 0..10 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let middle_node_state_id = &collapsed_wave_function.node_state_per_node_id["node_1"];
+            assert_ne!(middle_node_state_id, &collapsed_wave_function.node_state_per_node_id["node_0"]);
+            assert_ne!(middle_node_state_id, &collapsed_wave_function.node_state_per_node_id["node_2"]);
+        }
+    }
+
+    #[test]
+    fn anonymous_node_state_collections_with_colliding_ids_are_kept_apart() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        let mut nodes: Vec<Node<String>> = (0..2)
+            .map(|node_index| Node::new_with_ordered_node_states(format!("node_{node_index}"), node_state_ids.iter().map(|node_state_id| (node_state_id.clone(), 1.0)).collect(), HashMap::new()))
+            .collect();
+
+        // the id is derived from the contents by a fixed hash
+        let node_state_collection_id = AnonymousNodeStateCollection::new(String::from("state_A"), vec![String::from("state_B")]).get_id();
+        assert_eq!("anonymous_29a50c86b819f35df3f74e15b6942b1b", node_state_collection_id);
+
+        // an id already taken within the node by different contents is followed by a numbered suffix
+        nodes[0].anonymous_node_state_collections.push((node_state_collection_id.clone(), AnonymousNodeStateCollection::new(String::from("state_B"), vec![String::from("state_B")])));
+        nodes[0].node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_1"), vec![node_state_collection_id.clone()]);
+        let suffixed_node_state_collection_id = nodes[0].add_constraint_to_neighbor("node_1", String::from("state_A"), vec![String::from("state_B")]);
+        assert_eq!(format!("{node_state_collection_id}_1"), suffixed_node_state_collection_id);
+        assert_eq!(suffixed_node_state_collection_id, nodes[0].add_constraint_to_neighbor("node_1", String::from("state_A"), vec![String::from("state_B")]));
+
+        // an id already taken by a provided node state collection with different contents is replaced by the id derived from the contents, or else the next unique id
+        let other_node_state_collection_id = AnonymousNodeStateCollection::new(String::from("state_B"), vec![String::from("state_B")]).get_id();
+        assert_eq!(node_state_collection_id, nodes[1].add_constraint_to_neighbor("node_0", String::from("state_A"), vec![String::from("state_B")]));
+        let wave_function = WaveFunction::new(nodes, vec![NodeStateCollection::new(node_state_collection_id.clone(), String::from("state_A"), vec![String::from("state_A")])]);
+        wave_function.validate().unwrap();
+        let node_state_collections: Vec<(String, String, Vec<String>)> = wave_function.get_node_state_collections()
+            .into_iter()
+            .map(|node_state_collection| (node_state_collection.id, node_state_collection.node_state_id, node_state_collection.node_state_ids))
+            .collect();
+        assert_eq!(vec![
+            (node_state_collection_id.clone(), String::from("state_A"), vec![String::from("state_A")]),
+            (other_node_state_collection_id.clone(), String::from("state_B"), vec![String::from("state_B")]),
+            (suffixed_node_state_collection_id.clone(), String::from("state_A"), vec![String::from("state_B")])
+        ], node_state_collections);
+        assert_eq!(vec![other_node_state_collection_id, suffixed_node_state_collection_id.clone()], wave_function.get_nodes()[0].node_state_collection_ids_per_neighbor_node_id["node_1"]);
+        assert_eq!(vec![suffixed_node_state_collection_id], wave_function.get_nodes()[1].node_state_collection_ids_per_neighbor_node_id["node_0"]);
+
+        // each node restricts its neighbor by the contents that it added, where the first node in either node state leaves only state_B for the second node
+        for random_seed in 0..10 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            wave_function.validate_collapsed(&collapsed_wave_function).unwrap();
+            assert_eq!("state_B", collapsed_wave_function.node_state_per_node_id["node_1"]);
+        }
+    }
+
+    #[test]
+    fn chain_without_immediate_repeats() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B"), String::from("state_C")];
+        let forward_node_state_collections: Vec<NodeStateCollection<String>> = node_state_ids
+            .iter()
+            .map(|node_state_id| NodeStateCollection::new_forbidden(format!("not_{node_state_id}"), node_state_id.clone(), vec![node_state_id.clone()]))
+            .collect();
+        let wave_function = WaveFunction::new_chain(20, NodeStateProbability::get_equal_probability(&node_state_ids), forward_node_state_collections, true).unwrap();
+
+        let nodes = wave_function.get_nodes();
+        assert_eq!(20, nodes.len());
+        for (node_index, node) in nodes.iter().enumerate() {
+            assert_eq!(format!("chain_{node_index}"), node.id);
+            let mut neighbor_node_ids: Vec<&String> = node.node_state_collection_ids_per_neighbor_node_id.keys().collect();
+            neighbor_node_ids.sort();
+            let mut expected_neighbor_node_ids: Vec<String> = Vec::new();
+            if node_index != 0 {
+                expected_neighbor_node_ids.push(format!("chain_{}", node_index - 1));
+            }
+            if node_index != 19 {
+                expected_neighbor_node_ids.push(format!("chain_{}", node_index + 1));
+            }
+            expected_neighbor_node_ids.sort();
+            assert_eq!(expected_neighbor_node_ids.iter().collect::<Vec<&String>>(), neighbor_node_ids);
+        }
+
+        for random_seed in 0..10 {
+            let collapsed_wave_functions = [
+                wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap(),
+                wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap(),
+                wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap()
+            ];
+            for collapsed_wave_function in collapsed_wave_functions.iter() {
+                for node_index in 1..20 {
+                    let previous_node_state_id = collapsed_wave_function.node_state_per_node_id.get(&format!("chain_{}", node_index - 1)).unwrap();
+                    let node_state_id = collapsed_wave_function.node_state_per_node_id.get(&format!("chain_{node_index}")).unwrap();
+                    assert_ne!(previous_node_state_id, node_state_id);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chain_with_leaps_followed_by_steps() {
+        init();
+
+        // each node state is a pitch along with whether the pitch was reached by a leap
+        let mut node_state_ids: Vec<(u8, bool)> = Vec::new();
+        for pitch in 0..8 {
+            node_state_ids.push((pitch, false));
+            node_state_ids.push((pitch, true));
+        }
+        let forward_node_state_collections: Vec<NodeStateCollection<(u8, bool)>> = node_state_ids
+            .iter()
+            .map(|(pitch, is_leap)| {
+                let permitted_node_state_ids: Vec<(u8, bool)> = node_state_ids
+                    .iter()
+                    .filter(|(next_pitch, is_next_leap)| {
+                        let interval = pitch.abs_diff(*next_pitch);
+                        interval != 0 && *is_next_leap == (interval > 2) && !(*is_leap && *is_next_leap)
+                    })
+                    .cloned()
+                    .collect();
+                NodeStateCollection::new(format!("from_{pitch}_{is_leap}"), (*pitch, *is_leap), permitted_node_state_ids)
+            })
+            .collect();
+        let wave_function = WaveFunction::new_chain(32, NodeStateProbability::get_equal_probability(&node_state_ids), forward_node_state_collections, true).unwrap();
+
+        for random_seed in 0..10 {
+            let collapsed_wave_functions = [
+                wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<(u8, bool)>>(Some(random_seed)).collapse().unwrap(),
+                wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<(u8, bool)>>(Some(random_seed)).collapse().unwrap()
+            ];
+            for collapsed_wave_function in collapsed_wave_functions.iter() {
+                let node_state_ids: Vec<&(u8, bool)> = (0..32)
+                    .map(|node_index| collapsed_wave_function.node_state_per_node_id.get(&format!("chain_{node_index}")).unwrap())
+                    .collect();
+                for node_index in 1..32 {
+                    let (previous_pitch, is_previous_leap) = node_state_ids[node_index - 1];
+                    let (pitch, is_leap) = node_state_ids[node_index];
+                    let interval = previous_pitch.abs_diff(*pitch);
+                    assert_ne!(0, interval);
+                    assert_eq!(*is_leap, interval > 2);
+                    if *is_previous_leap {
+                        assert!(interval <= 2, "Leap to chain_{} was followed by an interval of {interval}.", node_index - 1);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn chain_that_is_not_bidirectional() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        let forward_node_state_collections: Vec<NodeStateCollection<String>> = vec![
+            NodeStateCollection::new(String::from("A_to_B"), String::from("state_A"), vec![String::from("state_B")]),
+            NodeStateCollection::new(String::from("B_to_A"), String::from("state_B"), vec![String::from("state_A")])
+        ];
+        let wave_function = WaveFunction::new_chain(3, NodeStateProbability::get_equal_probability(&node_state_ids), forward_node_state_collections, false).unwrap();
+
+        assert_eq!(2, wave_function.get_node_state_collections().len());
+        let nodes = wave_function.get_nodes();
+        assert_eq!(vec![String::from("chain_1")], nodes[0].node_state_collection_ids_per_neighbor_node_id.keys().cloned().collect::<Vec<String>>());
+        assert_eq!(vec![String::from("chain_2")], nodes[1].node_state_collection_ids_per_neighbor_node_id.keys().cloned().collect::<Vec<String>>());
+        assert!(nodes[2].node_state_collection_ids_per_neighbor_node_id.is_empty());
+
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap();
+        assert_ne!(collapsed_wave_function.node_state_per_node_id.get("chain_0"), collapsed_wave_function.node_state_per_node_id.get("chain_1"));
+        assert_ne!(collapsed_wave_function.node_state_per_node_id.get("chain_1"), collapsed_wave_function.node_state_per_node_id.get("chain_2"));
+    }
+
+    #[test]
+    fn chain_without_nodes() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A")];
+        let error_message = WaveFunction::new_chain(0, NodeStateProbability::get_equal_probability(&node_state_ids), Vec::new(), true).err().unwrap();
+        assert_eq!("A chain must have at least one node.", error_message);
+    }
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, serde::Serialize, serde::Deserialize)]
+    enum Terrain {
+        Grass,
+        Sand,
+        Water
+    }
+
+    #[test]
+    fn map_states_of_chain_into_enum_and_back() {
+        init();
+
+        let terrain_per_node_state_id: HashMap<String, Terrain> = HashMap::from([
+            (String::from("grass"), Terrain::Grass),
+            (String::from("sand"), Terrain::Sand),
+            (String::from("water"), Terrain::Water)
+        ]);
+        let node_state_ids: Vec<String> = vec![String::from("grass"), String::from("sand"), String::from("water")];
+        // sand must be between grass and water
+        let forward_node_state_collections: Vec<NodeStateCollection<String>> = vec![
+            NodeStateCollection::new(String::from("after_grass"), String::from("grass"), vec![String::from("grass"), String::from("sand")]),
+            NodeStateCollection::new(String::from("after_sand"), String::from("sand"), node_state_ids.clone()),
+            NodeStateCollection::new(String::from("after_water"), String::from("water"), vec![String::from("sand"), String::from("water")])
+        ];
+        let wave_function = WaveFunction::new_chain(20, NodeStateProbability::get_equal_probability(&node_state_ids), forward_node_state_collections, true).unwrap();
+        let terrain_wave_function = wave_function.map_states(|node_state_id| terrain_per_node_state_id[node_state_id]);
+        terrain_wave_function.validate().unwrap();
+
+        for random_seed in 0..10 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let terrain_collapsed_wave_function = collapsed_wave_function.map_states(|node_state_id| terrain_per_node_state_id[node_state_id]);
+
+            // the converted wave function collapses into the converted collapsed wave function
+            assert_eq!(terrain_collapsed_wave_function, terrain_wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<Terrain>>(Some(random_seed)).collapse().unwrap());
+            terrain_wave_function.validate_collapsed(&terrain_collapsed_wave_function).unwrap();
+
+            // converting back is lossless since the conversion is bijective
+            let node_state_id_per_terrain: HashMap<Terrain, String> = terrain_per_node_state_id.iter().map(|(node_state_id, terrain)| (*terrain, node_state_id.clone())).collect();
+            assert_eq!(collapsed_wave_function, terrain_collapsed_wave_function.map_states(|terrain| node_state_id_per_terrain[terrain].clone()));
+            assert_eq!(serde_json::to_string(&collapsed_wave_function).unwrap(), serde_json::to_string(&terrain_collapsed_wave_function.map_states(|terrain| node_state_id_per_terrain[terrain].clone())).unwrap());
+
+            let node_state_per_node_index: HashMap<usize, String> = collapsed_wave_function.map_nodes(|node_id| node_id.trim_start_matches("chain_").parse().unwrap());
+            let terrain_per_node_index: HashMap<usize, Terrain> = collapsed_wave_function.clone().into_typed(|node_id| node_id.trim_start_matches("chain_").parse().unwrap(), |node_state_id| terrain_per_node_state_id[&node_state_id]);
+            assert_eq!(20, terrain_per_node_index.len());
+            for node_index in 0..20 {
+                assert_eq!(terrain_per_node_state_id[&node_state_per_node_index[&node_index]], terrain_per_node_index[&node_index]);
+                if node_index != 0 {
+                    let terrains = [terrain_per_node_index[&(node_index - 1)], terrain_per_node_index[&node_index]];
+                    assert!(terrains != [Terrain::Grass, Terrain::Water] && terrains != [Terrain::Water, Terrain::Grass]);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn validate_missing_node_state_collection() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A")];
+        let mut first_node = Node::new(String::from("node_1"), NodeStateProbability::get_equal_probability(&node_state_ids), HashMap::new());
+        first_node.node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_2"), vec![String::from("missing_collection")]);
+        let mut second_node = Node::new(String::from("node_2"), NodeStateProbability::get_equal_probability(&node_state_ids), HashMap::new());
+        second_node.node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_1"), Vec::new());
+
+        let wave_function = WaveFunction::new(vec![first_node, second_node], Vec::new());
+
+        assert_eq!("Node node_1 references node state collection missing_collection for neighbor node node_2 but it does not exist in main list of node state collections.", wave_function.validate().err().unwrap());
+    }
+
+    #[test]
+    fn validate_reports_every_missing_reference() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A")];
+        let node_state_collection = NodeStateCollection::new(String::from("existing_collection"), String::from("state_A"), node_state_ids.clone());
+        let mut first_node = Node::new(String::from("node_1"), NodeStateProbability::get_equal_probability(&node_state_ids), HashMap::new());
+        first_node.node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_2"), vec![String::from("existing_collection"), String::from("first_missing_collection")]);
+        first_node.node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_3"), vec![String::from("existing_collection")]);
+        let mut second_node = Node::new(String::from("node_2"), NodeStateProbability::get_equal_probability(&node_state_ids), HashMap::new());
+        // the same list of node state collections is still described for each node that references it
+        second_node.node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_1"), vec![String::from("existing_collection"), String::from("first_missing_collection")]);
+        second_node.node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_4"), vec![String::from("second_missing_collection")]);
+
+        let wave_function = WaveFunction::new(vec![first_node, second_node], vec![node_state_collection]);
+
+        let expected_error_message = [
+            "Node node_1 references node state collection first_missing_collection for neighbor node node_2 but it does not exist in main list of node state collections.",
+            "Node node_1 references neighbor node node_3 but it does not exist in main list of nodes.",
+            "Node node_2 references node state collection first_missing_collection for neighbor node node_1 but it does not exist in main list of node state collections.",
+            "Node node_2 references neighbor node node_4 but it does not exist in main list of nodes.",
+            "Node node_2 references node state collection second_missing_collection for neighbor node node_4 but it does not exist in main list of node state collections."
+        ].join(" ");
+        assert_eq!(expected_error_message, wave_function.validate().err().unwrap());
+    }
+
+    #[test]
+    fn validate_strict_node_state_collection_with_impossible_node_states() {
+        init();
+
+        let mut constraint: HashMap<String, Vec<String>> = HashMap::new();
+        constraint.insert(String::from("state_A"), vec![String::from("state_B")]);
+        let wave_function = WaveFunctionBuilder::new()
+            .add_node("node_1", vec![String::from("state_A")])
+            .add_node("node_2", vec![String::from("state_B")])
+            .add_constraint("node_1", "node_2", constraint)
+            .build()
+            .unwrap();
+        wave_function.validate_strict().unwrap();
+
+        // the same node state collection is reused in the reverse direction where neither node state is possible
+        let mut nodes = wave_function.get_nodes();
+        let node_state_collections = wave_function.get_node_state_collections();
+        nodes[1].node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_1"), vec![node_state_collections[0].id.clone()]);
+        let wave_function = WaveFunction::new(nodes.clone(), node_state_collections.clone());
+        wave_function.validate().unwrap();
+        assert_eq!("Node state collection node_state_collection_0 is for node state \"state_A\" that node node_2 can never be in.", wave_function.validate_strict().err().unwrap());
+
+        let reversed_node_state_collection = NodeStateCollection::new(String::from("reversed_collection"), String::from("state_B"), vec![String::from("state_B")]);
+        nodes[1].node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_1"), vec![reversed_node_state_collection.id.clone()]);
+        let wave_function = WaveFunction::new(nodes, vec![node_state_collections[0].clone(), reversed_node_state_collection]);
+        wave_function.validate().unwrap();
+        assert_eq!("Node state collection reversed_collection permits node state \"state_B\" that neighbor node node_1 of node node_2 can never be in.", wave_function.validate_strict().err().unwrap());
+    }
+
+    #[test]
+    fn entropic_node_selection_chooses_different_first_node() {
+        init();
+
+        // the first node has fewer node states while the second node has the lower Shannon entropy
+        let mut first_node_state_ratio_per_node_state_id: HashMap<String, f64> = HashMap::new();
+        first_node_state_ratio_per_node_state_id.insert(String::from("state_A"), 1.0);
+        first_node_state_ratio_per_node_state_id.insert(String::from("state_B"), 1.0);
+        let mut second_node_state_ratio_per_node_state_id: HashMap<String, f64> = HashMap::new();
+        second_node_state_ratio_per_node_state_id.insert(String::from("state_A"), 98.0);
+        second_node_state_ratio_per_node_state_id.insert(String::from("state_B"), 1.0);
+        second_node_state_ratio_per_node_state_id.insert(String::from("state_C"), 1.0);
+        let wave_function = WaveFunctionBuilder::new()
+            .add_node_with_probabilities("node_1", first_node_state_ratio_per_node_state_id)
+            .add_node_with_probabilities("node_2", second_node_state_ratio_per_node_state_id)
+            // without any permitted node states the neighbors connect together without restricting each other
+            .add_neighbors("node_1", "node_2")
+            .build()
+            .unwrap();
+
+        for random_seed in 0..10 {
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed));
+            collapsable_wave_function.set_node_selection_heuristic(NodeSelectionHeuristic::MinimumRemainingStates);
+            assert_eq!("node_1", collapsable_wave_function.collapse_into_steps().unwrap()[0].node_id);
+
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed));
+            collapsable_wave_function.set_node_selection_heuristic(NodeSelectionHeuristic::ShannonEntropy);
+            assert_eq!("node_2", collapsable_wave_function.collapse_into_steps().unwrap()[0].node_id);
+        }
+    }
+
+    #[test]
+    fn entropic_minimum_remaining_states_collapses_single_state_nodes_first() {
+        init();
+
+        let wave_function = get_three_color_grid_wave_function_with_size(4, 4);
+        let mut fixed_node_state_per_node_id: HashMap<String, String> = HashMap::new();
+        // the corners do not share any neighbors, so no other node is restricted to a single node state by collapsing them
+        fixed_node_state_per_node_id.insert(String::from("0_0"), String::from("red"));
+        fixed_node_state_per_node_id.insert(String::from("3_0"), String::from("green"));
+        fixed_node_state_per_node_id.insert(String::from("0_3"), String::from("blue"));
+        fixed_node_state_per_node_id.insert(String::from("3_3"), String::from("red"));
+
+        for random_seed in 0..10 {
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function_with_fixed_states::<EntropicCollapsableWaveFunction<String>>(Some(random_seed), &fixed_node_state_per_node_id).unwrap();
+            collapsable_wave_function.set_node_selection_heuristic(NodeSelectionHeuristic::MinimumRemainingStates);
+            let collapsed_node_states = collapsable_wave_function.collapse_into_steps().unwrap();
+            let mut first_node_ids: Vec<&String> = collapsed_node_states[0..4].iter().map(|collapsed_node_state| &collapsed_node_state.node_id).collect();
+            first_node_ids.sort();
+            assert_eq!(vec!["0_0", "0_3", "3_0", "3_3"], first_node_ids);
+            for collapsed_node_state in collapsed_node_states[0..4].iter() {
+                assert_eq!(fixed_node_state_per_node_id.get(&collapsed_node_state.node_id), collapsed_node_state.node_state_id.as_ref());
+            }
+        }
+    }
+
+    #[test]
+    fn entropic_shannon_entropy_respects_node_state_probabilities() {
+        init();
+
+        // every node has two equally likely node states except for one node that has three node states with one far more likely than the others
+        let mut weighted_node_state_ratio_per_node_state_id: HashMap<String, f64> = HashMap::new();
+        weighted_node_state_ratio_per_node_state_id.insert(String::from("state_A"), 98.0);
+        weighted_node_state_ratio_per_node_state_id.insert(String::from("state_B"), 1.0);
+        weighted_node_state_ratio_per_node_state_id.insert(String::from("state_C"), 1.0);
+        let mut wave_function_builder = WaveFunctionBuilder::new();
+        for y in 0..3 {
+            for x in 0..3 {
+                let node_id = format!("{x}_{y}");
+                if x == 2 && y == 1 {
+                    wave_function_builder.add_node_with_probabilities(&node_id, weighted_node_state_ratio_per_node_state_id.clone());
+                }
+                else {
+                    wave_function_builder.add_node(&node_id, vec![String::from("state_A"), String::from("state_B")]);
+                }
+                if x != 0 {
+                    wave_function_builder.add_neighbors(&format!("{}_{y}", x - 1), &node_id);
+                }
+                if y != 0 {
+                    wave_function_builder.add_neighbors(&format!("{x}_{}", y - 1), &node_id);
+                }
+            }
+        }
+        let wave_function = wave_function_builder.build().unwrap();
+
+        for random_seed in 0..10 {
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed));
+            collapsable_wave_function.set_node_selection_heuristic(NodeSelectionHeuristic::ShannonEntropy);
+            assert_eq!("2_1", collapsable_wave_function.collapse_into_steps().unwrap()[0].node_id);
+
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed));
+            collapsable_wave_function.set_node_selection_heuristic(NodeSelectionHeuristic::MinimumRemainingStates);
+            let collapsed_node_states = collapsable_wave_function.collapse_into_steps().unwrap();
+            assert_eq!("2_1", collapsed_node_states.last().unwrap().node_id);
+        }
+    }
+
+    #[test]
+    fn entropic_most_constrained_neighbors_collapses_next_to_collapsed_nodes() {
+        init();
+
+        let wave_function = get_three_color_grid_wave_function_with_size(5, 5);
+        for random_seed in 0..10 {
+            let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed));
+            collapsable_wave_function.set_node_selection_heuristic(NodeSelectionHeuristic::MostConstrainedNeighbors);
+            let collapsed_node_states = collapsable_wave_function.collapse_into_steps().unwrap();
+            assert_eq!(25, collapsed_node_states.len());
+            let mut collapsed_coordinates: Vec<(i32, i32)> = Vec::new();
+            for collapsed_node_state in collapsed_node_states.iter() {
+                let (x, y) = collapsed_node_state.node_id.split_once('_').unwrap();
+                let (x, y): (i32, i32) = (x.parse().unwrap(), y.parse().unwrap());
+                if !collapsed_coordinates.is_empty() {
+                    assert!(collapsed_coordinates.iter().any(|(other_x, other_y)| (x - other_x).abs() + (y - other_y).abs() == 1), "Node {} was collapsed without a collapsed neighbor.", collapsed_node_state.node_id);
+                }
+                collapsed_coordinates.push((x, y));
+            }
+        }
+    }
+
+    #[test]
+    fn entropic_node_selection_heuristics_are_reproducible() {
+        init();
+
+        let wave_function = get_three_color_grid_wave_function_with_size(5, 5);
+        for node_selection_heuristic in [NodeSelectionHeuristic::MinimumRemainingStates, NodeSelectionHeuristic::ShannonEntropy, NodeSelectionHeuristic::MostConstrainedNeighbors, NodeSelectionHeuristic::Random] {
+            let mut first_node_ids: Vec<String> = Vec::new();
+            for random_seed in 0..10 {
+                let get_collapsed_node_states = || {
+                    let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed));
+                    collapsable_wave_function.set_node_selection_heuristic(node_selection_heuristic);
+                    collapsable_wave_function.collapse_into_steps().unwrap()
+                };
+                let collapsed_node_states = get_collapsed_node_states();
+                assert_eq!(collapsed_node_states, get_collapsed_node_states());
+                if !first_node_ids.contains(&collapsed_node_states[0].node_id) {
+                    first_node_ids.push(collapsed_node_states[0].node_id.clone());
+                }
+            }
+            // every node is tied at the start, so the first node depends on the random seed
+            assert!(first_node_ids.len() > 1, "{node_selection_heuristic:?} always chose {first_node_ids:?} first.");
+        }
+    }
+
+    #[test]
+    fn entropic_node_selection_breaks_ties_randomly() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        let wave_function = WaveFunctionBuilder::new()
+            .add_node("node_1", node_state_ids.clone())
+            .add_node("node_2", node_state_ids.clone())
+            .add_node("node_3", node_state_ids)
+            .add_neighbors("node_1", "node_2")
+            .add_neighbors("node_2", "node_3")
+            .build()
+            .unwrap();
+
+        let mut first_node_ids: Vec<String> = Vec::new();
+        for random_seed in 0..30 {
+            let first_node_id = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed)).collapse_into_steps().unwrap()[0].node_id.clone();
+            if !first_node_ids.contains(&first_node_id) {
+                first_node_ids.push(first_node_id);
+            }
+        }
+        assert_eq!(3, first_node_ids.len());
+    }
+
+    #[test]
+    fn merge_two_three_node_wave_functions_with_bridges() {
+        init();
+
+        let room_wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("room");
+        let corridor_wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("corridor");
+
+        // the bridged nodes must be in the same node state
+        let mut bridges: Vec<(String, String, NodeStateCollection<String>)> = Vec::new();
+        for node_state_id in ["state_A", "state_B", "state_C"] {
+            bridges.push((String::from("room_3"), String::from("corridor_1"), NodeStateCollection::new(format!("bridge_same_{node_state_id}"), String::from(node_state_id), vec![String::from(node_state_id)])));
+        }
+
+        let wave_function = room_wave_function.merge(corridor_wave_function, bridges).unwrap();
+        wave_function.validate_strict().unwrap();
+        assert_eq!(6, wave_function.get_nodes().len());
+        assert_eq!(9, wave_function.get_node_state_collections().len());
+
+        for random_seed in 0..20 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            for node_id_prefix in ["room", "corridor"] {
+                let mut collapsed_node_state_ids: Vec<&String> = (1..=3).map(|node_index| collapsed_wave_function.node_state_per_node_id.get(&format!("{node_id_prefix}_{node_index}")).unwrap()).collect();
+                collapsed_node_state_ids.sort();
+                collapsed_node_state_ids.dedup();
+                assert_eq!(3, collapsed_node_state_ids.len());
+            }
+            assert_eq!(collapsed_wave_function.node_state_per_node_id.get("room_3"), collapsed_wave_function.node_state_per_node_id.get("corridor_1"));
+        }
+    }
+
+    #[test]
+    fn merge_wave_functions_with_duplicate_node_ids() {
+        init();
+
+        let first_wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("room");
+        let second_wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("room");
+
+        let error_message = first_wave_function.merge(second_wave_function, Vec::new()).err().unwrap();
+        assert_eq!("Node room_1 exists in both wave functions.", error_message);
+    }
+
+    fn get_unconnected_nodes(nodes_total: usize, node_state_ratio_per_node_state_id: HashMap<String, f64>) -> Vec<Node<String>> {
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        for index in 0..nodes_total {
+            nodes.push(Node::new(
+                format!("node_{index}"),
+                node_state_ratio_per_node_state_id.clone(),
+                HashMap::new()
+            ));
+        }
+        nodes
+    }
+
+    #[test]
+    fn weighted_node_state_rate_unbiased_when_neighbor_restricts_node_states() {
+        init();
+
+        let node_state_collections: Vec<NodeStateCollection<String>> = vec![
+            NodeStateCollection::new(String::from("blocker_excludes_heavy_a"), String::from("blocker"), vec![String::from("heavy_b"), String::from("light")]),
+            NodeStateCollection::new(String::from("free_permits_all"), String::from("free"), vec![String::from("heavy_a"), String::from("heavy_b"), String::from("light")])
+        ];
+        let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+        node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_1"), vec![String::from("blocker_excludes_heavy_a"), String::from("free_permits_all")]);
+        let nodes: Vec<Node<String>> = vec![
+            Node::new(
+                String::from("node_0"),
+                NodeStateProbability::get_equal_probability(&vec![String::from("blocker"), String::from("free")]),
+                node_state_collection_ids_per_neighbor_node_id
+            ),
+            Node::new(
+                String::from("node_1"),
+                NodeStateProbability::get_weighted_probability(vec![(String::from("heavy_a"), 1.0), (String::from("heavy_b"), 1.0), (String::from("light"), 0.01)]).unwrap(),
+                HashMap::new()
+            )
+        ];
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate().unwrap();
+
+        // the sequential collapsable wave function chooses node_0 before node_1, so heavy_a is excluded from node_1 half of the time before it chooses
+        let mut light_total_per_is_blocked: HashMap<bool, usize> = HashMap::new();
+        let mut collapses_total_per_is_blocked: HashMap<bool, usize> = HashMap::new();
+        for random_seed in 0..40000 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let is_blocked = collapsed_wave_function.node_state_per_node_id.get("node_0").unwrap() == "blocker";
+            let node_state_id = collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap();
+            assert!(!is_blocked || node_state_id != "heavy_a");
+            *collapses_total_per_is_blocked.entry(is_blocked).or_default() += 1;
+            if node_state_id == "light" {
+                *light_total_per_is_blocked.entry(is_blocked).or_default() += 1;
+            }
+        }
+
+        // the light node state should appear in proportion to its weight relative to only the node states that remain
+        for (is_blocked, remaining_weight_total) in [(true, 1.01), (false, 2.01)] {
+            let collapses_total = *collapses_total_per_is_blocked.get(&is_blocked).unwrap() as f64;
+            let expected_light_total = collapses_total * 0.01 / remaining_weight_total;
+            let light_total = *light_total_per_is_blocked.get(&is_blocked).unwrap_or(&0) as f64;
+            // within five standard deviations
+            let tolerance = 5.0 * expected_light_total.sqrt();
+            assert!((light_total - expected_light_total).abs() < tolerance, "blocked: {is_blocked}, light: {light_total}, expected: {expected_light_total}");
+        }
+    }
+
+    #[test]
+    fn global_constraint_at_least_one_requires_improbable_node_state() {
+        init();
+
+        let node_state_ratio_per_node_state_id = NodeStateProbability::get_weighted_probability(vec![(String::from("state_A"), 1.0), (String::from("state_B"), 0.0001)]).unwrap();
+        let nodes = get_unconnected_nodes(8, node_state_ratio_per_node_state_id);
+        let node_ids: Vec<String> = nodes.iter().map(|node| node.get_id()).collect();
+        let wave_function = WaveFunction::new_with_global_constraints(nodes, Vec::new(), vec![
+            GlobalConstraint::AtLeastOne { node_ids, state: String::from("state_B") }
+        ]).unwrap();
+
+        for random_seed in 0..10 {
+            let sequential_collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let entropic_collapsed_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            for collapsed_wave_function in [sequential_collapsed_wave_function, entropic_collapsed_wave_function] {
+                assert!(collapsed_wave_function.node_state_per_node_id.values().any(|node_state_id| node_state_id == "state_B"));
+            }
+        }
+    }
+
+    #[test]
+    fn global_constraint_exactly_zero_forbids_node_state() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        let nodes = get_unconnected_nodes(4, NodeStateProbability::get_equal_probability(&node_state_ids));
+        let node_ids: Vec<String> = nodes.iter().map(|node| node.get_id()).collect();
+        let wave_function = WaveFunction::new_with_global_constraints(nodes, Vec::new(), vec![
+            GlobalConstraint::ExactlyN { node_ids, state: String::from("state_A"), n: 0 }
+        ]).unwrap();
+
+        for random_seed in 0..10 {
+            let sequential_collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let entropic_collapsed_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            for collapsed_wave_function in [sequential_collapsed_wave_function, entropic_collapsed_wave_function] {
+                assert!(collapsed_wave_function.node_state_per_node_id.values().all(|node_state_id| node_state_id == "state_B"));
+            }
+        }
+
+        // forbidding the only node state leaves nothing to collapse into
+        let nodes = get_unconnected_nodes(4, NodeStateProbability::get_equal_probability(&vec![String::from("state_A")]));
+        let node_ids: Vec<String> = nodes.iter().map(|node| node.get_id()).collect();
+        let wave_function = WaveFunction::new_with_global_constraints(nodes, Vec::new(), vec![
+            GlobalConstraint::ExactlyN { node_ids, state: String::from("state_A"), n: 0 }
+        ]).unwrap();
+
+        assert!(wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().is_err());
+        assert!(wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(0)).collapse().is_err());
+        assert!(wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(0)).collapse().is_err());
+        assert!(wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(Some(0)).collapse().is_err());
+    }
+
+    #[test]
+    fn global_constraint_limits_enumerated_collapsed_wave_functions() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        for (global_constraint_n, is_exact, expected_collapsed_wave_functions_total) in [(1, false, 4), (2, true, 3), (0, true, 1)] {
+            let nodes = get_unconnected_nodes(3, NodeStateProbability::get_equal_probability(&node_state_ids));
+            let node_ids: Vec<String> = nodes.iter().map(|node| node.get_id()).collect();
+            let global_constraint = if is_exact {
+                GlobalConstraint::ExactlyN { node_ids, state: String::from("state_A"), n: global_constraint_n }
+            }
+            else {
+                GlobalConstraint::AtMostN { node_ids, state: String::from("state_A"), n: global_constraint_n }
+            };
+            let wave_function = WaveFunction::new_with_global_constraints(nodes, Vec::new(), vec![global_constraint]).unwrap();
+
+            let collapsed_wave_functions: Vec<CollapsedWaveFunction<String>> = wave_function.get_all_collapsed_wave_functions(None).collect::<Result<Vec<_>, String>>().unwrap();
+            assert_eq!(expected_collapsed_wave_functions_total, collapsed_wave_functions.len());
+            for collapsed_wave_function in collapsed_wave_functions.iter() {
+                let state_a_total = collapsed_wave_function.node_state_per_node_id.values().filter(|node_state_id| *node_state_id == "state_A").count();
+                if is_exact {
+                    assert_eq!(global_constraint_n, state_a_total);
+                }
+                else {
+                    assert!(state_a_total <= global_constraint_n);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn global_constraint_with_missing_node_and_serialized() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        let nodes = get_unconnected_nodes(2, NodeStateProbability::get_equal_probability(&node_state_ids));
+        let error_message = WaveFunction::new_with_global_constraints(nodes.clone(), Vec::new(), vec![
+            GlobalConstraint::AtLeastOne { node_ids: vec![String::from("node_0"), String::from("node_9")], state: String::from("state_A") }
+        ]).err().unwrap();
+        assert_eq!("Global constraint references node node_9 that does not exist.", error_message);
+
+        let wave_function = WaveFunction::new_with_global_constraints(nodes, Vec::new(), vec![
+            GlobalConstraint::ExactlyN { node_ids: vec![String::from("node_0"), String::from("node_1")], state: String::from("state_A"), n: 1 }
+        ]).unwrap();
+        let serialized_wave_function = serde_json::to_string(&wave_function).unwrap();
+        let deserialized_wave_function: WaveFunction<String> = serde_json::from_str(&serialized_wave_function).unwrap();
+        assert_eq!(1, deserialized_wave_function.get_global_constraints().len());
+
+        for random_seed in 0..10 {
+            let collapsed_wave_function = deserialized_wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            assert_ne!(collapsed_wave_function.node_state_per_node_id.get("node_0"), collapsed_wave_function.node_state_per_node_id.get("node_1"));
+        }
+
+        // wave functions without global constraints serialize as they did before global constraints existed
+        let wave_function = WaveFunction::new(get_unconnected_nodes(1, NodeStateProbability::get_equal_probability(&node_state_ids)), Vec::new());
+        assert!(!serde_json::to_string(&wave_function).unwrap().contains("global_constraints"));
+    }
+
+    #[test]
+    fn aggregate_constraint_exactly_one_door_among_four_neighbors() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("door"), String::from("wall")];
+        let mut nodes = get_unconnected_nodes(4, NodeStateProbability::get_equal_probability(&node_state_ids));
+        let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+        for node in nodes.iter() {
+            node_state_collection_ids_per_neighbor_node_id.insert(node.get_id(), Vec::new());
+        }
+        let mut center_node = Node::new(
+            String::from("center"),
+            NodeStateProbability::get_equal_probability(&vec![String::from("room")]),
+            node_state_collection_ids_per_neighbor_node_id
+        );
+        center_node.add_aggregate_constraint(AggregateConstraint::Exactly(1, vec![String::from("door")]));
+        nodes.push(center_node);
+        let wave_function = WaveFunction::new(nodes, Vec::new());
+
+        for random_seed in 0..100 {
+            let sequential_collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let entropic_collapsed_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            for collapsed_wave_function in [sequential_collapsed_wave_function, entropic_collapsed_wave_function] {
+                let doors_total = collapsed_wave_function.node_state_per_node_id.values().filter(|node_state_id| *node_state_id == "door").count();
+                assert_eq!(1, doors_total);
+                wave_function.validate_collapsed(&collapsed_wave_function).unwrap();
+            }
+        }
+
+        // the aggregate constraint is kept when the wave function is serialized
+        let deserialized_wave_function: WaveFunction<String> = serde_json::from_str(&serde_json::to_string(&wave_function).unwrap()).unwrap();
+        let collapsed_wave_function = deserialized_wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap();
+        assert_eq!(1, collapsed_wave_function.node_state_per_node_id.values().filter(|node_state_id| *node_state_id == "door").count());
+
+        // any other number of doors is rejected by validation
+        let mut node_state_per_node_id = collapsed_wave_function.node_state_per_node_id.clone();
+        for node_state_id in node_state_per_node_id.values_mut() {
+            if node_state_id != "room" {
+                *node_state_id = String::from("door");
+            }
+        }
+        assert!(wave_function.validate_collapsed(&CollapsedWaveFunction { node_state_per_node_id, ..collapsed_wave_function }).is_err());
+    }
+
+    #[test]
+    fn node_that_is_its_own_neighbor_restricts_its_own_node_states() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        // state_B does not permit itself, so node_1 can only be in state_A
+        let mut self_permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        self_permitted_node_state_ids_per_node_state_id.insert(String::from("state_A"), vec![String::from("state_A")]);
+        self_permitted_node_state_ids_per_node_state_id.insert(String::from("state_B"), vec![String::from("state_A")]);
+        let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_A"), vec![String::from("state_B")]);
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_B"), vec![String::from("state_A")]);
+
+        let wave_function = WaveFunctionBuilder::new()
+            .add_node("node_1", node_state_ids.clone())
+            .add_node("node_2", node_state_ids.clone())
+            .add_constraint("node_1", "node_1", self_permitted_node_state_ids_per_node_state_id)
+            .add_bidirectional_constraint("node_1", "node_2", permitted_node_state_ids_per_node_state_id)
+            .build()
+            .unwrap();
+        wave_function.validate().unwrap();
+        wave_function.validate_satisfiability().unwrap();
+
+        for random_seed in 0..20 {
+            let sequential_collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let accommodating_collapsed_wave_function = wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            for collapsed_wave_function in [sequential_collapsed_wave_function, accommodating_collapsed_wave_function] {
+                assert_eq!("state_A", collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap());
+                assert_eq!("state_B", collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap());
+                wave_function.validate_collapsed(&collapsed_wave_function).unwrap();
+            }
+        }
+
+        // neither node state permits itself, so the node cannot be in any node state
+        let mut impossible_permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        impossible_permitted_node_state_ids_per_node_state_id.insert(String::from("state_A"), vec![String::from("state_B")]);
+        impossible_permitted_node_state_ids_per_node_state_id.insert(String::from("state_B"), vec![String::from("state_A")]);
+
+        let wave_function = WaveFunctionBuilder::new()
+            .add_node("node_1", node_state_ids.clone())
+            .add_node("node_2", node_state_ids)
+            .add_constraint("node_1", "node_1", impossible_permitted_node_state_ids_per_node_state_id.clone())
+            .add_bidirectional_constraint("node_1", "node_2", impossible_permitted_node_state_ids_per_node_state_id)
+            .build()
+            .unwrap();
+        assert_eq!(Err(String::from("Node node_1 has no possible node states that its node state collections for itself permit.")), wave_function.validate_satisfiability());
+
+        for random_seed in 0..20 {
+            assert!(wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().is_err());
+            assert!(wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(random_seed)).collapse().is_err());
+        }
+    }
+
+    #[test]
+    fn node_that_is_its_own_neighbor_is_not_counted_by_its_aggregate_constraint() {
+        init();
+
+        // the center is always a door, so it would satisfy its own aggregate constraint if it were counted among its neighbors
+        let node_state_ids: Vec<String> = vec![String::from("door"), String::from("wall")];
+        let mut nodes = get_unconnected_nodes(1, NodeStateProbability::get_equal_probability(&node_state_ids));
+        let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+        node_state_collection_ids_per_neighbor_node_id.insert(nodes[0].get_id(), Vec::new());
+        node_state_collection_ids_per_neighbor_node_id.insert(String::from("center"), Vec::new());
+        let mut center_node = Node::new(
+            String::from("center"),
+            NodeStateProbability::get_equal_probability(&vec![String::from("door")]),
+            node_state_collection_ids_per_neighbor_node_id
+        );
+        center_node.add_aggregate_constraint(AggregateConstraint::Exactly(1, vec![String::from("door")]));
+        nodes.push(center_node);
+        let wave_function = WaveFunction::new(nodes, Vec::new());
+        let neighbor_node_id = wave_function.get_nodes()[0].get_id();
+
+        for random_seed in 0..20 {
+            let sequential_collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let entropic_collapsed_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            for collapsed_wave_function in [sequential_collapsed_wave_function, entropic_collapsed_wave_function] {
+                assert_eq!("door", collapsed_wave_function.node_state_per_node_id.get(&neighbor_node_id).unwrap());
+                wave_function.validate_collapsed(&collapsed_wave_function).unwrap();
+            }
+        }
+
+        // a wall next to the center leaves it without a neighboring door
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap();
+        let mut node_state_per_node_id = collapsed_wave_function.node_state_per_node_id.clone();
+        node_state_per_node_id.insert(neighbor_node_id, String::from("wall"));
+        assert!(wave_function.validate_collapsed(&CollapsedWaveFunction { node_state_per_node_id, ..collapsed_wave_function }).is_err());
+    }
+
+    #[test]
+    fn get_all_collapsed_wave_functions_two_nodes_different_states() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_A"), vec![String::from("state_B")]);
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_B"), vec![String::from("state_A")]);
+
+        let wave_function = WaveFunctionBuilder::new()
+            .add_node("node_1", node_state_ids.clone())
+            .add_node("node_2", node_state_ids)
+            .add_bidirectional_constraint("node_1", "node_2", permitted_node_state_ids_per_node_state_id)
+            .build()
+            .unwrap();
+        wave_function.validate().unwrap();
+
+        let collapsed_wave_functions: Vec<CollapsedWaveFunction<String>> = wave_function.get_all_collapsed_wave_functions(None).collect::<Result<Vec<_>, String>>().unwrap();
+        assert_eq!(2, collapsed_wave_functions.len());
+        assert_ne!(collapsed_wave_functions[0], collapsed_wave_functions[1]);
+        for collapsed_wave_function in collapsed_wave_functions.iter() {
+            assert_ne!(collapsed_wave_function.node_state_per_node_id.get("node_1"), collapsed_wave_function.node_state_per_node_id.get("node_2"));
+        }
+    }
+
+    #[test]
+    fn get_all_collapsed_wave_functions_with_conflicting_state_requirements() {
+        init();
+
+        let wave_function = get_two_nodes_with_conflicting_state_requirements_wave_function();
+        wave_function.validate().unwrap();
+
+        let collapsed_wave_functions: Vec<CollapsedWaveFunction<String>> = wave_function.get_all_collapsed_wave_functions(None).collect::<Result<Vec<_>, String>>().unwrap();
+        assert!(collapsed_wave_functions.is_empty());
+    }
+
+    #[test]
+    fn get_all_collapsed_wave_functions_with_limit() {
+        init();
+
+        let wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        wave_function.validate().unwrap();
+
+        // every permutation of the three node states
+        let collapsed_wave_functions: Vec<CollapsedWaveFunction<String>> = wave_function.get_all_collapsed_wave_functions(None).collect::<Result<Vec<_>, String>>().unwrap();
+        assert_eq!(6, collapsed_wave_functions.len());
+        for (collapsed_wave_function_index, collapsed_wave_function) in collapsed_wave_functions.iter().enumerate() {
+            assert!(!collapsed_wave_functions[..collapsed_wave_function_index].contains(collapsed_wave_function));
+            let mut collapsed_node_state_ids: Vec<&String> = collapsed_wave_function.node_state_per_node_id.values().collect();
+            collapsed_node_state_ids.sort();
+            collapsed_node_state_ids.dedup();
+            assert_eq!(3, collapsed_node_state_ids.len());
+        }
+
+        let limited_collapsed_wave_functions: Vec<CollapsedWaveFunction<String>> = wave_function.get_all_collapsed_wave_functions(Some(4)).collect::<Result<Vec<_>, String>>().unwrap();
+        assert_eq!(collapsed_wave_functions[..4], limited_collapsed_wave_functions[..]);
+    }
+
+    #[test]
+    fn collapse_into_all_four_nodes_as_square() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        let wave_function = WaveFunctionBuilder::new()
+            .permit_only(String::from("state_A"), vec![String::from("state_B")])
+            .permit_only(String::from("state_B"), vec![String::from("state_A")])
+            .add_node("node_1", node_state_ids.clone())
+            .add_node("node_2", node_state_ids.clone())
+            .add_node("node_3", node_state_ids.clone())
+            .add_node("node_4", node_state_ids)
+            .add_neighbors("node_1", "node_2")
+            .add_neighbors("node_2", "node_3")
+            .add_neighbors("node_3", "node_4")
+            .add_neighbors("node_4", "node_1")
+            .build()
+            .unwrap();
+
+        // the node states are tried in a different order per random seed but the same two checkerboards are always found
+        for random_seed in 0..10 {
+            let collapsed_wave_functions: Vec<CollapsedWaveFunction<String>> = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed))
+                .collapse_into_all()
+                .collect::<Result<Vec<_>, String>>()
+                .unwrap();
+            assert_eq!(2, collapsed_wave_functions.len());
+            assert_ne!(collapsed_wave_functions[0], collapsed_wave_functions[1]);
+            for collapsed_wave_function in collapsed_wave_functions.iter() {
+                let node_state_per_node_id = &collapsed_wave_function.node_state_per_node_id;
+                assert_eq!(node_state_per_node_id.get("node_1"), node_state_per_node_id.get("node_3"));
+                assert_eq!(node_state_per_node_id.get("node_2"), node_state_per_node_id.get("node_4"));
+                assert_ne!(node_state_per_node_id.get("node_1"), node_state_per_node_id.get("node_2"));
+            }
+        }
+    }
+
+    #[test]
+    fn collapse_into_all_uncollapsable_is_empty() {
+        init();
+
+        let wave_function = get_two_nodes_with_conflicting_state_requirements_wave_function();
+        for random_seed in 0..10 {
+            let mut collapsed_wave_functions = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse_into_all();
+            assert!(collapsed_wave_functions.next().is_none());
+            assert!(collapsed_wave_functions.next().is_none());
+        }
+    }
+
+    #[test]
+    fn get_statistics_four_nodes_as_square() {
+        init();
+
+        let two_node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B")];
+        let three_node_state_ids: Vec<String> = vec![String::from("state_A"), String::from("state_B"), String::from("state_C")];
+        let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_A"), vec![String::from("state_B")]);
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_B"), vec![String::from("state_A")]);
+
+        let wave_function = WaveFunctionBuilder::new()
+            .add_node("node_1", two_node_state_ids.clone())
+            .add_node("node_2", two_node_state_ids.clone())
+            .add_node("node_3", two_node_state_ids)
+            .add_node("node_4", three_node_state_ids)
+            .add_bidirectional_constraint("node_1", "node_2", permitted_node_state_ids_per_node_state_id.clone())
+            .add_bidirectional_constraint("node_2", "node_3", permitted_node_state_ids_per_node_state_id.clone())
+            .add_bidirectional_constraint("node_3", "node_4", permitted_node_state_ids_per_node_state_id.clone())
+            .add_bidirectional_constraint("node_4", "node_1", permitted_node_state_ids_per_node_state_id)
+            .build()
+            .unwrap();
+        wave_function.validate().unwrap();
+
+        let statistics = wave_function.get_statistics();
+        assert_eq!(4, statistics.nodes_total);
+        assert_eq!(3, statistics.distinct_node_states_total);
+        assert_eq!(2, statistics.minimum_node_states_per_node);
+        assert_eq!(3, statistics.maximum_node_states_per_node);
+        assert_eq!(2.25, statistics.mean_node_states_per_node);
+        assert_eq!(8, statistics.neighbor_edges_total);
+        assert_eq!(2.0, statistics.mean_node_state_collections_per_neighbor_edge);
+        assert!(!statistics.is_any_node_without_node_states);
+
+        let wave_function: WaveFunction<String> = WaveFunctionBuilder::new()
+            .add_node("node_1", Vec::new())
+            .build()
+            .unwrap();
+        let statistics = wave_function.get_statistics();
+        assert_eq!(1, statistics.nodes_total);
+        assert_eq!(0, statistics.neighbor_edges_total);
+        assert_eq!(0.0, statistics.mean_node_state_collections_per_neighbor_edge);
+        assert!(statistics.is_any_node_without_node_states);
+    }
+
+    #[test]
+    fn validate_with_arc_consistency_conflicting_state_requirements() {
+        init();
+
+        let wave_function = get_two_nodes_with_conflicting_state_requirements_wave_function();
+        wave_function.validate().unwrap();
+
+        // each constraint is satisfiable alone but no pair of node states satisfies both
+        let error_message = wave_function.validate_with_arc_consistency().err().unwrap();
+        assert!(error_message.contains("node_1"), "The error should mention node_1: {error_message}");
+        assert!(error_message.contains("node_2"), "The error should mention node_2: {error_message}");
+
+        let collapsed_wave_function_result = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse();
+        assert_eq!("Cannot collapse wave function.", collapsed_wave_function_result.err().unwrap());
+    }
+
+    #[test]
+    fn validate_with_arc_consistency_three_nodes_as_dense_neighbors() {
+        init();
+
+        let wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        wave_function.validate_with_arc_consistency().unwrap();
+    }
+
+    #[test]
+    fn collapse_into_steps_diagnostics_one_node_one_state() {
+        init();
+
+        let wave_function: WaveFunction<String> = WaveFunctionBuilder::new()
+            .add_node("node_1", vec![String::from("state_A")])
+            .build()
+            .unwrap();
+        wave_function.validate().unwrap();
+
+        let collapsed_node_states_per_collapsable_wave_function: Vec<Vec<CollapsedNodeState<String>>> = vec![
+            wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse_into_steps().unwrap(),
+            wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(None).collapse_into_steps().unwrap(),
+            wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(None).collapse_into_steps().unwrap(),
+            wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(None).collapse_into_steps().unwrap()
+        ];
+        for collapsed_node_states in collapsed_node_states_per_collapsable_wave_function.iter() {
+            let collapsed_wave_function_with_diagnostics = CollapsedWaveFunctionWithDiagnostics::from_steps(collapsed_node_states, 0).unwrap();
+            assert_eq!("state_A", collapsed_wave_function_with_diagnostics.collapsed_wave_function.node_state_per_node_id.get("node_1").unwrap());
+            let diagnostics = collapsed_wave_function_with_diagnostics.diagnostics_per_node_id.get("node_1").unwrap();
+            assert_eq!(1, diagnostics.remaining_node_states_total);
+            assert_eq!(1.0, diagnostics.probability);
+        }
+    }
+
+    #[test]
+    fn collapse_into_steps_diagnostics_weighted_node_states() {
+        init();
+
+        let node_state_ratio_per_node_state_id = NodeStateProbability::get_weighted_probability(vec![(String::from("state_A"), 3.0), (String::from("state_B"), 1.0)]).unwrap();
+        let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_A"), vec![String::from("state_B")]);
+        permitted_node_state_ids_per_node_state_id.insert(String::from("state_B"), vec![String::from("state_A")]);
+
+        let wave_function = WaveFunctionBuilder::new()
+            .add_node_with_probabilities("node_1", node_state_ratio_per_node_state_id.clone())
+            .add_node_with_probabilities("node_2", node_state_ratio_per_node_state_id)
+            .add_bidirectional_constraint("node_1", "node_2", permitted_node_state_ids_per_node_state_id)
+            .build()
+            .unwrap();
+        wave_function.validate().unwrap();
+
+        for random_seed in 0..20 {
+            let collapsed_node_states = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse_into_steps().unwrap();
+            let collapsed_wave_function_with_diagnostics = CollapsedWaveFunctionWithDiagnostics::from_steps(&collapsed_node_states, random_seed).unwrap();
+
+            // the first node chosen had both node states available while the second node was restricted to one
+            let mut remaining_node_states_totals: Vec<usize> = Vec::new();
+            for (node_id, node_state_id) in collapsed_wave_function_with_diagnostics.collapsed_wave_function.node_state_per_node_id.iter() {
+                let diagnostics = collapsed_wave_function_with_diagnostics.diagnostics_per_node_id.get(node_id).unwrap();
+                let expected_probability = if node_state_id == "state_A" { 0.75 } else { 0.25 };
+                assert_eq!(expected_probability, diagnostics.probability);
+                remaining_node_states_totals.push(diagnostics.remaining_node_states_total);
+            }
+            remaining_node_states_totals.sort();
+            assert_eq!(vec![1, 2], remaining_node_states_totals);
+        }
+    }
+
+    #[allow(non_snake_case)]
+    fn get_3D_grid_all_different_states_wave_function(size: usize, node_states_total: usize) -> WaveFunction<String> {
+        testing::generate_all_different_3d_grid(size, node_states_total).unwrap()
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn collapse_options_max_backtracks_sequential_3D_grid() {
+        init();
+
+        let wave_function = get_3D_grid_all_different_states_wave_function(4, 8);
+        wave_function.validate().unwrap();
+
+        let mut collapsable_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0));
+        collapsable_wave_function.set_collapse_options(CollapseOptions {
+            max_iterations: None,
+            max_duration: None,
+            max_backtracks: Some(10),
+            max_restarts: None,
+            cancel_token: None,
+            lookahead: PropagationDepth::None
+        });
+        let error_message = collapsable_wave_function.collapse().err().unwrap();
+
+        assert!(error_message.starts_with("Collapse exceeded the maximum of 10 backtracks with "), "Unexpected error: {error_message}");
+        assert!(error_message.ends_with(" of 64 nodes collapsed."), "Unexpected error: {error_message}");
+    }
+
+    #[test]
+    #[allow(non_snake_case)]
+    fn collapse_with_restarts_3D_grid_all_different_states() {
+        init();
+
+        let size = 4;
+        let wave_function = get_3D_grid_all_different_states_wave_function(size, 8);
+        wave_function.validate().unwrap();
+
+        for random_seed in 0..5 {
+            let collapse_options = CollapseOptions {
+                max_iterations: None,
+                max_duration: None,
+                max_backtracks: Some(100),
+                max_restarts: None,
+                cancel_token: None,
+                lookahead: PropagationDepth::None
+            };
+            let (attempts_total, collapsed_wave_function) = wave_function.collapse_with_restarts::<SequentialCollapsableWaveFunction<String>>(random_seed, collapse_options, 1000).unwrap();
+            assert!(attempts_total >= 1);
+
+            for node in wave_function.get_nodes().iter() {
+                let node_state_id = collapsed_wave_function.node_state_per_node_id.get(&node.id).unwrap();
+                for neighbor_node_id in node.node_state_collection_ids_per_neighbor_node_id.keys() {
+                    assert_ne!(node_state_id, collapsed_wave_function.node_state_per_node_id.get(neighbor_node_id).unwrap());
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn deserialize_symmetric_constraints_same_as_directional_node_state_collections() {
+        init();
+
+        let node_state_collections_json = r#"[
+            { "id": "one_permits_two", "node_state_id": "one", "node_state_ids": ["two"] },
+            { "id": "two_permits_one", "node_state_id": "two", "node_state_ids": ["one"] }
+        ]"#;
+        let node_state_ratios_json = r#""node_state_ids": ["one", "two"], "node_state_ratios": [1.0, 1.0]"#;
+        let both_collections_json = r#"["one_permits_two", "two_permits_one"]"#;
+
+        let symmetric_json = format!(r#"{{
+            "nodes": [
+                {{ "id": "node_1", "node_state_collection_ids_per_neighbor_node_id": {{}}, {node_state_ratios_json} }},
+                {{ "id": "node_2", "node_state_collection_ids_per_neighbor_node_id": {{}}, {node_state_ratios_json} }},
+                {{ "id": "node_3", "node_state_collection_ids_per_neighbor_node_id": {{}}, {node_state_ratios_json} }}
+            ],
+            "node_state_collections": {node_state_collections_json},
+            "symmetric_constraints": [
+                {{ "first_node_id": "node_1", "second_node_id": "node_2", "node_state_collection_ids": {both_collections_json} }},
+                {{ "first_node_id": "node_2", "second_node_id": "node_3", "node_state_collection_ids": {both_collections_json} }}
+            ]
+        }}"#);
+        let directional_json = format!(r#"{{
+            "nodes": [
+                {{ "id": "node_1", "node_state_collection_ids_per_neighbor_node_id": {{ "node_2": {both_collections_json} }}, {node_state_ratios_json} }},
+                {{ "id": "node_2", "node_state_collection_ids_per_neighbor_node_id": {{ "node_1": {both_collections_json}, "node_3": {both_collections_json} }}, {node_state_ratios_json} }},
+                {{ "id": "node_3", "node_state_collection_ids_per_neighbor_node_id": {{ "node_2": {both_collections_json} }}, {node_state_ratios_json} }}
+            ],
+            "node_state_collections": {node_state_collections_json}
+        }}"#);
+
+        let symmetric_wave_function: WaveFunction<String> = serde_json::from_str(&symmetric_json).unwrap();
+        let directional_wave_function: WaveFunction<String> = serde_json::from_str(&directional_json).unwrap();
+        symmetric_wave_function.validate().unwrap();
+
+        for (symmetric_node, directional_node) in symmetric_wave_function.get_nodes().iter().zip(directional_wave_function.get_nodes().iter()) {
+            assert_eq!(directional_node.node_state_collection_ids_per_neighbor_node_id, symmetric_node.node_state_collection_ids_per_neighbor_node_id);
+        }
+
+        // the symmetric constraints are serialized in their expanded form
+        let round_trip_wave_function: WaveFunction<String> = serde_json::from_str(&serde_json::to_string(&symmetric_wave_function).unwrap()).unwrap();
+        for (round_trip_node, directional_node) in round_trip_wave_function.get_nodes().iter().zip(directional_wave_function.get_nodes().iter()) {
+            assert_eq!(directional_node.node_state_collection_ids_per_neighbor_node_id, round_trip_node.node_state_collection_ids_per_neighbor_node_id);
+        }
+
+        for random_seed in 0..10 {
+            let symmetric_collapsed_wave_function = symmetric_wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let directional_collapsed_wave_function = directional_wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            assert_eq!(directional_collapsed_wave_function.node_state_per_node_id, symmetric_collapsed_wave_function.node_state_per_node_id);
+            assert_ne!(symmetric_collapsed_wave_function.node_state_per_node_id.get("node_1"), symmetric_collapsed_wave_function.node_state_per_node_id.get("node_2"));
+            assert_ne!(symmetric_collapsed_wave_function.node_state_per_node_id.get("node_2"), symmetric_collapsed_wave_function.node_state_per_node_id.get("node_3"));
+        }
+    }
+
+    #[test]
+    fn new_with_symmetric_constraints_missing_node() {
+        init();
+
+        let nodes = vec![
+            Node::new(String::from("node_1"), NodeStateProbability::get_equal_probability(&vec![String::from("one")]), HashMap::new())
+        ];
+        let node_state_collections = vec![
+            NodeStateCollection::new(String::from("one_permits_one"), String::from("one"), vec![String::from("one")])
+        ];
+        let symmetric_constraints = vec![
+            SymmetricConstraint::new(String::from("node_1"), String::from("node_2"), vec![String::from("one_permits_one")])
+        ];
+
+        let error_message = WaveFunction::new_with_symmetric_constraints(nodes, node_state_collections, symmetric_constraints).err().unwrap();
+
+        assert_eq!("Symmetric constraint references node node_2 that does not exist.", error_message);
+    }
+
+    #[test]
+    fn make_symmetric_checkerboard_declared_in_one_direction() {
+        init();
+
+        let size: usize = 4;
+        let node_state_ids: Vec<String> = vec![String::from("black"), String::from("white")];
+        let node_state_collections: Vec<NodeStateCollection<String>> = vec![
+            NodeStateCollection::new(String::from("black_permits_white"), String::from("black"), vec![String::from("white")]),
+            NodeStateCollection::new(String::from("white_permits_black"), String::from("white"), vec![String::from("black")])
+        ];
+        let get_node_id = |x: usize, y: usize| -> String {
+            format!("node_{x}_{y}")
+        };
+
+        // each node only restricts the node to its right and the node below it
+        let mut nodes: Vec<Node<String>> = Vec::new();
+        for y in 0..size {
+            for x in 0..size {
+                let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+                for (neighbor_x, neighbor_y) in [(x + 1, y), (x, y + 1)] {
+                    if neighbor_x < size && neighbor_y < size {
+                        node_state_collection_ids_per_neighbor_node_id.insert(get_node_id(neighbor_x, neighbor_y), vec![String::from("black_permits_white"), String::from("white_permits_black")]);
+                    }
+                }
+                nodes.push(Node::new(get_node_id(x, y), NodeStateProbability::get_equal_probability(&node_state_ids), node_state_collection_ids_per_neighbor_node_id));
+            }
+        }
+
+        let mut wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.make_symmetric().unwrap();
+        wave_function.validate().unwrap();
+
+        // each inverse permits the same node states as the original, so both inverses are shared by every pair of nodes
+        assert_eq!(4, wave_function.get_node_state_collections().len());
+        let nodes = wave_function.get_nodes();
+        assert!(nodes.iter().find(|node| node.id == get_node_id(1, 1)).unwrap().node_state_collection_ids_per_neighbor_node_id.contains_key(&get_node_id(0, 1)));
+        assert!(nodes.iter().find(|node| node.id == get_node_id(1, 1)).unwrap().node_state_collection_ids_per_neighbor_node_id.contains_key(&get_node_id(1, 0)));
+
+        // every constraint now exists in both directions, so mirroring again changes nothing
+        wave_function.make_symmetric().unwrap();
+        assert_eq!(4, wave_function.get_node_state_collections().len());
+        for (node, mirrored_node) in nodes.iter().zip(wave_function.get_nodes().iter()) {
+            assert_eq!(node.node_state_collection_ids_per_neighbor_node_id, mirrored_node.node_state_collection_ids_per_neighbor_node_id);
+        }
+
+        for random_seed in 0..10 {
+            let collapsed_wave_functions = [
+                wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap(),
+                wave_function.get_collapsable_wave_function::<AccommodatingCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap(),
+                wave_function.get_collapsable_wave_function::<AccommodatingSequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap(),
+                wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap()
+            ];
+            for collapsed_wave_function in collapsed_wave_functions.iter() {
+                wave_function.validate_collapsed(collapsed_wave_function).unwrap();
+                let node_state_per_node_id = &collapsed_wave_function.node_state_per_node_id;
+                for y in 0..size {
+                    for x in 0..size {
+                        let node_state_id = node_state_per_node_id.get(&get_node_id(x, y)).unwrap();
+                        if x + 1 < size {
+                            assert_ne!(node_state_id, node_state_per_node_id.get(&get_node_id(x + 1, y)).unwrap());
+                        }
+                        if y + 1 < size {
+                            assert_ne!(node_state_id, node_state_per_node_id.get(&get_node_id(x, y + 1)).unwrap());
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn make_symmetric_keeps_neighbors_that_already_restrict_back() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("one"), String::from("two")];
+        let node_state_collections: Vec<NodeStateCollection<String>> = vec![
+            NodeStateCollection::new(String::from("one_permits_two"), String::from("one"), vec![String::from("two")]),
+            NodeStateCollection::new(String::from("two_permits_two"), String::from("two"), vec![String::from("two")])
+        ];
+        let mut first_node = Node::new(String::from("node_1"), NodeStateProbability::get_equal_probability(&node_state_ids), HashMap::new());
+        first_node.node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_2"), vec![String::from("one_permits_two")]);
+        let mut second_node = Node::new(String::from("node_2"), NodeStateProbability::get_equal_probability(&node_state_ids), HashMap::new());
+        second_node.node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_1"), vec![String::from("two_permits_two")]);
+
+        let mut wave_function = WaveFunction::new(vec![first_node, second_node], node_state_collections);
+        wave_function.make_symmetric().unwrap();
+
+        assert_eq!(2, wave_function.get_node_state_collections().len());
+        assert_eq!(vec![String::from("two_permits_two")], wave_function.get_nodes()[1].node_state_collection_ids_per_neighbor_node_id[&String::from("node_1")]);
+    }
+
+    #[test]
+    fn visualize_plain_text_three_by_three_grid_with_hole() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("a"), String::from("b"), String::from("c")];
+        let mut grid_builder = GridBuilder::new(3, 3, node_state_ids.clone());
+        for node_state_id in node_state_ids.iter() {
+            for other_node_state_id in node_state_ids.iter() {
+                if node_state_id != other_node_state_id {
+                    grid_builder
+                        .allow(node_state_id.clone(), Direction::Right, other_node_state_id.clone())
+                        .allow(node_state_id.clone(), Direction::Down, other_node_state_id.clone());
+                }
+            }
+        }
+        let wave_function = grid_builder.build().unwrap();
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap();
+
+        // the center node is left out so that it is drawn as the background
+        let get_coordinate = |node_id: &str| -> Option<(usize, usize)> {
+            let (x, y) = node_id.split_once('_').unwrap();
+            let coordinate: (usize, usize) = (x.parse().unwrap(), y.parse().unwrap());
+            if coordinate == (1, 1) {
+                None
+            }
+            else {
+                Some(coordinate)
+            }
+        };
+        let text = visualize::get_plain_text(&collapsed_wave_function, get_coordinate, |node_state_id: &String| node_state_id.chars().next().unwrap(), '.');
+
+        assert_eq!("abc\nc.b\nbca\n", text);
+    }
+
+    #[cfg(feature = "image")]
+    fn get_grid_coordinate(node_id: &str) -> Option<(u32, u32)> {
+        let (x, y) = node_id.split_once('_').unwrap();
+        Some((x.parse().unwrap(), y.parse().unwrap()))
+    }
+
+    #[cfg(feature = "image")]
+    fn get_red_or_blue_color(node_state_id: &String) -> [u8; 4] {
+        if node_state_id == "red" {
+            [255, 0, 0, 255]
+        }
+        else {
+            [0, 0, 255, 255]
+        }
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn visualize_save_png_scaled() {
+        init();
+
+        // the node at 1_0 is missing so that its cell is left transparent
+        let mut node_state_per_node_id: HashMap<String, String> = HashMap::new();
+        node_state_per_node_id.insert(String::from("0_0"), String::from("red"));
+        node_state_per_node_id.insert(String::from("0_1"), String::from("blue"));
+        node_state_per_node_id.insert(String::from("1_1"), String::from("red"));
+        let collapsed_wave_function = CollapsedWaveFunction {
+            node_state_per_node_id,
+            random_seed: 0,
+            node_metadata_per_node: HashMap::new()
+        };
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let file_path: &str = file.path().to_str().unwrap();
+        visualize::save_png(&collapsed_wave_function, file_path, 2, 2, Some(3), get_grid_coordinate, get_red_or_blue_color).unwrap();
+
+        let bytes = std::fs::read(file_path).unwrap();
+        assert_eq!(b"\x89PNG\r\n\x1a\n", &bytes[0..8]);
+
+        let image = image::load_from_memory_with_format(&bytes, image::ImageFormat::Png).unwrap().to_rgba8();
+        file.close().unwrap();
+
+        assert_eq!((6, 6), image.dimensions());
+        assert_eq!([255, 0, 0, 255], image.get_pixel(0, 0).0);
+        assert_eq!([255, 0, 0, 255], image.get_pixel(2, 2).0);
+        assert_eq!([0, 0, 0, 0], image.get_pixel(3, 0).0);
+        assert_eq!([0, 0, 255, 255], image.get_pixel(0, 3).0);
+        assert_eq!([0, 0, 255, 255], image.get_pixel(2, 5).0);
+        assert_eq!([255, 0, 0, 255], image.get_pixel(5, 5).0);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn visualize_write_png_in_memory() {
+        init();
+
+        let mut node_state_per_node_id: HashMap<String, String> = HashMap::new();
+        for x in 0..3 {
+            for y in 0..2 {
+                let node_state_id = if x == y { "red" } else { "blue" };
+                node_state_per_node_id.insert(format!("{x}_{y}"), String::from(node_state_id));
+            }
+        }
+        let collapsed_wave_function = CollapsedWaveFunction {
+            node_state_per_node_id,
+            random_seed: 0,
+            node_metadata_per_node: HashMap::new()
+        };
+
+        let mut cursor = std::io::Cursor::new(Vec::new());
+        visualize::write_png(&collapsed_wave_function, &mut cursor, 3, 2, Some(8), get_grid_coordinate, get_red_or_blue_color).unwrap();
+        let bytes = cursor.into_inner();
+        assert_eq!(b"\x89PNG\r\n\x1a\n", &bytes[0..8]);
+
+        let image = image::load_from_memory_with_format(&bytes, image::ImageFormat::Png).unwrap().to_rgba8();
+        assert_eq!((24, 16), image.dimensions());
+        assert_eq!([255, 0, 0, 255], image.get_pixel(0, 0).0);
+        assert_eq!([255, 0, 0, 255], image.get_pixel(15, 15).0);
+        assert_eq!([0, 0, 255, 255], image.get_pixel(8, 7).0);
+        assert_eq!([0, 0, 255, 255], image.get_pixel(23, 8).0);
+
+        // the image is identical to the one saved to file
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let file_path: &str = file.path().to_str().unwrap();
+        visualize::save_png(&collapsed_wave_function, file_path, 3, 2, Some(8), get_grid_coordinate, get_red_or_blue_color).unwrap();
+        let saved_image = image::load_from_memory_with_format(&std::fs::read(file_path).unwrap(), image::ImageFormat::Png).unwrap().to_rgba8();
+        file.close().unwrap();
+        assert_eq!(saved_image, image);
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn visualize_save_png_out_of_bounds() {
+        init();
+
+        let mut node_state_per_node_id: HashMap<String, String> = HashMap::new();
+        node_state_per_node_id.insert(String::from("0_0"), String::from("red"));
+        node_state_per_node_id.insert(String::from("2_0"), String::from("blue"));
+        node_state_per_node_id.insert(String::from("0_3"), String::from("red"));
+        let collapsed_wave_function = CollapsedWaveFunction {
+            node_state_per_node_id,
+            random_seed: 0,
+            node_metadata_per_node: HashMap::new()
+        };
+
+        let file = tempfile::NamedTempFile::new().unwrap();
+        let file_path: &str = file.path().to_str().unwrap();
+        let error_message = visualize::save_png(&collapsed_wave_function, file_path, 2, 2, None, get_grid_coordinate, get_red_or_blue_color).err().unwrap();
+        file.close().unwrap();
+
+        assert_eq!("Nodes 0_3, 2_0 are outside of the 2 by 2 image.", error_message);
+    }
+
+    #[test]
+    fn to_dot_three_nodes_as_dense_neighbors() {
+        init();
+
+        let wave_function = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node");
+        let dot = wave_function.to_dot(DotOptions::default());
+        let expected_dot = [
+            "digraph wave_function {",
+            "    \"node_1\" [label=\"node_1\\n3 node states\"];",
+            "    \"node_2\" [label=\"node_2\\n3 node states\"];",
+            "    \"node_3\" [label=\"node_3\\n3 node states\"];",
+            "    \"node_1\" -> \"node_2\" [label=\"node_different_state_A, node_different_state_B, node_different_state_C\"];",
+            "    \"node_1\" -> \"node_3\" [label=\"node_different_state_A, node_different_state_B, node_different_state_C\"];",
+            "    \"node_2\" -> \"node_1\" [label=\"node_different_state_A, node_different_state_B, node_different_state_C\"];",
+            "    \"node_2\" -> \"node_3\" [label=\"node_different_state_A, node_different_state_B, node_different_state_C\"];",
+            "    \"node_3\" -> \"node_1\" [label=\"node_different_state_A, node_different_state_B, node_different_state_C\"];",
+            "    \"node_3\" -> \"node_2\" [label=\"node_different_state_A, node_different_state_B, node_different_state_C\"];",
+            "}",
+            ""
+        ].join("\n");
+        assert_eq!(expected_dot, dot);
+
+        let dot = wave_function.to_dot(DotOptions {
+            is_edge_label_summarized: true,
+            is_problem_highlighted: true
+        });
+        assert!(dot.contains("    \"node_1\" -> \"node_2\" [label=\"3 collections\"];\n"), "Unexpected DOT: {dot}");
+        assert!(!dot.contains("fillcolor"), "Unexpected DOT: {dot}");
+
+        // each node is labelled by its node state, where the three different node states have three different colors
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)).collapse().unwrap();
+        let dot = wave_function.to_dot_with_collapsed(&collapsed_wave_function, DotOptions::default());
+        for node_id in ["node_1", "node_2", "node_3"] {
+            let node_state_id = collapsed_wave_function.node_state_per_node_id.get(node_id).unwrap();
+            assert!(dot.contains(&format!("    \"{node_id}\" [label=\"{node_id}\\n\\\"{node_state_id}\\\"\", style=filled, fillcolor=")), "Unexpected DOT: {dot}");
+        }
+        for fill_color in ["\"0.000 0.400 1.000\"", "\"0.333 0.400 1.000\"", "\"0.667 0.400 1.000\""] {
+            assert_eq!(1, dot.matches(fill_color).count(), "Unexpected DOT: {dot}");
+        }
+    }
+
+    #[test]
+    fn to_dot_highlights_problematic_nodes() {
+        init();
+
+        let mut nodes = get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node").get_nodes();
+        nodes.push(Node::new(String::from("node_4"), HashMap::new(), HashMap::new()));
+        nodes.push(Node::new(String::from("node \"5\""), NodeStateProbability::get_equal_probability(&vec![String::from("state_A")]), HashMap::new()));
+        let wave_function = WaveFunction::new(nodes, get_three_nodes_as_dense_neighbors_all_different_states_wave_function("node").get_node_state_collections());
+        assert!(wave_function.validate().is_err());
+
+        let dot = wave_function.to_dot(DotOptions {
+            is_edge_label_summarized: false,
+            is_problem_highlighted: true
+        });
+        assert!(dot.contains("    \"node_1\" [label=\"node_1\\n3 node states\"];\n"), "Unexpected DOT: {dot}");
+        assert!(dot.contains("    \"node_4\" [label=\"node_4\\n0 node states\", style=filled, fillcolor=\"#ff9999\"];\n"), "Unexpected DOT: {dot}");
+        assert!(dot.contains("    \"node \\\"5\\\"\" [label=\"node \\\"5\\\"\\n1 node state\", style=filled, fillcolor=\"#ff9999\"];\n"), "Unexpected DOT: {dot}");
+    }
+
+    #[test]
+    fn new_with_all_different_constraints_sudoku() {
+        init();
+
+        let puzzle = [
+            "53..7....",
+            "6..195...",
+            ".98....6.",
+            "8...6...3",
+            "4..8.3..1",
+            "7...2...6",
+            ".6....28.",
+            "...419..5",
+            "....8..79"
+        ];
+        let get_node_id = |row_index: usize, column_index: usize| -> String {
+            format!("node_{row_index}_{column_index}")
+        };
+
+        let mut nodes: Vec<Node<u8>> = Vec::new();
+        for (row_index, row) in puzzle.iter().enumerate() {
+            for (column_index, character) in row.chars().enumerate() {
+                let node_state_ids: Vec<u8> = match character.to_digit(10) {
+                    Some(number) => vec![number as u8],
+                    None => (1..=9).collect()
+                };
+                nodes.push(Node::new(get_node_id(row_index, column_index), NodeStateProbability::get_equal_probability(&node_state_ids), HashMap::new()));
+            }
+        }
+
+        let mut all_different_constraints: Vec<AllDifferentConstraint> = Vec::new();
+        for index in 0..9 {
+            all_different_constraints.push(AllDifferentConstraint::new((0..9).map(|column_index| get_node_id(index, column_index)).collect()));
+            all_different_constraints.push(AllDifferentConstraint::new((0..9).map(|row_index| get_node_id(row_index, index)).collect()));
+            all_different_constraints.push(AllDifferentConstraint::new((0..9).map(|box_index| get_node_id((index / 3) * 3 + box_index / 3, (index % 3) * 3 + box_index % 3)).collect()));
+        }
+
+        let wave_function = WaveFunction::new_with_all_different_constraints(nodes, Vec::new(), all_different_constraints).unwrap();
+        wave_function.validate().unwrap();
+
+        // every group has the same node states and so they all share the same node state collections
+        assert_eq!(9, wave_function.get_node_state_collections().len());
+
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<u8>>(Some(0)).collapse().unwrap();
+        let get_number = |row_index: usize, column_index: usize| -> u8 {
+            *collapsed_wave_function.node_state_per_node_id.get(&get_node_id(row_index, column_index)).unwrap()
+        };
+
+        for (row_index, row) in puzzle.iter().enumerate() {
+            for (column_index, character) in row.chars().enumerate() {
+                if let Some(number) = character.to_digit(10) {
+                    assert_eq!(number as u8, get_number(row_index, column_index));
+                }
+            }
+        }
+        for index in 0..9 {
+            let mut row_numbers: Vec<u8> = (0..9).map(|column_index| get_number(index, column_index)).collect();
+            let mut column_numbers: Vec<u8> = (0..9).map(|row_index| get_number(row_index, index)).collect();
+            let mut box_numbers: Vec<u8> = (0..9).map(|box_index| get_number((index / 3) * 3 + box_index / 3, (index % 3) * 3 + box_index % 3)).collect();
+            for numbers in [&mut row_numbers, &mut column_numbers, &mut box_numbers] {
+                numbers.sort();
+                assert_eq!((1..=9).collect::<Vec<u8>>(), *numbers);
+            }
+        }
+    }
+
+    #[test]
+    fn new_with_all_different_constraints_missing_node() {
+        init();
+
+        let nodes = vec![
+            Node::new(String::from("node_1"), NodeStateProbability::get_equal_probability(&vec![String::from("one"), String::from("two")]), HashMap::new())
+        ];
+        let all_different_constraints = vec![
+            AllDifferentConstraint::new(vec![String::from("node_1"), String::from("node_2")])
+        ];
+
+        let error_message = WaveFunction::new_with_all_different_constraints(nodes, Vec::new(), all_different_constraints).err().unwrap();
+
+        assert_eq!("All different constraint references node node_2 that does not exist.", error_message);
+    }
+
+    #[test]
+    fn collapse_grid_with_long_node_state_ids_allocations() {
+        init();
+
+        let node_state_ids: Vec<String> = (0..8).map(|index| format!("a_very_long_node_state_id_that_would_be_expensive_to_clone_{index}")).collect();
+        let mut grid_builder = GridBuilder::new(12, 12, node_state_ids.clone());
+        for node_state_id in node_state_ids.iter() {
+            for other_node_state_id in node_state_ids.iter() {
+                if node_state_id != other_node_state_id {
+                    grid_builder
+                        .allow(node_state_id.clone(), Direction::Right, other_node_state_id.clone())
+                        .allow(node_state_id.clone(), Direction::Down, other_node_state_id.clone());
+                }
+            }
+        }
+        let wave_function = grid_builder.build().unwrap();
+
+        let (mut collapsable_wave_function, allocations_total, allocated_bytes_total) = allocation::count_allocations(|| wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(0)));
+        let collapsed_wave_function = collapsable_wave_function.collapse().unwrap();
+
+        // constructing the masks by comparing node states, instead of by their interned keys, required about 20000 allocations and 2.7MB
+        assert!(allocations_total < 12000, "Unexpected allocations total: {allocations_total}");
+        assert!(allocated_bytes_total < 2_000_000, "Unexpected allocated bytes total: {allocated_bytes_total}");
+
+        // the same collapse for this random seed regardless of how the masks are constructed
+        let get_coordinate = |node_id: &str| -> Option<(usize, usize)> {
+            let (x, y) = node_id.split_once('_').unwrap();
+            Some((x.parse().unwrap(), y.parse().unwrap()))
+        };
+        let text = visualize::get_plain_text(&collapsed_wave_function, get_coordinate, |node_state_id: &String| node_state_id.chars().last().unwrap(), '.');
+        assert_eq!("625234035365\n702456157074\n124030764240\n670527215374\n561760650715\n632034263423\n560216501372\n727573616404\n356265162143\n515641070362\n360562527624\n401427672307\n", text);
+    }
+
+    #[test]
+    fn new_with_functional_node_state_collections_numeric_chain() {
+        init();
+
+        let nodes_total: usize = 50;
+        let node_state_ids: Vec<u32> = (0..1000).collect();
+
+        // the first node is pinned so that the chain must wander from it
+        let mut nodes: Vec<Node<u32>> = Vec::new();
+        for node_index in 0..nodes_total {
+            let node_state_ids: Vec<u32> = if node_index == 0 {
+                vec![500]
+            }
+            else {
+                node_state_ids.clone()
+            };
+            let mut node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+            if node_index != 0 {
+                node_state_collection_ids_per_neighbor_node_id.insert(format!("node_{}", node_index - 1), vec![String::from("within_one")]);
+            }
+            if node_index != nodes_total - 1 {
+                node_state_collection_ids_per_neighbor_node_id.insert(format!("node_{}", node_index + 1), vec![String::from("within_one")]);
+            }
+            nodes.push(Node::new(format!("node_{node_index}"), NodeStateProbability::get_equal_probability(&node_state_ids), node_state_collection_ids_per_neighbor_node_id));
+        }
+        let functional_node_state_collections = vec![
+            FunctionalNodeStateCollection::new(String::from("within_one"), |node_state_id: &u32, neighbor_node_state_id: &u32| node_state_id.abs_diff(*neighbor_node_state_id) <= 1)
+        ];
+
+        let wave_function = WaveFunction::new_with_functional_node_state_collections(nodes, Vec::new(), functional_node_state_collections).unwrap();
+        wave_function.validate().unwrap();
+
+        // expansions are shared between nodes with identical neighbor node states, so only the neighbor of the pinned node requires its own
+        assert_eq!(2000, wave_function.get_node_state_collections().len());
+
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<u32>>(Some(0)).collapse().unwrap();
+        assert_eq!(500, *collapsed_wave_function.node_state_per_node_id.get("node_0").unwrap());
+        for node_index in 1..nodes_total {
+            let previous_node_state_id = collapsed_wave_function.node_state_per_node_id.get(&format!("node_{}", node_index - 1)).unwrap();
+            let node_state_id = collapsed_wave_function.node_state_per_node_id.get(&format!("node_{node_index}")).unwrap();
+            assert!(previous_node_state_id.abs_diff(*node_state_id) <= 1, "Node {node_index} is in {node_state_id} after {previous_node_state_id}.");
+        }
+    }
+
+    #[test]
+    fn new_with_functional_node_state_collections_mixed_with_explicit() {
+        init();
+
+        let node_state_ids: Vec<u32> = (0..10).collect();
+        let mut first_node_state_collection_ids_per_neighbor_node_id: HashMap<String, Vec<String>> = HashMap::new();
+        first_node_state_collection_ids_per_neighbor_node_id.insert(String::from("node_2"), vec![String::from("greater"), String::from("five_permits_even")]);
+        let nodes = vec![
+            Node::new(String::from("node_1"), NodeStateProbability::get_equal_probability(&vec![5]), first_node_state_collection_ids_per_neighbor_node_id),
+            Node::new(String::from("node_2"), NodeStateProbability::get_equal_probability(&node_state_ids), HashMap::new())
+        ];
+        let node_state_collections = vec![
+            NodeStateCollection::new(String::from("five_permits_even"), 5, vec![0, 2, 4, 6, 8])
+        ];
+        let functional_node_state_collections = vec![
+            FunctionalNodeStateCollection::new(String::from("greater"), |node_state_id: &u32, neighbor_node_state_id: &u32| neighbor_node_state_id > node_state_id)
+        ];
+
+        let wave_function = WaveFunction::new_with_functional_node_state_collections(nodes, node_state_collections, functional_node_state_collections).unwrap();
+        wave_function.validate().unwrap();
+
+        // the neighbor must be both greater than five and even
+        for random_seed in 0..10 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<u32>>(Some(random_seed)).collapse().unwrap();
+            let node_state_id = *collapsed_wave_function.node_state_per_node_id.get("node_2").unwrap();
+            assert!(node_state_id == 6 || node_state_id == 8, "Unexpected node state: {node_state_id}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod indexed_view_unit_tests {
+
+    use uuid::Uuid;
+    use bitvec::prelude::*;
     use crate::wave_function::indexed_view::IndexedView;
 
     fn init() {
@@ -3626,18 +8426,147 @@ mod indexed_view_unit_tests {
         init();
 
         let node_state_ids: Vec<u32> = Vec::new();
-        let node_state_probabilities: Vec<f32> = Vec::new();
+        let node_state_probabilities: Vec<f64> = Vec::new();
         let _indexed_view = IndexedView::new(node_state_ids, node_state_probabilities);
         
         debug!("Succeeded to initialize IndexedView instance.");
     }
 
+    fn get_visited_node_state_ids(indexed_view: &mut IndexedView<u32>) -> Vec<u32> {
+        let mut node_state_ids: Vec<u32> = Vec::new();
+        while indexed_view.try_move_next() {
+            node_state_ids.push(*indexed_view.get().unwrap());
+        }
+        node_state_ids
+    }
+
+    #[test]
+    fn shuffle_orders_node_states_by_weight() {
+        init();
+
+        // the first node state is drawn in proportion to the weights and the second from the weights of those remaining
+        let trials: u32 = 10000;
+        let mut count_per_first_node_state_id: [u32; 3] = [0; 3];
+        let mut count_per_second_node_state_id: [u32; 3] = [0; 3];
+        for random_seed in 0..trials as u64 {
+            let mut indexed_view = IndexedView::new(vec![0, 1, 2], vec![6.0, 3.0, 1.0]);
+            indexed_view.shuffle(&mut fastrand::Rng::with_seed(random_seed));
+            let visited_node_state_ids = get_visited_node_state_ids(&mut indexed_view);
+
+            // the same random seed always shuffles into the same order
+            let mut other_indexed_view = IndexedView::new(vec![0, 1, 2], vec![6.0, 3.0, 1.0]);
+            other_indexed_view.shuffle(&mut fastrand::Rng::with_seed(random_seed));
+            assert_eq!(visited_node_state_ids, get_visited_node_state_ids(&mut other_indexed_view));
+
+            count_per_first_node_state_id[visited_node_state_ids[0] as usize] += 1;
+            count_per_second_node_state_id[visited_node_state_ids[1] as usize] += 1;
+        }
+
+        let expected_first_counts: [f64; 3] = [6000.0, 3000.0, 1000.0];
+        // P(second) = sum over each other first node state of P(first) * weight / remaining weight
+        let expected_second_counts: [f64; 3] = [
+            trials as f64 * (0.3 * 6.0 / 7.0 + 0.1 * 6.0 / 9.0),
+            trials as f64 * (0.6 * 3.0 / 4.0 + 0.1 * 3.0 / 9.0),
+            trials as f64 * (0.6 * 1.0 / 4.0 + 0.3 * 1.0 / 7.0)
+        ];
+        for node_state_id in 0..3 {
+            for (count, expected_count) in [(count_per_first_node_state_id[node_state_id], expected_first_counts[node_state_id]), (count_per_second_node_state_id[node_state_id], expected_second_counts[node_state_id])] {
+                let tolerance = 5.0 * expected_count.sqrt();
+                assert!((count as f64 - expected_count).abs() < tolerance, "Node state {node_state_id} was counted {count} times when expecting {expected_count}.");
+            }
+        }
+    }
+
+    #[test]
+    fn reset_and_reshuffle_keep_masks() {
+        init();
+
+        let node_state_ids: Vec<u32> = (0..10).collect();
+        let node_state_probabilities: Vec<f64> = vec![1.0; 10];
+        let mut indexed_view = IndexedView::new(node_state_ids, node_state_probabilities);
+        let mut random_instance = fastrand::Rng::with_seed(0);
+        indexed_view.shuffle(&mut random_instance);
+
+        // restrict the odd node states
+        let mut mask: BitVec = BitVec::new();
+        for index in 0..10 {
+            mask.push(index % 2 == 0);
+        }
+        indexed_view.add_mask(&mask);
+
+        assert_eq!(5, indexed_view.get_remaining_node_states_total());
+        let first_visited_node_state_ids = get_visited_node_state_ids(&mut indexed_view);
+        assert_eq!(5, first_visited_node_state_ids.len());
+        assert!(first_visited_node_state_ids.iter().all(|node_state_id| node_state_id % 2 == 0));
+        assert_eq!(0, indexed_view.get_remaining_node_states_total());
+        assert!(!indexed_view.try_move_next());
+
+        indexed_view.reset();
+        assert_eq!(5, indexed_view.get_remaining_node_states_total());
+        assert!(indexed_view.try_move_next());
+        assert_eq!(4, indexed_view.get_remaining_node_states_total());
+        indexed_view.reset();
+        assert_eq!(first_visited_node_state_ids, get_visited_node_state_ids(&mut indexed_view));
+
+        indexed_view.reshuffle(&mut fastrand::Rng::with_seed(1));
+        let reshuffled_node_state_ids = get_visited_node_state_ids(&mut indexed_view);
+        assert_ne!(first_visited_node_state_ids, reshuffled_node_state_ids);
+        let mut sorted_reshuffled_node_state_ids = reshuffled_node_state_ids.clone();
+        sorted_reshuffled_node_state_ids.sort();
+        assert_eq!(vec![0, 2, 4, 6, 8], sorted_reshuffled_node_state_ids);
+
+        // the same random seed always reshuffles into the same order
+        indexed_view.reshuffle(&mut fastrand::Rng::with_seed(1));
+        assert_eq!(reshuffled_node_state_ids, get_visited_node_state_ids(&mut indexed_view));
+    }
+
+    #[test]
+    fn masks_spanning_many_words() {
+        init();
+
+        // more node states than fit in two words so that partial words are compared too
+        let node_state_ids: Vec<u32> = (0..150).collect();
+        let node_state_probabilities: Vec<f64> = vec![1.0; 150];
+        let mut indexed_view = IndexedView::new(node_state_ids, node_state_probabilities);
+        assert!(indexed_view.is_fully_unmasked());
+        assert_eq!(150, indexed_view.get_unrestricted_node_states_total());
+
+        // restrict every node state except the multiples of seven
+        let mut mask: BitVec = BitVec::new();
+        for index in 0..150 {
+            mask.push(index % 7 == 0);
+        }
+        assert!(indexed_view.is_mask_restrictive(&mask));
+        indexed_view.add_mask(&mask);
+        assert!(!indexed_view.is_fully_unmasked());
+        assert!(!indexed_view.is_mask_restrictive(&mask));
+        assert_eq!(22, indexed_view.get_unrestricted_node_states_total());
+        assert_eq!((0..150).filter(|index| index % 7 == 0).collect::<Vec<u32>>(), indexed_view.get_possible_states());
+
+        // a mask that only restricts node states which are already restricted changes nothing
+        let mut other_mask: BitVec = BitVec::repeat(true, 150);
+        other_mask.set(149, false);
+        assert!(!indexed_view.is_mask_restrictive(&other_mask));
+        other_mask.set(147, false);
+        assert!(indexed_view.is_mask_restrictive(&other_mask));
+
+        let mut mask_state = indexed_view.stash_mask_state();
+        assert!(indexed_view.is_fully_unmasked());
+        assert_eq!(150, indexed_view.get_unrestricted_node_states_total());
+        assert!(indexed_view.is_mask_restrictive(&mask));
+
+        indexed_view.unstash_mask_state(&mut mask_state);
+        assert_eq!(22, indexed_view.get_unrestricted_node_states_total());
+        indexed_view.subtract_mask(&mask);
+        assert!(indexed_view.is_fully_unmasked());
+    }
+
     #[test]
     fn one_item() {
         init();
 
         let mut node_state_ids: Vec<u32> = Vec::new();
-        let mut node_state_probabilities: Vec<f32> = Vec::new();
+        let mut node_state_probabilities: Vec<f64> = Vec::new();
         let original_node_state_id: u32 = 1;
         node_state_ids.push(original_node_state_id);
         node_state_probabilities.push(1.0);
@@ -3656,7 +8585,7 @@ mod indexed_view_unit_tests {
         init();
 
         let mut node_state_ids: Vec<u32> = Vec::new();
-        let mut node_state_probabilities: Vec<f32> = Vec::new();
+        let mut node_state_probabilities: Vec<f64> = Vec::new();
         let one_original_node_state_id: u32 = 1;
         let two_original_node_state_id: u32 = 2;
         node_state_ids.push(one_original_node_state_id);
@@ -3679,7 +8608,7 @@ mod indexed_view_unit_tests {
         init();
 
         let mut node_state_ids: Vec<u32> = Vec::new();
-        let mut node_state_probabilities: Vec<f32> = Vec::new();
+        let mut node_state_probabilities: Vec<f64> = Vec::new();
         let number_of_items: u32 = 10000;
         for node_state_id in 0..number_of_items {
             node_state_ids.push(node_state_id);
@@ -3702,7 +8631,7 @@ mod indexed_view_unit_tests {
         init();
 
         let mut node_state_ids: Vec<u32> = Vec::new();
-        let mut node_state_probabilities: Vec<f32> = Vec::new();
+        let mut node_state_probabilities: Vec<f64> = Vec::new();
         let number_of_items: u32 = 10000;
         for node_state_id in (0..number_of_items).rev() {
             node_state_ids.push(node_state_id);
@@ -3728,7 +8657,7 @@ mod indexed_view_unit_tests {
         let mut random_instance = fastrand::Rng::new();
 
         let mut node_state_ids: Vec<u32> = Vec::new();
-        let mut node_state_probabilities: Vec<f32> = Vec::new();
+        let mut node_state_probabilities: Vec<f64> = Vec::new();
         for node_state_id in 0..number_of_items {
             node_state_ids.push(node_state_id);
             node_state_probabilities.push(1.0);
@@ -3754,7 +8683,7 @@ mod indexed_view_unit_tests {
         let mut random_instance = fastrand::Rng::new();
 
         let mut node_state_ids: Vec<String> = Vec::new();
-        let mut node_state_probabilities: Vec<f32> = Vec::new();
+        let mut node_state_probabilities: Vec<f64> = Vec::new();
         for _ in 0..number_of_items {
             node_state_ids.push(Uuid::new_v4().to_string());
             node_state_probabilities.push(1.0);
@@ -3780,7 +8709,7 @@ mod indexed_view_unit_tests {
         let mut random_instance = fastrand::Rng::new();
 
         let mut node_state_ids: Vec<String> = Vec::new();
-        let mut node_state_probabilities: Vec<f32> = Vec::new();
+        let mut node_state_probabilities: Vec<f64> = Vec::new();
         for _ in 0..number_of_items {
             node_state_ids.push(Uuid::new_v4().to_string());
             node_state_probabilities.push(1.0);
@@ -3800,4 +8729,343 @@ mod indexed_view_unit_tests {
         }
         assert!(!indexed_view.try_move_next());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn remove_mask_from_source_with_overlapping_masks() {
+        init();
+
+        let node_state_ids: Vec<u32> = vec![0, 1, 2, 3];
+        let node_state_probabilities: Vec<f64> = vec![1.0, 1.0, 1.0, 1.0];
+        let mut indexed_view = IndexedView::new(node_state_ids, node_state_probabilities);
+
+        // the first source restricts node states 0 and 1 while the second source restricts node states 1 and 2
+        indexed_view.add_mask_from_source("first", &bitvec![0, 0, 1, 1]);
+        indexed_view.add_mask_from_source("second", &bitvec![1, 0, 0, 1]);
+        assert_eq!(1, indexed_view.get_unrestricted_node_states_total());
+
+        // node state 1 remains restricted by the second source
+        assert!(indexed_view.remove_mask_from_source("first"));
+        assert_eq!(2, indexed_view.get_unrestricted_node_states_total());
+        assert_eq!(vec![0, 3], indexed_view.get_possible_states());
+        assert!(!indexed_view.remove_mask_from_source("first"));
+
+        // replacing the mask of a source removes its previous restrictions
+        indexed_view.add_mask_from_source("second", &bitvec![1, 1, 1, 0]);
+        assert_eq!(vec![0, 1, 2], indexed_view.get_possible_states());
+
+        assert!(indexed_view.remove_mask_from_source("second"));
+        assert_eq!(4, indexed_view.get_unrestricted_node_states_total());
+        assert!(indexed_view.is_fully_unmasked());
+    }
+
+    #[test]
+    fn masks_from_sources_are_kept_across_reverse_mask_and_stash() {
+        init();
+
+        let node_state_ids: Vec<u32> = vec![0, 1, 2, 3];
+        let node_state_probabilities: Vec<f64> = vec![1.0, 1.0, 1.0, 1.0];
+        let mut indexed_view = IndexedView::new(node_state_ids, node_state_probabilities);
+
+        indexed_view.add_mask_from_source("first", &bitvec![0, 1, 1, 1]);
+        indexed_view.forward_mask(&bitvec![1, 1, 0, 1]);
+        indexed_view.add_mask_from_source("second", &bitvec![0, 0, 1, 1]);
+        assert_eq!(vec![3], indexed_view.get_possible_states());
+
+        // reversing the mask forgets the second source since it applied its mask afterward
+        indexed_view.reverse_mask();
+        assert_eq!(vec![1, 2, 3], indexed_view.get_possible_states());
+        assert!(!indexed_view.remove_mask_from_source("second"));
+
+        // the first source can still be removed once its mask is stashed and unstashed
+        indexed_view.add_mask_from_source("second", &bitvec![0, 1, 1, 1]);
+        let mut mask_state = indexed_view.stash_mask_state();
+        assert!(indexed_view.is_fully_unmasked());
+        assert!(!indexed_view.remove_mask_from_source("first"));
+        indexed_view.unstash_mask_state(&mut mask_state);
+        assert_eq!(vec![1, 2, 3], indexed_view.get_possible_states());
+
+        // node state 0 remains restricted by the second source
+        assert!(indexed_view.remove_mask_from_source("first"));
+        assert_eq!(vec![1, 2, 3], indexed_view.get_possible_states());
+        assert!(indexed_view.remove_mask_from_source("second"));
+        assert!(indexed_view.is_fully_unmasked());
+
+        // a source that applied a new mask while its previous mask was stashed keeps only the new mask
+        indexed_view.add_mask_from_source("first", &bitvec![0, 1, 1, 1]);
+        let mut mask_state = indexed_view.stash_mask_state();
+        indexed_view.add_mask_from_source("first", &bitvec![1, 0, 1, 1]);
+        indexed_view.unstash_mask_state(&mut mask_state);
+        assert_eq!(vec![0, 2, 3], indexed_view.get_possible_states());
+        assert!(indexed_view.remove_mask_from_source("first"));
+        assert!(indexed_view.is_fully_unmasked());
+    }
+}
+#[cfg(test)]
+mod grid_unit_tests {
+
+    use crate::wave_function::{grid::{self, AdjacencyKind, Direction, GridBuilder}, collapsable_wave_function::{collapsable_wave_function::{CollapsableWaveFunction, CollapsedWaveFunction}, sequential_collapsable_wave_function::SequentialCollapsableWaveFunction}};
+
+    fn init() {
+        std::env::set_var("RUST_LOG", "trace");
+        //pretty_env_logger::try_init();
+    }
+
+    fn get_checkerboard_grid_builder(width: usize, height: usize) -> GridBuilder<String> {
+        let mut grid_builder = GridBuilder::new(width, height, vec![String::from("black"), String::from("white")]);
+        grid_builder
+            .allow(String::from("black"), Direction::Right, String::from("white"))
+            .allow(String::from("white"), Direction::Right, String::from("black"))
+            .allow(String::from("black"), Direction::Down, String::from("white"))
+            .allow(String::from("white"), Direction::Down, String::from("black"));
+        grid_builder
+    }
+
+    #[test]
+    fn checkerboard() {
+        init();
+
+        let grid_builder = get_checkerboard_grid_builder(5, 4);
+        let wave_function = grid_builder.build().unwrap();
+        wave_function.validate().unwrap();
+
+        for random_seed in 0..10 {
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+            let node_state_per_y_per_x = grid_builder.get_grid(&collapsed_wave_function).unwrap();
+
+            assert_eq!(5, node_state_per_y_per_x.len());
+            for x in 0..5 {
+                assert_eq!(4, node_state_per_y_per_x[x].len());
+                for y in 0..4 {
+                    if x + 1 < 5 {
+                        assert_ne!(node_state_per_y_per_x[x][y], node_state_per_y_per_x[x + 1][y]);
+                    }
+                    if y + 1 < 4 {
+                        assert_ne!(node_state_per_y_per_x[x][y], node_state_per_y_per_x[x][y + 1]);
+                    }
+                }
+            }
+        }
+    }
+
+    // neighbors with an odd offset total must differ while the others must match, which only a checkerboard satisfies
+    fn get_checkerboard_permitted_node_state_ids(node_state_id: &str, neighbor_offset: &[i64]) -> Vec<String> {
+        let is_different = neighbor_offset.iter().sum::<i64>() % 2 != 0;
+        match (node_state_id, is_different) {
+            ("black", true) | ("white", false) => vec![String::from("white")],
+            _ => vec![String::from("black")]
+        }
+    }
+
+    #[test]
+    fn build_2d_grid_checkerboard() {
+        init();
+
+        for adjacency_kind in [AdjacencyKind::Four, AdjacencyKind::Eight] {
+            let wave_function = grid::build_2d_grid(10, 10, vec![String::from("black"), String::from("white")], adjacency_kind, |node_state_id, neighbor_offset| get_checkerboard_permitted_node_state_ids(node_state_id, neighbor_offset)).unwrap();
+            let nodes = wave_function.get_nodes();
+            assert_eq!(100, nodes.len());
+            // the node state collections of each offset are shared by every node
+            let expected_node_state_collections_total = if adjacency_kind == AdjacencyKind::Four { 2 } else { 4 };
+            assert_eq!(expected_node_state_collections_total, wave_function.get_node_state_collections().len());
+            let expected_neighbors_total = if adjacency_kind == AdjacencyKind::Four { 4 } else { 8 };
+            let center_node = nodes.iter().find(|node| node.id == "5_5").unwrap();
+            assert_eq!(expected_neighbors_total, center_node.node_state_collection_ids_per_neighbor_node_id.len());
+
+            for random_seed in 0..5 {
+                let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+                let node_state_per_y_per_x = grid::get_2d_grid(&collapsed_wave_function, 10, 10).unwrap();
+                assert_eq!(10, node_state_per_y_per_x.len());
+                for x in 0..10 {
+                    assert_eq!(10, node_state_per_y_per_x[x].len());
+                    for y in 0..10 {
+                        assert_eq!((x + y) % 2 == 0, node_state_per_y_per_x[x][y] == node_state_per_y_per_x[0][0], "Node {x}_{y} does not alternate.");
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn build_3d_grid_checkerboard() {
+        init();
+
+        for adjacency_kind in [AdjacencyKind::Six, AdjacencyKind::TwentySix] {
+            let wave_function = grid::build_3d_grid(4, 3, 5, vec![String::from("black"), String::from("white")], adjacency_kind, |node_state_id, neighbor_offset| get_checkerboard_permitted_node_state_ids(node_state_id, neighbor_offset)).unwrap();
+            let nodes = wave_function.get_nodes();
+            assert_eq!(60, nodes.len());
+            let expected_neighbors_total = if adjacency_kind == AdjacencyKind::Six { 6 } else { 26 };
+            let center_node = nodes.iter().find(|node| node.id == "1_1_2").unwrap();
+            assert_eq!(expected_neighbors_total, center_node.node_state_collection_ids_per_neighbor_node_id.len());
+            let corner_node = nodes.iter().find(|node| node.id == "3_2_4").unwrap();
+            assert_eq!(if adjacency_kind == AdjacencyKind::Six { 3 } else { 7 }, corner_node.node_state_collection_ids_per_neighbor_node_id.len());
+
+            for random_seed in 0..5 {
+                let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(random_seed)).collapse().unwrap();
+                let node_state_per_z_per_y_per_x = grid::get_3d_grid(&collapsed_wave_function, 4, 3, 5).unwrap();
+                for x in 0..4 {
+                    for y in 0..3 {
+                        assert_eq!(5, node_state_per_z_per_y_per_x[x][y].len());
+                        for z in 0..5 {
+                            assert_eq!((x + y + z) % 2 == 0, node_state_per_z_per_y_per_x[x][y][z] == node_state_per_z_per_y_per_x[0][0][0], "Node {x}_{y}_{z} does not alternate.");
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn build_grid_with_wrong_dimensions() {
+        init();
+
+        let node_state_ids: Vec<String> = vec![String::from("black"), String::from("white")];
+        assert_eq!("Adjacency Six cannot be used for a grid of 2 dimensions.", grid::build_2d_grid(3, 3, node_state_ids.clone(), AdjacencyKind::Six, |node_state_id, neighbor_offset| get_checkerboard_permitted_node_state_ids(node_state_id, neighbor_offset)).err().unwrap());
+        assert_eq!("Adjacency Eight cannot be used for a grid of 3 dimensions.", grid::build_3d_grid(3, 3, 3, node_state_ids, AdjacencyKind::Eight, |node_state_id, neighbor_offset| get_checkerboard_permitted_node_state_ids(node_state_id, neighbor_offset)).err().unwrap());
+
+        let collapsed_wave_function: CollapsedWaveFunction<String> = CollapsedWaveFunction {
+            node_state_per_node_id: std::collections::HashMap::new(),
+            random_seed: 0,
+            node_metadata_per_node: std::collections::HashMap::new()
+        };
+        assert_eq!("Collapsed wave function does not contain node 0_0.", grid::get_2d_grid(&collapsed_wave_function, 1, 1).err().unwrap());
+        assert_eq!("Collapsed wave function does not contain node 0_0_0.", grid::get_3d_grid(&collapsed_wave_function, 1, 1, 1).err().unwrap());
+    }
+
+    #[test]
+    fn vertical_stripes() {
+        init();
+
+        let mut grid_builder = GridBuilder::new(4, 3, vec![String::from("red"), String::from("blue")]);
+        grid_builder
+            .allow(String::from("red"), Direction::Right, String::from("blue"))
+            .allow(String::from("blue"), Direction::Right, String::from("red"))
+            .allow(String::from("red"), Direction::Down, String::from("red"))
+            .allow(String::from("blue"), Direction::Down, String::from("blue"));
+        let wave_function = grid_builder.build().unwrap();
+        wave_function.validate().unwrap();
+
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse().unwrap();
+        let node_state_per_y_per_x = grid_builder.get_grid(&collapsed_wave_function).unwrap();
+
+        for x in 0..4 {
+            for y in 0..3 {
+                assert_eq!(node_state_per_y_per_x[x][0], node_state_per_y_per_x[x][y]);
+            }
+            if x + 1 < 4 {
+                assert_ne!(node_state_per_y_per_x[x][0], node_state_per_y_per_x[x + 1][0]);
+            }
+        }
+    }
+
+    #[test]
+    fn wrapping_checkerboard_even() {
+        init();
+
+        let mut grid_builder = get_checkerboard_grid_builder(4, 4);
+        grid_builder.set_is_wrapping(true);
+        let wave_function = grid_builder.build().unwrap();
+        wave_function.validate().unwrap();
+
+        let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse().unwrap();
+        let node_state_per_y_per_x = grid_builder.get_grid(&collapsed_wave_function).unwrap();
+
+        for x in 0..4 {
+            for y in 0..4 {
+                assert_ne!(node_state_per_y_per_x[x][y], node_state_per_y_per_x[(x + 1) % 4][y]);
+                assert_ne!(node_state_per_y_per_x[x][y], node_state_per_y_per_x[x][(y + 1) % 4]);
+            }
+        }
+    }
+
+    #[test]
+    fn wrapping_checkerboard_odd() {
+        init();
+
+        let mut grid_builder = get_checkerboard_grid_builder(3, 3);
+        grid_builder.set_is_wrapping(true);
+        let wave_function = grid_builder.build().unwrap();
+        wave_function.validate().unwrap();
+
+        let collapsed_wave_function_result = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(None).collapse();
+
+        assert_eq!("Cannot collapse wave function.", collapsed_wave_function_result.err().unwrap());
+    }
+
+    #[test]
+    fn wrapping_too_small() {
+        init();
+
+        let mut grid_builder = get_checkerboard_grid_builder(2, 5);
+        grid_builder.set_is_wrapping(true);
+
+        assert_eq!("A wrapping grid must be at least 3 by 3 but was 2 by 5.", grid_builder.build().err().unwrap());
+    }
+}
+
+#[cfg(test)]
+mod testing_property_tests {
+
+    use proptest::prelude::*;
+    use crate::wave_function::{testing::{generate_grid, generate_tree, generate_all_different_clique, generate_checkerboard, find_violation}, collapsable_wave_function::{collapsable_wave_function::CollapsableWaveFunction, sequential_collapsable_wave_function::SequentialCollapsableWaveFunction, entropic_collapsable_wave_function::EntropicCollapsableWaveFunction}};
+
+    proptest! {
+        #![proptest_config(ProptestConfig::with_cases(64))]
+
+        #[test]
+        fn generated_grid_is_deterministic_with_valid_assignment(width in 1usize..6, height in 1usize..6, states in 1u32..5, seed in any::<u64>()) {
+            let (wave_function, node_state_per_node_id) = generate_grid(width, height, states, seed).unwrap();
+            let (regenerated_wave_function, regenerated_node_state_per_node_id) = generate_grid(width, height, states, seed).unwrap();
+
+            prop_assert_eq!(width * height, node_state_per_node_id.len());
+            prop_assert_eq!(None, find_violation(&wave_function, &node_state_per_node_id));
+            prop_assert_eq!(&node_state_per_node_id, &regenerated_node_state_per_node_id);
+            prop_assert_eq!(wave_function.get_statistics(), regenerated_wave_function.get_statistics());
+        }
+
+        #[test]
+        fn sequential_collapse_of_generated_grid_is_never_unsatisfiable(width in 1usize..5, height in 1usize..5, states in 1u32..4, seed in any::<u64>()) {
+            let (wave_function, _) = generate_grid(width, height, states, seed).unwrap();
+
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<u32>>(Some(seed)).collapse();
+
+            prop_assert!(collapsed_wave_function.is_ok());
+            prop_assert_eq!(None, find_violation(&wave_function, &collapsed_wave_function.unwrap().node_state_per_node_id));
+        }
+
+        #[test]
+        fn collapse_of_generated_tree_never_violates_constraints(depth in 0usize..4, branching in 1usize..4, states in 1u32..5, seed in any::<u64>()) {
+            let (wave_function, node_state_per_node_id) = generate_tree(depth, branching, states, seed).unwrap();
+            prop_assert_eq!(None, find_violation(&wave_function, &node_state_per_node_id));
+
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<u32>>(Some(seed)).collapse();
+            prop_assert!(collapsed_wave_function.is_ok());
+            prop_assert_eq!(None, find_violation(&wave_function, &collapsed_wave_function.unwrap().node_state_per_node_id));
+
+            // the entropic collapsable wave function does not backtrack, so it may fail, but never with a violated constraint
+            let entropic_collapsed_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<u32>>(Some(seed)).collapse();
+            if let Ok(collapsed_wave_function) = entropic_collapsed_wave_function {
+                prop_assert_eq!(None, find_violation(&wave_function, &collapsed_wave_function.node_state_per_node_id));
+            }
+        }
+
+        #[test]
+        fn collapse_of_generated_checkerboard_alternates(width in 1usize..8, height in 1usize..8, seed in any::<u64>()) {
+            let wave_function = generate_checkerboard(width, height).unwrap();
+
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(seed)).collapse().unwrap();
+            prop_assert_eq!(None, find_violation(&wave_function, &collapsed_wave_function.node_state_per_node_id));
+            prop_assert_eq!(width * height, collapsed_wave_function.node_state_per_node_id.len());
+        }
+
+        #[test]
+        fn generated_clique_is_collapsable_only_with_enough_node_states(nodes_total in 1usize..6, node_states_total in 1usize..6, seed in any::<u64>()) {
+            let wave_function = generate_all_different_clique(nodes_total, node_states_total).unwrap();
+
+            let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<String>>(Some(seed)).collapse();
+            prop_assert_eq!(node_states_total >= nodes_total, collapsed_wave_function.is_ok());
+            if let Ok(collapsed_wave_function) = collapsed_wave_function {
+                prop_assert_eq!(None, find_violation(&wave_function, &collapsed_wave_function.node_state_per_node_id));
+            }
+        }
+    }
+}
@@ -0,0 +1,216 @@
+use std::{collections::HashMap, hash::Hash};
+use colored::Colorize;
+use super::{WaveFunction, traverse_node_indexes};
+use super::collapsable_wave_function::collapsable_wave_function::CollapsedWaveFunction;
+
+// each cell is drawn with two characters so that it appears roughly square in a terminal
+const COLORED_CELL_CHARACTER: &str = "\u{2588}\u{2588}";
+
+// arranges the node states by y and then x, leaving None where no node is positioned
+fn get_node_state_per_x_per_y<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(collapsed_wave_function: &CollapsedWaveFunction<TNodeState>, get_coordinate: impl Fn(&str) -> Option<(usize, usize)>) -> Vec<Vec<Option<&TNodeState>>> {
+    let mut node_state_per_coordinate: Vec<((usize, usize), &TNodeState)> = Vec::new();
+    for (node_id, node_state_id) in collapsed_wave_function.node_state_per_node_id.iter() {
+        if let Some(coordinate) = get_coordinate(node_id) {
+            node_state_per_coordinate.push((coordinate, node_state_id));
+        }
+    }
+
+    let width = node_state_per_coordinate.iter().map(|((x, _), _)| x + 1).max().unwrap_or(0);
+    let height = node_state_per_coordinate.iter().map(|((_, y), _)| y + 1).max().unwrap_or(0);
+    let mut node_state_per_x_per_y: Vec<Vec<Option<&TNodeState>>> = vec![vec![None; width]; height];
+    for ((x, y), node_state_id) in node_state_per_coordinate.into_iter() {
+        node_state_per_x_per_y[y][x] = Some(node_state_id);
+    }
+    node_state_per_x_per_y
+}
+
+/// This function renders the collapsed wave function as one line of characters per row, where get_coordinate positions each node by its id as (x, y). Nodes without a coordinate are skipped and positions without a node are drawn as the background character.
+pub fn get_plain_text<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(collapsed_wave_function: &CollapsedWaveFunction<TNodeState>, get_coordinate: impl Fn(&str) -> Option<(usize, usize)>, get_character: impl Fn(&TNodeState) -> char, background_character: char) -> String {
+    let mut text = String::new();
+    for node_state_per_x in get_node_state_per_x_per_y(collapsed_wave_function, get_coordinate).iter() {
+        for node_state_id in node_state_per_x.iter() {
+            match node_state_id {
+                Some(node_state_id) => text.push(get_character(node_state_id)),
+                None => text.push(background_character)
+            }
+        }
+        text.push('\n');
+    }
+    text
+}
+
+/// This function renders the collapsed wave function as truecolor terminal text, where get_coordinate positions each node by its id as (x, y) and get_color provides the RGBA color of each node state. The alpha channel is ignored. Nodes without a coordinate are skipped and positions without a node are drawn in the background color.
+pub fn get_colored_text<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(collapsed_wave_function: &CollapsedWaveFunction<TNodeState>, get_coordinate: impl Fn(&str) -> Option<(usize, usize)>, get_color: impl Fn(&TNodeState) -> [u8; 4], background_color: [u8; 4]) -> String {
+    let mut text = String::new();
+    for node_state_per_x in get_node_state_per_x_per_y(collapsed_wave_function, get_coordinate).iter() {
+        for node_state_id in node_state_per_x.iter() {
+            let color = match node_state_id {
+                Some(node_state_id) => get_color(node_state_id),
+                None => background_color
+            };
+            text.push_str(&COLORED_CELL_CHARACTER.truecolor(color[0], color[1], color[2]).to_string());
+        }
+        text.push('\n');
+    }
+    text
+}
+
+// draws each positioned node as a square of pixel_scale pixels of its color, leaving cells without a node transparent
+#[cfg(feature = "image")]
+fn get_image<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(collapsed_wave_function: &CollapsedWaveFunction<TNodeState>, width: u32, height: u32, pixel_scale: Option<u32>, get_coordinate: impl Fn(&str) -> Option<(u32, u32)>, get_color: impl Fn(&TNodeState) -> [u8; 4]) -> Result<image::RgbaImage, String> {
+    let pixel_scale = pixel_scale.unwrap_or(1);
+    if pixel_scale == 0 {
+        return Err(String::from("Pixel scale must be at least 1."));
+    }
+
+    let mut color_per_coordinate: Vec<((u32, u32), [u8; 4])> = Vec::new();
+    let mut out_of_bounds_node_ids: Vec<&str> = Vec::new();
+    for (node_id, node_state_id) in collapsed_wave_function.node_state_per_node_id.iter() {
+        if let Some((x, y)) = get_coordinate(node_id) {
+            if x >= width || y >= height {
+                out_of_bounds_node_ids.push(node_id);
+            }
+            else {
+                color_per_coordinate.push(((x, y), get_color(node_state_id)));
+            }
+        }
+    }
+    if !out_of_bounds_node_ids.is_empty() {
+        out_of_bounds_node_ids.sort();
+        return Err(format!("Nodes {} are outside of the {width} by {height} image.", out_of_bounds_node_ids.join(", ")));
+    }
+
+    let Some(image_width) = width.checked_mul(pixel_scale) else {
+        return Err(format!("Image width of {width} scaled by {pixel_scale} is too large."));
+    };
+    let Some(image_height) = height.checked_mul(pixel_scale) else {
+        return Err(format!("Image height of {height} scaled by {pixel_scale} is too large."));
+    };
+    let mut image = image::RgbaImage::new(image_width, image_height);
+    for ((x, y), color) in color_per_coordinate.into_iter() {
+        for pixel_x in (x * pixel_scale)..((x + 1) * pixel_scale) {
+            for pixel_y in (y * pixel_scale)..((y + 1) * pixel_scale) {
+                image.put_pixel(pixel_x, pixel_y, image::Rgba(color));
+            }
+        }
+    }
+    Ok(image)
+}
+
+/// This function saves the collapsed wave function as an RGBA PNG image of width by height cells, where get_coordinate positions each node by its id as (x, y) and get_color provides the RGBA color of each node state. Each cell is drawn as a square of pixel_scale pixels, defaulting to one pixel. Nodes without a coordinate are skipped and cells without a node are left transparent.
+#[cfg(feature = "image")]
+pub fn save_png<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(collapsed_wave_function: &CollapsedWaveFunction<TNodeState>, file_path: &str, width: u32, height: u32, pixel_scale: Option<u32>, get_coordinate: impl Fn(&str) -> Option<(u32, u32)>, get_color: impl Fn(&TNodeState) -> [u8; 4]) -> Result<(), String> {
+    let image = get_image(collapsed_wave_function, width, height, pixel_scale, get_coordinate, get_color)?;
+    image.save_with_format(file_path, image::ImageFormat::Png).map_err(|error| format!("Failed to save image to {file_path}: {error}"))
+}
+
+/// This function writes the same PNG image as save_png to the writer instead of to a file, such as to keep the image in memory.
+#[cfg(feature = "image")]
+pub fn write_png<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(collapsed_wave_function: &CollapsedWaveFunction<TNodeState>, writer: &mut (impl std::io::Write + std::io::Seek), width: u32, height: u32, pixel_scale: Option<u32>, get_coordinate: impl Fn(&str) -> Option<(u32, u32)>, get_color: impl Fn(&TNodeState) -> [u8; 4]) -> Result<(), String> {
+    let image = get_image(collapsed_wave_function, width, height, pixel_scale, get_coordinate, get_color)?;
+    image.write_to(writer, image::ImageOutputFormat::Png).map_err(|error| format!("Failed to write image: {error}"))
+}
+
+/// This struct describes how a wave function is rendered as a GraphViz DOT graph.
+#[derive(Debug, Clone, Default)]
+pub struct DotOptions {
+    // labels each edge with the number of node state collections instead of listing their ids
+    pub is_edge_label_summarized: bool,
+    // fills in the nodes without any node states and the nodes outside of the largest connected group of nodes
+    pub is_problem_highlighted: bool
+}
+
+// the fill color of nodes that can never be collapsed or that are not connected to the rest of the nodes
+const DOT_PROBLEM_FILL_COLOR: &str = "#ff9999";
+
+// quotes the text as a DOT identifier, where a newline becomes a line break within a label
+fn get_dot_quoted(text: &str) -> String {
+    format!("\"{}\"", text.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n"))
+}
+
+// flags each node that has no node states or that is outside of the largest group of nodes that are connected to each other, ignoring the direction of each neighbor
+fn get_is_problem_per_node_index<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(wave_function: &WaveFunction<TNodeState>) -> Vec<bool> {
+    let nodes_length = wave_function.nodes.len();
+    let mut node_index_per_id: HashMap<&str, usize> = HashMap::new();
+    for (node_index, node) in wave_function.nodes.iter().enumerate() {
+        node_index_per_id.insert(&node.id, node_index);
+    }
+    let mut undirected_neighbor_node_indexes_per_node_index: Vec<Vec<usize>> = vec![Vec::new(); nodes_length];
+    for (node_index, node) in wave_function.nodes.iter().enumerate() {
+        for neighbor_node_id in node.node_state_collection_ids_per_neighbor_node_id.keys() {
+            if let Some(neighbor_node_index) = node_index_per_id.get(neighbor_node_id.as_str()) {
+                undirected_neighbor_node_indexes_per_node_index[node_index].push(*neighbor_node_index);
+                undirected_neighbor_node_indexes_per_node_index[*neighbor_node_index].push(node_index);
+            }
+        }
+    }
+
+    let mut is_traversed_per_node_index: Vec<bool> = vec![false; nodes_length];
+    let mut largest_group_node_indexes: Vec<usize> = Vec::new();
+    for node_index in 0..nodes_length {
+        if !is_traversed_per_node_index[node_index] {
+            let group_node_indexes = traverse_node_indexes(&undirected_neighbor_node_indexes_per_node_index, node_index, &mut is_traversed_per_node_index);
+            if group_node_indexes.len() > largest_group_node_indexes.len() {
+                largest_group_node_indexes = group_node_indexes;
+            }
+        }
+    }
+
+    let mut is_problem_per_node_index: Vec<bool> = vec![true; nodes_length];
+    for node_index in largest_group_node_indexes.into_iter() {
+        is_problem_per_node_index[node_index] = wave_function.nodes[node_index].node_state_ids.is_empty();
+    }
+    is_problem_per_node_index
+}
+
+/// This function renders the wave function as a GraphViz DOT graph with a node labelled by its id and number of node states for each node and an edge labelled by its node state collections from each node to each of its neighbors. Providing a collapsed wave function labels each node by its node state instead, filling in each node with a color per node state.
+pub fn get_dot<TNodeState: Eq + Hash + Clone + std::fmt::Debug>(wave_function: &WaveFunction<TNodeState>, collapsed_wave_function: Option<&CollapsedWaveFunction<TNodeState>>, dot_options: &DotOptions) -> String {
+    let is_problem_per_node_index = get_is_problem_per_node_index(wave_function);
+
+    // spread the node states evenly around the hue circle in order of their first appearance
+    let mut hue_index_per_node_state: HashMap<&TNodeState, usize> = HashMap::new();
+    if let Some(collapsed_wave_function) = collapsed_wave_function {
+        for node in wave_function.nodes.iter() {
+            if let Some(node_state) = collapsed_wave_function.node_state_per_node_id.get(&node.id) {
+                let hue_index = hue_index_per_node_state.len();
+                hue_index_per_node_state.entry(node_state).or_insert(hue_index);
+            }
+        }
+    }
+
+    let mut dot = String::from("digraph wave_function {\n");
+    for (node_index, node) in wave_function.nodes.iter().enumerate() {
+        let mut attributes: Vec<String> = Vec::new();
+        match collapsed_wave_function.and_then(|collapsed_wave_function| collapsed_wave_function.node_state_per_node_id.get(&node.id)) {
+            Some(node_state) => {
+                attributes.push(format!("label={}", get_dot_quoted(&format!("{}\n{node_state:?}", node.id))));
+                let hue = *hue_index_per_node_state.get(node_state).unwrap() as f64 / hue_index_per_node_state.len() as f64;
+                attributes.push(format!("style=filled, fillcolor=\"{hue:.3} 0.400 1.000\""));
+            },
+            None => {
+                let node_states_total = node.node_state_ids.len();
+                attributes.push(format!("label={}", get_dot_quoted(&format!("{}\n{node_states_total} {}", node.id, if node_states_total == 1 { "node state" } else { "node states" }))));
+                if dot_options.is_problem_highlighted && is_problem_per_node_index[node_index] {
+                    attributes.push(format!("style=filled, fillcolor=\"{DOT_PROBLEM_FILL_COLOR}\""));
+                }
+            }
+        }
+        dot.push_str(&format!("    {} [{}];\n", get_dot_quoted(&node.id), attributes.join(", ")));
+    }
+    for node in wave_function.nodes.iter() {
+        let mut neighbor_node_ids: Vec<&String> = node.node_state_collection_ids_per_neighbor_node_id.keys().collect();
+        neighbor_node_ids.sort();
+        for neighbor_node_id in neighbor_node_ids.into_iter() {
+            let node_state_collection_ids = node.node_state_collection_ids_per_neighbor_node_id.get(neighbor_node_id).unwrap();
+            let label = if dot_options.is_edge_label_summarized {
+                format!("{} {}", node_state_collection_ids.len(), if node_state_collection_ids.len() == 1 { "collection" } else { "collections" })
+            }
+            else {
+                node_state_collection_ids.join(", ")
+            };
+            dot.push_str(&format!("    {} -> {} [label={}];\n", get_dot_quoted(&node.id), get_dot_quoted(neighbor_node_id), get_dot_quoted(&label)));
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
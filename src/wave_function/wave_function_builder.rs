@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::hash::Hash;
+use serde::{Serialize, de::DeserializeOwned};
+use super::{Node, NodeStateCollection, NodeStateProbability, WaveFunction};
+
+/// This struct builds a WaveFunction from node states and the permitted neighbor node states per node state, generating the node state collections and their ids internally.
+pub struct WaveFunctionBuilder<TNodeState: Eq + Hash + Clone + std::fmt::Debug> {
+    node_state_ratio_per_node_state_id_per_node_id: Vec<(String, HashMap<TNodeState, f64>)>,
+    // each constraint is the original node, the neighbor node, and the permitted neighbor node states per original node state
+    constraints: Vec<(String, String, HashMap<TNodeState, Vec<TNodeState>>)>,
+    // the permitted neighbor node states per node state shared by every pair of neighbors
+    permitted_node_state_ids_per_node_state_id: HashMap<TNodeState, Vec<TNodeState>>,
+    neighbor_node_ids: Vec<(String, String)>,
+    metadata_per_node_id: Vec<(String, serde_json::Value)>
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Serialize + DeserializeOwned> Default for WaveFunctionBuilder<TNodeState> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<TNodeState: Eq + Hash + Clone + std::fmt::Debug + Serialize + DeserializeOwned> WaveFunctionBuilder<TNodeState> {
+    pub fn new() -> Self {
+        WaveFunctionBuilder {
+            node_state_ratio_per_node_state_id_per_node_id: Vec::new(),
+            constraints: Vec::new(),
+            permitted_node_state_ids_per_node_state_id: HashMap::new(),
+            neighbor_node_ids: Vec::new(),
+            metadata_per_node_id: Vec::new()
+        }
+    }
+
+    /// This function adds a node that can be in any of the provided node states with equal probability.
+    pub fn add_node(&mut self, node_id: &str, node_state_ids: Vec<TNodeState>) -> &mut Self {
+        self.add_node_with_probabilities(node_id, NodeStateProbability::get_equal_probability(&node_state_ids))
+    }
+
+    pub fn add_node_with_probabilities(&mut self, node_id: &str, node_state_ratio_per_node_state_id: HashMap<TNodeState, f64>) -> &mut Self {
+        self.node_state_ratio_per_node_state_id_per_node_id.push((String::from(node_id), node_state_ratio_per_node_state_id));
+        self
+    }
+
+    /// This function attaches metadata to the node that is carried untouched into the collapsed wave function.
+    pub fn set_node_metadata(&mut self, node_id: &str, metadata: serde_json::Value) -> &mut Self {
+        self.metadata_per_node_id.push((String::from(node_id), metadata));
+        self
+    }
+
+    /// This function restricts the neighbor node to the permitted node states when the original node is in the keyed node state. Original node states that are not keyed do not restrict the neighbor node.
+    pub fn add_constraint(&mut self, original_node_id: &str, neighbor_node_id: &str, permitted_node_state_ids_per_node_state_id: HashMap<TNodeState, Vec<TNodeState>>) -> &mut Self {
+        self.constraints.push((String::from(original_node_id), String::from(neighbor_node_id), permitted_node_state_ids_per_node_state_id));
+        self
+    }
+
+    /// This function applies the same constraint from the first node to the second node and from the second node to the first node.
+    pub fn add_bidirectional_constraint(&mut self, first_node_id: &str, second_node_id: &str, permitted_node_state_ids_per_node_state_id: HashMap<TNodeState, Vec<TNodeState>>) -> &mut Self {
+        self.add_constraint(first_node_id, second_node_id, permitted_node_state_ids_per_node_state_id.clone());
+        self.add_constraint(second_node_id, first_node_id, permitted_node_state_ids_per_node_state_id)
+    }
+
+    /// This function restricts any neighbor added by add_neighbors to the permitted node states whenever a node is in the node state, replacing any node states previously permitted for that node state.
+    pub fn permit_only(&mut self, node_state_id: TNodeState, permitted_node_state_ids: Vec<TNodeState>) -> &mut Self {
+        self.permitted_node_state_ids_per_node_state_id.insert(node_state_id, permitted_node_state_ids);
+        self
+    }
+
+    /// This function applies the node states permitted by permit_only from the first node to the second node and from the second node to the first node. Node states that either node cannot be in are ignored for that node.
+    pub fn add_neighbors(&mut self, first_node_id: &str, second_node_id: &str) -> &mut Self {
+        self.neighbor_node_ids.push((String::from(first_node_id), String::from(second_node_id)));
+        self
+    }
+
+    /// This function builds and validates the wave function. Identical node state collections are shared by every constraint that generates them.
+    pub fn build(&self) -> Result<WaveFunction<TNodeState>, String> {
+        let mut nodes: Vec<Node<TNodeState>> = Vec::new();
+        let mut node_index_per_node_id: HashMap<&str, usize> = HashMap::new();
+        for (node_id, node_state_ratio_per_node_state_id) in self.node_state_ratio_per_node_state_id_per_node_id.iter() {
+            if node_index_per_node_id.insert(node_id, nodes.len()).is_some() {
+                return Err(format!("Node {node_id} is added more than once."));
+            }
+            nodes.push(Node::new(node_id.clone(), node_state_ratio_per_node_state_id.clone(), HashMap::new()));
+        }
+
+        for (node_id, metadata) in self.metadata_per_node_id.iter() {
+            let Some(node_index) = node_index_per_node_id.get(node_id.as_str()).cloned() else {
+                return Err(format!("Metadata references node {node_id} that does not exist."));
+            };
+            nodes[node_index].set_metadata(metadata.clone());
+        }
+
+        let mut neighbor_constraints: Vec<(String, String, HashMap<TNodeState, Vec<TNodeState>>)> = Vec::new();
+        for (first_node_id, second_node_id) in self.neighbor_node_ids.iter() {
+            for (original_node_id, neighbor_node_id) in [(first_node_id, second_node_id), (second_node_id, first_node_id)] {
+                let Some(original_node) = node_index_per_node_id.get(original_node_id.as_str()).map(|node_index| &nodes[*node_index]) else {
+                    return Err(format!("Neighbors reference node {original_node_id} that does not exist."));
+                };
+                let Some(neighbor_node) = node_index_per_node_id.get(neighbor_node_id.as_str()).map(|node_index| &nodes[*node_index]) else {
+                    return Err(format!("Neighbors reference node {neighbor_node_id} that does not exist."));
+                };
+                let permitted_node_state_ids_per_node_state_id: HashMap<TNodeState, Vec<TNodeState>> = self.permitted_node_state_ids_per_node_state_id
+                    .iter()
+                    .filter(|(node_state_id, _)| original_node.node_state_ids.contains(node_state_id))
+                    .map(|(node_state_id, permitted_node_state_ids)| {
+                        (node_state_id.clone(), permitted_node_state_ids.iter().filter(|permitted_node_state_id| neighbor_node.node_state_ids.contains(permitted_node_state_id)).cloned().collect())
+                    })
+                    .collect();
+                neighbor_constraints.push((original_node_id.clone(), neighbor_node_id.clone(), permitted_node_state_ids_per_node_state_id));
+            }
+        }
+
+        let mut node_state_collections: Vec<NodeStateCollection<TNodeState>> = Vec::new();
+        let mut node_state_collection_id_per_node_state_collection: HashMap<(&TNodeState, &Vec<TNodeState>), String> = HashMap::new();
+        for (original_node_id, neighbor_node_id, permitted_node_state_ids_per_node_state_id) in self.constraints.iter().chain(neighbor_constraints.iter()) {
+            let Some(original_node_index) = node_index_per_node_id.get(original_node_id.as_str()).cloned() else {
+                return Err(format!("Constraint references node {original_node_id} that does not exist."));
+            };
+            let Some(neighbor_node_index) = node_index_per_node_id.get(neighbor_node_id.as_str()).cloned() else {
+                return Err(format!("Constraint references node {neighbor_node_id} that does not exist."));
+            };
+
+            for node_state_id in permitted_node_state_ids_per_node_state_id.keys() {
+                if !nodes[original_node_index].node_state_ids.contains(node_state_id) {
+                    return Err(format!("Constraint references node state {node_state_id:?} that does not exist for node {original_node_id}."));
+                }
+            }
+
+            // follow the order of the node states of the original node so that the generated ids do not depend on hashmap order
+            let node_state_ids_and_permitted_node_state_ids: Vec<(&TNodeState, &Vec<TNodeState>)> = nodes[original_node_index].node_state_ids
+                .iter()
+                .filter_map(|node_state_id| permitted_node_state_ids_per_node_state_id.get_key_value(node_state_id))
+                .collect();
+
+            let mut node_state_collection_ids: Vec<String> = Vec::new();
+            for (node_state_id, permitted_node_state_ids) in node_state_ids_and_permitted_node_state_ids {
+                for permitted_node_state_id in permitted_node_state_ids.iter() {
+                    if !nodes[neighbor_node_index].node_state_ids.contains(permitted_node_state_id) {
+                        return Err(format!("Constraint references node state {permitted_node_state_id:?} that does not exist for node {neighbor_node_id}."));
+                    }
+                }
+
+                let node_state_collection_id: &String = node_state_collection_id_per_node_state_collection
+                    .entry((node_state_id, permitted_node_state_ids))
+                    .or_insert_with(|| {
+                        let node_state_collection_id: String = format!("node_state_collection_{}", node_state_collections.len());
+                        node_state_collections.push(NodeStateCollection::new(node_state_collection_id.clone(), node_state_id.clone(), permitted_node_state_ids.clone()));
+                        node_state_collection_id
+                    });
+                node_state_collection_ids.push(node_state_collection_id.clone());
+            }
+
+            let neighbor_node_state_collection_ids = nodes[original_node_index].node_state_collection_ids_per_neighbor_node_id
+                .entry(neighbor_node_id.clone())
+                .or_default();
+            for node_state_collection_id in node_state_collection_ids {
+                if !neighbor_node_state_collection_ids.contains(&node_state_collection_id) {
+                    neighbor_node_state_collection_ids.push(node_state_collection_id);
+                }
+            }
+        }
+
+        let wave_function = WaveFunction::new(nodes, node_state_collections);
+        wave_function.validate()?;
+        Ok(wave_function)
+    }
+}
@@ -0,0 +1,54 @@
+use std::collections::HashMap;
+use wave_function_collapse::wave_function::{
+    WaveFunction,
+    wave_function_builder::WaveFunctionBuilder,
+    collapsable_wave_function::{
+        collapsable_wave_function::{CollapsableWaveFunction, CollapsedWaveFunction},
+        sequential_collapsable_wave_function::SequentialCollapsableWaveFunction,
+        entropic_collapsable_wave_function::EntropicCollapsableWaveFunction
+    }
+};
+
+// a chain of nodes where each node must be in a different node state than the node before it
+fn get_chain_wave_function(nodes_total: usize) -> WaveFunction<u32> {
+    let node_state_ids: Vec<u32> = vec![0, 1, 2];
+    let mut permitted_node_state_ids_per_node_state_id: HashMap<u32, Vec<u32>> = HashMap::new();
+    for node_state_id in node_state_ids.iter() {
+        permitted_node_state_ids_per_node_state_id.insert(*node_state_id, node_state_ids.iter().filter(|other_node_state_id| *other_node_state_id != node_state_id).cloned().collect());
+    }
+    let mut wave_function_builder: WaveFunctionBuilder<u32> = WaveFunctionBuilder::new();
+    for node_index in 0..nodes_total {
+        wave_function_builder.add_node(&format!("node_{node_index}"), node_state_ids.clone());
+    }
+    for node_index in 1..nodes_total {
+        wave_function_builder.add_constraint(&format!("node_{}", node_index - 1), &format!("node_{node_index}"), permitted_node_state_ids_per_node_state_id.clone());
+        wave_function_builder.add_constraint(&format!("node_{node_index}"), &format!("node_{}", node_index - 1), permitted_node_state_ids_per_node_state_id.clone());
+    }
+    wave_function_builder.build().unwrap()
+}
+
+fn assert_neighbors_differ(nodes_total: usize, collapsed_wave_function: &CollapsedWaveFunction<u32>) {
+    for node_index in 1..nodes_total {
+        assert_ne!(
+            collapsed_wave_function.node_state_per_node_id.get(&format!("node_{}", node_index - 1)),
+            collapsed_wave_function.node_state_per_node_id.get(&format!("node_{node_index}"))
+        );
+    }
+}
+
+// exercises collapsing without file IO or logging via: cargo test --no-default-features --test minimal
+#[test]
+fn collapse_chain_sequentially() {
+    let wave_function = get_chain_wave_function(100);
+    let collapsed_wave_function = wave_function.get_collapsable_wave_function::<SequentialCollapsableWaveFunction<u32>>(Some(0)).collapse().unwrap();
+    assert_eq!(100, collapsed_wave_function.node_state_per_node_id.len());
+    assert_neighbors_differ(100, &collapsed_wave_function);
+}
+
+#[test]
+fn collapse_chain_entropically() {
+    let wave_function = get_chain_wave_function(100);
+    let collapsed_wave_function = wave_function.get_collapsable_wave_function::<EntropicCollapsableWaveFunction<u32>>(Some(0)).collapse().unwrap();
+    assert_eq!(100, collapsed_wave_function.node_state_per_node_id.len());
+    assert_neighbors_differ(100, &collapsed_wave_function);
+}
@@ -0,0 +1,154 @@
+use std::{collections::HashMap, process::{Command, Output}};
+use tempfile::TempDir;
+use wave_function_collapse::wave_function::{
+    WaveFunction,
+    WaveFunctionStatistics,
+    Node,
+    NodeStateProbability,
+    wave_function_builder::WaveFunctionBuilder,
+    collapsable_wave_function::collapsable_wave_function::CollapsedWaveFunction
+};
+
+fn run_wfc(arguments: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_wfc"))
+        .args(arguments)
+        .output()
+        .unwrap()
+}
+
+fn write_wave_function(directory: &TempDir, file_name: &str, wave_function: &WaveFunction<String>) -> String {
+    let file_path = directory.path().join(file_name).to_str().unwrap().to_string();
+    wave_function.save_to_file(&file_path).unwrap();
+    file_path
+}
+
+// three nodes that must each be in a different node state
+fn get_three_nodes_all_different_wave_function() -> WaveFunction<String> {
+    let node_state_ids: Vec<String> = vec![String::from("one"), String::from("two"), String::from("three")];
+    let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+    for node_state_id in node_state_ids.iter() {
+        permitted_node_state_ids_per_node_state_id.insert(node_state_id.clone(), node_state_ids.iter().filter(|other_node_state_id| *other_node_state_id != node_state_id).cloned().collect());
+    }
+    WaveFunctionBuilder::new()
+        .add_node("node_1", node_state_ids.clone())
+        .add_node("node_2", node_state_ids.clone())
+        .add_node("node_3", node_state_ids)
+        .add_bidirectional_constraint("node_1", "node_2", permitted_node_state_ids_per_node_state_id.clone())
+        .add_bidirectional_constraint("node_2", "node_3", permitted_node_state_ids_per_node_state_id.clone())
+        .add_bidirectional_constraint("node_1", "node_3", permitted_node_state_ids_per_node_state_id)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn validate_valid_wave_function() {
+    let directory = TempDir::new().unwrap();
+    let input_file_path = write_wave_function(&directory, "input.json", &get_three_nodes_all_different_wave_function());
+
+    let output = run_wfc(&["validate", &input_file_path]);
+
+    assert_eq!(Some(0), output.status.code());
+}
+
+#[test]
+fn validate_disconnected_wave_function() {
+    let directory = TempDir::new().unwrap();
+    // the builder refuses to build a disconnected wave function, so the nodes are constructed directly
+    let node_state_ids: Vec<String> = vec![String::from("one")];
+    let wave_function = WaveFunction::new(vec![
+        Node::new(String::from("node_1"), NodeStateProbability::get_equal_probability(&node_state_ids), HashMap::new()),
+        Node::new(String::from("node_2"), NodeStateProbability::get_equal_probability(&node_state_ids), HashMap::new())
+    ], Vec::new());
+    let input_file_path = write_wave_function(&directory, "input.json", &wave_function);
+
+    let output = run_wfc(&["validate", &input_file_path]);
+
+    assert_eq!(Some(3), output.status.code());
+    assert!(String::from_utf8(output.stderr).unwrap().starts_with("Not all nodes connect together."));
+}
+
+#[test]
+fn collapse_to_output_file_with_seed() {
+    let directory = TempDir::new().unwrap();
+    let input_file_path = write_wave_function(&directory, "input.json", &get_three_nodes_all_different_wave_function());
+    let first_output_file_path = directory.path().join("first_output.json").to_str().unwrap().to_string();
+    let second_output_file_path = directory.path().join("second_output.json").to_str().unwrap().to_string();
+
+    for output_file_path in [&first_output_file_path, &second_output_file_path] {
+        let output = run_wfc(&["collapse", &input_file_path, "--algorithm", "entropic", "--seed", "42", "--output", output_file_path]);
+        assert_eq!(Some(0), output.status.code());
+    }
+
+    let serialized_collapsed_wave_function = std::fs::read_to_string(&first_output_file_path).unwrap();
+    let collapsed_wave_function: CollapsedWaveFunction<String> = serde_json::from_str(&serialized_collapsed_wave_function).unwrap();
+    let mut node_state_ids: Vec<&String> = collapsed_wave_function.node_state_per_node_id.values().collect();
+    node_state_ids.sort();
+    node_state_ids.dedup();
+    assert_eq!(3, node_state_ids.len());
+
+    // the same seed produces the same collapsed wave function
+    assert_eq!(serialized_collapsed_wave_function, std::fs::read_to_string(&second_output_file_path).unwrap());
+}
+
+#[test]
+fn collapse_newline_delimited_input_matches_json_input() {
+    let directory = TempDir::new().unwrap();
+    let wave_function = get_three_nodes_all_different_wave_function();
+    let input_file_path = write_wave_function(&directory, "input.json", &wave_function);
+    let newline_delimited_input_file_path = directory.path().join("input.ndjson").to_str().unwrap().to_string();
+    wave_function.to_writer(std::fs::File::create(&newline_delimited_input_file_path).unwrap()).unwrap();
+
+    let output = run_wfc(&["collapse", &input_file_path, "--seed", "7"]);
+    let newline_delimited_output = run_wfc(&["collapse", &newline_delimited_input_file_path, "--seed", "7"]);
+
+    assert_eq!(Some(0), newline_delimited_output.status.code());
+    assert_eq!(output.stdout, newline_delimited_output.stdout);
+}
+
+#[test]
+fn collapse_unsatisfiable_wave_function() {
+    let directory = TempDir::new().unwrap();
+    let mut permitted_node_state_ids_per_node_state_id: HashMap<String, Vec<String>> = HashMap::new();
+    permitted_node_state_ids_per_node_state_id.insert(String::from("one"), Vec::new());
+    let wave_function = WaveFunctionBuilder::new()
+        .add_node("node_1", vec![String::from("one")])
+        .add_node("node_2", vec![String::from("one")])
+        .add_bidirectional_constraint("node_1", "node_2", permitted_node_state_ids_per_node_state_id)
+        .build()
+        .unwrap();
+    let input_file_path = write_wave_function(&directory, "input.json", &wave_function);
+
+    let output = run_wfc(&["collapse", &input_file_path]);
+
+    assert_eq!(Some(4), output.status.code());
+}
+
+#[test]
+fn collapse_missing_input_file() {
+    let directory = TempDir::new().unwrap();
+    let input_file_path = directory.path().join("missing.json").to_str().unwrap().to_string();
+
+    let output = run_wfc(&["collapse", &input_file_path]);
+
+    assert_eq!(Some(2), output.status.code());
+}
+
+#[test]
+fn stats_three_nodes() {
+    let directory = TempDir::new().unwrap();
+    let input_file_path = write_wave_function(&directory, "input.json", &get_three_nodes_all_different_wave_function());
+
+    let output = run_wfc(&["stats", &input_file_path]);
+
+    assert_eq!(Some(0), output.status.code());
+    let statistics: WaveFunctionStatistics = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(3, statistics.nodes_total);
+    assert_eq!(6, statistics.neighbor_edges_total);
+}
+
+#[test]
+fn unknown_subcommand() {
+    let output = run_wfc(&["explode", "input.json"]);
+
+    assert_eq!(Some(1), output.status.code());
+}